@@ -0,0 +1,181 @@
+// Account health
+// Tracks authentication failures per account. After a few consecutive failures (expired
+// refresh token, changed password) the account is marked as needing attention and a single
+// `account-needs-attention` event is emitted; further auth errors are reported with a fixed
+// message so the UI can show one "sign in again" prompt instead of an error per retry, and
+// IDLE stops reconnecting. The next successful login clears the state and emits
+// `account-recovered`, on which the frontend dismisses the prompt and restarts IDLE.
+
+use crate::commands::event_buffer::emit_buffered;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{command, AppHandle};
+
+/// Consecutive auth failures before an account needs attention
+const AUTH_FAILURE_THRESHOLD: u32 = 2;
+
+/// Prefix of errors returned while an account needs attention
+pub const NEEDS_ATTENTION_ERROR: &str = "Account needs attention";
+
+/// Why an account can't authenticate
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttentionReason {
+    TokenExpired,       // OAuth refresh token revoked or expired
+    InvalidCredentials, // Password changed or login refused
+}
+
+impl AttentionReason {
+    fn message(self) -> &'static str {
+        match self {
+            AttentionReason::TokenExpired => "Your sign-in has expired. Please sign in again.",
+            AttentionReason::InvalidCredentials => {
+                "The server rejected your credentials. Please update your password."
+            }
+        }
+    }
+}
+
+/// Payload of `account-needs-attention`, also returned by `get_account_health`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountAttention {
+    pub account_id: i32,
+    pub reason: AttentionReason,
+    pub message: String,
+    pub error: String, // The last server/provider error
+    pub since: i64,
+}
+
+#[derive(Default)]
+struct HealthState {
+    consecutive_failures: u32,
+    attention: Option<AccountAttention>,
+}
+
+lazy_static::lazy_static! {
+    static ref HEALTH: Mutex<HashMap<i32, HealthState>> = Mutex::new(HashMap::new());
+}
+
+/// Tell whether an error means the account's credentials are no longer accepted
+pub fn classify_auth_error(error: &str) -> Option<AttentionReason> {
+    let lower = error.to_lowercase();
+
+    if lower.contains("invalid_grant")
+        || lower.contains("failed to refresh access token")
+        || lower.contains("no refresh token available")
+        || lower.contains("oauth2 authentication failed")
+    {
+        return Some(AttentionReason::TokenExpired);
+    }
+
+    if lower.contains("login failed")
+        || lower.contains("authenticationfailed")
+        || lower.contains("invalid credentials")
+        || lower.contains("authentication failed")
+    {
+        return Some(AttentionReason::InvalidCredentials);
+    }
+
+    None
+}
+
+/// Whether an account currently needs the user to sign in again
+pub fn needs_attention(account_id: i32) -> bool {
+    HEALTH
+        .lock()
+        .map(|health| {
+            health
+                .get(&account_id)
+                .is_some_and(|state| state.attention.is_some())
+        })
+        .unwrap_or(false)
+}
+
+/// Record a failed connection attempt and return the error to report
+///
+/// Errors that aren't auth failures are returned unchanged. Auth failures past the threshold
+/// mark the account (emitting the event once) and are replaced by a fixed message.
+pub fn record_failure(app_handle: &AppHandle, account_id: i32, error: String) -> String {
+    let Some(reason) = classify_auth_error(&error) else {
+        return error;
+    };
+
+    let Ok(mut health) = HEALTH.lock() else {
+        return error;
+    };
+    let state = health.entry(account_id).or_default();
+    state.consecutive_failures += 1;
+
+    if state.attention.is_none() && state.consecutive_failures >= AUTH_FAILURE_THRESHOLD {
+        let attention = AccountAttention {
+            account_id,
+            reason,
+            message: reason.message().to_string(),
+            error: error.clone(),
+            since: chrono::Utc::now().timestamp(),
+        };
+        tracing::warn!(
+            account_id = account_id,
+            reason = ?reason,
+            error = %error,
+            "Account needs attention"
+        );
+        emit_buffered(app_handle, "account-needs-attention", &attention);
+        state.attention = Some(attention);
+    }
+
+    match &state.attention {
+        Some(attention) => format!("{}: {}", NEEDS_ATTENTION_ERROR, attention.message),
+        None => error,
+    }
+}
+
+/// Record a successful login, clearing any attention state
+pub fn record_success(app_handle: &AppHandle, account_id: i32) {
+    let recovered = HEALTH
+        .lock()
+        .ok()
+        .and_then(|mut health| health.remove(&account_id))
+        .is_some_and(|state| state.attention.is_some());
+
+    if recovered {
+        tracing::info!(account_id = account_id, "Account recovered");
+        emit_buffered(app_handle, "account-recovered", account_id);
+    }
+}
+
+/// List accounts that currently need the user to sign in again
+#[command]
+pub fn get_account_health() -> Vec<AccountAttention> {
+    HEALTH
+        .lock()
+        .map(|health| {
+            health
+                .values()
+                .filter_map(|state| state.attention.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_auth_error() {
+        assert_eq!(
+            classify_auth_error("Failed to refresh access token: invalid_grant"),
+            Some(AttentionReason::TokenExpired)
+        );
+        assert_eq!(
+            classify_auth_error("Login failed: [AUTHENTICATIONFAILED] Invalid credentials"),
+            Some(AttentionReason::InvalidCredentials)
+        );
+        assert_eq!(
+            classify_auth_error("Failed to connect to IMAP server: timed out"),
+            None
+        );
+    }
+}
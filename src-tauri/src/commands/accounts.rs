@@ -1,12 +1,38 @@
+use crate::commands::audit;
+use crate::commands::authorization::{authorize, Sensitivity};
 use crate::db::pool;
 use crate::models::{AccountConfig, AuthType};
+use crate::oauth2_config::OAuth2Provider;
 use crate::security;
 use tauri::command;
 
 #[command]
 pub async fn delete_account(email: String) -> Result<(), String> {
+    authorize("delete_account", Sensitivity::HighRisk).await?;
+
     let pool = pool();
 
+    // Look up the keyring namespace before the row is gone
+    let account = sqlx::query_as::<_, (Option<String>, String, String)>(
+        "SELECT credential_ns, auth_type, imap_server FROM accounts WHERE email = ?",
+    )
+    .bind(&email)
+    .fetch_optional(&*pool)
+    .await
+    .map_err(|e| format!("Failed to load account: {}", e))?;
+
+    let namespace = account
+        .as_ref()
+        .and_then(|(namespace, _, _)| namespace.clone())
+        .unwrap_or_default();
+
+    // Revoke the OAuth grant so it doesn't linger in the provider's security page
+    if let Some((_, auth_type, imap_server)) = &account {
+        if auth_type == "oauth2" {
+            revoke_oauth_grant(&namespace, &email, imap_server).await;
+        }
+    }
+
     // Delete from database
     sqlx::query("DELETE FROM accounts WHERE email = ?")
         .bind(&email)
@@ -15,13 +41,14 @@ pub async fn delete_account(email: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to delete account: {}", e))?;
 
     // Delete credentials from keyring
-    if let Err(e) = security::delete_credentials(&email) {
+    if let Err(e) = security::delete_credentials(&namespace, &email) {
         eprintln!(
             "⚠️  Warning: Failed to delete credentials from keyring: {}",
             e
         );
     }
 
+    audit::record("account_deleted", Some(&email), "").await;
     println!("✅ Account deleted: {}", email);
     Ok(())
 }
@@ -38,7 +65,7 @@ pub async fn save_account_config(config: AccountConfig) -> Result<(), String> {
     // Save non-sensitive data to database
     // If config has an ID, use UPDATE to preserve the ID
     // Otherwise use INSERT to create new account
-    if let Some(id) = config.id {
+    let namespace = if let Some(id) = config.id {
         // Update existing account
         sqlx::query(
             "UPDATE accounts
@@ -56,12 +83,15 @@ pub async fn save_account_config(config: AccountConfig) -> Result<(), String> {
         .execute(&*pool)
         .await
         .map_err(|e| e.to_string())?;
+
+        security::account_namespace(id).await?
     } else {
-        // Insert new account
+        // Insert new account with its own keyring namespace
+        let namespace = security::new_namespace();
         sqlx::query(
             "INSERT INTO accounts
-             (email, imap_server, imap_port, smtp_server, smtp_port, auth_type, display_name)
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+             (email, imap_server, imap_port, smtp_server, smtp_port, auth_type, display_name, credential_ns)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&config.email)
         .bind(&config.imap_server)
@@ -70,10 +100,13 @@ pub async fn save_account_config(config: AccountConfig) -> Result<(), String> {
         .bind(config.smtp_port as i64)
         .bind(auth_type)
         .bind(&config.display_name)
+        .bind(&namespace)
         .execute(&*pool)
         .await
         .map_err(|e| e.to_string())?;
-    }
+
+        namespace
+    };
 
     // Save sensitive credentials to OS keyring
     let credentials = security::AccountCredentials {
@@ -84,8 +117,9 @@ pub async fn save_account_config(config: AccountConfig) -> Result<(), String> {
         token_expires_at: config.token_expires_at,
     };
 
-    security::store_credentials(&credentials)?;
+    security::store_credentials(&namespace, &credentials)?;
 
+    audit::record("account_credentials_saved", Some(&config.email), auth_type).await;
     println!("✅ Account saved securely: {}", config.email);
     Ok(())
 }
@@ -95,58 +129,95 @@ pub async fn load_account_configs() -> Result<Vec<AccountConfig>, String> {
     let pool = pool();
 
     // Load non-sensitive data from database
-    let accounts = sqlx::query_as::<_, (i64, String, String, i64, String, i64, String, Option<String>)>(
+    let rows = sqlx::query_as::<_, (i64, String, String, i64, String, i64, String, Option<String>)>(
         "SELECT id, email, imap_server, imap_port, smtp_server, smtp_port, auth_type, display_name FROM accounts",
     )
     .fetch_all(&*pool)
     .await
-    .map_err(|e| e.to_string())?
-    .into_iter()
-    .map(
-        |(id, email, imap_server, imap_port, smtp_server, smtp_port, auth_type, display_name)| {
-            let auth_type_enum = match auth_type.as_str() {
-                "oauth2" => Some(AuthType::OAuth2),
-                _ => Some(AuthType::Basic),
-            };
-
-            // Load sensitive credentials from keyring
-            match security::get_credentials(&email) {
-                Ok(creds) => AccountConfig {
+    .map_err(|e| e.to_string())?;
+
+    let mut accounts = Vec::with_capacity(rows.len());
+    for (id, email, imap_server, imap_port, smtp_server, smtp_port, auth_type, display_name) in rows
+    {
+        let auth_type_enum = match auth_type.as_str() {
+            "oauth2" => Some(AuthType::OAuth2),
+            _ => Some(AuthType::Basic),
+        };
+
+        // Load sensitive credentials from keyring
+        let creds = match security::account_namespace(id as i32).await {
+            Ok(namespace) => security::get_credentials(&namespace, &email),
+            Err(e) => Err(e),
+        };
+
+        let account = match creds {
+            Ok(creds) => AccountConfig {
+                id: Some(id as i32),
+                email,
+                password: creds.password,
+                imap_server,
+                imap_port: imap_port as u16,
+                smtp_server,
+                smtp_port: smtp_port as u16,
+                auth_type: auth_type_enum,
+                access_token: creds.access_token,
+                refresh_token: creds.refresh_token,
+                token_expires_at: creds.token_expires_at,
+                display_name,
+            },
+            Err(e) => {
+                eprintln!("⚠️  Failed to load credentials for {}: {}", email, e);
+                // Return account config without credentials
+                AccountConfig {
                     id: Some(id as i32),
                     email,
-                    password: creds.password,
+                    password: None,
                     imap_server,
                     imap_port: imap_port as u16,
                     smtp_server,
                     smtp_port: smtp_port as u16,
                     auth_type: auth_type_enum,
-                    access_token: creds.access_token,
-                    refresh_token: creds.refresh_token,
-                    token_expires_at: creds.token_expires_at,
+                    access_token: None,
+                    refresh_token: None,
+                    token_expires_at: None,
                     display_name,
-                },
-                Err(e) => {
-                    eprintln!("⚠️  Failed to load credentials for {}: {}", email, e);
-                    // Return account config without credentials
-                    AccountConfig {
-                        id: Some(id as i32),
-                        email,
-                        password: None,
-                        imap_server,
-                        imap_port: imap_port as u16,
-                        smtp_server,
-                        smtp_port: smtp_port as u16,
-                        auth_type: auth_type_enum,
-                        access_token: None,
-                        refresh_token: None,
-                        token_expires_at: None,
-                        display_name,
-                    }
                 }
             }
-        },
-    )
-    .collect();
+        };
+        accounts.push(account);
+    }
 
     Ok(accounts)
 }
+
+/// Best-effort revocation of an OAuth account's tokens; failures are logged only
+async fn revoke_oauth_grant(namespace: &str, email: &str, imap_server: &str) {
+    let Some(provider) = OAuth2Provider::for_imap_server(imap_server) else {
+        return;
+    };
+
+    let creds = match security::get_credentials(namespace, email) {
+        Ok(creds) => creds,
+        Err(e) => {
+            eprintln!("⚠️  Could not load tokens to revoke for {}: {}", email, e);
+            return;
+        }
+    };
+
+    // Revoking the refresh token also invalidates access tokens issued from it
+    let Some(token) = creds.refresh_token.or(creds.access_token) else {
+        return;
+    };
+
+    match provider.revoke_token(&token).await {
+        Ok(true) => {
+            audit::record("oauth_grant_revoked", Some(email), &provider.name).await;
+            println!("✅ OAuth grant revoked for {}", email);
+        }
+        Ok(false) => println!(
+            "ℹ️  {} has no revocation endpoint; remove access for {} from the account's security page",
+            provider.name, email
+        ),
+        Err(e) => eprintln!("⚠️  Failed to revoke OAuth grant for {}: {}", email, e),
+    }
+}
@@ -0,0 +1,433 @@
+// Email aging and auto-archive policies
+// Per-folder rules such as "archive read mail older than one year". A maintenance task applies
+// the enabled policies a few times a day; `preview_folder_policy` shows what a rule would move.
+
+use crate::commands::accounts::load_account_configs;
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::emails::delete::find_trash_folder;
+use crate::commands::emails::imap_helpers;
+use crate::commands::folder_names::FolderRole;
+use crate::commands::folders::folder_for_role;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use crate::models::AccountConfig;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::command;
+
+/// How often the maintenance task applies policies
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Delay before the first run, so startup sync isn't competing with it
+const FIRST_RUN_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// UIDs per MOVE/COPY command, to keep command lines reasonably short
+const UIDS_PER_COMMAND: usize = 500;
+
+/// What a policy does with matching emails
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    Archive,
+    Trash,
+}
+
+impl PolicyAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PolicyAction::Archive => "archive",
+            PolicyAction::Trash => "trash",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "trash" => PolicyAction::Trash,
+            _ => PolicyAction::Archive,
+        }
+    }
+}
+
+/// An aging policy for one folder
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FolderPolicy {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub account_id: i32,
+    pub folder_name: String,
+    pub action: PolicyAction,
+    pub older_than_days: i64,
+    pub only_read: bool,
+    pub target_folder: Option<String>, // Archive destination; detected when not set
+    pub enabled: bool,
+    #[serde(default)]
+    pub last_run_at: Option<i64>,
+}
+
+/// An email a policy would move
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyCandidate {
+    pub uid: u32,
+    pub subject: String,
+    pub from: String,
+    pub date: String,
+    pub timestamp: i64,
+}
+
+/// Outcome of applying one policy
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyRunResult {
+    pub policy_id: i64,
+    pub folder_name: String,
+    pub moved: usize,
+    pub error: Option<String>,
+}
+
+type PolicyRow = (
+    i64,
+    i32,
+    String,
+    String,
+    i64,
+    bool,
+    Option<String>,
+    bool,
+    Option<i64>,
+);
+
+fn policy_from_row(row: PolicyRow) -> FolderPolicy {
+    let (
+        id,
+        account_id,
+        folder_name,
+        action,
+        older_than_days,
+        only_read,
+        target_folder,
+        enabled,
+        last_run_at,
+    ) = row;
+    FolderPolicy {
+        id: Some(id),
+        account_id,
+        folder_name,
+        action: PolicyAction::parse(&action),
+        older_than_days,
+        only_read,
+        target_folder,
+        enabled,
+        last_run_at,
+    }
+}
+
+async fn load_policies(account_id: Option<i32>) -> Result<Vec<FolderPolicy>, String> {
+    let pool = db::pool();
+
+    let rows = sqlx::query_as::<_, PolicyRow>(
+        "SELECT id, account_id, folder_name, action, older_than_days, only_read, target_folder,
+            enabled, last_run_at
+         FROM folder_policies
+         WHERE ?1 IS NULL OR account_id = ?1
+         ORDER BY account_id, folder_name",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load folder policies: {}", e))?;
+
+    Ok(rows.into_iter().map(policy_from_row).collect())
+}
+
+/// List the aging policies of an account
+#[command]
+pub async fn list_folder_policies(account_id: i32) -> Result<Vec<FolderPolicy>, String> {
+    load_policies(Some(account_id)).await
+}
+
+/// Create or replace the aging policy of a folder, returning its id
+#[command]
+pub async fn save_folder_policy(policy: FolderPolicy) -> Result<i64, String> {
+    if policy.older_than_days < 1 {
+        return Err("Age must be at least one day".to_string());
+    }
+
+    let pool = db::pool();
+
+    let (id,) = sqlx::query_as::<_, (i64,)>(
+        "INSERT INTO folder_policies
+            (account_id, folder_name, action, older_than_days, only_read, target_folder, enabled)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(account_id, folder_name) DO UPDATE SET
+            action = excluded.action,
+            older_than_days = excluded.older_than_days,
+            only_read = excluded.only_read,
+            target_folder = excluded.target_folder,
+            enabled = excluded.enabled
+         RETURNING id",
+    )
+    .bind(policy.account_id)
+    .bind(&policy.folder_name)
+    .bind(policy.action.as_str())
+    .bind(policy.older_than_days)
+    .bind(policy.only_read)
+    .bind(&policy.target_folder)
+    .bind(policy.enabled)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save folder policy: {}", e))?;
+
+    Ok(id)
+}
+
+/// Delete an aging policy
+#[command]
+pub async fn delete_folder_policy(policy_id: i64) -> Result<(), String> {
+    let pool = db::pool();
+
+    sqlx::query("DELETE FROM folder_policies WHERE id = ?")
+        .bind(policy_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete folder policy: {}", e))?;
+
+    Ok(())
+}
+
+/// Dry run: list the cached emails a policy would move right now (saved or not)
+#[command]
+pub async fn preview_folder_policy(policy: FolderPolicy) -> Result<Vec<PolicyCandidate>, String> {
+    find_candidates(&policy).await
+}
+
+/// Apply the enabled policies now, for one account or all of them
+#[command]
+pub async fn run_folder_policies(account_id: Option<i32>) -> Result<Vec<PolicyRunResult>, String> {
+    let accounts = load_account_configs().await?;
+    let mut results = Vec::new();
+
+    for policy in load_policies(account_id).await? {
+        if !policy.enabled {
+            continue;
+        }
+        let Some(config) = accounts
+            .iter()
+            .find(|account| account.id == Some(policy.account_id))
+        else {
+            continue;
+        };
+
+        let policy_id = policy.id.unwrap_or_default();
+        let result = apply_policy(config.clone(), &policy).await;
+
+        match &result {
+            Ok(moved) => println!(
+                "🗄️ Policy for '{}' moved {} emails ({})",
+                policy.folder_name,
+                moved,
+                policy.action.as_str()
+            ),
+            Err(e) => eprintln!("⚠️ Policy for '{}' failed: {}", policy.folder_name, e),
+        }
+
+        results.push(PolicyRunResult {
+            policy_id,
+            folder_name: policy.folder_name.clone(),
+            moved: *result.as_ref().unwrap_or(&0),
+            error: result.err(),
+        });
+    }
+
+    Ok(results)
+}
+
+/// Background task that applies enabled policies periodically until shutdown
+pub async fn maintenance_loop() {
+    let shutdown_token = crate::shutdown::token();
+    let mut delay = FIRST_RUN_DELAY;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+
+        if let Err(e) = run_folder_policies(None).await {
+            tracing::warn!(error = %e, "Folder policy maintenance failed");
+        }
+        if let Err(e) = crate::commands::attachment_archive::run_policy().await {
+            tracing::warn!(error = %e, "Attachment archive maintenance failed");
+        }
+        delay = MAINTENANCE_INTERVAL;
+    }
+}
+
+/// Cached emails in the policy's folder that are old enough (and read, if required)
+async fn find_candidates(policy: &FolderPolicy) -> Result<Vec<PolicyCandidate>, String> {
+    let pool = db::pool();
+    let cutoff = chrono::Utc::now().timestamp() - policy.older_than_days * 24 * 60 * 60;
+
+    let rows = sqlx::query_as::<_, (i64, String, String, String, i64)>(
+        "SELECT uid, subject, from_addr, date, timestamp FROM emails
+         WHERE account_id = ? AND folder_name = ? AND timestamp < ?
+            AND (? = 0 OR COALESCE(seen, 0) = 1)
+         ORDER BY timestamp",
+    )
+    .bind(policy.account_id)
+    .bind(&policy.folder_name)
+    .bind(cutoff)
+    .bind(policy.only_read)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to find emails for policy: {}", e))?;
+
+    let decrypt_subjects = is_encryption_enabled().await? && is_encryption_unlocked();
+
+    Ok(rows
+        .into_iter()
+        .map(|(uid, subject, from, date, timestamp)| PolicyCandidate {
+            uid: uid as u32,
+            subject: if decrypt_subjects {
+                decrypt(&subject).unwrap_or_else(|_| format!("[Encrypted subject - UID {}]", uid))
+            } else {
+                subject
+            },
+            from,
+            date,
+            timestamp,
+        })
+        .collect())
+}
+
+/// Archive folder of an account: the policy's target, a \Archive folder, or one named Archive
+async fn resolve_archive_folder(policy: &FolderPolicy) -> Result<String, String> {
+    if let Some(target) = policy.target_folder.as_ref().filter(|t| !t.is_empty()) {
+        return Ok(target.clone());
+    }
+    if let Some(archive) = folder_for_role(policy.account_id, FolderRole::Archive).await? {
+        return Ok(archive);
+    }
+
+    let pool = db::pool();
+    sqlx::query_as::<_, (String,)>(
+        "SELECT name FROM folders
+         WHERE account_id = ? AND COALESCE(is_local, 0) = 0
+            AND (flags LIKE '%Archive%' OR lower(display_name) IN ('archive', 'archives'))
+         ORDER BY flags LIKE '%Archive%' DESC
+         LIMIT 1",
+    )
+    .bind(policy.account_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to look up archive folder: {}", e))?
+    .map(|(name,)| name)
+    .ok_or_else(|| "No archive folder found; choose a target folder for this policy".to_string())
+}
+
+/// Move the policy's candidates on the server and drop them from the local cache
+async fn apply_policy(config: AccountConfig, policy: &FolderPolicy) -> Result<usize, String> {
+    let uids: Vec<u32> = find_candidates(policy)
+        .await?
+        .into_iter()
+        .map(|candidate| candidate.uid)
+        .collect();
+    if uids.is_empty() {
+        return Ok(0);
+    }
+
+    let archive_folder = match policy.action {
+        PolicyAction::Archive => Some(resolve_archive_folder(policy).await?),
+        PolicyAction::Trash => None,
+    };
+    if archive_folder.as_deref() == Some(policy.folder_name.as_str()) {
+        return Err("Target folder is the same as the source folder".to_string());
+    }
+
+    let config = ensure_valid_token(config).await?;
+    let folder_name = policy.folder_name.clone();
+    let uids_for_task = uids.clone();
+    let known_trash = match archive_folder {
+        Some(_) => None,
+        None => folder_for_role(policy.account_id, FolderRole::Trash).await?,
+    };
+
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+        let target = match archive_folder.or(known_trash) {
+            Some(folder) => folder,
+            None => find_trash_folder(&mut imap_session)?,
+        };
+
+        let capabilities = imap_session
+            .capabilities()
+            .map_err(|e| format!("Failed to get capabilities: {}", e))?;
+        let supports_move = capabilities.has_str("MOVE");
+        let supports_uidplus = capabilities.has_str("UIDPLUS");
+
+        // Policies run unattended: a plain EXPUNGE would also remove whatever the user marked
+        // \Deleted in another client, so without MOVE or UIDPLUS the policy is skipped
+        if !supports_move && !supports_uidplus {
+            let _ = imap_session.logout();
+            return Err(
+                "Skipped: the server supports neither MOVE nor UIDPLUS, so messages can't be \
+                 moved without expunging others marked as deleted"
+                    .to_string(),
+            );
+        }
+
+        imap_session
+            .select(&folder_name)
+            .map_err(|e| format!("Cannot access folder '{}': {}", folder_name, e))?;
+
+        for chunk in uids_for_task.chunks(UIDS_PER_COMMAND) {
+            let uid_set = chunk
+                .iter()
+                .map(|uid| uid.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            if supports_move {
+                imap_session
+                    .uid_mv(&uid_set, &target)
+                    .map_err(|e| format!("Failed to move emails to '{}': {}", target, e))?;
+                continue;
+            }
+
+            imap_session
+                .uid_copy(&uid_set, &target)
+                .map_err(|e| format!("Failed to copy emails to '{}': {}", target, e))?;
+            imap_session
+                .uid_store(&uid_set, "+FLAGS.SILENT (\\Deleted)")
+                .map_err(|e| format!("Failed to mark emails as deleted: {}", e))?;
+            imap_session
+                .uid_expunge(&uid_set)
+                .map_err(|e| format!("Failed to expunge emails: {}", e))?;
+        }
+
+        let _ = imap_session.logout();
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+
+    let pool = db::pool();
+    for uid in &uids {
+        sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?")
+            .bind(policy.account_id)
+            .bind(&policy.folder_name)
+            .bind(*uid as i64)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to remove moved email from cache: {}", e))?;
+    }
+
+    sqlx::query("UPDATE folder_policies SET last_run_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().timestamp())
+        .bind(policy.id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to update policy run time: {}", e))?;
+
+    Ok(uids.len())
+}
@@ -0,0 +1,304 @@
+// Attachment archive
+// Attachment blobs of old emails can be moved out of the database into a user-chosen directory
+// so the app data folder doesn't grow without bound. The attachment row keeps its metadata and
+// the path of the archived file, and its data is read back from there whenever the attachment
+// is opened. Blobs are written exactly as stored, so encrypted attachments stay encrypted.
+// Deleting an attachment row (directly or with its email or account) queues its file in
+// `archived_attachment_removals`, and `remove_deleted_files` takes it off the disk.
+
+use crate::db;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::command;
+
+const DIR_KEY: &str = "attachment_archive_dir";
+const MONTHS_KEY: &str = "attachment_archive_months";
+/// Attachments moved per database round trip
+const BATCH_SIZE: i64 = 100;
+
+/// Where old attachments go and how old they must be
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttachmentArchivePolicy {
+    pub directory: String,
+    pub older_than_months: u32,
+}
+
+/// Result of an archive run
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AttachmentArchiveResult {
+    pub archived: u64,
+    pub bytes_moved: u64,
+}
+
+/// Attachment data, read from the archive if the blob was moved out of the database
+pub async fn rehydrate(data: Vec<u8>, archived_path: Option<String>) -> Result<Vec<u8>, String> {
+    let Some(path) = archived_path else {
+        return Ok(data);
+    };
+    tokio::task::spawn_blocking(move || {
+        std::fs::read(&path)
+            .map_err(|e| format!("Failed to read archived attachment {}: {}", path, e))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Delete the archived files of attachments that were deleted; returns the bytes freed
+pub async fn remove_deleted_files() -> Result<u64, String> {
+    let pool = db::pool();
+    let paths: Vec<String> =
+        sqlx::query_as::<_, (String,)>("SELECT path FROM archived_attachment_removals")
+            .fetch_all(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load deleted archived attachments: {}", e))?
+            .into_iter()
+            .map(|(path,)| path)
+            .collect();
+    if paths.is_empty() {
+        return Ok(0);
+    }
+
+    let (removed, freed) = tokio::task::spawn_blocking(move || {
+        let mut removed = Vec::new();
+        let mut freed = 0u64;
+        for path in paths {
+            let size = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            match std::fs::remove_file(&path) {
+                Ok(()) => freed += size,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    eprintln!("⚠️ Failed to delete archived attachment {}: {}", path, e);
+                    continue;
+                }
+            }
+            removed.push(path);
+        }
+        (removed, freed)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    for path in &removed {
+        sqlx::query("DELETE FROM archived_attachment_removals WHERE path = ?")
+            .bind(path)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to update deleted archived attachments: {}", e))?;
+    }
+    if !removed.is_empty() {
+        println!(
+            "🧹 Deleted {} archived attachment file(s) ({} bytes)",
+            removed.len(),
+            freed
+        );
+    }
+    Ok(freed)
+}
+
+/// Total size of the archived attachment files still in use
+pub async fn archived_bytes() -> Result<u64, String> {
+    let pool = db::pool();
+    let paths: Vec<String> = sqlx::query_as::<_, (String,)>(
+        "SELECT archived_path FROM attachments WHERE archived_path IS NOT NULL",
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load archived attachments: {}", e))?
+    .into_iter()
+    .map(|(path,)| path)
+    .collect();
+
+    tokio::task::spawn_blocking(move || {
+        paths
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|meta| meta.len())
+            .sum()
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// Every archived attachment file, including files of deleted rows not removed yet
+pub async fn archived_files() -> Result<Vec<PathBuf>, String> {
+    let pool = db::pool();
+    let paths = sqlx::query_as::<_, (String,)>(
+        "SELECT archived_path FROM attachments WHERE archived_path IS NOT NULL
+         UNION SELECT path FROM archived_attachment_removals",
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load archived attachments: {}", e))?;
+    Ok(paths
+        .into_iter()
+        .map(|(path,)| PathBuf::from(path))
+        .collect())
+}
+
+async fn load_policy() -> Result<Option<AttachmentArchivePolicy>, String> {
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT key, value FROM settings WHERE key IN (?, ?)",
+    )
+    .bind(DIR_KEY)
+    .bind(MONTHS_KEY)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load attachment archive policy: {}", e))?;
+
+    let value = |key: &str| rows.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+    Ok(match (value(DIR_KEY), value(MONTHS_KEY)) {
+        (Some(directory), Some(months)) => {
+            months
+                .parse()
+                .ok()
+                .map(|older_than_months| AttachmentArchivePolicy {
+                    directory,
+                    older_than_months,
+                })
+        }
+        _ => None,
+    })
+}
+
+/// Move attachment blobs of emails older than the policy's age into its directory
+async fn archive_attachments(
+    policy: &AttachmentArchivePolicy,
+) -> Result<AttachmentArchiveResult, String> {
+    if policy.older_than_months == 0 {
+        return Err("Archive age must be at least one month".to_string());
+    }
+    let directory = PathBuf::from(&policy.directory);
+
+    let pool = db::pool();
+    let cutoff = (chrono::Utc::now() - chrono::Months::new(policy.older_than_months)).timestamp();
+    let mut result = AttachmentArchiveResult::default();
+
+    loop {
+        let rows = sqlx::query_as::<_, (i64, i32, Vec<u8>)>(
+            "SELECT a.id, e.account_id, a.data FROM attachments a
+             JOIN emails e ON e.id = a.email_id
+             WHERE a.archived_path IS NULL AND e.timestamp < ? AND LENGTH(a.data) > 0
+             LIMIT ?",
+        )
+        .bind(cutoff)
+        .bind(BATCH_SIZE)
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load attachments to archive: {}", e))?;
+
+        if rows.is_empty() {
+            break;
+        }
+
+        for (id, account_id, data) in rows {
+            // Row ids can be reused after a delete, so file names carry a random part and a
+            // new attachment never takes over the file of an old one
+            let path = directory.join(account_id.to_string()).join(format!(
+                "{}-{:016x}.bin",
+                id,
+                rand::random::<u64>()
+            ));
+            let size = data.len() as u64;
+            let path_for_write = path.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Some(dir) = path_for_write.parent() {
+                    std::fs::create_dir_all(dir)
+                        .map_err(|e| format!("Failed to create archive directory: {}", e))?;
+                }
+                std::fs::write(&path_for_write, &data)
+                    .map_err(|e| format!("Failed to write archived attachment: {}", e))
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+
+            // Only drop the blob once the file is safely written
+            sqlx::query("UPDATE attachments SET data = X'', archived_path = ? WHERE id = ?")
+                .bind(path.to_string_lossy().to_string())
+                .bind(id)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to update archived attachment: {}", e))?;
+
+            result.archived += 1;
+            result.bytes_moved += size;
+        }
+
+        if crate::shutdown::is_shutting_down() {
+            break;
+        }
+    }
+
+    if result.archived > 0 {
+        println!(
+            "📦 Archived {} attachments ({} bytes) to {}",
+            result.archived, result.bytes_moved, policy.directory
+        );
+    }
+    Ok(result)
+}
+
+/// Apply the saved archive policy, if any, and delete files of removed attachments (called by
+/// periodic maintenance)
+pub async fn run_policy() -> Result<(), String> {
+    remove_deleted_files().await?;
+    if let Some(policy) = load_policy().await? {
+        archive_attachments(&policy).await?;
+    }
+    Ok(())
+}
+
+/// Get the saved attachment archive policy
+#[command]
+pub async fn get_attachment_archive_policy() -> Result<Option<AttachmentArchivePolicy>, String> {
+    load_policy().await
+}
+
+/// Save (or with `None`, remove) the policy applied by periodic maintenance
+#[command]
+pub async fn set_attachment_archive_policy(
+    policy: Option<AttachmentArchivePolicy>,
+) -> Result<(), String> {
+    let pool = db::pool();
+    match policy {
+        Some(policy) => {
+            if policy.older_than_months == 0 {
+                return Err("Archive age must be at least one month".to_string());
+            }
+            for (key, value) in [
+                (DIR_KEY, policy.directory),
+                (MONTHS_KEY, policy.older_than_months.to_string()),
+            ] {
+                sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+                    .bind(key)
+                    .bind(value)
+                    .execute(pool.as_ref())
+                    .await
+                    .map_err(|e| format!("Failed to save attachment archive policy: {}", e))?;
+            }
+        }
+        None => {
+            sqlx::query("DELETE FROM settings WHERE key IN (?, ?)")
+                .bind(DIR_KEY)
+                .bind(MONTHS_KEY)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to remove attachment archive policy: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Archive old attachments now, using the given policy or the saved one
+#[command]
+pub async fn archive_old_attachments(
+    policy: Option<AttachmentArchivePolicy>,
+) -> Result<AttachmentArchiveResult, String> {
+    let policy = match policy {
+        Some(policy) => policy,
+        None => load_policy()
+            .await?
+            .ok_or("No attachment archive directory configured")?,
+    };
+    archive_attachments(&policy).await
+}
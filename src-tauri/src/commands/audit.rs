@@ -0,0 +1,69 @@
+// Security audit log
+// Append-only record of security-relevant actions (vault unlock/lock, password changes,
+// credential changes, denied commands). Rows can't be updated or deleted; see db::init().
+
+use crate::db;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// A single audit log entry
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: i64,
+    pub event_type: String,
+    pub account: Option<String>, // Account email, for account-scoped events
+    pub details: String,
+}
+
+/// Append an event to the audit log
+///
+/// Failures are logged but never returned, so auditing can't break the action being audited.
+pub async fn record(event_type: &str, account: Option<&str>, details: &str) {
+    tracing::info!(
+        target: "audit",
+        event = event_type,
+        account = account.unwrap_or(""),
+        details = details,
+        "Security event"
+    );
+
+    let pool = db::pool();
+    let result = sqlx::query(
+        "INSERT INTO audit_log (timestamp, event_type, account, details) VALUES (?, ?, ?, ?)",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(event_type)
+    .bind(account)
+    .bind(details)
+    .execute(pool.as_ref())
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("⚠️ Failed to write audit log entry '{}': {}", event_type, e);
+    }
+}
+
+/// Get audit log entries, newest first
+#[command]
+pub async fn get_audit_log(
+    limit: Option<i64>,
+    offset: Option<i64>,
+    event_type: Option<String>,
+) -> Result<Vec<AuditEntry>, String> {
+    let pool = db::pool();
+
+    sqlx::query_as::<_, AuditEntry>(
+        "SELECT id, timestamp, event_type, account, details
+         FROM audit_log
+         WHERE ?1 IS NULL OR event_type = ?1
+         ORDER BY id DESC
+         LIMIT ?2 OFFSET ?3",
+    )
+    .bind(event_type)
+    .bind(limit.unwrap_or(200))
+    .bind(offset.unwrap_or(0))
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load audit log: {}", e))
+}
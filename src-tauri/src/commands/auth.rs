@@ -1,11 +1,14 @@
+use crate::commands::authorization::{authorize, Sensitivity};
 use keyring::Entry;
 use tauri::command;
 
-const AUTH_SERVICE_NAME: &str = "com.colimail.app.auth";
+pub(crate) const AUTH_SERVICE_NAME: &str = "com.colimail.app.auth";
 
 /// Store a value in secure storage (OS keyring)
 #[command]
 pub async fn get_secure_storage(key: String) -> Result<String, String> {
+    authorize("get_secure_storage", Sensitivity::Sensitive).await?;
+
     let entry = Entry::new(AUTH_SERVICE_NAME, &key)
         .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
 
@@ -0,0 +1,162 @@
+// Backend authorization for sensitive commands
+// Sensitive commands require the encryption vault to be unlocked. High-risk commands can
+// additionally require the master password to have been re-entered recently.
+
+use crate::commands::audit;
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::encryption_manager::{
+    check_unlock_allowed, record_failed_unlock, reset_unlock_attempts,
+};
+use crate::db;
+use crate::encryption::{is_encryption_unlocked, verify_password};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle};
+
+/// How long a master password confirmation authorizes high-risk commands
+const CONFIRMATION_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+lazy_static::lazy_static! {
+    /// When the master password was last confirmed in this session
+    static ref LAST_CONFIRMED: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// How sensitive a command is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensitivity {
+    /// Needs an unlocked vault
+    Sensitive,
+    /// Needs an unlocked vault and, if re-prompting is enabled, a recent password confirmation
+    HighRisk,
+}
+
+/// Check that `command_name` may run now
+///
+/// When encryption is disabled there is no vault to unlock, so only the re-prompt
+/// requirement (if enabled) applies.
+pub async fn authorize(command_name: &str, sensitivity: Sensitivity) -> Result<(), String> {
+    let result = check(sensitivity).await;
+
+    match (&result, sensitivity) {
+        (Ok(()), Sensitivity::Sensitive) => {
+            tracing::debug!(command = command_name, "Sensitive command authorized")
+        }
+        (Ok(()), Sensitivity::HighRisk) => {
+            audit::record("command_authorized", None, command_name).await;
+        }
+        (Err(reason), _) => {
+            audit::record(
+                "command_denied",
+                None,
+                &format!("{}: {}", command_name, reason),
+            )
+            .await;
+        }
+    }
+
+    result
+}
+
+async fn check(sensitivity: Sensitivity) -> Result<(), String> {
+    let encryption_enabled = is_encryption_enabled().await?;
+
+    if encryption_enabled && !is_encryption_unlocked() {
+        return Err("Vault is locked. Unlock it with your master password first.".to_string());
+    }
+
+    if sensitivity == Sensitivity::HighRisk
+        && encryption_enabled
+        && get_reprompt_sensitive_commands().await?
+        && !has_recent_confirmation()
+    {
+        return Err("Master password confirmation required".to_string());
+    }
+
+    Ok(())
+}
+
+fn has_recent_confirmation() -> bool {
+    LAST_CONFIRMED
+        .lock()
+        .unwrap()
+        .map(|at| at.elapsed() < CONFIRMATION_WINDOW)
+        .unwrap_or(false)
+}
+
+/// Forget any earlier password confirmation (called when the vault is locked)
+pub fn clear_confirmation() {
+    *LAST_CONFIRMED.lock().unwrap() = None;
+}
+
+/// Re-enter the master password to authorize high-risk commands for a few minutes
+#[command]
+pub async fn confirm_master_password(
+    app_handle: AppHandle,
+    password: String,
+) -> Result<(), String> {
+    // Shares the unlock attempt counter so it can't be used to bypass the lockout
+    check_unlock_allowed().await?;
+
+    let pool = db::pool();
+
+    let password_hash =
+        sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = 'password_hash'")
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to get password hash: {}", e))?
+            .map(|(hash,)| hash)
+            .filter(|hash| !hash.is_empty())
+            .ok_or("Encryption is not enabled")?;
+
+    if !verify_password(&password, &password_hash)
+        .map_err(|e| format!("Password verification failed: {}", e))?
+    {
+        audit::record("master_password_confirm_failed", None, "Invalid password").await;
+        record_failed_unlock(&app_handle).await?;
+        return Err("Invalid password".to_string());
+    }
+
+    reset_unlock_attempts().await?;
+
+    *LAST_CONFIRMED.lock().unwrap() = Some(Instant::now());
+    audit::record("master_password_confirmed", None, "").await;
+    Ok(())
+}
+
+/// Get whether high-risk commands re-prompt for the master password
+#[command]
+pub async fn get_reprompt_sensitive_commands() -> Result<bool, String> {
+    let pool = db::pool();
+    let result = sqlx::query_as::<_, (String,)>(
+        "SELECT value FROM settings WHERE key = 'reprompt_sensitive_commands'",
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to get re-prompt setting: {}", e))?;
+
+    Ok(result.map(|(value,)| value == "true").unwrap_or(false))
+}
+
+/// Set whether high-risk commands re-prompt for the master password
+#[command]
+pub async fn set_reprompt_sensitive_commands(enabled: bool) -> Result<(), String> {
+    // Turning the re-prompt off must itself pass the re-prompt
+    if !enabled {
+        authorize("set_reprompt_sensitive_commands", Sensitivity::HighRisk).await?;
+    }
+
+    let pool = db::pool();
+    let value = if enabled { "true" } else { "false" };
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('reprompt_sensitive_commands', ?)",
+    )
+    .bind(value)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to set re-prompt setting: {}", e))?;
+
+    audit::record("reprompt_setting_changed", None, value).await;
+
+    Ok(())
+}
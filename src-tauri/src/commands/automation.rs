@@ -0,0 +1,413 @@
+// Automation hooks
+// Users can register actions that run when new mail arrives: POST a JSON payload to a webhook
+// or pipe it into a local command. Each hook has a filter and a minimum interval between runs.
+
+use crate::commands::authorization::{authorize, Sensitivity};
+use crate::db;
+use crate::models::EmailHeader;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::command;
+use tokio::io::AsyncWriteExt;
+
+/// How long a webhook request or local command may take
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a hook does when it fires
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookAction {
+    /// POST the payload to `target` (a URL)
+    Webhook,
+    /// Run `target` (an executable path, no shell) with the payload on stdin
+    Command,
+}
+
+impl HookAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HookAction::Webhook => "webhook",
+            HookAction::Command => "command",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "command" => HookAction::Command,
+            _ => HookAction::Webhook,
+        }
+    }
+}
+
+/// A registered automation hook for new mail
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AutomationHook {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub name: String,
+    pub enabled: bool,
+    pub account_id: Option<i32>,       // None matches all accounts
+    pub folder_name: Option<String>,   // None matches all folders
+    pub match_from: Option<String>,    // Case-insensitive substring of the sender
+    pub match_subject: Option<String>, // Case-insensitive substring of the subject
+    pub action: HookAction,
+    pub target: String,
+    pub payload_template: Option<String>, // JSON with {{placeholders}}; a default payload if None
+    pub min_interval_secs: i64,
+    #[serde(default)]
+    pub last_fired_at: Option<i64>,
+}
+
+type HookRow = (
+    i64,
+    String,
+    bool,
+    Option<i32>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    String,
+    Option<String>,
+    i64,
+    Option<i64>,
+);
+
+fn hook_from_row(row: HookRow) -> AutomationHook {
+    let (
+        id,
+        name,
+        enabled,
+        account_id,
+        folder_name,
+        match_from,
+        match_subject,
+        action,
+        target,
+        payload_template,
+        min_interval_secs,
+        last_fired_at,
+    ) = row;
+    AutomationHook {
+        id: Some(id),
+        name,
+        enabled,
+        account_id,
+        folder_name,
+        match_from,
+        match_subject,
+        action: HookAction::parse(&action),
+        target,
+        payload_template,
+        min_interval_secs,
+        last_fired_at,
+    }
+}
+
+async fn load_hooks() -> Result<Vec<AutomationHook>, String> {
+    let pool = db::pool();
+
+    let rows = sqlx::query_as::<_, HookRow>(
+        "SELECT id, name, enabled, account_id, folder_name, match_from, match_subject, action,
+            target, payload_template, min_interval_secs, last_fired_at
+         FROM automation_hooks ORDER BY id",
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load automation hooks: {}", e))?;
+
+    Ok(rows.into_iter().map(hook_from_row).collect())
+}
+
+/// List registered automation hooks
+#[command]
+pub async fn list_automation_hooks() -> Result<Vec<AutomationHook>, String> {
+    load_hooks().await
+}
+
+/// Create or update an automation hook, returning its id
+#[command]
+pub async fn save_automation_hook(hook: AutomationHook) -> Result<i64, String> {
+    // Running local programs on incoming mail is as sensitive as it gets
+    if hook.action == HookAction::Command {
+        authorize("save_automation_hook", Sensitivity::HighRisk).await?;
+    }
+
+    match hook.action {
+        HookAction::Webhook => {
+            let url = url::Url::parse(&hook.target).map_err(|e| format!("Invalid URL: {}", e))?;
+            if url.scheme() != "https" && url.scheme() != "http" {
+                return Err("Webhook URL must use http or https".to_string());
+            }
+        }
+        HookAction::Command => {
+            if !std::path::Path::new(&hook.target).is_absolute() {
+                return Err("Command must be an absolute path to an executable".to_string());
+            }
+        }
+    }
+    if let Some(template) = &hook.payload_template {
+        // Placeholders are substituted as JSON string content, so a valid template stays valid
+        serde_json::from_str::<serde_json::Value>(&render_template(template, &sample_email(), ""))
+            .map_err(|e| format!("Payload template is not valid JSON: {}", e))?;
+    }
+
+    let pool = db::pool();
+    let (id,) = sqlx::query_as::<_, (i64,)>(
+        "INSERT INTO automation_hooks
+            (id, name, enabled, account_id, folder_name, match_from, match_subject, action, target,
+             payload_template, min_interval_secs)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            enabled = excluded.enabled,
+            account_id = excluded.account_id,
+            folder_name = excluded.folder_name,
+            match_from = excluded.match_from,
+            match_subject = excluded.match_subject,
+            action = excluded.action,
+            target = excluded.target,
+            payload_template = excluded.payload_template,
+            min_interval_secs = excluded.min_interval_secs
+         RETURNING id",
+    )
+    .bind(hook.id)
+    .bind(&hook.name)
+    .bind(hook.enabled)
+    .bind(hook.account_id)
+    .bind(&hook.folder_name)
+    .bind(&hook.match_from)
+    .bind(&hook.match_subject)
+    .bind(hook.action.as_str())
+    .bind(&hook.target)
+    .bind(&hook.payload_template)
+    .bind(hook.min_interval_secs.max(0))
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save automation hook: {}", e))?;
+
+    crate::commands::audit::record(
+        "automation_hook_saved",
+        None,
+        &format!("{} ({}: {})", hook.name, hook.action.as_str(), hook.target),
+    )
+    .await;
+
+    Ok(id)
+}
+
+/// Delete an automation hook
+#[command]
+pub async fn delete_automation_hook(hook_id: i64) -> Result<(), String> {
+    let pool = db::pool();
+
+    sqlx::query("DELETE FROM automation_hooks WHERE id = ?")
+        .bind(hook_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete automation hook: {}", e))?;
+
+    Ok(())
+}
+
+/// Fire a hook once with a sample email, ignoring its filter and rate limit
+#[command]
+pub async fn test_automation_hook(hook_id: i64) -> Result<(), String> {
+    let hook = load_hooks()
+        .await?
+        .into_iter()
+        .find(|hook| hook.id == Some(hook_id))
+        .ok_or("Automation hook not found")?;
+
+    execute(&hook, &sample_email(), "INBOX").await
+}
+
+/// Run the hooks that match newly arrived emails
+///
+/// Hooks that fired less than `min_interval_secs` ago are skipped, so a burst of new mail
+/// triggers a hook at most once per interval. Failures are logged, never returned.
+pub async fn on_new_mail(account_id: i32, folder_name: &str, emails: &[EmailHeader]) {
+    if emails.is_empty() {
+        return;
+    }
+
+    let hooks = match load_hooks().await {
+        Ok(hooks) => hooks,
+        Err(e) => {
+            eprintln!("⚠️ {}", e);
+            return;
+        }
+    };
+
+    for mut hook in hooks.into_iter().filter(|hook| hook.enabled) {
+        for email in emails {
+            if !matches(&hook, account_id, folder_name, email) {
+                continue;
+            }
+
+            let now = chrono::Utc::now().timestamp();
+            if let Some(last) = hook.last_fired_at {
+                if now - last < hook.min_interval_secs {
+                    tracing::debug!(hook = %hook.name, uid = email.uid, "Automation hook rate limited");
+                    continue;
+                }
+            }
+
+            hook.last_fired_at = Some(now);
+            let pool = db::pool();
+            let _ = sqlx::query("UPDATE automation_hooks SET last_fired_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(hook.id)
+                .execute(pool.as_ref())
+                .await;
+
+            match execute(&hook, email, folder_name).await {
+                Ok(()) => println!(
+                    "⚡ Automation hook '{}' fired for UID {}",
+                    hook.name, email.uid
+                ),
+                Err(e) => eprintln!("⚠️ Automation hook '{}' failed: {}", hook.name, e),
+            }
+        }
+    }
+}
+
+fn matches(hook: &AutomationHook, account_id: i32, folder_name: &str, email: &EmailHeader) -> bool {
+    let contains = |haystack: &str, needle: &Option<String>| match needle {
+        Some(needle) if !needle.is_empty() => {
+            haystack.to_lowercase().contains(&needle.to_lowercase())
+        }
+        _ => true,
+    };
+
+    hook.account_id.map_or(true, |id| id == account_id)
+        && hook
+            .folder_name
+            .as_ref()
+            .map_or(true, |folder| folder == folder_name)
+        && contains(&email.from, &hook.match_from)
+        && contains(&email.subject, &hook.match_subject)
+}
+
+/// Substitute {{placeholders}} with JSON-escaped email fields (without surrounding quotes)
+fn render_template(template: &str, email: &EmailHeader, folder_name: &str) -> String {
+    let escape = |value: &str| {
+        let quoted = serde_json::to_string(value).unwrap_or_default();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+
+    template
+        .replace("{{uid}}", &email.uid.to_string())
+        .replace("{{subject}}", &escape(&email.subject))
+        .replace("{{from}}", &escape(&email.from))
+        .replace("{{to}}", &escape(&email.to))
+        .replace("{{date}}", &escape(&email.date))
+        .replace("{{folder}}", &escape(folder_name))
+        .replace(
+            "{{message_id}}",
+            &escape(email.message_id.as_deref().unwrap_or("")),
+        )
+}
+
+fn payload(hook: &AutomationHook, email: &EmailHeader, folder_name: &str) -> String {
+    match &hook.payload_template {
+        Some(template) => render_template(template, email, folder_name),
+        None => serde_json::json!({
+            "event": "new_mail",
+            "folder": folder_name,
+            "uid": email.uid,
+            "subject": email.subject,
+            "from": email.from,
+            "to": email.to,
+            "date": email.date,
+            "message_id": email.message_id,
+        })
+        .to_string(),
+    }
+}
+
+async fn execute(
+    hook: &AutomationHook,
+    email: &EmailHeader,
+    folder_name: &str,
+) -> Result<(), String> {
+    let body = payload(hook, email, folder_name);
+
+    match hook.action {
+        HookAction::Webhook => {
+            let response = reqwest::Client::new()
+                .post(&hook.target)
+                .header("Content-Type", "application/json")
+                .timeout(HOOK_TIMEOUT)
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| format!("Webhook request failed: {}", e))?;
+
+            if !response.status().is_success() {
+                return Err(format!("Webhook returned {}", response.status()));
+            }
+            Ok(())
+        }
+        HookAction::Command => {
+            let mut child = tokio::process::Command::new(&hook.target)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| format!("Failed to start command: {}", e))?;
+
+            // Write the payload and wait for the exit under one timeout, so a command that
+            // never reads stdin can't hang the hook
+            let mut stdin = child.stdin.take();
+            let write_payload = async move {
+                if let Some(stdin) = stdin.as_mut() {
+                    stdin
+                        .write_all(body.as_bytes())
+                        .await
+                        .map_err(|e| format!("Failed to write payload: {}", e))?;
+                }
+                drop(stdin);
+                Ok::<(), String>(())
+            };
+            let (written, output) = tokio::time::timeout(HOOK_TIMEOUT, async {
+                tokio::join!(write_payload, child.wait_with_output())
+            })
+            .await
+            .map_err(|_| "Command timed out".to_string())?;
+            let output = output.map_err(|e| format!("Command failed: {}", e))?;
+            written?;
+
+            if !output.status.success() {
+                return Err(format!(
+                    "Command exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn sample_email() -> EmailHeader {
+    EmailHeader {
+        uid: 0,
+        subject: "Test from Colimail".to_string(),
+        from: "sender@example.com".to_string(),
+        to: "you@example.com".to_string(),
+        cc: String::new(),
+        bcc: String::new(),
+        date: chrono::Utc::now().to_rfc2822(),
+        timestamp: chrono::Utc::now().timestamp(),
+        has_attachments: false,
+        seen: false,
+        flagged: false,
+        message_id: None,
+        received_at: None,
+        raw_headers: Vec::new(),
+        security: None,
+    }
+}
@@ -0,0 +1,445 @@
+// Configuration backup
+// Exports the user's environment (settings, folder and automation rules, contact groups,
+// identities, recurring sends, host overrides) to a JSON file and restores it, in whole or by
+// section, so a restore reproduces more than the message cache. Accounts and their credentials
+// are not included, and neither are the encryption salt and password hash. Account ids differ
+// between installations, so the backup records each account's email and rows are restored to
+// the local account with that email; rows for accounts that don't exist locally are skipped.
+
+use crate::commands::audit;
+use crate::commands::authorization::{authorize, Sensitivity};
+use crate::db;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{Column, Row, TypeInfo, ValueRef};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tauri::command;
+
+/// Version 2 added the account emails that account-scoped rows are mapped by
+const BACKUP_VERSION: u32 = 2;
+
+/// Settings that belong to this installation and are never exported or restored
+const EXCLUDED_SETTINGS: &[&str] = &[
+    "encryption_enabled",
+    "encryption_salt",
+    "password_hash",
+    "local_store_format", // Changing it needs a store migration, not a row update
+    "unlock_failed_attempts", // Restoring would reset or extend the unlock lockout
+    "unlock_retry_after",
+    "content_hash_version", // Tracks a migration of the local cache
+    "onboarding_state",
+    "window_state",
+];
+
+/// Parts of the configuration that can be backed up and restored independently
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupSection {
+    Settings,
+    /// Only read from old backups and never restored: sync state that doesn't match the local
+    /// message cache makes incremental syncs skip older mail
+    SyncStatus,
+    FolderSettings,
+    Automation,
+    ContactGroups,
+    Identities,
+    RecurringSends,
+    HostOverrides,
+}
+
+impl BackupSection {
+    const ALL: [BackupSection; 7] = [
+        BackupSection::Settings,
+        BackupSection::FolderSettings,
+        BackupSection::Automation,
+        BackupSection::ContactGroups,
+        BackupSection::Identities,
+        BackupSection::RecurringSends,
+        BackupSection::HostOverrides,
+    ];
+
+    /// Tables in the section, parents before children
+    fn tables(self) -> &'static [&'static str] {
+        match self {
+            BackupSection::Settings => &["settings"],
+            BackupSection::SyncStatus => &["sync_status"],
+            BackupSection::FolderSettings => &["folder_policies", "folder_sync_settings"],
+            BackupSection::Automation => &["automation_hooks"],
+            BackupSection::ContactGroups => &["contact_groups", "contact_group_members"],
+            BackupSection::Identities => &["account_identities"],
+            BackupSection::RecurringSends => &["recurring_sends"],
+            BackupSection::HostOverrides => &["host_overrides"],
+        }
+    }
+}
+
+type TableRows = Vec<Map<String, Value>>;
+type SqliteQuery<'q> = sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>;
+
+/// Tables restored by updating rows in place instead of replacing the table, because deleting
+/// a row would cascade to history that isn't part of the backup
+const UPSERTED_TABLES: &[&str] = &["recurring_sends"];
+
+/// A configuration backup file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigBackup {
+    pub version: u32,
+    pub created_at: i64,
+    /// Email of each account id used in the rows (empty in version 1 backups)
+    #[serde(default)]
+    pub accounts: BTreeMap<i64, String>,
+    pub sections: BTreeMap<BackupSection, BTreeMap<String, TableRows>>,
+}
+
+/// Rows written per section by a restore
+#[derive(Serialize, Debug, Clone)]
+pub struct RestoreSummary {
+    pub restored: BTreeMap<BackupSection, usize>,
+    pub skipped: usize, // Rows for unknown accounts, excluded settings or sync state
+}
+
+/// Read every row of a table as column → JSON value
+async fn dump_table(table: &str) -> Result<TableRows, String> {
+    let pool = db::pool();
+    let rows = sqlx::query(&format!("SELECT * FROM {}", table))
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+
+    let mut result = Vec::with_capacity(rows.len());
+    for row in rows {
+        let mut object = Map::new();
+        for column in row.columns() {
+            let index = column.ordinal();
+            let raw = row.try_get_raw(index).map_err(|e| e.to_string())?;
+            let value = if raw.is_null() {
+                Value::Null
+            } else {
+                match raw.type_info().name() {
+                    "INTEGER" | "BOOLEAN" => row.try_get::<i64, _>(index).map(Value::from),
+                    "REAL" => row.try_get::<f64, _>(index).map(Value::from),
+                    "BLOB" => row
+                        .try_get::<Vec<u8>, _>(index)
+                        .map(|data| serde_json::json!({ "base64": BASE64.encode(data) })),
+                    _ => row.try_get::<String, _>(index).map(Value::from),
+                }
+                .map_err(|e| format!("Failed to read {}.{}: {}", table, column.name(), e))?
+            };
+            object.insert(column.name().to_string(), value);
+        }
+        result.push(object);
+    }
+    Ok(result)
+}
+
+/// Column names of a table, to validate the keys of restored rows against
+async fn table_columns(table: &str) -> Result<HashSet<String>, String> {
+    let pool = db::pool();
+    let columns = sqlx::query_as::<_, (String,)>("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to read columns of {}: {}", table, e))?;
+    Ok(columns.into_iter().map(|(name,)| name).collect())
+}
+
+/// Whether a setting belongs in a backup
+fn is_exported_setting(row: &Map<String, Value>) -> bool {
+    let key = row.get("key").and_then(Value::as_str).unwrap_or_default();
+    !EXCLUDED_SETTINGS.contains(&key)
+}
+
+/// The row to write for a backed-up row, with its account id mapped to the local account;
+/// None if it shouldn't be restored
+fn restored_row(
+    table: &str,
+    row: &Map<String, Value>,
+    account_ids: &HashMap<i64, i64>,
+) -> Option<Map<String, Value>> {
+    if table == "settings" {
+        return is_exported_setting(row).then(|| row.clone());
+    }
+    let mut row = row.clone();
+    if let Some(account_id) = row.get("account_id").filter(|value| !value.is_null()) {
+        let local_id = account_id
+            .as_i64()
+            .and_then(|account_id| account_ids.get(&account_id))?;
+        row.insert("account_id".to_string(), Value::from(*local_id));
+    }
+    Some(row)
+}
+
+/// Bind a backed-up JSON value to a query
+fn bind_value<'q>(query: SqliteQuery<'q>, value: &Value) -> SqliteQuery<'q> {
+    match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Bool(b) => query.bind(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => query.bind(i),
+            None => query.bind(n.as_f64()),
+        },
+        Value::String(s) => query.bind(s.clone()),
+        Value::Object(object) => query.bind(
+            object
+                .get("base64")
+                .and_then(Value::as_str)
+                .and_then(|data| BASE64.decode(data).ok())
+                .unwrap_or_default(),
+        ),
+        Value::Array(_) => query.bind(value.to_string()),
+    }
+}
+
+/// Export the selected sections (all if None) to a JSON file
+#[command]
+pub async fn export_config_backup(
+    path: String,
+    sections: Option<Vec<BackupSection>>,
+) -> Result<ConfigBackup, String> {
+    authorize("export_config_backup", Sensitivity::Sensitive).await?;
+
+    let sections = sections.unwrap_or_else(|| BackupSection::ALL.to_vec());
+    let pool = db::pool();
+    let accounts = sqlx::query_as::<_, (i64, String)>("SELECT id, email FROM accounts")
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load accounts: {}", e))?
+        .into_iter()
+        .collect();
+    let mut backup = ConfigBackup {
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().timestamp(),
+        accounts,
+        sections: BTreeMap::new(),
+    };
+
+    for section in sections {
+        if section == BackupSection::SyncStatus {
+            continue;
+        }
+        let mut tables = BTreeMap::new();
+        for table in section.tables() {
+            let mut rows = dump_table(table).await?;
+            if *table == "settings" {
+                rows.retain(is_exported_setting);
+            }
+            tables.insert(table.to_string(), rows);
+        }
+        backup.sections.insert(section, tables);
+    }
+
+    let json = serde_json::to_string_pretty(&backup)
+        .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write backup: {}", e))?;
+
+    audit::record(
+        "config_backup_exported",
+        None,
+        &format!("{} section(s) to {}", backup.sections.len(), path),
+    )
+    .await;
+    Ok(backup)
+}
+
+/// Read a backup file without restoring it (to offer its sections for selection)
+#[command]
+pub async fn read_config_backup(path: String) -> Result<ConfigBackup, String> {
+    let json =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read backup: {}", e))?;
+    let backup: ConfigBackup =
+        serde_json::from_str(&json).map_err(|e| format!("Not a configuration backup: {}", e))?;
+    if backup.version > BACKUP_VERSION {
+        return Err(format!(
+            "This backup was made by a newer version (format {})",
+            backup.version
+        ));
+    }
+    Ok(backup)
+}
+
+/// Restore the selected sections of a backup file
+///
+/// Each restored table is replaced by the backup's rows, except settings, which are merged
+/// so installation-specific keys survive, and recurring sends, which are updated in place so
+/// their send history is kept. Account-scoped rows go to the local account with the same email;
+/// version 1 backups don't record emails, so only their account-independent rows are restored.
+/// Everything is written in one transaction.
+#[command]
+pub async fn restore_config_backup(
+    path: String,
+    sections: Vec<BackupSection>,
+) -> Result<RestoreSummary, String> {
+    authorize("restore_config_backup", Sensitivity::HighRisk).await?;
+    let backup = read_config_backup(path.clone()).await?;
+
+    let pool = db::pool();
+    let local_accounts: HashMap<String, i64> =
+        sqlx::query_as::<_, (i64, String)>("SELECT id, email FROM accounts")
+            .fetch_all(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load accounts: {}", e))?
+            .into_iter()
+            .map(|(id, email)| (email.to_lowercase(), id))
+            .collect();
+    // Backup account id → local account id
+    let account_ids: HashMap<i64, i64> = backup
+        .accounts
+        .iter()
+        .filter_map(|(id, email)| {
+            local_accounts
+                .get(&email.to_lowercase())
+                .map(|local| (*id, *local))
+        })
+        .collect();
+
+    let mut summary = RestoreSummary {
+        restored: BTreeMap::new(),
+        skipped: 0,
+    };
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    for section in sections {
+        let Some(tables) = backup.sections.get(&section) else {
+            continue;
+        };
+        if section == BackupSection::SyncStatus {
+            summary.skipped += tables.values().map(Vec::len).sum::<usize>();
+            continue;
+        }
+        let mut restored = 0;
+
+        for table in section.tables() {
+            let Some(rows) = tables.get(*table) else {
+                continue;
+            };
+            let columns = table_columns(table).await?;
+            let upsert = UPSERTED_TABLES.contains(table);
+
+            let rows: Vec<Map<String, Value>> = rows
+                .iter()
+                .filter_map(|row| restored_row(table, row, &account_ids))
+                .collect();
+            summary.skipped += tables[*table].len() - rows.len();
+
+            if upsert {
+                // Drop only the rows the backup doesn't have; the others are updated below
+                let ids: Vec<String> = rows
+                    .iter()
+                    .filter_map(|row| row.get("id").and_then(Value::as_i64))
+                    .map(|id| id.to_string())
+                    .collect();
+                sqlx::query(&format!(
+                    "DELETE FROM {} WHERE id NOT IN ({})",
+                    table,
+                    ids.join(", ")
+                ))
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+            } else if *table != "settings" {
+                sqlx::query(&format!("DELETE FROM {}", table))
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+            }
+
+            for row in &rows {
+                let values: Vec<(&String, &Value)> = row
+                    .iter()
+                    .filter(|(name, _)| columns.contains(*name))
+                    .collect();
+                if values.is_empty() {
+                    continue;
+                }
+                let names: Vec<String> = values
+                    .iter()
+                    .map(|(name, _)| format!("\"{}\"", name))
+                    .collect();
+                let sql = if upsert {
+                    format!(
+                        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+                        table,
+                        names.join(", "),
+                        vec!["?"; values.len()].join(", "),
+                        names
+                            .iter()
+                            .map(|name| format!("{} = excluded.{}", name, name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                } else {
+                    format!(
+                        "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+                        table,
+                        names.join(", "),
+                        vec!["?"; values.len()].join(", ")
+                    )
+                };
+
+                let mut query = sqlx::query(&sql);
+                for (_, value) in values {
+                    query = bind_value(query, value);
+                }
+                query
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to restore {}: {}", table, e))?;
+                restored += 1;
+            }
+        }
+        summary.restored.insert(section, restored);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to restore backup: {}", e))?;
+
+    audit::record(
+        "config_backup_restored",
+        None,
+        &format!(
+            "{:?} from {} ({} skipped)",
+            summary.restored.keys().collect::<Vec<_>>(),
+            path,
+            summary.skipped
+        ),
+    )
+    .await;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(key: &str, value: &str) -> Map<String, Value> {
+        let mut row = Map::new();
+        row.insert("key".to_string(), Value::from(key));
+        row.insert("value".to_string(), Value::from(value));
+        row
+    }
+
+    #[test]
+    fn test_restore_skips_installation_settings() {
+        let account_ids = HashMap::new();
+        for key in [
+            "unlock_failed_attempts",
+            "unlock_retry_after",
+            "content_hash_version",
+            "onboarding_state",
+            "window_state",
+            "password_hash",
+        ] {
+            assert!(
+                restored_row("settings", &setting(key, "1"), &account_ids).is_none(),
+                "{} should not be restored",
+                key
+            );
+        }
+        assert!(restored_row("settings", &setting("theme", "dark"), &account_ids).is_some());
+    }
+}
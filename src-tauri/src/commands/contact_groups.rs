@@ -0,0 +1,253 @@
+// Contact groups
+// Named recipient lists (e.g. "Design Team") that expand into their member addresses when a
+// message is composed. Members can be opted out of a group without being removed from it, so
+// they're skipped on expansion but kept for when they opt back in.
+
+use crate::db;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::command;
+
+/// A member of a contact group
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContactGroupMember {
+    pub address: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub opted_out: bool,
+}
+
+/// A named list of recipients
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContactGroup {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub name: String,
+    pub members: Vec<ContactGroupMember>,
+}
+
+impl ContactGroupMember {
+    /// The member as a recipient, e.g. `Jane Doe <jane@example.com>`
+    fn recipient(&self) -> String {
+        match &self.name {
+            Some(name) if !name.trim().is_empty() => format!("{} <{}>", name.trim(), self.address),
+            _ => self.address.clone(),
+        }
+    }
+}
+
+async fn load_groups() -> Result<Vec<ContactGroup>, String> {
+    let pool = db::pool();
+    let groups = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, name FROM contact_groups ORDER BY name COLLATE NOCASE",
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load contact groups: {}", e))?;
+
+    let mut result = Vec::with_capacity(groups.len());
+    for (id, name) in groups {
+        let members = sqlx::query_as::<_, (String, Option<String>, bool)>(
+            "SELECT address, name, opted_out FROM contact_group_members
+             WHERE group_id = ? ORDER BY id",
+        )
+        .bind(id)
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load contact group members: {}", e))?
+        .into_iter()
+        .map(|(address, name, opted_out)| ContactGroupMember {
+            address,
+            name,
+            opted_out,
+        })
+        .collect();
+
+        result.push(ContactGroup {
+            id: Some(id),
+            name,
+            members,
+        });
+    }
+    Ok(result)
+}
+
+/// Replace group names in a comma-separated recipient list with their members
+///
+/// Entries containing `@` are kept as they are; other entries that name a group
+/// (case-insensitively) become its members that haven't opted out. Duplicate addresses are
+/// dropped.
+fn expand(recipients: &str, groups: &[ContactGroup]) -> Result<String, String> {
+    let mut seen = HashSet::new();
+    let mut expanded = Vec::new();
+    let mut push = |recipient: String, address: &str| {
+        if seen.insert(address.trim().to_lowercase()) {
+            expanded.push(recipient);
+        }
+    };
+
+    for entry in recipients
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+    {
+        if entry.contains('@') {
+            let address = entry
+                .rsplit_once('<')
+                .map(|(_, rest)| rest.trim_end_matches('>'))
+                .unwrap_or(entry);
+            push(entry.to_string(), address);
+            continue;
+        }
+
+        let group = groups
+            .iter()
+            .find(|group| group.name.eq_ignore_ascii_case(entry))
+            .ok_or_else(|| format!("'{}' is neither an address nor a contact group", entry))?;
+        for member in group.members.iter().filter(|member| !member.opted_out) {
+            push(member.recipient(), &member.address);
+        }
+    }
+
+    Ok(expanded.join(", "))
+}
+
+/// List contact groups with their members
+#[command]
+pub async fn list_contact_groups() -> Result<Vec<ContactGroup>, String> {
+    load_groups().await
+}
+
+/// Create or update a contact group (members are replaced), returning its id
+#[command]
+pub async fn save_contact_group(group: ContactGroup) -> Result<i64, String> {
+    let name = group.name.trim();
+    if name.is_empty() || name.contains([',', '@']) {
+        return Err("Group names must be non-empty and can't contain ',' or '@'".to_string());
+    }
+    if let Some(member) = group.members.iter().find(|m| !m.address.contains('@')) {
+        return Err(format!("'{}' is not an email address", member.address));
+    }
+
+    let pool = db::pool();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let (id,) = sqlx::query_as::<_, (i64,)>(
+        "INSERT INTO contact_groups (id, name) VALUES (?, ?)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name
+         RETURNING id",
+    )
+    .bind(group.id)
+    .bind(name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save contact group (names must be unique): {}", e))?;
+
+    sqlx::query("DELETE FROM contact_group_members WHERE group_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update contact group members: {}", e))?;
+
+    for member in &group.members {
+        sqlx::query(
+            "INSERT OR IGNORE INTO contact_group_members (group_id, address, name, opted_out)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(member.address.trim())
+        .bind(&member.name)
+        .bind(member.opted_out)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save contact group member: {}", e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save contact group: {}", e))?;
+    Ok(id)
+}
+
+/// Delete a contact group
+#[command]
+pub async fn delete_contact_group(group_id: i64) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("DELETE FROM contact_groups WHERE id = ?")
+        .bind(group_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete contact group: {}", e))?;
+    Ok(())
+}
+
+/// Opt a member out of (or back into) a group
+#[command]
+pub async fn set_contact_group_opt_out(
+    group_id: i64,
+    address: String,
+    opted_out: bool,
+) -> Result<(), String> {
+    let pool = db::pool();
+    let result = sqlx::query(
+        "UPDATE contact_group_members SET opted_out = ?
+         WHERE group_id = ? AND address = ? COLLATE NOCASE",
+    )
+    .bind(opted_out)
+    .bind(group_id)
+    .bind(address.trim())
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to update opt-out: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("{} is not a member of this group", address));
+    }
+    Ok(())
+}
+
+/// Expand contact group names in a recipient list into member addresses
+#[command]
+pub async fn expand_recipients(recipients: String) -> Result<String, String> {
+    expand(&recipients, &load_groups().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let groups = vec![ContactGroup {
+            id: Some(1),
+            name: "Design Team".to_string(),
+            members: vec![
+                ContactGroupMember {
+                    address: "ana@example.com".to_string(),
+                    name: Some("Ana".to_string()),
+                    opted_out: false,
+                },
+                ContactGroupMember {
+                    address: "bo@example.com".to_string(),
+                    name: None,
+                    opted_out: true,
+                },
+                ContactGroupMember {
+                    address: "cy@example.com".to_string(),
+                    name: None,
+                    opted_out: false,
+                },
+            ],
+        }];
+
+        assert_eq!(
+            expand("Cy <CY@example.com>, design team", &groups).unwrap(),
+            "Cy <CY@example.com>, Ana <ana@example.com>"
+        );
+        assert!(expand("Sales", &groups).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+// Email date display
+// Dates can be shown in the user's local time or in the sender's own timezone (as written in
+// the Date header). The cache keeps the UTC timestamp and the header's UTC offset side by
+// side, so either form can be produced without reparsing. `format_email_date` is the single
+// formatter for backend-rendered dates (exports, notifications).
+
+use crate::db;
+use chrono::{FixedOffset, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+const SETTING_KEY: &str = "date_display_zone";
+
+/// Which timezone email dates are displayed in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DateDisplayZone {
+    #[default]
+    Local, // The user's system timezone
+    Sender, // The offset from the message's Date header
+}
+
+/// Format a message date for display
+///
+/// `sender_offset` is the Date header's offset in seconds east of UTC; without it (or in
+/// local mode) the system timezone is used. Sender times carry their offset, e.g.
+/// "Tue, 14 May 2024 09:30 +02:00".
+pub fn format_email_date(
+    timestamp: i64,
+    sender_offset: Option<i32>,
+    zone: DateDisplayZone,
+) -> String {
+    let Some(utc) = Utc.timestamp_opt(timestamp, 0).single() else {
+        return String::new();
+    };
+
+    match (zone, sender_offset.and_then(FixedOffset::east_opt)) {
+        (DateDisplayZone::Sender, Some(offset)) => utc
+            .with_timezone(&offset)
+            .format("%a, %d %b %Y %H:%M %:z")
+            .to_string(),
+        _ => utc
+            .with_timezone(&Local)
+            .format("%a, %d %b %Y %H:%M")
+            .to_string(),
+    }
+}
+
+/// The configured display timezone
+pub async fn display_zone() -> DateDisplayZone {
+    let pool = db::pool();
+    let value = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(SETTING_KEY)
+        .fetch_optional(pool.as_ref())
+        .await
+        .ok()
+        .flatten();
+
+    match value.as_ref().map(|(value,)| value.as_str()) {
+        Some("sender") => DateDisplayZone::Sender,
+        _ => DateDisplayZone::Local,
+    }
+}
+
+/// Get whether dates are shown in local time or the sender's timezone
+#[command]
+pub async fn get_date_display_zone() -> Result<DateDisplayZone, String> {
+    Ok(display_zone().await)
+}
+
+/// Show dates in local time or in the sender's timezone
+#[command]
+pub async fn set_date_display_zone(zone: DateDisplayZone) -> Result<(), String> {
+    let value = match zone {
+        DateDisplayZone::Local => "local",
+        DateDisplayZone::Sender => "sender",
+    };
+
+    let pool = db::pool();
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(SETTING_KEY)
+        .bind(value)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save date display setting: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_email_date_in_sender_zone() {
+        // 2024-05-14 07:30 UTC, sent from UTC+2
+        assert_eq!(
+            format_email_date(1_715_671_800, Some(2 * 3600), DateDisplayZone::Sender),
+            "Tue, 14 May 2024 09:30 +02:00"
+        );
+        // Without a known offset, sender mode falls back to local time
+        assert_eq!(
+            format_email_date(1_715_671_800, None, DateDisplayZone::Sender),
+            format_email_date(1_715_671_800, None, DateDisplayZone::Local)
+        );
+    }
+}
@@ -0,0 +1,339 @@
+// Demo account
+// Seeds an offline account with generated mail (folders, reply threads, attachments, read and
+// flagged state) so the UI and cache paths can be exercised without real credentials. The
+// account points at the reserved `.invalid` domain; sync, folder listing, STATUS and IDLE skip
+// it, and its folders are local so folder operations never try to reach a server. The data
+// comes from a fixed seed, so every demo account looks the same.
+
+use crate::commands::accounts::save_account_config;
+use crate::commands::emails::cache::{
+    save_attachments_to_cache, save_email_body_to_cache, save_emails_to_cache,
+};
+use crate::commands::emails::codec::{
+    decode_header, parse_email_date_with_fallback, parse_header_block,
+};
+use crate::commands::emails::fetch::parse_raw_message;
+use crate::commands::emails::sync::parse::extract_indexed_headers;
+use crate::db;
+use crate::models::{AccountConfig, AuthType, EmailHeader};
+use base64::{engine::general_purpose, Engine as _};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tauri::command;
+
+/// Host name of demo accounts (RFC 2606 reserves `.invalid`, so it never resolves)
+pub const DEMO_HOST: &str = "demo.invalid";
+
+const DEMO_EMAIL: &str = "you@demo.invalid";
+const DEMO_SEED: u64 = 0xC011_3A11;
+
+const DEMO_FOLDERS: &[&str] = &["INBOX", "Sent", "Archive", "Projects"];
+
+const CONTACTS: &[(&str, &str)] = &[
+    ("Alice Martin", "alice@example.com"),
+    ("Bob Chen", "bob.chen@example.org"),
+    ("Carla Souza", "carla@example.net"),
+    ("Deepak Rao", "deepak@example.com"),
+    ("Emma Wilson", "emma.wilson@example.org"),
+    ("Team Updates", "updates@example.net"),
+];
+
+const TOPICS: &[&str] = &[
+    "Quarterly planning",
+    "Design review for the new dashboard",
+    "Lunch on Friday?",
+    "Invoice #4821",
+    "Release notes draft",
+    "Conference travel",
+    "Weekly status",
+    "Onboarding checklist",
+];
+
+const LINES: &[&str] = &[
+    "Thanks for sending this over, I had a look this morning.",
+    "Can we move the meeting to Thursday afternoon?",
+    "I've attached the latest version, let me know what you think.",
+    "Most of the open points are resolved now.",
+    "Please review before the end of the week.",
+    "Looks good to me, just a few small comments inline.",
+    "I'll follow up with the rest of the team.",
+    "Do you have the numbers from last quarter?",
+];
+
+/// Whether an account is a demo account that must stay offline
+pub fn is_demo_account(config: &AccountConfig) -> bool {
+    config.imap_server.eq_ignore_ascii_case(DEMO_HOST)
+}
+
+/// A generated message before it is stored
+struct DemoMessage {
+    raw: Vec<u8>,
+    seen: bool,
+    flagged: bool,
+}
+
+/// Generate the raw messages of a folder; `start` is the newest message's timestamp
+fn generate_folder(rng: &mut StdRng, folder: &str, start: i64) -> Vec<DemoMessage> {
+    let thread_count = match folder {
+        "INBOX" => 8,
+        "Projects" => 3,
+        _ => 4,
+    };
+
+    let mut messages = Vec::new();
+    let mut timestamp = start;
+    for thread in 0..thread_count {
+        let topic = TOPICS[rng.gen_range(0..TOPICS.len())];
+        let replies = rng.gen_range(1..=4);
+        let mut references: Vec<String> = Vec::new();
+
+        for reply in 0..replies {
+            let (name, address) = CONTACTS[rng.gen_range(0..CONTACTS.len())];
+            let (from, to) = if folder == "Sent" {
+                (
+                    format!("You <{}>", DEMO_EMAIL),
+                    format!("{} <{}>", name, address),
+                )
+            } else {
+                (
+                    format!("{} <{}>", name, address),
+                    format!("You <{}>", DEMO_EMAIL),
+                )
+            };
+            let subject = if reply == 0 {
+                topic.to_string()
+            } else {
+                format!("Re: {}", topic)
+            };
+            let message_id = format!(
+                "<{}.{}.{}@demo.invalid>",
+                folder.to_lowercase(),
+                thread,
+                reply
+            );
+            let date = chrono::DateTime::from_timestamp(timestamp, 0)
+                .unwrap_or_default()
+                .to_rfc2822();
+
+            let mut headers = format!(
+                "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: {}\r\nMIME-Version: 1.0\r\n",
+                from, to, subject, date, message_id
+            );
+            if let Some(parent) = references.last() {
+                headers.push_str(&format!(
+                    "In-Reply-To: {}\r\nReferences: {}\r\n",
+                    parent,
+                    references.join(" ")
+                ));
+            }
+
+            let text = (0..rng.gen_range(2..=4))
+                .map(|_| LINES[rng.gen_range(0..LINES.len())])
+                .collect::<Vec<_>>()
+                .join("\r\n\r\n");
+            let body = format!("Hi,\r\n\r\n{}\r\n\r\n-- \r\n{}\r\n", text, name);
+
+            let raw = if rng.gen_bool(0.25) {
+                with_attachment(rng, headers, &body)
+            } else {
+                format!(
+                    "{}Content-Type: text/plain; charset=utf-8\r\n\r\n{}",
+                    headers, body
+                )
+            };
+
+            messages.push(DemoMessage {
+                raw: raw.into_bytes(),
+                seen: folder != "INBOX" || thread > 2 || rng.gen_bool(0.5),
+                flagged: rng.gen_bool(0.1),
+            });
+            references.push(message_id);
+            timestamp -= rng.gen_range(20 * 60..36 * 60 * 60);
+        }
+    }
+
+    // Oldest first, so UIDs increase with the date as on a real server
+    messages.reverse();
+    messages
+}
+
+/// Wrap a body in multipart/mixed with a generated CSV or text attachment
+fn with_attachment(rng: &mut StdRng, headers: String, body: &str) -> String {
+    let (filename, content_type, content) = if rng.gen_bool(0.5) {
+        let rows = (1..=rng.gen_range(3..=8))
+            .map(|i| {
+                format!(
+                    "{},{},{}",
+                    i,
+                    TOPICS[i % TOPICS.len()],
+                    rng.gen_range(10..1000)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        (
+            "report.csv",
+            "text/csv",
+            format!("id,item,amount\r\n{}", rows),
+        )
+    } else {
+        ("notes.txt", "text/plain", LINES.join("\r\n"))
+    };
+    let boundary = format!("demo-{:08x}", rng.gen::<u32>());
+
+    format!(
+        "{headers}Content-Type: multipart/mixed; boundary=\"{boundary}\"\r\n\r\n\
+         --{boundary}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{body}\r\n\
+         --{boundary}\r\nContent-Type: {content_type}; name=\"{filename}\"\r\n\
+         Content-Disposition: attachment; filename=\"{filename}\"\r\n\
+         Content-Transfer-Encoding: base64\r\n\r\n{data}\r\n--{boundary}--\r\n",
+        data = general_purpose::STANDARD.encode(content),
+    )
+}
+
+/// Store generated messages in a folder of the demo account
+async fn store_messages(
+    account_id: i32,
+    folder: &str,
+    messages: Vec<DemoMessage>,
+) -> Result<usize, String> {
+    let pool = db::pool();
+
+    for (index, message) in messages.iter().enumerate() {
+        let uid = index as u32 + 1;
+        let (body, attachments) = parse_raw_message(&message.raw)?;
+        let headers = parse_header_block(&message.raw);
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default()
+        };
+
+        let date = header("date");
+        let email = EmailHeader {
+            uid,
+            subject: decode_header(&header("subject")),
+            from: decode_header(&header("from")),
+            to: decode_header(&header("to")),
+            cc: String::new(),
+            bcc: String::new(),
+            timestamp: parse_email_date_with_fallback(&date, None),
+            date,
+            has_attachments: !attachments.is_empty(),
+            seen: message.seen,
+            flagged: message.flagged,
+            message_id: Some(header("message-id")),
+            received_at: None,
+            raw_headers: extract_indexed_headers(&message.raw),
+            security: None,
+        };
+        save_emails_to_cache(account_id, folder, std::slice::from_ref(&email)).await?;
+
+        let (email_id,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT id FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(account_id)
+        .bind(folder)
+        .bind(uid as i64)
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to get email id: {}", e))?;
+
+        if !attachments.is_empty() {
+            save_attachments_to_cache(email_id, &attachments).await?;
+        }
+        sqlx::query("UPDATE emails SET has_attachments = ? WHERE id = ?")
+            .bind(!attachments.is_empty() as i64)
+            .bind(email_id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to update attachment info: {}", e))?;
+
+        save_email_body_to_cache(account_id, folder, uid, &body).await?;
+    }
+
+    Ok(messages.len())
+}
+
+/// Create an offline demo account filled with generated mail
+#[command]
+pub async fn create_demo_account() -> Result<AccountConfig, String> {
+    let pool = db::pool();
+
+    let existing = sqlx::query_as::<_, (i64,)>("SELECT id FROM accounts WHERE email = ?")
+        .bind(DEMO_EMAIL)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to check for demo account: {}", e))?;
+    if existing.is_some() {
+        return Err("A demo account already exists".to_string());
+    }
+
+    let mut config = AccountConfig {
+        id: None,
+        email: DEMO_EMAIL.to_string(),
+        password: None,
+        imap_server: DEMO_HOST.to_string(),
+        imap_port: 993,
+        smtp_server: DEMO_HOST.to_string(),
+        smtp_port: 587,
+        auth_type: Some(AuthType::Basic),
+        access_token: None,
+        refresh_token: None,
+        token_expires_at: None,
+        display_name: Some("Demo Account".to_string()),
+    };
+    save_account_config(config.clone()).await?;
+
+    let (account_id,) = sqlx::query_as::<_, (i64,)>("SELECT id FROM accounts WHERE email = ?")
+        .bind(DEMO_EMAIL)
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load demo account: {}", e))?;
+    let account_id = account_id as i32;
+    config.id = Some(account_id);
+
+    let mut rng = StdRng::seed_from_u64(DEMO_SEED);
+    let now = chrono::Utc::now().timestamp();
+    let mut total = 0;
+    for folder in DEMO_FOLDERS {
+        sqlx::query(
+            "INSERT INTO folders (account_id, name, display_name, delimiter, flags, is_local) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(account_id)
+        .bind(folder)
+        .bind(folder)
+        .bind("/")
+        .bind(None::<String>)
+        .bind(1)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to create demo folder: {}", e))?;
+
+        let newest = now - rng.gen_range(0..3600);
+        let messages = generate_folder(&mut rng, folder, newest);
+        total += store_messages(account_id, folder, messages).await?;
+    }
+
+    println!("🧪 Demo account created with {} messages", total);
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_threads_parse() {
+        let mut rng = StdRng::seed_from_u64(DEMO_SEED);
+        let messages = generate_folder(&mut rng, "INBOX", 1_700_000_000);
+        assert!(!messages.is_empty());
+
+        for message in &messages {
+            let (body, _) = parse_raw_message(&message.raw).unwrap();
+            assert!(body.contains("Hi,"));
+        }
+    }
+}
@@ -18,12 +18,10 @@ pub async fn detect_display_name_from_sent(
 
     // Connect to IMAP server
     let result = tokio::task::spawn_blocking(move || -> Result<Option<String>, String> {
-        // Use ClientBuilder as per imap 3.0.0 API
         let domain = config.imap_server.as_str();
         let port = config.imap_port;
 
-        let client = imap::ClientBuilder::new(domain, port)
-            .connect()
+        let client = crate::net::connect_imap(domain, port, config.id)
             .map_err(|e| format!("Connection failed: {}", e))?;
 
         // Authenticate
@@ -116,7 +114,7 @@ fn authenticate_imap(
     }
 }
 
-fn find_sent_folder(
+pub(crate) fn find_sent_folder(
     session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
 ) -> Result<Option<String>, String> {
     let folders = session
@@ -1,5 +1,9 @@
+use crate::commands::emails::cache::{is_encryption_enabled, load_email_body_from_cache};
 use crate::db;
-use crate::models::{DraftListItem, DraftType};
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use crate::models::{
+    DraftConflict, DraftConflictResolution, DraftListItem, DraftType, DraftVersion,
+};
 use sqlx::Row;
 use tauri::command;
 
@@ -86,7 +90,8 @@ pub async fn list_drafts(account_id: i32) -> Result<Vec<DraftListItem>, String>
     let pool = db::pool();
 
     let rows = sqlx::query(
-        "SELECT id, account_id, to_addr, cc_addr, subject, draft_type, created_at, updated_at
+        "SELECT id, account_id, to_addr, cc_addr, subject, draft_type, created_at, updated_at,
+                conflict_uid IS NOT NULL AS has_conflict
          FROM drafts WHERE account_id = ? ORDER BY updated_at DESC",
     )
     .bind(account_id)
@@ -125,6 +130,9 @@ pub async fn list_drafts(account_id: i32) -> Result<Vec<DraftListItem>, String>
             updated_at: row
                 .try_get("updated_at")
                 .map_err(|e| format!("Failed to get updated_at: {}", e))?,
+            has_conflict: row
+                .try_get("has_conflict")
+                .map_err(|e| format!("Failed to get has_conflict: {}", e))?,
         });
     }
 
@@ -144,3 +152,337 @@ pub async fn delete_draft(draft_id: i64) -> Result<(), String> {
 
     Ok(())
 }
+
+/// Server copy of a draft, read from the email cache
+struct RemoteDraft {
+    uid: i64,
+    timestamp: i64,
+    message_id: Option<String>,
+    version: DraftVersion,
+}
+
+/// Load a cached server draft (subject decrypted, body only if cached)
+async fn load_remote_draft(
+    account_id: i32,
+    folder_name: &str,
+    uid: i64,
+) -> Result<Option<RemoteDraft>, String> {
+    let pool = db::pool();
+
+    let row = sqlx::query_as::<_, (String, Option<String>, String, i64, Option<String>)>(
+        "SELECT to_addr, cc_addr, subject, timestamp, message_id
+         FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(uid)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load server draft: {}", e))?;
+
+    let Some((to_addr, cc_addr, subject, timestamp, message_id)) = row else {
+        return Ok(None);
+    };
+
+    let subject = if is_encryption_enabled().await? && is_encryption_unlocked() {
+        decrypt(&subject).map_err(|e| format!("Failed to decrypt subject: {}", e))?
+    } else {
+        subject
+    };
+    let body = load_email_body_from_cache(account_id, folder_name, uid as u32).await?;
+
+    Ok(Some(RemoteDraft {
+        uid,
+        timestamp,
+        message_id,
+        version: DraftVersion {
+            to_addr,
+            cc_addr: cc_addr.unwrap_or_default(),
+            subject,
+            body,
+            updated_at: timestamp,
+        },
+    }))
+}
+
+/// Whether a folder holds the account's server drafts
+async fn is_drafts_folder(account_id: i32, folder_name: &str) -> Result<bool, String> {
+    let pool = db::pool();
+    let (flags, role) = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+        "SELECT flags, role FROM folders WHERE account_id = ? AND name = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load folder: {}", e))?
+    .unwrap_or_default();
+
+    Ok(role.as_deref() == Some("drafts")
+        || flags.unwrap_or_default().contains("Drafts")
+        || folder_name.to_lowercase().contains("draft"))
+}
+
+/// Open a server draft for editing as a local draft linked to its server copy
+///
+/// Returns the existing local draft if this server draft is already open.
+#[command]
+pub async fn open_remote_draft(
+    account_id: i32,
+    folder_name: String,
+    uid: u32,
+) -> Result<i64, String> {
+    let remote = load_remote_draft(account_id, &folder_name, uid as i64)
+        .await?
+        .ok_or("Server draft not found in cache")?;
+    let body = remote
+        .version
+        .body
+        .clone()
+        .ok_or("Server draft body is not downloaded yet")?;
+
+    let pool = db::pool();
+
+    if let Some(message_id) = &remote.message_id {
+        let existing = sqlx::query_as::<_, (i64,)>(
+            "SELECT id FROM drafts WHERE account_id = ? AND message_id = ?",
+        )
+        .bind(account_id)
+        .bind(message_id)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to look up draft: {}", e))?;
+        if let Some((id,)) = existing {
+            return Ok(id);
+        }
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "INSERT INTO drafts (account_id, to_addr, cc_addr, subject, body, attachments, draft_type,
+         created_at, updated_at, message_id, remote_folder, remote_uid, remote_timestamp, remote_synced_at)
+         VALUES (?, ?, ?, ?, ?, '[]', 'compose', ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(account_id)
+    .bind(&remote.version.to_addr)
+    .bind(&remote.version.cc_addr)
+    .bind(&remote.version.subject)
+    .bind(&body)
+    .bind(now)
+    .bind(now)
+    .bind(&remote.message_id)
+    .bind(&folder_name)
+    .bind(remote.uid)
+    .bind(remote.timestamp)
+    .bind(now)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to create draft: {}", e))?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Compare linked local drafts with the freshly synced server drafts
+///
+/// A server copy that changed (new UID or newer date for the same Message-ID) is taken over
+/// silently if the local draft has no edits since it last matched; otherwise the draft is
+/// flagged as conflicting. Returns the number of new conflicts.
+pub async fn detect_draft_conflicts(account_id: i32, folder_name: &str) -> Result<usize, String> {
+    if !is_drafts_folder(account_id, folder_name).await? {
+        return Ok(0);
+    }
+
+    let pool = db::pool();
+
+    let changed = sqlx::query_as::<_, (i64, i64, i64, i64)>(
+        "SELECT d.id, d.updated_at, COALESCE(d.remote_synced_at, d.created_at), e.uid
+         FROM drafts d
+         JOIN emails e ON e.account_id = d.account_id
+             AND e.folder_name = d.remote_folder
+             AND e.message_id = d.message_id
+         WHERE d.account_id = ? AND d.remote_folder = ? AND d.message_id IS NOT NULL
+             AND (e.uid != d.remote_uid OR e.timestamp > d.remote_timestamp)
+             AND (d.conflict_uid IS NULL OR d.conflict_uid != e.uid)
+         ORDER BY e.timestamp ASC",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to check draft conflicts: {}", e))?;
+
+    let mut conflicts = 0;
+    for (draft_id, updated_at, synced_at, remote_uid) in changed {
+        if updated_at > synced_at {
+            sqlx::query("UPDATE drafts SET conflict_uid = ? WHERE id = ?")
+                .bind(remote_uid)
+                .bind(draft_id)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to flag draft conflict: {}", e))?;
+            conflicts += 1;
+            println!(
+                "⚠️ Draft {} conflicts with server draft UID {}",
+                draft_id, remote_uid
+            );
+        } else if let Some(remote) = load_remote_draft(account_id, folder_name, remote_uid).await? {
+            // Without a cached body, pick it up on a later sync
+            if remote.version.body.is_some() {
+                take_remote_version(draft_id, folder_name, &remote).await?;
+            }
+        }
+    }
+
+    Ok(conflicts)
+}
+
+/// Get both versions of a conflicting draft
+#[command]
+pub async fn get_draft_conflict(draft_id: i64) -> Result<Option<DraftConflict>, String> {
+    let pool = db::pool();
+
+    let row = sqlx::query_as::<_, (i32, String, Option<String>, String, String, i64, String, i64)>(
+        "SELECT account_id, to_addr, cc_addr, subject, body, updated_at, remote_folder, conflict_uid
+         FROM drafts WHERE id = ? AND conflict_uid IS NOT NULL",
+    )
+    .bind(draft_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load draft: {}", e))?;
+
+    let Some((
+        account_id,
+        to_addr,
+        cc_addr,
+        subject,
+        body,
+        updated_at,
+        remote_folder,
+        conflict_uid,
+    )) = row
+    else {
+        return Ok(None);
+    };
+
+    let remote = load_remote_draft(account_id, &remote_folder, conflict_uid)
+        .await?
+        .ok_or("Conflicting server draft is no longer cached")?;
+
+    Ok(Some(DraftConflict {
+        draft_id,
+        remote_folder,
+        remote_uid: conflict_uid as u32,
+        local: DraftVersion {
+            to_addr,
+            cc_addr: cc_addr.unwrap_or_default(),
+            subject,
+            body: Some(body),
+            updated_at,
+        },
+        remote: remote.version,
+    }))
+}
+
+/// Resolve a draft conflict
+///
+/// Returns the ID of the extra draft created by `KeepBoth`.
+#[command]
+pub async fn resolve_draft_conflict(
+    draft_id: i64,
+    resolution: DraftConflictResolution,
+) -> Result<Option<i64>, String> {
+    let pool = db::pool();
+
+    let (account_id, remote_folder, conflict_uid) = sqlx::query_as::<_, (i32, String, i64)>(
+        "SELECT account_id, remote_folder, conflict_uid FROM drafts
+             WHERE id = ? AND conflict_uid IS NOT NULL",
+    )
+    .bind(draft_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load draft: {}", e))?
+    .ok_or("Draft has no conflict")?;
+
+    let remote = load_remote_draft(account_id, &remote_folder, conflict_uid)
+        .await?
+        .ok_or("Conflicting server draft is no longer cached")?;
+
+    match resolution {
+        DraftConflictResolution::KeepLocal => {
+            // Re-base on the new server copy but keep the local edits marked as unsynced
+            sqlx::query(
+                "UPDATE drafts SET remote_uid = ?, remote_timestamp = ?, conflict_uid = NULL
+                 WHERE id = ?",
+            )
+            .bind(remote.uid)
+            .bind(remote.timestamp)
+            .bind(draft_id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to resolve draft conflict: {}", e))?;
+            Ok(None)
+        }
+        DraftConflictResolution::KeepRemote => {
+            take_remote_version(draft_id, &remote_folder, &remote).await?;
+            Ok(None)
+        }
+        DraftConflictResolution::KeepBoth => {
+            // Copy the local edits into a new, unlinked draft first
+            let now = chrono::Utc::now().timestamp();
+            let result = sqlx::query(
+                "INSERT INTO drafts (account_id, to_addr, cc_addr, subject, body, attachments,
+                 draft_type, original_email_id, created_at, updated_at)
+                 SELECT account_id, to_addr, cc_addr, subject, body, attachments,
+                 draft_type, original_email_id, ?, ?
+                 FROM drafts WHERE id = ?",
+            )
+            .bind(now)
+            .bind(now)
+            .bind(draft_id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to copy draft: {}", e))?;
+
+            take_remote_version(draft_id, &remote_folder, &remote).await?;
+            Ok(Some(result.last_insert_rowid()))
+        }
+    }
+}
+
+/// Overwrite a local draft with its server copy and mark it in sync
+async fn take_remote_version(
+    draft_id: i64,
+    folder_name: &str,
+    remote: &RemoteDraft,
+) -> Result<(), String> {
+    let body = remote
+        .version
+        .body
+        .clone()
+        .ok_or("Server draft body is not downloaded yet")?;
+
+    let pool = db::pool();
+    let now = chrono::Utc::now().timestamp();
+
+    sqlx::query(
+        "UPDATE drafts SET to_addr = ?, cc_addr = ?, subject = ?, body = ?, updated_at = ?,
+         remote_folder = ?, remote_uid = ?, remote_timestamp = ?, remote_synced_at = ?,
+         conflict_uid = NULL
+         WHERE id = ?",
+    )
+    .bind(&remote.version.to_addr)
+    .bind(&remote.version.cc_addr)
+    .bind(&remote.version.subject)
+    .bind(&body)
+    .bind(now)
+    .bind(folder_name)
+    .bind(remote.uid)
+    .bind(remote.timestamp)
+    .bind(now)
+    .bind(draft_id)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to update draft from server: {}", e))?;
+
+    Ok(())
+}
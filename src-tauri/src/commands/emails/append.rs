@@ -0,0 +1,534 @@
+// Bulk APPEND operations
+// This module uploads cached messages (local folders, imports) into IMAP folders.
+// Uses MULTIAPPEND + LITERAL+ when the server supports both, otherwise one APPEND per message.
+
+use crate::commands::emails::attachments::load_email_attachments;
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::emails::imap_helpers;
+use crate::commands::emails::maildir;
+use crate::commands::reply_all::split_addresses;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use crate::models::{AccountConfig, AppendJobSummary, AppendProgress, Attachment};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use tauri::{command, AppHandle, Emitter};
+
+/// Maximum number of messages sent in a single MULTIAPPEND command
+const MAX_MESSAGES_PER_BATCH: usize = 20;
+
+/// Maximum total literal size sent in a single MULTIAPPEND command (8 MB)
+const MAX_BYTES_PER_BATCH: usize = 8 * 1024 * 1024;
+
+/// Whether cached subjects and bodies must be decrypted before upload
+///
+/// Fails while encryption is locked, as the ciphertext would otherwise be uploaded as the
+/// message content. The job can be run again after unlocking and resumes where it stopped.
+pub(crate) async fn upload_needs_decryption() -> Result<bool, String> {
+    let encrypted = is_encryption_enabled().await?;
+    if encrypted && !is_encryption_unlocked() {
+        return Err("Unlock encryption to upload messages, then try again".to_string());
+    }
+    Ok(encrypted)
+}
+
+/// A fully built RFC 5322 message ready to be appended to a mailbox
+pub struct AppendMessage {
+    pub raw: Vec<u8>,
+    pub seen: bool,
+    pub flagged: bool,
+    pub internal_date: Option<DateTime<FixedOffset>>,
+}
+
+/// Check whether the server can take several messages in one APPEND command
+pub fn supports_multiappend(session: &mut imap::Session<Box<dyn imap::ImapConnection>>) -> bool {
+    match session.capabilities() {
+        Ok(caps) => caps.has_str("MULTIAPPEND") && caps.has_str("LITERAL+"),
+        Err(e) => {
+            eprintln!(
+                "⚠️ Failed to read capabilities, falling back to APPEND: {}",
+                e
+            );
+            false
+        }
+    }
+}
+
+/// Append a batch of messages to a mailbox
+///
+/// With `multiappend` the whole batch is sent as one command using non-synchronizing
+/// literals (RFC 3502 + RFC 7888). Otherwise each message is appended individually.
+pub fn append_messages(
+    session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
+    folder: &str,
+    messages: &[AppendMessage],
+    multiappend: bool,
+) -> Result<(), String> {
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    if multiappend {
+        let mut command = format!("APPEND {}", imap_helpers::quote_mailbox(folder));
+        for message in messages {
+            let raw = std::str::from_utf8(&message.raw)
+                .map_err(|e| format!("Message is not 7-bit clean: {}", e))?;
+            command.push_str(&format!(" ({})", flag_list(message)));
+            if let Some(date) = message.internal_date {
+                command.push_str(&format!(" \"{}\"", date.format("%d-%b-%Y %H:%M:%S %z")));
+            }
+            command.push_str(&format!(" {{{}+}}\r\n{}", message.raw.len(), raw));
+        }
+
+        session
+            .run_command_and_read_response(&command)
+            .map_err(|e| format!("MULTIAPPEND to '{}' failed: {}", folder, e))?;
+        return Ok(());
+    }
+
+    for message in messages {
+        let mut flags = Vec::new();
+        if message.seen {
+            flags.push(imap::types::Flag::Seen);
+        }
+        if message.flagged {
+            flags.push(imap::types::Flag::Flagged);
+        }
+
+        let mut append = session.append(folder, &message.raw[..]);
+        append.flags(flags);
+        if let Some(date) = message.internal_date {
+            append.internal_date(date);
+        }
+        append
+            .finish()
+            .map_err(|e| format!("APPEND to '{}' failed: {}", folder, e))?;
+    }
+
+    Ok(())
+}
+
+/// Build a raw RFC 5322 message from cached header fields, body and attachments
+///
+/// All parts are base64 encoded so the result is 7-bit clean and can be sent as a literal.
+#[allow(clippy::too_many_arguments)]
+pub fn build_raw_message(
+    from: &str,
+    to: &str,
+    cc: &str,
+    subject: &str,
+    date: &str,
+    timestamp: i64,
+    html_body: &str,
+    attachments: &[Attachment],
+) -> Vec<u8> {
+    let date_header = if date.trim().is_empty() {
+        Utc.timestamp_opt(timestamp, 0)
+            .single()
+            .unwrap_or_else(Utc::now)
+            .to_rfc2822()
+    } else {
+        date.to_string()
+    };
+
+    let mut out = String::new();
+    out.push_str(&format!("From: {}\r\n", encode_address_list(from)));
+    if !to.trim().is_empty() {
+        out.push_str(&format!("To: {}\r\n", encode_address_list(to)));
+    }
+    if !cc.trim().is_empty() {
+        out.push_str(&format!("Cc: {}\r\n", encode_address_list(cc)));
+    }
+    out.push_str(&format!("Subject: {}\r\n", encode_header_value(subject)));
+    out.push_str(&format!("Date: {}\r\n", date_header));
+    out.push_str("MIME-Version: 1.0\r\n");
+
+    let body_part = format!(
+        "Content-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        wrap_base64(html_body.as_bytes())
+    );
+
+    if attachments.is_empty() {
+        out.push_str(&body_part);
+        return out.into_bytes();
+    }
+
+    let boundary = format!("=_colimail_{}_{}", timestamp, attachments.len());
+    out.push_str(&format!(
+        "Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
+        boundary
+    ));
+    out.push_str(&format!("--{}\r\n{}", boundary, body_part));
+
+    for attachment in attachments {
+        let data = attachment.data.as_deref().unwrap_or_default();
+        let filename = encode_header_value(&attachment.filename).replace('"', "'");
+        out.push_str(&format!(
+            "--{}\r\nContent-Type: {}; name=\"{}\"\r\nContent-Disposition: attachment; filename=\"{}\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+            boundary, attachment.content_type, filename, filename, wrap_base64(data)
+        ));
+    }
+    out.push_str(&format!("--{}--\r\n", boundary));
+
+    out.into_bytes()
+}
+
+/// Load cached messages of a folder with UID greater than `after_uid` (ascending)
+///
+/// Returns (uid, message) pairs. Stops once `MAX_MESSAGES_PER_BATCH` messages or
+/// `MAX_BYTES_PER_BATCH` bytes have been collected, whichever comes first.
+pub async fn load_append_batch(
+    account_id: i32,
+    folder_name: &str,
+    after_uid: u32,
+) -> Result<Vec<(u32, AppendMessage)>, String> {
+    let pool = db::pool();
+
+    let rows = sqlx::query_as::<
+        _,
+        (
+            i64,
+            i64,
+            String,
+            String,
+            String,
+            Option<String>,
+            String,
+            i64,
+            Option<String>,
+            i64,
+            i64,
+        ),
+    >(
+        "SELECT id, uid, subject, from_addr, to_addr, cc_addr, date, timestamp, body,
+                COALESCE(seen, 0), COALESCE(flagged, 0)
+         FROM emails
+         WHERE account_id = ? AND folder_name = ? AND uid > ?
+         ORDER BY uid ASC
+         LIMIT ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(after_uid as i64)
+    .bind(MAX_MESSAGES_PER_BATCH as i64)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load messages for upload: {}", e))?;
+
+    let decrypt_fields = upload_needs_decryption().await?;
+
+    let mut batch = Vec::with_capacity(rows.len());
+    let mut batch_bytes = 0usize;
+
+    for (email_id, uid, subject, from, to, cc, date, timestamp, body, seen, flagged) in rows {
+        let (subject, body) = if decrypt_fields {
+            let subject = decrypt(&subject)
+                .map_err(|e| format!("Failed to decrypt subject for UID {}: {}", uid, e))?;
+            let body = match body {
+                Some(body) => Some(
+                    decrypt(&body)
+                        .map_err(|e| format!("Failed to decrypt body for UID {}: {}", uid, e))?,
+                ),
+                None => None,
+            };
+            (subject, body)
+        } else {
+            (subject, body)
+        };
+
+        // Local folders kept in Maildir have no body in SQLite
+        let body = body.or_else(|| maildir::read_body(account_id, folder_name, uid as u32));
+        if body.is_none() {
+            eprintln!("⚠️ UID {} has no cached body, uploading headers only", uid);
+        }
+
+        let attachments = load_email_attachments(email_id).await?;
+        let raw = build_raw_message(
+            &from,
+            &to,
+            cc.as_deref().unwrap_or_default(),
+            &subject,
+            &date,
+            timestamp,
+            body.as_deref().unwrap_or_default(),
+            &attachments,
+        );
+
+        // Always take at least one message, even if it alone exceeds the byte budget
+        if !batch.is_empty() && batch_bytes + raw.len() > MAX_BYTES_PER_BATCH {
+            break;
+        }
+        batch_bytes += raw.len();
+
+        let internal_date = Utc
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|dt| dt.fixed_offset());
+
+        batch.push((
+            uid as u32,
+            AppendMessage {
+                raw,
+                seen: seen != 0,
+                flagged: flagged != 0,
+                internal_date,
+            },
+        ));
+    }
+
+    Ok(batch)
+}
+
+/// Upload every cached message of `source_folder` into the remote `target_folder`
+///
+/// Progress is persisted in `append_jobs` after each batch, so calling this again for the
+/// same source/target pair resumes after the last uploaded UID instead of starting over.
+/// Emits `append-progress` events while running.
+pub async fn run_append_job(
+    app_handle: &AppHandle,
+    config: AccountConfig,
+    source_folder: &str,
+    target_folder: &str,
+) -> Result<AppendJobSummary, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let pool = db::pool();
+    let now = Utc::now().timestamp();
+
+    // Ensure we have a valid access token (refresh if needed)
+    let config = ensure_valid_token(config).await?;
+
+    let total: i64 = sqlx::query_as::<_, (i64,)>(
+        "SELECT COUNT(*) FROM emails WHERE account_id = ? AND folder_name = ?",
+    )
+    .bind(account_id)
+    .bind(source_folder)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to count messages: {}", e))?
+    .0;
+
+    // Resume an unfinished job for the same source/target if there is one
+    let existing = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT id, last_uid, uploaded_count FROM append_jobs
+         WHERE account_id = ? AND source_folder = ? AND target_folder = ? AND status != 'completed'
+         ORDER BY id DESC LIMIT 1",
+    )
+    .bind(account_id)
+    .bind(source_folder)
+    .bind(target_folder)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load append job: {}", e))?;
+
+    let (job_id, mut last_uid, mut uploaded, resumed) = match existing {
+        Some((id, last_uid, uploaded)) => {
+            println!(
+                "🔄 Resuming upload job {} from UID {} ({} already uploaded)",
+                id, last_uid, uploaded
+            );
+            (id, last_uid as u32, uploaded, true)
+        }
+        None => {
+            let result = sqlx::query(
+                "INSERT INTO append_jobs
+                 (account_id, source_folder, target_folder, last_uid, uploaded_count, total_count, status, created_at, updated_at)
+                 VALUES (?, ?, ?, 0, 0, ?, 'running', ?, ?)",
+            )
+            .bind(account_id)
+            .bind(source_folder)
+            .bind(target_folder)
+            .bind(total)
+            .bind(now)
+            .bind(now)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to create append job: {}", e))?;
+            (result.last_insert_rowid(), 0, 0, false)
+        }
+    };
+
+    sqlx::query(
+        "UPDATE append_jobs SET status = 'running', total_count = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(total)
+    .bind(now)
+    .bind(job_id)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to update append job: {}", e))?;
+
+    // Open one session for the whole job and move it in and out of the blocking pool per batch
+    let config_clone = config.clone();
+    let (mut session, multiappend) = tokio::task::spawn_blocking(
+        move || -> Result<(imap::Session<Box<dyn imap::ImapConnection>>, bool), String> {
+            let mut session = imap_helpers::connect_and_login(&config_clone)?;
+            let multiappend = supports_multiappend(&mut session);
+            Ok((session, multiappend))
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())??;
+
+    println!(
+        "📤 Uploading {} messages from '{}' to '{}' (MULTIAPPEND: {})",
+        total, source_folder, target_folder, multiappend
+    );
+
+    let outcome: Result<(), String> = async {
+        loop {
+            let batch = load_append_batch(account_id, source_folder, last_uid).await?;
+            if batch.is_empty() {
+                break;
+            }
+
+            let batch_last_uid = batch.last().map(|(uid, _)| *uid).unwrap_or(last_uid);
+            let batch_len = batch.len() as i64;
+            let messages: Vec<AppendMessage> = batch.into_iter().map(|(_, m)| m).collect();
+            let target = target_folder.to_string();
+
+            let (returned_session, result) = tokio::task::spawn_blocking(move || {
+                let result = append_messages(&mut session, &target, &messages, multiappend);
+                (session, result)
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+            session = returned_session;
+            result?;
+
+            last_uid = batch_last_uid;
+            uploaded += batch_len;
+
+            sqlx::query(
+                "UPDATE append_jobs SET last_uid = ?, uploaded_count = ?, updated_at = ? WHERE id = ?",
+            )
+            .bind(last_uid as i64)
+            .bind(uploaded)
+            .bind(Utc::now().timestamp())
+            .bind(job_id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to save append progress: {}", e))?;
+
+            let _ = app_handle.emit(
+                "append-progress",
+                AppendProgress {
+                    job_id,
+                    source_folder: source_folder.to_string(),
+                    target_folder: target_folder.to_string(),
+                    uploaded,
+                    total,
+                },
+            );
+        }
+
+        let _ = tokio::task::spawn_blocking(move || session.logout()).await;
+        Ok(())
+    }
+    .await;
+
+    let status = if outcome.is_ok() {
+        "completed"
+    } else {
+        "failed"
+    };
+    sqlx::query("UPDATE append_jobs SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(Utc::now().timestamp())
+        .bind(job_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to update append job: {}", e))?;
+
+    outcome?;
+
+    println!(
+        "✅ Uploaded {} messages from '{}' to '{}'",
+        uploaded, source_folder, target_folder
+    );
+
+    Ok(AppendJobSummary {
+        job_id,
+        uploaded,
+        total,
+        resumed,
+    })
+}
+
+/// Upload a local folder (or imported batch) into an IMAP folder
+///
+/// Re-running the command after an interruption resumes where the previous run stopped.
+#[command]
+pub async fn upload_folder_to_remote(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    source_folder: String,
+    target_folder: String,
+) -> Result<AppendJobSummary, String> {
+    run_append_job(&app_handle, config, &source_folder, &target_folder).await
+}
+
+/// Build the IMAP flag list for a message (e.g. `\Seen \Flagged`)
+fn flag_list(message: &AppendMessage) -> String {
+    let mut flags = Vec::new();
+    if message.seen {
+        flags.push("\\Seen");
+    }
+    if message.flagged {
+        flags.push("\\Flagged");
+    }
+    flags.join(" ")
+}
+
+/// Encode a header value as an RFC 2047 encoded-word if it contains non-ASCII characters
+fn encode_header_value(value: &str) -> String {
+    if value.is_ascii() {
+        value.replace(['\r', '\n'], " ")
+    } else {
+        format!("=?UTF-8?B?{}?=", general_purpose::STANDARD.encode(value))
+    }
+}
+
+/// Encode the display names of an address list; the addresses themselves must stay readable
+fn encode_address_list(value: &str) -> String {
+    split_addresses(value)
+        .into_iter()
+        .map(|entry| match entry.rsplit_once('<') {
+            Some((name, address)) if !name.is_ascii() => format!(
+                "{} <{}",
+                encode_header_value(name.trim().trim_matches('"')),
+                address.trim()
+            ),
+            _ => entry.replace(['\r', '\n'], " "),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Base64 encode and wrap at 76 characters per line (RFC 2045)
+fn wrap_base64(data: &[u8]) -> String {
+    let encoded = general_purpose::STANDARD.encode(data);
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / 76 * 2 + 2);
+    for chunk in encoded.as_bytes().chunks(76) {
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\r\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_address_list() {
+        assert_eq!(
+            encode_address_list("José Ruiz <jose@example.com>, bob@example.com"),
+            "=?UTF-8?B?Sm9zw6kgUnVpeg==?= <jose@example.com>, bob@example.com"
+        );
+        assert_eq!(
+            encode_address_list("\"Smith, Ann\" <ann@example.com>"),
+            "\"Smith, Ann\" <ann@example.com>"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// Archive
+// Moves a message out of the inbox into the account's archive folder. The folder is found by
+// its \Archive attribute where the server supports SPECIAL-USE, and otherwise by provider:
+// Gmail archives to "[Gmail]/All Mail" (moving there just drops the Inbox label), Outlook and
+// most other servers use a folder named "Archive". If none exists, "Archive" is created.
+
+use crate::commands::emails::imap_helpers;
+use crate::commands::emails::move_copy::{transfer_emails, TransferMode};
+use crate::commands::folder_names::FolderRole;
+use crate::commands::folders::{create_remote_folder, folder_for_role};
+use crate::commands::reader_windows;
+use crate::commands::utils::ensure_valid_token;
+use crate::models::AccountConfig;
+use imap_proto::types::NameAttribute;
+use tauri::{command, AppHandle};
+
+const DEFAULT_ARCHIVE_FOLDER: &str = "Archive";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Provider {
+    Gmail,
+    Outlook,
+    Generic,
+}
+
+fn provider(imap_server: &str) -> Provider {
+    let server = imap_server.to_lowercase();
+    if server.contains("gmail") || server.contains("googlemail") {
+        Provider::Gmail
+    } else if server.contains("outlook")
+        || server.contains("office365")
+        || server.contains("hotmail")
+    {
+        Provider::Outlook
+    } else {
+        Provider::Generic
+    }
+}
+
+/// Folder names to look for, most specific first
+fn archive_candidates(provider: &Provider) -> &'static [&'static str] {
+    match provider {
+        Provider::Gmail => &["[Gmail]/All Mail", "[Google Mail]/All Mail"],
+        Provider::Outlook => &["Archive"],
+        Provider::Generic => &["Archive", "Archives", "INBOX.Archive", "INBOX/Archive"],
+    }
+}
+
+/// Find the archive folder of an account, if it has one
+fn find_archive_folder(
+    imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
+    imap_server: &str,
+) -> Result<Option<String>, String> {
+    let mailbox_list = imap_session
+        .list(Some(""), Some("*"))
+        .map_err(|e| format!("Failed to list folders: {}", e))?;
+
+    let selectable: Vec<_> = mailbox_list
+        .iter()
+        .filter(|mailbox| {
+            !mailbox
+                .attributes()
+                .iter()
+                .any(|attr| matches!(attr, NameAttribute::NoSelect))
+        })
+        .collect();
+
+    let provider = provider(imap_server);
+
+    // SPECIAL-USE: \Archive anywhere, \All for Gmail
+    let special_use = selectable.iter().find(|mailbox| {
+        mailbox.attributes().iter().any(|attr| {
+            matches!(attr, NameAttribute::Archive)
+                || (provider == Provider::Gmail && matches!(attr, NameAttribute::All))
+        })
+    });
+    if let Some(mailbox) = special_use {
+        return Ok(Some(mailbox.name().to_string()));
+    }
+
+    for candidate in archive_candidates(&provider) {
+        if let Some(mailbox) = selectable
+            .iter()
+            .find(|mailbox| mailbox.name().eq_ignore_ascii_case(candidate))
+        {
+            return Ok(Some(mailbox.name().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Move an email to the account's archive folder, returning that folder's name
+#[command]
+pub async fn archive_email(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    uid: u32,
+    folder: Option<String>,
+) -> Result<String, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+    println!("Archiving email UID {} from {}", uid, folder_name);
+
+    let config = ensure_valid_token(config).await?;
+    let archive_folder = match folder_for_role(account_id, FolderRole::Archive).await? {
+        Some(archive_folder) => Some(archive_folder),
+        None => {
+            let config_for_task = config.clone();
+            tokio::task::spawn_blocking(move || -> Result<Option<String>, String> {
+                let mut imap_session = imap_helpers::connect_and_login(&config_for_task)?;
+                let archive_folder =
+                    find_archive_folder(&mut imap_session, &config_for_task.imap_server);
+                let _ = imap_session.logout();
+                archive_folder
+            })
+            .await
+            .map_err(|e| e.to_string())??
+        }
+    };
+
+    let archive_folder = match archive_folder {
+        Some(archive_folder) => archive_folder,
+        None => {
+            println!(
+                "No archive folder found, creating '{}'",
+                DEFAULT_ARCHIVE_FOLDER
+            );
+            create_remote_folder(config.clone(), DEFAULT_ARCHIVE_FOLDER.to_string(), None)
+                .await?
+                .name
+        }
+    };
+    println!("Using archive folder: {}", archive_folder);
+
+    transfer_emails(
+        config,
+        &folder_name,
+        &[uid],
+        &archive_folder,
+        TransferMode::Move,
+    )
+    .await?;
+
+    reader_windows::close_for_message(&app_handle, account_id, &folder_name, uid);
+    println!("✅ Archived email UID {} to {}", uid, archive_folder);
+    Ok(archive_folder)
+}
@@ -0,0 +1,332 @@
+// Attachment text search
+// A background task extracts plain text from cached PDF, DOCX and text attachments into the
+// `attachment_fts` full-text table (rowid = attachment id), so searches can match what's
+// inside documents. Large files and long texts are capped to keep indexing cheap. While
+// encryption is enabled nothing is indexed, since the index would hold the text in plaintext.
+// A trigger drops the text of deleted attachments (also when their email or folder goes), and
+// each pass sweeps any rows left behind so a reused attachment id never shows old text.
+
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::db;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::time::Duration;
+use tauri::command;
+
+/// Attachments larger than this are not indexed
+const MAX_INPUT_BYTES: i64 = 20 * 1024 * 1024;
+/// Extracted text is cut off after this many characters
+const MAX_TEXT_CHARS: usize = 200_000;
+/// Attachments processed per indexing pass
+const BATCH_SIZE: i64 = 20;
+const FIRST_RUN_DELAY: Duration = Duration::from_secs(60);
+const INDEX_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Values of `attachments.text_indexed`
+const NOT_INDEXED: i64 = 0;
+const INDEXED: i64 = 1;
+const UNSUPPORTED: i64 = 2;
+
+/// An attachment whose text matched a search
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttachmentMatch {
+    pub attachment_id: i64,
+    pub email_id: i64,
+    pub account_id: i32,
+    pub folder_name: String,
+    pub uid: u32,
+    pub filename: String,
+    pub snippet: String, // Matched text with the hits wrapped in [ ]
+}
+
+#[derive(Debug, PartialEq)]
+enum DocumentKind {
+    Text,
+    Pdf,
+    Docx,
+}
+
+fn document_kind(filename: &str, content_type: &str) -> Option<DocumentKind> {
+    let content_type = content_type.to_lowercase();
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+
+    if content_type == "application/pdf" || extension == "pdf" {
+        Some(DocumentKind::Pdf)
+    } else if content_type
+        == "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        || extension == "docx"
+    {
+        Some(DocumentKind::Docx)
+    } else if content_type.starts_with("text/")
+        || matches!(
+            extension.as_str(),
+            "txt" | "csv" | "md" | "log" | "html" | "htm"
+        )
+    {
+        Some(DocumentKind::Text)
+    } else {
+        None
+    }
+}
+
+/// Text of a DOCX body, one line per paragraph
+fn docx_text(data: &[u8]) -> Result<String, String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))
+        .map_err(|e| format!("Failed to open DOCX: {}", e))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| format!("DOCX has no document body: {}", e))?
+        .take(MAX_INPUT_BYTES as u64)
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("Failed to read DOCX: {}", e))?;
+
+    Ok(strip_tags(
+        &xml.replace("</w:p>", "\n").replace("<w:tab/>", " "),
+    ))
+}
+
+/// Drop markup tags and decode the basic XML entities
+fn strip_tags(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len() / 2);
+    let mut in_tag = false;
+    for c in markup.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Extract searchable text from an attachment; `None` if the format isn't supported
+fn extract_text(filename: &str, content_type: &str, data: &[u8]) -> Option<String> {
+    let text = match document_kind(filename, content_type)? {
+        DocumentKind::Text
+            if content_type.contains("html") || filename.to_lowercase().contains(".htm") =>
+        {
+            strip_tags(&String::from_utf8_lossy(data))
+        }
+        DocumentKind::Text => String::from_utf8_lossy(data).into_owned(),
+        DocumentKind::Docx => docx_text(data).ok()?,
+        // The PDF parser can panic on malformed files
+        DocumentKind::Pdf => std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(data))
+            .ok()?
+            .ok()?,
+    };
+
+    let text: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_TEXT_CHARS)
+        .collect();
+    Some(text)
+}
+
+/// Index the next batch of unindexed attachments; returns how many were processed
+async fn index_batch() -> Result<usize, String> {
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (i64, String, String, i64)>(
+        "SELECT id, filename, content_type, size FROM attachments
+         WHERE COALESCE(text_indexed, 0) = 0
+         ORDER BY id DESC LIMIT ?",
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load attachments to index: {}", e))?;
+
+    for (id, filename, content_type, size) in &rows {
+        let supported = size <= &MAX_INPUT_BYTES && document_kind(filename, content_type).is_some();
+        let text = if supported {
+            let (data, archived_path) = sqlx::query_as::<_, (Vec<u8>, Option<String>)>(
+                "SELECT data, archived_path FROM attachments WHERE id = ?",
+            )
+            .bind(id)
+            .fetch_one(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load attachment: {}", e))?;
+            let data = crate::commands::attachment_archive::rehydrate(data, archived_path).await?;
+            let (filename, content_type) = (filename.clone(), content_type.clone());
+            tokio::task::spawn_blocking(move || extract_text(&filename, &content_type, &data))
+                .await
+                .map_err(|e| format!("Text extraction task failed: {}", e))?
+        } else {
+            None
+        };
+
+        let state = match &text {
+            Some(text) if !text.is_empty() => {
+                sqlx::query("INSERT OR REPLACE INTO attachment_fts (rowid, content) VALUES (?, ?)")
+                    .bind(id)
+                    .bind(text)
+                    .execute(pool.as_ref())
+                    .await
+                    .map_err(|e| format!("Failed to index attachment: {}", e))?;
+                INDEXED
+            }
+            _ => UNSUPPORTED,
+        };
+
+        sqlx::query("UPDATE attachments SET text_indexed = ? WHERE id = ?")
+            .bind(state)
+            .bind(id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to update attachment index state: {}", e))?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Drop indexed text whose attachment no longer exists; returns how many rows were removed
+async fn remove_orphans() -> Result<u64, String> {
+    let pool = db::pool();
+    sqlx::query("DELETE FROM attachment_fts WHERE rowid NOT IN (SELECT id FROM attachments)")
+        .execute(pool.as_ref())
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(|e| format!("Failed to remove orphaned attachment text: {}", e))
+}
+
+/// Background loop that indexes new attachments until shutdown
+pub async fn index_loop() {
+    let shutdown_token = crate::shutdown::token();
+    let mut delay = FIRST_RUN_DELAY;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+        delay = INDEX_INTERVAL;
+
+        match remove_orphans().await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!(count, "Removed orphaned attachment text"),
+            Err(e) => tracing::warn!(error = %e, "Attachment index cleanup failed"),
+        }
+
+        if is_encryption_enabled().await.unwrap_or(true) {
+            continue;
+        }
+
+        // Work through the backlog batch by batch, stopping early on shutdown
+        loop {
+            match index_batch().await {
+                Ok(0) => break,
+                Ok(count) => tracing::debug!(count, "Indexed attachment text"),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Attachment indexing failed");
+                    break;
+                }
+            }
+            if shutdown_token.is_cancelled() {
+                return;
+            }
+        }
+    }
+}
+
+/// Drop all indexed attachment text and mark every attachment as unindexed
+pub async fn clear_index() -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("DELETE FROM attachment_fts")
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to clear attachment index: {}", e))?;
+    sqlx::query("UPDATE attachments SET text_indexed = ?")
+        .bind(NOT_INDEXED)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to reset attachment index state: {}", e))?;
+    Ok(())
+}
+
+/// Quote each word so user input can't be read as FTS5 query syntax
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Search the text of indexed attachments
+#[command]
+pub async fn search_attachment_text(
+    query: String,
+    account_id: Option<i32>,
+) -> Result<Vec<AttachmentMatch>, String> {
+    let query = fts_query(&query);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (i64, i64, i32, String, i64, String, String)>(
+        "SELECT a.id, a.email_id, e.account_id, e.folder_name, e.uid, a.filename,
+                snippet(attachment_fts, 0, '[', ']', '…', 16)
+         FROM attachment_fts
+         JOIN attachments a ON a.id = attachment_fts.rowid
+         JOIN emails e ON e.id = a.email_id
+         WHERE attachment_fts MATCH ? AND (? IS NULL OR e.account_id = ?)
+         ORDER BY rank LIMIT 100",
+    )
+    .bind(&query)
+    .bind(account_id)
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to search attachments: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(attachment_id, email_id, account_id, folder_name, uid, filename, snippet)| {
+                AttachmentMatch {
+                    attachment_id,
+                    email_id,
+                    account_id,
+                    folder_name,
+                    uid: uid as u32,
+                    filename,
+                    snippet,
+                }
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_text() {
+        assert_eq!(
+            extract_text("notes.txt", "text/plain", b"Quarterly\n\n  report").as_deref(),
+            Some("Quarterly report")
+        );
+        assert_eq!(
+            extract_text("page.html", "text/html", b"<p>Tom &amp; Jerry</p>").as_deref(),
+            Some("Tom & Jerry")
+        );
+        assert_eq!(extract_text("photo.jpg", "image/jpeg", b"\xff\xd8"), None);
+        assert_eq!(fts_query("budget \"2024"), "\"budget\" \"\"\"2024\"");
+    }
+}
@@ -1,6 +1,7 @@
 // Attachment management operations
 // This module handles loading and downloading email attachments
 
+use crate::commands::attachment_archive::rehydrate;
 use crate::db;
 use crate::encryption::{decrypt_bytes, is_encryption_unlocked};
 use crate::models::{Attachment, AttachmentInfo};
@@ -81,13 +82,14 @@ pub async fn load_attachments_info(
 pub async fn download_attachment(attachment_id: i64) -> Result<Attachment, String> {
     let pool = db::pool();
 
-    let row = sqlx::query_as::<_, (String, String, i64, Vec<u8>)>(
-        "SELECT filename, content_type, size, data FROM attachments WHERE id = ?",
+    let row = sqlx::query_as::<_, (String, String, i64, Vec<u8>, Option<String>)>(
+        "SELECT filename, content_type, size, data, archived_path FROM attachments WHERE id = ?",
     )
     .bind(attachment_id)
     .fetch_one(pool.as_ref())
     .await
     .map_err(|e| format!("Failed to load attachment: {}", e))?;
+    let data = rehydrate(row.3, row.4).await?;
 
     // Check if encryption is enabled
     let encryption_enabled = is_encryption_enabled().await?;
@@ -95,11 +97,11 @@ pub async fn download_attachment(attachment_id: i64) -> Result<Attachment, Strin
     // Decrypt attachment data if encryption is enabled and unlocked
     let decrypted_data = if encryption_enabled && is_encryption_unlocked() {
         // Data is stored as base64 string, convert to string first
-        let encrypted_str = String::from_utf8(row.3)
+        let encrypted_str = String::from_utf8(data)
             .map_err(|e| format!("Failed to convert encrypted data to string: {}", e))?;
         decrypt_bytes(&encrypted_str).map_err(|e| format!("Failed to decrypt attachment: {}", e))?
     } else {
-        row.3
+        data
     };
 
     Ok(Attachment {
@@ -117,11 +119,14 @@ pub async fn save_attachment_to_file(attachment_id: i64, file_path: String) -> R
     let pool = db::pool();
 
     // Load attachment data from database
-    let row = sqlx::query_as::<_, (Vec<u8>,)>("SELECT data FROM attachments WHERE id = ?")
-        .bind(attachment_id)
-        .fetch_one(pool.as_ref())
-        .await
-        .map_err(|e| format!("Failed to load attachment: {}", e))?;
+    let row = sqlx::query_as::<_, (Vec<u8>, Option<String>)>(
+        "SELECT data, archived_path FROM attachments WHERE id = ?",
+    )
+    .bind(attachment_id)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load attachment: {}", e))?;
+    let stored = rehydrate(row.0, row.1).await?;
 
     // Check if encryption is enabled
     let encryption_enabled = is_encryption_enabled().await?;
@@ -129,11 +134,11 @@ pub async fn save_attachment_to_file(attachment_id: i64, file_path: String) -> R
     // Decrypt attachment data if encryption is enabled and unlocked
     let data = if encryption_enabled && is_encryption_unlocked() {
         // Data is stored as base64 string, convert to string first
-        let encrypted_str = String::from_utf8(row.0)
+        let encrypted_str = String::from_utf8(stored)
             .map_err(|e| format!("Failed to convert encrypted data to string: {}", e))?;
         decrypt_bytes(&encrypted_str).map_err(|e| format!("Failed to decrypt attachment: {}", e))?
     } else {
-        row.0
+        stored
     };
 
     // Write to file
@@ -148,3 +153,42 @@ pub async fn save_attachment_to_file(attachment_id: i64, file_path: String) -> R
     );
     Ok(())
 }
+
+/// Load all cached attachments (with decrypted data) for an email row
+pub async fn load_email_attachments(email_id: i64) -> Result<Vec<Attachment>, String> {
+    let pool = db::pool();
+
+    let rows = sqlx::query_as::<_, (i64, String, String, i64, Vec<u8>, Option<String>)>(
+        "SELECT id, filename, content_type, size, data, archived_path
+         FROM attachments WHERE email_id = ?",
+    )
+    .bind(email_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load attachments: {}", e))?;
+
+    let encryption_enabled = is_encryption_enabled().await?;
+
+    let mut attachments = Vec::with_capacity(rows.len());
+    for (id, filename, content_type, size, data, archived_path) in rows {
+        let data = rehydrate(data, archived_path).await?;
+        let data = if encryption_enabled && is_encryption_unlocked() {
+            let encrypted_str = String::from_utf8(data)
+                .map_err(|e| format!("Failed to convert encrypted data to string: {}", e))?;
+            decrypt_bytes(&encrypted_str)
+                .map_err(|e| format!("Failed to decrypt attachment: {}", e))?
+        } else {
+            data
+        };
+
+        attachments.push(Attachment {
+            id: Some(id),
+            filename,
+            content_type,
+            size,
+            data: Some(data),
+        });
+    }
+
+    Ok(attachments)
+}
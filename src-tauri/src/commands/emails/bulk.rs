@@ -0,0 +1,199 @@
+// Bulk email operations
+// Batch variants of the single-message commands for multi-select in the message list. Each
+// sends the whole selection to the server as one UID set (one STORE/EXPUNGE/MOVE instead of a
+// connection per message) and applies the result to the cache in a single transaction.
+
+use crate::commands::emails::move_copy::{transfer_emails, TransferMode};
+use crate::commands::emails::{imap_helpers, maildir};
+use crate::commands::reader_windows;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::AccountConfig;
+use std::collections::HashMap;
+use tauri::{command, AppHandle};
+
+/// Mark several emails as read (set \Seen) on the IMAP server and in the local cache
+#[command]
+pub async fn mark_emails_as_read(
+    config: AccountConfig,
+    uids: Vec<u32>,
+    folder: Option<String>,
+) -> Result<(), String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+    if uids.is_empty() {
+        return Ok(());
+    }
+    println!(
+        "Marking {} email(s) as read in folder {}",
+        uids.len(),
+        folder_name
+    );
+
+    let config = ensure_valid_token(config).await?;
+    let folder_for_task = folder_name.clone();
+    let uid_set = imap_helpers::uid_set(&uids);
+
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+        imap_session
+            .select(&folder_for_task)
+            .map_err(|e| format!("Cannot access folder '{}': {}", folder_for_task, e))?;
+
+        imap_session
+            .uid_store(&uid_set, "+FLAGS.SILENT (\\Seen)")
+            .map_err(|e| format!("Failed to set \\Seen flag: {}", e))?;
+
+        let _ = imap_session.logout();
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let pool = db::pool();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for uid in &uids {
+        sqlx::query(
+            "UPDATE emails SET seen = 1 WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(account_id)
+        .bind(&folder_name)
+        .bind(*uid as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update local cache: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update local cache: {}", e))?;
+
+    println!("✅ Marked {} email(s) as read", uids.len());
+    Ok(())
+}
+
+/// Permanently delete several emails (hard delete)
+#[command]
+pub async fn delete_emails(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    uids: Vec<u32>,
+    folder: Option<String>,
+) -> Result<(), String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+    if uids.is_empty() {
+        return Ok(());
+    }
+    println!(
+        "Permanently deleting {} email(s) from {}",
+        uids.len(),
+        folder_name
+    );
+
+    let config = ensure_valid_token(config).await?;
+    let folder_for_task = folder_name.clone();
+    let uid_set = imap_helpers::uid_set(&uids);
+    let uids_for_task = uids.clone();
+
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+        let supports_uidplus = imap_session
+            .capabilities()
+            .map_err(|e| format!("Failed to get capabilities: {}", e))?
+            .has_str("UIDPLUS");
+
+        imap_session.select(&folder_for_task).map_err(|e| {
+            eprintln!("❌ Failed to SELECT folder '{}': {}", folder_for_task, e);
+            format!("Cannot access folder '{}': {}", folder_for_task, e)
+        })?;
+        if !supports_uidplus {
+            imap_helpers::ensure_plain_expunge_safe(&mut imap_session, &uids_for_task)?;
+        }
+
+        imap_session
+            .uid_store(&uid_set, "+FLAGS.SILENT (\\Deleted)")
+            .map_err(|e| format!("Failed to mark emails as deleted: {}", e))?;
+
+        // UID EXPUNGE leaves alone messages another client marked \Deleted
+        if supports_uidplus {
+            imap_session
+                .uid_expunge(&uid_set)
+                .map_err(|e| format!("Failed to permanently delete emails: {}", e))?;
+        } else {
+            imap_session
+                .expunge()
+                .map_err(|e| format!("Failed to permanently delete emails: {}", e))?;
+        }
+
+        let _ = imap_session.logout();
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let pool = db::pool();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for uid in &uids {
+        sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?")
+            .bind(account_id)
+            .bind(&folder_name)
+            .bind(*uid as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to remove emails from cache: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to remove emails from cache: {}", e))?;
+
+    for uid in &uids {
+        maildir::remove_message(account_id, &folder_name, *uid);
+        reader_windows::close_for_message(&app_handle, account_id, &folder_name, *uid);
+    }
+
+    println!("✅ Permanently deleted {} email(s)", uids.len());
+    Ok(())
+}
+
+/// Move several emails to another folder of the same account
+///
+/// Returns the new UIDs by old UID, for the messages that could be found in the target folder.
+#[command]
+pub async fn move_emails_to_folder(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    uids: Vec<u32>,
+    folder: Option<String>,
+    target_folder: String,
+) -> Result<HashMap<u32, u32>, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+    println!(
+        "Moving {} email(s) from {} to {}",
+        uids.len(),
+        folder_name,
+        target_folder
+    );
+
+    let new_uids = transfer_emails(
+        config,
+        &folder_name,
+        &uids,
+        &target_folder,
+        TransferMode::Move,
+    )
+    .await?;
+
+    for uid in &uids {
+        reader_windows::close_for_message(&app_handle, account_id, &folder_name, *uid);
+    }
+    Ok(new_uids)
+}
@@ -1,14 +1,19 @@
 // Database cache operations for emails
 // This module handles storing and retrieving emails from local SQLite database
 
+use crate::commands::emails::codec::date_header_offset;
+use crate::commands::emails::content_hash::hash_email_content;
+use crate::commands::emails::maildir;
 use crate::db;
 use crate::encryption::{decrypt, encrypt, encrypt_bytes, is_encryption_unlocked};
-use crate::models::{Attachment, EmailHeader};
+use crate::models::{
+    Attachment, EmailHeader, EmailSortOrder, SenderUnreadCount, ThreadUnreadCount,
+};
 use chrono::Utc;
 use tauri::command;
 
 /// Check if encryption is enabled in database settings
-async fn is_encryption_enabled() -> Result<bool, String> {
+pub(crate) async fn is_encryption_enabled() -> Result<bool, String> {
     let pool = db::pool();
     let result = sqlx::query_as::<_, (String,)>(
         "SELECT value FROM settings WHERE key = 'encryption_enabled'",
@@ -33,6 +38,18 @@ pub async fn save_emails_to_cache(
     let encryption_enabled = is_encryption_enabled().await?;
 
     for email in emails {
+        // The conversation starts at the first message of References; with only In-Reply-To,
+        // the parent's conversation is used if the parent is cached
+        let parent_ids = |name: &str| {
+            email
+                .raw_headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .and_then(|(_, value)| value.split(' ').next().map(str::to_string))
+        };
+        let references_root = parent_ids("references");
+        let in_reply_to = parent_ids("in-reply-to");
+
         // Encrypt subject if encryption is enabled and unlocked
         let subject_to_store = if encryption_enabled && is_encryption_unlocked() {
             encrypt(&email.subject).map_err(|e| format!("Failed to encrypt subject: {}", e))?
@@ -43,19 +60,29 @@ pub async fn save_emails_to_cache(
         // Use INSERT with ON CONFLICT to preserve cached body
         let result = sqlx::query(
             "INSERT INTO emails
-            (account_id, folder_name, uid, subject, from_addr, to_addr, cc_addr, date, timestamp, has_attachments, seen, flagged, synced_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (account_id, folder_name, uid, subject, from_addr, to_addr, cc_addr, bcc_addr, date, timestamp, has_attachments, seen, flagged, synced_at, message_id, received_at, date_offset, security, thread_root)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7,
+                COALESCE(NULLIF(?8, ''), (SELECT bcc_addr FROM sent_bcc
+                    WHERE account_id = ?1 AND message_id = ?15)), ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                COALESCE(?19, (SELECT COALESCE(thread_root, message_id) FROM emails
+                    WHERE account_id = ?1 AND message_id = ?20 LIMIT 1), ?20))
             ON CONFLICT(account_id, folder_name, uid) DO UPDATE SET
                 subject = excluded.subject,
                 from_addr = excluded.from_addr,
                 to_addr = excluded.to_addr,
                 cc_addr = excluded.cc_addr,
+                bcc_addr = excluded.bcc_addr,
                 date = excluded.date,
                 timestamp = excluded.timestamp,
                 has_attachments = excluded.has_attachments,
                 seen = excluded.seen,
                 flagged = excluded.flagged,
-                synced_at = excluded.synced_at",
+                synced_at = excluded.synced_at,
+                message_id = COALESCE(excluded.message_id, emails.message_id),
+                received_at = COALESCE(excluded.received_at, emails.received_at),
+                date_offset = excluded.date_offset,
+                security = COALESCE(emails.security, excluded.security),
+                thread_root = COALESCE(excluded.thread_root, emails.thread_root)",
         )
         .bind(account_id)
         .bind(folder_name)
@@ -64,12 +91,24 @@ pub async fn save_emails_to_cache(
         .bind(&email.from)
         .bind(&email.to)
         .bind(&email.cc)
+        .bind(&email.bcc)
         .bind(&email.date)
         .bind(email.timestamp)
         .bind(None::<i64>)  // has_attachments: NULL (未检查), 后台任务会填充
         .bind(email.seen as i64)
         .bind(email.flagged as i64)
         .bind(current_time)
+        .bind(&email.message_id)
+        .bind(email.received_at)
+        .bind(date_header_offset(&email.date))
+        .bind(
+            email
+                .security
+                .as_ref()
+                .and_then(|security| serde_json::to_string(security).ok()),
+        )
+        .bind(references_root)
+        .bind(in_reply_to)
         .execute(pool.as_ref())
         .await;
 
@@ -81,6 +120,10 @@ pub async fn save_emails_to_cache(
                 email.uid, e
             ));
         }
+
+        if !email.raw_headers.is_empty() {
+            save_raw_headers(account_id, folder_name, email).await?;
+        }
     }
 
     println!(
@@ -91,11 +134,48 @@ pub async fn save_emails_to_cache(
     Ok(())
 }
 
+/// Replace the stored searchable raw headers of an email
+async fn save_raw_headers(
+    account_id: i32,
+    folder_name: &str,
+    email: &EmailHeader,
+) -> Result<(), String> {
+    let pool = db::pool();
+
+    let (email_id,) = sqlx::query_as::<_, (i64,)>(
+        "SELECT id FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(email.uid as i64)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to get email id: {}", e))?;
+
+    sqlx::query("DELETE FROM email_raw_headers WHERE email_id = ?")
+        .bind(email_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to clear raw headers: {}", e))?;
+
+    for (name, value) in &email.raw_headers {
+        sqlx::query("INSERT INTO email_raw_headers (email_id, name, value) VALUES (?, ?, ?)")
+            .bind(email_id)
+            .bind(name)
+            .bind(value)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to save raw header: {}", e))?;
+    }
+    Ok(())
+}
+
 /// Load emails from database cache
 #[command]
 pub async fn load_emails_from_cache(
     account_id: i32,
     folder: Option<String>,
+    sort_by: Option<EmailSortOrder>,
 ) -> Result<Vec<EmailHeader>, String> {
     let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
     println!(
@@ -105,11 +185,21 @@ pub async fn load_emails_from_cache(
 
     let pool = db::pool();
 
-    let rows = sqlx::query_as::<_, (i64, String, String, String, Option<String>, String, i64, i64, i64, i64)>(
-        "SELECT uid, subject, from_addr, to_addr, cc_addr, date, timestamp, COALESCE(has_attachments, 0), COALESCE(seen, 0), COALESCE(flagged, 0)
-        FROM emails
-        WHERE account_id = ? AND folder_name = ?
-        ORDER BY timestamp DESC",
+    // Emails cached before INTERNALDATE was stored sort by their Date header
+    let order_by = match sort_by.unwrap_or(EmailSortOrder::DateSent) {
+        EmailSortOrder::DateSent => "timestamp DESC",
+        EmailSortOrder::DateReceived => "COALESCE(received_at, timestamp) DESC",
+    };
+
+    #[allow(clippy::type_complexity)]
+    let rows = sqlx::query_as::<_, (i64, String, String, String, Option<String>, Option<String>, String, i64, i64, i64, i64, Option<String>, Option<i64>, Option<String>)>(
+        &format!(
+            "SELECT uid, subject, from_addr, to_addr, cc_addr, bcc_addr, date, timestamp, COALESCE(has_attachments, 0), COALESCE(seen, 0), COALESCE(flagged, 0), message_id, received_at, security
+            FROM emails
+            WHERE account_id = ? AND folder_name = ?
+            ORDER BY {}",
+            order_by
+        ),
     )
     .bind(account_id)
     .bind(&folder_name)
@@ -123,7 +213,7 @@ pub async fn load_emails_from_cache(
     let emails: Vec<EmailHeader> = rows
         .into_iter()
         .map(
-            |(uid, subject, from, to, cc, date, timestamp, has_attachments, seen, flagged)| {
+            |(uid, subject, from, to, cc, bcc, date, timestamp, has_attachments, seen, flagged, message_id, received_at, security)| {
                 // Decrypt subject if encryption is enabled and unlocked
                 let decrypted_subject = if encryption_enabled && is_encryption_unlocked() {
                     decrypt(&subject).unwrap_or_else(|e| {
@@ -145,11 +235,16 @@ pub async fn load_emails_from_cache(
                     from,
                     to,
                     cc: cc.unwrap_or_default(),
+                    bcc: bcc.unwrap_or_default(),
                     date,
                     timestamp,
                     has_attachments: has_attachments != 0,
                     seen: seen != 0,
                     flagged: flagged != 0,
+                    message_id,
+                    received_at,
+                    security: security.and_then(|security| serde_json::from_str(&security).ok()),
+                    raw_headers: Vec::new(),
                 }
             },
         )
@@ -163,6 +258,88 @@ pub async fn load_emails_from_cache(
     Ok(emails)
 }
 
+/// Get message and unread counts per sender, optionally limited to one folder
+///
+/// Senders with unread mail come first, then by most recent message.
+#[command]
+pub async fn get_unread_counts_by_sender(
+    account_id: i32,
+    folder: Option<String>,
+    only_unread: Option<bool>,
+) -> Result<Vec<SenderUnreadCount>, String> {
+    let pool = db::pool();
+
+    sqlx::query_as::<_, SenderUnreadCount>(
+        "SELECT from_addr, SUM(total) AS total, SUM(unread) AS unread,
+            MAX(latest_timestamp) AS latest_timestamp
+        FROM sender_unread_counts
+        WHERE account_id = ?1 AND (?2 IS NULL OR folder_name = ?2)
+        GROUP BY from_addr
+        HAVING ?3 = 0 OR SUM(unread) > 0
+        ORDER BY SUM(unread) > 0 DESC, latest_timestamp DESC",
+    )
+    .bind(account_id)
+    .bind(folder)
+    .bind(only_unread.unwrap_or(false))
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load unread counts by sender: {}", e))
+}
+
+/// Get message and unread counts per conversation, optionally limited to one folder
+///
+/// Conversations with unread mail come first, then by most recent message.
+#[command]
+pub async fn get_unread_counts_by_thread(
+    account_id: i32,
+    folder: Option<String>,
+    only_unread: Option<bool>,
+) -> Result<Vec<ThreadUnreadCount>, String> {
+    let pool = db::pool();
+
+    sqlx::query_as::<_, ThreadUnreadCount>(
+        "SELECT thread_id, SUM(total) AS total, SUM(unread) AS unread,
+            MAX(latest_timestamp) AS latest_timestamp
+        FROM thread_unread_counts
+        WHERE account_id = ?1 AND (?2 IS NULL OR folder_name = ?2)
+        GROUP BY thread_id
+        HAVING ?3 = 0 OR SUM(unread) > 0
+        ORDER BY SUM(unread) > 0 DESC, latest_timestamp DESC",
+    )
+    .bind(account_id)
+    .bind(folder)
+    .bind(only_unread.unwrap_or(false))
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load unread counts by conversation: {}", e))
+}
+
+/// Get the message and unread counts of the conversation a message belongs to, across folders
+#[command]
+pub async fn get_conversation_unread_count(
+    account_id: i32,
+    folder_name: String,
+    uid: u32,
+) -> Result<Option<ThreadUnreadCount>, String> {
+    let pool = db::pool();
+
+    sqlx::query_as::<_, ThreadUnreadCount>(
+        "SELECT thread_id, SUM(total) AS total, SUM(unread) AS unread,
+            MAX(latest_timestamp) AS latest_timestamp
+        FROM thread_unread_counts
+        WHERE account_id = ?1 AND thread_id = (
+            SELECT COALESCE(thread_root, message_id) FROM emails
+            WHERE account_id = ?1 AND folder_name = ?2 AND uid = ?3)
+        GROUP BY thread_id",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(uid as i64)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load conversation unread count: {}", e))
+}
+
 /// Save email body to cache
 pub async fn save_email_body_to_cache(
     account_id: i32,
@@ -175,6 +352,38 @@ pub async fn save_email_body_to_cache(
     // Check if encryption is enabled
     let encryption_enabled = is_encryption_enabled().await?;
 
+    // Hash the plaintext body together with the Message-ID
+    let message_id = sqlx::query_as::<_, (Option<String>,)>(
+        "SELECT message_id FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(uid as i64)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load email headers: {}", e))?;
+    let content_hash =
+        message_id.map(|(message_id,)| hash_email_content(message_id.as_deref(), body));
+
+    // Local folders kept in Maildir store the body as a file and only the hash in SQLite
+    if !encryption_enabled && maildir::uses_maildir(account_id, folder_name).await {
+        maildir::store_body(account_id, folder_name, uid, body).await?;
+        sqlx::query(
+            "UPDATE emails SET body = NULL, content_hash = ?
+             WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(&content_hash)
+        .bind(account_id)
+        .bind(folder_name)
+        .bind(uid as i64)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save content hash: {}", e))?;
+
+        println!("✅ Saved body to Maildir for UID {}", uid);
+        return Ok(());
+    }
+
     // Encrypt body if encryption is enabled and unlocked
     let body_to_store = if encryption_enabled && is_encryption_unlocked() {
         encrypt(body).map_err(|e| format!("Failed to encrypt body: {}", e))?
@@ -182,14 +391,18 @@ pub async fn save_email_body_to_cache(
         body.to_string()
     };
 
-    sqlx::query("UPDATE emails SET body = ? WHERE account_id = ? AND folder_name = ? AND uid = ?")
-        .bind(&body_to_store)
-        .bind(account_id)
-        .bind(folder_name)
-        .bind(uid as i64)
-        .execute(pool.as_ref())
-        .await
-        .map_err(|e| format!("Failed to save body to cache: {}", e))?;
+    sqlx::query(
+        "UPDATE emails SET body = ?, content_hash = ?
+         WHERE account_id = ? AND folder_name = ? AND uid = ?",
+    )
+    .bind(&body_to_store)
+    .bind(&content_hash)
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(uid as i64)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save body to cache: {}", e))?;
 
     println!(
         "✅ Saved body to cache for UID {} (encrypted: {})",
@@ -239,6 +452,8 @@ pub async fn load_email_body_from_cache(
         } else {
             Ok(Some(body))
         }
+    } else if result.is_some() && maildir::uses_maildir(account_id, folder_name).await {
+        Ok(maildir::read_body(account_id, folder_name, uid))
     } else {
         Ok(None)
     }
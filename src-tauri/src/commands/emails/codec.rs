@@ -1,6 +1,7 @@
 // RFC 2047 encoding/decoding utilities and email parsing helpers
 // This module handles character encoding conversions for email headers
 
+use crate::models::{MessageSecurity, SecurityScheme, SignatureStatus};
 use chrono::{DateTime, Utc};
 use encoding_rs::Encoding;
 use std::collections::HashMap;
@@ -186,6 +187,36 @@ pub fn decode_bytes_to_string(bytes: &[u8]) -> String {
     }
 }
 
+/// Format an envelope address list as "Name <mailbox@host>, ..."
+/// Every recipient keeps its address so the list can be used to reply to all of them.
+/// Group markers (entries without a host) are skipped.
+pub fn format_address_list(addrs: &[imap_proto::types::Address]) -> String {
+    addrs
+        .iter()
+        .filter_map(|addr| {
+            let host = decode_bytes_to_string(addr.host.as_ref()?.as_ref());
+            let mailbox = decode_bytes_to_string(addr.mailbox.clone().unwrap_or_default().as_ref());
+            let address = format!("{}@{}", mailbox, host);
+
+            let name = addr
+                .name
+                .as_ref()
+                .map(|name| decode_header(&decode_bytes_to_string(name.as_ref())))
+                .filter(|name| !name.trim().is_empty());
+            Some(match name {
+                Some(name) if name.contains([',', '"', '<', '>', ';', '@']) => format!(
+                    "\"{}\" <{}>",
+                    name.replace('\\', "\\\\").replace('"', "\\\""),
+                    address
+                ),
+                Some(name) => format!("{} <{}>", name, address),
+                None => address,
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Parse RFC 2822 date string to Unix timestamp
 /// Email dates are in format like: "Mon, 15 Jan 2024 14:30:00 +0800"
 /// If the Date header cannot be parsed and an INTERNALDATE is provided, use it instead
@@ -194,23 +225,53 @@ pub fn parse_email_date(date_str: &str) -> i64 {
     parse_email_date_with_fallback(date_str, None)
 }
 
-/// Parse email date with optional INTERNALDATE fallback
-/// INTERNALDATE is the server's received time (more reliable than Date header)
-pub fn parse_email_date_with_fallback(date_str: &str, internaldate: Option<&str>) -> i64 {
+/// How far a Date header may be ahead of INTERNALDATE (time zones, clock skew)
+const MAX_DATE_AHEAD_OF_RECEIVED_SECS: i64 = 24 * 60 * 60;
+
+/// Parse a Date header in RFC 2822, RFC 3339 or a common non-standard format
+fn parse_date_header(date_str: &str) -> Option<i64> {
+    parse_date_header_with_offset(date_str).map(|dt| dt.timestamp())
+}
+
+fn parse_date_header_with_offset(date_str: &str) -> Option<DateTime<chrono::FixedOffset>> {
     // Try to parse the RFC 2822 format date
     if let Ok(dt) = DateTime::parse_from_rfc2822(date_str) {
-        return dt.timestamp();
+        return Some(dt);
     }
 
     // Try alternative RFC 3339 format (ISO 8601)
     if let Ok(dt) = DateTime::parse_from_rfc3339(date_str) {
-        return dt.timestamp();
+        return Some(dt);
     }
 
-    // If parsing fails, try to extract timestamp from various formats
     // Some servers might send non-standard date formats
-    if let Ok(dt) = chrono::DateTime::parse_from_str(date_str, "%a, %d %b %Y %H:%M:%S %z") {
-        return dt.timestamp();
+    chrono::DateTime::parse_from_str(date_str, "%a, %d %b %Y %H:%M:%S %z").ok()
+}
+
+/// UTC offset of a Date header in seconds east, i.e. the sender's timezone
+pub fn date_header_offset(date_str: &str) -> Option<i32> {
+    parse_date_header_with_offset(date_str).map(|dt| dt.offset().local_minus_utc())
+}
+
+/// Parse email date with optional INTERNALDATE fallback
+/// INTERNALDATE is the server's received time (more reliable than Date header)
+pub fn parse_email_date_with_fallback(date_str: &str, internaldate: Option<&str>) -> i64 {
+    if let Some(timestamp) = parse_date_header(date_str) {
+        // A message can't be sent after the server received it; a Date that is more than a
+        // day ahead of INTERNALDATE comes from a broken clock or a spammer, so don't trust it
+        let received = internaldate
+            .and_then(|d| DateTime::parse_from_rfc2822(d).ok())
+            .map(|dt| dt.timestamp());
+        match received {
+            Some(received) if timestamp > received + MAX_DATE_AHEAD_OF_RECEIVED_SECS => {
+                eprintln!(
+                    "⚠️ Date header '{}' is later than INTERNALDATE, using INTERNALDATE",
+                    date_str
+                );
+                return received;
+            }
+            _ => return timestamp,
+        }
     }
 
     // If Date header parsing failed, try to use INTERNALDATE as fallback
@@ -250,3 +311,94 @@ pub fn check_for_attachments<T: std::fmt::Debug>(body: &T) -> bool {
     // Check for common attachment indicators in BODYSTRUCTURE
     lower.contains("attachment") || lower.contains("filename")
 }
+
+/// Detect S/MIME or PGP signing and encryption from a BODYSTRUCTURE
+///
+/// Like `check_for_attachments`, this looks at the content types and parameters in the
+/// structure's debug form. Signatures are only detected here, not verified.
+pub fn detect_security<T: std::fmt::Debug>(body: &T) -> Option<MessageSecurity> {
+    let lower = format!("{:?}", body).to_lowercase();
+
+    let (scheme, signed, encrypted) =
+        if lower.contains("pgp-signature") || lower.contains("pgp-encrypted") {
+            (
+                SecurityScheme::Pgp,
+                lower.contains("pgp-signature"),
+                lower.contains("pgp-encrypted"),
+            )
+        } else if lower.contains("pkcs7-signature") || lower.contains("pkcs7-mime") {
+            // Opaque pkcs7-mime parts are encrypted unless marked as signed-data
+            let opaque_signed = lower.contains("signed-data");
+            (
+                SecurityScheme::Smime,
+                lower.contains("pkcs7-signature") || opaque_signed,
+                lower.contains("pkcs7-mime") && !opaque_signed,
+            )
+        } else {
+            return None;
+        };
+
+    Some(MessageSecurity {
+        scheme,
+        signed,
+        encrypted,
+        signature: SignatureStatus::Unverified,
+    })
+}
+
+/// Message IDs (`<id@host>`) in a header value; bare IDs get their angle brackets back
+pub fn message_ids(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|id| id.trim_start_matches('<').trim_end_matches('>'))
+        .filter(|id| id.contains('@'))
+        .map(|id| format!("<{}>", id))
+        .collect()
+}
+
+/// Split the header block of a raw message into unfolded (name, value) pairs
+pub fn parse_header_block(raw: &[u8]) -> Vec<(String, String)> {
+    let text = String::from_utf8_lossy(raw);
+    let mut headers: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_security() {
+        let signed = detect_security(
+            &r#"Multipart { ty: "multipart", subtype: "signed", params: [("protocol", "application/pgp-signature")] }"#,
+        )
+        .unwrap();
+        assert_eq!(signed.scheme, SecurityScheme::Pgp);
+        assert!(signed.signed && !signed.encrypted);
+
+        let encrypted = detect_security(
+            &r#"Basic { ty: "application", subtype: "pkcs7-mime", params: [("smime-type", "enveloped-data")] }"#,
+        )
+        .unwrap();
+        assert_eq!(encrypted.scheme, SecurityScheme::Smime);
+        assert!(encrypted.encrypted && !encrypted.signed);
+
+        assert_eq!(detect_security(&r#"Text { subtype: "plain" }"#), None);
+    }
+}
@@ -0,0 +1,206 @@
+// Canonical content hashing for cached emails
+// A SHA-256 over the Message-ID and the normalized plaintext body identifies a message
+// independently of its folder, UID and at-rest encryption. Used to find duplicates and to
+// verify cached bodies.
+
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tauri::command;
+
+/// A cached copy of a message that shares its content hash with others
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DuplicateEmail {
+    pub folder_name: String,
+    pub uid: u32,
+}
+
+/// Cached messages with identical content
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    pub emails: Vec<DuplicateEmail>,
+}
+
+/// Result of verifying cached bodies against their stored hashes
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BodyVerificationReport {
+    pub checked: usize,
+    pub mismatched: usize,
+    pub hashed: usize,   // Bodies cached before hashing existed that got a hash now
+    pub repaired: usize, // Mismatched bodies dropped so they are fetched again
+}
+
+/// Compute the canonical content hash of a message (lowercase hex SHA-256)
+///
+/// Only the Message-ID and body are covered: the other cached headers are rewritten by every
+/// sync and may change format, which would make an intact body look corrupted. Line endings
+/// and trailing whitespace are normalized so the same message hashes equally from any source.
+pub fn hash_email_content(message_id: Option<&str>, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(message_id.unwrap_or("").trim().as_bytes());
+    hasher.update(b"\n\n");
+    hasher.update(canonical_body(body).as_bytes());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Normalize CRLF/CR to LF, strip trailing whitespace per line and trailing blank lines
+fn canonical_body(body: &str) -> String {
+    let normalized = body.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = normalized.lines().map(|line| line.trim_end()).collect();
+    lines.join("\n").trim_end_matches('\n').to_string()
+}
+
+/// Find cached messages of an account that have identical content
+#[command]
+pub async fn find_duplicate_emails(account_id: i32) -> Result<Vec<DuplicateGroup>, String> {
+    let pool = db::pool();
+
+    let rows = sqlx::query_as::<_, (String, String, i64)>(
+        "SELECT content_hash, folder_name, uid FROM emails
+         WHERE account_id = ?1 AND content_hash IN (
+             SELECT content_hash FROM emails
+             WHERE account_id = ?1 AND content_hash IS NOT NULL
+             GROUP BY content_hash HAVING COUNT(*) > 1
+         )
+         ORDER BY content_hash, folder_name, uid",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to find duplicate emails: {}", e))?;
+
+    let mut groups: BTreeMap<String, Vec<DuplicateEmail>> = BTreeMap::new();
+    for (content_hash, folder_name, uid) in rows {
+        groups
+            .entry(content_hash)
+            .or_default()
+            .push(DuplicateEmail {
+                folder_name,
+                uid: uid as u32,
+            });
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(content_hash, emails)| DuplicateGroup {
+            content_hash,
+            emails,
+        })
+        .collect())
+}
+
+/// Recompute the hash of every cached body of an account and compare it with the stored one
+///
+/// With `repair` (default true), mismatched bodies are dropped so they are fetched from the
+/// server again, and bodies without a hash get one. Bodies in local folders are the only copy
+/// and are never dropped, only reported.
+#[command]
+pub async fn verify_cached_bodies(
+    account_id: i32,
+    repair: Option<bool>,
+) -> Result<BodyVerificationReport, String> {
+    let repair = repair.unwrap_or(true);
+    let pool = db::pool();
+
+    let encryption_enabled = is_encryption_enabled().await?;
+    if encryption_enabled && !is_encryption_unlocked() {
+        return Err("Vault is locked. Unlock it to verify cached emails.".to_string());
+    }
+
+    let rows = sqlx::query_as::<_, (i64, Option<String>, String, Option<String>, bool)>(
+        "SELECT e.id, e.message_id, e.body, e.content_hash, COALESCE(f.is_local, 0)
+         FROM emails e
+         LEFT JOIN folders f ON f.account_id = e.account_id AND f.name = e.folder_name
+         WHERE e.account_id = ? AND e.body IS NOT NULL",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load cached bodies: {}", e))?;
+
+    let mut report = BodyVerificationReport::default();
+
+    for (id, message_id, stored_body, stored_hash, is_local) in rows {
+        report.checked += 1;
+
+        // Bodies cached while the vault was locked are stored in plaintext, so one that doesn't
+        // decrypt is only corrupt if it doesn't hash as plaintext either
+        let actual_hash = if encryption_enabled {
+            match decrypt(&stored_body) {
+                Ok(body) => Some(hash_email_content(message_id.as_deref(), &body)),
+                Err(_) => Some(hash_email_content(message_id.as_deref(), &stored_body))
+                    .filter(|plaintext_hash| stored_hash.as_ref() == Some(plaintext_hash)),
+            }
+        } else {
+            Some(hash_email_content(message_id.as_deref(), &stored_body))
+        };
+
+        match (stored_hash, actual_hash) {
+            (Some(expected), Some(actual)) if expected == actual => {}
+            // Can't tell a plaintext body from a corrupt one without a hash; leave it alone
+            (None, None) => {}
+            (None, Some(actual)) => {
+                if repair {
+                    sqlx::query("UPDATE emails SET content_hash = ? WHERE id = ?")
+                        .bind(&actual)
+                        .bind(id)
+                        .execute(pool.as_ref())
+                        .await
+                        .map_err(|e| format!("Failed to store content hash: {}", e))?;
+                    report.hashed += 1;
+                }
+            }
+            _ => {
+                report.mismatched += 1;
+                if repair && !is_local {
+                    sqlx::query("UPDATE emails SET body = NULL, content_hash = NULL WHERE id = ?")
+                        .bind(id)
+                        .execute(pool.as_ref())
+                        .await
+                        .map_err(|e| format!("Failed to drop corrupted body: {}", e))?;
+                    report.repaired += 1;
+                }
+            }
+        }
+    }
+
+    if report.mismatched > 0 {
+        tracing::warn!(
+            account_id = account_id,
+            mismatched = report.mismatched,
+            repaired = report.repaired,
+            "Cached email bodies failed hash verification"
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_ignores_line_endings_and_trailing_whitespace() {
+        let a = hash_email_content(Some("<a@x>"), "Hello \r\nWorld\r\n\r\n");
+        let b = hash_email_content(Some("<a@x>"), "Hello\nWorld");
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+    }
+
+    #[test]
+    fn test_hash_depends_on_message_id_and_body() {
+        let base = hash_email_content(Some("<a@x>"), "body");
+        assert_ne!(base, hash_email_content(Some("<b@x>"), "body"));
+        assert_ne!(base, hash_email_content(Some("<a@x>"), "other"));
+    }
+}
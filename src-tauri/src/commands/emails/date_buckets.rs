@@ -0,0 +1,128 @@
+// Date-grouped email lists
+// Groups a cached folder into the buckets shown as list headers (Today, Yesterday, This Week,
+// Last Week, This Month, then one bucket per older month), so the UI doesn't recompute them
+// for thousands of rows. Buckets follow the user's calendar: the system timezone, or the
+// offset the UI passes in.
+
+use crate::commands::emails::cache::load_emails_from_cache;
+use crate::models::{EmailHeader, EmailSortOrder};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// A group of emails under one date header
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DateBucket {
+    pub key: String, // "today", "yesterday", "this_week", "last_week", "this_month" or "2024-03"
+    pub label: String, // English header text; the UI may translate by key
+    pub emails: Vec<EmailHeader>,
+}
+
+/// Bucket key and label of a date, relative to `today`
+fn bucket_for(date: NaiveDate, today: NaiveDate) -> (String, String) {
+    let days_ago = (today - date).num_days();
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+
+    let named = if days_ago <= 0 {
+        Some(("today", "Today"))
+    } else if days_ago == 1 {
+        Some(("yesterday", "Yesterday"))
+    } else if date >= week_start {
+        Some(("this_week", "This Week"))
+    } else if date >= week_start - Duration::days(7) {
+        Some(("last_week", "Last Week"))
+    } else if date.year() == today.year() && date.month() == today.month() {
+        Some(("this_month", "This Month"))
+    } else {
+        None
+    };
+
+    match named {
+        Some((key, label)) => (key.to_string(), label.to_string()),
+        None => (
+            format!("{:04}-{:02}", date.year(), date.month()),
+            date.format("%B %Y").to_string(),
+        ),
+    }
+}
+
+/// Group emails (already sorted newest first) into date buckets
+fn group_by_date(
+    emails: Vec<EmailHeader>,
+    sort_by: EmailSortOrder,
+    offset: FixedOffset,
+    now: DateTime<Utc>,
+) -> Vec<DateBucket> {
+    let today = now.with_timezone(&offset).date_naive();
+    let mut buckets: Vec<DateBucket> = Vec::new();
+
+    for email in emails {
+        let timestamp = match sort_by {
+            EmailSortOrder::DateSent => email.timestamp,
+            EmailSortOrder::DateReceived => email.received_at.unwrap_or(email.timestamp),
+        };
+        let date = offset
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|date| date.date_naive())
+            .unwrap_or(today);
+        let (key, label) = bucket_for(date, today);
+
+        match buckets.last_mut() {
+            Some(bucket) if bucket.key == key => bucket.emails.push(email),
+            _ => buckets.push(DateBucket {
+                key,
+                label,
+                emails: vec![email],
+            }),
+        }
+    }
+
+    buckets
+}
+
+/// Load a cached folder grouped into date buckets
+///
+/// `utc_offset_minutes` overrides the system timezone (e.g. `-300` for UTC-5).
+#[command]
+pub async fn load_emails_by_date(
+    account_id: i32,
+    folder: Option<String>,
+    sort_by: Option<EmailSortOrder>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Vec<DateBucket>, String> {
+    let sort_by = sort_by.unwrap_or(EmailSortOrder::DateSent);
+    let emails = load_emails_from_cache(account_id, folder, Some(sort_by)).await?;
+
+    let offset = match utc_offset_minutes {
+        Some(minutes) => FixedOffset::east_opt(minutes * 60)
+            .ok_or_else(|| format!("Invalid UTC offset: {} minutes", minutes))?,
+        None => *Local::now().offset(),
+    };
+
+    Ok(group_by_date(emails, sort_by, offset, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_bucket_for() {
+        // Thursday
+        let today = day(2024, 5, 16);
+        assert_eq!(bucket_for(day(2024, 5, 16), today).0, "today");
+        assert_eq!(bucket_for(day(2024, 5, 15), today).0, "yesterday");
+        assert_eq!(bucket_for(day(2024, 5, 13), today).0, "this_week");
+        assert_eq!(bucket_for(day(2024, 5, 6), today).0, "last_week");
+        assert_eq!(bucket_for(day(2024, 5, 2), today).0, "this_month");
+        assert_eq!(
+            bucket_for(day(2024, 3, 9), today),
+            ("2024-03".to_string(), "March 2024".to_string())
+        );
+    }
+}
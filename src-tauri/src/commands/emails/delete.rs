@@ -1,17 +1,20 @@
 // Email deletion operations
 // This module handles moving emails to trash and permanent deletion
 
-use crate::commands::emails::imap_helpers;
+use crate::commands::emails::{imap_helpers, maildir};
+use crate::commands::folder_names::FolderRole;
+use crate::commands::folders::folder_for_role;
+use crate::commands::reader_windows;
 use crate::commands::utils::ensure_valid_token;
 use crate::models::AccountConfig;
-use tauri::command;
+use tauri::{command, AppHandle};
 
 // Import NameAttribute from imap-proto for folder attribute checking
 use imap_proto::types::NameAttribute;
 
 /// Helper function to find the trash/deleted folder for an account
 /// Different providers use different names for the trash folder
-fn find_trash_folder(
+pub(crate) fn find_trash_folder(
     imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
 ) -> Result<String, String> {
     // Common trash folder names used by different providers
@@ -70,6 +73,7 @@ fn find_trash_folder(
 /// Move email to trash folder (soft delete)
 #[command]
 pub async fn move_email_to_trash(
+    app_handle: AppHandle,
     config: AccountConfig,
     uid: u32,
     folder: Option<String>,
@@ -83,6 +87,7 @@ pub async fn move_email_to_trash(
 
     // Clone folder_name for use in both the blocking task and later cache removal
     let folder_name_for_task = folder_name.clone();
+    let known_trash = folder_for_role(account_id, FolderRole::Trash).await?;
 
     tokio::task::spawn_blocking(move || -> Result<(), String> {
         let folder_name = folder_name_for_task;
@@ -92,8 +97,11 @@ pub async fn move_email_to_trash(
 
         println!("IMAP authentication successful");
 
-        // Find the trash folder
-        let trash_folder = find_trash_folder(&mut imap_session)?;
+        // Find the trash folder: the \Trash folder from the last folder sync, else by name
+        let trash_folder = match known_trash {
+            Some(trash_folder) => trash_folder,
+            None => find_trash_folder(&mut imap_session)?,
+        };
         println!("Using trash folder: {}", trash_folder);
 
         // Select the source folder
@@ -145,6 +153,9 @@ pub async fn move_email_to_trash(
         .execute(pool.as_ref())
         .await
         .map_err(|e| format!("Failed to remove email from cache: {}", e))?;
+    maildir::remove_message(account_id, &folder_name, uid);
+
+    reader_windows::close_for_message(&app_handle, account_id, &folder_name, uid);
 
     println!(
         "✅ Successfully moved email UID {} to trash and removed from cache",
@@ -157,6 +168,7 @@ pub async fn move_email_to_trash(
 /// Permanently delete email (hard delete)
 #[command]
 pub async fn delete_email(
+    app_handle: AppHandle,
     config: AccountConfig,
     uid: u32,
     folder: Option<String>,
@@ -221,6 +233,9 @@ pub async fn delete_email(
         .execute(pool.as_ref())
         .await
         .map_err(|e| format!("Failed to remove email from cache: {}", e))?;
+    maildir::remove_message(account_id, &folder_name, uid);
+
+    reader_windows::close_for_message(&app_handle, account_id, &folder_name, uid);
 
     println!(
         "✅ Successfully deleted email UID {} from server and removed from cache",
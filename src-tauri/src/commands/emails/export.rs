@@ -0,0 +1,270 @@
+// Conversation export
+// Writes every cached message of a conversation into one self-contained HTML file, oldest
+// first, with headers, bodies and attachments embedded. Messages are grouped by subject with
+// reply/forward prefixes removed, across all folders of the account. The file can be printed
+// to PDF from any browser.
+
+use crate::commands::date_format::{display_zone, format_email_date};
+use crate::commands::emails::attachments::load_email_attachments;
+use crate::commands::emails::cache::{is_encryption_enabled, load_email_body_from_cache};
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use base64::{engine::general_purpose, Engine as _};
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::command;
+
+/// Attachments larger than this are listed but not embedded
+const MAX_EMBEDDED_ATTACHMENT_BYTES: i64 = 10 * 1024 * 1024;
+
+/// Result of a conversation export
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConversationExport {
+    pub path: String,
+    pub messages: usize,
+    pub missing_bodies: usize, // Messages whose body was never downloaded
+}
+
+struct ConversationMessage {
+    id: i64,
+    folder_name: String,
+    uid: u32,
+    subject: String,
+    from: String,
+    to: String,
+    cc: Option<String>,
+    date: String,
+}
+
+/// Strip any number of reply/forward prefixes ("Re:", "Fwd:", "AW:", "回复:" ...)
+fn normalize_subject(subject: &str) -> String {
+    const PREFIXES: [&str; 8] = ["re", "fw", "fwd", "aw", "wg", "sv", "回复", "转发"];
+
+    let mut rest = subject.trim();
+    loop {
+        let Some((prefix, after)) = rest.split_once([':', '：']) else {
+            break;
+        };
+        // Allow counters like "Re[2]:"
+        let prefix = prefix.trim().split('[').next().unwrap_or_default();
+        if !PREFIXES.iter().any(|p| p.eq_ignore_ascii_case(prefix)) {
+            break;
+        }
+        rest = after.trim_start();
+    }
+    rest.to_lowercase()
+}
+
+/// Export the conversation containing a message to an HTML file
+#[command]
+pub async fn export_conversation(
+    account_id: i32,
+    folder_name: String,
+    uid: u32,
+    output_path: String,
+) -> Result<ConversationExport, String> {
+    let encryption_enabled = is_encryption_enabled().await?;
+    if encryption_enabled && !is_encryption_unlocked() {
+        return Err("Vault is locked. Unlock it to export emails.".to_string());
+    }
+
+    let pool = db::pool();
+    let date_zone = display_zone().await;
+
+    #[allow(clippy::type_complexity)]
+    let rows = sqlx::query_as::<
+        _,
+        (
+            i64,
+            String,
+            i64,
+            String,
+            String,
+            String,
+            Option<String>,
+            i64,
+            Option<i32>,
+            Option<String>,
+        ),
+    >(
+        "SELECT id, folder_name, uid, subject, from_addr, to_addr, cc_addr, timestamp, date_offset, message_id
+         FROM emails WHERE account_id = ? ORDER BY timestamp ASC, id ASC",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load emails: {}", e))?;
+
+    let mut messages: Vec<(ConversationMessage, Option<String>)> = rows
+        .into_iter()
+        .map(
+            |(
+                id,
+                folder,
+                message_uid,
+                subject,
+                from,
+                to,
+                cc,
+                timestamp,
+                date_offset,
+                message_id,
+            )| {
+                let subject = if encryption_enabled {
+                    decrypt(&subject).unwrap_or_default()
+                } else {
+                    subject
+                };
+                (
+                    ConversationMessage {
+                        id,
+                        folder_name: folder,
+                        uid: message_uid as u32,
+                        subject,
+                        from,
+                        to,
+                        cc,
+                        date: format_email_date(timestamp, date_offset, date_zone),
+                    },
+                    message_id,
+                )
+            },
+        )
+        .collect();
+
+    let selected = messages
+        .iter()
+        .find(|(message, _)| message.folder_name == folder_name && message.uid == uid)
+        .ok_or("Email not found in cache")?;
+    let selected_id = selected.0.id;
+    let topic = normalize_subject(&selected.0.subject);
+
+    // The same message may be cached in several folders (e.g. INBOX and an archive)
+    let mut seen_message_ids = HashSet::new();
+    messages.retain(|(message, message_id)| {
+        let same_topic = if topic.is_empty() {
+            message.id == selected_id
+        } else {
+            normalize_subject(&message.subject) == topic
+        };
+        same_topic
+            && message_id
+                .as_ref()
+                .map_or(true, |id| seen_message_ids.insert(id.clone()))
+    });
+
+    let title = messages
+        .first()
+        .map(|(message, _)| message.subject.clone())
+        .unwrap_or_default();
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", Roboto, Arial, sans-serif; margin: 24px; color: #333; }}\n\
+         .message {{ border-top: 1px solid #ccc; padding: 16px 0; page-break-inside: avoid; }}\n\
+         .headers {{ font-size: 13px; color: #555; margin-bottom: 8px; }}\n\
+         .headers b {{ color: #333; }}\n\
+         iframe {{ width: 100%; min-height: 300px; border: none; }}\n\
+         .attachments img {{ max-width: 100%; display: block; margin: 8px 0; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n<p>{} messages</p>\n",
+        encode_text(&title),
+        encode_text(&title),
+        messages.len()
+    );
+
+    let mut missing_bodies = 0;
+    for (message, _) in &messages {
+        html.push_str("<div class=\"message\">\n<div class=\"headers\">\n");
+        for (label, value) in [
+            ("From", Some(message.from.as_str())),
+            ("To", Some(message.to.as_str())),
+            ("Cc", message.cc.as_deref()),
+            ("Date", Some(message.date.as_str())),
+            ("Subject", Some(message.subject.as_str())),
+            ("Folder", Some(message.folder_name.as_str())),
+        ] {
+            if let Some(value) = value.filter(|value| !value.trim().is_empty()) {
+                html.push_str(&format!(
+                    "<div><b>{}:</b> {}</div>\n",
+                    label,
+                    encode_text(value)
+                ));
+            }
+        }
+        html.push_str("</div>\n");
+
+        // Each body is its own HTML document, so keep it isolated in an iframe
+        match load_email_body_from_cache(account_id, &message.folder_name, message.uid).await? {
+            Some(body) => html.push_str(&format!(
+                "<iframe sandbox srcdoc=\"{}\"></iframe>\n",
+                encode_double_quoted_attribute(&body)
+            )),
+            None => {
+                missing_bodies += 1;
+                html.push_str("<p><i>(Message body was not downloaded)</i></p>\n");
+            }
+        }
+
+        let attachments = load_email_attachments(message.id).await?;
+        if !attachments.is_empty() {
+            html.push_str("<div class=\"attachments\">\n<b>Attachments:</b>\n<ul>\n");
+            for attachment in attachments {
+                let name = encode_text(&attachment.filename);
+                match attachment.data.as_deref() {
+                    Some(data) if attachment.size <= MAX_EMBEDDED_ATTACHMENT_BYTES => {
+                        let data_url = format!(
+                            "data:{};base64,{}",
+                            encode_double_quoted_attribute(&attachment.content_type),
+                            general_purpose::STANDARD.encode(data)
+                        );
+                        html.push_str(&format!(
+                            "<li><a download=\"{}\" href=\"{}\">{}</a>",
+                            encode_double_quoted_attribute(&attachment.filename),
+                            data_url,
+                            name
+                        ));
+                        if attachment.content_type.starts_with("image/") {
+                            html.push_str(&format!("<img src=\"{}\" alt=\"{}\">", data_url, name));
+                        }
+                        html.push_str("</li>\n");
+                    }
+                    _ => html.push_str(&format!(
+                        "<li>{} ({} bytes, not embedded)</li>\n",
+                        name, attachment.size
+                    )),
+                }
+            }
+            html.push_str("</ul>\n</div>\n");
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+
+    std::fs::write(&output_path, html).map_err(|e| format!("Failed to write export: {}", e))?;
+
+    println!(
+        "✅ Exported conversation '{}' ({} messages) to {}",
+        title,
+        messages.len(),
+        output_path
+    );
+
+    Ok(ConversationExport {
+        path: output_path,
+        messages: messages.len(),
+        missing_bodies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_subject_strips_reply_prefixes() {
+        assert_eq!(normalize_subject("Re: Fwd: RE[2]: Budget"), "budget");
+        assert_eq!(normalize_subject("回复：Budget"), "budget");
+        assert_eq!(normalize_subject("Meeting: Budget"), "meeting: budget");
+    }
+}
@@ -5,7 +5,8 @@ use crate::commands::emails::cache::{
     load_email_body_from_cache, save_attachments_to_cache, save_email_body_to_cache,
 };
 use crate::commands::emails::codec::{
-    check_for_attachments, decode_bytes_to_string, decode_header, parse_email_date_with_fallback,
+    check_for_attachments, decode_bytes_to_string, decode_header, detect_security,
+    format_address_list, parse_email_date_with_fallback,
 };
 use crate::commands::emails::imap_helpers;
 use crate::commands::utils::ensure_valid_token;
@@ -136,59 +137,27 @@ pub async fn fetch_emails(
 
             let to = envelope
                 .to
-                .as_ref()
-                .map(|addrs| {
-                    addrs
-                        .iter()
-                        .map(|addr| {
-                            // Try to get the display name first
-                            if let Some(ref name_bytes) = addr.name {
-                                let name = decode_bytes_to_string(name_bytes.as_ref());
-                                // Only use the name if it's not empty
-                                if !name.trim().is_empty() {
-                                    // Decode RFC 2047 encoded words
-                                    return decode_header(&name);
-                                }
-                            }
-                            // Fall back to email address if no display name
-                            let mailbox = decode_bytes_to_string(
-                                addr.mailbox.clone().unwrap_or_default().as_ref(),
-                            );
-                            let host = decode_bytes_to_string(
-                                addr.host.clone().unwrap_or_default().as_ref(),
-                            );
-                            format!("{}@{}", mailbox, host)
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                })
+                .as_deref()
+                .map(format_address_list)
                 .unwrap_or_else(|| "(Unknown Recipient)".to_string());
 
             let cc = envelope
                 .cc
+                .as_deref()
+                .map(format_address_list)
+                .unwrap_or_default();
+
+            let bcc = envelope
+                .bcc
+                .as_deref()
+                .map(format_address_list)
+                .unwrap_or_default();
+
+            let message_id = envelope
+                .message_id
                 .as_ref()
-                .map(|addrs| {
-                    addrs
-                        .iter()
-                        .map(|addr| {
-                            if let Some(ref name_bytes) = addr.name {
-                                let name = decode_bytes_to_string(name_bytes.as_ref());
-                                if !name.trim().is_empty() {
-                                    return decode_header(&name);
-                                }
-                            }
-                            let mailbox = decode_bytes_to_string(
-                                addr.mailbox.clone().unwrap_or_default().as_ref(),
-                            );
-                            let host = decode_bytes_to_string(
-                                addr.host.clone().unwrap_or_default().as_ref(),
-                            );
-                            format!("{}@{}", mailbox, host)
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                })
-                .unwrap_or_else(|| "".to_string());
+                .map(|id| decode_bytes_to_string(id.as_ref()).trim().to_string())
+                .filter(|id| !id.is_empty());
 
             // Check if email has attachments by examining BODYSTRUCTURE
             let has_attachments = msg
@@ -214,11 +183,16 @@ pub async fn fetch_emails(
                 from,
                 to,
                 cc,
+                bcc,
                 date,
                 timestamp,
                 has_attachments,
                 seen,
                 flagged,
+                message_id,
+                received_at: msg.internal_date().map(|d| d.timestamp()),
+                security: msg.bodystructure().and_then(detect_security),
+                raw_headers: Vec::new(),
             });
         }
 
@@ -284,96 +258,10 @@ pub async fn fetch_email_body_with_attachments(
 
             let raw_body = message.body().unwrap_or_default();
 
-            let parsed_mail = mail_parser::MessageParser::default()
-                .parse(raw_body)
-                .ok_or_else(|| {
-                    eprintln!("❌ Failed to parse email message for UID {}", uid);
-                    "Failed to parse email message".to_string()
-                })?;
-
-            // mail-parser automatically handles multipart messages
-            let final_body = if let Some(html_body) = parsed_mail.body_html(0) {
-                // Check if the email already contains a complete HTML document
-                let html_lower = html_body.to_lowercase();
-                let is_complete_html = html_lower.contains("<!doctype")
-                    || (html_lower.contains("<html") && html_lower.contains("</html>"));
-
-                if is_complete_html {
-                    // Email already has complete HTML structure, use as-is
-                    html_body.to_string()
-                } else {
-                    // HTML fragment without document structure, wrap it
-                    format!(
-                        r#"<!DOCTYPE html>
-<html>
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <style>
-        body {{
-            font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, "Helvetica Neue", Arial, sans-serif;
-            line-height: 1.6;
-            color: #333;
-            max-width: 100%;
-            overflow-wrap: break-word;
-            word-wrap: break-word;
-            margin: 0;
-            padding: 20px;
-        }}
-        img {{
-            max-width: 100%;
-            height: auto;
-        }}
-        table {{
-            max-width: 100%;
-            border-collapse: collapse;
-        }}
-        a {{
-            color: #0066cc;
-            text-decoration: none;
-        }}
-        a:hover {{
-            text-decoration: underline;
-        }}
-    </style>
-</head>
-<body>
-{}
-</body>
-</html>"#,
-                        html_body
-                    )
-                }
-            } else if let Some(text_body) = parsed_mail.body_text(0) {
-                format!("<pre>{}</pre>", html_escape::encode_text(&text_body))
-            } else {
-                "(No readable body found)".to_string()
-            };
-
-            // Extract attachments from the email
-            let mut attachments = Vec::new();
-            for attachment in parsed_mail.attachments() {
-                let filename = attachment
-                    .attachment_name()
-                    .unwrap_or("unnamed_attachment")
-                    .to_string();
-
-                let content_type = attachment
-                    .content_type()
-                    .map(|ct| ct.c_type.to_string())
-                    .unwrap_or_else(|| "application/octet-stream".to_string());
-
-                let data = attachment.contents().to_vec();
-                let size = data.len() as i64;
-
-                attachments.push(Attachment {
-                    id: None,
-                    filename,
-                    content_type,
-                    size,
-                    data: Some(data),
-                });
-            }
+            let (final_body, attachments) = parse_raw_message(raw_body).map_err(|e| {
+                eprintln!("❌ Failed to parse email message for UID {}", uid);
+                e
+            })?;
 
             let _ = imap_session.logout();
             Ok((final_body, attachments))
@@ -415,8 +303,23 @@ pub async fn fetch_email_body_cached(
         account_id, uid, folder_name
     );
 
+    let on_open_data = serde_json::json!({
+        "account_id": account_id,
+        "folder": folder_name,
+        "uid": uid,
+    });
+    tokio::spawn(async move {
+        let _ = crate::commands::plugins::run_hook(
+            crate::commands::plugins::PluginHook::OnOpen,
+            on_open_data,
+        )
+        .await;
+    });
+
     // Try to load from cache first
-    if let Some(cached_body) = load_email_body_from_cache(account_id, &folder_name, uid).await? {
+    let cached = load_email_body_from_cache(account_id, &folder_name, uid).await?;
+    crate::metrics::record_cache("email_body", cached.is_some());
+    if let Some(cached_body) = cached {
         println!("✅ Loaded body from cache for UID {}", uid);
         return Ok(cached_body);
     }
@@ -424,8 +327,12 @@ pub async fn fetch_email_body_cached(
     println!("📥 Cache miss - fetching body from server for UID {}", uid);
 
     // Not in cache, fetch from server
+    let fetch_started = std::time::Instant::now();
     let (body, attachments) = match fetch_email_body_with_attachments(config, uid, folder).await {
-        Ok(result) => result,
+        Ok(result) => {
+            crate::metrics::record_duration("body_fetch_ms", fetch_started.elapsed());
+            result
+        }
         Err(e) => {
             eprintln!("❌ Failed to fetch email body from server: {}", e);
             return Err(e);
@@ -477,3 +384,107 @@ pub async fn fetch_email_body_cached(
     );
     Ok(body)
 }
+
+/// Parse a raw RFC 5322 message into display-ready HTML and its attachments
+///
+/// HTML fragments are wrapped in a full document; plain-text bodies are escaped into `<pre>`.
+pub fn parse_raw_message(raw_message: &[u8]) -> Result<(String, Vec<Attachment>), String> {
+    let parsed_mail = mail_parser::MessageParser::default()
+        .parse(raw_message)
+        .ok_or_else(|| "Failed to parse email message".to_string())?;
+
+    // mail-parser automatically handles multipart messages
+    let final_body = if let Some(html_body) = parsed_mail.body_html(0) {
+        // Check if the email already contains a complete HTML document
+        let html_lower = html_body.to_lowercase();
+        let is_complete_html = html_lower.contains("<!doctype")
+            || (html_lower.contains("<html") && html_lower.contains("</html>"));
+
+        if is_complete_html {
+            // Email already has complete HTML structure, use as-is
+            html_body.to_string()
+        } else {
+            // HTML fragment without document structure, wrap it
+            format!(
+                r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <style>
+        body {{
+            font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, "Helvetica Neue", Arial, sans-serif;
+            line-height: 1.6;
+            color: #333;
+            max-width: 100%;
+            overflow-wrap: break-word;
+            word-wrap: break-word;
+            margin: 0;
+            padding: 20px;
+        }}
+        img {{
+            max-width: 100%;
+            height: auto;
+        }}
+        table {{
+            max-width: 100%;
+            border-collapse: collapse;
+        }}
+        a {{
+            color: #0066cc;
+            text-decoration: none;
+        }}
+        a:hover {{
+            text-decoration: underline;
+        }}
+    </style>
+</head>
+<body>
+{}
+</body>
+</html>"#,
+                html_body
+            )
+        }
+    } else if let Some(text_body) = parsed_mail.body_text(0) {
+        format!("<pre>{}</pre>", html_escape::encode_text(&text_body))
+    } else {
+        "(No readable body found)".to_string()
+    };
+
+    // Extract attachments from the email
+    let mut attachments = Vec::new();
+    for attachment in parsed_mail.attachments() {
+        let filename = attachment
+            .attachment_name()
+            .unwrap_or("unnamed_attachment")
+            .to_string();
+
+        let content_type = attachment
+            .content_type()
+            .map(|ct| ct.c_type.to_string())
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        let data = attachment.contents().to_vec();
+
+        // Unpack winmail.dat into the files it hides; keep it as-is if that fails
+        if super::tnef::is_tnef(&filename, &content_type) {
+            if let Some(unpacked) = super::tnef::decode(&data) {
+                attachments.extend(unpacked);
+                continue;
+            }
+        }
+
+        let size = data.len() as i64;
+
+        attachments.push(Attachment {
+            id: None,
+            filename,
+            content_type,
+            size,
+            data: Some(data),
+        });
+    }
+
+    Ok((final_body, attachments))
+}
@@ -1,18 +1,26 @@
+use crate::commands::emails::codec::detect_security;
 use crate::commands::emails::imap_helpers;
+use crate::commands::utils::ensure_valid_token;
 use crate::db;
-use crate::models::{AccountConfig, AuthType};
+use crate::models::{AccountConfig, AuthType, MessageSecurity};
 use crate::security;
+use crate::watchdog::Heartbeat;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use tauri::command;
 
-/// Check if an email has attachments by fetching its BODYSTRUCTURE
-#[allow(dead_code)]
+/// Most UIDs fetched by one `prioritize_bodystructure` call (roughly one screen of messages)
+const MAX_PRIORITY_UIDS: usize = 100;
+
+/// Attachment info resolved from an email's BODYSTRUCTURE
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AttachmentInfo {
     pub uid: u32,
     pub has_attachments: bool,
     pub attachment_count: usize,
+    #[serde(default)]
+    pub security: Option<MessageSecurity>,
 }
 
 /// Background task to fetch BODYSTRUCTURE for emails (newest first)
@@ -36,6 +44,7 @@ pub async fn fetch_bodystructure_background(
     account_id: i64,
     folder_name: String,
     cancel_token: Arc<AtomicBool>,
+    heartbeat: Heartbeat,
 ) -> Result<(), String> {
     println!(
         "🔄 Starting background BODYSTRUCTURE fetch for account {} folder '{}'",
@@ -43,40 +52,7 @@ pub async fn fetch_bodystructure_background(
     );
 
     let pool = db::pool();
-
-    // Get account config
-    let (id, email, imap_server, imap_port, smtp_server, smtp_port, auth_type) =
-        sqlx::query_as::<_, (i64, String, String, i64, String, i64, String)>(
-            "SELECT id, email, imap_server, imap_port, smtp_server, smtp_port, auth_type FROM accounts WHERE id = ?"
-        )
-        .bind(account_id)
-        .fetch_one(pool.as_ref())
-        .await
-        .map_err(|e| format!("Account {} not found: {}", account_id, e))?;
-
-    let auth_type_enum = match auth_type.as_str() {
-        "oauth2" => Some(AuthType::OAuth2),
-        _ => Some(AuthType::Basic),
-    };
-
-    // Load sensitive credentials from keyring
-    let creds = security::get_credentials(&email)
-        .map_err(|e| format!("Failed to load credentials for {}: {}", email, e))?;
-
-    let config = AccountConfig {
-        id: Some(id as i32),
-        email,
-        password: creds.password,
-        imap_server,
-        imap_port: imap_port as u16,
-        smtp_server,
-        smtp_port: smtp_port as u16,
-        auth_type: auth_type_enum,
-        access_token: creds.access_token,
-        refresh_token: creds.refresh_token,
-        token_expires_at: creds.token_expires_at,
-        display_name: None,
-    };
+    let config = load_account_config(account_id).await?;
 
     // Get all UIDs that don't have attachment info yet (has_attachments IS NULL)
     let pending_uids: Vec<i64> = sqlx::query_as::<_, (i64,)>(
@@ -107,8 +83,9 @@ pub async fn fetch_bodystructure_background(
     let folder_name_clone = folder_name.clone();
     let config_clone = config.clone();
 
-    let results =
-        tokio::task::spawn_blocking(move || -> Result<Vec<(u32, bool, usize)>, String> {
+    #[allow(clippy::type_complexity)]
+    let results = tokio::task::spawn_blocking(
+        move || -> Result<Vec<(u32, bool, usize, Option<MessageSecurity>)>, String> {
             // Connect to IMAP
             let mut imap_session = imap_helpers::connect_and_login(&config_clone)
                 .map_err(|e| format!("Failed to connect to IMAP: {}", e))?;
@@ -131,6 +108,7 @@ pub async fn fetch_bodystructure_background(
                     println!("⏹️ Background BODYSTRUCTURE fetch cancelled");
                     break;
                 }
+                heartbeat.beat();
 
                 let batch_num = batch_idx + 1;
 
@@ -164,7 +142,8 @@ pub async fn fetch_bodystructure_background(
                         for msg in messages.iter() {
                             if let Some(uid) = msg.uid {
                                 let (has_attachments, attachment_count) = check_attachments(msg);
-                                results.push((uid, has_attachments, attachment_count));
+                                let security = msg.bodystructure().and_then(detect_security);
+                                results.push((uid, has_attachments, attachment_count, security));
                             }
                         }
 
@@ -234,6 +213,7 @@ pub async fn fetch_bodystructure_background(
                         println!("⏹️ Background BODYSTRUCTURE fetch cancelled during retry");
                         break;
                     }
+                    heartbeat.beat();
 
                     match imap_session.uid_fetch(uid.to_string(), "(UID BODYSTRUCTURE)") {
                         Ok(messages) => {
@@ -241,7 +221,13 @@ pub async fn fetch_bodystructure_background(
                                 if let Some(uid) = msg.uid {
                                     let (has_attachments, attachment_count) =
                                         check_attachments(msg);
-                                    results.push((uid, has_attachments, attachment_count));
+                                    let security = msg.bodystructure().and_then(detect_security);
+                                    results.push((
+                                        uid,
+                                        has_attachments,
+                                        attachment_count,
+                                        security,
+                                    ));
                                 }
                             }
                         }
@@ -276,7 +262,7 @@ pub async fn fetch_bodystructure_background(
                                     "  ℹ️ Marking UID {} as no attachments due to persistent error",
                                     uid
                                 );
-                                results.push((uid as u32, false, 0));
+                                results.push((uid as u32, false, 0, None));
                             }
                         }
                     }
@@ -287,18 +273,20 @@ pub async fn fetch_bodystructure_background(
             }
 
             Ok(results)
-        })
-        .await
-        .map_err(|e| format!("Background task panicked: {}", e))??;
+        },
+    )
+    .await
+    .map_err(|e| format!("Background task panicked: {}", e))??;
 
     // Update database with results
     let mut updated_count = 0;
-    for (uid, has_attachments, _attachment_count) in results {
+    for (uid, has_attachments, _attachment_count, security) in results {
         let result = sqlx::query(
-            "UPDATE emails SET has_attachments = ? 
+            "UPDATE emails SET has_attachments = ?, security = COALESCE(security, ?)
              WHERE account_id = ? AND folder_name = ? AND uid = ?",
         )
         .bind(has_attachments as i64)
+        .bind(security.and_then(|security| serde_json::to_string(&security).ok()))
         .bind(account_id)
         .bind(&folder_name)
         .bind(uid as i64)
@@ -318,6 +306,143 @@ pub async fn fetch_bodystructure_background(
     Ok(())
 }
 
+/// Fetch BODYSTRUCTURE right away for the given (usually visible) emails
+///
+/// The background task works newest-first through the whole backlog; this lets the list
+/// resolve attachment icons for what the user is looking at without waiting for it.
+/// UIDs that already have attachment info are skipped. Returns the resolved emails.
+#[command]
+pub async fn prioritize_bodystructure(
+    account_id: i64,
+    folder_name: String,
+    uids: Vec<u32>,
+) -> Result<Vec<AttachmentInfo>, String> {
+    let pool = db::pool();
+
+    let mut pending_uids = Vec::new();
+    for uid in uids.into_iter().take(MAX_PRIORITY_UIDS) {
+        let pending = sqlx::query_as::<_, (i64,)>(
+            "SELECT 1 FROM emails
+             WHERE account_id = ? AND folder_name = ? AND uid = ? AND has_attachments IS NULL",
+        )
+        .bind(account_id)
+        .bind(&folder_name)
+        .bind(uid as i64)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to check attachment info: {}", e))?
+        .is_some();
+
+        if pending {
+            pending_uids.push(uid);
+        }
+    }
+
+    if pending_uids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let config = ensure_valid_token(load_account_config(account_id).await?).await?;
+    let folder_name_clone = folder_name.clone();
+
+    let results = tokio::task::spawn_blocking(move || -> Result<Vec<AttachmentInfo>, String> {
+        let mut imap_session = reconnect(&config, &folder_name_clone)?;
+
+        let uid_list = pending_uids
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let messages = imap_session
+            .uid_fetch(&uid_list, "(UID BODYSTRUCTURE)")
+            .map_err(|e| format!("Failed to fetch BODYSTRUCTURE: {}", e))?;
+
+        let results = messages
+            .iter()
+            .filter_map(|msg| {
+                let uid = msg.uid?;
+                let (has_attachments, attachment_count) = check_attachments(msg);
+                Some(AttachmentInfo {
+                    uid,
+                    has_attachments,
+                    attachment_count,
+                    security: msg.bodystructure().and_then(detect_security),
+                })
+            })
+            .collect();
+
+        let _ = imap_session.logout();
+        Ok(results)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+
+    for info in &results {
+        sqlx::query(
+            "UPDATE emails SET has_attachments = ?, security = COALESCE(security, ?)
+             WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(info.has_attachments as i64)
+        .bind(
+            info.security
+                .as_ref()
+                .and_then(|security| serde_json::to_string(security).ok()),
+        )
+        .bind(account_id)
+        .bind(&folder_name)
+        .bind(info.uid as i64)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to update attachment info: {}", e))?;
+    }
+
+    println!(
+        "⚡ Prioritized BODYSTRUCTURE fetch resolved {} emails in '{}'",
+        results.len(),
+        folder_name
+    );
+    Ok(results)
+}
+
+/// Load an account's config with credentials from the keyring
+pub(crate) async fn load_account_config(account_id: i64) -> Result<AccountConfig, String> {
+    let pool = db::pool();
+
+    let (id, email, imap_server, imap_port, smtp_server, smtp_port, auth_type) =
+        sqlx::query_as::<_, (i64, String, String, i64, String, i64, String)>(
+            "SELECT id, email, imap_server, imap_port, smtp_server, smtp_port, auth_type FROM accounts WHERE id = ?"
+        )
+        .bind(account_id)
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Account {} not found: {}", account_id, e))?;
+
+    let auth_type_enum = match auth_type.as_str() {
+        "oauth2" => Some(AuthType::OAuth2),
+        _ => Some(AuthType::Basic),
+    };
+
+    // Load sensitive credentials from keyring
+    let namespace = security::account_namespace(id as i32).await?;
+    let creds = security::get_credentials(&namespace, &email)
+        .map_err(|e| format!("Failed to load credentials for {}: {}", email, e))?;
+
+    Ok(AccountConfig {
+        id: Some(id as i32),
+        email,
+        password: creds.password,
+        imap_server,
+        imap_port: imap_port as u16,
+        smtp_server,
+        smtp_port: smtp_port as u16,
+        auth_type: auth_type_enum,
+        access_token: creds.access_token,
+        refresh_token: creds.refresh_token,
+        token_expires_at: creds.token_expires_at,
+        display_name: None,
+    })
+}
+
 /// Reconnect to IMAP server and select folder
 fn reconnect(
     config: &AccountConfig,
@@ -1,6 +1,7 @@
 // Email flag operations (mark as read/unread)
 // This module handles setting IMAP flags and syncing with the server
 
+use crate::commands::emails::fetch_bodystructure::load_account_config;
 use crate::commands::emails::imap_helpers;
 use crate::commands::utils::ensure_valid_token;
 use crate::db;
@@ -230,3 +231,98 @@ pub async fn mark_email_as_unflagged(
     println!("✅ Marked email UID {} as unflagged/unstarred", uid);
     Ok(())
 }
+
+/// Number of UIDs per STORE when `UID STORE 1:*` is refused and unread UIDs are set in chunks
+const MARK_READ_CHUNK_SIZE: usize = 500;
+
+/// Mark every email in a folder as read with one STORE on the server and one cache update
+///
+/// Returns the number of cached emails that were unread.
+#[command]
+pub async fn mark_folder_as_read(account_id: i32, folder: String) -> Result<u64, String> {
+    println!("Marking all emails in folder {} as read", folder);
+    let pool = db::pool();
+
+    let is_local = sqlx::query_as::<_, (i64,)>(
+        "SELECT COALESCE(is_local, 0) FROM folders WHERE account_id = ? AND name = ?",
+    )
+    .bind(account_id)
+    .bind(&folder)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to look up folder: {}", e))?
+    .is_some_and(|(is_local,)| is_local != 0);
+
+    if !is_local {
+        let config = load_account_config(account_id as i64).await?;
+        let config = ensure_valid_token(config).await?;
+        let folder_name = folder.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<(), String> {
+            let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+            let mailbox = imap_session
+                .select(&folder_name)
+                .map_err(|e| format!("Cannot access folder '{}': {}", folder_name, e))?;
+            if mailbox.exists == 0 {
+                let _ = imap_session.logout();
+                return Ok(());
+            }
+
+            if let Err(e) = imap_session.uid_store("1:*", "+FLAGS.SILENT (\\Seen)") {
+                // Some servers limit how many messages one STORE may touch
+                eprintln!(
+                    "⚠️ UID STORE 1:* failed ({}), marking unread UIDs in chunks",
+                    e
+                );
+                let mut unseen: Vec<u32> = imap_session
+                    .uid_search("UNSEEN")
+                    .map_err(|e| format!("Failed to search unread emails: {}", e))?
+                    .into_iter()
+                    .collect();
+                unseen.sort_unstable();
+
+                for chunk in unseen.chunks(MARK_READ_CHUNK_SIZE) {
+                    let uid_list = chunk
+                        .iter()
+                        .map(|uid| uid.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    imap_session
+                        .uid_store(&uid_list, "+FLAGS.SILENT (\\Seen)")
+                        .map_err(|e| format!("Failed to set \\Seen flag: {}", e))?;
+                }
+            }
+
+            let _ = imap_session.logout();
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+    }
+
+    // Update local cache and the folder's unread count
+    let result = sqlx::query(
+        "UPDATE emails SET seen = 1
+         WHERE account_id = ? AND folder_name = ? AND COALESCE(seen, 0) = 0",
+    )
+    .bind(account_id)
+    .bind(&folder)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to update local cache: {}", e))?;
+
+    sqlx::query("UPDATE folders SET unread_count = 0 WHERE account_id = ? AND name = ?")
+        .bind(account_id)
+        .bind(&folder)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to update unread count: {}", e))?;
+
+    println!(
+        "✅ Marked {} emails in folder {} as read",
+        result.rows_affected(),
+        folder
+    );
+    Ok(result.rows_affected())
+}
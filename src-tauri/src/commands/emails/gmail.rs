@@ -0,0 +1,279 @@
+// Gmail IMAP extensions
+// On servers advertising X-GM-EXT-1, flag sync also fetches each message's Gmail thread ID
+// (X-GM-THRID) and labels (X-GM-LABELS) and stores them in the cache. `search_gmail_raw`
+// passes a query straight to Gmail's own search engine through X-GM-RAW, so operators like
+// `has:attachment`, `older_than:1y` or `label:work` work as they do in the Gmail web UI.
+
+use crate::commands::emails::imap_helpers;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::AccountConfig;
+use tauri::command;
+
+type ImapSession = imap::Session<Box<dyn imap::ImapConnection>>;
+
+/// FETCH items for Gmail metadata
+const GMAIL_FETCH_ITEMS: &str = "(UID X-GM-THRID X-GM-LABELS)";
+
+/// Gmail thread ID and labels of one message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GmailMetadata {
+    pub uid: u32,
+    pub thread_id: Option<String>,
+    pub labels: Vec<String>,
+}
+
+/// Whether the server is Gmail (or speaks its extensions)
+pub fn supports_gmail_extensions(imap_session: &mut ImapSession) -> bool {
+    imap_session
+        .capabilities()
+        .map(|caps| caps.has_str("X-GM-EXT-1"))
+        .unwrap_or(false)
+}
+
+/// A parsed element of a FETCH response: an atom, string or literal, or a list
+#[derive(Debug)]
+enum Item {
+    Atom(String),
+    List(Vec<Item>),
+}
+
+/// Parse the element at the start of `input`, returning it and the remaining input
+fn parse_item(input: &str) -> Option<(Item, &str)> {
+    let input = input.trim_start_matches(' ');
+    match input.chars().next()? {
+        '(' => {
+            let mut items = Vec::new();
+            let mut rest = &input[1..];
+            loop {
+                rest = rest.trim_start_matches(' ');
+                if let Some(after) = rest.strip_prefix(')') {
+                    return Some((Item::List(items), after));
+                }
+                let (item, after) = parse_item(rest)?;
+                items.push(item);
+                rest = after;
+            }
+        }
+        '"' => {
+            let mut value = String::new();
+            let mut escaped = false;
+            for (i, c) in input.char_indices().skip(1) {
+                match (escaped, c) {
+                    (false, '\\') => escaped = true,
+                    (false, '"') => return Some((Item::Atom(value), &input[i + 1..])),
+                    (_, c) => {
+                        value.push(c);
+                        escaped = false;
+                    }
+                }
+            }
+            None
+        }
+        '{' => {
+            let close = input.find('}')?;
+            let len: usize = input[1..close].trim_end_matches('+').parse().ok()?;
+            let body = input[close + 1..].strip_prefix("\r\n")?;
+            let value = body.get(..len)?;
+            Some((Item::Atom(value.to_string()), &body[len..]))
+        }
+        ')' | '\r' | '\n' => None,
+        _ => {
+            let end = input
+                .find([' ', '(', ')', '\r', '\n'])
+                .unwrap_or(input.len());
+            Some((Item::Atom(input[..end].to_string()), &input[end..]))
+        }
+    }
+}
+
+/// Parse the raw response of a `UID FETCH ... (UID X-GM-THRID X-GM-LABELS)`
+///
+/// The imap crate doesn't know these attributes, so the untagged FETCH lines are read here.
+/// Labels are decoded from modified UTF-7.
+pub fn parse_gmail_fetch(raw: &[u8]) -> Vec<GmailMetadata> {
+    let text = String::from_utf8_lossy(raw);
+    let mut results = Vec::new();
+    let mut rest: &str = &text;
+
+    while let Some(pos) = rest.find(" FETCH (") {
+        let Some((Item::List(items), after)) = parse_item(&rest[pos + 7..]) else {
+            rest = &rest[pos + 8..];
+            continue;
+        };
+        rest = after;
+
+        let mut uid = None;
+        let mut thread_id = None;
+        let mut labels = Vec::new();
+        for pair in items.chunks(2) {
+            match pair {
+                [Item::Atom(key), Item::Atom(value)] if key.eq_ignore_ascii_case("UID") => {
+                    uid = value.parse::<u32>().ok();
+                }
+                [Item::Atom(key), Item::Atom(value)] if key.eq_ignore_ascii_case("X-GM-THRID") => {
+                    thread_id = Some(value.clone());
+                }
+                [Item::Atom(key), Item::List(values)]
+                    if key.eq_ignore_ascii_case("X-GM-LABELS") =>
+                {
+                    labels = values
+                        .iter()
+                        .filter_map(|value| match value {
+                            Item::Atom(label) => Some(utf7_imap::decode_utf7_imap(label.clone())),
+                            Item::List(_) => None,
+                        })
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(uid) = uid {
+            results.push(GmailMetadata {
+                uid,
+                thread_id,
+                labels,
+            });
+        }
+    }
+
+    results
+}
+
+/// Fetch Gmail metadata for a UID set, optionally only for messages changed since a MODSEQ
+/// The folder must already be selected
+pub fn fetch_gmail_metadata(
+    imap_session: &mut ImapSession,
+    uid_set: &str,
+    changed_since: Option<u64>,
+) -> Result<Vec<GmailMetadata>, String> {
+    let command = match changed_since {
+        Some(mod_seq) => format!(
+            "UID FETCH {} {} (CHANGEDSINCE {})",
+            uid_set, GMAIL_FETCH_ITEMS, mod_seq
+        ),
+        None => format!("UID FETCH {} {}", uid_set, GMAIL_FETCH_ITEMS),
+    };
+    let raw = imap_session
+        .run_command_and_read_response(command)
+        .map_err(|e| format!("Failed to fetch Gmail labels: {}", e))?;
+    Ok(parse_gmail_fetch(&raw))
+}
+
+/// Store fetched Gmail thread IDs and labels on the cached messages
+pub async fn save_gmail_metadata(
+    account_id: i32,
+    folder_name: &str,
+    metadata: &[GmailMetadata],
+) -> Result<(), String> {
+    let pool = db::pool();
+
+    for message in metadata {
+        let labels = serde_json::to_string(&message.labels).unwrap_or_default();
+        sqlx::query(
+            "UPDATE emails SET gmail_thread_id = ?, gmail_labels = ?
+             WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(&message.thread_id)
+        .bind(labels)
+        .bind(account_id)
+        .bind(folder_name)
+        .bind(message.uid as i64)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save Gmail labels: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Quote a search string; non-ASCII queries are sent with CHARSET UTF-8, which Gmail accepts
+fn gmail_raw_criteria(query: &str) -> Result<String, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    if query.chars().any(|c| c.is_control()) {
+        return Err("Search query can't contain line breaks or control characters".to_string());
+    }
+
+    let quoted = format!("\"{}\"", query.replace('\\', "\\\\").replace('"', "\\\""));
+    Ok(if query.is_ascii() {
+        format!("X-GM-RAW {}", quoted)
+    } else {
+        format!("CHARSET UTF-8 X-GM-RAW {}", quoted)
+    })
+}
+
+/// Search a Gmail folder with Gmail's own query syntax (X-GM-RAW)
+/// Returns the matching UIDs, newest first. Use "[Gmail]/All Mail" to search every message.
+#[command]
+pub async fn search_gmail_raw(
+    config: AccountConfig,
+    query: String,
+    folder: Option<String>,
+) -> Result<Vec<u32>, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let criteria = gmail_raw_criteria(&query)?;
+    println!("🔍 Gmail search in {}: {}", folder_name, query);
+
+    let config = ensure_valid_token(config).await?;
+    let uids = tokio::task::spawn_blocking(move || -> Result<Vec<u32>, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+        if !supports_gmail_extensions(&mut imap_session) {
+            let _ = imap_session.logout();
+            return Err("This server doesn't support Gmail search".to_string());
+        }
+
+        imap_session
+            .select(&folder_name)
+            .map_err(|e| format!("Cannot access folder '{}': {}", folder_name, e))?;
+        let result = imap_session
+            .uid_search(&criteria)
+            .map_err(|e| format!("Gmail search failed: {}", e));
+
+        let _ = imap_session.logout();
+        let mut uids: Vec<u32> = result?.into_iter().collect();
+        uids.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(uids)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    println!("✅ Gmail search found {} messages", uids.len());
+    Ok(uids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gmail_fetch() {
+        let raw = b"* 1 FETCH (X-GM-THRID 1278455344230334865 X-GM-LABELS (\\Inbox \"Work stuff\" &AOk-t&AOk-) UID 42)\r\n\
+                    * 2 FETCH (UID 43 X-GM-THRID 1278455344230334866 X-GM-LABELS ())\r\n\
+                    A3 OK Success\r\n";
+
+        assert_eq!(
+            parse_gmail_fetch(raw),
+            vec![
+                GmailMetadata {
+                    uid: 42,
+                    thread_id: Some("1278455344230334865".to_string()),
+                    labels: vec![
+                        "\\Inbox".to_string(),
+                        "Work stuff".to_string(),
+                        "été".to_string()
+                    ],
+                },
+                GmailMetadata {
+                    uid: 43,
+                    thread_id: Some("1278455344230334866".to_string()),
+                    labels: Vec::new(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+// Raw header search
+// Searches the raw headers kept during sync (List-Id, X-Mailer, Return-Path,
+// Authentication-Results, Received IPs and Delivered-To) with `operator:value` terms, e.g.
+// `list-id:newsletter.example.com ip:192.0.2.1 auth:"dkim=fail"`. Terms are combined with AND;
+// a term without operator matches any stored header. Values match case-insensitively as
+// substrings.
+
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Maximum number of results returned
+const MAX_RESULTS: i64 = 500;
+
+/// An email matching a header search
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HeaderSearchResult {
+    pub folder_name: String,
+    pub uid: u32,
+    pub subject: String,
+    pub from: String,
+    pub date: String,
+    pub timestamp: i64,
+    pub headers: Vec<(String, String)>, // All stored raw headers of the email
+}
+
+/// One `operator:value` term; `header` is None for terms without operator
+#[derive(Debug, PartialEq, Eq)]
+struct SearchTerm {
+    header: Option<&'static str>,
+    value: String,
+}
+
+/// Map a search operator to the stored header name
+fn header_for_operator(operator: &str) -> Option<&'static str> {
+    match operator.to_ascii_lowercase().as_str() {
+        "list-id" | "list" => Some("list-id"),
+        "x-mailer" | "mailer" => Some("x-mailer"),
+        "return-path" => Some("return-path"),
+        "authentication-results" | "auth" => Some("authentication-results"),
+        "received-ip" | "ip" => Some("received-ip"),
+        "delivered-to" => Some("delivered-to"),
+        _ => None,
+    }
+}
+
+/// Split a query into terms, honoring double quotes in values
+fn parse_query(query: &str) -> Result<Vec<SearchTerm>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+        .into_iter()
+        .map(|token| match token.split_once(':') {
+            Some((operator, value)) if !operator.is_empty() => {
+                match header_for_operator(operator) {
+                    Some(header) => Ok(SearchTerm {
+                        header: Some(header),
+                        value: value.to_string(),
+                    }),
+                    // Only fail for things that look like operators, not for values like "a:b"
+                    None if operator
+                        .chars()
+                        .all(|c| c.is_ascii_alphabetic() || c == '-') =>
+                    {
+                        Err(format!("Unknown search operator '{}'", operator))
+                    }
+                    None => Ok(SearchTerm {
+                        header: None,
+                        value: token,
+                    }),
+                }
+            }
+            _ => Ok(SearchTerm {
+                header: None,
+                value: token,
+            }),
+        })
+        .filter(|term| !matches!(term, Ok(term) if term.value.is_empty()))
+        .collect()
+}
+
+/// Escape LIKE wildcards; used with `ESCAPE '\'`
+fn like_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped.to_lowercase())
+}
+
+/// Search cached emails of an account by their raw headers
+#[command]
+pub async fn search_emails_by_header(
+    account_id: i32,
+    query: String,
+    folder: Option<String>,
+) -> Result<Vec<HeaderSearchResult>, String> {
+    let terms = parse_query(&query)?;
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut sql = String::from(
+        "SELECT e.id, e.folder_name, e.uid, e.subject, e.from_addr, e.date, e.timestamp
+         FROM emails e WHERE e.account_id = ?",
+    );
+    if folder.is_some() {
+        sql.push_str(" AND e.folder_name = ?");
+    }
+    for term in &terms {
+        sql.push_str(" AND EXISTS (SELECT 1 FROM email_raw_headers h WHERE h.email_id = e.id");
+        if term.header.is_some() {
+            sql.push_str(" AND h.name = ?");
+        }
+        sql.push_str(" AND lower(h.value) LIKE ? ESCAPE '\\')");
+    }
+    sql.push_str(" ORDER BY e.timestamp DESC LIMIT ?");
+
+    let mut db_query =
+        sqlx::query_as::<_, (i64, String, i64, String, String, String, i64)>(&sql).bind(account_id);
+    if let Some(folder) = &folder {
+        db_query = db_query.bind(folder);
+    }
+    for term in &terms {
+        if let Some(header) = term.header {
+            db_query = db_query.bind(header);
+        }
+        db_query = db_query.bind(like_pattern(&term.value));
+    }
+
+    let pool = db::pool();
+    let rows = db_query
+        .bind(MAX_RESULTS)
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to search headers: {}", e))?;
+
+    let decrypt_subject = is_encryption_enabled().await? && is_encryption_unlocked();
+
+    let mut results = Vec::with_capacity(rows.len());
+    for (id, folder_name, uid, subject, from, date, timestamp) in rows {
+        let headers = sqlx::query_as::<_, (String, String)>(
+            "SELECT name, value FROM email_raw_headers WHERE email_id = ? ORDER BY id",
+        )
+        .bind(id)
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load raw headers: {}", e))?;
+
+        results.push(HeaderSearchResult {
+            folder_name,
+            uid: uid as u32,
+            subject: if decrypt_subject {
+                decrypt(&subject).unwrap_or_default()
+            } else {
+                subject
+            },
+            from,
+            date,
+            timestamp,
+            headers,
+        });
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_operators_and_quotes() {
+        let terms = parse_query(r#"list-id:news.example.com auth:"dkim=fail" 192.0.2.1"#).unwrap();
+        assert_eq!(
+            terms,
+            vec![
+                SearchTerm {
+                    header: Some("list-id"),
+                    value: "news.example.com".to_string()
+                },
+                SearchTerm {
+                    header: Some("authentication-results"),
+                    value: "dkim=fail".to_string()
+                },
+                SearchTerm {
+                    header: None,
+                    value: "192.0.2.1".to_string()
+                },
+            ]
+        );
+        assert!(parse_query("subject:hello").is_err());
+    }
+}
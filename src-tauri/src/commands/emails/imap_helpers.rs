@@ -15,14 +15,17 @@ pub fn connect_and_login(
 
     println!("🔌 Connecting to {}:{}", domain, port);
 
-    // Use ClientBuilder::new().connect() as per imap 3.0.0 API
-    let client = imap::ClientBuilder::new(domain, port)
-        .connect()
-        .map_err(|e| format!("Failed to connect to IMAP server: {}", e))?;
+    // Races IPv6/IPv4 addresses, then TLS (port 993) or STARTTLS
+    let client = crate::metrics::time("imap_connect_ms", || {
+        crate::net::connect_imap(domain, port, config.id)
+    })
+    .map_err(|e| format!("Failed to connect to IMAP server: {}", e))?;
 
     println!("✅ Connected successfully");
+    crate::faults::imap_login()?;
 
     // Authenticate based on auth type
+    let login_started = std::time::Instant::now();
     let imap_session = match &config.auth_type {
         Some(AuthType::OAuth2) => {
             let access_token = config
@@ -55,6 +58,7 @@ pub fn connect_and_login(
         }
     };
 
+    crate::metrics::record_duration("imap_login_ms", login_started.elapsed());
     println!("✅ Authentication successful");
 
     // Send IMAP ID command after authentication for providers that require it (like 163.com)
@@ -116,3 +120,76 @@ fn send_imap_id(session: &mut imap::Session<Box<dyn imap::ImapConnection>>) -> R
         Err(e) => Err(format!("Failed to send IMAP ID: {}", e)),
     }
 }
+
+/// Quote a mailbox name for use in a raw IMAP command
+///
+/// Cached folder names are already in modified UTF-7; a name with non-ASCII characters isn't,
+/// so it is encoded first.
+pub fn quote_mailbox(name: &str) -> String {
+    let name = if name.is_ascii() {
+        name.to_string()
+    } else {
+        crate::commands::folders::encode_folder_name(name)
+    };
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Refuse a plain EXPUNGE of `uids` if other messages in the selected folder are marked \Deleted
+///
+/// Without UIDPLUS a plain EXPUNGE is the only way to remove messages, and it also removes
+/// whatever other clients marked \Deleted, so it is only used when nothing else is marked.
+pub fn ensure_plain_expunge_safe(
+    session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
+    uids: &[u32],
+) -> Result<(), String> {
+    let marked = session
+        .uid_search("DELETED")
+        .map_err(|e| format!("Failed to search deleted messages: {}", e))?;
+    if marked.iter().any(|uid| !uids.contains(uid)) {
+        return Err(
+            "Other messages in this folder are marked as deleted and the server can't \
+             expunge selectively (no UIDPLUS); expunge the folder first"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// IMAP sequence set for a list of UIDs, with consecutive runs collapsed (`3,5:8,12`)
+pub fn uid_set(uids: &[u32]) -> String {
+    let mut sorted = uids.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for uid in sorted {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == uid => *end = uid,
+            _ => ranges.push((uid, uid)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}:{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uid_set() {
+        assert_eq!(uid_set(&[12, 5, 6, 7, 8, 3, 6]), "3,5:8,12");
+        assert_eq!(uid_set(&[42]), "42");
+        assert_eq!(uid_set(&[]), "");
+    }
+}
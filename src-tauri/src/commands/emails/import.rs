@@ -0,0 +1,379 @@
+// Mailbox import
+// Imports mbox files and Maildir directories exported by other clients (e.g. Thunderbird) into
+// a local folder. Read and flagged state is taken from the Status / X-Status /
+// X-Mozilla-Status headers (mbox) or the filename info (Maildir), so migrated mail keeps it.
+// Uploading the folder with `upload_folder_to_remote` then carries the state to the server
+// as APPEND flags.
+
+use crate::commands::emails::cache::{
+    save_attachments_to_cache, save_email_body_to_cache, save_emails_to_cache,
+};
+use crate::commands::emails::codec::{
+    decode_header, parse_email_date_with_fallback, parse_header_block,
+};
+use crate::commands::emails::fetch::parse_raw_message;
+use crate::commands::emails::sync::parse::extract_indexed_headers;
+use crate::db;
+use crate::models::EmailHeader;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Emitter};
+use tokio::io::AsyncBufReadExt;
+
+/// X-Mozilla-Status bits (see nsMsgMessageFlags)
+const MOZILLA_READ: u32 = 0x0001;
+const MOZILLA_MARKED: u32 = 0x0004;
+const MOZILLA_EXPUNGED: u32 = 0x0008;
+
+/// Outcome of an import
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub seen: usize,
+    pub flagged: usize,
+    pub skipped: usize, // Deleted or unparseable messages
+}
+
+/// Progress event emitted while importing
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportProgress {
+    pub folder_name: String,
+    pub imported: usize,
+}
+
+/// State carried over from the exporting client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ImportedFlags {
+    seen: bool,
+    flagged: bool,
+    deleted: bool,
+}
+
+/// Read/flagged/deleted state from mbox status headers
+///
+/// `Status: RO` marks read messages, `X-Status: F` flagged ones; Thunderbird keeps the same
+/// information as a hex bitmask in `X-Mozilla-Status`, which wins when present.
+fn flags_from_headers(headers: &[(String, String)]) -> ImportedFlags {
+    let mut flags = ImportedFlags::default();
+
+    for (name, value) in headers {
+        match name.to_ascii_lowercase().as_str() {
+            "status" => flags.seen |= value.contains('R'),
+            "x-status" => {
+                flags.flagged |= value.contains('F');
+                flags.deleted |= value.contains('D');
+            }
+            _ => {}
+        }
+    }
+
+    let mozilla = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("x-mozilla-status"))
+        .and_then(|(_, value)| u32::from_str_radix(value.trim(), 16).ok());
+    if let Some(bits) = mozilla {
+        flags.seen = bits & MOZILLA_READ != 0;
+        flags.flagged = bits & MOZILLA_MARKED != 0;
+        flags.deleted |= bits & MOZILLA_EXPUNGED != 0;
+    }
+
+    flags
+}
+
+/// Read/flagged/deleted state from a Maildir filename (`<unique>:2,<flags>`)
+///
+/// Messages still in `new/` have never been seen by a client.
+fn flags_from_maildir_name(file_name: &str, in_new: bool) -> ImportedFlags {
+    // Windows-friendly exports use '!' or ';' instead of ':'
+    let info = [":2,", "!2,", ";2,"]
+        .iter()
+        .find_map(|separator| file_name.rsplit_once(separator).map(|(_, info)| info))
+        .unwrap_or("");
+
+    ImportedFlags {
+        seen: !in_new && info.contains('S'),
+        flagged: info.contains('F'),
+        deleted: info.contains('T'),
+    }
+}
+
+/// Import an mbox file or Maildir directory into a local folder
+///
+/// `folder_name` must be an existing local folder of the account. Messages get UIDs after
+/// the folder's current highest UID. Deleted messages are skipped. Emits `import-progress`.
+#[command]
+pub async fn import_mailbox(
+    app_handle: AppHandle,
+    account_id: i32,
+    path: String,
+    folder_name: String,
+) -> Result<ImportSummary, String> {
+    let pool = db::pool();
+
+    let is_local = sqlx::query_as::<_, (i64,)>(
+        "SELECT COALESCE(is_local, 0) FROM folders WHERE account_id = ? AND name = ?",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to look up folder: {}", e))?;
+    if is_local != Some((1,)) {
+        return Err("Mail can only be imported into a local folder".to_string());
+    }
+
+    let (max_uid,) = sqlx::query_as::<_, (i64,)>(
+        "SELECT COALESCE(MAX(uid), 0) FROM emails WHERE account_id = ? AND folder_name = ?",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to read highest UID: {}", e))?;
+
+    let mut importer = Importer {
+        app_handle,
+        account_id,
+        folder_name,
+        next_uid: max_uid as u32 + 1,
+        summary: ImportSummary::default(),
+    };
+
+    let path = PathBuf::from(path);
+    if path.join("cur").is_dir() || path.join("new").is_dir() {
+        println!("📥 Importing Maildir {}", path.display());
+        import_maildir(&mut importer, &path).await?;
+    } else {
+        println!("📥 Importing mbox {}", path.display());
+        import_mbox(&mut importer, &path).await?;
+    }
+
+    println!(
+        "✅ Imported {} messages into '{}' ({} read, {} flagged, {} skipped)",
+        importer.summary.imported,
+        importer.folder_name,
+        importer.summary.seen,
+        importer.summary.flagged,
+        importer.summary.skipped
+    );
+    Ok(importer.summary)
+}
+
+struct Importer {
+    app_handle: AppHandle,
+    account_id: i32,
+    folder_name: String,
+    next_uid: u32,
+    summary: ImportSummary,
+}
+
+impl Importer {
+    /// Store one raw message with the given state
+    async fn add(&mut self, raw: &[u8], flags: ImportedFlags) -> Result<(), String> {
+        if flags.deleted || raw.iter().all(|byte| byte.is_ascii_whitespace()) {
+            self.summary.skipped += 1;
+            return Ok(());
+        }
+
+        let headers = parse_header_block(raw);
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+
+        let (body, attachments) = match parse_raw_message(raw) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("⚠️ Skipping unparseable message: {}", e);
+                self.summary.skipped += 1;
+                return Ok(());
+            }
+        };
+
+        let date = header("date").unwrap_or_default().to_string();
+        let email = EmailHeader {
+            uid: self.next_uid,
+            subject: decode_header(header("subject").unwrap_or("(No Subject)")),
+            from: decode_header(header("from").unwrap_or_default()),
+            to: decode_header(header("to").unwrap_or_default()),
+            cc: decode_header(header("cc").unwrap_or_default()),
+            bcc: decode_header(header("bcc").unwrap_or_default()),
+            timestamp: parse_email_date_with_fallback(&date, None),
+            date,
+            has_attachments: !attachments.is_empty(),
+            seen: flags.seen,
+            flagged: flags.flagged,
+            message_id: header("message-id").map(|id| id.to_string()),
+            received_at: None,
+            raw_headers: extract_indexed_headers(raw),
+            security: None,
+        };
+        self.next_uid += 1;
+
+        save_emails_to_cache(
+            self.account_id,
+            &self.folder_name,
+            std::slice::from_ref(&email),
+        )
+        .await?;
+
+        let pool = db::pool();
+        let (email_id,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT id FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(self.account_id)
+        .bind(&self.folder_name)
+        .bind(email.uid as i64)
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to get email id: {}", e))?;
+
+        if !attachments.is_empty() {
+            save_attachments_to_cache(email_id, &attachments).await?;
+        }
+
+        sqlx::query("UPDATE emails SET has_attachments = ? WHERE id = ?")
+            .bind(!attachments.is_empty() as i64)
+            .bind(email_id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to update attachment info: {}", e))?;
+
+        // After the attachments, so a Maildir copy of the message includes them
+        save_email_body_to_cache(self.account_id, &self.folder_name, email.uid, &body).await?;
+
+        self.summary.imported += 1;
+        self.summary.seen += flags.seen as usize;
+        self.summary.flagged += flags.flagged as usize;
+
+        if self.summary.imported % 100 == 0 {
+            let _ = self.app_handle.emit(
+                "import-progress",
+                ImportProgress {
+                    folder_name: self.folder_name.clone(),
+                    imported: self.summary.imported,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Read an mbox file message by message (mboxrd `>From ` quoting is undone)
+async fn import_mbox(importer: &mut Importer, path: &Path) -> Result<(), String> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open mbox file: {}", e))?;
+    let mut reader = tokio::io::BufReader::new(file);
+
+    let mut message: Vec<u8> = Vec::new();
+    let mut line: Vec<u8> = Vec::new();
+    let mut started = false;
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .await
+            .map_err(|e| format!("Failed to read mbox file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+
+        if line.starts_with(b"From ") {
+            if started {
+                let headers = parse_header_block(&message);
+                importer.add(&message, flags_from_headers(&headers)).await?;
+                message.clear();
+            }
+            started = true;
+            continue;
+        }
+        if !started {
+            continue;
+        }
+
+        let quote_depth = line.iter().take_while(|&&byte| byte == b'>').count();
+        if quote_depth > 0 && line[quote_depth..].starts_with(b"From ") {
+            message.extend_from_slice(&line[1..]);
+        } else {
+            message.extend_from_slice(&line);
+        }
+    }
+
+    if started {
+        let headers = parse_header_block(&message);
+        importer.add(&message, flags_from_headers(&headers)).await?;
+    }
+    Ok(())
+}
+
+/// Import every message in the `cur/` and `new/` subdirectories of a Maildir
+async fn import_maildir(importer: &mut Importer, path: &Path) -> Result<(), String> {
+    for (subdir, in_new) in [("cur", false), ("new", true)] {
+        let Ok(entries) = std::fs::read_dir(path.join(subdir)) else {
+            continue;
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect();
+        // Maildir names start with the delivery time, so this keeps arrival order
+        files.sort();
+
+        for file in files {
+            let raw = tokio::fs::read(&file)
+                .await
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+            let file_name = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            importer
+                .add(&raw, flags_from_maildir_name(&file_name, in_new))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_flags_from_mbox_headers() {
+        let flags = flags_from_headers(&headers(&[("Status", "RO"), ("X-Status", "F")]));
+        assert!(flags.seen && flags.flagged && !flags.deleted);
+
+        // X-Mozilla-Status overrides Status
+        let flags = flags_from_headers(&headers(&[("Status", "RO"), ("X-Mozilla-Status", "0004")]));
+        assert!(!flags.seen && flags.flagged);
+
+        let flags = flags_from_headers(&headers(&[("X-Mozilla-Status", "0009")]));
+        assert!(flags.seen && flags.deleted);
+    }
+
+    #[test]
+    fn test_flags_from_maildir_name() {
+        let flags = flags_from_maildir_name("1700000000.M1P2.host:2,FS", false);
+        assert!(flags.seen && flags.flagged && !flags.deleted);
+        assert!(flags_from_maildir_name("1700000000.M1P2.host!2,ST", false).deleted);
+        assert!(!flags_from_maildir_name("1700000000.M1P2.host:2,S", true).seen);
+        assert_eq!(
+            flags_from_maildir_name("1700000000.M1P2.host", false),
+            ImportedFlags::default()
+        );
+    }
+}
@@ -0,0 +1,196 @@
+// Jump to a message by Message-ID
+// Finds where a message lives from its Message-ID, so notifications, reminders and
+// `colimail://message/<id>` or `mid:` links can open it. The cache of every account is
+// searched first; if the message isn't cached, the server can be asked with
+// `UID SEARCH HEADER Message-ID` in each folder of the account(s).
+
+use crate::commands::accounts::load_account_configs;
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::emails::imap_helpers;
+use crate::commands::folders::load_folders;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Where a message was found
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailLocation {
+    pub account_id: i32,
+    pub folder_name: String,
+    pub uid: u32,
+    pub subject: Option<String>, // None for messages only found on the server
+    pub cached: bool,
+}
+
+/// Bare Message-ID (without angle brackets) from an ID, `mid:` URL or deep link
+fn normalize_message_id(input: &str) -> Option<String> {
+    let mut id = input.trim();
+    for prefix in ["colimail://message/", "mid:"] {
+        if id
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+        {
+            id = &id[prefix.len()..];
+        }
+    }
+
+    let decoded = urlencoding::decode(id)
+        .map(|id| id.into_owned())
+        .unwrap_or_else(|_| id.to_string());
+    let bare = decoded
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim();
+
+    // Quotes and backslashes never appear in a valid ID and would break the IMAP search string
+    if bare.is_empty() || bare.contains(['"', '\\', '\r', '\n']) {
+        return None;
+    }
+    Some(bare.to_string())
+}
+
+async fn locate_in_cache(
+    message_id: &str,
+    account_id: Option<i32>,
+) -> Result<Vec<EmailLocation>, String> {
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (i32, String, i64, String)>(
+        "SELECT account_id, folder_name, uid, subject FROM emails
+         WHERE message_id IN (?1, ?2) AND (?3 IS NULL OR account_id = ?3)
+         ORDER BY account_id, folder_name",
+    )
+    .bind(format!("<{}>", message_id))
+    .bind(message_id)
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to search cache: {}", e))?;
+
+    let decrypt_subject = is_encryption_enabled().await? && is_encryption_unlocked();
+    Ok(rows
+        .into_iter()
+        .map(|(account_id, folder_name, uid, subject)| EmailLocation {
+            account_id,
+            folder_name,
+            uid: uid as u32,
+            subject: Some(if decrypt_subject {
+                decrypt(&subject).unwrap_or_default()
+            } else {
+                subject
+            }),
+            cached: true,
+        })
+        .collect())
+}
+
+async fn locate_on_server(
+    message_id: &str,
+    account_id: Option<i32>,
+) -> Result<Vec<EmailLocation>, String> {
+    let accounts = load_account_configs()
+        .await?
+        .into_iter()
+        .filter(|account| account_id.is_none() || account.id == account_id)
+        .filter(|account| !crate::commands::demo::is_demo_account(account));
+
+    let mut locations = Vec::new();
+    for config in accounts {
+        let Some(id) = config.id else {
+            continue;
+        };
+        let folders: Vec<String> = load_folders(id)
+            .await?
+            .into_iter()
+            .filter(|folder| !folder.is_local && folder.is_selectable())
+            .map(|folder| folder.name)
+            .collect();
+
+        let config = match ensure_valid_token(config).await {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("⚠️ Skipping account {} in message lookup: {}", id, e);
+                continue;
+            }
+        };
+        let criteria = format!("HEADER Message-ID \"{}\"", message_id);
+        let found = tokio::task::spawn_blocking(move || {
+            let mut session = imap_helpers::connect_and_login(&config)?;
+            let mut found = Vec::new();
+            for folder in folders {
+                if session.examine(&folder).is_err() {
+                    continue;
+                }
+                if let Ok(uids) = session.uid_search(&criteria) {
+                    found.extend(uids.into_iter().map(|uid| (folder.clone(), uid)));
+                }
+            }
+            let _ = session.logout();
+            Ok::<_, String>(found)
+        })
+        .await
+        .map_err(|e| format!("Message lookup task failed: {}", e))?;
+
+        match found {
+            Ok(found) => {
+                locations.extend(found.into_iter().map(|(folder_name, uid)| EmailLocation {
+                    account_id: id,
+                    folder_name,
+                    uid,
+                    subject: None,
+                    cached: false,
+                }))
+            }
+            Err(e) => eprintln!("⚠️ Message lookup failed for account {}: {}", id, e),
+        }
+    }
+
+    Ok(locations)
+}
+
+/// Find every copy of a message by its Message-ID, `mid:` URL or `colimail://message/` link
+///
+/// Only the cache is searched unless `search_server` is set; the server is asked only when
+/// the cache has no copy.
+#[command]
+pub async fn locate_email(
+    message_id: String,
+    account_id: Option<i32>,
+    search_server: Option<bool>,
+) -> Result<Vec<EmailLocation>, String> {
+    let message_id = normalize_message_id(&message_id)
+        .ok_or_else(|| format!("'{}' is not a valid Message-ID", message_id.trim()))?;
+
+    let locations = locate_in_cache(&message_id, account_id).await?;
+    if !locations.is_empty() || !search_server.unwrap_or(false) {
+        return Ok(locations);
+    }
+
+    println!("🔎 <{}> not cached, searching the server", message_id);
+    locate_on_server(&message_id, account_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_message_id() {
+        assert_eq!(
+            normalize_message_id(" <abc@example.com> ").as_deref(),
+            Some("abc@example.com")
+        );
+        assert_eq!(
+            normalize_message_id("mid:abc%40example.com").as_deref(),
+            Some("abc@example.com")
+        );
+        assert_eq!(
+            normalize_message_id("colimail://message/%3Cabc%40example.com%3E").as_deref(),
+            Some("abc@example.com")
+        );
+        assert_eq!(normalize_message_id("<a\"b@example.com>"), None);
+        assert_eq!(normalize_message_id("<>"), None);
+    }
+}
@@ -0,0 +1,466 @@
+// Maildir local store
+// Optionally keeps the messages of local folders as one file per message in a Maildir tree
+// (`<data dir>/maildir/<account id>/<folder>/{cur,new,tmp}`) instead of body blobs in SQLite,
+// so external indexing and backup tools can read them. SQLite still holds headers, flags and
+// attachments. Not available together with at-rest encryption, since the files are plaintext.
+// Folder names are percent-encoded into directory names, so every folder gets its own directory.
+
+use crate::commands::emails::append::build_raw_message;
+use crate::commands::emails::attachments::load_email_attachments;
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::emails::fetch::parse_raw_message;
+use crate::db;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+const SETTING_KEY: &str = "local_store_format";
+
+/// Where bodies of local folders are kept
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalStoreFormat {
+    Sqlite,
+    Maildir,
+}
+
+/// Result of moving local folders between stores
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LocalStoreMigration {
+    pub format: LocalStoreFormat,
+    pub migrated: usize,
+}
+
+/// Root of the Maildir tree
+pub fn maildir_root() -> PathBuf {
+    db::db_path()
+        .parent()
+        .map(|dir| dir.join("maildir"))
+        .unwrap_or_else(|| PathBuf::from("maildir"))
+}
+
+/// Directory name of a folder: every byte outside `[A-Za-z0-9_-]` is percent-encoded, so two
+/// folders never share a directory and no name can be `.`, `..` or contain a separator
+fn dir_name(folder_name: &str) -> String {
+    folder_name
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' => (byte as char).to_string(),
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Directory name the first Maildir version used; several folders could map to the same one
+fn legacy_dir_name(folder_name: &str) -> String {
+    let safe_name: String = folder_name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+    safe_name.trim_start_matches('.').to_string()
+}
+
+/// Maildir of one folder
+fn folder_dir(account_id: i32, folder_name: &str) -> Result<PathBuf, String> {
+    if folder_name.is_empty() {
+        return Err("Folder name is empty".to_string());
+    }
+    Ok(maildir_root()
+        .join(account_id.to_string())
+        .join(dir_name(folder_name)))
+}
+
+/// Move folders stored under the first Maildir version's directory names to their current ones
+///
+/// Directories that several local folders mapped to are left alone and reported, since their
+/// files can't be told apart.
+pub(crate) async fn migrate_legacy_dirs() -> Result<(), String> {
+    if local_store_format().await != LocalStoreFormat::Maildir {
+        return Ok(());
+    }
+
+    let pool = db::pool();
+    let folders = sqlx::query_as::<_, (i32, String)>(
+        "SELECT account_id, name FROM folders WHERE COALESCE(is_local, 0) = 1",
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load local folders: {}", e))?;
+
+    let mut by_legacy_dir: HashMap<(i32, String), Vec<String>> = HashMap::new();
+    for (account_id, name) in folders {
+        let legacy = legacy_dir_name(&name);
+        if !legacy.is_empty() && legacy != dir_name(&name) {
+            by_legacy_dir
+                .entry((account_id, legacy))
+                .or_default()
+                .push(name);
+        }
+    }
+
+    for ((account_id, legacy), names) in by_legacy_dir {
+        let legacy_dir = maildir_root().join(account_id.to_string()).join(&legacy);
+        if !legacy_dir.is_dir() {
+            continue;
+        }
+        if names.len() > 1 {
+            eprintln!(
+                "⚠️ Maildir {} is shared by folders {:?}; leaving it in place",
+                legacy_dir.display(),
+                names
+            );
+            continue;
+        }
+        let dir = folder_dir(account_id, &names[0])?;
+        if dir.exists() {
+            continue;
+        }
+        std::fs::rename(&legacy_dir, &dir)
+            .map_err(|e| format!("Failed to move Maildir of '{}': {}", names[0], e))?;
+        println!("📁 Moved Maildir of '{}' to {}", names[0], dir.display());
+    }
+    Ok(())
+}
+
+/// Find the file of a message; names are `<uid>.colimail:2,<flags>`
+fn find_message_file(dir: &Path, uid: u32) -> Option<PathBuf> {
+    let prefix = format!("{}.colimail", uid);
+    std::fs::read_dir(dir.join("cur"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.split([':', '!']).next() == Some(prefix.as_str()))
+        })
+}
+
+/// Get the configured store for local folders
+pub(crate) async fn local_store_format() -> LocalStoreFormat {
+    let pool = db::pool();
+    let value = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(SETTING_KEY)
+        .fetch_optional(pool.as_ref())
+        .await
+        .ok()
+        .flatten();
+
+    match value {
+        Some((value,)) if value == "maildir" => LocalStoreFormat::Maildir,
+        _ => LocalStoreFormat::Sqlite,
+    }
+}
+
+/// Whether a body of this folder belongs in the Maildir store
+pub(crate) async fn uses_maildir(account_id: i32, folder_name: &str) -> bool {
+    if local_store_format().await != LocalStoreFormat::Maildir {
+        return false;
+    }
+
+    let pool = db::pool();
+    sqlx::query_as::<_, (i64,)>(
+        "SELECT COALESCE(is_local, 0) FROM folders WHERE account_id = ? AND name = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .ok()
+    .flatten()
+    .is_some_and(|(is_local,)| is_local != 0)
+}
+
+/// Write a message into the folder's Maildir (via tmp/, as the Maildir protocol requires)
+pub(crate) fn write_message(
+    account_id: i32,
+    folder_name: &str,
+    uid: u32,
+    raw: &[u8],
+    seen: bool,
+    flagged: bool,
+) -> Result<(), String> {
+    let dir = folder_dir(account_id, folder_name)?;
+    for subdir in ["cur", "new", "tmp"] {
+        std::fs::create_dir_all(dir.join(subdir))
+            .map_err(|e| format!("Failed to create Maildir: {}", e))?;
+    }
+
+    // Maildir flags must be in ASCII order
+    let mut flags = String::new();
+    if flagged {
+        flags.push('F');
+    }
+    if seen {
+        flags.push('S');
+    }
+    let separator = if cfg!(windows) { '!' } else { ':' };
+    let file_name = format!("{}.colimail{}2,{}", uid, separator, flags);
+
+    let tmp_path = dir.join("tmp").join(format!("{}.colimail", uid));
+    std::fs::write(&tmp_path, raw).map_err(|e| format!("Failed to write message: {}", e))?;
+    if let Some(existing) = find_message_file(&dir, uid) {
+        let _ = std::fs::remove_file(existing);
+    }
+    std::fs::rename(&tmp_path, dir.join("cur").join(file_name))
+        .map_err(|e| format!("Failed to store message: {}", e))
+}
+
+/// Read the raw message of a UID from the folder's Maildir
+pub(crate) fn read_message(account_id: i32, folder_name: &str, uid: u32) -> Option<Vec<u8>> {
+    let path = find_message_file(&folder_dir(account_id, folder_name).ok()?, uid)?;
+    std::fs::read(path).ok()
+}
+
+/// Load the display body of a message stored in Maildir
+pub(crate) fn read_body(account_id: i32, folder_name: &str, uid: u32) -> Option<String> {
+    let raw = read_message(account_id, folder_name, uid)?;
+    parse_raw_message(&raw).ok().map(|(body, _)| body)
+}
+
+/// Store a body of a local folder as a Maildir file, using the cached headers and attachments
+pub(crate) async fn store_body(
+    account_id: i32,
+    folder_name: &str,
+    uid: u32,
+    body: &str,
+) -> Result<(), String> {
+    let pool = db::pool();
+
+    #[allow(clippy::type_complexity)]
+    let (email_id, subject, from, to, cc, date, timestamp, seen, flagged) = sqlx::query_as::<
+        _,
+        (
+            i64,
+            String,
+            String,
+            String,
+            Option<String>,
+            String,
+            i64,
+            i64,
+            i64,
+        ),
+    >(
+        "SELECT id, subject, from_addr, to_addr, cc_addr, date, timestamp,
+                COALESCE(seen, 0), COALESCE(flagged, 0)
+             FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(uid as i64)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load email for Maildir: {}", e))?;
+
+    let attachments = load_email_attachments(email_id).await?;
+    let raw = build_raw_message(
+        &from,
+        &to,
+        cc.as_deref().unwrap_or_default(),
+        &subject,
+        &date,
+        timestamp,
+        body,
+        &attachments,
+    );
+    write_message(account_id, folder_name, uid, &raw, seen != 0, flagged != 0)
+}
+
+/// Remove the file of a message (after it was deleted or moved away); no-op without one
+pub(crate) fn remove_message(account_id: i32, folder_name: &str, uid: u32) {
+    let Ok(dir) = folder_dir(account_id, folder_name) else {
+        return;
+    };
+    if let Some(path) = find_message_file(&dir, uid) {
+        if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("⚠️ Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Carry a message's file along when it is moved or copied to another folder
+///
+/// The file goes into the target's Maildir only if that folder is kept in Maildir; otherwise
+/// its body is fetched from the server again like any other. Moves drop the source file.
+pub(crate) async fn transfer_message(
+    account_id: i32,
+    folder_name: &str,
+    uid: u32,
+    target_folder: &str,
+    new_uid: Option<u32>,
+    keep_source: bool,
+) -> Result<(), String> {
+    let Some(source) = folder_dir(account_id, folder_name)
+        .ok()
+        .and_then(|dir| find_message_file(&dir, uid))
+    else {
+        return Ok(());
+    };
+
+    if let Some(new_uid) = new_uid {
+        if uses_maildir(account_id, target_folder).await {
+            let dir = folder_dir(account_id, target_folder)?;
+            for subdir in ["cur", "new", "tmp"] {
+                std::fs::create_dir_all(dir.join(subdir))
+                    .map_err(|e| format!("Failed to create Maildir: {}", e))?;
+            }
+            if let Some(existing) = find_message_file(&dir, new_uid) {
+                let _ = std::fs::remove_file(existing);
+            }
+            // Keep the flags part of the name (":2,FS"), only the UID changes
+            let file_name = source
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            let info = &file_name[file_name.find(".colimail").unwrap_or(file_name.len())..];
+            let target = dir.join("cur").join(format!("{}{}", new_uid, info));
+            let result = if keep_source {
+                std::fs::copy(&source, &target).map(|_| ())
+            } else {
+                std::fs::rename(&source, &target)
+            };
+            return result.map_err(|e| format!("Failed to move message file: {}", e));
+        }
+    }
+
+    if !keep_source {
+        std::fs::remove_file(&source)
+            .map_err(|e| format!("Failed to remove message file: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Rename the Maildir of a folder along with the folder
+pub(crate) fn rename_folder(
+    account_id: i32,
+    folder_name: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let dir = folder_dir(account_id, folder_name)?;
+    let new_dir = folder_dir(account_id, new_name)?;
+    if !dir.exists() {
+        return Ok(());
+    }
+    if new_dir.exists() {
+        return Err(format!(
+            "Maildir for '{}' already exists at {}",
+            new_name,
+            new_dir.display()
+        ));
+    }
+    std::fs::rename(&dir, &new_dir).map_err(|e| format!("Failed to rename Maildir: {}", e))
+}
+
+/// Remove the Maildir of a folder (after the folder or its local copies are gone)
+pub(crate) fn remove_folder(account_id: i32, folder_name: &str) {
+    let Ok(dir) = folder_dir(account_id, folder_name) else {
+        return;
+    };
+    if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            eprintln!("⚠️ Failed to remove Maildir {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// Get the store used for local folders
+#[command]
+pub async fn get_local_store_format() -> Result<LocalStoreFormat, String> {
+    Ok(local_store_format().await)
+}
+
+/// Switch the store for local folders, moving every cached body of a local folder over
+#[command]
+pub async fn migrate_local_store(format: LocalStoreFormat) -> Result<LocalStoreMigration, String> {
+    if format == LocalStoreFormat::Maildir && is_encryption_enabled().await? {
+        return Err(
+            "Maildir storage keeps messages unencrypted; disable encryption first".to_string(),
+        );
+    }
+
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (i32, String, i64, Option<String>)>(
+        "SELECT e.account_id, e.folder_name, e.uid, e.body
+         FROM emails e
+         JOIN folders f ON f.account_id = e.account_id AND f.name = e.folder_name
+         WHERE COALESCE(f.is_local, 0) = 1
+         ORDER BY e.account_id, e.folder_name, e.uid",
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load local emails: {}", e))?;
+
+    let mut migrated = 0;
+    for (account_id, folder_name, uid, body) in rows {
+        let uid = uid as u32;
+        match format {
+            LocalStoreFormat::Maildir => {
+                let Some(body) = body else { continue };
+                store_body(account_id, &folder_name, uid, &body).await?;
+                sqlx::query(
+                    "UPDATE emails SET body = NULL
+                     WHERE account_id = ? AND folder_name = ? AND uid = ?",
+                )
+                .bind(account_id)
+                .bind(&folder_name)
+                .bind(uid as i64)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to clear migrated body: {}", e))?;
+            }
+            LocalStoreFormat::Sqlite => {
+                let Some(body) = read_body(account_id, &folder_name, uid) else {
+                    continue;
+                };
+                sqlx::query(
+                    "UPDATE emails SET body = ?
+                     WHERE account_id = ? AND folder_name = ? AND uid = ?",
+                )
+                .bind(&body)
+                .bind(account_id)
+                .bind(&folder_name)
+                .bind(uid as i64)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to restore body: {}", e))?;
+                remove_message(account_id, &folder_name, uid);
+            }
+        }
+        migrated += 1;
+    }
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(SETTING_KEY)
+        .bind(match format {
+            LocalStoreFormat::Sqlite => "sqlite",
+            LocalStoreFormat::Maildir => "maildir",
+        })
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save local store format: {}", e))?;
+
+    println!(
+        "✅ Moved {} local messages to {:?} storage",
+        migrated, format
+    );
+    Ok(LocalStoreMigration { format, migrated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dir_names_are_distinct_and_safe() {
+        assert_eq!(dir_name("INBOX"), "INBOX");
+        assert_ne!(dir_name("A/B"), dir_name("A_B"));
+        assert_eq!(dir_name(".."), "%2E%2E");
+        assert_eq!(dir_name("Été"), "%C3%89t%C3%A9");
+        assert!(folder_dir(1, "").is_err());
+    }
+}
@@ -1,26 +1,71 @@
 // Email commands module
 // This module handles all email-related IMAP operations
 
+pub mod append;
+pub mod archive;
+pub mod attachment_index;
 pub mod attachments;
+pub mod bulk;
 pub mod cache;
 pub mod codec;
+pub mod content_hash;
+pub mod date_buckets;
 pub mod delete;
+pub mod export;
 pub mod fetch;
 pub mod fetch_bodystructure;
 pub mod flags;
+pub mod gmail;
+pub mod header_search;
 pub mod imap_helpers;
+pub mod import;
+pub mod locate;
+pub mod maildir;
+pub mod move_copy;
+pub mod notes;
+pub mod search;
+pub mod spam;
 pub mod sync;
+pub mod sync_all;
 pub mod sync_interval;
+pub mod tnef;
+pub mod unified;
 
 // Re-export public command functions for use in main.rs
+pub use append::upload_folder_to_remote;
+pub use archive::archive_email;
+pub use attachment_index::search_attachment_text;
 pub use attachments::{download_attachment, load_attachments_info, save_attachment_to_file};
-pub use cache::load_emails_from_cache;
+pub use bulk::{delete_emails, mark_emails_as_read, move_emails_to_folder};
+pub use cache::{
+    get_conversation_unread_count, get_unread_counts_by_sender, get_unread_counts_by_thread,
+    load_emails_from_cache,
+};
+pub use content_hash::{find_duplicate_emails, verify_cached_bodies};
+pub use date_buckets::load_emails_by_date;
 pub use delete::{delete_email, move_email_to_trash};
+pub use export::export_conversation;
 pub use fetch::{fetch_email_body, fetch_email_body_cached, fetch_emails};
+pub use fetch_bodystructure::prioritize_bodystructure;
 pub use flags::{
     mark_email_as_flagged, mark_email_as_read, mark_email_as_unflagged, mark_email_as_unread,
+    mark_folder_as_read,
 };
+pub use gmail::search_gmail_raw;
+pub use header_search::search_emails_by_header;
+pub use import::import_mailbox;
+pub use locate::locate_email;
+pub use maildir::{get_local_store_format, migrate_local_store};
+pub use move_copy::{copy_email_to_folder, move_email_to_folder};
+pub use notes::{delete_email_note, get_email_note, list_email_notes, save_email_note};
+pub use search::search_emails;
+pub use spam::{mark_as_not_spam, mark_as_spam};
 pub use sync::{
-    get_last_sync_time, should_sync, sync_email_flags, sync_emails, sync_specific_email_flags,
+    get_last_sync_time, get_sync_window, load_older_emails, set_sync_window, should_sync,
+    sync_email_flags, sync_emails, sync_specific_email_flags,
+};
+pub use sync_all::sync_all_folders;
+pub use sync_interval::{
+    get_idle_poll_interval, get_sync_interval, set_idle_poll_interval, set_sync_interval,
 };
-pub use sync_interval::{get_sync_interval, set_sync_interval};
+pub use unified::{load_unified_inbox, sync_unified_inbox};
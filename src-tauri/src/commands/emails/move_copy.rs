@@ -0,0 +1,338 @@
+// Move and copy between folders
+// Moves use UID MOVE (RFC 6851) where the server supports it, and otherwise COPY + \Deleted +
+// EXPUNGE (UID EXPUNGE with UIDPLUS, so other messages marked deleted are left alone). After
+// the server operation, the messages are looked up in the target folder by Message-ID so the
+// cached copy (including its body) can follow them; the cache is then updated in a single
+// transaction. Messages that can't be found are dropped from the cache for move, and left to
+// the next sync of the target folder for copy.
+
+use crate::commands::emails::{imap_helpers, maildir};
+use crate::commands::reader_windows;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::AccountConfig;
+use std::collections::HashMap;
+use tauri::{command, AppHandle};
+
+/// UIDs sent per MOVE/COPY command, to keep command lines short
+const UIDS_PER_COMMAND: usize = 200;
+
+/// Cached columns carried over to a copy, besides its id and location
+const COPIED_COLUMNS: &str = "subject, from_addr, to_addr, cc_addr, bcc_addr, date, timestamp,
+    body, has_attachments, flags, seen, flagged, message_id, content_hash, received_at,
+    date_offset, security, thread_root";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TransferMode {
+    Move,
+    Copy,
+}
+
+/// Move or copy messages to another folder of the same account, on the server and in the cache
+///
+/// Returns the new UIDs by old UID, for the messages that could be found in the target folder.
+pub(crate) async fn transfer_emails(
+    config: AccountConfig,
+    folder_name: &str,
+    uids: &[u32],
+    target_folder: &str,
+    mode: TransferMode,
+) -> Result<HashMap<u32, u32>, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    if folder_name == target_folder {
+        return Err("Target folder is the same as the source folder".to_string());
+    }
+    if uids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    // Message-IDs let us find the messages again in the target folder
+    let pool = db::pool();
+    let mut message_ids = Vec::new();
+    for uid in uids {
+        let message_id = sqlx::query_as::<_, (Option<String>,)>(
+            "SELECT message_id FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(account_id)
+        .bind(folder_name)
+        .bind(*uid as i64)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load message ID: {}", e))?
+        .and_then(|(message_id,)| message_id)
+        .filter(|message_id| !message_id.is_empty() && !message_id.contains(['"', '\\']));
+        if let Some(message_id) = message_id {
+            message_ids.push((*uid, message_id));
+        }
+    }
+
+    let config = ensure_valid_token(config).await?;
+    let source = folder_name.to_string();
+    let target = target_folder.to_string();
+    let uids_for_task = uids.to_vec();
+
+    let new_uids = tokio::task::spawn_blocking(move || -> Result<HashMap<u32, u32>, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+        let capabilities = imap_session
+            .capabilities()
+            .map_err(|e| format!("Failed to get capabilities: {}", e))?;
+        let supports_move = capabilities.has_str("MOVE");
+        let supports_uidplus = capabilities.has_str("UIDPLUS");
+
+        imap_session.select(&source).map_err(|e| {
+            eprintln!("❌ Failed to SELECT folder '{}': {}", source, e);
+            format!("Cannot access folder '{}': {}", source, e)
+        })?;
+        if mode == TransferMode::Move && !supports_move && !supports_uidplus {
+            imap_helpers::ensure_plain_expunge_safe(&mut imap_session, &uids_for_task)?;
+        }
+
+        for chunk in uids_for_task.chunks(UIDS_PER_COMMAND) {
+            let uid_set = imap_helpers::uid_set(chunk);
+
+            if mode == TransferMode::Move && supports_move {
+                imap_session
+                    .uid_mv(&uid_set, &target)
+                    .map_err(|e| format!("Failed to move emails to '{}': {}", target, e))?;
+                continue;
+            }
+
+            imap_session
+                .uid_copy(&uid_set, &target)
+                .map_err(|e| format!("Failed to copy emails to '{}': {}", target, e))?;
+            if mode == TransferMode::Copy {
+                continue;
+            }
+
+            imap_session
+                .uid_store(&uid_set, "+FLAGS.SILENT (\\Deleted)")
+                .map_err(|e| format!("Failed to mark emails as deleted: {}", e))?;
+            if supports_uidplus {
+                imap_session
+                    .uid_expunge(&uid_set)
+                    .map_err(|e| format!("Failed to expunge emails: {}", e))?;
+            } else {
+                imap_session
+                    .expunge()
+                    .map_err(|e| format!("Failed to expunge emails: {}", e))?;
+            }
+        }
+        println!(
+            "✅ {:?} of {} email(s) from '{}' to '{}' done on server (MOVE supported: {})",
+            mode,
+            uids_for_task.len(),
+            source,
+            target,
+            supports_move
+        );
+
+        // Find the new UIDs; the highest match is the message that just arrived
+        let mut new_uids = HashMap::new();
+        if !message_ids.is_empty() && imap_session.examine(&target).is_ok() {
+            for (uid, message_id) in message_ids {
+                let criteria = format!("HEADER Message-ID \"{}\"", message_id);
+                if let Some(new_uid) = imap_session
+                    .uid_search(&criteria)
+                    .ok()
+                    .and_then(|found| found.into_iter().max())
+                {
+                    new_uids.insert(uid, new_uid);
+                }
+            }
+        }
+
+        let _ = imap_session.logout();
+        Ok(new_uids)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+
+    update_cache(
+        account_id,
+        folder_name,
+        uids,
+        target_folder,
+        mode,
+        &new_uids,
+    )
+    .await?;
+    Ok(new_uids)
+}
+
+/// Apply a finished move/copy to the cache in one transaction
+async fn update_cache(
+    account_id: i32,
+    folder_name: &str,
+    uids: &[u32],
+    target_folder: &str,
+    mode: TransferMode,
+    new_uids: &HashMap<u32, u32>,
+) -> Result<(), String> {
+    let pool = db::pool();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let copy_sql = format!(
+        "INSERT INTO emails (account_id, folder_name, uid, synced_at, {cols})
+         SELECT account_id, ?, ?, synced_at, {cols} FROM emails
+         WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        cols = COPIED_COLUMNS
+    );
+
+    // Copies whose body must be fetched again (see below)
+    let mut uncached_copies = Vec::new();
+    for uid in uids {
+        let new_uid = new_uids.get(uid).map(|uid| *uid as i64);
+
+        // A sync of the target folder may have cached the message already
+        if let Some(new_uid) = new_uid {
+            sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?")
+                .bind(account_id)
+                .bind(target_folder)
+                .bind(new_uid)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to update cache: {}", e))?;
+        }
+
+        let query = match (mode, new_uid) {
+            (TransferMode::Move, Some(new_uid)) => sqlx::query(
+                "UPDATE emails SET folder_name = ?, uid = ?
+                 WHERE account_id = ? AND folder_name = ? AND uid = ?",
+            )
+            .bind(target_folder)
+            .bind(new_uid),
+            (TransferMode::Move, None) => sqlx::query(
+                "DELETE FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+            ),
+            (TransferMode::Copy, Some(new_uid)) => {
+                sqlx::query(&copy_sql).bind(target_folder).bind(new_uid)
+            }
+            (TransferMode::Copy, None) => continue,
+        };
+        let result = query
+            .bind(account_id)
+            .bind(folder_name)
+            .bind(*uid as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update cache: {}", e))?;
+
+        // The copied body comes with its attachments. Archived files can't be shared between
+        // rows (deleting either row removes the file), so a copy with archived attachments
+        // leaves its body uncached and fetches everything again when opened.
+        if mode == TransferMode::Copy && result.rows_affected() > 0 {
+            let copy_id = result.last_insert_rowid();
+            let source_id = "(SELECT id FROM emails
+                WHERE account_id = ? AND folder_name = ? AND uid = ?)";
+            sqlx::query(&format!(
+                "INSERT INTO attachments (email_id, filename, content_type, size, data)
+                 SELECT ?, filename, content_type, size, data FROM attachments
+                 WHERE email_id = {} AND archived_path IS NULL",
+                source_id
+            ))
+            .bind(copy_id)
+            .bind(account_id)
+            .bind(folder_name)
+            .bind(*uid as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to copy cached attachments: {}", e))?;
+
+            let uncached = sqlx::query(&format!(
+                "UPDATE emails SET body = NULL, content_hash = NULL
+                 WHERE id = ? AND EXISTS (SELECT 1 FROM attachments
+                    WHERE email_id = {} AND archived_path IS NOT NULL)",
+                source_id
+            ))
+            .bind(copy_id)
+            .bind(account_id)
+            .bind(folder_name)
+            .bind(*uid as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update cache: {}", e))?;
+            if uncached.rows_affected() > 0 {
+                uncached_copies.push(*uid);
+            }
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update cache: {}", e))?;
+
+    // Messages of Maildir-backed folders have their body in a file, which moves with them
+    for uid in uids {
+        maildir::transfer_message(
+            account_id,
+            folder_name,
+            *uid,
+            target_folder,
+            new_uids
+                .get(uid)
+                .copied()
+                .filter(|_| !uncached_copies.contains(uid)),
+            mode == TransferMode::Copy,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Move an email to another folder of the same account
+#[command]
+pub async fn move_email_to_folder(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    uid: u32,
+    folder: Option<String>,
+    target_folder: String,
+) -> Result<Option<u32>, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+    println!(
+        "Moving email UID {} from {} to {}",
+        uid, folder_name, target_folder
+    );
+
+    let new_uids = transfer_emails(
+        config,
+        &folder_name,
+        &[uid],
+        &target_folder,
+        TransferMode::Move,
+    )
+    .await?;
+
+    reader_windows::close_for_message(&app_handle, account_id, &folder_name, uid);
+    Ok(new_uids.get(&uid).copied())
+}
+
+/// Copy an email to another folder of the same account
+#[command]
+pub async fn copy_email_to_folder(
+    config: AccountConfig,
+    uid: u32,
+    folder: Option<String>,
+    target_folder: String,
+) -> Result<Option<u32>, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    println!(
+        "Copying email UID {} from {} to {}",
+        uid, folder_name, target_folder
+    );
+
+    let new_uids = transfer_emails(
+        config,
+        &folder_name,
+        &[uid],
+        &target_folder,
+        TransferMode::Copy,
+    )
+    .await?;
+    Ok(new_uids.get(&uid).copied())
+}
@@ -0,0 +1,248 @@
+// Per-message notes
+// Users can attach a private note to any message. Notes live in the local database (encrypted
+// like subjects when encryption is on) and are pushed to the server as a private `/comment`
+// annotation (RFC 5257) when it supports ANNOTATE.
+
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::emails::imap_helpers;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::encryption::{decrypt, encrypt, is_encryption_unlocked};
+use crate::models::AccountConfig;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// A private note on a message
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailNote {
+    pub folder_name: String,
+    pub uid: u32,
+    pub message_id: Option<String>,
+    pub note: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+type NoteRow = (String, i64, Option<String>, String, i64, i64);
+
+/// Whether notes are stored encrypted; fails while encryption is locked
+async fn notes_encrypted() -> Result<bool, String> {
+    let encrypted = is_encryption_enabled().await?;
+    if encrypted && !is_encryption_unlocked() {
+        return Err("Unlock encryption to access notes".to_string());
+    }
+    Ok(encrypted)
+}
+
+fn note_from_row(row: NoteRow, encrypted: bool) -> Result<EmailNote, String> {
+    let (folder_name, uid, message_id, note, created_at, updated_at) = row;
+
+    let note = if encrypted {
+        decrypt(&note).map_err(|e| format!("Failed to decrypt note: {}", e))?
+    } else {
+        note
+    };
+
+    Ok(EmailNote {
+        folder_name,
+        uid: uid as u32,
+        message_id,
+        note,
+        created_at,
+        updated_at,
+    })
+}
+
+/// Get the note of a message
+///
+/// Falls back to a note saved for the same Message-ID elsewhere, so notes survive moves.
+#[command]
+pub async fn get_email_note(
+    account_id: i32,
+    folder_name: String,
+    uid: u32,
+) -> Result<Option<EmailNote>, String> {
+    let pool = db::pool();
+
+    let row = sqlx::query_as::<_, NoteRow>(
+        "SELECT n.folder_name, n.uid, n.message_id, n.note, n.created_at, n.updated_at
+         FROM email_notes n
+         LEFT JOIN emails e ON e.account_id = ?1 AND e.folder_name = ?2 AND e.uid = ?3
+         WHERE n.account_id = ?1
+            AND ((n.folder_name = ?2 AND n.uid = ?3)
+                OR (e.message_id IS NOT NULL AND n.message_id = e.message_id))
+         ORDER BY (n.folder_name = ?2 AND n.uid = ?3) DESC, n.updated_at DESC
+         LIMIT 1",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .bind(uid as i64)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load note: {}", e))?;
+
+    match row {
+        Some(row) => note_from_row(row, notes_encrypted().await?).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// List all notes of an account, most recently edited first
+#[command]
+pub async fn list_email_notes(account_id: i32) -> Result<Vec<EmailNote>, String> {
+    let pool = db::pool();
+
+    let rows = sqlx::query_as::<_, NoteRow>(
+        "SELECT folder_name, uid, message_id, note, created_at, updated_at
+         FROM email_notes WHERE account_id = ? ORDER BY updated_at DESC",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load notes: {}", e))?;
+
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    let encrypted = notes_encrypted().await?;
+    rows.into_iter()
+        .map(|row| note_from_row(row, encrypted))
+        .collect()
+}
+
+/// Create or replace the note of a message; an empty note deletes it
+#[command]
+pub async fn save_email_note(
+    config: AccountConfig,
+    folder_name: String,
+    uid: u32,
+    note: String,
+) -> Result<Option<EmailNote>, String> {
+    if note.trim().is_empty() {
+        delete_email_note(config, folder_name, uid).await?;
+        return Ok(None);
+    }
+
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let pool = db::pool();
+    let now = chrono::Utc::now().timestamp();
+
+    let note_to_store = if notes_encrypted().await? {
+        encrypt(&note).map_err(|e| format!("Failed to encrypt note: {}", e))?
+    } else {
+        note.clone()
+    };
+
+    sqlx::query(
+        "INSERT INTO email_notes (account_id, folder_name, uid, message_id, note, created_at, updated_at)
+         VALUES (?1, ?2, ?3,
+            (SELECT message_id FROM emails WHERE account_id = ?1 AND folder_name = ?2 AND uid = ?3),
+            ?4, ?5, ?5)
+         ON CONFLICT(account_id, folder_name, uid) DO UPDATE SET
+            note = excluded.note,
+            message_id = COALESCE(excluded.message_id, email_notes.message_id),
+            updated_at = excluded.updated_at",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .bind(uid as i64)
+    .bind(&note_to_store)
+    .bind(now)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save note: {}", e))?;
+
+    push_annotation(config, folder_name.clone(), uid, Some(note));
+
+    get_email_note(account_id, folder_name, uid).await
+}
+
+/// Delete the note of a message
+#[command]
+pub async fn delete_email_note(
+    config: AccountConfig,
+    folder_name: String,
+    uid: u32,
+) -> Result<(), String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let pool = db::pool();
+
+    sqlx::query("DELETE FROM email_notes WHERE account_id = ? AND folder_name = ? AND uid = ?")
+        .bind(account_id)
+        .bind(&folder_name)
+        .bind(uid as i64)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete note: {}", e))?;
+
+    push_annotation(config, folder_name, uid, None);
+    Ok(())
+}
+
+/// Mirror a note to the server in the background; local folders and servers without
+/// ANNOTATE are skipped silently, other failures are only logged
+fn push_annotation(config: AccountConfig, folder_name: String, uid: u32, note: Option<String>) {
+    tokio::spawn(async move {
+        if folder_name.starts_with("local_") {
+            return;
+        }
+        let config = match ensure_valid_token(config).await {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("⚠️ Not syncing note: {}", e);
+                return;
+            }
+        };
+
+        let result = tokio::task::spawn_blocking(move || -> Result<bool, String> {
+            let mut session = imap_helpers::connect_and_login(&config)?;
+            let caps = session
+                .capabilities()
+                .map_err(|e| format!("Failed to read capabilities: {}", e))?;
+            if !caps.has_str("ANNOTATE-EXPERIMENT-1") {
+                let _ = session.logout();
+                return Ok(false);
+            }
+            let literal_plus = caps.has_str("LITERAL+");
+            drop(caps);
+
+            let value = match &note {
+                None => "NIL".to_string(),
+                Some(note) if is_quotable(note) => {
+                    format!("\"{}\"", note.replace('\\', "\\\\").replace('"', "\\\""))
+                }
+                Some(note) if literal_plus => format!("{{{}+}}\r\n{}", note.len(), note),
+                Some(_) => {
+                    let _ = session.logout();
+                    return Err("Note needs a literal but the server lacks LITERAL+".to_string());
+                }
+            };
+
+            session
+                .select(&folder_name)
+                .map_err(|e| format!("Cannot access folder '{}': {}", folder_name, e))?;
+            session
+                .run_command_and_read_response(format!(
+                    "UID STORE {} ANNOTATION (/comment (value.priv {}))",
+                    uid, value
+                ))
+                .map_err(|e| format!("Failed to store annotation: {}", e))?;
+
+            let _ = session.logout();
+            Ok(true)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(true)) => println!("📝 Synced note for UID {} to server", uid),
+            Ok(Ok(false)) => {}
+            Ok(Err(e)) => eprintln!("⚠️ Failed to sync note for UID {}: {}", uid, e),
+            Err(e) => eprintln!("⚠️ Failed to sync note for UID {}: {}", uid, e),
+        }
+    });
+}
+
+/// Whether a note can be sent as an IMAP quoted string (7-bit, single line)
+fn is_quotable(note: &str) -> bool {
+    note.is_ascii() && !note.contains(['\r', '\n', '\0'])
+}
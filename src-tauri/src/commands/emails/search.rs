@@ -0,0 +1,473 @@
+// Full-text search over cached emails
+// Searches subject, sender, recipients and body of everything in the local cache through the
+// `emails_fts` index, without contacting the server. Queries are plain words and "quoted
+// phrases", optionally restricted to a field with `subject:`, `from:`, `to:` or `body:`; all
+// terms must match and each matches as a prefix. Queries without a field also match notes
+// (`notes_fts`) and the extracted text of attachments (`attachment_fts`) that contain every
+// term. While encryption is enabled the subject, body and notes are stored encrypted, so the
+// indexes can't see them: the cache is then decrypted and scanned instead, which is slower but
+// finds the same messages (attachment text isn't indexed then).
+
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+/// Characters of context shown on each side of a hit in scanned snippets
+const SNIPPET_CONTEXT: usize = 60;
+
+/// An email matching a search
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailSearchResult {
+    pub account_id: i32,
+    pub folder_name: String,
+    pub uid: u32,
+    pub subject: String,
+    pub from: String,
+    pub to: String,
+    pub date: String,
+    pub timestamp: i64,
+    pub seen: bool,
+    pub flagged: bool,
+    pub has_attachments: bool,
+    pub snippet: String, // Matched text with the hits wrapped in [ ]
+}
+
+/// One page of search results, newest first
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailSearchPage {
+    pub results: Vec<EmailSearchResult>,
+    pub total: i64, // Matches across all pages
+    pub offset: i64,
+    pub limit: i64,
+}
+
+/// Searchable fields, named after their `emails_fts` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Subject,
+    From,
+    To,
+    Body,
+}
+
+impl Field {
+    fn column(self) -> &'static str {
+        match self {
+            Field::Subject => "subject",
+            Field::From => "from_addr",
+            Field::To => "to_addr",
+            Field::Body => "body",
+        }
+    }
+}
+
+/// A word or phrase, optionally restricted to one field
+#[derive(Debug, PartialEq, Eq)]
+struct SearchTerm {
+    field: Option<Field>,
+    value: String,
+}
+
+/// Split a query into terms, honoring double quotes; unknown `x:` prefixes are kept as text
+fn parse_query(query: &str) -> Vec<SearchTerm> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+        .into_iter()
+        .map(|token| {
+            let field = token.split_once(':').and_then(|(operator, value)| {
+                let field = match operator.to_ascii_lowercase().as_str() {
+                    "subject" => Field::Subject,
+                    "from" => Field::From,
+                    "to" => Field::To,
+                    "body" => Field::Body,
+                    _ => return None,
+                };
+                Some((field, value.to_string()))
+            });
+            match field {
+                Some((field, value)) => SearchTerm {
+                    field: Some(field),
+                    value,
+                },
+                None => SearchTerm {
+                    field: None,
+                    value: token,
+                },
+            }
+        })
+        .filter(|term| !term.value.trim().is_empty())
+        .collect()
+}
+
+/// FTS5 expression for the terms: each quoted (so input isn't read as query syntax) and
+/// matched as a prefix
+fn fts_expression(terms: &[SearchTerm]) -> String {
+    terms
+        .iter()
+        .map(|term| {
+            let phrase = format!("\"{}\"*", term.value.replace('"', "\"\""));
+            match term.field {
+                Some(field) => format!("{} : {}", field.column(), phrase),
+                None => phrase,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// Text around the first occurrence of `needle` (lowercase), with the hit wrapped in [ ]
+fn scan_snippet(text: &str, needle: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    // Lowercasing can change byte lengths; only use the position if it maps back cleanly
+    let start = lower.find(needle).filter(|_| lower.len() == text.len())?;
+    let end = start + needle.len();
+    if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+        return None;
+    }
+
+    let before: String = text[..start]
+        .chars()
+        .rev()
+        .take(SNIPPET_CONTEXT)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let after: String = text[end..].chars().take(SNIPPET_CONTEXT).collect();
+    Some(format!(
+        "{}{}[{}]{}{}",
+        if before.len() < start { "…" } else { "" },
+        before,
+        &text[start..end],
+        after,
+        if after.len() < text.len() - end {
+            "…"
+        } else {
+            ""
+        }
+    ))
+}
+
+type SearchRow = (
+    i64,
+    i32,
+    String,
+    String,
+    String,
+    String,
+    String,
+    i64,
+    i64,
+    i64,
+    i64,
+    Option<String>,
+);
+
+/// A search row plus the email's note, for scanning
+type ScanRow = (
+    i64,
+    i32,
+    String,
+    String,
+    String,
+    String,
+    String,
+    i64,
+    i64,
+    i64,
+    i64,
+    Option<String>,
+    Option<String>,
+);
+
+/// Search through the FTS index (cache stored in plaintext)
+async fn search_index(
+    terms: &[SearchTerm],
+    account_id: Option<i32>,
+    folder: Option<&str>,
+    offset: i64,
+    limit: i64,
+) -> Result<EmailSearchPage, String> {
+    let pool = db::pool();
+    let expression = fts_expression(terms);
+
+    // Matching emails with the best snippet of each: the email itself, then its note, then
+    // its attachments. Notes and attachments have no fields, so only plain queries reach them.
+    let mut hits = vec![
+        "SELECT rowid AS id, 0 AS source, snippet(emails_fts, -1, '[', ']', '…', 16) AS snippet
+         FROM emails_fts WHERE emails_fts MATCH ?1",
+    ];
+    if terms.iter().all(|term| term.field.is_none()) {
+        hits.push(
+            "SELECT e.id, 1, snippet(notes_fts, 0, '[', ']', '…', 16)
+             FROM notes_fts
+             JOIN email_notes n ON n.id = notes_fts.rowid
+             JOIN emails e ON e.account_id = n.account_id
+                AND ((e.folder_name = n.folder_name AND e.uid = n.uid)
+                    OR (n.message_id IS NOT NULL AND e.message_id = n.message_id))
+             WHERE notes_fts MATCH ?1",
+        );
+        hits.push(
+            "SELECT a.email_id, 2, snippet(attachment_fts, 0, '[', ']', '…', 16)
+             FROM attachment_fts
+             JOIN attachments a ON a.id = attachment_fts.rowid
+             WHERE attachment_fts MATCH ?1",
+        );
+    }
+    // SQLite takes the bare `snippet` from the row that has the MIN(source)
+    let matches = format!(
+        "emails e JOIN (SELECT id, MIN(source), snippet FROM ({}) GROUP BY id) hit
+            ON hit.id = e.id
+         WHERE (?2 IS NULL OR e.account_id = ?2) AND (?3 IS NULL OR e.folder_name = ?3)",
+        hits.join(" UNION ALL ")
+    );
+
+    let (total,) = sqlx::query_as::<_, (i64,)>(&format!("SELECT COUNT(*) FROM {}", matches))
+        .bind(&expression)
+        .bind(account_id)
+        .bind(folder)
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to search emails: {}", e))?;
+
+    let rows = sqlx::query_as::<_, SearchRow>(&format!(
+        "SELECT e.uid, e.account_id, e.folder_name, e.subject, e.from_addr, e.to_addr, e.date,
+                e.timestamp, COALESCE(e.seen, 0), COALESCE(e.flagged, 0),
+                COALESCE(e.has_attachments, 0), hit.snippet
+         FROM {}
+         ORDER BY e.timestamp DESC LIMIT ?4 OFFSET ?5",
+        matches
+    ))
+    .bind(&expression)
+    .bind(account_id)
+    .bind(folder)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to search emails: {}", e))?;
+
+    Ok(EmailSearchPage {
+        results: rows.into_iter().map(into_result).collect(),
+        total,
+        offset,
+        limit,
+    })
+}
+
+/// Search by decrypting and scanning the cache (encryption enabled)
+async fn search_encrypted(
+    terms: &[SearchTerm],
+    account_id: Option<i32>,
+    folder: Option<&str>,
+    offset: i64,
+    limit: i64,
+) -> Result<EmailSearchPage, String> {
+    if !is_encryption_unlocked() {
+        return Err("Unlock encryption to search your mail".to_string());
+    }
+
+    let pool = db::pool();
+    // The note is found like `get_email_note` does: by position, or by Message-ID after a move
+    let rows = sqlx::query_as::<_, ScanRow>(
+        "SELECT e.uid, e.account_id, e.folder_name, e.subject, e.from_addr, e.to_addr, e.date,
+                e.timestamp, COALESCE(e.seen, 0), COALESCE(e.flagged, 0),
+                COALESCE(e.has_attachments, 0), e.body,
+                (SELECT n.note FROM email_notes n
+                 WHERE n.account_id = e.account_id
+                    AND ((n.folder_name = e.folder_name AND n.uid = e.uid)
+                        OR (e.message_id IS NOT NULL AND n.message_id = e.message_id))
+                 ORDER BY (n.folder_name = e.folder_name AND n.uid = e.uid) DESC,
+                    n.updated_at DESC
+                 LIMIT 1)
+         FROM emails e
+         WHERE (?1 IS NULL OR e.account_id = ?1) AND (?2 IS NULL OR e.folder_name = ?2)
+         ORDER BY e.timestamp DESC",
+    )
+    .bind(account_id)
+    .bind(folder)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to search emails: {}", e))?;
+
+    let needles: Vec<(Option<Field>, String)> = terms
+        .iter()
+        .map(|term| (term.field, term.value.to_lowercase()))
+        .collect();
+
+    let mut total = 0;
+    let mut results = Vec::new();
+    for (
+        uid,
+        row_account,
+        row_folder,
+        subject,
+        from,
+        to,
+        date,
+        timestamp,
+        seen,
+        flagged,
+        attached,
+        body,
+        note,
+    ) in rows
+    {
+        let mut row: SearchRow = (
+            uid,
+            row_account,
+            row_folder,
+            decrypt(&subject).unwrap_or_default(),
+            from,
+            to,
+            date,
+            timestamp,
+            seen,
+            flagged,
+            attached,
+            None,
+        );
+        let body = body.and_then(|body| decrypt(&body).ok());
+        // Notes written before encryption was enabled are still plaintext
+        let note = note.map(|note| decrypt(&note).unwrap_or(note));
+        let note = note.as_deref().unwrap_or_default();
+        let fields = [
+            (Field::Subject, row.3.as_str()),
+            (Field::From, row.4.as_str()),
+            (Field::To, row.5.as_str()),
+            (Field::Body, body.as_deref().unwrap_or_default()),
+        ];
+
+        let matches = needles.iter().all(|(field, needle)| {
+            fields.iter().any(|(name, text)| {
+                (field.is_none() || *field == Some(*name)) && text.to_lowercase().contains(needle)
+            })
+        }) || (needles.iter().all(|(field, _)| field.is_none())
+            && needles
+                .iter()
+                .all(|(_, needle)| note.to_lowercase().contains(needle)));
+        if !matches {
+            continue;
+        }
+
+        total += 1;
+        if total > offset && results.len() < limit as usize {
+            row.11 = needles.iter().find_map(|(_, needle)| {
+                fields
+                    .iter()
+                    .map(|(_, text)| *text)
+                    .chain([note])
+                    .find_map(|text| scan_snippet(text, needle))
+            });
+            results.push(into_result(row));
+        }
+    }
+
+    Ok(EmailSearchPage {
+        results,
+        total,
+        offset,
+        limit,
+    })
+}
+
+fn into_result(row: SearchRow) -> EmailSearchResult {
+    let (
+        uid,
+        account_id,
+        folder_name,
+        subject,
+        from,
+        to,
+        date,
+        timestamp,
+        seen,
+        flagged,
+        has_attachments,
+        snippet,
+    ) = row;
+    EmailSearchResult {
+        account_id,
+        folder_name,
+        uid: uid as u32,
+        subject,
+        from,
+        to,
+        date,
+        timestamp,
+        seen: seen != 0,
+        flagged: flagged != 0,
+        has_attachments: has_attachments != 0,
+        snippet: snippet.unwrap_or_default(),
+    }
+}
+
+/// Search cached emails, optionally within one account and/or folder
+#[command]
+pub async fn search_emails(
+    query: String,
+    account_id: Option<i32>,
+    folder: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<EmailSearchPage, String> {
+    let offset = offset.unwrap_or(0).max(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let terms = parse_query(&query);
+    if terms.is_empty() {
+        return Ok(EmailSearchPage {
+            results: Vec::new(),
+            total: 0,
+            offset,
+            limit,
+        });
+    }
+
+    if is_encryption_enabled().await? {
+        search_encrypted(&terms, account_id, folder.as_deref(), offset, limit).await
+    } else {
+        search_index(&terms, account_id, folder.as_deref(), offset, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_parsing() {
+        let terms = parse_query(r#"from:alice "quarterly report" re:budget"#);
+        assert_eq!(
+            fts_expression(&terms),
+            r#"from_addr : "alice"* AND "quarterly report"* AND "re:budget"*"#
+        );
+        assert!(parse_query("  subject: ").is_empty());
+        assert_eq!(
+            scan_snippet("Your Invoice is ready", "invoice").as_deref(),
+            Some("Your [Invoice] is ready")
+        );
+    }
+}
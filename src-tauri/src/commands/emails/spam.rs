@@ -0,0 +1,176 @@
+// Spam reporting
+// "Mark as spam" moves a message to the account's Junk folder and "not spam" moves it back to
+// the inbox. Optionally the $Junk / $NotJunk keywords are set first, so they travel with the
+// message and servers that train their filters on them (Dovecot, Fastmail, ...) learn from
+// the correction. Servers that don't allow keywords just keep the move.
+
+use crate::commands::emails::imap_helpers;
+use crate::commands::emails::move_copy::{transfer_emails, TransferMode};
+use crate::commands::folder_names::FolderRole;
+use crate::commands::folders::folder_for_role;
+use crate::commands::reader_windows;
+use crate::commands::utils::ensure_valid_token;
+use crate::models::AccountConfig;
+use imap_proto::types::NameAttribute;
+use tauri::{command, AppHandle};
+
+/// Find the junk/spam folder of an account
+/// Prefers the \Junk attribute and falls back to names different providers use
+fn find_junk_folder(
+    imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
+) -> Result<String, String> {
+    let junk_candidates = [
+        "[Gmail]/Spam", // Gmail
+        "Junk",         // Standard IMAP, iCloud, Fastmail
+        "Junk Email",   // Outlook/Exchange
+        "Junk E-mail",  // Older Exchange
+        "Spam",         // Common alternative
+        "Bulk Mail",    // Yahoo
+        "INBOX.Junk",   // Some IMAP servers
+        "INBOX.Spam",   // Some IMAP servers
+        "垃圾邮件",     // Chinese
+    ];
+
+    let mailbox_list = imap_session
+        .list(Some(""), Some("*"))
+        .map_err(|e| format!("Failed to list folders: {}", e))?;
+
+    let selectable: Vec<_> = mailbox_list
+        .iter()
+        .filter(|mailbox| {
+            !mailbox
+                .attributes()
+                .iter()
+                .any(|attr| matches!(attr, NameAttribute::NoSelect))
+        })
+        .collect();
+
+    if let Some(mailbox) = selectable.iter().find(|mailbox| {
+        mailbox
+            .attributes()
+            .iter()
+            .any(|attr| matches!(attr, NameAttribute::Junk))
+    }) {
+        return Ok(mailbox.name().to_string());
+    }
+
+    for candidate in junk_candidates {
+        if let Some(mailbox) = selectable
+            .iter()
+            .find(|mailbox| mailbox.name().eq_ignore_ascii_case(candidate))
+        {
+            return Ok(mailbox.name().to_string());
+        }
+    }
+
+    Err("Could not find a junk/spam folder for this account.".to_string())
+}
+
+/// Set `add` and clear `remove` on a message; failures only warn, since many servers don't
+/// accept custom keywords
+fn tag_message(
+    imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
+    uid: u32,
+    add: &str,
+    remove: &str,
+) {
+    let result = imap_session
+        .uid_store(uid.to_string(), format!("+FLAGS.SILENT ({})", add))
+        .and_then(|_| {
+            imap_session.uid_store(uid.to_string(), format!("-FLAGS.SILENT ({})", remove))
+        });
+    if let Err(e) = result {
+        eprintln!("⚠️ Server did not accept {} for UID {}: {}", add, uid, e);
+    }
+}
+
+/// Move an email to the junk folder, optionally tagging it $Junk
+#[command]
+pub async fn mark_as_spam(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    uid: u32,
+    folder: Option<String>,
+    set_keywords: Option<bool>,
+) -> Result<String, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let tag = set_keywords.unwrap_or(true);
+    println!("Marking email UID {} in {} as spam", uid, folder_name);
+
+    let config = ensure_valid_token(config).await?;
+    let config_for_task = config.clone();
+    let folder_for_task = folder_name.clone();
+    let known_junk = folder_for_role(account_id, FolderRole::Junk).await?;
+    let junk_folder = tokio::task::spawn_blocking(move || -> Result<String, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config_for_task)?;
+        let junk_folder = match known_junk {
+            Some(junk_folder) => junk_folder,
+            None => find_junk_folder(&mut imap_session)?,
+        };
+
+        if tag {
+            imap_session
+                .select(&folder_for_task)
+                .map_err(|e| format!("Cannot access folder '{}': {}", folder_for_task, e))?;
+            tag_message(&mut imap_session, uid, "$Junk", "$NotJunk");
+        }
+
+        let _ = imap_session.logout();
+        Ok(junk_folder)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    transfer_emails(
+        config,
+        &folder_name,
+        &[uid],
+        &junk_folder,
+        TransferMode::Move,
+    )
+    .await?;
+
+    reader_windows::close_for_message(&app_handle, account_id, &folder_name, uid);
+    println!("✅ Moved email UID {} to {}", uid, junk_folder);
+    Ok(junk_folder)
+}
+
+/// Move an email out of the junk folder (to the inbox unless `target_folder` is given),
+/// optionally tagging it $NotJunk
+#[command]
+pub async fn mark_as_not_spam(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    uid: u32,
+    folder: String,
+    target_folder: Option<String>,
+    set_keywords: Option<bool>,
+) -> Result<String, String> {
+    let target_folder = target_folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+    println!("Marking email UID {} in {} as not spam", uid, folder);
+
+    let config = ensure_valid_token(config).await?;
+    if set_keywords.unwrap_or(true) {
+        let config_for_task = config.clone();
+        let folder_for_task = folder.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), String> {
+            let mut imap_session = imap_helpers::connect_and_login(&config_for_task)?;
+            imap_session
+                .select(&folder_for_task)
+                .map_err(|e| format!("Cannot access folder '{}': {}", folder_for_task, e))?;
+            tag_message(&mut imap_session, uid, "$NotJunk", "$Junk");
+            let _ = imap_session.logout();
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+    }
+
+    transfer_emails(config, &folder, &[uid], &target_folder, TransferMode::Move).await?;
+
+    reader_windows::close_for_message(&app_handle, account_id, &folder, uid);
+    println!("✅ Moved email UID {} to {}", uid, target_folder);
+    Ok(target_folder)
+}
@@ -2,44 +2,138 @@
 // This module coordinates incremental sync using UIDVALIDITY and UIDs
 
 // Sub-modules
-mod parse;
+pub(crate) mod parse;
 mod sync_core;
 mod sync_fetch;
 mod sync_flags;
 mod sync_state;
+mod sync_window;
 
 // Re-export public command functions
 pub use sync_flags::{sync_email_flags, sync_specific_email_flags};
 pub use sync_state::{get_last_sync_time, should_sync};
+pub use sync_window::{get_sync_window, load_older_emails, set_sync_window};
 
+use crate::commands::account_health;
 use crate::commands::emails::cache::load_emails_from_cache;
 use crate::commands::emails::fetch_bodystructure;
 use crate::models::{AccountConfig, EmailHeader};
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
-use tauri::command;
+use std::time::Duration;
+use sync_core::SyncError;
+use tauri::{command, AppHandle};
+
+/// Attempts per sync; retries resume from the checkpoint instead of starting over
+const MAX_SYNC_ATTEMPTS: u32 = 3;
+
+/// A sync attempt that saves nothing for this long is considered stuck and aborted
+const SYNC_STALL_THRESHOLD: Duration = Duration::from_secs(5 * 60);
+
+/// Background BODYSTRUCTURE fetch progresses every batch, so a few minutes of silence is a hang
+const BODYSTRUCTURE_STALL_THRESHOLD: Duration = Duration::from_secs(3 * 60);
 
 /// Sync emails from server and update cache (incremental sync)
+///
+/// `background` marks syncs the user didn't ask for by opening the folder (refresh-all, IDLE
+/// events for other folders); those skip folders that only sync on open.
 #[command]
 pub async fn sync_emails(
+    app_handle: AppHandle,
     config: AccountConfig,
     folder: Option<String>,
+    background: Option<bool>,
 ) -> Result<Vec<EmailHeader>, String> {
     let account_id = config.id.ok_or("Account ID is required")?;
     let folder_name = folder.clone().unwrap_or_else(|| "INBOX".to_string());
 
+    // Demo accounts have no server; their generated mail is already in the cache
+    if crate::commands::demo::is_demo_account(&config) {
+        return load_emails_from_cache(account_id, Some(folder_name), None).await;
+    }
+
+    if background.unwrap_or(false)
+        && sync_state::defer_until_opened(account_id, &folder_name).await?
+    {
+        println!("⏭️ Skipping '{}' until it is opened", folder_name);
+        return load_emails_from_cache(account_id, Some(folder_name), None).await;
+    }
+
     println!(
         "🔄 Starting incremental sync for account {} folder {}",
         account_id, folder_name
     );
 
-    // Perform incremental sync
-    let new_emails = sync_core::incremental_sync(config.clone(), account_id, &folder_name).await?;
+    // Remember where the last sync stopped, to tell which cached emails are new afterwards
+    let previous_state = sync_state::get_sync_state(account_id, &folder_name).await?;
+
+    // Perform incremental sync, retrying transient failures (a full sync resumes where it stopped)
+    let sync_started = std::time::Instant::now();
+    let mut attempt = 1;
+    let summary = loop {
+        let attempt_config = config.clone();
+        let attempt_folder = folder_name.clone();
+        let result = crate::watchdog::run_supervised(
+            "sync",
+            SYNC_STALL_THRESHOLD,
+            move |heartbeat| async move {
+                Ok(sync_core::incremental_sync(
+                    attempt_config,
+                    account_id,
+                    &attempt_folder,
+                    heartbeat,
+                )
+                .await)
+            },
+        )
+        .await
+        .map_err(SyncError::Failed)
+        .and_then(|outcome| outcome);
+
+        match result {
+            Ok(summary) => {
+                account_health::record_success(&app_handle, account_id);
+                break summary;
+            }
+            Err(SyncError::FolderRefused(e)) => {
+                crate::commands::folders::mark_folder_inaccessible(account_id, &folder_name)
+                    .await?;
+                return Err(e);
+            }
+            // Retrying a refused login only risks a lockout
+            Err(SyncError::Failed(e)) if account_health::classify_auth_error(&e).is_some() => {
+                return Err(account_health::record_failure(&app_handle, account_id, e));
+            }
+            Err(SyncError::Failed(e))
+                if attempt < MAX_SYNC_ATTEMPTS && !crate::shutdown::is_shutting_down() =>
+            {
+                eprintln!(
+                    "⚠️ Sync attempt {}/{} failed: {}. Retrying...",
+                    attempt, MAX_SYNC_ATTEMPTS, e
+                );
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                attempt += 1;
+            }
+            Err(SyncError::Failed(e)) => return Err(e),
+        }
+    };
 
     println!(
-        "✅ Incremental sync completed: fetched {} new emails",
-        new_emails.len()
+        "✅ Incremental sync completed: fetched {} new emails (highest new UID: {:?})",
+        summary.new_count, summary.max_uid
     );
+    let sync_elapsed = sync_started.elapsed();
+    crate::metrics::record_duration("sync_ms", sync_elapsed);
+    if summary.new_count > 0 {
+        crate::metrics::record(
+            "sync_fetch_msgs_per_sec",
+            summary.new_count as f64 / sync_elapsed.as_secs_f64().max(0.001),
+        );
+    }
+
+    // Flag local drafts whose server copy was edited on another device
+    if let Err(e) = crate::commands::drafts::detect_draft_conflicts(account_id, &folder_name).await
+    {
+        eprintln!("⚠️ Draft conflict check failed: {}", e);
+    }
 
     // Sync flags for existing emails (to catch flag changes from other clients)
     // This ensures star/read status stays in sync with server
@@ -62,24 +156,74 @@ pub async fn sync_emails(
     });
 
     // Load all cached emails (for display)
-    let emails = load_emails_from_cache(account_id, Some(folder_name.clone())).await?;
+    let emails = load_emails_from_cache(account_id, Some(folder_name.clone()), None).await?;
 
     println!("✅ Sync completed: {} emails in cache total", emails.len());
 
+    // Run automation hooks for new mail (not on a first or full resync, which would fire for
+    // everything in the folder)
+    let current_state = sync_state::get_sync_state(account_id, &folder_name).await?;
+    if let (Some(previous), Some(current)) = (previous_state, current_state) {
+        if previous.uidvalidity == current.uidvalidity && summary.new_count > 0 {
+            let highest_uid = previous.highest_uid.unwrap_or(0);
+            let new_emails: Vec<EmailHeader> = emails
+                .iter()
+                .filter(|email| email.uid as i64 > highest_uid)
+                .cloned()
+                .collect();
+            let folder_name_for_hooks = folder_name.clone();
+            tokio::spawn(async move {
+                crate::commands::automation::on_new_mail(
+                    account_id,
+                    &folder_name_for_hooks,
+                    &new_emails,
+                )
+                .await;
+            });
+        }
+    }
+
+    let post_sync_data = serde_json::json!({
+        "account_id": account_id,
+        "folder": folder_name,
+        "new_count": summary.new_count,
+        "total_count": emails.len(),
+    });
+    tokio::spawn(async move {
+        let _ = crate::commands::plugins::run_hook(
+            crate::commands::plugins::PluginHook::PostSync,
+            post_sync_data,
+        )
+        .await;
+    });
+
+    // Safe mode skips background fetchers
+    if crate::safe_mode::is_enabled() {
+        return Ok(emails);
+    }
+
     // Start background task to fetch BODYSTRUCTURE (newest first)
     // This improves perceived performance by showing emails immediately
     let account_id_i64 = account_id as i64;
     let folder_name_clone = folder_name.clone();
-    let cancel_token = Arc::new(AtomicBool::new(false));
+    let cancel_token = crate::shutdown::cancel_flag();
 
-    tokio::spawn(async move {
-        if let Err(e) = fetch_bodystructure::fetch_bodystructure_background(
-            account_id_i64,
-            folder_name_clone,
-            cancel_token,
+    crate::shutdown::spawn("bodystructure_fetch", async move {
+        let result = crate::watchdog::run_supervised(
+            "bodystructure_fetch",
+            BODYSTRUCTURE_STALL_THRESHOLD,
+            move |heartbeat| {
+                fetch_bodystructure::fetch_bodystructure_background(
+                    account_id_i64,
+                    folder_name_clone,
+                    cancel_token,
+                    heartbeat,
+                )
+            },
         )
-        .await
-        {
+        .await;
+
+        if let Err(e) = result {
             eprintln!("⚠️ Background BODYSTRUCTURE fetch failed: {}", e);
         }
     });
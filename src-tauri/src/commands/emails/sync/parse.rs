@@ -2,9 +2,71 @@
 // This module handles parsing IMAP FETCH responses into EmailHeader structs
 
 use crate::commands::emails::codec::{
-    check_for_attachments, decode_bytes_to_string, decode_header, parse_email_date_with_fallback,
+    check_for_attachments, decode_bytes_to_string, decode_header, detect_security,
+    format_address_list, message_ids, parse_email_date_with_fallback, parse_header_block,
 };
 use crate::models::EmailHeader;
+use std::net::IpAddr;
+
+/// Raw header fields fetched during sync and stored for header search and threading
+pub const INDEXED_HEADER_FIELDS: &str = "LIST-ID X-MAILER RETURN-PATH AUTHENTICATION-RESULTS \
+     RECEIVED DELIVERED-TO X-ORIGINAL-TO IN-REPLY-TO REFERENCES";
+
+/// Pick the searchable headers out of a `BODY[HEADER.FIELDS (...)]` block
+///
+/// Names are lowercased. `Received` headers are reduced to the IP addresses they name,
+/// stored as `received-ip`; `In-Reply-To` and `References` keep only their message IDs.
+pub fn extract_indexed_headers(raw: &[u8]) -> Vec<(String, String)> {
+    let mut indexed: Vec<(String, String)> = Vec::new();
+
+    for (name, value) in parse_header_block(raw) {
+        let name = name.to_ascii_lowercase();
+        match name.as_str() {
+            "received" => {
+                for ip in received_ips(&value) {
+                    let entry = ("received-ip".to_string(), ip);
+                    if !indexed.contains(&entry) {
+                        indexed.push(entry);
+                    }
+                }
+            }
+            "list-id"
+            | "x-mailer"
+            | "return-path"
+            | "authentication-results"
+            | "delivered-to"
+            | "x-original-to"
+            | "reply-to" => {
+                indexed.push((name, decode_header(&value)));
+            }
+            "in-reply-to" | "references" => {
+                let ids = message_ids(&value);
+                if !ids.is_empty() {
+                    indexed.push((name, ids.join(" ")));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    indexed
+}
+
+/// IP addresses in brackets, e.g. `from mx.example.com ([192.0.2.1])` or `[IPv6:2001:db8::1]`
+fn received_ips(value: &str) -> Vec<String> {
+    value
+        .split('[')
+        .skip(1)
+        .filter_map(|part| part.split(']').next())
+        .map(|candidate| {
+            candidate
+                .trim_start_matches("IPv6:")
+                .trim_start_matches("ipv6:")
+        })
+        .filter_map(|candidate| candidate.parse::<IpAddr>().ok())
+        .map(|ip| ip.to_string())
+        .collect()
+}
 
 /// Helper function to parse IMAP fetch results into EmailHeader
 /// In imap 3.0.0, Fetch type requires lifetime parameter
@@ -71,53 +133,28 @@ where
 
         let to = envelope
             .to
-            .as_ref()
-            .map(|addrs| {
-                addrs
-                    .iter()
-                    .map(|addr| {
-                        if let Some(ref name_bytes) = addr.name {
-                            let name = decode_bytes_to_string(name_bytes.as_ref());
-                            if !name.trim().is_empty() {
-                                return decode_header(&name);
-                            }
-                        }
-                        let mailbox = decode_bytes_to_string(
-                            addr.mailbox.clone().unwrap_or_default().as_ref(),
-                        );
-                        let host =
-                            decode_bytes_to_string(addr.host.clone().unwrap_or_default().as_ref());
-                        format!("{}@{}", mailbox, host)
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            })
+            .as_deref()
+            .map(format_address_list)
             .unwrap_or_else(|| "(Unknown Recipient)".to_string());
 
         let cc = envelope
             .cc
+            .as_deref()
+            .map(format_address_list)
+            .unwrap_or_default();
+
+        // Only present on copies the account sent itself (e.g. in Sent)
+        let bcc = envelope
+            .bcc
+            .as_deref()
+            .map(format_address_list)
+            .unwrap_or_default();
+
+        let message_id = envelope
+            .message_id
             .as_ref()
-            .map(|addrs| {
-                addrs
-                    .iter()
-                    .map(|addr| {
-                        if let Some(ref name_bytes) = addr.name {
-                            let name = decode_bytes_to_string(name_bytes.as_ref());
-                            if !name.trim().is_empty() {
-                                return decode_header(&name);
-                            }
-                        }
-                        let mailbox = decode_bytes_to_string(
-                            addr.mailbox.clone().unwrap_or_default().as_ref(),
-                        );
-                        let host =
-                            decode_bytes_to_string(addr.host.clone().unwrap_or_default().as_ref());
-                        format!("{}@{}", mailbox, host)
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            })
-            .unwrap_or_else(|| "".to_string());
+            .map(|id| decode_bytes_to_string(id.as_ref()).trim().to_string())
+            .filter(|id| !id.is_empty());
 
         // Check if email has attachments by examining BODYSTRUCTURE
         let has_attachments = msg
@@ -143,11 +180,19 @@ where
             from,
             to,
             cc,
+            bcc,
             date,
             timestamp,
             has_attachments,
             seen,
             flagged,
+            message_id,
+            received_at: msg.internal_date().map(|d| d.timestamp()),
+            security: msg.bodystructure().and_then(detect_security),
+            raw_headers: msg
+                .header()
+                .map(extract_indexed_headers)
+                .unwrap_or_default(),
         });
     }
 
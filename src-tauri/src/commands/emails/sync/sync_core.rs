@@ -1,26 +1,64 @@
 // Core synchronization logic
 // This module handles the incremental sync algorithm and deletion detection
 
-use super::sync_fetch::{fetch_all_by_sequence, fetch_new_by_uid_list};
-use super::sync_state::{get_sync_state, update_sync_state};
+use super::sync_fetch::{fetch_all_by_sequence, fetch_new_by_uid_list, BatchSender};
+use super::sync_state::{
+    clear_sync_checkpoint, get_sync_checkpoint, get_sync_state, save_sync_checkpoint,
+    update_sync_state, SyncCheckpoint,
+};
+use super::sync_window::{load_sync_window, window_uids, SyncWindow};
 use crate::commands::emails::cache::save_emails_to_cache;
 use crate::commands::emails::imap_helpers;
 use crate::commands::utils::ensure_valid_token;
 use crate::db;
 use crate::models::{AccountConfig, EmailHeader};
+use crate::watchdog::Heartbeat;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Number of parsed batches that may wait for the cache writer at any time.
+/// Together with `MAX_BATCH_SIZE` this bounds memory during a full sync.
+const MAX_BATCHES_IN_FLIGHT: usize = 2;
+
+/// Outcome of an incremental sync
+pub struct SyncSummary {
+    pub new_count: usize,
+    pub max_uid: Option<u32>,
+}
+
+/// Why an incremental sync failed
+#[derive(Debug)]
+pub enum SyncError {
+    /// The server refused to SELECT the folder (NO), as opposed to a connection problem
+    FolderRefused(String),
+    Failed(String),
+}
+
+impl From<String> for SyncError {
+    fn from(message: String) -> Self {
+        SyncError::Failed(message)
+    }
+}
 
 /// Perform incremental synchronization using UIDVALIDITY and UIDs
+///
+/// New headers are streamed from the blocking IMAP task to an async cache writer
+/// batch by batch, so memory stays flat regardless of mailbox size.
+/// The heartbeat is beaten for every saved batch and at each phase boundary.
 pub async fn incremental_sync(
     config: AccountConfig,
     account_id: i32,
     folder_name: &str,
-) -> Result<Vec<EmailHeader>, String> {
+    heartbeat: Heartbeat,
+) -> Result<SyncSummary, SyncError> {
     // Ensure we have a valid access token
     let config = ensure_valid_token(config).await?;
 
     // Get cached sync state (UIDVALIDITY and highest UID)
     let sync_state = get_sync_state(account_id, folder_name).await?;
     let sync_state_for_task = sync_state.clone();
+    let checkpoint = get_sync_checkpoint(account_id, folder_name).await?;
+    let sync_window = load_sync_window(account_id).await?;
     let folder_name_owned = folder_name.to_string();
     let config_for_uid_check = config.clone();
 
@@ -42,122 +80,180 @@ pub async fn incremental_sync(
         "Cache state"
     );
 
-    // Connect to IMAP and check current state
-    let (server_uidvalidity, _server_exists, new_emails) =
-        tokio::task::spawn_blocking(move || -> Result<(u32, u32, Vec<EmailHeader>), String> {
-            // Use new imap_helpers to connect and login
-            let mut imap_session = imap_helpers::connect_and_login(&config)?;
+    // UIDVALIDITY of a running full sync; non-zero tells the writer to record checkpoints
+    let checkpoint_uidvalidity = Arc::new(AtomicU32::new(0));
+    let checkpoint_uidvalidity_for_task = checkpoint_uidvalidity.clone();
+
+    // Cache writer: saves each batch as soon as the fetch loop hands it over
+    let (batch_tx, mut batch_rx) =
+        tokio::sync::mpsc::channel::<Vec<EmailHeader>>(MAX_BATCHES_IN_FLIGHT);
+    let folder_name_for_writer = folder_name.to_string();
+    let heartbeat_for_writer = heartbeat.clone();
+    let writer = tokio::spawn(async move {
+        let mut saved_count = 0usize;
+        let mut max_uid: Option<u32> = None;
+        while let Some(batch) = batch_rx.recv().await {
+            save_emails_to_cache(account_id, &folder_name_for_writer, &batch).await?;
+            heartbeat_for_writer.beat();
+            saved_count += batch.len();
+            max_uid = batch.iter().map(|e| e.uid).max().max(max_uid);
+
+            // Full syncs fetch in ascending UID order, so everything up to max_uid is cached
+            let uidvalidity = checkpoint_uidvalidity.load(Ordering::Relaxed);
+            if let (true, Some(last_uid)) = (uidvalidity != 0, max_uid) {
+                save_sync_checkpoint(
+                    account_id,
+                    &folder_name_for_writer,
+                    uidvalidity as i64,
+                    last_uid as i64,
+                )
+                .await?;
+            }
+        }
+        Ok::<(usize, Option<u32>), String>((saved_count, max_uid))
+    });
 
-            tracing::info!("IMAP authentication successful");
+    // Connect to IMAP and check current state
+    let fetch_result = tokio::task::spawn_blocking(move || -> Result<(u32, u32, usize), SyncError> {
+        // Use new imap_helpers to connect and login
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
 
-            // SELECT the folder and get UIDVALIDITY
-            let mailbox = imap_session
-                .select(&folder_name_owned)
-                .map_err(|e| format!("Cannot access folder '{}': {}", folder_name_owned, e))?;
+        tracing::info!("IMAP authentication successful");
 
-            let server_uidvalidity = mailbox.uid_validity.unwrap_or(0);
-            let server_exists = mailbox.exists;
+        // SELECT the folder and get UIDVALIDITY
+        let mailbox = imap_session.select(&folder_name_owned).map_err(|e| {
+            let message = format!("Cannot access folder '{}': {}", folder_name_owned, e);
+            if matches!(e, imap::Error::No(_)) {
+                SyncError::FolderRefused(message)
+            } else {
+                SyncError::Failed(message)
+            }
+        })?;
+
+        let server_uidvalidity = mailbox.uid_validity.unwrap_or(0);
+        let server_exists = mailbox.exists;
+
+        tracing::info!(
+            uidvalidity = server_uidvalidity,
+            exists = server_exists,
+            "Server state"
+        );
+
+        // Determine sync strategy based on UIDVALIDITY
+        let fetched_count = if let Some(ref sync_state) = sync_state_for_task {
+            if sync_state.uidvalidity != Some(server_uidvalidity as i64) {
+                // Full sync needed: UIDVALIDITY changed
+                tracing::warn!("UIDVALIDITY changed! Full resync required");
+
+                checkpoint_uidvalidity_for_task.store(server_uidvalidity, Ordering::Relaxed);
+                fetch_full_or_resume(
+                    &mut imap_session,
+                    server_uidvalidity,
+                    server_exists,
+                    checkpoint.as_ref(),
+                    &sync_window,
+                    &config,
+                    &folder_name_owned,
+                    &batch_tx,
+                )?
+            } else {
+                // Incremental sync: fetch only new messages
+                let highest_uid = sync_state.highest_uid.unwrap_or(0);
 
-            tracing::info!(
-                uidvalidity = server_uidvalidity,
-                exists = server_exists,
-                "Server state"
-            );
+                tracing::info!(highest_uid = highest_uid, "Incremental sync from UID");
 
-            // Determine sync strategy based on UIDVALIDITY
-            let new_emails = if let Some(ref sync_state) = sync_state_for_task {
-                if sync_state.uidvalidity != Some(server_uidvalidity as i64) {
-                    // Full sync needed: UIDVALIDITY changed
-                    tracing::warn!("UIDVALIDITY changed! Full resync required");
-
-                    fetch_all_by_sequence(
-                        &mut imap_session,
-                        server_exists,
-                        &config,
-                        &folder_name_owned,
-                    )?
+                if highest_uid == 0 || server_exists == 0 {
+                    // No previous emails or empty folder
+                    0
                 } else {
-                    // Incremental sync: fetch only new messages
-                    let highest_uid = sync_state.highest_uid.unwrap_or(0);
-
-                    tracing::info!(highest_uid = highest_uid, "Incremental sync from UID");
+                    // First, use UID SEARCH to find if there are any new messages
+                    // This avoids Gmail's bug where UID FETCH with reversed range returns old messages
+                    let search_criteria = format!("UID {}:*", highest_uid + 1);
+
+                    tracing::debug!(criteria = %search_criteria, "Searching for new messages");
+
+                    let search_result = match imap_session.uid_search(&search_criteria) {
+                        Ok(uids) => {
+                            // Convert HashSet to Vec and sort
+                            let mut uid_vec: Vec<u32> = uids.into_iter().collect();
+                            uid_vec.sort_unstable();
+                            uid_vec
+                        }
+                        Err(e) => {
+                            tracing::warn!(error = %e, "UID SEARCH failed, falling back to FETCH");
+                            Vec::new()
+                        }
+                    };
 
-                    if highest_uid == 0 || server_exists == 0 {
-                        // No previous emails or empty folder
-                        Vec::new()
+                    if search_result.is_empty() {
+                        tracing::debug!("No new messages (SEARCH returned empty)");
+                        0
                     } else {
-                        // First, use UID SEARCH to find if there are any new messages
-                        // This avoids Gmail's bug where UID FETCH with reversed range returns old messages
-                        let search_criteria = format!("UID {}:*", highest_uid + 1);
-
-                        tracing::debug!(criteria = %search_criteria, "Searching for new messages");
-
-                        let search_result = match imap_session.uid_search(&search_criteria) {
-                            Ok(uids) => {
-                                // Convert HashSet to Vec and sort
-                                let mut uid_vec: Vec<u32> = uids.into_iter().collect();
-                                uid_vec.sort_unstable();
-                                uid_vec
-                            }
-                            Err(e) => {
-                                tracing::warn!(error = %e, "UID SEARCH failed, falling back to FETCH");
-                                Vec::new()
-                            }
-                        };
-
-                        if search_result.is_empty() {
-                            tracing::debug!("No new messages (SEARCH returned empty)");
-                            Vec::new()
+                        tracing::info!(
+                            count = search_result.len(),
+                            uids = ?search_result,
+                            "SEARCH found UIDs"
+                        );
+
+                        // Filter out UIDs <= highest_uid (Gmail bug workaround)
+                        let new_uids: Vec<u32> = search_result
+                            .into_iter()
+                            .filter(|&uid| uid > highest_uid as u32)
+                            .collect();
+
+                        if new_uids.is_empty() {
+                            tracing::debug!("No genuinely new messages after filtering");
+                            0
                         } else {
-                            tracing::info!(
-                                count = search_result.len(),
-                                uids = ?search_result,
-                                "SEARCH found UIDs"
-                            );
-
-                            // Filter out UIDs <= highest_uid (Gmail bug workaround)
-                            let new_uids: Vec<u32> = search_result
-                                .into_iter()
-                                .filter(|&uid| uid > highest_uid as u32)
-                                .collect();
-
-                            if new_uids.is_empty() {
-                                tracing::debug!("No genuinely new messages after filtering");
-                                Vec::new()
-                            } else {
-                                fetch_new_by_uid_list(&mut imap_session, new_uids, highest_uid)?
-                            }
+                            fetch_new_by_uid_list(
+                                &mut imap_session,
+                                new_uids,
+                                highest_uid,
+                                &batch_tx,
+                            )?
                         }
                     }
                 }
-            } else {
-                // Full sync needed: no previous state
-                tracing::info!("First sync for this folder");
-
-                fetch_all_by_sequence(
-                    &mut imap_session,
-                    server_exists,
-                    &config,
-                    &folder_name_owned,
-                )?
-            };
-
-            let _ = imap_session.logout();
-            Ok((server_uidvalidity, server_exists, new_emails))
-        })
-        .await
-        .map_err(|e| e.to_string())??;
-
-    tracing::info!(count = new_emails.len(), "Fetched new emails from server");
+            }
+        } else {
+            // Full sync needed: no previous state
+            tracing::info!("First sync for this folder");
+
+            checkpoint_uidvalidity_for_task.store(server_uidvalidity, Ordering::Relaxed);
+            fetch_full_or_resume(
+                &mut imap_session,
+                server_uidvalidity,
+                server_exists,
+                checkpoint.as_ref(),
+                &sync_window,
+                &config,
+                &folder_name_owned,
+                &batch_tx,
+            )?
+        };
 
-    // Save new emails to cache
-    if !new_emails.is_empty() {
-        save_emails_to_cache(account_id, folder_name, &new_emails).await?;
-    }
+        let _ = imap_session.logout();
+        Ok((server_uidvalidity, server_exists, fetched_count))
+    })
+    .await
+    .map_err(|e| e.to_string());
+
+    // The sender is dropped with the blocking task, so the writer drains and finishes.
+    // A writer error takes precedence: it is the reason the fetch loop stopped.
+    let (saved_count, new_emails_max_uid) = writer.await.map_err(|e| e.to_string())??;
+    let (server_uidvalidity, _server_exists, fetched_count) = fetch_result??;
+    heartbeat.beat();
+
+    tracing::info!(
+        fetched = fetched_count,
+        saved = saved_count,
+        "Fetched new emails from server"
+    );
 
     // Get all UIDs currently on server to detect deletions
     tracing::debug!("Checking for deleted emails...");
     let server_uids = get_all_server_uids(config_for_uid_check, folder_name).await?;
+    heartbeat.beat();
 
     // Delete emails from cache that no longer exist on server
     let deleted_count =
@@ -173,7 +269,6 @@ pub async fn incremental_sync(
     // 2. Querying all server UIDs (second IMAP connection)
     // If a new email arrives in between, server_max_uid will be higher than what we actually got
 
-    let new_emails_max_uid = new_emails.iter().map(|e| e.uid).max();
     let server_max_uid = server_uids.iter().copied().max();
     let previous_highest_uid = sync_state
         .as_ref()
@@ -202,7 +297,66 @@ pub async fn incremental_sync(
     )
     .await?;
 
-    Ok(new_emails)
+    // The sync state now covers everything a checkpoint could
+    clear_sync_checkpoint(account_id, folder_name).await?;
+
+    Ok(SyncSummary {
+        new_count: saved_count,
+        max_uid: new_emails_max_uid,
+    })
+}
+
+/// Fetch all messages for a full sync (or those inside the account's sync window), or only
+/// those after a matching checkpoint
+///
+/// A checkpoint from an interrupted run is only trusted if UIDVALIDITY is unchanged.
+fn fetch_full_or_resume(
+    imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
+    server_uidvalidity: u32,
+    server_exists: u32,
+    checkpoint: Option<&SyncCheckpoint>,
+    sync_window: &SyncWindow,
+    config: &AccountConfig,
+    folder_name: &str,
+    batch_tx: &BatchSender,
+) -> Result<usize, String> {
+    let resume_from = checkpoint
+        .filter(|cp| cp.uidvalidity == server_uidvalidity as i64 && cp.last_uid > 0)
+        .map(|cp| cp.last_uid);
+
+    let Some(last_uid) = resume_from else {
+        if sync_window.is_unlimited() || server_exists == 0 {
+            return fetch_all_by_sequence(
+                imap_session,
+                server_exists,
+                config,
+                folder_name,
+                batch_tx,
+            );
+        }
+
+        // Window UIDs ascend like a full sync's, so checkpoints and resuming work the same
+        let uids = window_uids(imap_session, sync_window)?;
+        tracing::info!(
+            window = ?sync_window,
+            count = uids.len(),
+            exists = server_exists,
+            "Full sync limited to sync window"
+        );
+        return fetch_new_by_uid_list(imap_session, uids, 0, batch_tx);
+    };
+
+    tracing::info!(last_uid = last_uid, "Resuming interrupted full sync");
+
+    let mut remaining: Vec<u32> = imap_session
+        .uid_search(&format!("UID {}:*", last_uid + 1))
+        .map_err(|e| format!("Failed to search for remaining messages: {}", e))?
+        .into_iter()
+        .filter(|&uid| uid > last_uid as u32)
+        .collect();
+    remaining.sort_unstable();
+
+    fetch_new_by_uid_list(imap_session, remaining, last_uid, batch_tx)
 }
 
 /// Get all UIDs currently on server
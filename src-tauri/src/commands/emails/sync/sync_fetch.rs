@@ -1,32 +1,60 @@
 // IMAP batch fetching logic with retry and reconnection mechanisms
 // This module handles fetching emails in batches with adaptive batch sizing
 
-use super::parse::parse_email_headers;
+use super::parse::{parse_email_headers, INDEXED_HEADER_FIELDS};
 use crate::commands::emails::imap_helpers;
 use crate::models::{AccountConfig, EmailHeader};
 
+/// Upper bound for adaptive batch sizes, so a single FETCH response never holds
+/// more than this many parsed headers in memory
+pub const MAX_BATCH_SIZE: u32 = 500;
+
+/// FETCH items for header sync: envelope data plus the raw headers kept for header search
+fn header_fetch_items() -> String {
+    format!(
+        "(UID ENVELOPE FLAGS INTERNALDATE RFC822.SIZE BODY.PEEK[HEADER.FIELDS ({})])",
+        INDEXED_HEADER_FIELDS
+    )
+}
+
+/// Channel used to stream parsed header batches to the async cache writer.
+/// It is bounded, so the fetch loop blocks while the writer catches up.
+pub type BatchSender = tokio::sync::mpsc::Sender<Vec<EmailHeader>>;
+
+/// Hand a parsed batch to the cache writer (blocks if the channel is full)
+fn send_batch(batch_tx: &BatchSender, batch: Vec<EmailHeader>) -> Result<(), String> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    batch_tx
+        .blocking_send(batch)
+        .map_err(|_| "Cache writer stopped unexpectedly".to_string())
+}
+
 /// Check if an IMAP error is a connection error (Bye) that requires reconnection
 pub fn is_connection_error(error: &imap::Error) -> bool {
     matches!(error, imap::Error::Bye(_))
 }
 
 /// Fetch all emails from folder using sequence numbers (full sync)
-/// Uses adaptive batch sizing with reconnection on Bye errors
+/// Uses adaptive batch sizing with reconnection on Bye errors.
+/// Each parsed batch is streamed to `batch_tx`; returns the number of headers fetched.
 pub fn fetch_all_by_sequence(
     imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
     server_exists: u32,
     config: &AccountConfig,
     folder_name: &str,
-) -> Result<Vec<EmailHeader>, String> {
+    batch_tx: &BatchSender,
+) -> Result<usize, String> {
     if server_exists == 0 {
-        return Ok(Vec::new());
+        return Ok(0);
     }
 
     // Fetch in batches to avoid overwhelming the IMAP server and parser
     // Start with batch size 20, increase exponentially until hitting server limit
     let mut batch_size = 20u32;
     let mut max_batch_size: Option<u32> = None; // Lock batch size after first Bye error
-    let mut all_headers = Vec::new();
+    let mut fetched_count = 0usize;
     let mut current_pos = 1u32;
 
     println!(
@@ -47,18 +75,16 @@ pub fn fetch_all_by_sequence(
         );
 
         // Fetch without BODYSTRUCTURE (causes issues with GMX)
-        match imap_session.fetch(
-            seq_range.as_str(),
-            "(UID ENVELOPE FLAGS INTERNALDATE RFC822.SIZE)",
-        ) {
+        match imap_session.fetch(seq_range.as_str(), header_fetch_items()) {
             Ok(messages) => {
                 let batch_headers = parse_email_headers(messages.iter());
-                all_headers.extend(batch_headers);
+                drop(messages);
+                fetched_count += batch_headers.len();
+                send_batch(batch_tx, batch_headers)?;
 
                 println!(
                     "  ✓ Batch {} complete, {} total emails so far",
-                    batch_num,
-                    all_headers.len()
+                    batch_num, fetched_count
                 );
 
                 current_pos = end_seq + 1;
@@ -73,9 +99,9 @@ pub fn fetch_all_by_sequence(
                             batch_size, max
                         );
                     }
-                } else {
-                    // No limit yet, keep doubling
-                    batch_size *= 2;
+                } else if batch_size < MAX_BATCH_SIZE {
+                    // No limit yet, keep doubling up to the memory cap
+                    batch_size = (batch_size * 2).min(MAX_BATCH_SIZE);
                     println!("  📈 Increasing batch size to {}", batch_size);
                 }
             }
@@ -156,26 +182,24 @@ pub fn fetch_all_by_sequence(
         }
     }
 
-    // Sort by timestamp descending (newest first)
-    all_headers.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-
     println!(
         "✅ Fetched {} emails in {} batch(es)",
-        all_headers.len(),
-        batch_num
+        fetched_count, batch_num
     );
-    Ok(all_headers)
+    Ok(fetched_count)
 }
 
 /// Fetch new emails by UID list (incremental sync)
-/// Uses adaptive batch sizing with retry on errors
+/// Uses adaptive batch sizing with retry on errors.
+/// Each parsed batch is streamed to `batch_tx`; returns the number of new headers fetched.
 pub fn fetch_new_by_uid_list(
     imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
     new_uids: Vec<u32>,
     highest_uid: i64,
-) -> Result<Vec<EmailHeader>, String> {
+    batch_tx: &BatchSender,
+) -> Result<usize, String> {
     if new_uids.is_empty() {
-        return Ok(Vec::new());
+        return Ok(0);
     }
 
     println!(
@@ -187,7 +211,7 @@ pub fn fetch_new_by_uid_list(
     // Start with batch size 20, increase exponentially until hitting server limit
     let mut batch_size = 20usize;
     let mut max_batch_size: Option<usize> = None; // Lock batch size after first Bye error
-    let mut all_new_headers = Vec::new();
+    let mut fetched_count = 0usize;
     let mut current_idx = 0usize;
     let total_count = new_uids.len();
 
@@ -211,7 +235,7 @@ pub fn fetch_new_by_uid_list(
             .join(",");
 
         // Fetch without BODYSTRUCTURE (causes issues with GMX)
-        match imap_session.uid_fetch(&uid_list, "(UID ENVELOPE FLAGS INTERNALDATE RFC822.SIZE)") {
+        match imap_session.uid_fetch(&uid_list, header_fetch_items()) {
             Ok(messages) => {
                 let count = messages.len();
                 if count > 0 {
@@ -248,15 +272,16 @@ pub fn fetch_new_by_uid_list(
                         );
                     }
 
-                    all_new_headers.extend(filtered);
+                    drop(messages);
+                    fetched_count += filtered.len();
+                    send_batch(batch_tx, filtered)?;
                 } else {
                     println!("  ✅ Batch {} returned no messages", batch_num);
                 }
 
                 println!(
                     "  ✓ Batch {} complete, {} total new emails so far",
-                    batch_num,
-                    all_new_headers.len()
+                    batch_num, fetched_count
                 );
 
                 // Move to next batch
@@ -272,9 +297,9 @@ pub fn fetch_new_by_uid_list(
                             batch_size, max
                         );
                     }
-                } else {
-                    // No limit yet, keep doubling
-                    batch_size *= 2;
+                } else if batch_size < MAX_BATCH_SIZE as usize {
+                    // No limit yet, keep doubling up to the memory cap
+                    batch_size = (batch_size * 2).min(MAX_BATCH_SIZE as usize);
                     println!("  📈 Increasing batch size to {}", batch_size);
                 }
             }
@@ -313,14 +338,14 @@ pub fn fetch_new_by_uid_list(
         }
     }
 
-    if all_new_headers.is_empty() {
+    if fetched_count == 0 {
         println!("✅ No new messages after fetching all batches");
     } else {
         println!(
             "✨ Total {} genuinely new message(s) from all batches",
-            all_new_headers.len()
+            fetched_count
         );
     }
 
-    Ok(all_new_headers)
+    Ok(fetched_count)
 }
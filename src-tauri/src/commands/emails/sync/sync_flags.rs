@@ -1,12 +1,240 @@
 // Email flag synchronization
 // This module handles syncing read/starred flags between server and cache
+// On servers with CONDSTORE (RFC 7162) only messages whose flags changed since the last sync
+// are fetched, using the folder's HIGHESTMODSEQ stored in sync_status. With QRESYNC the
+// server also reports messages expunged since then (VANISHED), which are dropped from cache.
+// On Gmail the same pass refreshes thread IDs and labels.
 
+use super::sync_state::{get_sync_state, save_highest_modseq};
+use crate::commands::emails::gmail::{self, GmailMetadata};
 use crate::commands::emails::imap_helpers;
 use crate::commands::utils::ensure_valid_token;
 use crate::db;
 use crate::models::AccountConfig;
+use imap_proto::types::{AttributeValue, Response, ResponseCode};
+use std::ops::RangeInclusive;
 use tauri::command;
 
+type ImapSession = imap::Session<Box<dyn imap::ImapConnection>>;
+
+/// Flags fetched from the server, as (uid, seen, flagged)
+type FlagList = Vec<(u32, bool, bool)>;
+
+/// What the untagged responses of a raw CONDSTORE SELECT or FETCH reported
+#[derive(Default, Debug)]
+struct CondstoreResponse {
+    uid_validity: Option<u32>,
+    highest_mod_seq: Option<u64>,
+    flags: FlagList,
+    vanished: Vec<RangeInclusive<u32>>,
+}
+
+/// Flags and expunged messages found by a flag sync
+struct FlagChanges {
+    flags: FlagList,
+    vanished: Vec<RangeInclusive<u32>>,
+    mod_seq: Option<(u32, u64)>, // (UIDVALIDITY, HIGHESTMODSEQ) at the time of SELECT
+    incremental: bool,
+    gmail: Vec<GmailMetadata>,
+}
+
+/// Parse the raw response of a `SELECT ... (CONDSTORE)` or `UID FETCH ... (CHANGEDSINCE n)`
+fn parse_condstore_response(raw: &[u8]) -> CondstoreResponse {
+    let mut parsed = CondstoreResponse::default();
+    let mut rest = raw;
+
+    while !rest.is_empty() {
+        match imap_proto::parser::parse_response(rest) {
+            Ok((remaining, response)) => {
+                match response {
+                    Response::Data {
+                        code: Some(ResponseCode::UidValidity(uid_validity)),
+                        ..
+                    } => parsed.uid_validity = Some(uid_validity),
+                    Response::Data {
+                        code: Some(ResponseCode::HighestModSeq(mod_seq)),
+                        ..
+                    } => parsed.highest_mod_seq = Some(mod_seq),
+                    Response::Fetch(_, attributes) => {
+                        let mut uid = None;
+                        let (mut seen, mut flagged) = (false, false);
+                        for attribute in attributes {
+                            match attribute {
+                                AttributeValue::Uid(value) => uid = Some(value),
+                                AttributeValue::Flags(flags) => {
+                                    seen = flags.iter().any(|f| f.eq_ignore_ascii_case("\\Seen"));
+                                    flagged =
+                                        flags.iter().any(|f| f.eq_ignore_ascii_case("\\Flagged"));
+                                }
+                                _ => {}
+                            }
+                        }
+                        if let Some(uid) = uid {
+                            parsed.flags.push((uid, seen, flagged));
+                        }
+                    }
+                    Response::Vanished { uids, .. } => parsed.vanished.extend(uids),
+                    _ => {}
+                }
+                rest = remaining;
+            }
+            // Skip lines we can't parse instead of failing the sync
+            Err(_) => match rest.windows(2).position(|w| w == b"\r\n") {
+                Some(pos) => rest = &rest[pos + 2..],
+                None => break,
+            },
+        }
+    }
+
+    parsed
+}
+
+/// Fetch the flags of the given UIDs in batches
+fn fetch_all_flags(imap_session: &mut ImapSession, uids: &[u32]) -> FlagList {
+    let mut all_flags = Vec::new();
+
+    // Process in batches of 100 to avoid overwhelming the server
+    for chunk in uids.chunks(100) {
+        let uid_list = chunk
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        match imap_session.uid_fetch(&uid_list, "(UID FLAGS)") {
+            Ok(messages) => {
+                for msg in messages.iter() {
+                    let uid = msg.uid.unwrap_or(0);
+                    let seen = msg
+                        .flags()
+                        .iter()
+                        .any(|flag| matches!(flag, imap::types::Flag::Seen));
+                    let flagged = msg
+                        .flags()
+                        .iter()
+                        .any(|flag| matches!(flag, imap::types::Flag::Flagged));
+                    all_flags.push((uid, seen, flagged));
+                }
+            }
+            Err(e) => {
+                eprintln!("⚠️ Failed to fetch flags for batch: {}", e);
+                // Continue with other batches
+            }
+        }
+    }
+
+    all_flags
+}
+
+/// Fetch changed flags from the server, incrementally when CONDSTORE allows it
+/// `stored` is the (UIDVALIDITY, HIGHESTMODSEQ) recorded by the previous flag sync
+fn fetch_flag_changes(
+    imap_session: &mut ImapSession,
+    folder_name: &str,
+    cached_uids: &[u32],
+    stored: Option<(i64, i64)>,
+) -> Result<FlagChanges, String> {
+    let capabilities = imap_session
+        .capabilities()
+        .map_err(|e| format!("Failed to get capabilities: {}", e))?;
+    let qresync = capabilities.has_str("QRESYNC");
+    let condstore = qresync || capabilities.has_str("CONDSTORE");
+    let gmail = capabilities.has_str("X-GM-EXT-1");
+    let all_cached = imap_helpers::uid_set(cached_uids);
+
+    if !condstore {
+        imap_session
+            .select(folder_name)
+            .map_err(|e| format!("Cannot select folder: {}", e))?;
+        return Ok(FlagChanges {
+            flags: fetch_all_flags(imap_session, cached_uids),
+            vanished: Vec::new(),
+            mod_seq: None,
+            incremental: false,
+            gmail: fetch_gmail(imap_session, gmail, &all_cached, None),
+        });
+    }
+
+    // QRESYNC has to be enabled before SELECT for the server to report VANISHED
+    let qresync = qresync
+        && match imap_session.run_command_and_read_response("ENABLE QRESYNC") {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("⚠️ ENABLE QRESYNC failed, continuing without it: {}", e);
+                false
+            }
+        };
+
+    let raw = imap_session
+        .run_command_and_read_response(format!(
+            "SELECT {} (CONDSTORE)",
+            imap_helpers::quote_mailbox(folder_name)
+        ))
+        .map_err(|e| format!("Cannot select folder: {}", e))?;
+    let selected = parse_condstore_response(&raw);
+    // Mailboxes that can't store mod-sequences answer with NOMODSEQ instead
+    let mod_seq = selected.uid_validity.zip(selected.highest_mod_seq);
+
+    let since = match (mod_seq, stored) {
+        (Some((uid_validity, _)), Some((stored_validity, stored_mod_seq)))
+            if uid_validity as i64 == stored_validity && stored_mod_seq > 0 =>
+        {
+            stored_mod_seq as u64
+        }
+        _ => {
+            return Ok(FlagChanges {
+                flags: fetch_all_flags(imap_session, cached_uids),
+                vanished: Vec::new(),
+                mod_seq,
+                incremental: false,
+                gmail: fetch_gmail(imap_session, gmail, &all_cached, None),
+            })
+        }
+    };
+
+    let mut changes = FlagChanges {
+        flags: Vec::new(),
+        vanished: Vec::new(),
+        mod_seq,
+        incremental: true,
+        gmail: Vec::new(),
+    };
+    if mod_seq.is_some_and(|(_, highest)| highest <= since) {
+        println!("✅ No flag changes since MODSEQ {}", since);
+        return Ok(changes);
+    }
+
+    let modifiers = if qresync {
+        format!("CHANGEDSINCE {} VANISHED", since)
+    } else {
+        format!("CHANGEDSINCE {}", since)
+    };
+    let raw = imap_session
+        .run_command_and_read_response(format!("UID FETCH 1:* (UID FLAGS) ({})", modifiers))
+        .map_err(|e| format!("Failed to fetch changed flags: {}", e))?;
+    let fetched = parse_condstore_response(&raw);
+    changes.flags = fetched.flags;
+    changes.vanished = fetched.vanished;
+    changes.gmail = fetch_gmail(imap_session, gmail, "1:*", Some(since));
+    Ok(changes)
+}
+
+/// Fetch Gmail thread IDs and labels if the server has them; failures only warn
+fn fetch_gmail(
+    imap_session: &mut ImapSession,
+    enabled: bool,
+    uid_set: &str,
+    changed_since: Option<u64>,
+) -> Vec<GmailMetadata> {
+    if !enabled {
+        return Vec::new();
+    }
+    gmail::fetch_gmail_metadata(imap_session, uid_set, changed_since).unwrap_or_else(|e| {
+        eprintln!("⚠️ {}", e);
+        Vec::new()
+    })
+}
+
 /// Sync flags (seen, flagged) for existing emails in cache
 /// This ensures local cache stays in sync with server when flags change from other clients
 /// This is called when IDLE detects flag changes, not during regular incremental sync
@@ -37,64 +265,42 @@ pub async fn sync_email_flags(
         return Ok(());
     }
 
+    let stored = get_sync_state(account_id, folder_name)
+        .await?
+        .and_then(|state| state.uidvalidity.zip(state.highest_modseq));
+
     let email_count = cached_uids.len();
     println!("🔄 Syncing flags for {} cached emails", email_count);
     let fetch_start = std::time::Instant::now();
 
     let folder_name_owned = folder_name.to_string();
+    let uids_for_task = cached_uids.clone();
 
-    // Fetch flags from server in batches
-    let flags_data =
-        tokio::task::spawn_blocking(move || -> Result<Vec<(u32, bool, bool)>, String> {
-            let mut imap_session = imap_helpers::connect_and_login(&config)?;
-
-            imap_session
-                .select(&folder_name_owned)
-                .map_err(|e| format!("Cannot select folder: {}", e))?;
-
-            let mut all_flags = Vec::new();
-
-            // Process in batches of 100 to avoid overwhelming the server
-            for chunk in cached_uids.chunks(100) {
-                let uid_list = chunk
-                    .iter()
-                    .map(|uid| uid.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",");
-
-                match imap_session.uid_fetch(&uid_list, "(UID FLAGS)") {
-                    Ok(messages) => {
-                        for msg in messages.iter() {
-                            let uid = msg.uid.unwrap_or(0);
-                            let seen = msg
-                                .flags()
-                                .iter()
-                                .any(|flag| matches!(flag, imap::types::Flag::Seen));
-                            let flagged = msg
-                                .flags()
-                                .iter()
-                                .any(|flag| matches!(flag, imap::types::Flag::Flagged));
-                            all_flags.push((uid, seen, flagged));
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("⚠️ Failed to fetch flags for batch: {}", e);
-                        // Continue with other batches
-                    }
-                }
-            }
-
-            let _ = imap_session.logout();
-            Ok(all_flags)
-        })
-        .await
-        .map_err(|e| e.to_string())??;
+    // Fetch flags from server
+    let changes = tokio::task::spawn_blocking(move || -> Result<FlagChanges, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+        let changes = fetch_flag_changes(
+            &mut imap_session,
+            &folder_name_owned,
+            &uids_for_task,
+            stored,
+        );
+        let _ = imap_session.logout();
+        changes
+    })
+    .await
+    .map_err(|e| e.to_string())??;
 
     let fetch_elapsed = fetch_start.elapsed();
     println!(
-        "⏱️ IMAP fetch took {:.2}s for {} emails",
+        "⏱️ IMAP fetch took {:.2}s for {} emails{}",
         fetch_elapsed.as_secs_f64(),
-        email_count
+        changes.flags.len(),
+        if changes.incremental {
+            " (changed since last sync)"
+        } else {
+            ""
+        }
     );
 
     // Update cache with new flag values
@@ -103,7 +309,7 @@ pub async fn sync_email_flags(
     let mut updated_count = 0;
     let mut changed_count = 0;
 
-    for (uid, seen, flagged) in flags_data {
+    for &(uid, seen, flagged) in &changes.flags {
         let result = sqlx::query(
             "UPDATE emails SET seen = ?, flagged = ? WHERE account_id = ? AND folder_name = ? AND uid = ? AND (seen != ? OR flagged != ?)"
         )
@@ -125,6 +331,42 @@ pub async fn sync_email_flags(
         }
     }
 
+    // Drop messages the server reported as expunged
+    let vanished_uids: Vec<u32> = cached_uids
+        .iter()
+        .copied()
+        .filter(|uid| changes.vanished.iter().any(|range| range.contains(uid)))
+        .collect();
+    for uid in &vanished_uids {
+        sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?")
+            .bind(account_id)
+            .bind(folder_name)
+            .bind(*uid as i64)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to delete email UID {}: {}", uid, e))?;
+    }
+    if !vanished_uids.is_empty() {
+        println!(
+            "🗑️ Removed {} vanished emails from cache",
+            vanished_uids.len()
+        );
+    }
+
+    if !changes.gmail.is_empty() {
+        gmail::save_gmail_metadata(account_id, folder_name, &changes.gmail).await?;
+    }
+
+    if let Some((uid_validity, highest_mod_seq)) = changes.mod_seq {
+        save_highest_modseq(
+            account_id,
+            folder_name,
+            uid_validity as i64,
+            highest_mod_seq as i64,
+        )
+        .await?;
+    }
+
     let db_elapsed = db_start.elapsed();
     let total_elapsed = start_time.elapsed();
 
@@ -228,3 +470,24 @@ pub async fn sync_specific_email_flags(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_condstore_response() {
+        let raw = b"* OK [UIDVALIDITY 3857529045] UIDs valid\r\n\
+                    * OK [HIGHESTMODSEQ 715194045007] Highest\r\n\
+                    * 4 FETCH (UID 12 FLAGS (\\Seen \\Flagged) MODSEQ (715194045001))\r\n\
+                    * 7 FETCH (UID 15 FLAGS () MODSEQ (715194045006))\r\n\
+                    * VANISHED (EARLIER) 3,8:10\r\n\
+                    A1 OK Fetch completed\r\n";
+
+        let parsed = parse_condstore_response(raw);
+        assert_eq!(parsed.uid_validity, Some(3857529045));
+        assert_eq!(parsed.highest_mod_seq, Some(715194045007));
+        assert_eq!(parsed.flags, vec![(12, true, true), (15, false, false)]);
+        assert_eq!(parsed.vanished, vec![3..=3, 8..=10]);
+    }
+}
@@ -10,6 +10,7 @@ use tauri::command;
 pub struct SyncState {
     pub uidvalidity: Option<i64>,
     pub highest_uid: Option<i64>,
+    pub highest_modseq: Option<i64>, // Only set for servers with CONDSTORE
 }
 
 /// Get sync state for a folder
@@ -19,8 +20,9 @@ pub async fn get_sync_state(
 ) -> Result<Option<SyncState>, String> {
     let pool = db::pool();
 
-    let result = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
-        "SELECT uidvalidity, highest_uid FROM sync_status WHERE account_id = ? AND folder_name = ?",
+    let result = sqlx::query_as::<_, (Option<i64>, Option<i64>, Option<i64>)>(
+        "SELECT uidvalidity, highest_uid, highest_modseq FROM sync_status
+         WHERE account_id = ? AND folder_name = ?",
     )
     .bind(account_id)
     .bind(folder_name)
@@ -28,13 +30,43 @@ pub async fn get_sync_state(
     .await
     .map_err(|e| format!("Failed to get sync state: {}", e))?;
 
-    Ok(result.map(|(uidvalidity, highest_uid)| SyncState {
-        uidvalidity,
-        highest_uid,
-    }))
+    Ok(
+        result.map(|(uidvalidity, highest_uid, highest_modseq)| SyncState {
+            uidvalidity,
+            highest_uid,
+            highest_modseq,
+        }),
+    )
+}
+
+/// Whether a background sync should leave a folder alone until the user opens it
+///
+/// True for folders set to sync on open, and for folders that were never synced, so accounts
+/// with hundreds of folders only build sync state for the ones actually used. INBOX is always
+/// synced.
+pub async fn defer_until_opened(account_id: i32, folder_name: &str) -> Result<bool, String> {
+    if folder_name.eq_ignore_ascii_case("INBOX") {
+        return Ok(false);
+    }
+
+    let pool = db::pool();
+    let (sync_on_open, synced) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT
+            COALESCE((SELECT sync_on_open FROM folder_sync_settings
+                      WHERE account_id = ?1 AND folder_name = ?2), 0),
+            EXISTS(SELECT 1 FROM sync_status WHERE account_id = ?1 AND folder_name = ?2)",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to read folder sync settings: {}", e))?;
+
+    Ok(sync_on_open != 0 || synced == 0)
 }
 
 /// Update sync state for a folder
+/// The stored HIGHESTMODSEQ is kept unless UIDVALIDITY changed, which invalidates it
 pub async fn update_sync_state(
     account_id: i32,
     folder_name: &str,
@@ -45,8 +77,14 @@ pub async fn update_sync_state(
     let current_time = Utc::now().timestamp();
 
     sqlx::query(
-        "INSERT OR REPLACE INTO sync_status (account_id, folder_name, last_sync_time, uidvalidity, highest_uid)
-        VALUES (?, ?, ?, ?, ?)",
+        "INSERT INTO sync_status (account_id, folder_name, last_sync_time, uidvalidity, highest_uid)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(account_id, folder_name) DO UPDATE SET
+            last_sync_time = excluded.last_sync_time,
+            highest_modseq = CASE WHEN uidvalidity = excluded.uidvalidity
+                THEN highest_modseq END,
+            uidvalidity = excluded.uidvalidity,
+            highest_uid = excluded.highest_uid",
     )
     .bind(account_id)
     .bind(folder_name)
@@ -65,6 +103,99 @@ pub async fn update_sync_state(
     Ok(())
 }
 
+/// Record the HIGHESTMODSEQ up to which a folder's cached flags are current
+/// Ignored if the folder's UIDVALIDITY has changed since it was read
+pub async fn save_highest_modseq(
+    account_id: i32,
+    folder_name: &str,
+    uidvalidity: i64,
+    highest_modseq: i64,
+) -> Result<(), String> {
+    let pool = db::pool();
+
+    sqlx::query(
+        "UPDATE sync_status SET highest_modseq = ?
+         WHERE account_id = ? AND folder_name = ? AND uidvalidity = ?",
+    )
+    .bind(highest_modseq)
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(uidvalidity)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save HIGHESTMODSEQ: {}", e))?;
+
+    Ok(())
+}
+
+/// Progress of a full sync that has not finished yet
+#[derive(Clone)]
+pub struct SyncCheckpoint {
+    pub uidvalidity: i64,
+    pub last_uid: i64, // All messages up to this UID are cached
+}
+
+/// Get the checkpoint of an unfinished full sync, if any
+pub async fn get_sync_checkpoint(
+    account_id: i32,
+    folder_name: &str,
+) -> Result<Option<SyncCheckpoint>, String> {
+    let pool = db::pool();
+
+    let result = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT uidvalidity, last_uid FROM sync_checkpoints WHERE account_id = ? AND folder_name = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to get sync checkpoint: {}", e))?;
+
+    Ok(result.map(|(uidvalidity, last_uid)| SyncCheckpoint {
+        uidvalidity,
+        last_uid,
+    }))
+}
+
+/// Record that a full sync has cached everything up to `last_uid`
+pub async fn save_sync_checkpoint(
+    account_id: i32,
+    folder_name: &str,
+    uidvalidity: i64,
+    last_uid: i64,
+) -> Result<(), String> {
+    let pool = db::pool();
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO sync_checkpoints (account_id, folder_name, uidvalidity, last_uid, updated_at)
+        VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .bind(uidvalidity)
+    .bind(last_uid)
+    .bind(Utc::now().timestamp())
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save sync checkpoint: {}", e))?;
+
+    Ok(())
+}
+
+/// Drop the checkpoint once a full sync has completed
+pub async fn clear_sync_checkpoint(account_id: i32, folder_name: &str) -> Result<(), String> {
+    let pool = db::pool();
+
+    sqlx::query("DELETE FROM sync_checkpoints WHERE account_id = ? AND folder_name = ?")
+        .bind(account_id)
+        .bind(folder_name)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to clear sync checkpoint: {}", e))?;
+
+    Ok(())
+}
+
 /// Get last sync time for a folder
 #[command]
 pub async fn get_last_sync_time(account_id: i32, folder: Option<String>) -> Result<i64, String> {
@@ -90,6 +221,11 @@ pub async fn should_sync(
     folder: Option<String>,
     sync_interval: i64,
 ) -> Result<bool, String> {
+    // Safe mode never syncs automatically
+    if crate::safe_mode::is_enabled() {
+        return Ok(false);
+    }
+
     // sync_interval in seconds, 0 = manual, -1 = never
     if sync_interval == -1 {
         return Ok(false); // Never sync
@@ -0,0 +1,231 @@
+// Sync window
+// A full sync (first sync, or after UIDVALIDITY changed) can be limited to the newest messages
+// of a folder, by age and/or count, instead of downloading every header in the mailbox.
+// Later syncs only add newer mail, so the cache keeps covering the window's tail;
+// `load_older_emails` extends it backwards on demand.
+
+use super::sync_fetch::fetch_new_by_uid_list;
+use super::sync_state::get_sync_state;
+use crate::commands::emails::cache::save_emails_to_cache;
+use crate::commands::emails::imap_helpers;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::{AccountConfig, EmailHeader};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Messages fetched by one `load_older_emails` call unless the caller asks otherwise
+const DEFAULT_OLDER_BATCH: u32 = 500;
+
+/// How much of a folder a full sync downloads; None means no limit
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct SyncWindow {
+    /// Only messages received in the last this many days
+    pub days: Option<u32>,
+    /// Only the newest this many messages
+    pub messages: Option<u32>,
+}
+
+impl SyncWindow {
+    pub fn is_unlimited(&self) -> bool {
+        self.days.is_none() && self.messages.is_none()
+    }
+}
+
+/// Result of extending a folder's cache backwards
+#[derive(Serialize, Debug, Clone)]
+pub struct OlderEmails {
+    pub loaded: usize,
+    /// Older messages still on the server but not cached
+    pub remaining: usize,
+}
+
+/// Load an account's sync window
+pub async fn load_sync_window(account_id: i32) -> Result<SyncWindow, String> {
+    let pool = db::pool();
+    let row = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+        "SELECT sync_window_days, sync_window_messages FROM accounts WHERE id = ?",
+    )
+    .bind(account_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load sync window: {}", e))?;
+
+    let (days, messages) = row.unwrap_or((None, None));
+    Ok(SyncWindow {
+        days: days.and_then(|d| u32::try_from(d).ok()),
+        messages: messages.and_then(|m| u32::try_from(m).ok()),
+    })
+}
+
+/// SEARCH criteria for messages received in the last `days` days
+fn since_criteria(today: NaiveDate, days: u32) -> String {
+    let since = today - chrono::Duration::days(days as i64);
+    format!("SINCE {}", since.format("%d-%b-%Y"))
+}
+
+/// UIDs of the selected folder that fall inside the window, in ascending order
+pub fn window_uids(
+    imap_session: &mut imap::Session<Box<dyn imap::ImapConnection>>,
+    window: &SyncWindow,
+) -> Result<Vec<u32>, String> {
+    let criteria = match window.days {
+        Some(days) => since_criteria(chrono::Local::now().date_naive(), days),
+        None => "ALL".to_string(),
+    };
+
+    let mut uids: Vec<u32> = imap_session
+        .uid_search(&criteria)
+        .map_err(|e| format!("Failed to search sync window: {}", e))?
+        .into_iter()
+        .collect();
+    uids.sort_unstable();
+
+    if let Some(limit) = window.messages {
+        let skip = uids.len().saturating_sub(limit as usize);
+        uids.drain(..skip);
+    }
+
+    Ok(uids)
+}
+
+/// Get the sync window of an account
+#[command]
+pub async fn get_sync_window(account_id: i32) -> Result<SyncWindow, String> {
+    load_sync_window(account_id).await
+}
+
+/// Set the sync window of an account
+///
+/// Applies to the next full sync of each folder; folders already cached keep what they have.
+#[command]
+pub async fn set_sync_window(account_id: i32, window: SyncWindow) -> Result<(), String> {
+    if window.days == Some(0) || window.messages == Some(0) {
+        return Err("Sync window must be at least one day and one message".to_string());
+    }
+
+    let pool = db::pool();
+    sqlx::query("UPDATE accounts SET sync_window_days = ?, sync_window_messages = ? WHERE id = ?")
+        .bind(window.days)
+        .bind(window.messages)
+        .bind(account_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save sync window: {}", e))?;
+
+    println!("⚙️ Sync window for account {}: {:?}", account_id, window);
+    Ok(())
+}
+
+/// Fetch the next `count` messages older than anything cached for a folder
+#[command]
+pub async fn load_older_emails(
+    config: AccountConfig,
+    folder: String,
+    count: Option<u32>,
+) -> Result<OlderEmails, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let count = count.unwrap_or(DEFAULT_OLDER_BATCH).max(1) as usize;
+
+    if crate::commands::demo::is_demo_account(&config) {
+        return Ok(OlderEmails {
+            loaded: 0,
+            remaining: 0,
+        });
+    }
+
+    let uidvalidity = get_sync_state(account_id, &folder)
+        .await?
+        .and_then(|state| state.uidvalidity)
+        .ok_or("Folder has not been synced yet")?;
+
+    let pool = db::pool();
+    let (min_uid,) = sqlx::query_as::<_, (Option<i64>,)>(
+        "SELECT MIN(uid) FROM emails WHERE account_id = ? AND folder_name = ?",
+    )
+    .bind(account_id)
+    .bind(&folder)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to query cache min UID: {}", e))?;
+
+    // Nothing cached yet means the next sync fills the window; UID 1 has nothing before it
+    let oldest_cached = match min_uid {
+        Some(uid) if uid > 1 => uid as u32,
+        _ => {
+            return Ok(OlderEmails {
+                loaded: 0,
+                remaining: 0,
+            })
+        }
+    };
+
+    println!(
+        "📜 Loading up to {} emails older than UID {} in '{}'",
+        count, oldest_cached, folder
+    );
+
+    let config = ensure_valid_token(config).await?;
+    let (batch_tx, mut batch_rx) = tokio::sync::mpsc::channel::<Vec<EmailHeader>>(2);
+    let folder_for_writer = folder.clone();
+    let writer = tokio::spawn(async move {
+        let mut saved_count = 0usize;
+        while let Some(batch) = batch_rx.recv().await {
+            save_emails_to_cache(account_id, &folder_for_writer, &batch).await?;
+            saved_count += batch.len();
+        }
+        Ok::<usize, String>(saved_count)
+    });
+
+    let fetch_result = tokio::task::spawn_blocking(move || -> Result<usize, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+        let mailbox = imap_session
+            .select(&folder)
+            .map_err(|e| format!("Cannot access folder '{}': {}", folder, e))?;
+
+        // UIDs from another UIDVALIDITY don't line up with the cache; a sync resets it first
+        if mailbox.uid_validity.map(|v| v as i64) != Some(uidvalidity) {
+            let _ = imap_session.logout();
+            return Err("Folder changed on the server; sync it before loading older emails".into());
+        }
+
+        let mut older: Vec<u32> = imap_session
+            .uid_search(&format!("UID 1:{}", oldest_cached - 1))
+            .map_err(|e| format!("Failed to search older messages: {}", e))?
+            .into_iter()
+            .filter(|&uid| uid < oldest_cached)
+            .collect();
+        older.sort_unstable();
+
+        let remaining = older.len().saturating_sub(count);
+        let to_fetch = older.split_off(remaining);
+        fetch_new_by_uid_list(&mut imap_session, to_fetch, 0, &batch_tx)?;
+
+        let _ = imap_session.logout();
+        Ok(remaining)
+    })
+    .await
+    .map_err(|e| e.to_string());
+
+    let loaded = writer.await.map_err(|e| e.to_string())??;
+    let remaining = fetch_result??;
+
+    println!(
+        "✅ Loaded {} older emails in this folder ({} still on the server)",
+        loaded, remaining
+    );
+    Ok(OlderEmails { loaded, remaining })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn since_criteria_counts_back_from_today() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+        assert_eq!(since_criteria(today, 90), "SINCE 05-Dec-2025");
+        assert_eq!(since_criteria(today, 1), "SINCE 04-Mar-2026");
+    }
+}
@@ -0,0 +1,170 @@
+// Sync every folder of an account
+// Runs the incremental sync of each cached remote folder with a few running at once, so a
+// refresh doesn't have to loop over folders one call at a time from the frontend. Every
+// folder is reported, and one failing folder doesn't stop the rest.
+
+use crate::commands::emails::sync::sync_emails;
+use crate::commands::folders::load_folders;
+use crate::db;
+use crate::models::AccountConfig;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+
+/// Folder syncs running at once; each holds its own IMAP connection, and providers cap those
+const MAX_PARALLEL_SYNCS: usize = 3;
+
+/// Outcome of syncing one folder
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FolderSyncSummary {
+    pub folder_name: String,
+    pub new_count: usize,
+    pub total_count: usize,
+    pub error: Option<String>,
+}
+
+/// Highest UID cached for a folder, to tell which emails a sync added
+async fn cached_max_uid(account_id: i32, folder_name: &str) -> Result<u32, String> {
+    let pool = db::pool();
+    let (max_uid,) = sqlx::query_as::<_, (Option<i64>,)>(
+        "SELECT MAX(uid) FROM emails WHERE account_id = ? AND folder_name = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to query cache max UID: {}", e))?;
+    Ok(max_uid.unwrap_or(0) as u32)
+}
+
+/// Sync one folder and summarize the result
+async fn sync_folder(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    account_id: i32,
+    folder_name: String,
+    background: bool,
+) -> FolderSyncSummary {
+    let result = async {
+        let previous_max_uid = cached_max_uid(account_id, &folder_name).await?;
+        let emails = sync_emails(
+            app_handle,
+            config,
+            Some(folder_name.clone()),
+            Some(background),
+        )
+        .await?;
+        let new_count = emails
+            .iter()
+            .filter(|email| email.uid > previous_max_uid)
+            .count();
+        Ok::<_, String>((new_count, emails.len()))
+    }
+    .await;
+
+    match result {
+        Ok((new_count, total_count)) => FolderSyncSummary {
+            folder_name,
+            new_count,
+            total_count,
+            error: None,
+        },
+        Err(error) => {
+            eprintln!("⚠️ Sync failed for folder '{}': {}", folder_name, error);
+            FolderSyncSummary {
+                folder_name,
+                new_count: 0,
+                total_count: 0,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+/// Keep the summary of a finished folder sync; a task that panicked leaves its folder
+/// without one, which `sync_all_folders` reports as an error afterwards
+fn collect_summary(
+    joined: Result<FolderSyncSummary, tokio::task::JoinError>,
+    summaries: &mut Vec<FolderSyncSummary>,
+) {
+    match joined {
+        Ok(summary) => summaries.push(summary),
+        Err(e) => eprintln!("⚠️ Folder sync task failed: {}", e),
+    }
+}
+
+/// Sync all remote folders of an account, a few at a time
+///
+/// `open_folder` is the folder the user is looking at; it is always synced, while the others
+/// are background syncs that skip folders set to sync only when opened. Summaries come back in
+/// folder list order.
+#[command]
+pub async fn sync_all_folders(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    open_folder: Option<String>,
+) -> Result<Vec<FolderSyncSummary>, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let folder_names: Vec<String> = load_folders(account_id)
+        .await?
+        .into_iter()
+        .filter(|folder| !folder.is_local && folder.is_selectable())
+        .map(|folder| folder.name)
+        .collect();
+
+    println!(
+        "🔄 Syncing {} folders of account {} ({} at a time)",
+        folder_names.len(),
+        account_id,
+        MAX_PARALLEL_SYNCS
+    );
+
+    let mut syncs = tokio::task::JoinSet::new();
+    let mut summaries = Vec::new();
+    for folder_name in folder_names.iter().cloned() {
+        // Wait for a slot before starting the next folder
+        if syncs.len() >= MAX_PARALLEL_SYNCS {
+            if let Some(joined) = syncs.join_next().await {
+                collect_summary(joined, &mut summaries);
+            }
+        }
+        let background = open_folder.as_deref() != Some(folder_name.as_str());
+        syncs.spawn(sync_folder(
+            app_handle.clone(),
+            config.clone(),
+            account_id,
+            folder_name,
+            background,
+        ));
+    }
+    while let Some(joined) = syncs.join_next().await {
+        collect_summary(joined, &mut summaries);
+    }
+
+    // Every folder is reported, including those whose task died without a summary
+    let missing: Vec<String> = folder_names
+        .iter()
+        .filter(|name| !summaries.iter().any(|s| s.folder_name == **name))
+        .cloned()
+        .collect();
+    summaries.extend(missing.into_iter().map(|folder_name| FolderSyncSummary {
+        folder_name,
+        new_count: 0,
+        total_count: 0,
+        error: Some("Sync task failed unexpectedly".to_string()),
+    }));
+
+    summaries.sort_by_key(|summary| {
+        folder_names
+            .iter()
+            .position(|name| *name == summary.folder_name)
+    });
+
+    let failed = summaries.iter().filter(|s| s.error.is_some()).count();
+    println!(
+        "✅ Synced {} folders of account {} ({} failed)",
+        summaries.len(),
+        account_id,
+        failed
+    );
+    Ok(summaries)
+}
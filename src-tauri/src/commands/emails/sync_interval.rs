@@ -35,3 +35,42 @@ pub async fn set_sync_interval(interval: i64) -> Result<(), String> {
     println!("✅ Set sync interval to {} seconds", interval);
     Ok(())
 }
+
+/// Default seconds between checks on servers without IDLE
+const DEFAULT_IDLE_POLL_INTERVAL: i64 = 120;
+
+/// Get how often folders are polled when the server doesn't support IDLE
+#[command]
+pub async fn get_idle_poll_interval() -> Result<i64, String> {
+    let pool = db::pool();
+
+    let result = sqlx::query_as::<_, (String,)>(
+        "SELECT value FROM settings WHERE key = 'idle_poll_interval'",
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to get IDLE poll interval: {}", e))?;
+
+    Ok(result
+        .and_then(|(v,)| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_IDLE_POLL_INTERVAL))
+}
+
+/// Set how often folders are polled when the server doesn't support IDLE
+/// Takes effect when the polling connection next reconnects.
+#[command]
+pub async fn set_idle_poll_interval(interval: i64) -> Result<(), String> {
+    if !(30..=1800).contains(&interval) {
+        return Err("Poll interval must be between 30 seconds and 30 minutes".to_string());
+    }
+    let pool = db::pool();
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES ('idle_poll_interval', ?)")
+        .bind(interval.to_string())
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to set IDLE poll interval: {}", e))?;
+
+    println!("✅ Set IDLE poll interval to {} seconds", interval);
+    Ok(())
+}
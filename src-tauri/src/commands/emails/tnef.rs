@@ -0,0 +1,358 @@
+// TNEF (winmail.dat) decoding
+// Outlook sometimes wraps a message's attachments and rich-text body into a single
+// `application/ms-tnef` part named winmail.dat. This unpacks it: every embedded file becomes a
+// normal attachment, and the compressed RTF body (if any) is decompressed and surfaced as
+// `message.rtf`. Only the parts of the format needed for that are parsed.
+
+use crate::models::Attachment;
+
+const TNEF_SIGNATURE: u32 = 0x223E_9F78;
+
+// Attribute IDs (type in the high word, id in the low word)
+const ATT_BODY: u32 = 0x0001_800C;
+const ATT_MAPI_PROPS: u32 = 0x0006_9003;
+const ATT_ATTACH_REND_DATA: u32 = 0x0006_9002;
+const ATT_ATTACH_TITLE: u32 = 0x0001_8010;
+const ATT_ATTACH_DATA: u32 = 0x0006_800F;
+const ATT_ATTACHMENT: u32 = 0x0006_9005;
+
+// MAPI property tags used here
+const PR_RTF_COMPRESSED: u16 = 0x1009;
+const PR_ATTACH_LONG_FILENAME: u16 = 0x3707;
+const PR_ATTACH_MIME_TAG: u16 = 0x370E;
+
+/// Whether an attachment is a TNEF container
+pub fn is_tnef(filename: &str, content_type: &str) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    content_type == "application/ms-tnef"
+        || content_type == "application/vnd.ms-tnef"
+        || filename.eq_ignore_ascii_case("winmail.dat")
+}
+
+/// Little-endian reader over a byte slice; every read fails cleanly past the end
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.bytes(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+#[derive(Default)]
+struct EmbeddedFile {
+    title: Option<String>,
+    long_name: Option<String>,
+    mime_type: Option<String>,
+    data: Option<Vec<u8>>,
+}
+
+/// A MAPI property value we care about
+enum PropValue {
+    Binary(Vec<u8>),
+    Text(String),
+}
+
+/// Null-terminated 8-bit string (Windows-1252 in practice)
+fn ansi_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let (text, _, _) = encoding_rs::WINDOWS_1252.decode(&bytes[..end]);
+    text.into_owned()
+}
+
+/// Null-terminated UTF-16LE string
+fn unicode_string(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Parse a MAPI property list, keeping string and binary values by property id
+fn parse_mapi_props(data: &[u8]) -> Option<Vec<(u16, PropValue)>> {
+    let mut reader = Reader::new(data);
+    let count = reader.u32()?;
+    let mut props = Vec::new();
+
+    for _ in 0..count {
+        let prop_type = reader.u16()?;
+        let prop_id = reader.u16()?;
+
+        // Named properties carry their name before the value
+        if prop_id >= 0x8000 {
+            reader.bytes(16)?; // GUID
+            if reader.u32()? == 1 {
+                let len = reader.u32()? as usize;
+                reader.bytes(len.div_ceil(4) * 4)?;
+            } else {
+                reader.u32()?;
+            }
+        }
+
+        let multi = prop_type & 0x1000 != 0;
+        let base_type = prop_type & 0x0FFF;
+        let variable = matches!(base_type, 0x001E | 0x001F | 0x0102 | 0x000D);
+        let values = if multi || variable { reader.u32()? } else { 1 };
+
+        for _ in 0..values {
+            if variable {
+                let len = reader.u32()? as usize;
+                let bytes = reader.bytes(len)?;
+                reader.bytes((4 - len % 4) % 4)?;
+                let value = match base_type {
+                    0x001E => PropValue::Text(ansi_string(bytes)),
+                    0x001F => PropValue::Text(unicode_string(bytes)),
+                    _ => PropValue::Binary(bytes.to_vec()),
+                };
+                props.push((prop_id, value));
+            } else {
+                let size = match base_type {
+                    0x0002 | 0x0003 | 0x0004 | 0x000A | 0x000B => 4,
+                    0x0005 | 0x0006 | 0x0007 | 0x0014 | 0x0040 => 8,
+                    0x0048 => 16,
+                    0x0001 => 0, // PT_NULL
+                    _ => return None,
+                };
+                reader.bytes(size)?;
+            }
+        }
+    }
+
+    Some(props)
+}
+
+/// Decompress an RTF body stored as PR_RTF_COMPRESSED ([MS-OXRTFCP])
+fn decompress_rtf(data: &[u8]) -> Option<Vec<u8>> {
+    const PREBUF: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}{\\f0\\fnil \\froman \\fswiss \\fmodern \\fscript \\fdecor MS Sans SerifSymbolArialTimes New RomanCourier{\\colortbl\\red0\\green0\\blue0\r\n\\par \\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+    const COMPRESSED: u32 = 0x7546_5A4C; // "LZFu"
+    const UNCOMPRESSED: u32 = 0x414C_454D; // "MELA"
+
+    let mut reader = Reader::new(data);
+    let comp_size = reader.u32()? as usize;
+    let raw_size = reader.u32()? as usize;
+    let comp_type = reader.u32()?;
+    reader.u32()?; // CRC
+    let end = (comp_size + 4).min(data.len());
+
+    match comp_type {
+        UNCOMPRESSED => return Some(reader.bytes(raw_size.min(end.saturating_sub(16)))?.to_vec()),
+        COMPRESSED => {}
+        _ => return None,
+    }
+
+    let mut dictionary = [0u8; 4096];
+    dictionary[..PREBUF.len()].copy_from_slice(PREBUF);
+    let mut write = PREBUF.len();
+    let mut out = Vec::with_capacity(raw_size);
+
+    let mut pos = 16;
+    while pos < end {
+        let control = data[pos];
+        pos += 1;
+        for bit in 0..8 {
+            if pos >= end {
+                break;
+            }
+            if control & (1 << bit) == 0 {
+                let byte = data[pos];
+                pos += 1;
+                out.push(byte);
+                dictionary[write] = byte;
+                write = (write + 1) % 4096;
+            } else {
+                let reference = u16::from_be_bytes([data[pos], *data.get(pos + 1)?]) as usize;
+                pos += 2;
+                let offset = reference >> 4;
+                if offset == write {
+                    return Some(out);
+                }
+                for i in 0..(reference & 0xF) + 2 {
+                    let byte = dictionary[(offset + i) % 4096];
+                    out.push(byte);
+                    dictionary[write] = byte;
+                    write = (write + 1) % 4096;
+                }
+            }
+        }
+    }
+
+    Some(out)
+}
+
+/// Unpack a TNEF blob into its attachments and RTF body
+///
+/// Returns None if the data isn't TNEF or nothing could be recovered from it.
+pub fn decode(data: &[u8]) -> Option<Vec<Attachment>> {
+    let mut reader = Reader::new(data);
+    if reader.u32()? != TNEF_SIGNATURE {
+        return None;
+    }
+    reader.u16()?; // Legacy key
+
+    let mut files: Vec<EmbeddedFile> = Vec::new();
+    let mut rtf_body = None;
+    let mut text_body = None;
+
+    while !reader.at_end() {
+        let Some(level) = reader.u8() else { break };
+        let (Some(id), Some(len)) = (reader.u32(), reader.u32()) else {
+            break;
+        };
+        let Some(value) = reader.bytes(len as usize) else {
+            break;
+        };
+        let _ = reader.u16(); // Checksum
+
+        match (level, id) {
+            (1, ATT_BODY) => text_body = Some(ansi_string(value)),
+            (1, ATT_MAPI_PROPS) => {
+                for (prop_id, prop) in parse_mapi_props(value).unwrap_or_default() {
+                    if let (PR_RTF_COMPRESSED, PropValue::Binary(bytes)) = (prop_id, prop) {
+                        rtf_body = decompress_rtf(&bytes);
+                    }
+                }
+            }
+            (2, ATT_ATTACH_REND_DATA) => files.push(EmbeddedFile::default()),
+            (2, ATT_ATTACH_TITLE) => {
+                if let Some(file) = files.last_mut() {
+                    file.title = Some(ansi_string(value));
+                }
+            }
+            (2, ATT_ATTACH_DATA) => {
+                if let Some(file) = files.last_mut() {
+                    file.data = Some(value.to_vec());
+                }
+            }
+            (2, ATT_ATTACHMENT) => {
+                let Some(file) = files.last_mut() else {
+                    continue;
+                };
+                for (prop_id, prop) in parse_mapi_props(value).unwrap_or_default() {
+                    match (prop_id, prop) {
+                        (PR_ATTACH_LONG_FILENAME, PropValue::Text(name)) => {
+                            file.long_name = Some(name)
+                        }
+                        (PR_ATTACH_MIME_TAG, PropValue::Text(mime)) => file.mime_type = Some(mime),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut attachments: Vec<Attachment> = files
+        .into_iter()
+        .filter_map(|file| {
+            let data = file.data?;
+            let filename = file
+                .long_name
+                .or(file.title)
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| "attachment".to_string());
+            Some(Attachment {
+                id: None,
+                filename,
+                content_type: file
+                    .mime_type
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+                size: data.len() as i64,
+                data: Some(data),
+            })
+        })
+        .collect();
+
+    let body = match (rtf_body, text_body) {
+        (Some(rtf), _) => Some(("message.rtf", "application/rtf", rtf)),
+        (None, Some(text)) if !text.trim().is_empty() => {
+            Some(("message.txt", "text/plain", text.into_bytes()))
+        }
+        _ => None,
+    };
+    if let Some((filename, content_type, data)) = body {
+        attachments.push(Attachment {
+            id: None,
+            filename: filename.to_string(),
+            content_type: content_type.to_string(),
+            size: data.len() as i64,
+            data: Some(data),
+        });
+    }
+
+    (!attachments.is_empty()).then_some(attachments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(out: &mut Vec<u8>, level: u8, id: u32, value: &[u8]) {
+        out.push(level);
+        out.extend_from_slice(&id.to_le_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        out.extend_from_slice(value);
+        out.extend_from_slice(&[0, 0]);
+    }
+
+    #[test]
+    fn test_decompress_rtf() {
+        // Example from [MS-OXRTFCP] section 4.1
+        let compressed = [
+            0x2d, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x00, 0x00, 0x4c, 0x5a, 0x46, 0x75, 0xf1, 0xc5,
+            0xc7, 0xa7, 0x03, 0x00, 0x0a, 0x00, 0x72, 0x63, 0x70, 0x67, 0x31, 0x32, 0x35, 0x42,
+            0x32, 0x0a, 0xf3, 0x20, 0x68, 0x65, 0x6c, 0x09, 0x00, 0x20, 0x62, 0x77, 0x05, 0xb0,
+            0x6c, 0x64, 0x7d, 0x0a, 0x80, 0x0f, 0xa0,
+        ];
+        assert_eq!(
+            decompress_rtf(&compressed).unwrap(),
+            b"{\\rtf1\\ansi\\ansicpg1252\\pard hello world}\r\n"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut tnef = Vec::new();
+        tnef.extend_from_slice(&TNEF_SIGNATURE.to_le_bytes());
+        tnef.extend_from_slice(&[0x01, 0x00]);
+        attribute(&mut tnef, 1, ATT_BODY, b"See attached\0");
+        attribute(&mut tnef, 2, ATT_ATTACH_REND_DATA, &[0; 14]);
+        attribute(&mut tnef, 2, ATT_ATTACH_TITLE, b"REPORT~1.PDF\0");
+        attribute(&mut tnef, 2, ATT_ATTACH_DATA, b"%PDF-1.4");
+
+        let attachments = decode(&tnef).unwrap();
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].filename, "REPORT~1.PDF");
+        assert_eq!(attachments[0].data.as_deref(), Some(&b"%PDF-1.4"[..]));
+        assert_eq!(attachments[1].filename, "message.txt");
+
+        assert!(decode(b"not tnef").is_none());
+        assert!(is_tnef("WINMAIL.DAT", "application/octet-stream"));
+    }
+}
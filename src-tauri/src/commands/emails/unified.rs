@@ -0,0 +1,111 @@
+// Unified inbox
+// Shows the INBOX of every configured account as one list, newest first, with each email
+// tagged with the account it belongs to. Loading reads the cache only; the sync entry point
+// syncs all inboxes in parallel and then returns the merged list, so one unreachable server
+// doesn't hold back the others.
+
+use crate::commands::accounts::load_account_configs;
+use crate::commands::emails::cache::load_emails_from_cache;
+use crate::commands::emails::sync::sync_emails;
+use crate::models::{EmailHeader, EmailSortOrder};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use tauri::{command, AppHandle};
+
+const INBOX: &str = "INBOX";
+
+/// An email in the unified inbox
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnifiedEmailHeader {
+    pub account_id: i32,
+    pub account_email: String,
+    #[serde(flatten)]
+    pub header: EmailHeader,
+}
+
+/// An account whose inbox could not be synced
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnifiedSyncError {
+    pub account_id: i32,
+    pub error: String,
+}
+
+/// Result of syncing the unified inbox; failed accounts are still listed from the cache
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnifiedSyncResult {
+    pub emails: Vec<UnifiedEmailHeader>,
+    pub errors: Vec<UnifiedSyncError>,
+}
+
+/// Merge the cached inboxes of all accounts
+async fn merged_inbox(sort_by: EmailSortOrder) -> Result<Vec<UnifiedEmailHeader>, String> {
+    let mut emails = Vec::new();
+    for account in load_account_configs().await? {
+        let Some(account_id) = account.id else {
+            continue;
+        };
+        let headers = load_emails_from_cache(account_id, Some(INBOX.to_string()), None).await?;
+        emails.extend(headers.into_iter().map(|header| UnifiedEmailHeader {
+            account_id,
+            account_email: account.email.clone(),
+            header,
+        }));
+    }
+
+    // Same ordering as a single folder: cached emails without INTERNALDATE use their Date header
+    match sort_by {
+        EmailSortOrder::DateSent => emails.sort_by_key(|email| Reverse(email.header.timestamp)),
+        EmailSortOrder::DateReceived => emails.sort_by_key(|email| {
+            Reverse(email.header.received_at.unwrap_or(email.header.timestamp))
+        }),
+    }
+    Ok(emails)
+}
+
+/// Load the INBOX emails of all accounts from the cache, newest first
+#[command]
+pub async fn load_unified_inbox(
+    sort_by: Option<EmailSortOrder>,
+) -> Result<Vec<UnifiedEmailHeader>, String> {
+    merged_inbox(sort_by.unwrap_or(EmailSortOrder::DateSent)).await
+}
+
+/// Sync the INBOX of every account, then return the merged list
+#[command]
+pub async fn sync_unified_inbox(
+    app_handle: AppHandle,
+    sort_by: Option<EmailSortOrder>,
+) -> Result<UnifiedSyncResult, String> {
+    let mut syncs = tokio::task::JoinSet::new();
+    for config in load_account_configs().await? {
+        let Some(account_id) = config.id else {
+            continue;
+        };
+        let app_handle = app_handle.clone();
+        syncs.spawn(async move {
+            let result = sync_emails(app_handle, config, Some(INBOX.to_string()), None).await;
+            (account_id, result)
+        });
+    }
+
+    let mut errors = Vec::new();
+    while let Some(joined) = syncs.join_next().await {
+        match joined {
+            Ok((_, Ok(_))) => {}
+            Ok((account_id, Err(error))) => {
+                eprintln!(
+                    "⚠️ Unified inbox sync failed for account {}: {}",
+                    account_id, error
+                );
+                errors.push(UnifiedSyncError { account_id, error });
+            }
+            Err(e) => eprintln!("⚠️ Unified inbox sync task failed: {}", e),
+        }
+    }
+    errors.sort_by_key(|error| error.account_id);
+
+    Ok(UnifiedSyncResult {
+        emails: merged_inbox(sort_by.unwrap_or(EmailSortOrder::DateSent)).await?,
+        errors,
+    })
+}
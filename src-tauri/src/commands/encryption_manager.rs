@@ -1,9 +1,12 @@
 // Encryption management commands
 // Handles master password setup, unlock, and encryption status
 
+use crate::commands::audit;
+use crate::commands::authorization::{authorize, clear_confirmation, Sensitivity};
 use crate::db;
 use crate::encryption::{
-    init_encryption, is_encryption_unlocked, lock_encryption, unlock_encryption, verify_password,
+    decrypt, decrypt_bytes, encrypt, encrypt_bytes, init_encryption, is_encryption_unlocked,
+    lock_encryption, unlock_encryption, verify_password,
 };
 use argon2::{
     password_hash::{PasswordHasher, SaltString},
@@ -12,7 +15,7 @@ use argon2::{
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use tauri::{command, AppHandle, Emitter};
 
 #[derive(Serialize, Deserialize)]
 pub struct EncryptionStatus {
@@ -20,6 +23,21 @@ pub struct EncryptionStatus {
     pub unlocked: bool,
 }
 
+/// Failed unlock attempts that are allowed before delays kick in
+const FREE_UNLOCK_ATTEMPTS: i64 = 3;
+
+/// Longest delay between attempts before the lockout threshold is reached
+const MAX_UNLOCK_DELAY_SECS: i64 = 300;
+
+/// Brute-force protection state for the master password, emitted as "unlock-cooldown"
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnlockCooldown {
+    pub failed_attempts: i64,
+    pub remaining_attempts: i64, // Failures left before the lockout
+    pub retry_after_secs: i64,   // 0 when an attempt is allowed right now
+    pub locked_out: bool,
+}
+
 /// Check if encryption is enabled and unlocked
 #[command]
 pub async fn get_encryption_status() -> Result<EncryptionStatus, String> {
@@ -50,6 +68,16 @@ pub async fn enable_encryption(password: String) -> Result<(), String> {
         return Err("Password must be at least 8 characters long".to_string());
     }
 
+    // Maildir files can't be encrypted, so local folders must be back in SQLite first
+    if crate::commands::emails::maildir::local_store_format().await
+        == crate::commands::emails::maildir::LocalStoreFormat::Maildir
+    {
+        return Err(
+            "Local folders are stored as Maildir; switch them back to the database first"
+                .to_string(),
+        );
+    }
+
     let pool = db::pool();
 
     // Check if encryption is already enabled
@@ -83,6 +111,28 @@ pub async fn enable_encryption(password: String) -> Result<(), String> {
     // Initialize encryption in memory
     init_encryption(&password).map_err(|e| format!("Failed to initialize encryption: {}", e))?;
 
+    // Notes are kept when the cache is cleared, so existing ones are encrypted now
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let notes = sqlx::query_as::<_, (i64, String)>("SELECT id, note FROM email_notes")
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load notes: {}", e))?;
+    for (id, note) in notes {
+        let stored = encrypt(&note).map_err(|e| format!("Failed to encrypt note: {}", e))?;
+        sqlx::query("UPDATE email_notes SET note = ? WHERE id = ?")
+            .bind(stored)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save note: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save notes: {}", e))?;
+
     // Store encryption settings in database
     sqlx::query("UPDATE settings SET value = 'true' WHERE key = 'encryption_enabled'")
         .execute(pool.as_ref())
@@ -101,6 +151,10 @@ pub async fn enable_encryption(password: String) -> Result<(), String> {
         .await
         .map_err(|e| format!("Failed to save password hash: {}", e))?;
 
+    // The attachment text index is plaintext; drop it rather than leave it readable
+    crate::commands::emails::attachment_index::clear_index().await?;
+
+    audit::record("encryption_enabled", None, "").await;
     println!("✅ Encryption enabled successfully");
     Ok(())
 }
@@ -108,7 +162,9 @@ pub async fn enable_encryption(password: String) -> Result<(), String> {
 /// Disable encryption
 /// WARNING: This will not decrypt existing data - use with caution
 #[command]
-pub async fn disable_encryption(password: String) -> Result<(), String> {
+pub async fn disable_encryption(app_handle: AppHandle, password: String) -> Result<(), String> {
+    check_unlock_allowed().await?;
+
     let pool = db::pool();
 
     // Verify password first
@@ -123,11 +179,16 @@ pub async fn disable_encryption(password: String) -> Result<(), String> {
     if !verify_password(&password, &password_hash)
         .map_err(|e| format!("Password verification failed: {}", e))?
     {
+        audit::record("encryption_disable_failed", None, "Invalid password").await;
+        record_failed_unlock(&app_handle).await?;
         return Err("Invalid password".to_string());
     }
 
+    reset_unlock_attempts().await?;
+
     // Lock encryption
     lock_encryption();
+    clear_confirmation();
 
     // Clear encryption settings
     sqlx::query("UPDATE settings SET value = 'false' WHERE key = 'encryption_enabled'")
@@ -145,6 +206,7 @@ pub async fn disable_encryption(password: String) -> Result<(), String> {
         .await
         .map_err(|e| format!("Failed to clear password hash: {}", e))?;
 
+    audit::record("encryption_disabled", None, "").await;
     println!("🔓 Encryption disabled");
     Ok(())
 }
@@ -152,7 +214,12 @@ pub async fn disable_encryption(password: String) -> Result<(), String> {
 /// Unlock encryption with master password
 /// Called when the app starts or after locking
 #[command]
-pub async fn unlock_encryption_with_password(password: String) -> Result<(), String> {
+pub async fn unlock_encryption_with_password(
+    app_handle: AppHandle,
+    password: String,
+) -> Result<(), String> {
+    check_unlock_allowed().await?;
+
     let pool = db::pool();
 
     // Check if encryption is enabled
@@ -195,26 +262,34 @@ pub async fn unlock_encryption_with_password(password: String) -> Result<(), Str
     if !verify_password(&password, &password_hash)
         .map_err(|e| format!("Password verification failed: {}", e))?
     {
+        audit::record("vault_unlock_failed", None, "Invalid password").await;
+        record_failed_unlock(&app_handle).await?;
         return Err("Invalid password".to_string());
     }
 
+    reset_unlock_attempts().await?;
+
     // Unlock encryption
     unlock_encryption(&password, &salt_bytes)
         .map_err(|e| format!("Failed to unlock encryption: {}", e))?;
 
+    audit::record("vault_unlocked", None, "").await;
     println!("🔓 Encryption unlocked successfully");
     Ok(())
 }
 
 /// Lock encryption (clear key from memory)
 #[command]
-pub fn lock_encryption_command() {
+pub async fn lock_encryption_command() {
     lock_encryption();
+    clear_confirmation();
+    audit::record("vault_locked", None, "").await;
 }
 
 /// Change master password
 #[command]
 pub async fn change_master_password(
+    app_handle: AppHandle,
     old_password: String,
     new_password: String,
 ) -> Result<(), String> {
@@ -222,6 +297,8 @@ pub async fn change_master_password(
         return Err("New password must be at least 8 characters long".to_string());
     }
 
+    check_unlock_allowed().await?;
+
     let pool = db::pool();
 
     // Verify old password first
@@ -236,9 +313,32 @@ pub async fn change_master_password(
     if !verify_password(&old_password, &old_password_hash)
         .map_err(|e| format!("Password verification failed: {}", e))?
     {
+        audit::record(
+            "master_password_change_failed",
+            None,
+            "Invalid old password",
+        )
+        .await;
+        record_failed_unlock(&app_handle).await?;
         return Err("Invalid old password".to_string());
     }
 
+    reset_unlock_attempts().await?;
+
+    // Data that is kept across the change is decrypted with the old key, so make sure it's loaded
+    let old_salt_b64 =
+        sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = 'encryption_salt'")
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to get encryption salt: {}", e))?
+            .ok_or("Encryption salt not found")?
+            .0;
+    let old_salt_bytes = BASE64
+        .decode(&old_salt_b64)
+        .map_err(|e| format!("Failed to decode salt: {}", e))?;
+    unlock_encryption(&old_password, &old_salt_bytes)
+        .map_err(|e| format!("Failed to unlock encryption: {}", e))?;
+
     // Generate a new salt
     let mut new_salt_bytes = [0u8; 16];
     OsRng.fill_bytes(&mut new_salt_bytes);
@@ -255,44 +355,260 @@ pub async fn change_master_password(
 
     tracing::info!("🔄 Starting password change process");
 
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
     // Delete all cached email data and sync state
     // This is simpler and more reliable than re-encrypting
     tracing::info!("Deleting all cached email data and sync state...");
 
     // Delete all emails
     sqlx::query("DELETE FROM emails")
-        .execute(pool.as_ref())
+        .execute(&mut *tx)
         .await
         .map_err(|e| format!("Failed to delete email cache: {}", e))?;
 
     // Delete all sync state (uidvalidity, highest_uid, etc.)
     sqlx::query("DELETE FROM sync_status")
-        .execute(pool.as_ref())
+        .execute(&mut *tx)
         .await
         .map_err(|e| format!("Failed to delete sync state: {}", e))?;
 
-    tracing::info!("✅ Cached email data and sync state deleted successfully");
-
-    // Update encryption key in memory immediately with new password
+    // Queued outgoing messages can't be fetched again like the cache, so they are re-encrypted
+    let outbox =
+        sqlx::query_as::<_, (i64, Vec<u8>)>("SELECT id, message FROM outbox WHERE encrypted = 1")
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load outbox: {}", e))?
+            .into_iter()
+            .map(|(id, message)| {
+                decrypt_bytes(&String::from_utf8_lossy(&message))
+                    .map(|raw| (id, raw))
+                    .map_err(|e| format!("Failed to decrypt outbox message {}: {}", id, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+    // Notes only exist locally, so they are re-encrypted too
+    let notes = sqlx::query_as::<_, (i64, String)>("SELECT id, note FROM email_notes")
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load notes: {}", e))?
+        .into_iter()
+        .map(|(id, note)| {
+            decrypt(&note)
+                .map(|note| (id, note))
+                .map_err(|e| format!("Failed to decrypt note {}: {}", id, e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Update encryption key in memory with the new password for re-encrypting
     unlock_encryption(&new_password, &new_salt_bytes)
         .map_err(|e| format!("Failed to unlock with new password: {}", e))?;
 
-    // Update database with new credentials
-    sqlx::query("UPDATE settings SET value = ? WHERE key = 'encryption_salt'")
-        .bind(&new_salt_b64)
-        .execute(pool.as_ref())
-        .await
-        .map_err(|e| format!("Failed to save new encryption salt: {}", e))?;
+    let saved = async {
+        for (id, raw) in outbox {
+            let stored = encrypt_bytes(&raw)
+                .map_err(|e| format!("Failed to encrypt outbox message {}: {}", id, e))?;
+            sqlx::query("UPDATE outbox SET message = ? WHERE id = ?")
+                .bind(stored.into_bytes())
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to update outbox: {}", e))?;
+        }
 
-    sqlx::query("UPDATE settings SET value = ? WHERE key = 'password_hash'")
-        .bind(&new_password_hash)
-        .execute(pool.as_ref())
-        .await
-        .map_err(|e| format!("Failed to save new password hash: {}", e))?;
+        for (id, note) in notes {
+            let stored =
+                encrypt(&note).map_err(|e| format!("Failed to encrypt note {}: {}", id, e))?;
+            sqlx::query("UPDATE email_notes SET note = ? WHERE id = ?")
+                .bind(stored)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to update note: {}", e))?;
+        }
+
+        // Update database with new credentials
+        sqlx::query("UPDATE settings SET value = ? WHERE key = 'encryption_salt'")
+            .bind(&new_salt_b64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save new encryption salt: {}", e))?;
+
+        sqlx::query("UPDATE settings SET value = ? WHERE key = 'password_hash'")
+            .bind(&new_password_hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save new password hash: {}", e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to change master password: {}", e))
+    }
+    .await;
+
+    // Nothing was changed on disk, so the old key still applies
+    if let Err(e) = saved {
+        let _ = unlock_encryption(&old_password, &old_salt_bytes);
+        return Err(e);
+    }
+
+    tracing::info!("✅ Cached email data and sync state deleted successfully");
 
+    audit::record(
+        "master_password_changed",
+        None,
+        "Email cache and sync state cleared",
+    )
+    .await;
     tracing::info!(
         "✅ Master password changed successfully. Email cache and sync state have been deleted."
     );
 
     Ok(())
 }
+
+/// Get the current unlock cooldown (e.g. to show a countdown on the unlock screen)
+#[command]
+pub async fn get_unlock_cooldown() -> Result<UnlockCooldown, String> {
+    unlock_cooldown().await
+}
+
+/// Configure how many failed unlocks are allowed and how long the lockout lasts
+#[command]
+pub async fn set_unlock_lockout_policy(
+    max_attempts: i64,
+    lockout_minutes: i64,
+) -> Result<(), String> {
+    authorize("set_unlock_lockout_policy", Sensitivity::Sensitive).await?;
+
+    if max_attempts < FREE_UNLOCK_ATTEMPTS + 1 {
+        return Err(format!(
+            "Max attempts must be at least {}",
+            FREE_UNLOCK_ATTEMPTS + 1
+        ));
+    }
+    if lockout_minutes < 1 {
+        return Err("Lockout must be at least 1 minute".to_string());
+    }
+
+    set_setting("unlock_max_attempts", &max_attempts.to_string()).await?;
+    set_setting("unlock_lockout_minutes", &lockout_minutes.to_string()).await?;
+
+    audit::record(
+        "unlock_policy_changed",
+        None,
+        &format!(
+            "max_attempts={}, lockout_minutes={}",
+            max_attempts, lockout_minutes
+        ),
+    )
+    .await;
+    Ok(())
+}
+
+/// Refuse an unlock attempt while a cooldown or lockout is active
+pub(crate) async fn check_unlock_allowed() -> Result<(), String> {
+    let cooldown = unlock_cooldown().await?;
+    if cooldown.retry_after_secs > 0 {
+        return Err(format!(
+            "Too many failed attempts. Try again in {} seconds",
+            cooldown.retry_after_secs
+        ));
+    }
+    Ok(())
+}
+
+/// Count a failed attempt, start the next delay (or the lockout) and notify the UI
+pub(crate) async fn record_failed_unlock(app_handle: &AppHandle) -> Result<(), String> {
+    let max_attempts = get_setting_i64("unlock_max_attempts", 10).await?;
+    let failed_attempts = failed_unlock_attempts(max_attempts).await? + 1;
+    let lockout_minutes = get_setting_i64("unlock_lockout_minutes", 15).await?;
+
+    // Exponential delay after the free attempts: 1s, 2s, 4s, ... capped, then a full lockout
+    let delay_secs = if failed_attempts >= max_attempts {
+        lockout_minutes * 60
+    } else if failed_attempts > FREE_UNLOCK_ATTEMPTS {
+        let exponent = (failed_attempts - FREE_UNLOCK_ATTEMPTS - 1).min(16) as u32;
+        (1i64 << exponent).min(MAX_UNLOCK_DELAY_SECS)
+    } else {
+        0
+    };
+
+    let retry_after = chrono::Utc::now().timestamp() + delay_secs;
+    set_setting("unlock_failed_attempts", &failed_attempts.to_string()).await?;
+    set_setting("unlock_retry_after", &retry_after.to_string()).await?;
+
+    if failed_attempts >= max_attempts {
+        audit::record(
+            "vault_unlock_lockout",
+            None,
+            &format!(
+                "{} failed attempts, locked for {} minutes",
+                failed_attempts, lockout_minutes
+            ),
+        )
+        .await;
+    }
+
+    let cooldown = unlock_cooldown().await?;
+    let _ = app_handle.emit("unlock-cooldown", &cooldown);
+    Ok(())
+}
+
+/// Clear the failure counter after a successful unlock
+pub(crate) async fn reset_unlock_attempts() -> Result<(), String> {
+    set_setting("unlock_failed_attempts", "0").await?;
+    set_setting("unlock_retry_after", "0").await
+}
+
+/// Failed attempts that still count; a lockout that has run out starts a fresh series, so the
+/// next mistake doesn't lock the vault again right away
+async fn failed_unlock_attempts(max_attempts: i64) -> Result<i64, String> {
+    let failed_attempts = get_setting_i64("unlock_failed_attempts", 0).await?;
+    let retry_after = get_setting_i64("unlock_retry_after", 0).await?;
+    if failed_attempts >= max_attempts && retry_after <= chrono::Utc::now().timestamp() {
+        return Ok(0);
+    }
+    Ok(failed_attempts)
+}
+
+async fn unlock_cooldown() -> Result<UnlockCooldown, String> {
+    let max_attempts = get_setting_i64("unlock_max_attempts", 10).await?;
+    let failed_attempts = failed_unlock_attempts(max_attempts).await?;
+    let retry_after = get_setting_i64("unlock_retry_after", 0).await?;
+    let now = chrono::Utc::now().timestamp();
+
+    Ok(UnlockCooldown {
+        failed_attempts,
+        remaining_attempts: (max_attempts - failed_attempts).max(0),
+        retry_after_secs: (retry_after - now).max(0),
+        locked_out: failed_attempts >= max_attempts && retry_after > now,
+    })
+}
+
+async fn get_setting_i64(key: &str, default: i64) -> Result<i64, String> {
+    let pool = db::pool();
+    let result = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to get {} setting: {}", key, e))?;
+
+    Ok(result
+        .and_then(|(value,)| value.parse().ok())
+        .unwrap_or(default))
+}
+
+async fn set_setting(key: &str, value: &str) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(key)
+        .bind(value)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to set {} setting: {}", key, e))?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+// Missed event replay
+// Events emitted while no window is listening (e.g. while the main window is recreated after
+// a tray restore) are dropped by Tauri. Critical events (new mail, send failures, account
+// attention) are therefore also kept in a bounded in-memory buffer with an increasing cursor;
+// the frontend calls `fetch_missed_events` on mount with the last cursor it saw and replays
+// whatever it missed.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter};
+
+/// Events kept for replay; older ones are dropped first
+const MAX_BUFFERED_EVENTS: usize = 500;
+
+/// An emitted event kept for replay
+#[derive(Serialize, Debug, Clone)]
+pub struct BufferedEvent {
+    pub cursor: u64,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub emitted_at: i64,
+}
+
+/// Events after a cursor
+#[derive(Serialize, Debug, Clone)]
+pub struct MissedEvents {
+    pub events: Vec<BufferedEvent>,
+    pub cursor: u64,     // Pass this back on the next call
+    pub truncated: bool, // Some events after `since_cursor` were already dropped
+}
+
+struct EventBuffer {
+    events: VecDeque<BufferedEvent>,
+    next_cursor: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref BUFFER: Mutex<EventBuffer> = Mutex::new(EventBuffer {
+        events: VecDeque::new(),
+        next_cursor: 1,
+    });
+}
+
+/// Emit an event to all windows and keep it for `fetch_missed_events`
+pub fn emit_buffered<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if let Ok(mut buffer) = BUFFER.lock() {
+        let cursor = buffer.next_cursor;
+        buffer.next_cursor += 1;
+        buffer.events.push_back(BufferedEvent {
+            cursor,
+            event: event.to_string(),
+            payload: serde_json::to_value(payload.clone()).unwrap_or_default(),
+            emitted_at: chrono::Utc::now().timestamp(),
+        });
+        if buffer.events.len() > MAX_BUFFERED_EVENTS {
+            buffer.events.pop_front();
+        }
+    }
+
+    let _ = app_handle.emit(event, payload);
+}
+
+/// Buffered events emitted after `since_cursor` (all buffered events if None)
+#[command]
+pub fn fetch_missed_events(since_cursor: Option<u64>) -> Result<MissedEvents, String> {
+    let buffer = BUFFER.lock().map_err(|e| e.to_string())?;
+    let since = since_cursor.unwrap_or(0);
+
+    let events: Vec<BufferedEvent> = buffer
+        .events
+        .iter()
+        .filter(|event| event.cursor > since)
+        .cloned()
+        .collect();
+    let oldest = buffer.events.front().map(|event| event.cursor);
+
+    Ok(MissedEvents {
+        truncated: since_cursor.is_some() && oldest.is_some_and(|oldest| oldest > since + 1),
+        cursor: buffer.next_cursor - 1,
+        events,
+    })
+}
@@ -0,0 +1,500 @@
+// Local ↔ IMAP folder conversion
+// Guided backend operations that move a whole folder between local-only storage and the server.
+// Each direction rolls back its partial work if any step fails.
+
+use crate::commands::emails::append::{run_append_job, upload_needs_decryption};
+use crate::commands::emails::cache::{
+    save_attachments_to_cache, save_email_body_to_cache, save_emails_to_cache,
+};
+use crate::commands::emails::fetch::parse_raw_message;
+use crate::commands::emails::imap_helpers;
+use crate::commands::emails::sync::parse::parse_email_headers;
+use crate::commands::folders::encode_folder_name;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::{AccountConfig, Attachment, EmailHeader, Folder, FolderConversionProgress};
+use tauri::{command, AppHandle, Emitter};
+
+/// Number of messages downloaded per UID FETCH when converting a remote folder
+const DOWNLOAD_BATCH_SIZE: usize = 50;
+
+type ImapSession = imap::Session<Box<dyn imap::ImapConnection>>;
+
+/// Emit a `folder-conversion-progress` event
+fn emit_progress(
+    app_handle: &AppHandle,
+    folder_name: &str,
+    stage: &str,
+    processed: i64,
+    total: i64,
+) {
+    let _ = app_handle.emit(
+        "folder-conversion-progress",
+        FolderConversionProgress {
+            folder_name: folder_name.to_string(),
+            stage: stage.to_string(),
+            processed,
+            total,
+        },
+    );
+}
+
+/// Convert a local folder into a remote IMAP folder
+///
+/// Steps: create the remote folder, upload every cached message (bulk APPEND), then flip the
+/// folder row to remote. If creation or upload fails, the remote folder is deleted again and the
+/// local folder is left untouched.
+#[command]
+pub async fn convert_local_folder_to_remote(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    folder_name: String,
+) -> Result<Folder, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let pool = db::pool();
+
+    let (folder_id, display_name, delimiter) = sqlx::query_as::<_, (i32, String, Option<String>)>(
+        "SELECT id, display_name, delimiter FROM folders WHERE account_id = ? AND name = ? AND is_local = 1",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load folder: {}", e))?
+    .ok_or("Local folder not found")?;
+
+    // Fail before creating anything on the server rather than roll back mid-upload
+    upload_needs_decryption().await?;
+
+    println!(
+        "🔄 Converting local folder '{}' to remote for {}",
+        display_name, config.email
+    );
+
+    // Ensure we have a valid access token (refresh if needed)
+    let config = ensure_valid_token(config).await?;
+    let remote_name = encode_folder_name(&display_name);
+
+    // Step 1: create the remote folder
+    emit_progress(&app_handle, &folder_name, "creating", 0, 0);
+    let config_clone = config.clone();
+    let remote_name_clone = remote_name.clone();
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config_clone)?;
+        imap_session
+            .create(&remote_name_clone)
+            .map_err(|e| format!("Failed to create remote folder: {}", e))?;
+        let _ = imap_session.logout();
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // Step 2: upload the contents
+    emit_progress(&app_handle, &folder_name, "uploading", 0, 0);
+    let summary = match run_append_job(&app_handle, config.clone(), &folder_name, &remote_name)
+        .await
+    {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("❌ Upload failed, rolling back remote folder: {}", e);
+            emit_progress(&app_handle, &folder_name, "rolling_back", 0, 0);
+            rollback_remote_folder(&config, &remote_name).await;
+            let _ = sqlx::query(
+                "DELETE FROM append_jobs WHERE account_id = ? AND source_folder = ? AND target_folder = ?",
+            )
+            .bind(account_id)
+            .bind(&folder_name)
+            .bind(&remote_name)
+            .execute(pool.as_ref())
+            .await;
+            return Err(format!("Failed to convert folder: {}", e));
+        }
+    };
+
+    // Step 3: flip the folder to remote and drop the local copies.
+    // The next sync downloads the messages again with their server UIDs.
+    emit_progress(
+        &app_handle,
+        &folder_name,
+        "finalizing",
+        summary.uploaded,
+        summary.total,
+    );
+    let finalize: Result<(), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("UPDATE folders SET name = ?, is_local = 0 WHERE id = ?")
+            .bind(&remote_name)
+            .bind(folder_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "DELETE FROM attachments WHERE email_id IN
+             (SELECT id FROM emails WHERE account_id = ? AND folder_name = ?)",
+        )
+        .bind(account_id)
+        .bind(&folder_name)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ?")
+            .bind(account_id)
+            .bind(&folder_name)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM sync_status WHERE account_id = ? AND folder_name = ?")
+            .bind(account_id)
+            .bind(&folder_name)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "DELETE FROM append_jobs WHERE account_id = ? AND source_folder = ? AND target_folder = ?",
+        )
+        .bind(account_id)
+        .bind(&folder_name)
+        .bind(&remote_name)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(e) = finalize {
+        eprintln!(
+            "❌ Failed to update local state, rolling back remote folder: {}",
+            e
+        );
+        emit_progress(&app_handle, &folder_name, "rolling_back", 0, 0);
+        rollback_remote_folder(&config, &remote_name).await;
+        return Err(format!("Failed to convert folder: {}", e));
+    }
+    crate::commands::emails::maildir::remove_folder(account_id, &folder_name);
+
+    emit_progress(
+        &app_handle,
+        &folder_name,
+        "completed",
+        summary.uploaded,
+        summary.total,
+    );
+    println!(
+        "✅ Converted local folder '{}' to remote '{}' ({} messages)",
+        display_name, remote_name, summary.uploaded
+    );
+
+    Ok(Folder {
+        id: Some(folder_id),
+        account_id,
+        name: remote_name,
+        display_name,
+        delimiter,
+        flags: None,
+        is_local: false,
+        total_count: None,
+        unread_count: None,
+        sync_on_open: false,
+        role: None,
+    })
+}
+
+/// Convert a remote IMAP folder into a local folder
+///
+/// Steps: download every message (headers, body, attachments) into a new local folder, then
+/// delete the remote folder. If anything fails before the remote delete succeeds, the
+/// downloaded copies are removed and the remote folder is left untouched.
+#[command]
+pub async fn convert_remote_folder_to_local(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    folder_name: String,
+) -> Result<Folder, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let pool = db::pool();
+
+    let (display_name, delimiter) = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT display_name, delimiter FROM folders WHERE account_id = ? AND name = ? AND COALESCE(is_local, 0) = 0",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load folder: {}", e))?
+    .ok_or("Remote folder not found")?;
+
+    let local_name = format!("local_{}", display_name);
+    let existing: Option<(i32,)> =
+        sqlx::query_as("SELECT 1 FROM folders WHERE account_id = ? AND name = ?")
+            .bind(account_id)
+            .bind(&local_name)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| e.to_string())?;
+    if existing.is_some() {
+        return Err("A local folder with this name already exists".to_string());
+    }
+
+    println!(
+        "🔄 Converting remote folder '{}' to local for {}",
+        folder_name, config.email
+    );
+
+    // Ensure we have a valid access token (refresh if needed)
+    let config = ensure_valid_token(config).await?;
+
+    let result = download_and_delete_remote(
+        &app_handle,
+        &config,
+        account_id,
+        &folder_name,
+        &local_name,
+        &display_name,
+        delimiter.clone(),
+    )
+    .await;
+
+    match result {
+        Ok(folder) => {
+            emit_progress(&app_handle, &folder_name, "completed", 0, 0);
+            println!(
+                "✅ Converted remote folder '{}' to local '{}'",
+                folder_name, local_name
+            );
+            Ok(folder)
+        }
+        Err(e) => {
+            eprintln!("❌ Conversion failed, removing downloaded copies: {}", e);
+            emit_progress(&app_handle, &folder_name, "rolling_back", 0, 0);
+            let _ = sqlx::query(
+                "DELETE FROM attachments WHERE email_id IN
+                 (SELECT id FROM emails WHERE account_id = ? AND folder_name = ?)",
+            )
+            .bind(account_id)
+            .bind(&local_name)
+            .execute(pool.as_ref())
+            .await;
+            let _ = sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ?")
+                .bind(account_id)
+                .bind(&local_name)
+                .execute(pool.as_ref())
+                .await;
+            let _ = sqlx::query("DELETE FROM folders WHERE account_id = ? AND name = ?")
+                .bind(account_id)
+                .bind(&local_name)
+                .execute(pool.as_ref())
+                .await;
+            Err(format!("Failed to convert folder: {}", e))
+        }
+    }
+}
+
+/// Download a remote folder into `local_name`, then delete it on the server
+async fn download_and_delete_remote(
+    app_handle: &AppHandle,
+    config: &AccountConfig,
+    account_id: i32,
+    folder_name: &str,
+    local_name: &str,
+    display_name: &str,
+    delimiter: Option<String>,
+) -> Result<Folder, String> {
+    let pool = db::pool();
+
+    // Step 1: list all UIDs in the remote folder
+    let config_clone = config.clone();
+    let folder_clone = folder_name.to_string();
+    let (mut session, mut uids) =
+        tokio::task::spawn_blocking(move || -> Result<(ImapSession, Vec<u32>), String> {
+            let mut imap_session = imap_helpers::connect_and_login(&config_clone)?;
+            imap_session
+                .select(&folder_clone)
+                .map_err(|e| format!("Cannot access folder '{}': {}", folder_clone, e))?;
+            let uids = imap_session
+                .uid_search("ALL")
+                .map_err(|e| format!("Failed to search folder: {}", e))?;
+            Ok((imap_session, uids.into_iter().collect()))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+    uids.sort_unstable();
+
+    let total = uids.len() as i64;
+    let mut processed = 0i64;
+    emit_progress(app_handle, folder_name, "downloading", 0, total);
+
+    // Step 2: download in batches, storing each batch under the local folder name
+    for chunk in uids.chunks(DOWNLOAD_BATCH_SIZE) {
+        let uid_list = chunk
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let (returned_session, batch) = tokio::task::spawn_blocking(move || {
+            let result = download_batch(&mut session, &uid_list);
+            (session, result)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        session = returned_session;
+        let batch = batch?;
+
+        let headers: Vec<EmailHeader> = batch.iter().map(|(h, _, _)| h.clone()).collect();
+        save_emails_to_cache(account_id, local_name, &headers).await?;
+
+        for (header, body, attachments) in &batch {
+            save_email_body_to_cache(account_id, local_name, header.uid, body).await?;
+
+            let (email_id,) = sqlx::query_as::<_, (i64,)>(
+                "SELECT id FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+            )
+            .bind(account_id)
+            .bind(local_name)
+            .bind(header.uid as i64)
+            .fetch_one(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to get email id: {}", e))?;
+
+            if !attachments.is_empty() {
+                save_attachments_to_cache(email_id, attachments).await?;
+            }
+
+            sqlx::query("UPDATE emails SET has_attachments = ? WHERE id = ?")
+                .bind(!attachments.is_empty() as i64)
+                .bind(email_id)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to update attachment info: {}", e))?;
+        }
+
+        processed += batch.len() as i64;
+        emit_progress(app_handle, folder_name, "downloading", processed, total);
+    }
+
+    // Step 3: register the local folder
+    let result = sqlx::query(
+        "INSERT INTO folders (account_id, name, display_name, delimiter, flags, is_local) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(account_id)
+    .bind(local_name)
+    .bind(display_name)
+    .bind(&delimiter)
+    .bind(None::<String>)
+    .bind(1)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to create local folder: {}", e))?;
+    let folder_id = result.last_insert_rowid() as i32;
+
+    // Step 4: delete the remote folder (must unselect it first on most servers)
+    emit_progress(app_handle, folder_name, "deleting_remote", processed, total);
+    let folder_clone = folder_name.to_string();
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let _ = session.close();
+        session
+            .delete(&folder_clone)
+            .map_err(|e| format!("Failed to delete remote folder: {}", e))?;
+        let _ = session.logout();
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // The remote folder is gone, so from here on errors must not trigger a rollback
+    // of the local copies. Stale cache rows are only logged.
+    let cleanup: Result<(), sqlx::Error> = async {
+        sqlx::query(
+            "DELETE FROM attachments WHERE email_id IN
+             (SELECT id FROM emails WHERE account_id = ? AND folder_name = ?)",
+        )
+        .bind(account_id)
+        .bind(folder_name)
+        .execute(pool.as_ref())
+        .await?;
+        sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ?")
+            .bind(account_id)
+            .bind(folder_name)
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query("DELETE FROM sync_status WHERE account_id = ? AND folder_name = ?")
+            .bind(account_id)
+            .bind(folder_name)
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query("DELETE FROM folders WHERE account_id = ? AND name = ?")
+            .bind(account_id)
+            .bind(folder_name)
+            .execute(pool.as_ref())
+            .await?;
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = cleanup {
+        eprintln!(
+            "⚠️ Failed to clean up cache for removed folder '{}': {}",
+            folder_name, e
+        );
+    }
+
+    Ok(Folder {
+        id: Some(folder_id),
+        account_id,
+        name: local_name.to_string(),
+        display_name: display_name.to_string(),
+        delimiter,
+        flags: None,
+        is_local: true,
+        total_count: None,
+        unread_count: None,
+        sync_on_open: false,
+        role: None,
+    })
+}
+
+/// Fetch headers and full content for a set of UIDs
+fn download_batch(
+    session: &mut ImapSession,
+    uid_list: &str,
+) -> Result<Vec<(EmailHeader, String, Vec<Attachment>)>, String> {
+    let messages = session
+        .uid_fetch(uid_list, "(UID ENVELOPE FLAGS INTERNALDATE BODY.PEEK[])")
+        .map_err(|e| format!("Failed to download messages: {}", e))?;
+
+    let mut batch = Vec::new();
+    for msg in messages.iter() {
+        let header = match parse_email_headers(std::iter::once(msg)).into_iter().next() {
+            Some(header) => header,
+            None => continue,
+        };
+        let (body, attachments) = match parse_raw_message(msg.body().unwrap_or_default()) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("⚠️ Failed to parse UID {}: {}", header.uid, e);
+                ("(No readable body found)".to_string(), Vec::new())
+            }
+        };
+        batch.push((header, body, attachments));
+    }
+
+    Ok(batch)
+}
+
+/// Best-effort removal of a remote folder created during a failed conversion
+async fn rollback_remote_folder(config: &AccountConfig, remote_name: &str) {
+    let config = config.clone();
+    let remote_name = remote_name.to_string();
+    let result = tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+        imap_session
+            .delete(&remote_name)
+            .map_err(|e| format!("Failed to delete folder: {}", e))?;
+        let _ = imap_session.logout();
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => println!("↩️ Rolled back remote folder"),
+        Ok(Err(e)) => eprintln!("⚠️ Failed to roll back remote folder: {}", e),
+        Err(e) => eprintln!("⚠️ Rollback task panicked: {}", e),
+    }
+}
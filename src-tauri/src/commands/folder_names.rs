@@ -0,0 +1,246 @@
+// Localized folder names
+// Providers name their special folders in their own language ("Sent Items", "已发送",
+// "[Gmail]/Sent Mail"), so a mixed set of accounts gives an inconsistent sidebar. Special-use
+// folders are recognised by their SPECIAL-USE attribute or, failing that, a well-known name,
+// and shown under a name in the configured language. Only `display_name` changes; `name`
+// stays the raw IMAP name used for every server operation. The role itself is stored with the
+// folder, so delete, archive, spam and sent-copy paths can find their target folder.
+
+use crate::db;
+use crate::models::Folder;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+const SETTING_KEY: &str = "language";
+
+/// Languages folder names can be shown in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    En,
+    Zh,
+    Ja,
+    De,
+    Fr,
+    Es,
+}
+
+/// Role of a special-use folder (RFC 6154, plus the inbox)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FolderRole {
+    Inbox,
+    Sent,
+    Drafts,
+    Trash,
+    Junk,
+    Archive,
+    All,
+    Flagged,
+}
+
+impl Language {
+    fn code(self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Zh => "zh",
+            Language::Ja => "ja",
+            Language::De => "de",
+            Language::Fr => "fr",
+            Language::Es => "es",
+        }
+    }
+
+    fn from_code(code: &str) -> Self {
+        match code {
+            "zh" => Language::Zh,
+            "ja" => Language::Ja,
+            "de" => Language::De,
+            "fr" => Language::Fr,
+            "es" => Language::Es,
+            _ => Language::En,
+        }
+    }
+}
+
+impl FolderRole {
+    /// Name stored in the `role` column of `folders`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FolderRole::Inbox => "inbox",
+            FolderRole::Sent => "sent",
+            FolderRole::Drafts => "drafts",
+            FolderRole::Trash => "trash",
+            FolderRole::Junk => "junk",
+            FolderRole::Archive => "archive",
+            FolderRole::All => "all",
+            FolderRole::Flagged => "flagged",
+        }
+    }
+
+    fn label(self, language: Language) -> &'static str {
+        use FolderRole::*;
+        use Language::*;
+        match (self, language) {
+            (Inbox, En) => "Inbox",
+            (Inbox, Zh) => "收件箱",
+            (Inbox, Ja) => "受信トレイ",
+            (Inbox, De) => "Posteingang",
+            (Inbox, Fr) => "Boîte de réception",
+            (Inbox, Es) => "Bandeja de entrada",
+            (Sent, En) => "Sent",
+            (Sent, Zh) => "已发送",
+            (Sent, Ja) => "送信済み",
+            (Sent, De) => "Gesendet",
+            (Sent, Fr) => "Envoyés",
+            (Sent, Es) => "Enviados",
+            (Drafts, En) => "Drafts",
+            (Drafts, Zh) => "草稿箱",
+            (Drafts, Ja) => "下書き",
+            (Drafts, De) => "Entwürfe",
+            (Drafts, Fr) => "Brouillons",
+            (Drafts, Es) => "Borradores",
+            (Trash, En) => "Trash",
+            (Trash, Zh) => "已删除",
+            (Trash, Ja) => "ゴミ箱",
+            (Trash, De) => "Papierkorb",
+            (Trash, Fr) => "Corbeille",
+            (Trash, Es) => "Papelera",
+            (Junk, En) => "Junk",
+            (Junk, Zh) => "垃圾邮件",
+            (Junk, Ja) => "迷惑メール",
+            (Junk, De) => "Spam",
+            (Junk, Fr) => "Indésirables",
+            (Junk, Es) => "Correo no deseado",
+            (Archive, En) => "Archive",
+            (Archive, Zh) => "归档",
+            (Archive, Ja) => "アーカイブ",
+            (Archive, De) => "Archiv",
+            (Archive, Fr) => "Archives",
+            (Archive, Es) => "Archivo",
+            (All, En) => "All Mail",
+            (All, Zh) => "所有邮件",
+            (All, Ja) => "すべてのメール",
+            (All, De) => "Alle Nachrichten",
+            (All, Fr) => "Tous les messages",
+            (All, Es) => "Todos",
+            (Flagged, En) => "Starred",
+            (Flagged, Zh) => "已加星标",
+            (Flagged, Ja) => "スター付き",
+            (Flagged, De) => "Markiert",
+            (Flagged, Fr) => "Suivis",
+            (Flagged, Es) => "Destacados",
+        }
+    }
+}
+
+/// The folder's special-use role, from its attributes or else its (top-level) name
+pub fn folder_role(display_name: &str, flags: Option<&str>) -> Option<FolderRole> {
+    if display_name.eq_ignore_ascii_case("inbox") {
+        return Some(FolderRole::Inbox);
+    }
+
+    // Attributes are stored in their Debug form, e.g. `[Extension("\\HasNoChildren"), Sent]`;
+    // XLIST's \AllMail, \Spam and \Starred stand for \All, \Junk and \Flagged
+    let from_flags = flags.and_then(|flags| {
+        flags
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .find_map(|token| match token {
+                "Sent" => Some(FolderRole::Sent),
+                "Drafts" => Some(FolderRole::Drafts),
+                "Trash" => Some(FolderRole::Trash),
+                "Junk" => Some(FolderRole::Junk),
+                "Archive" => Some(FolderRole::Archive),
+                "All" | "AllMail" => Some(FolderRole::All),
+                "Flagged" | "Starred" => Some(FolderRole::Flagged),
+                "Spam" => Some(FolderRole::Junk),
+                _ => None,
+            })
+    });
+    if from_flags.is_some() {
+        return from_flags;
+    }
+
+    // Servers without SPECIAL-USE: only recognise unambiguous top-level names
+    match display_name.to_lowercase().as_str() {
+        "收件箱" => Some(FolderRole::Inbox),
+        "sent" | "sent items" | "sent mail" | "sent messages" | "已发送" | "已发送邮件" => {
+            Some(FolderRole::Sent)
+        }
+        "drafts" | "draft" | "草稿箱" | "草稿" => Some(FolderRole::Drafts),
+        "trash" | "deleted" | "deleted items" | "deleted messages" | "bin" | "已删除"
+        | "已删除邮件" => Some(FolderRole::Trash),
+        "junk" | "spam" | "junk e-mail" | "junk email" | "垃圾邮件" => Some(FolderRole::Junk),
+        "archive" | "archives" | "归档" => Some(FolderRole::Archive),
+        "all mail" => Some(FolderRole::All),
+        "starred" | "flagged" => Some(FolderRole::Flagged),
+        _ => None,
+    }
+}
+
+/// The configured display language
+pub async fn language() -> Language {
+    let pool = db::pool();
+    sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(SETTING_KEY)
+        .fetch_optional(pool.as_ref())
+        .await
+        .ok()
+        .flatten()
+        .map(|(value,)| Language::from_code(&value))
+        .unwrap_or_default()
+}
+
+/// Replace special-use folders' display names with their localized names
+pub fn localize(folders: &mut [Folder], language: Language) {
+    for folder in folders.iter_mut().filter(|folder| !folder.is_local) {
+        if let Some(role) = folder_role(&folder.display_name, folder.flags.as_deref()) {
+            folder.display_name = role.label(language).to_string();
+        }
+    }
+}
+
+/// Get the language folder names are shown in
+#[command]
+pub async fn get_language() -> Result<Language, String> {
+    Ok(language().await)
+}
+
+/// Set the language folder names are shown in
+#[command]
+pub async fn set_language(language: Language) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(SETTING_KEY)
+        .bind(language.code())
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save language setting: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_role() {
+        assert_eq!(folder_role("INBOX", None), Some(FolderRole::Inbox));
+        assert_eq!(
+            folder_role(
+                "Sent Mail",
+                Some("[Extension(\"\\\\HasNoChildren\"), Sent]")
+            ),
+            Some(FolderRole::Sent)
+        );
+        assert_eq!(folder_role("已发送", Some("[]")), Some(FolderRole::Sent));
+        // XLIST attributes
+        assert_eq!(
+            folder_role("Bulk", Some("[Extension(\"\\\\Spam\")]")),
+            Some(FolderRole::Junk)
+        );
+        assert_eq!(folder_role("Projects/Sent", Some("[]")), None);
+        assert_eq!(folder_role("Allotments", Some("[]")), None);
+        assert_eq!(FolderRole::Trash.label(Language::Zh), "已删除");
+    }
+}
@@ -0,0 +1,146 @@
+// Folder status counts
+// Message, unread and recent counts come from IMAP STATUS, which doesn't SELECT the folder,
+// so the sidebar can show unread badges for every folder without opening each one. Counts
+// are cached in the folders table (total_count, unread_count) and returned by `load_folders`.
+
+use crate::commands::emails::imap_helpers;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::AccountConfig;
+use serde::Serialize;
+use tauri::command;
+
+type ImapSession = imap::Session<Box<dyn imap::ImapConnection>>;
+
+/// STATUS counts of one folder
+#[derive(Serialize, Debug, Clone)]
+pub struct FolderStatus {
+    pub folder_name: String,
+    pub messages: u32,
+    pub unseen: u32,
+    pub recent: u32,
+}
+
+/// Run STATUS for a folder
+fn folder_status(
+    imap_session: &mut ImapSession,
+    folder_name: &str,
+) -> Result<FolderStatus, String> {
+    let mailbox = imap_session
+        .status(folder_name, "(MESSAGES UNSEEN RECENT)")
+        .map_err(|e| format!("STATUS failed for '{}': {}", folder_name, e))?;
+
+    Ok(FolderStatus {
+        folder_name: folder_name.to_string(),
+        messages: mailbox.exists,
+        unseen: mailbox.unseen.unwrap_or(0),
+        recent: mailbox.recent,
+    })
+}
+
+/// Store STATUS counts on the cached folders
+async fn cache_statuses(account_id: i32, statuses: &[FolderStatus]) -> Result<(), String> {
+    let pool = db::pool();
+
+    for status in statuses {
+        sqlx::query(
+            "UPDATE folders SET total_count = ?, unread_count = ? WHERE account_id = ? AND name = ?",
+        )
+        .bind(status.messages as i64)
+        .bind(status.unseen as i64)
+        .bind(account_id)
+        .bind(&status.folder_name)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to cache folder counts: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Get (and cache) the message, unread and recent counts of one folder
+#[command]
+pub async fn get_folder_status(
+    config: AccountConfig,
+    folder_name: String,
+) -> Result<FolderStatus, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    if crate::commands::demo::is_demo_account(&config) {
+        return Err("Demo folders are local and have no server status".to_string());
+    }
+    let config = ensure_valid_token(config).await?;
+
+    let status = tokio::task::spawn_blocking(move || -> Result<FolderStatus, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+        let status = folder_status(&mut imap_session, &folder_name);
+        let _ = imap_session.logout();
+        status
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    cache_statuses(account_id, std::slice::from_ref(&status)).await?;
+    Ok(status)
+}
+
+/// Get (and cache) the counts of every cached remote folder of an account over one connection
+/// Folders whose STATUS fails are left out.
+#[command]
+pub async fn get_all_folder_status(config: AccountConfig) -> Result<Vec<FolderStatus>, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    if crate::commands::demo::is_demo_account(&config) {
+        return Ok(Vec::new());
+    }
+
+    let pool = db::pool();
+    let folder_names: Vec<String> = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT name, flags FROM folders
+         WHERE account_id = ? AND COALESCE(is_local, 0) = 0 AND COALESCE(inaccessible, 0) = 0",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load folders: {}", e))?
+    .into_iter()
+    .filter(|(_, flags)| {
+        let flags = flags.as_deref().unwrap_or_default().to_lowercase();
+        !flags.contains("noselect") && !flags.contains("nonexistent")
+    })
+    .map(|(name, _)| name)
+    .collect();
+
+    if folder_names.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let config = ensure_valid_token(config).await?;
+    let statuses = tokio::task::spawn_blocking(move || -> Result<Vec<FolderStatus>, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+        let statuses = folder_names
+            .iter()
+            .filter_map(
+                |folder_name| match folder_status(&mut imap_session, folder_name) {
+                    Ok(status) => Some(status),
+                    Err(e) => {
+                        eprintln!("⚠️ {}", e);
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        let _ = imap_session.logout();
+        Ok(statuses)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    cache_statuses(account_id, &statuses).await?;
+    println!(
+        "✅ Refreshed counts for {} folders of account {}",
+        statuses.len(),
+        account_id
+    );
+    Ok(statuses)
+}
@@ -1,10 +1,24 @@
-use crate::commands::emails::imap_helpers;
+use crate::commands::emails::{imap_helpers, maildir};
+use crate::commands::folder_names::{folder_role, language, localize, FolderRole};
 use crate::commands::utils::ensure_valid_token;
 use crate::db;
 use crate::models::{AccountConfig, Folder};
+use base64::{engine::general_purpose, Engine as _};
+use imap_proto::types::{MailboxDatum, Response, StatusAttribute};
+use std::collections::{HashMap, HashSet};
 use tauri::command;
 use utf7_imap;
 
+type ImapSession = imap::Session<Box<dyn imap::ImapConnection>>;
+
+/// A mailbox returned by `LIST ... RETURN (STATUS ...)` (RFC 5819)
+struct ListedMailbox {
+    name: String,
+    delimiter: Option<String>,
+    flags: String,
+    status: Option<(u32, u32)>, // (MESSAGES, UNSEEN); absent for non-selectable mailboxes
+}
+
 /// Decode IMAP folder name from modified UTF-7 encoding
 fn decode_folder_name(encoded_name: &str) -> String {
     // The utf7_imap crate's decode function doesn't return a Result,
@@ -12,12 +26,42 @@ fn decode_folder_name(encoded_name: &str) -> String {
     utf7_imap::decode_utf7_imap(encoded_name.to_string())
 }
 
-/// Encode folder name to IMAP modified UTF-7 encoding
-fn encode_folder_name(folder_name: &str) -> String {
-    // For now, we'll just return the original string as most modern mail providers
-    // support UTF-8. If needed, we can add a proper UTF-7 encoding implementation.
-    // The utf7-imap crate only provides decode functionality.
-    folder_name.to_string()
+/// Encode folder name to IMAP modified UTF-7 encoding (RFC 3501 section 5.1.3)
+///
+/// The utf7-imap crate only provides decoding. Printable ASCII stays as is ('&' becomes "&-");
+/// other characters are written as UTF-16 in base64 (with ',' for '/') between '&' and '-'.
+pub(crate) fn encode_folder_name(folder_name: &str) -> String {
+    fn flush(pending: &mut Vec<u16>, out: &mut String) {
+        if pending.is_empty() {
+            return;
+        }
+        let bytes: Vec<u8> = pending.drain(..).flat_map(u16::to_be_bytes).collect();
+        out.push('&');
+        out.push_str(
+            &general_purpose::STANDARD_NO_PAD
+                .encode(bytes)
+                .replace('/', ","),
+        );
+        out.push('-');
+    }
+
+    let mut out = String::with_capacity(folder_name.len());
+    let mut pending = Vec::new();
+    for c in folder_name.chars() {
+        if (' '..='~').contains(&c) {
+            flush(&mut pending, &mut out);
+            if c == '&' {
+                out.push_str("&-");
+            } else {
+                out.push(c);
+            }
+        } else {
+            let mut units = [0u16; 2];
+            pending.extend_from_slice(c.encode_utf16(&mut units));
+        }
+    }
+    flush(&mut pending, &mut out);
+    out
 }
 
 /// Generate a user-friendly display name for a folder
@@ -39,9 +83,15 @@ fn get_display_name(full_name: &str) -> String {
 pub async fn fetch_folders(config: AccountConfig) -> Result<Vec<Folder>, String> {
     println!("Fetching folders for {}", config.email);
 
+    // Demo folders are local and never listed from a server
+    if crate::commands::demo::is_demo_account(&config) {
+        return load_folders(config.id.ok_or("Account ID is required")?).await;
+    }
+
     // Ensure we have a valid access token (refresh if needed)
     let config = ensure_valid_token(config).await?;
     let account_id = config.id.ok_or("Account ID is required")?;
+    let subscribed_only = load_subscribed_only(account_id).await?;
 
     let folders = tokio::task::spawn_blocking(move || -> Result<Vec<Folder>, String> {
         // Use helper function for connection with imap 3.0.0 API
@@ -49,39 +99,108 @@ pub async fn fetch_folders(config: AccountConfig) -> Result<Vec<Folder>, String>
 
         println!("IMAP authentication successful, listing folders...");
 
-        // List all folders using "*" pattern
-        let mailbox_list = imap_session
-            .list(Some(""), Some("*"))
-            .map_err(|e| e.to_string())?;
+        let capabilities = imap_session
+            .capabilities()
+            .map_err(|e| format!("Failed to get capabilities: {}", e))?;
+        let supports_list_status = capabilities.has_str("LIST-STATUS");
+        let supports_special_use = capabilities.has_str("SPECIAL-USE");
+        // Older Gmail-style servers report special-use attributes through XLIST instead
+        let supports_xlist = !supports_special_use && capabilities.has_str("XLIST");
+
+        // With LIST-STATUS one command returns attributes and counts for every folder, and a
+        // STATUS reply proves the folder is accessible. Otherwise trust the LIST attributes;
+        // folders that turn out to be inaccessible are hidden on first use (see
+        // `mark_folder_inaccessible`) instead of SELECTing every folder up front.
+        let listed_with_status = if supports_list_status {
+            match list_with_status(&mut imap_session, supports_special_use) {
+                Ok(listed) => Some(listed),
+                Err(e) => {
+                    println!("⚠️ LIST-STATUS failed ({}), falling back to LIST", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let has_status = listed_with_status.is_some();
+        let listed_with_xlist = if !has_status && supports_xlist {
+            match list_with_xlist(&mut imap_session) {
+                Ok(listed) => Some(listed),
+                Err(e) => {
+                    println!("⚠️ XLIST failed ({}), falling back to LIST", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let listed = match listed_with_status.or(listed_with_xlist) {
+            Some(listed) => listed,
+            None => imap_session
+                .list(Some(""), Some("*"))
+                .map_err(|e| e.to_string())?
+                .iter()
+                .map(|mailbox| ListedMailbox {
+                    name: mailbox.name().to_string(),
+                    delimiter: mailbox.delimiter().map(|d| d.to_string()),
+                    flags: format!("{:?}", mailbox.attributes()),
+                    status: None,
+                })
+                .collect(),
+        };
+
+        // Accounts with many shared folders can limit colimail to the ones they subscribed to
+        let subscribed: Option<HashSet<String>> = if subscribed_only {
+            let names = imap_session
+                .lsub(Some(""), Some("*"))
+                .map_err(|e| format!("Failed to list subscribed folders: {}", e))?
+                .iter()
+                .map(|mailbox| mailbox.name().to_string())
+                .collect();
+            Some(names)
+        } else {
+            None
+        };
 
         let mut folders = Vec::new();
-        let mut tested_accessible = Vec::new();
 
-        for mailbox in mailbox_list.iter() {
-            let raw_name = mailbox.name().to_string();
+        for mailbox in listed {
+            let raw_name = mailbox.name;
+
+            // INBOX is always kept, even if the server doesn't report it as subscribed
+            if let Some(subscribed) = &subscribed {
+                if !subscribed.contains(&raw_name) && !raw_name.eq_ignore_ascii_case("INBOX") {
+                    println!("  📁 Folder: {} ⊗ (Not subscribed, skipping)", raw_name);
+                    continue;
+                }
+            }
 
             // Decode from UTF-7 IMAP encoding for display purposes
             let decoded_name = decode_folder_name(&raw_name);
 
             // Generate user-friendly display name
             let display_name = get_display_name(&decoded_name);
-
-            let delimiter = mailbox.delimiter().map(|d| d.to_string());
-            let flags = Some(format!("{:?}", mailbox.attributes()));
+            let role = folder_role(&display_name, Some(&mailbox.flags));
 
             let folder = Folder {
                 id: None,
                 account_id,
                 name: raw_name.clone(), // Store RAW (encoded) name for IMAP operations
                 display_name: display_name.clone(),
-                delimiter,
-                flags: flags.clone(),
+                delimiter: mailbox.delimiter,
+                flags: Some(mailbox.flags),
                 is_local: false, // IMAP folders are not local
+                total_count: mailbox.status.map(|(messages, _)| messages as i64),
+                unread_count: mailbox.status.map(|(_, unseen)| unseen as i64),
+                sync_on_open: false,
+                role: role.map(|role| role.as_str().to_string()),
             };
 
             // Log folder info with visibility status
             let status = if !folder.is_selectable() {
-                "✗ (Noselect)"
+                "✗ (Noselect/NonExistent)"
             } else if !folder.should_show_to_user() {
                 "⊗ (System folder, hidden)"
             } else {
@@ -92,31 +211,22 @@ pub async fn fetch_folders(config: AccountConfig) -> Result<Vec<Folder>, String>
                 raw_name, display_name, status
             );
 
-            // Only test and add folders that should be shown to users
-            if folder.should_show_to_user() {
-                // Test if folder is actually accessible by trying to SELECT it
-                // IMPORTANT: Use raw_name (UTF-7 encoded) for IMAP operations
-                let folder_name_for_log = display_name.clone();
-                match imap_session.select(&raw_name) {
-                    Ok(_) => {
-                        println!("     ✓ Folder is accessible");
-                        tested_accessible.push(folder_name_for_log);
-                        folders.push(folder);
-                    }
-                    Err(e) => {
-                        println!("     ✗ Folder cannot be accessed: {} (skipping)", e);
-                    }
-                }
+            // Only add folders that should be shown to users
+            if !folder.should_show_to_user() {
+                continue;
+            }
+
+            if has_status && folder.total_count.is_none() {
+                println!("     ✗ No STATUS returned, folder is not accessible (skipping)");
+                continue;
             }
+
+            folders.push(folder);
         }
 
         let _ = imap_session.logout();
 
-        println!(
-            "✅ Found {} accessible folders (tested {} candidates)",
-            folders.len(),
-            tested_accessible.len()
-        );
+        println!("✅ Found {} folders with one LIST", folders.len());
 
         Ok(folders)
     })
@@ -127,12 +237,116 @@ pub async fn fetch_folders(config: AccountConfig) -> Result<Vec<Folder>, String>
     Ok(folders)
 }
 
+/// List all folders with their STATUS counts in a single command (LIST-EXTENDED + LIST-STATUS)
+fn list_with_status(
+    imap_session: &mut ImapSession,
+    special_use: bool,
+) -> Result<Vec<ListedMailbox>, String> {
+    let return_options = if special_use {
+        "SPECIAL-USE STATUS (MESSAGES UNSEEN)"
+    } else {
+        "STATUS (MESSAGES UNSEEN)"
+    };
+    let raw = imap_session
+        .run_command_and_read_response(format!("LIST \"\" \"*\" RETURN ({})", return_options))
+        .map_err(|e| format!("LIST-STATUS failed: {}", e))?;
+
+    Ok(parse_list_response(&raw))
+}
+
+/// List all folders with XLIST, whose replies carry special-use attributes like \Trash
+fn list_with_xlist(imap_session: &mut ImapSession) -> Result<Vec<ListedMailbox>, String> {
+    let raw = imap_session
+        .run_command_and_read_response("XLIST \"\" \"*\"")
+        .map_err(|e| format!("XLIST failed: {}", e))?;
+
+    // XLIST replies have the same form as LIST replies, so they're parsed as such
+    let raw = String::from_utf8_lossy(&raw).replace("* XLIST ", "* LIST ");
+    Ok(parse_list_response(raw.as_bytes()))
+}
+
+/// Parse the untagged LIST (and STATUS) replies of a raw LIST command
+fn parse_list_response(raw: &[u8]) -> Vec<ListedMailbox> {
+    let mut mailboxes = Vec::new();
+    let mut statuses: HashMap<String, (u32, u32)> = HashMap::new();
+    let mut rest = raw;
+
+    while !rest.is_empty() {
+        match imap_proto::parser::parse_response(rest) {
+            Ok((remaining, response)) => {
+                match response {
+                    Response::MailboxData(MailboxDatum::List {
+                        name_attributes,
+                        delimiter,
+                        name,
+                    }) => mailboxes.push(ListedMailbox {
+                        name: name.to_string(),
+                        delimiter: delimiter.map(|d| d.to_string()),
+                        flags: format!("{:?}", name_attributes),
+                        status: None,
+                    }),
+                    Response::MailboxData(MailboxDatum::Status { mailbox, status }) => {
+                        let mut counts = (0, 0);
+                        for attribute in status {
+                            match attribute {
+                                StatusAttribute::Messages(messages) => counts.0 = messages,
+                                StatusAttribute::Unseen(unseen) => counts.1 = unseen,
+                                _ => {}
+                            }
+                        }
+                        statuses.insert(mailbox.to_string(), counts);
+                    }
+                    _ => {}
+                }
+                rest = remaining;
+            }
+            // Skip lines we can't parse (e.g. extended LIST data) instead of failing the listing
+            Err(_) => match rest.windows(2).position(|w| w == b"\r\n") {
+                Some(pos) => rest = &rest[pos + 2..],
+                None => break,
+            },
+        }
+    }
+
+    for mailbox in &mut mailboxes {
+        mailbox.status = statuses.remove(&mailbox.name);
+    }
+
+    mailboxes
+}
+
+/// The IMAP name of an account's folder with a special-use role, as found by the last folder sync
+pub(crate) async fn folder_for_role(
+    account_id: i32,
+    role: FolderRole,
+) -> Result<Option<String>, String> {
+    let pool = db::pool();
+    let folder = sqlx::query_as::<_, (String,)>(
+        "SELECT name FROM folders
+         WHERE account_id = ? AND role = ? AND COALESCE(is_local, 0) = 0
+           AND COALESCE(inaccessible, 0) = 0
+         ORDER BY id LIMIT 1",
+    )
+    .bind(account_id)
+    .bind(role.as_str())
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to look up {} folder: {}", role.as_str(), e))?;
+
+    Ok(folder.map(|(name,)| name))
+}
+
 #[command]
 pub async fn sync_folders(config: AccountConfig) -> Result<Vec<Folder>, String> {
     println!("Syncing folders for {}", config.email);
 
     let account_id = config.id.ok_or("Account ID is required")?;
 
+    // Replacing the folder rows would drop the demo account's local folders
+    if crate::commands::demo::is_demo_account(&config) {
+        return load_folders(account_id).await;
+    }
+
     // Fetch folders from IMAP server
     let folders = fetch_folders(config).await?;
 
@@ -148,13 +362,16 @@ pub async fn sync_folders(config: AccountConfig) -> Result<Vec<Folder>, String>
 
     // Insert new folders
     for folder in &folders {
-        sqlx::query("INSERT INTO folders (account_id, name, display_name, delimiter, flags, is_local) VALUES (?, ?, ?, ?, ?, ?)")
+        sqlx::query("INSERT INTO folders (account_id, name, display_name, delimiter, flags, is_local, total_count, unread_count, role) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")
             .bind(account_id)
             .bind(&folder.name)
             .bind(&folder.display_name)
             .bind(&folder.delimiter)
             .bind(&folder.flags)
             .bind(if folder.is_local { 1 } else { 0 })
+            .bind(folder.total_count)
+            .bind(folder.unread_count)
+            .bind(&folder.role)
             .execute(pool.as_ref())
             .await
             .map_err(|e| e.to_string())?;
@@ -183,6 +400,11 @@ pub async fn sync_folders(config: AccountConfig) -> Result<Vec<Folder>, String>
 pub async fn check_folder_capabilities(config: AccountConfig) -> Result<bool, String> {
     println!("Checking folder capabilities for {}", config.email);
 
+    // New demo folders are created locally
+    if crate::commands::demo::is_demo_account(&config) {
+        return Ok(false);
+    }
+
     // Ensure we have a valid access token (refresh if needed)
     let config = ensure_valid_token(config).await?;
 
@@ -208,11 +430,72 @@ pub async fn check_folder_capabilities(config: AccountConfig) -> Result<bool, St
     Ok(supports_create_delete)
 }
 
-/// Create a remote IMAP folder
+/// Full name of a folder `name` inside `parent`, joined with the server's hierarchy delimiter
+fn nested_folder_name(parent: Option<&str>, name: &str, delimiter: Option<&str>) -> String {
+    match (parent.filter(|parent| !parent.is_empty()), delimiter) {
+        (Some(parent), Some(delimiter)) => format!("{}{}{}", parent, delimiter, name),
+        _ => name.to_string(),
+    }
+}
+
+/// Full name of a folder after its last level is renamed to `new_name`
+fn renamed_folder_name(folder_name: &str, new_name: &str, delimiter: Option<&str>) -> String {
+    let parent = delimiter
+        .filter(|delimiter| !delimiter.is_empty())
+        .and_then(|delimiter| folder_name.rsplit_once(delimiter))
+        .map(|(parent, _)| parent);
+    nested_folder_name(parent, new_name, delimiter)
+}
+
+/// Check a single folder level typed by the user
+fn validate_folder_level(name: &str, delimiter: Option<&str>) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Folder name can't be empty".to_string());
+    }
+    if let Some(delimiter) = delimiter.filter(|delimiter| name.contains(*delimiter)) {
+        return Err(format!("Folder name can't contain '{}'", delimiter));
+    }
+    Ok(())
+}
+
+/// The hierarchy delimiter of a folder, from the cache or else asked from the server
+/// (`LIST "" ""` returns the delimiter of the root)
+fn hierarchy_delimiter(
+    imap_session: &mut ImapSession,
+    cached: Option<String>,
+) -> Result<Option<String>, String> {
+    if cached.is_some() {
+        return Ok(cached);
+    }
+    let root = imap_session
+        .list(Some(""), Some(""))
+        .map_err(|e| format!("Failed to get folder delimiter: {}", e))?;
+    Ok(root
+        .iter()
+        .next()
+        .and_then(|mailbox| mailbox.delimiter().map(|d| d.to_string())))
+}
+
+/// Cached hierarchy delimiter of a remote folder
+async fn cached_delimiter(account_id: i32, folder_name: &str) -> Result<Option<String>, String> {
+    let pool = db::pool();
+    Ok(sqlx::query_as::<_, (Option<String>,)>(
+        "SELECT delimiter FROM folders WHERE account_id = ? AND name = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load folder: {}", e))?
+    .and_then(|(delimiter,)| delimiter))
+}
+
+/// Create a remote IMAP folder, optionally nested inside `parent` (its raw IMAP name)
 #[command]
 pub async fn create_remote_folder(
     config: AccountConfig,
     folder_name: String,
+    parent: Option<String>,
 ) -> Result<Folder, String> {
     println!(
         "Creating remote folder '{}' for {}",
@@ -222,19 +505,31 @@ pub async fn create_remote_folder(
     // Ensure we have a valid access token (refresh if needed)
     let config = ensure_valid_token(config).await?;
     let account_id = config.id.ok_or("Account ID is required")?;
+    let parent = parent.filter(|parent| !parent.is_empty());
+    let cached = match &parent {
+        Some(parent) => cached_delimiter(account_id, parent).await?,
+        None => None,
+    };
 
     let folder = tokio::task::spawn_blocking(move || -> Result<Folder, String> {
         let mut imap_session = imap_helpers::connect_and_login(&config)?;
 
+        let delimiter = hierarchy_delimiter(&mut imap_session, cached)?;
+        validate_folder_level(&folder_name, delimiter.as_deref())?;
+
         // Encode folder name to UTF-7 IMAP
-        let encoded_name = encode_folder_name(&folder_name);
+        let encoded_name = nested_folder_name(
+            parent.as_deref(),
+            &encode_folder_name(&folder_name),
+            delimiter.as_deref(),
+        );
 
         // Create the folder
         imap_session
             .create(&encoded_name)
             .map_err(|e| format!("Failed to create folder: {}", e))?;
 
-        println!("✅ Created remote folder '{}'", folder_name);
+        println!("✅ Created remote folder '{}'", encoded_name);
 
         let _ = imap_session.logout();
 
@@ -242,11 +537,15 @@ pub async fn create_remote_folder(
         Ok(Folder {
             id: None,
             account_id,
+            display_name: get_display_name(&decode_folder_name(&encoded_name)),
             name: encoded_name,
-            display_name: folder_name,
-            delimiter: Some("/".to_string()),
+            delimiter: delimiter.or_else(|| Some("/".to_string())),
             flags: None,
             is_local: false,
+            total_count: None,
+            unread_count: None,
+            sync_on_open: false,
+            role: None,
         })
     })
     .await
@@ -275,6 +574,171 @@ pub async fn create_remote_folder(
     })
 }
 
+/// Point cached data at a folder's new name after a rename, including its child folders
+async fn rename_cached_folder(
+    account_id: i32,
+    old_name: &str,
+    new_name: &str,
+    delimiter: Option<&str>,
+) -> Result<(), String> {
+    // Children are `old_name` + delimiter + ...; without a delimiter there are none
+    let child_prefix = delimiter.map(|delimiter| format!("{}{}", old_name, delimiter));
+    let prefix_len = child_prefix
+        .as_ref()
+        .map_or(0, |prefix| prefix.chars().count() as i64);
+    let old_len = old_name.chars().count() as i64;
+
+    let pool = db::pool();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // Local child folders may keep their messages in Maildir directories named after them
+    let local_folders = sqlx::query_as::<_, (String,)>(
+        "SELECT name FROM folders
+         WHERE account_id = ? AND COALESCE(is_local, 0) = 1
+           AND (name = ? OR substr(name, 1, ?) = ?)",
+    )
+    .bind(account_id)
+    .bind(old_name)
+    .bind(prefix_len)
+    .bind(child_prefix.as_deref())
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load local folders: {}", e))?;
+
+    let columns = [
+        ("folders", "name"),
+        ("emails", "folder_name"),
+        ("sync_status", "folder_name"),
+        ("sync_checkpoints", "folder_name"),
+        ("folder_sync_settings", "folder_name"),
+        ("folder_policies", "folder_name"),
+        ("folder_policies", "target_folder"),
+        ("email_notes", "folder_name"),
+        ("drafts", "remote_folder"),
+        ("append_jobs", "target_folder"),
+        ("automation_hooks", "folder_name"),
+    ];
+    for (table, column) in columns {
+        let query = format!(
+            "UPDATE {table} SET {column} = ? || substr({column}, ?)
+             WHERE account_id = ? AND ({column} = ? OR substr({column}, 1, ?) = ?)",
+            table = table,
+            column = column
+        );
+        sqlx::query(&query)
+            .bind(new_name)
+            .bind(old_len + 1)
+            .bind(account_id)
+            .bind(old_name)
+            .bind(prefix_len)
+            .bind(child_prefix.as_deref())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to rename folder in {}: {}", table, e))?;
+    }
+
+    // Display names follow the new full names
+    let new_prefix = delimiter.map(|delimiter| format!("{}{}", new_name, delimiter));
+    let renamed = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, name FROM folders
+         WHERE account_id = ? AND (name = ? OR substr(name, 1, ?) = ?)",
+    )
+    .bind(account_id)
+    .bind(new_name)
+    .bind(new_prefix.as_ref().map_or(0, |p| p.chars().count() as i64))
+    .bind(new_prefix.as_deref())
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load renamed folders: {}", e))?;
+    for (id, name) in renamed {
+        sqlx::query("UPDATE folders SET display_name = ? WHERE id = ?")
+            .bind(get_display_name(&decode_folder_name(&name)))
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update folder name: {}", e))?;
+    }
+
+    // Move Maildir directories before committing, undoing them if anything fails
+    let mut moved_dirs: Vec<(String, String)> = Vec::new();
+    let mut result = Ok(());
+    for (name,) in local_folders {
+        let renamed = format!("{}{}", new_name, &name[old_name.len()..]);
+        if let Err(e) = maildir::rename_folder(account_id, &name, &renamed) {
+            result = Err(e);
+            break;
+        }
+        moved_dirs.push((name, renamed));
+    }
+    if result.is_ok() {
+        result = tx
+            .commit()
+            .await
+            .map_err(|e| format!("Failed to commit folder rename: {}", e));
+    }
+    if result.is_err() {
+        for (name, renamed) in moved_dirs.iter().rev() {
+            let _ = maildir::rename_folder(account_id, renamed, name);
+        }
+    }
+    result
+}
+
+/// Rename a remote IMAP folder; `new_name` replaces its last level, so it keeps its parent
+/// The server renames child folders along with it, and the cache is updated to match.
+#[command]
+pub async fn rename_remote_folder(
+    config: AccountConfig,
+    folder_name: String,
+    new_name: String,
+) -> Result<String, String> {
+    if folder_name.eq_ignore_ascii_case("INBOX") {
+        return Err("The inbox can't be renamed".to_string());
+    }
+    println!(
+        "Renaming remote folder '{}' to '{}' for {}",
+        folder_name, new_name, config.email
+    );
+
+    let config = ensure_valid_token(config).await?;
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let cached = cached_delimiter(account_id, &folder_name).await?;
+
+    let folder_for_task = folder_name.clone();
+    let (renamed, delimiter) =
+        tokio::task::spawn_blocking(move || -> Result<(String, Option<String>), String> {
+            let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+            let delimiter = hierarchy_delimiter(&mut imap_session, cached)?;
+            validate_folder_level(&new_name, delimiter.as_deref())?;
+            let renamed = renamed_folder_name(
+                &folder_for_task,
+                &encode_folder_name(new_name.trim()),
+                delimiter.as_deref(),
+            );
+
+            imap_session
+                .rename(&folder_for_task, &renamed)
+                .map_err(|e| format!("Failed to rename folder: {}", e))?;
+
+            let _ = imap_session.logout();
+            Ok((renamed, delimiter))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+    rename_cached_folder(account_id, &folder_name, &renamed, delimiter.as_deref()).await?;
+
+    println!(
+        "✅ Renamed remote folder '{}' to '{}'",
+        folder_name, renamed
+    );
+    Ok(renamed)
+}
+
 /// Delete a remote IMAP folder
 #[command]
 pub async fn delete_remote_folder(
@@ -322,6 +786,107 @@ pub async fn delete_remote_folder(
     Ok(())
 }
 
+async fn load_subscribed_only(account_id: i32) -> Result<bool, String> {
+    let pool = db::pool();
+    let value =
+        sqlx::query_as::<_, (Option<i64>,)>("SELECT subscribed_only FROM accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load subscription setting: {}", e))?
+            .ok_or("Account not found")?;
+
+    Ok(value.0.unwrap_or(0) != 0)
+}
+
+/// Subscribe to a remote IMAP folder
+/// With "subscribed folders only" on, the folder shows up on the next `sync_folders`.
+#[command]
+pub async fn subscribe_folder(config: AccountConfig, folder_name: String) -> Result<(), String> {
+    let config = ensure_valid_token(config).await?;
+
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+        let result = imap_session
+            .subscribe(&folder_name)
+            .map_err(|e| format!("Failed to subscribe to folder: {}", e));
+        let _ = imap_session.logout();
+        result?;
+
+        println!("✅ Subscribed to folder '{}'", folder_name);
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Unsubscribe from a remote IMAP folder
+/// With "subscribed folders only" on, the folder is also removed from the cached folder list
+/// so it is no longer monitored or synced.
+#[command]
+pub async fn unsubscribe_folder(config: AccountConfig, folder_name: String) -> Result<(), String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    if folder_name.eq_ignore_ascii_case("INBOX") {
+        return Err("INBOX can't be unsubscribed".to_string());
+    }
+    let config = ensure_valid_token(config).await?;
+
+    let folder_name_for_db = folder_name.clone();
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+        let result = imap_session
+            .unsubscribe(&folder_name)
+            .map_err(|e| format!("Failed to unsubscribe from folder: {}", e));
+        let _ = imap_session.logout();
+        result?;
+
+        println!("✅ Unsubscribed from folder '{}'", folder_name);
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if load_subscribed_only(account_id).await? {
+        let pool = db::pool();
+        sqlx::query(
+            "DELETE FROM folders WHERE account_id = ? AND name = ? AND COALESCE(is_local, 0) = 0",
+        )
+        .bind(account_id)
+        .bind(&folder_name_for_db)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to remove unsubscribed folder: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Get whether only subscribed folders are listed and synced for an account
+#[command]
+pub async fn get_subscribed_folders_only(account_id: i32) -> Result<bool, String> {
+    load_subscribed_only(account_id).await
+}
+
+/// Choose whether only subscribed folders are listed and synced for an account
+/// Takes effect on the next `sync_folders`.
+#[command]
+pub async fn set_subscribed_folders_only(account_id: i32, enabled: bool) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("UPDATE accounts SET subscribed_only = ? WHERE id = ?")
+        .bind(enabled as i64)
+        .bind(account_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save subscription setting: {}", e))?;
+
+    println!(
+        "⚙️ Subscribed folders only for account {}: {}",
+        account_id,
+        if enabled { "on" } else { "off" }
+    );
+    Ok(())
+}
+
 /// Create a local-only folder
 #[command]
 pub async fn create_local_folder(account_id: i32, folder_name: String) -> Result<Folder, String> {
@@ -374,6 +939,10 @@ pub async fn create_local_folder(account_id: i32, folder_name: String) -> Result
         delimiter: Some("/".to_string()),
         flags: None,
         is_local: true,
+        total_count: None,
+        unread_count: None,
+        sync_on_open: false,
+        role: None,
     })
 }
 
@@ -399,36 +968,153 @@ pub async fn delete_local_folder(account_id: i32, folder_name: String) -> Result
     if result.rows_affected() == 0 {
         return Err("Local folder not found".to_string());
     }
+    crate::commands::emails::maildir::remove_folder(account_id, &folder_name);
 
     println!("✅ Deleted local folder '{}'", folder_name);
 
     Ok(())
 }
 
+/// Hide a folder that LIST reported but the server refuses to open
+///
+/// Folders aren't SELECTed during folder sync, so this is how inaccessible ones are found:
+/// the first sync that can't open the folder hides it. The next folder sync lists it again.
+pub(crate) async fn mark_folder_inaccessible(
+    account_id: i32,
+    folder_name: &str,
+) -> Result<(), String> {
+    let pool = db::pool();
+
+    sqlx::query("UPDATE folders SET inaccessible = 1 WHERE account_id = ? AND name = ?")
+        .bind(account_id)
+        .bind(folder_name)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to mark folder inaccessible: {}", e))?;
+
+    println!(
+        "⚠️ Folder '{}' cannot be accessed, hiding it until the next folder sync",
+        folder_name
+    );
+    Ok(())
+}
+
 #[command]
 pub async fn load_folders(account_id: i32) -> Result<Vec<Folder>, String> {
     println!("Loading folders for account_id: {}", account_id);
 
     let pool = db::pool();
 
-    let folders = sqlx::query_as::<_, (Option<i32>, i32, String, String, Option<String>, Option<String>, i32)>(
-        "SELECT id, account_id, name, display_name, delimiter, flags, COALESCE(is_local, 0) FROM folders WHERE account_id = ? ORDER BY display_name",
+    #[allow(clippy::type_complexity)]
+    let mut folders: Vec<Folder> = sqlx::query_as::<_, (Option<i32>, i32, String, String, Option<String>, Option<String>, i32, Option<i64>, Option<i64>, i32, Option<String>)>(
+        "SELECT f.id, f.account_id, f.name, f.display_name, f.delimiter, f.flags, COALESCE(f.is_local, 0), f.total_count, f.unread_count, COALESCE(s.sync_on_open, 0), f.role
+         FROM folders f
+         LEFT JOIN folder_sync_settings s ON s.account_id = f.account_id AND s.folder_name = f.name
+         WHERE f.account_id = ? AND COALESCE(f.inaccessible, 0) = 0 ORDER BY f.display_name",
     )
     .bind(account_id)
     .fetch_all(pool.as_ref())
     .await
     .map_err(|e| e.to_string())?
     .into_iter()
-    .map(|(id, account_id, name, display_name, delimiter, flags, is_local)| Folder {
-        id,
-        account_id,
-        name,
-        display_name,
-        delimiter,
-        flags,
-        is_local: is_local != 0,
-    })
+    .map(
+        |(id, account_id, name, display_name, delimiter, flags, is_local, total_count, unread_count, sync_on_open, role)| {
+            Folder {
+                id,
+                account_id,
+                name,
+                display_name,
+                delimiter,
+                flags,
+                is_local: is_local != 0,
+                total_count,
+                unread_count,
+                sync_on_open: sync_on_open != 0,
+                role,
+            }
+        },
+    )
     .collect();
 
+    // Special-use folders get their name in the configured language
+    localize(&mut folders, language().await);
+
     Ok(folders)
 }
+
+/// Only sync a folder when it is opened (skip it in background and refresh-all syncs)
+#[command]
+pub async fn set_folder_sync_on_open(
+    account_id: i32,
+    folder_name: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query(
+        "INSERT INTO folder_sync_settings (account_id, folder_name, sync_on_open) VALUES (?, ?, ?)
+         ON CONFLICT(account_id, folder_name) DO UPDATE SET sync_on_open = excluded.sync_on_open",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .bind(enabled as i64)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save folder sync setting: {}", e))?;
+
+    println!(
+        "⚙️ Folder '{}' sync on open: {}",
+        folder_name,
+        if enabled { "on" } else { "off" }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_and_renamed_folder_names() {
+        assert_eq!(
+            nested_folder_name(Some("Work"), "Clients", Some("/")),
+            "Work/Clients"
+        );
+        assert_eq!(
+            nested_folder_name(Some("INBOX"), "Clients", Some(".")),
+            "INBOX.Clients"
+        );
+        assert_eq!(nested_folder_name(None, "Clients", Some("/")), "Clients");
+
+        assert_eq!(
+            renamed_folder_name("Work/Clients", "Customers", Some("/")),
+            "Work/Customers"
+        );
+        assert_eq!(
+            renamed_folder_name("Clients", "Customers", Some("/")),
+            "Customers"
+        );
+        assert_eq!(
+            renamed_folder_name("INBOX.a.b", "c", Some(".")),
+            "INBOX.a.c"
+        );
+
+        assert!(validate_folder_level("a/b", Some("/")).is_err());
+        assert!(validate_folder_level(" ", Some("/")).is_err());
+        assert!(validate_folder_level("Customers", Some("/")).is_ok());
+    }
+
+    #[test]
+    fn test_encode_folder_name() {
+        assert_eq!(encode_folder_name("INBOX"), "INBOX");
+        assert_eq!(encode_folder_name("Entwürfe"), "Entw&APw-rfe");
+        assert_eq!(encode_folder_name("A&B"), "A&-B");
+        assert_eq!(
+            encode_folder_name("~peter/mail/台北/日本語"),
+            "~peter/mail/&U,BTFw-/&ZeVnLIqe-"
+        );
+        assert_eq!(
+            decode_folder_name(&encode_folder_name("Корзина")),
+            "Корзина"
+        );
+    }
+}
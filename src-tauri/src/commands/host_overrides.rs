@@ -0,0 +1,125 @@
+// Per-account host overrides
+// Pins a mail or OAuth host name to a fixed IP for one account, for networks whose internal
+// DNS differs from the public one (split-horizon). TLS certificates are still verified
+// against the host name, so an override can't silently redirect mail to another server.
+
+use crate::commands::authorization::{authorize, Sensitivity};
+use crate::db;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tauri::command;
+
+/// A host name pinned to an IP address
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HostOverride {
+    pub account_id: i32,
+    pub host: String,
+    pub ip: String,
+}
+
+async fn load_overrides(account_id: Option<i32>) -> Result<Vec<HostOverride>, String> {
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (i32, String, String)>(
+        "SELECT account_id, host, ip FROM host_overrides
+         WHERE ?1 IS NULL OR account_id = ?1
+         ORDER BY account_id, host",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load host overrides: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(account_id, host, ip)| HostOverride {
+            account_id,
+            host,
+            ip,
+        })
+        .collect())
+}
+
+/// Load all overrides into the resolver; called at startup and after every change
+pub async fn apply_host_overrides() -> Result<(), String> {
+    let overrides = load_overrides(None).await?;
+    crate::net::set_host_overrides(overrides.into_iter().filter_map(|entry| {
+        match entry.ip.parse::<IpAddr>() {
+            Ok(ip) => Some((entry.account_id, entry.host, ip)),
+            Err(_) => {
+                eprintln!(
+                    "⚠️ Ignoring invalid override {} -> {}",
+                    entry.host, entry.ip
+                );
+                None
+            }
+        }
+    }));
+    Ok(())
+}
+
+/// List the host overrides of an account
+#[command]
+pub async fn list_host_overrides(account_id: i32) -> Result<Vec<HostOverride>, String> {
+    load_overrides(Some(account_id)).await
+}
+
+/// Pin a host to an IP for an account; `ip: None` removes the override
+#[command]
+pub async fn set_host_override(
+    account_id: i32,
+    host: String,
+    ip: Option<String>,
+) -> Result<Vec<HostOverride>, String> {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    if host.is_empty() {
+        return Err("Host name is required".to_string());
+    }
+
+    let pool = db::pool();
+    match ip {
+        Some(ip) => {
+            let ip = ip
+                .trim()
+                .parse::<IpAddr>()
+                .map_err(|_| format!("'{}' is not a valid IP address", ip.trim()))?;
+
+            // Changes where the account's credentials are sent
+            authorize("set_host_override", Sensitivity::HighRisk).await?;
+
+            sqlx::query(
+                "INSERT OR REPLACE INTO host_overrides (account_id, host, ip) VALUES (?, ?, ?)",
+            )
+            .bind(account_id)
+            .bind(&host)
+            .bind(ip.to_string())
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to save host override: {}", e))?;
+
+            crate::commands::audit::record(
+                "host_override_set",
+                Some(&account_id.to_string()),
+                &format!("{} -> {}", host, ip),
+            )
+            .await;
+        }
+        None => {
+            sqlx::query("DELETE FROM host_overrides WHERE account_id = ? AND host = ?")
+                .bind(account_id)
+                .bind(&host)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to remove host override: {}", e))?;
+        }
+    }
+
+    apply_host_overrides().await?;
+    load_overrides(Some(account_id)).await
+}
+
+/// Drop all cached DNS lookups, e.g. after switching networks
+#[command]
+pub fn clear_dns_cache() {
+    crate::net::clear_dns_cache();
+    println!("🧹 DNS cache cleared");
+}
@@ -0,0 +1,195 @@
+// Sending identities
+// Besides its login address, an account can send as extra addresses (aliases). When replying,
+// `suggest_reply_identity` picks the address the original message was delivered to, looking
+// at Delivered-To / X-Original-To first and then the To and Cc lists, so replies go out from
+// the alias the sender actually wrote to.
+
+use crate::db;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// An address an account can send from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Identity {
+    #[serde(default)]
+    pub id: Option<i64>, // None for the account's own address
+    pub account_id: i32,
+    pub email: String,
+    pub display_name: Option<String>,
+}
+
+/// Bare lowercase addresses in a header value like `A <a@x.com>, b@y.com`
+fn addresses(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            let address = match entry.rsplit_once('<') {
+                Some((_, rest)) => rest.trim_end_matches('>'),
+                None => entry,
+            };
+            let address = address.trim().to_lowercase();
+            address.contains('@').then_some(address)
+        })
+        .collect()
+}
+
+/// The identity the message was addressed to, checking each source in order
+fn pick_identity(identities: &[Identity], sources: &[String]) -> Option<Identity> {
+    sources
+        .iter()
+        .flat_map(|value| addresses(value))
+        .find_map(|address| {
+            identities
+                .iter()
+                .find(|identity| identity.email.eq_ignore_ascii_case(&address))
+                .cloned()
+        })
+}
+
+/// The account's own address followed by its aliases
+pub(crate) async fn load_identities(account_id: i32) -> Result<Vec<Identity>, String> {
+    let pool = db::pool();
+    let (email, display_name) = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT email, display_name FROM accounts WHERE id = ?",
+    )
+    .bind(account_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load account: {}", e))?
+    .ok_or("Account not found")?;
+
+    let mut identities = vec![Identity {
+        id: None,
+        account_id,
+        email,
+        display_name,
+    }];
+
+    let aliases = sqlx::query_as::<_, (i64, String, Option<String>)>(
+        "SELECT id, email, display_name FROM account_identities WHERE account_id = ? ORDER BY id",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load identities: {}", e))?;
+    identities.extend(
+        aliases
+            .into_iter()
+            .map(|(id, email, display_name)| Identity {
+                id: Some(id),
+                account_id,
+                email,
+                display_name,
+            }),
+    );
+
+    Ok(identities)
+}
+
+/// List the addresses an account can send from, its own address first
+#[command]
+pub async fn list_identities(account_id: i32) -> Result<Vec<Identity>, String> {
+    load_identities(account_id).await
+}
+
+/// Add or update an alias, returning its id
+#[command]
+pub async fn save_identity(identity: Identity) -> Result<i64, String> {
+    let email = identity.email.trim();
+    if !email.contains('@') {
+        return Err(format!("'{}' is not an email address", email));
+    }
+
+    let pool = db::pool();
+    let (id,) = sqlx::query_as::<_, (i64,)>(
+        "INSERT INTO account_identities (id, account_id, email, display_name)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            email = excluded.email,
+            display_name = excluded.display_name
+         RETURNING id",
+    )
+    .bind(identity.id)
+    .bind(identity.account_id)
+    .bind(email)
+    .bind(&identity.display_name)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save identity: {}", e))?;
+
+    Ok(id)
+}
+
+/// Remove an alias
+#[command]
+pub async fn delete_identity(identity_id: i64) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("DELETE FROM account_identities WHERE id = ?")
+        .bind(identity_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete identity: {}", e))?;
+    Ok(())
+}
+
+/// The identity to reply from for a cached email, if one of the account's addresses received it
+#[command]
+pub async fn suggest_reply_identity(email_id: i64) -> Result<Option<Identity>, String> {
+    let pool = db::pool();
+    let (account_id, to, cc) = sqlx::query_as::<_, (i32, String, Option<String>)>(
+        "SELECT account_id, to_addr, cc_addr FROM emails WHERE id = ?",
+    )
+    .bind(email_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load email: {}", e))?
+    .ok_or("Email not found")?;
+
+    // Delivered-To is the most precise: it names the mailbox even for Bcc and list mail
+    let mut sources: Vec<String> = sqlx::query_as::<_, (String,)>(
+        "SELECT value FROM email_raw_headers
+         WHERE email_id = ? AND name IN ('delivered-to', 'x-original-to')
+         ORDER BY id",
+    )
+    .bind(email_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load delivery headers: {}", e))?
+    .into_iter()
+    .map(|(value,)| value)
+    .collect();
+    sources.push(to);
+    sources.extend(cc);
+
+    Ok(pick_identity(&load_identities(account_id).await?, &sources))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_identity() {
+        let identity = |email: &str| Identity {
+            id: None,
+            account_id: 1,
+            email: email.to_string(),
+            display_name: None,
+        };
+        let identities = vec![identity("me@example.com"), identity("sales@example.com")];
+
+        let sources = vec![
+            "Team <team@lists.example.com>, Sales <SALES@example.com>".to_string(),
+            "me@example.com".to_string(),
+        ];
+        assert_eq!(
+            pick_identity(&identities, &sources).map(|i| i.email),
+            Some("sales@example.com".to_string())
+        );
+        assert_eq!(
+            pick_identity(&identities, &["other@example.org".to_string()]),
+            None
+        );
+    }
+}
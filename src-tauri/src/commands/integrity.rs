@@ -0,0 +1,172 @@
+// Database integrity check and self-repair
+// Detects corrupted or inconsistent cache state (orphaned rows, missing schema, broken indexes)
+// and optionally repairs it. Runs report-only at startup, so nothing is deleted before the user
+// has seen it; repairs run on demand from the UI.
+
+use crate::db;
+use crate::models::{IntegrityIssue, IntegrityReport};
+use sqlx::SqlitePool;
+use tauri::command;
+
+/// Tables that must exist after `db::init()`
+const REQUIRED_TABLES: &[&str] = &[
+    "accounts",
+    "folders",
+    "emails",
+    "sync_status",
+    "settings",
+    "attachments",
+    "drafts",
+    "audit_log",
+];
+
+/// Run the integrity check and, if `repair` is true, fix what can be fixed
+#[command]
+pub async fn run_integrity_check(repair: Option<bool>) -> Result<IntegrityReport, String> {
+    let repair = repair.unwrap_or(true);
+    let pool = db::pool();
+    let pool = pool.as_ref();
+
+    let mut issues = Vec::new();
+
+    // 1. SQLite page-level consistency
+    let rows = sqlx::query_as::<_, (String,)>("PRAGMA quick_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to run quick_check: {}", e))?;
+    let problems: Vec<String> = rows
+        .into_iter()
+        .map(|(line,)| line)
+        .filter(|line| line != "ok")
+        .collect();
+    if !problems.is_empty() {
+        issues.push(IntegrityIssue {
+            kind: "database_corruption".to_string(),
+            description: problems.join("; "),
+            count: problems.len() as i64,
+            repaired: false,
+        });
+    }
+
+    // 2. Schema: all required tables present
+    for table in REQUIRED_TABLES {
+        if !table_exists(pool, table).await? {
+            issues.push(IntegrityIssue {
+                kind: "missing_table".to_string(),
+                description: format!("Table '{}' is missing (restart to recreate it)", table),
+                count: 1,
+                repaired: false,
+            });
+        }
+    }
+
+    // 3. Orphaned rows (FOREIGN KEY targets that no longer exist)
+    let orphan_checks: [(&str, &str, &str); 4] = [
+        (
+            "orphaned_emails",
+            "Emails belonging to deleted accounts",
+            "FROM emails WHERE account_id NOT IN (SELECT id FROM accounts)",
+        ),
+        (
+            "orphaned_attachments",
+            "Attachments whose email no longer exists",
+            "FROM attachments WHERE email_id NOT IN (SELECT id FROM emails)",
+        ),
+        (
+            "orphaned_folders",
+            "Folders belonging to deleted accounts",
+            "FROM folders WHERE account_id NOT IN (SELECT id FROM accounts)",
+        ),
+        (
+            "dangling_sync_status",
+            "Sync state for deleted accounts or folders",
+            "FROM sync_status WHERE account_id NOT IN (SELECT id FROM accounts)
+             OR (folder_name != '__folders__'
+                 AND EXISTS (SELECT 1 FROM folders f WHERE f.account_id = sync_status.account_id)
+                 AND folder_name NOT IN (SELECT name FROM folders f WHERE f.account_id = sync_status.account_id))",
+        ),
+    ];
+
+    for (kind, description, from_clause) in orphan_checks {
+        let (count,) = sqlx::query_as::<_, (i64,)>(&format!("SELECT COUNT(*) {}", from_clause))
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Failed to check {}: {}", kind, e))?;
+
+        if count == 0 {
+            continue;
+        }
+
+        let repaired = if repair {
+            match sqlx::query(&format!("DELETE {}", from_clause))
+                .execute(pool)
+                .await
+            {
+                Ok(_) => true,
+                Err(e) => {
+                    eprintln!("⚠️ Failed to repair {}: {}", kind, e);
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        issues.push(IntegrityIssue {
+            kind: kind.to_string(),
+            description: description.to_string(),
+            count,
+            repaired,
+        });
+    }
+
+    // 4. Full-text search index (only present once search has been set up)
+    if table_exists(pool, "emails_fts").await? {
+        let check = sqlx::query("INSERT INTO emails_fts(emails_fts) VALUES('integrity-check')")
+            .execute(pool)
+            .await;
+
+        if let Err(e) = check {
+            let repaired = repair
+                && sqlx::query("INSERT INTO emails_fts(emails_fts) VALUES('rebuild')")
+                    .execute(pool)
+                    .await
+                    .is_ok();
+
+            issues.push(IntegrityIssue {
+                kind: "corrupted_fts_index".to_string(),
+                description: format!("Search index is inconsistent: {}", e),
+                count: 1,
+                repaired,
+            });
+        }
+    }
+
+    let ok = issues.iter().all(|issue| issue.repaired);
+
+    if issues.is_empty() {
+        println!("✅ Database integrity check passed");
+    } else {
+        for issue in &issues {
+            eprintln!(
+                "⚠️ Integrity issue [{}]: {} (count: {}, repaired: {})",
+                issue.kind, issue.description, issue.count, issue.repaired
+            );
+        }
+    }
+
+    Ok(IntegrityReport { ok, issues })
+}
+
+/// Check whether a table (or virtual table) exists
+async fn table_exists(pool: &SqlitePool, name: &str) -> Result<bool, String> {
+    let row = sqlx::query_as::<_, (i64,)>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+    )
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to inspect schema: {}", e))?;
+
+    Ok(row.0 > 0)
+}
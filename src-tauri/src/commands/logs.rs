@@ -83,12 +83,14 @@ pub fn read_log_file(filename: String) -> Result<String, String> {
 ///
 /// Creates a ZIP file containing all log files in the application's data directory.
 /// The ZIP file is saved with a timestamped filename for easy identification.
+/// With `include_metrics`, a snapshot of the local performance metrics is added as
+/// `metrics.json`; they are left out by default.
 ///
 /// # Returns
 /// * `Ok(String)` - The absolute path to the created ZIP file
 /// * `Err(String)` - Error message if the export fails
 #[command]
-pub fn export_logs_as_zip() -> Result<String, String> {
+pub fn export_logs_as_zip(include_metrics: Option<bool>) -> Result<String, String> {
     tracing::info!("Starting log export to ZIP");
 
     // Get log directory
@@ -159,6 +161,15 @@ pub fn export_logs_as_zip() -> Result<String, String> {
             .map_err(|e| format!("Failed to write file '{}' to ZIP: {}", filename, e))?;
     }
 
+    if include_metrics.unwrap_or(false) {
+        let metrics = serde_json::to_string_pretty(&crate::metrics::snapshot())
+            .map_err(|e| format!("Failed to serialize metrics: {}", e))?;
+        zip.start_file("metrics.json", options)
+            .map_err(|e| format!("Failed to add metrics to ZIP: {}", e))?;
+        zip.write_all(metrics.as_bytes())
+            .map_err(|e| format!("Failed to write metrics to ZIP: {}", e))?;
+    }
+
     // Add a README file with instructions
     let readme_content = format!(
         "Colimail Log Export\n\
@@ -195,3 +206,33 @@ pub fn export_logs_as_zip() -> Result<String, String> {
 
     Ok(zip_path_str)
 }
+
+/// Get local performance metrics (timings, throughput, cache hit rates)
+#[command]
+pub fn get_performance_metrics() -> crate::metrics::PerformanceMetrics {
+    crate::metrics::snapshot()
+}
+
+/// Clear the recorded performance metrics
+#[command]
+pub fn reset_performance_metrics() {
+    crate::metrics::reset()
+}
+
+/// Get tasks the watchdog aborted because they stopped making progress
+#[command]
+pub fn get_watchdog_incidents() -> Vec<crate::watchdog::WatchdogIncident> {
+    crate::watchdog::incidents()
+}
+
+/// Configure simulated server faults (debug builds only); `None` turns them off
+#[command]
+pub fn set_fault_injection(config: Option<crate::faults::FaultConfig>) -> Result<(), String> {
+    crate::faults::configure(config)
+}
+
+/// Get the active simulated server faults
+#[command]
+pub fn get_fault_injection() -> Option<crate::faults::FaultConfig> {
+    crate::faults::current()
+}
@@ -1,47 +1,156 @@
+pub mod account_health;
 pub mod accounts;
+pub mod aging;
+pub mod attachment_archive;
+pub mod audit;
 pub mod auth;
+pub mod authorization;
+pub mod automation;
+pub mod config_backup;
+pub mod contact_groups;
+pub mod date_format;
+pub mod demo;
 pub mod detect_display_name;
 pub mod drafts;
 pub mod emails;
 pub mod encryption_manager;
+pub mod event_buffer;
+pub mod folder_conversion;
+pub mod folder_names;
+pub mod folder_status;
 pub mod folders;
+pub mod host_overrides;
+pub mod identities;
+pub mod integrity;
 pub mod logs;
+pub mod notification_sounds;
 pub mod notifications;
 pub mod oauth2;
+pub mod onboarding;
+pub mod outbox;
+pub mod plugins;
+pub mod quick_reply;
+pub mod quota;
+pub mod reader_windows;
+pub mod recurring;
+pub mod reply_all;
 pub mod send;
+pub mod send_checks;
+pub mod sent_copy;
+pub mod smtp_pool;
+pub mod storage;
+pub mod sync_scheduler;
 pub mod test_connection;
+pub mod token_expiry;
 pub mod utils; // Public so idle_manager can access ensure_valid_token
+pub mod window_state;
+pub mod wipe;
 
+pub use account_health::get_account_health;
 pub use accounts::{delete_account, load_account_configs, save_account_config};
+pub use aging::{
+    delete_folder_policy, list_folder_policies, preview_folder_policy, run_folder_policies,
+    save_folder_policy,
+};
+pub use attachment_archive::{
+    archive_old_attachments, get_attachment_archive_policy, set_attachment_archive_policy,
+};
+pub use audit::get_audit_log;
 pub use auth::{
     delete_app_user, delete_secure_storage, get_app_user, get_secure_storage, set_secure_storage,
     sync_app_user,
 };
+pub use authorization::{
+    confirm_master_password, get_reprompt_sensitive_commands, set_reprompt_sensitive_commands,
+};
+pub use automation::{
+    delete_automation_hook, list_automation_hooks, save_automation_hook, test_automation_hook,
+};
+pub use config_backup::{export_config_backup, read_config_backup, restore_config_backup};
+pub use contact_groups::{
+    delete_contact_group, expand_recipients, list_contact_groups, save_contact_group,
+    set_contact_group_opt_out,
+};
+pub use date_format::{get_date_display_zone, set_date_display_zone};
+pub use demo::create_demo_account;
 pub use detect_display_name::detect_display_name_from_sent;
-pub use drafts::{delete_draft, list_drafts, load_draft, save_draft};
+pub use drafts::{
+    delete_draft, get_draft_conflict, list_drafts, load_draft, open_remote_draft,
+    resolve_draft_conflict, save_draft,
+};
 pub use emails::{
-    delete_email, download_attachment, fetch_email_body, fetch_email_body_cached, fetch_emails,
-    get_last_sync_time, get_sync_interval, load_attachments_info, load_emails_from_cache,
-    mark_email_as_flagged, mark_email_as_read, mark_email_as_unflagged, mark_email_as_unread,
-    move_email_to_trash, save_attachment_to_file, set_sync_interval, should_sync, sync_email_flags,
-    sync_emails, sync_specific_email_flags,
+    archive_email, copy_email_to_folder, delete_email, delete_email_note, delete_emails,
+    download_attachment, fetch_email_body, fetch_email_body_cached, fetch_emails,
+    find_duplicate_emails, get_conversation_unread_count, get_email_note, get_idle_poll_interval,
+    get_last_sync_time, get_local_store_format, get_sync_interval, get_sync_window,
+    get_unread_counts_by_sender, get_unread_counts_by_thread, import_mailbox, list_email_notes,
+    load_attachments_info, load_emails_by_date, load_emails_from_cache, load_older_emails,
+    load_unified_inbox, locate_email, mark_as_not_spam, mark_as_spam, mark_email_as_flagged,
+    mark_email_as_read, mark_email_as_unflagged, mark_email_as_unread, mark_emails_as_read,
+    mark_folder_as_read, migrate_local_store, move_email_to_folder, move_email_to_trash,
+    move_emails_to_folder, prioritize_bodystructure, save_attachment_to_file, save_email_note,
+    search_attachment_text, search_emails, search_emails_by_header, search_gmail_raw,
+    set_idle_poll_interval, set_sync_interval, set_sync_window, should_sync, sync_all_folders,
+    sync_email_flags, sync_emails, sync_specific_email_flags, sync_unified_inbox,
+    upload_folder_to_remote, verify_cached_bodies,
 };
 pub use encryption_manager::{
     change_master_password, disable_encryption, enable_encryption, get_encryption_status,
-    lock_encryption_command, unlock_encryption_with_password,
+    get_unlock_cooldown, lock_encryption_command, set_unlock_lockout_policy,
+    unlock_encryption_with_password,
 };
+pub use event_buffer::fetch_missed_events;
+pub use folder_conversion::{convert_local_folder_to_remote, convert_remote_folder_to_local};
+pub use folder_names::{get_language, set_language};
+pub use folder_status::{get_all_folder_status, get_folder_status};
 pub use folders::{
     check_folder_capabilities, create_local_folder, create_remote_folder, delete_local_folder,
-    delete_remote_folder, fetch_folders, load_folders, sync_folders,
+    delete_remote_folder, fetch_folders, get_subscribed_folders_only, load_folders,
+    rename_remote_folder, set_folder_sync_on_open, set_subscribed_folders_only, subscribe_folder,
+    sync_folders, unsubscribe_folder,
 };
+pub use host_overrides::{clear_dns_cache, list_host_overrides, set_host_override};
+pub use identities::{delete_identity, list_identities, save_identity, suggest_reply_identity};
+pub use integrity::run_integrity_check;
 pub use logs::{
-    export_logs_as_zip, get_current_log_file, get_log_directory, list_log_files, read_log_file,
-    read_recent_logs,
+    export_logs_as_zip, get_current_log_file, get_fault_injection, get_log_directory,
+    get_performance_metrics, get_watchdog_incidents, list_log_files, read_log_file,
+    read_recent_logs, reset_performance_metrics, set_fault_injection,
+};
+pub use notification_sounds::{
+    delete_notification_sound, get_account_notification_sound, get_notification_sound_data,
+    import_notification_sound, list_notification_sounds, set_account_notification_sound,
 };
 pub use notifications::{
     get_minimize_to_tray, get_notification_enabled, get_sound_enabled, set_minimize_to_tray,
     set_notification_enabled, set_sound_enabled,
 };
 pub use oauth2::{complete_oauth2_flow, listen_for_oauth_callback, start_oauth2_flow};
+pub use onboarding::{
+    cancel_onboarding, get_onboarding_state, onboarding_initial_sync, onboarding_test_connection,
+    start_onboarding,
+};
+pub use outbox::{
+    cancel_pending_send, delete_outbox_item, get_undo_send_delay, list_outbox, retry_outbox_item,
+    set_undo_send_delay,
+};
+pub use plugins::{list_plugins, set_plugin_enabled};
+pub use quick_reply::quick_reply;
+pub use quota::get_mailbox_quota;
+pub use reader_windows::{list_open_windows, open_reader_window};
+pub use recurring::{
+    delete_recurring_send, get_recurring_send_history, list_recurring_sends,
+    run_recurring_send_now, save_recurring_send,
+};
+pub use reply_all::{get_reply_all_recipients, reply_all_email};
 pub use send::{forward_email, get_attachment_size_limit, reply_email, send_email};
+pub use send_checks::check_email_before_send;
+pub use sent_copy::{get_sent_copy_mode, set_sent_copy_mode};
+pub use storage::{clear_folder_attachments, clear_folder_bodies, get_storage_usage};
+pub use sync_scheduler::{set_account_sync_interval, set_folder_sync_interval};
 pub use test_connection::test_connection;
+pub use token_expiry::{get_token_expiry, start_reauthorization};
+pub use window_state::{
+    get_window_state, restore_window_state, save_last_selection, save_window_state,
+};
+pub use wipe::wipe_all_data;
@@ -0,0 +1,225 @@
+// Notification sounds
+// Users can import their own sound files (WAV, MP3 or OGG, checked by content rather than
+// extension) and pick one per account. The backend decides which sound a new-mail event
+// plays and rate-limits them, so a burst of mail across accounts plays one sound instead of
+// dozens of overlapping ones; the frontend fetches the file as a data URL and plays it.
+
+use crate::db;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::command;
+
+/// Largest sound file accepted
+const MAX_SOUND_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Minimum gap between two notification sounds
+const MIN_SOUND_INTERVAL: Duration = Duration::from_secs(5);
+
+lazy_static::lazy_static! {
+    static ref LAST_SOUND: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// Payload of the `play-notification-sound` event
+#[derive(Serialize, Debug, Clone)]
+pub struct SoundRequest {
+    pub account_id: i32,
+    pub sound: Option<String>, // File name in the sounds directory; None for the built-in beep
+}
+
+/// Directory imported sounds are copied into
+pub(crate) fn sounds_dir() -> PathBuf {
+    db::db_path()
+        .parent()
+        .map(|dir| dir.join("sounds"))
+        .unwrap_or_else(|| PathBuf::from("sounds"))
+}
+
+/// MIME type of a supported audio file, detected from its first bytes
+fn sound_mime(header: &[u8]) -> Option<&'static str> {
+    match header {
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Some("audio/wav"),
+        [b'O', b'g', b'g', b'S', ..] => Some("audio/ogg"),
+        [b'I', b'D', b'3', ..] => Some("audio/mpeg"),
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 => Some("audio/mpeg"), // MPEG frame sync
+        _ => None,
+    }
+}
+
+/// Resolve a sound name to a file inside the sounds directory
+fn sound_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid sound name '{}'", name));
+    }
+    Ok(sounds_dir().join(name))
+}
+
+fn account_key(account_id: i32) -> String {
+    format!("notification_sound:{}", account_id)
+}
+
+/// Whether enough time has passed since the last sound, recording this one if so
+fn take_sound_slot() -> bool {
+    let Ok(mut last) = LAST_SOUND.lock() else {
+        return true;
+    };
+    let now = Instant::now();
+    if last.is_some_and(|last| now.duration_since(last) < MIN_SOUND_INTERVAL) {
+        return false;
+    }
+    *last = Some(now);
+    true
+}
+
+/// The sound to play for new mail in an account, or None if one played too recently
+pub async fn next_sound(account_id: i32) -> Option<SoundRequest> {
+    if !take_sound_slot() {
+        tracing::debug!(account_id, "Notification sound rate-limited");
+        return None;
+    }
+
+    let pool = db::pool();
+    let sound = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(account_key(account_id))
+        .fetch_optional(pool.as_ref())
+        .await
+        .ok()
+        .flatten()
+        .map(|(value,)| value)
+        .filter(|name| sound_path(name).is_ok_and(|path| path.is_file()));
+
+    Some(SoundRequest { account_id, sound })
+}
+
+/// Copy a sound file into the sounds directory, returning its name
+#[command]
+pub async fn import_notification_sound(path: String) -> Result<String, String> {
+    let source = Path::new(&path);
+    let size = std::fs::metadata(source)
+        .map_err(|e| format!("Failed to read sound file: {}", e))?
+        .len();
+    if size > MAX_SOUND_BYTES {
+        return Err(format!(
+            "Sound files can be at most {} MB",
+            MAX_SOUND_BYTES / 1024 / 1024
+        ));
+    }
+
+    let data = std::fs::read(source).map_err(|e| format!("Failed to read sound file: {}", e))?;
+    if sound_mime(&data).is_none() {
+        return Err("Only WAV, MP3 and OGG files can be used as notification sounds".to_string());
+    }
+
+    let name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("Invalid file name")?
+        .to_string();
+    let target = sound_path(&name)?;
+    std::fs::create_dir_all(sounds_dir())
+        .map_err(|e| format!("Failed to create sounds directory: {}", e))?;
+    std::fs::write(&target, data).map_err(|e| format!("Failed to save sound file: {}", e))?;
+
+    Ok(name)
+}
+
+/// List imported sound files
+#[command]
+pub async fn list_notification_sounds() -> Result<Vec<String>, String> {
+    let Ok(entries) = std::fs::read_dir(sounds_dir()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Delete an imported sound file; accounts using it fall back to the built-in sound
+#[command]
+pub async fn delete_notification_sound(name: String) -> Result<(), String> {
+    std::fs::remove_file(sound_path(&name)?)
+        .map_err(|e| format!("Failed to delete sound file: {}", e))?;
+
+    let pool = db::pool();
+    sqlx::query("DELETE FROM settings WHERE key LIKE 'notification_sound:%' AND value = ?")
+        .bind(&name)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to update sound settings: {}", e))?;
+    Ok(())
+}
+
+/// Get the sound selected for an account (None for the built-in sound)
+#[command]
+pub async fn get_account_notification_sound(account_id: i32) -> Result<Option<String>, String> {
+    let pool = db::pool();
+    let value = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(account_key(account_id))
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load sound setting: {}", e))?;
+    Ok(value.map(|(value,)| value))
+}
+
+/// Select the sound for an account (None for the built-in sound)
+#[command]
+pub async fn set_account_notification_sound(
+    account_id: i32,
+    name: Option<String>,
+) -> Result<(), String> {
+    let pool = db::pool();
+    match name {
+        Some(name) => {
+            if !sound_path(&name)?.is_file() {
+                return Err(format!("Sound '{}' not found", name));
+            }
+            sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+                .bind(account_key(account_id))
+                .bind(&name)
+                .execute(pool.as_ref())
+                .await
+        }
+        None => {
+            sqlx::query("DELETE FROM settings WHERE key = ?")
+                .bind(account_key(account_id))
+                .execute(pool.as_ref())
+                .await
+        }
+    }
+    .map_err(|e| format!("Failed to save sound setting: {}", e))?;
+    Ok(())
+}
+
+/// An imported sound as a data URL the frontend can play
+#[command]
+pub async fn get_notification_sound_data(name: String) -> Result<String, String> {
+    let data = std::fs::read(sound_path(&name)?)
+        .map_err(|e| format!("Failed to read sound file: {}", e))?;
+    let mime = sound_mime(&data).ok_or("Unsupported sound file")?;
+    Ok(format!("data:{};base64,{}", mime, BASE64.encode(data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sound_mime() {
+        assert_eq!(
+            sound_mime(b"RIFF\x24\x08\x00\x00WAVEfmt "),
+            Some("audio/wav")
+        );
+        assert_eq!(sound_mime(b"OggS\x00\x02"), Some("audio/ogg"));
+        assert_eq!(sound_mime(b"ID3\x04\x00"), Some("audio/mpeg"));
+        assert_eq!(sound_mime(&[0xFF, 0xFB, 0x90, 0x64]), Some("audio/mpeg"));
+        assert_eq!(sound_mime(b"RIFF\x24\x08\x00\x00AVI "), None);
+        assert_eq!(sound_mime(b"<html>"), None);
+    }
+}
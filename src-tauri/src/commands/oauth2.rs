@@ -1,16 +1,76 @@
+use crate::commands::audit;
 use crate::db::pool;
-use crate::models::{AccountConfig, AuthType, OAuth2StartRequest, OAuth2StartResponse};
-use crate::oauth2_config::OAuth2Provider;
+use crate::models::{
+    AccountConfig, AuthType, OAuth2StartRequest, OAuth2StartResponse, OAuthCodeReceived,
+};
+use crate::oauth2_config::{self, OAuth2Provider, FIXED_REDIRECT_PORT};
 use crate::security;
+use std::collections::HashMap;
+use std::time::Duration;
 use tauri::command;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+/// How long to wait for the browser to hit the loopback redirect
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+lazy_static::lazy_static! {
+    /// Loopback listeners bound by `start_oauth2_flow`, keyed by OAuth state
+    static ref PENDING_LISTENERS: Mutex<HashMap<String, TcpListener>> = Mutex::new(HashMap::new());
+}
 
 #[command]
 pub async fn start_oauth2_flow(request: OAuth2StartRequest) -> Result<OAuth2StartResponse, String> {
     let provider = OAuth2Provider::get_provider(&request.provider)?;
-    let (auth_url, state) = provider.generate_auth_url()?;
 
+    // Clean up flows that were abandoned or are replaced by this one. Flows for other
+    // accounts stay pending, unless they hold the provider's fixed redirect port.
+    let mut stale = oauth2_config::prune_expired_sessions();
+    stale.extend(oauth2_config::take_superseded_sessions(
+        &provider.name,
+        &request.email,
+        !provider.dynamic_redirect_port,
+    ));
+    {
+        let mut listeners = PENDING_LISTENERS.lock().await;
+        for state in stale {
+            listeners.remove(&state);
+        }
+    }
+
+    // Bind the redirect listener first so the redirect URI can carry the actual port
+    let (listener, redirect_uri) = if provider.dynamic_redirect_port {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("Failed to bind OAuth callback listener: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read callback listener address: {}", e))?
+            .port();
+        (listener, format!("http://127.0.0.1:{}/callback", port))
+    } else {
+        let listener = TcpListener::bind(("127.0.0.1", FIXED_REDIRECT_PORT))
+            .await
+            .map_err(|e| {
+                format!(
+                    "Failed to bind to port {} (is another sign-in in progress or another app using it?): {}",
+                    FIXED_REDIRECT_PORT, e
+                )
+            })?;
+        (
+            listener,
+            format!("http://localhost:{}/callback", FIXED_REDIRECT_PORT),
+        )
+    };
+
+    let (auth_url, state) = provider.generate_auth_url(&redirect_uri, &request.email)?;
+    PENDING_LISTENERS
+        .lock()
+        .await
+        .insert(state.clone(), listener);
+
+    println!("🎧 Listening for OAuth callback on {}", redirect_uri);
     Ok(OAuth2StartResponse { auth_url, state })
 }
 
@@ -28,7 +88,7 @@ pub async fn complete_oauth2_flow(
         provider_config.exchange_code(&code, &state).await?;
 
     // Create account config with OAuth2 credentials
-    let account = AccountConfig {
+    let mut account = AccountConfig {
         id: None,
         email: email.clone(),
         password: None,
@@ -46,10 +106,17 @@ pub async fn complete_oauth2_flow(
     // Save non-sensitive data to database
     let pool = pool();
 
+    // Upsert keeps the existing row (and its ID and keyring namespace) on re-authorization
     sqlx::query(
-        "INSERT OR REPLACE INTO accounts
-         (email, imap_server, imap_port, smtp_server, smtp_port, auth_type)
-         VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO accounts
+         (email, imap_server, imap_port, smtp_server, smtp_port, auth_type, credential_ns)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(email) DO UPDATE SET
+             imap_server = excluded.imap_server,
+             imap_port = excluded.imap_port,
+             smtp_server = excluded.smtp_server,
+             smtp_port = excluded.smtp_port,
+             auth_type = excluded.auth_type",
     )
     .bind(&account.email)
     .bind(&account.imap_server)
@@ -57,10 +124,13 @@ pub async fn complete_oauth2_flow(
     .bind(&account.smtp_server)
     .bind(account.smtp_port as i64)
     .bind("oauth2")
+    .bind(security::new_namespace())
     .execute(&*pool)
     .await
     .map_err(|e| format!("Failed to save account: {}", e))?;
 
+    let namespace = security::namespace_for_config(None, &email).await?;
+
     // Save sensitive credentials to OS keyring
     let credentials = security::AccountCredentials {
         email: email.clone(),
@@ -70,82 +140,206 @@ pub async fn complete_oauth2_flow(
         token_expires_at: expires_at,
     };
 
-    security::store_credentials(&credentials)?;
+    security::store_credentials(&namespace, &credentials)?;
+
+    let account_id = sqlx::query_as::<_, (i32,)>("SELECT id FROM accounts WHERE email = ?")
+        .bind(&email)
+        .fetch_one(&*pool)
+        .await
+        .map_err(|e| format!("Failed to load saved account: {}", e))?
+        .0;
+    if credentials.refresh_token.is_some() {
+        crate::commands::token_expiry::record_refresh_token_issued(account_id).await?;
+    }
 
+    audit::record("account_credentials_saved", Some(&email), "oauth2").await;
     println!("✅ OAuth2 account saved securely: {}", email);
+    account.id = Some(account_id);
     Ok(account)
 }
 
+/// Build the "oauth-code-received" payload for a deep-link callback URL
+///
+/// Returns None if the URL carries no authorization code.
+pub fn oauth_code_payload(url: &url::Url) -> Option<OAuthCodeReceived> {
+    let mut code = None;
+    let mut state = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "code" => code = Some(value.to_string()),
+            "state" => state = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    // Route the code to its flow; unknown or expired states carry no flow info
+    let (provider, account_hint) = state
+        .as_deref()
+        .and_then(oauth2_config::session_info)
+        .map(|(provider, account_hint)| (Some(provider), Some(account_hint)))
+        .unwrap_or((None, None));
+
+    Some(OAuthCodeReceived {
+        code: code?,
+        state,
+        provider,
+        account_hint,
+    })
+}
+
+/// Wait for the provider to redirect back to the loopback listener of a started flow
+///
+/// Requests that don't carry the expected state are answered with an error page and ignored,
+/// so a stray or forged request can't complete or abort the flow.
 #[command]
-pub async fn listen_for_oauth_callback() -> Result<(String, String), String> {
-    let listener = TcpListener::bind("127.0.0.1:8765")
-        .await
-        .map_err(|e| format!("Failed to bind to port 8765: {}", e))?;
+pub async fn listen_for_oauth_callback(state: Option<String>) -> Result<(String, String), String> {
+    let (expected_state, listener) = {
+        let mut listeners = PENDING_LISTENERS.lock().await;
+        let key = match state {
+            Some(state) => state,
+            // Older callers don't pass the state; only unambiguous with one pending flow
+            None if listeners.len() == 1 => listeners.keys().next().cloned().unwrap_or_default(),
+            None => return Err("No unique pending OAuth flow; pass the flow state".to_string()),
+        };
+        let listener = listeners
+            .remove(&key)
+            .ok_or_else(|| "No pending OAuth flow for this state".to_string())?;
+        (key, listener)
+    };
 
-    println!("🎧 Listening for OAuth callback on http://localhost:8765");
+    let result = tokio::time::timeout(
+        CALLBACK_TIMEOUT,
+        accept_callback(&listener, &expected_state),
+    )
+    .await;
 
-    let (mut stream, _) = listener
-        .accept()
-        .await
-        .map_err(|e| format!("Failed to accept connection: {}", e))?;
+    match result {
+        Ok(Ok(code)) => {
+            println!("✅ Successfully received authorization code");
+            Ok((code, expected_state))
+        }
+        Ok(Err(e)) => {
+            oauth2_config::discard_session(&expected_state);
+            Err(e)
+        }
+        Err(_) => {
+            oauth2_config::discard_session(&expected_state);
+            Err("Timed out waiting for the OAuth sign-in to complete".to_string())
+        }
+    }
+}
 
-    let buf_reader = tokio::io::BufReader::new(&mut stream);
-    let mut lines = buf_reader.lines();
+/// Accept connections until one carries a valid callback for `expected_state`
+async fn accept_callback(listener: &TcpListener, expected_state: &str) -> Result<String, String> {
+    loop {
+        let (mut stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("Failed to accept connection: {}", e))?;
 
-    let request_line = lines
-        .next_line()
-        .await
-        .map_err(|e| format!("Failed to read request line: {}", e))?
-        .ok_or_else(|| "Empty request".to_string())?;
+        let request_line = {
+            let buf_reader = tokio::io::BufReader::new(&mut stream);
+            let mut lines = buf_reader.lines();
+            match lines.next_line().await {
+                Ok(Some(line)) => line,
+                _ => continue,
+            }
+        };
 
-    println!("📥 Received request: {}", request_line);
+        // Parse the callback URL
+        let parts: Vec<&str> = request_line.split_whitespace().collect();
+        if parts.len() < 2 || parts[0] != "GET" {
+            let _ = write_response(
+                &mut stream,
+                "400 Bad Request",
+                &callback_page(false, "Invalid request."),
+            )
+            .await;
+            continue;
+        }
 
-    // Parse the callback URL
-    let parts: Vec<&str> = request_line.split_whitespace().collect();
-    if parts.len() < 2 {
-        return Err("Invalid HTTP request".to_string());
-    }
+        let parsed_url = match url::Url::parse(&format!("http://localhost{}", parts[1])) {
+            Ok(url) if url.path() == "/callback" => url,
+            // Browsers also ask for /favicon.ico and the like
+            _ => {
+                let _ = write_response(&mut stream, "404 Not Found", "").await;
+                continue;
+            }
+        };
 
-    let path = parts[1];
-    println!("🔍 Callback path: {}", path);
+        let mut code = None;
+        let mut state = None;
+        let mut error = None;
+        let mut error_description = None;
 
-    let url = format!("http://localhost:8765{}", path);
-    let parsed_url = url::Url::parse(&url).map_err(|e| format!("Failed to parse URL: {}", e))?;
+        for (key, value) in parsed_url.query_pairs() {
+            match key.as_ref() {
+                "code" => code = Some(value.to_string()),
+                "state" => state = Some(value.to_string()),
+                "error" => error = Some(value.to_string()),
+                "error_description" => error_description = Some(value.to_string()),
+                _ => {}
+            }
+        }
 
-    let mut code = None;
-    let mut state = None;
-    let mut error = None;
-    let mut error_description = None;
+        if state.as_deref() != Some(expected_state) {
+            println!("⚠️ Ignoring OAuth callback with unexpected state");
+            let _ = write_response(
+                &mut stream,
+                "400 Bad Request",
+                &callback_page(false, "This sign-in link is invalid or has expired."),
+            )
+            .await;
+            continue;
+        }
 
-    for (key, value) in parsed_url.query_pairs() {
-        println!("  {} = {}", key, value);
-        match key.as_ref() {
-            "code" => code = Some(value.to_string()),
-            "state" => state = Some(value.to_string()),
-            "error" => error = Some(value.to_string()),
-            "error_description" => error_description = Some(value.to_string()),
-            _ => {}
+        // Check for OAuth error responses
+        if let Some(err) = error {
+            let desc = error_description.unwrap_or_else(|| "No description".to_string());
+            let _ = write_response(
+                &mut stream,
+                "200 OK",
+                &callback_page(
+                    false,
+                    "Sign-in was cancelled or denied. You can close this window.",
+                ),
+            )
+            .await;
+            return Err(format!("OAuth error: {} - {}", err, desc));
         }
-    }
 
-    // Check for OAuth error responses
-    if let Some(err) = error {
-        let desc = error_description.unwrap_or_else(|| "No description".to_string());
-        return Err(format!("OAuth error: {} - {}", err, desc));
+        let Some(code) = code else {
+            println!("❌ Missing authorization code in callback URL");
+            let _ = write_response(
+                &mut stream,
+                "400 Bad Request",
+                &callback_page(false, "The sign-in response was incomplete."),
+            )
+            .await;
+            return Err("Missing authorization code. The OAuth provider may have sent an error or the callback was incomplete.".to_string());
+        };
+
+        write_response(
+            &mut stream,
+            "200 OK",
+            &callback_page(
+                true,
+                "You can now close this window and return to Colimail.",
+            ),
+        )
+        .await?;
+
+        return Ok(code);
     }
+}
 
-    // Send success response to browser
-    let response = "HTTP/1.1 200 OK\r\n\
-                   Content-Type: text/html; charset=UTF-8\r\n\
-                   \r\n\
-                   <!DOCTYPE html>\
-                   <html>\
-                   <head><title>Authentication Successful</title></head>\
-                   <body style='font-family: Arial, sans-serif; text-align: center; padding: 50px;'>\
-                   <h1>✅ Authentication Successful</h1>\
-                   <p>You can now close this window and return to the application.</p>\
-                   </body>\
-                   </html>";
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> Result<(), String> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=UTF-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
 
     stream
         .write_all(response.as_bytes())
@@ -155,17 +349,29 @@ pub async fn listen_for_oauth_callback() -> Result<(String, String), String> {
     stream
         .flush()
         .await
-        .map_err(|e| format!("Failed to flush stream: {}", e))?;
-
-    let code = code.ok_or_else(|| {
-        println!("❌ Missing authorization code in callback URL");
-        "Missing authorization code. The OAuth provider may have sent an error or the callback was incomplete.".to_string()
-    })?;
-    let state = state.ok_or_else(|| {
-        println!("❌ Missing state parameter in callback URL");
-        "Missing state parameter".to_string()
-    })?;
-
-    println!("✅ Successfully received authorization code");
-    Ok((code, state))
+        .map_err(|e| format!("Failed to flush stream: {}", e))
+}
+
+/// Page shown in the browser after the redirect
+fn callback_page(success: bool, message: &str) -> String {
+    let (icon, title) = if success {
+        ("✅", "Signed in to Colimail")
+    } else {
+        ("⚠️", "Colimail sign-in failed")
+    };
+
+    format!(
+        "<!DOCTYPE html>\
+         <html>\
+         <head><meta charset='utf-8'><title>{title}</title></head>\
+         <body style='font-family: -apple-system, Segoe UI, Arial, sans-serif; text-align: center; padding: 60px; color: #1f2937;'>\
+         <div style='font-size: 48px;'>{icon}</div>\
+         <h1 style='font-size: 22px;'>{title}</h1>\
+         <p style='color: #6b7280;'>{message}</p>\
+         </body>\
+         </html>",
+        icon = icon,
+        title = title,
+        message = message
+    )
 }
@@ -0,0 +1,401 @@
+// First-run onboarding
+// Drives the account setup wizard: detect the provider from the email domain, recommend OAuth
+// or a (app) password, test the connection, then run a small initial sync of the newest INBOX
+// messages so the mailbox isn't empty while the full sync runs. The state is kept in the
+// settings table, so an interrupted setup resumes at the same step.
+
+use crate::commands::emails::cache::save_emails_to_cache;
+use crate::commands::emails::imap_helpers;
+use crate::commands::emails::sync::parse::parse_email_headers;
+use crate::commands::test_connection::test_connection;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::{AccountConfig, AuthType};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+const STATE_KEY: &str = "onboarding_state";
+
+/// Messages fetched by the initial sync
+const INITIAL_SYNC_LIMIT: u32 = 200;
+
+/// Step of the setup wizard
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    ChooseAuth,     // Provider detected, waiting for credentials / OAuth sign-in
+    TestConnection, // Credentials entered, connection not verified yet
+    InitialSync,    // Connection works, account saved, first messages not fetched yet
+    Done,
+}
+
+/// How the account should sign in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthRecommendation {
+    OAuth,       // Sign in with the provider (`start_oauth2_flow`)
+    AppPassword, // Provider requires an app-specific password or authorization code
+    Password,
+}
+
+/// Server settings for a provider
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProviderSettings {
+    pub name: String,
+    pub imap_server: String,
+    pub imap_port: u16,
+    pub smtp_server: String,
+    pub smtp_port: u16,
+    pub oauth_provider: Option<String>, // Provider name for `start_oauth2_flow`
+    pub auth: AuthRecommendation,
+    pub known: bool, // False when the servers are only guessed from the domain
+    pub help: Option<String>,
+}
+
+/// Resumable state of the setup wizard
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OnboardingState {
+    pub email: String,
+    pub step: OnboardingStep,
+    pub provider: ProviderSettings,
+    pub account_id: Option<i32>,
+    pub last_error: Option<String>,
+    pub synced_messages: usize,
+    pub updated_at: i64,
+}
+
+struct KnownProvider {
+    domains: &'static [&'static str],
+    name: &'static str,
+    imap: (&'static str, u16),
+    smtp: (&'static str, u16),
+    oauth_provider: Option<&'static str>,
+    auth: AuthRecommendation,
+    help: Option<&'static str>,
+}
+
+const KNOWN_PROVIDERS: &[KnownProvider] = &[
+    KnownProvider {
+        domains: &["gmail.com", "googlemail.com"],
+        name: "Gmail",
+        imap: ("imap.gmail.com", 993),
+        smtp: ("smtp.gmail.com", 587),
+        oauth_provider: Some("google"),
+        auth: AuthRecommendation::OAuth,
+        help: None,
+    },
+    KnownProvider {
+        domains: &["outlook.com", "hotmail.com", "live.com", "msn.com"],
+        name: "Outlook.com",
+        imap: ("outlook.office365.com", 993),
+        smtp: ("smtp.office365.com", 587),
+        oauth_provider: Some("outlook"),
+        auth: AuthRecommendation::OAuth,
+        help: None,
+    },
+    KnownProvider {
+        domains: &["yahoo.com", "yahoo.co.uk", "yahoo.ca", "ymail.com"],
+        name: "Yahoo Mail",
+        imap: ("imap.mail.yahoo.com", 993),
+        smtp: ("smtp.mail.yahoo.com", 465),
+        oauth_provider: None,
+        auth: AuthRecommendation::AppPassword,
+        help: Some("Create an app password under Account security in your Yahoo account."),
+    },
+    KnownProvider {
+        domains: &["icloud.com", "me.com", "mac.com"],
+        name: "iCloud Mail",
+        imap: ("imap.mail.me.com", 993),
+        smtp: ("smtp.mail.me.com", 587),
+        oauth_provider: None,
+        auth: AuthRecommendation::AppPassword,
+        help: Some("Create an app-specific password at appleid.apple.com."),
+    },
+    KnownProvider {
+        domains: &["qq.com", "foxmail.com"],
+        name: "QQ Mail",
+        imap: ("imap.qq.com", 993),
+        smtp: ("smtp.qq.com", 465),
+        oauth_provider: None,
+        auth: AuthRecommendation::AppPassword,
+        help: Some("Enable IMAP in QQ Mail settings and use the authorization code as password."),
+    },
+    KnownProvider {
+        domains: &["163.com", "126.com", "yeah.net"],
+        name: "NetEase Mail",
+        imap: ("imap.163.com", 993),
+        smtp: ("smtp.163.com", 465),
+        oauth_provider: None,
+        auth: AuthRecommendation::AppPassword,
+        help: Some(
+            "Enable IMAP/SMTP in the mailbox settings and use the authorization code as password.",
+        ),
+    },
+    KnownProvider {
+        domains: &["aol.com"],
+        name: "AOL Mail",
+        imap: ("imap.aol.com", 993),
+        smtp: ("smtp.aol.com", 465),
+        oauth_provider: None,
+        auth: AuthRecommendation::AppPassword,
+        help: Some("Generate an app password in your AOL account security settings."),
+    },
+    KnownProvider {
+        domains: &["gmx.com", "gmx.net", "gmx.de"],
+        name: "GMX",
+        imap: ("imap.gmx.net", 993),
+        smtp: ("mail.gmx.net", 587),
+        oauth_provider: None,
+        auth: AuthRecommendation::Password,
+        help: Some("Enable POP3/IMAP access in the GMX settings first."),
+    },
+    KnownProvider {
+        domains: &["zoho.com"],
+        name: "Zoho Mail",
+        imap: ("imap.zoho.com", 993),
+        smtp: ("smtp.zoho.com", 465),
+        oauth_provider: None,
+        auth: AuthRecommendation::Password,
+        help: None,
+    },
+];
+
+/// Find server settings for an email address; unknown domains get `imap.`/`smtp.` guesses
+fn detect_provider(email: &str) -> Result<ProviderSettings, String> {
+    let domain = email
+        .trim()
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.to_lowercase())
+        .filter(|domain| domain.contains('.'))
+        .ok_or_else(|| format!("'{}' is not a valid email address", email.trim()))?;
+
+    let known = KNOWN_PROVIDERS
+        .iter()
+        .find(|provider| provider.domains.contains(&domain.as_str()));
+
+    Ok(match known {
+        Some(provider) => ProviderSettings {
+            name: provider.name.to_string(),
+            imap_server: provider.imap.0.to_string(),
+            imap_port: provider.imap.1,
+            smtp_server: provider.smtp.0.to_string(),
+            smtp_port: provider.smtp.1,
+            oauth_provider: provider.oauth_provider.map(str::to_string),
+            auth: provider.auth,
+            known: true,
+            help: provider.help.map(str::to_string),
+        },
+        None => ProviderSettings {
+            name: domain.clone(),
+            imap_server: format!("imap.{}", domain),
+            imap_port: 993,
+            smtp_server: format!("smtp.{}", domain),
+            smtp_port: 587,
+            oauth_provider: None,
+            auth: AuthRecommendation::Password,
+            known: false,
+            help: Some("Server settings were guessed; check them with your provider.".to_string()),
+        },
+    })
+}
+
+async fn save_state(state: &OnboardingState) -> Result<(), String> {
+    let value =
+        serde_json::to_string(state).map_err(|e| format!("Failed to encode setup state: {}", e))?;
+    let pool = db::pool();
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(STATE_KEY)
+        .bind(value)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save setup state: {}", e))?;
+    Ok(())
+}
+
+async fn load_state() -> Result<Option<OnboardingState>, String> {
+    let pool = db::pool();
+    let value = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(STATE_KEY)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load setup state: {}", e))?;
+
+    // A state from an older version that no longer parses just restarts the wizard
+    Ok(value.and_then(|(value,)| serde_json::from_str(&value).ok()))
+}
+
+async fn current_state() -> Result<OnboardingState, String> {
+    load_state()
+        .await?
+        .ok_or_else(|| "No account setup in progress".to_string())
+}
+
+async fn update_state(
+    mut state: OnboardingState,
+    step: OnboardingStep,
+    error: Option<String>,
+) -> Result<OnboardingState, String> {
+    state.step = step;
+    state.last_error = error;
+    state.updated_at = chrono::Utc::now().timestamp();
+    save_state(&state).await?;
+    Ok(state)
+}
+
+/// Start (or restart) account setup for an email address
+#[command]
+pub async fn start_onboarding(email: String) -> Result<OnboardingState, String> {
+    let provider = detect_provider(&email)?;
+    println!(
+        "🧭 Setup for {}: {} ({:?})",
+        email.trim(),
+        provider.name,
+        provider.auth
+    );
+
+    let state = OnboardingState {
+        email: email.trim().to_string(),
+        step: OnboardingStep::ChooseAuth,
+        provider,
+        account_id: None,
+        last_error: None,
+        synced_messages: 0,
+        updated_at: chrono::Utc::now().timestamp(),
+    };
+    save_state(&state).await?;
+    Ok(state)
+}
+
+/// Get the setup in progress, if any
+#[command]
+pub async fn get_onboarding_state() -> Result<Option<OnboardingState>, String> {
+    load_state().await
+}
+
+/// Abandon the setup in progress
+#[command]
+pub async fn cancel_onboarding() -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("DELETE FROM settings WHERE key = ?")
+        .bind(STATE_KEY)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to clear setup state: {}", e))?;
+    Ok(())
+}
+
+/// Test the entered (or OAuth-issued) credentials
+///
+/// On success the wizard moves on to the initial sync; the error is kept in the state otherwise.
+#[command]
+pub async fn onboarding_test_connection(config: AccountConfig) -> Result<OnboardingState, String> {
+    let state = update_state(current_state().await?, OnboardingStep::TestConnection, None).await?;
+
+    let result = if matches!(config.auth_type, Some(AuthType::OAuth2)) {
+        // OAuth accounts are already saved by `complete_oauth2_flow`; check they can log in
+        match ensure_valid_token(config.clone()).await {
+            Ok(config) => tokio::task::spawn_blocking(move || {
+                let mut session = imap_helpers::connect_and_login(&config)?;
+                let _ = session.logout();
+                Ok(())
+            })
+            .await
+            .map_err(|e| format!("Connection test failed: {}", e))
+            .and_then(|result| result),
+            Err(e) => Err(e),
+        }
+    } else {
+        test_connection(config.clone()).await.and_then(|result| {
+            match (result.imap_error, result.smtp_error) {
+                (None, None) => Ok(()),
+                (Some(e), _) => Err(e),
+                (None, Some(e)) => Err(e),
+            }
+        })
+    };
+
+    match result {
+        Ok(()) => {
+            let mut state = state;
+            state.account_id = config.id.or(state.account_id);
+            update_state(state, OnboardingStep::InitialSync, None).await
+        }
+        Err(e) => update_state(state, OnboardingStep::TestConnection, Some(e)).await,
+    }
+}
+
+/// Fetch the folder list and the newest INBOX messages of the saved account
+#[command]
+pub async fn onboarding_initial_sync(config: AccountConfig) -> Result<OnboardingState, String> {
+    let mut state = current_state().await?;
+    let account_id = config
+        .id
+        .or(state.account_id)
+        .ok_or("Save the account before the initial sync")?;
+    state.account_id = Some(account_id);
+
+    let config = AccountConfig {
+        id: Some(account_id),
+        ..config
+    };
+    match initial_sync(config).await {
+        Ok(count) => {
+            state.synced_messages = count;
+            update_state(state, OnboardingStep::Done, None).await
+        }
+        Err(e) => update_state(state, OnboardingStep::InitialSync, Some(e)).await,
+    }
+}
+
+async fn initial_sync(config: AccountConfig) -> Result<usize, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    crate::commands::folders::sync_folders(config.clone()).await?;
+
+    let config = ensure_valid_token(config).await?;
+    let emails = tokio::task::spawn_blocking(move || {
+        let mut session = imap_helpers::connect_and_login(&config)?;
+        let mailbox = session
+            .select("INBOX")
+            .map_err(|e| format!("Cannot access folder 'INBOX': {}", e))?;
+        if mailbox.exists == 0 {
+            let _ = session.logout();
+            return Ok(Vec::new());
+        }
+
+        let first = mailbox.exists.saturating_sub(INITIAL_SYNC_LIMIT - 1).max(1);
+        let messages = session
+            .fetch(
+                format!("{}:{}", first, mailbox.exists),
+                "(UID ENVELOPE BODYSTRUCTURE FLAGS INTERNALDATE)",
+            )
+            .map_err(|e| format!("Failed to fetch messages: {}", e))?;
+        let emails = parse_email_headers(messages.iter());
+        let _ = session.logout();
+        Ok::<_, String>(emails)
+    })
+    .await
+    .map_err(|e| format!("Initial sync task failed: {}", e))??;
+
+    save_emails_to_cache(account_id, "INBOX", &emails).await?;
+    println!("✅ Initial sync fetched {} messages", emails.len());
+    Ok(emails.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_provider() {
+        let gmail = detect_provider("someone@GMail.com").unwrap();
+        assert_eq!(gmail.oauth_provider.as_deref(), Some("google"));
+        assert!(gmail.known);
+
+        let custom = detect_provider("me@example.org").unwrap();
+        assert_eq!(custom.imap_server, "imap.example.org");
+        assert_eq!(custom.auth, AuthRecommendation::Password);
+        assert!(!custom.known);
+
+        assert!(detect_provider("not-an-address").is_err());
+    }
+}
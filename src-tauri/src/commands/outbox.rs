@@ -0,0 +1,649 @@
+// Outbox
+// Every outgoing message is written to the `outbox` table before it is handed to the SMTP
+// server, so a failed send is never lost. Temporary failures (no connection, 4xx replies) are
+// retried in the background with exponential backoff; permanent rejections (5xx) and messages
+// that keep failing are marked failed, reported with `send-failed`, and stay in the outbox
+// until the user retries or discards them. Each step is reported with `send-progress`. While
+// encryption is enabled the stored message is encrypted, and retries wait until it's unlocked.
+// With undo send enabled, new messages first wait in the outbox as `pending` for a few seconds
+// and can be taken back with `cancel_pending_send` until then. A message whose delay was cut
+// short by a quit or crash is held as `pending` until the user sends or discards it.
+
+use crate::commands::accounts::load_account_configs;
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::event_buffer::emit_buffered;
+use crate::commands::send::{deliver_raw, SendFailure};
+use crate::commands::smtp_pool::smtp_mailer;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::encryption::{decrypt_bytes, encrypt_bytes, is_encryption_unlocked};
+use crate::models::AccountConfig;
+use lettre::address::{Address, Envelope};
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{command, AppHandle};
+
+/// How often queued messages are checked for
+const RETRY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Attempts (including the first) before a message is marked failed
+const MAX_ATTEMPTS: i64 = 8;
+const BASE_BACKOFF_SECS: i64 = 60;
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+/// Allowed undo-send delays; 0 turns undo send off
+const MIN_UNDO_SEND_DELAY: i64 = 5;
+const MAX_UNDO_SEND_DELAY: i64 = 30;
+const DEFAULT_UNDO_SEND_DELAY: i64 = 10;
+
+/// Where a message is in the outbox
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    Pending, // Waiting out the undo-send delay, or held after a restart
+    Queued,
+    Sending,
+    Failed,
+    Sent,      // Only reported in events; sent messages leave the outbox
+    Cancelled, // Only reported in events; cancelled messages leave the outbox
+}
+
+impl OutboxStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Queued => "queued",
+            OutboxStatus::Sending => "sending",
+            OutboxStatus::Failed => "failed",
+            OutboxStatus::Sent => "sent",
+            OutboxStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "pending" => OutboxStatus::Pending,
+            "sending" => OutboxStatus::Sending,
+            "failed" => OutboxStatus::Failed,
+            "sent" => OutboxStatus::Sent,
+            "cancelled" => OutboxStatus::Cancelled,
+            _ => OutboxStatus::Queued,
+        }
+    }
+}
+
+/// Outcome of handing a message to the outbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Sent,
+    Queued,         // Temporary failure; will be retried in the background
+    Scheduled(i64), // Waiting out the undo-send delay, under this outbox id
+}
+
+/// A message waiting in the outbox
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutboxItem {
+    pub id: i64,
+    pub account_id: i32,
+    pub recipients: String,
+    pub subject: Option<String>, // None while the stored message can't be decrypted
+    pub status: OutboxStatus,
+    pub attempts: i64,
+    pub next_attempt_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+}
+
+/// Payload of the `send-progress` event
+#[derive(Serialize, Debug, Clone)]
+pub struct SendProgress {
+    pub outbox_id: i64,
+    pub account_id: i32,
+    pub status: OutboxStatus,
+    pub attempts: i64,
+    pub next_attempt_at: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// Keep a configured undo-send delay within the allowed range
+fn clamp_undo_send_delay(seconds: i64) -> i64 {
+    if seconds <= 0 {
+        0
+    } else {
+        seconds.clamp(MIN_UNDO_SEND_DELAY, MAX_UNDO_SEND_DELAY)
+    }
+}
+
+/// Delay before the next attempt after `attempts` failed ones
+fn backoff_secs(attempts: i64) -> i64 {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    BASE_BACKOFF_SECS
+        .saturating_mul(2i64.pow(exponent))
+        .min(MAX_BACKOFF_SECS)
+}
+
+fn parse_envelope(from: Option<&str>, to: &str) -> Result<Envelope, String> {
+    let from = from
+        .map(|from| from.parse::<Address>())
+        .transpose()
+        .map_err(|e| format!("Invalid sender in outbox: {}", e))?;
+    let to = to
+        .split(',')
+        .filter(|address| !address.is_empty())
+        .map(|address| address.parse::<Address>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid recipient in outbox: {}", e))?;
+    Envelope::new(from, to).map_err(|e| format!("Invalid envelope in outbox: {}", e))
+}
+
+/// Decrypt a stored message if needed; None while encryption is locked
+fn stored_message(message: Vec<u8>, encrypted: bool) -> Option<Vec<u8>> {
+    if !encrypted {
+        return Some(message);
+    }
+    if !is_encryption_unlocked() {
+        return None;
+    }
+    decrypt_bytes(&String::from_utf8_lossy(&message)).ok()
+}
+
+fn emit_progress(app_handle: &AppHandle, progress: SendProgress) {
+    emit_buffered(app_handle, "send-progress", progress);
+}
+
+/// Store a message in the outbox and try to send it right away, or after the undo-send delay
+pub(crate) async fn submit(
+    app_handle: &AppHandle,
+    config: &AccountConfig,
+    mailer: &AsyncSmtpTransport<Tokio1Executor>,
+    envelope: &Envelope,
+    raw: &[u8],
+) -> Result<Delivery, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+
+    // A locked vault must not turn into a plaintext copy of the message on disk
+    let encrypted = is_encryption_enabled().await?;
+    if encrypted && !is_encryption_unlocked() {
+        return Err("Unlock encryption to send messages".to_string());
+    }
+    let stored = if encrypted {
+        encrypt_bytes(raw)
+            .map_err(|e| format!("Failed to encrypt outgoing message: {}", e))?
+            .into_bytes()
+    } else {
+        raw.to_vec()
+    };
+    let envelope_to = envelope
+        .to()
+        .iter()
+        .map(|address| address.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let delay = get_undo_send_delay().await?;
+    let now = chrono::Utc::now().timestamp();
+    let (status, next_attempt_at) = if delay > 0 {
+        (OutboxStatus::Pending, Some(now + delay))
+    } else {
+        (OutboxStatus::Sending, None)
+    };
+
+    let pool = db::pool();
+    let (id,) = sqlx::query_as::<_, (i64,)>(
+        "INSERT INTO outbox (account_id, envelope_from, envelope_to, message, encrypted, status,
+                             next_attempt_at, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(account_id)
+    .bind(envelope.from().map(|from| from.to_string()))
+    .bind(&envelope_to)
+    .bind(stored)
+    .bind(encrypted)
+    .bind(status.as_str())
+    .bind(next_attempt_at)
+    .bind(now)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save message to outbox: {}", e))?;
+
+    if status == OutboxStatus::Sending {
+        return attempt(app_handle, id, config, mailer, envelope, raw, 0).await;
+    }
+
+    emit_progress(
+        app_handle,
+        SendProgress {
+            outbox_id: id,
+            account_id,
+            status,
+            attempts: 0,
+            next_attempt_at,
+            error: None,
+        },
+    );
+    println!("📤 Message {} will be sent in {} seconds", id, delay);
+
+    let app_handle = app_handle.clone();
+    let shutdown_token = crate::shutdown::token();
+    crate::shutdown::spawn("undo_send", async move {
+        // On quit the message stays pending and is held until the user sends or discards it
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(delay as u64)) => {}
+            _ = shutdown_token.cancelled() => return,
+        }
+        if let Err(e) = send_pending(&app_handle, id).await {
+            tracing::warn!(outbox_id = id, error = %e, "Delayed send failed");
+        }
+    });
+    Ok(Delivery::Scheduled(id))
+}
+
+/// Send a message whose undo-send delay has passed, unless it was cancelled meanwhile
+async fn send_pending(app_handle: &AppHandle, id: i64) -> Result<(), String> {
+    let pool = db::pool();
+    let pending =
+        sqlx::query_as::<_, (i64,)>("SELECT id FROM outbox WHERE id = ? AND status = 'pending'")
+            .bind(id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load outbox message: {}", e))?;
+    if pending.is_none() {
+        return Ok(());
+    }
+
+    let result = send_item(app_handle, id).await.map(|_| ());
+    if result.is_err() {
+        // Failed before the SMTP attempt (locked encryption, no token, ...); hand it to the
+        // retry loop instead of leaving it pending
+        sqlx::query("UPDATE outbox SET status = 'queued' WHERE id = ? AND status = 'pending'")
+            .bind(id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to update outbox: {}", e))?;
+    }
+    result
+}
+
+/// Try to send an outbox message once and record the outcome
+async fn attempt(
+    app_handle: &AppHandle,
+    id: i64,
+    config: &AccountConfig,
+    mailer: &AsyncSmtpTransport<Tokio1Executor>,
+    envelope: &Envelope,
+    raw: &[u8],
+    previous_attempts: i64,
+) -> Result<Delivery, String> {
+    let account_id = config.id.ok_or("Account ID is required")?;
+    let pool = db::pool();
+
+    emit_progress(
+        app_handle,
+        SendProgress {
+            outbox_id: id,
+            account_id,
+            status: OutboxStatus::Sending,
+            attempts: previous_attempts,
+            next_attempt_at: None,
+            error: None,
+        },
+    );
+
+    let failure = match deliver_raw(config, mailer, envelope, raw).await {
+        Ok(()) => {
+            sqlx::query("DELETE FROM outbox WHERE id = ?")
+                .bind(id)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to remove sent message from outbox: {}", e))?;
+            emit_progress(
+                app_handle,
+                SendProgress {
+                    outbox_id: id,
+                    account_id,
+                    status: OutboxStatus::Sent,
+                    attempts: previous_attempts + 1,
+                    next_attempt_at: None,
+                    error: None,
+                },
+            );
+            return Ok(Delivery::Sent);
+        }
+        Err(failure) => failure,
+    };
+
+    let attempts = previous_attempts + 1;
+    let error = failure.describe();
+    let gave_up = failure.permanent || attempts >= MAX_ATTEMPTS;
+    let (status, next_attempt_at) = if gave_up {
+        (OutboxStatus::Failed, None)
+    } else {
+        (
+            OutboxStatus::Queued,
+            Some(chrono::Utc::now().timestamp() + backoff_secs(attempts)),
+        )
+    };
+
+    sqlx::query(
+        "UPDATE outbox SET status = ?, attempts = ?, next_attempt_at = ?, last_error = ?
+         WHERE id = ?",
+    )
+    .bind(status.as_str())
+    .bind(attempts)
+    .bind(next_attempt_at)
+    .bind(&error)
+    .bind(id)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to update outbox: {}", e))?;
+
+    emit_progress(
+        app_handle,
+        SendProgress {
+            outbox_id: id,
+            account_id,
+            status,
+            attempts,
+            next_attempt_at,
+            error: Some(error.clone()),
+        },
+    );
+
+    if gave_up {
+        emit_buffered(
+            app_handle,
+            "send-failed",
+            SendFailure {
+                outbox_id: Some(id),
+                ..failure
+            },
+        );
+        Err(error)
+    } else {
+        println!(
+            "📤 Message {} queued for retry (attempt {} of {})",
+            id, attempts, MAX_ATTEMPTS
+        );
+        Ok(Delivery::Queued)
+    }
+}
+
+/// Send a stored outbox message (background retry or user request)
+async fn send_item(app_handle: &AppHandle, id: i64) -> Result<Delivery, String> {
+    let pool = db::pool();
+    let (account_id, envelope_from, envelope_to, message, encrypted, attempts) =
+        sqlx::query_as::<_, (i32, Option<String>, String, Vec<u8>, bool, i64)>(
+            "SELECT account_id, envelope_from, envelope_to, message, encrypted, attempts
+             FROM outbox WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load outbox message: {}", e))?
+        .ok_or_else(|| format!("Outbox message {} not found", id))?;
+
+    let raw = stored_message(message, encrypted)
+        .ok_or("Unlock encryption to send messages from the outbox")?;
+    let envelope = parse_envelope(envelope_from.as_deref(), &envelope_to)?;
+
+    let config = load_account_configs()
+        .await?
+        .into_iter()
+        .find(|config| config.id == Some(account_id))
+        .ok_or_else(|| format!("Account {} not found", account_id))?;
+    let config = ensure_valid_token(config).await?;
+    let mailer = smtp_mailer(&config).await?;
+
+    // Claim the message so a manual retry and the background loop can't both send it
+    let claimed =
+        sqlx::query("UPDATE outbox SET status = 'sending' WHERE id = ? AND status != 'sending'")
+            .bind(id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to update outbox: {}", e))?
+            .rows_affected();
+    if claimed == 0 {
+        return Err("This message is already being sent".to_string());
+    }
+
+    attempt(app_handle, id, &config, &mailer, &envelope, &raw, attempts).await
+}
+
+/// Retry queued messages whose backoff has passed
+async fn retry_due(app_handle: &AppHandle) -> Result<(), String> {
+    let pool = db::pool();
+    let due = sqlx::query_as::<_, (i64, bool)>(
+        "SELECT id, encrypted FROM outbox
+         WHERE status = 'queued' AND COALESCE(next_attempt_at, 0) <= ?
+         ORDER BY id",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load outbox: {}", e))?;
+
+    for (id, encrypted) in due {
+        if encrypted && !is_encryption_unlocked() {
+            continue;
+        }
+        if let Err(e) = send_item(app_handle, id).await {
+            tracing::warn!(outbox_id = id, error = %e, "Outbox retry failed");
+        }
+        if crate::shutdown::is_shutting_down() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Background loop that retries queued messages until shutdown
+pub async fn retry_loop(app_handle: AppHandle) {
+    // A send interrupted by a crash or quit is retried, as the server may not have it
+    let pool = db::pool();
+    if let Err(e) = sqlx::query(
+        "UPDATE outbox SET status = 'queued', next_attempt_at = NULL WHERE status = 'sending'",
+    )
+    .execute(pool.as_ref())
+    .await
+    {
+        tracing::warn!(error = %e, "Failed to requeue interrupted outbox messages");
+    }
+
+    // Messages whose undo window was cut short could still have been taken back, so they wait
+    // for `retry_outbox_item` or `cancel_pending_send` instead of going out on their own
+    if let Err(e) = sqlx::query("UPDATE outbox SET next_attempt_at = NULL WHERE status = 'pending'")
+        .execute(pool.as_ref())
+        .await
+    {
+        tracing::warn!(error = %e, "Failed to hold pending outbox messages");
+    }
+
+    let shutdown_token = crate::shutdown::token();
+    loop {
+        if let Err(e) = retry_due(&app_handle).await {
+            tracing::warn!(error = %e, "Outbox check failed");
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(RETRY_CHECK_INTERVAL) => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+    }
+}
+
+/// List the messages in the outbox, oldest first
+#[command]
+pub async fn list_outbox(account_id: Option<i32>) -> Result<Vec<OutboxItem>, String> {
+    let pool = db::pool();
+    #[allow(clippy::type_complexity)]
+    let rows = sqlx::query_as::<
+        _,
+        (
+            i64,
+            i32,
+            String,
+            Vec<u8>,
+            bool,
+            String,
+            i64,
+            Option<i64>,
+            Option<String>,
+            i64,
+        ),
+    >(
+        "SELECT id, account_id, envelope_to, message, encrypted, status, attempts,
+                next_attempt_at, last_error, created_at
+         FROM outbox WHERE ? IS NULL OR account_id = ?
+         ORDER BY id",
+    )
+    .bind(account_id)
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load outbox: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(
+                id,
+                account_id,
+                recipients,
+                message,
+                encrypted,
+                status,
+                attempts,
+                next_attempt_at,
+                last_error,
+                created_at,
+            )| {
+                let subject = stored_message(message, encrypted).and_then(|raw| {
+                    mail_parser::MessageParser::default()
+                        .parse(&raw)
+                        .and_then(|parsed| parsed.subject().map(str::to_string))
+                });
+                OutboxItem {
+                    id,
+                    account_id,
+                    recipients: recipients.replace(',', ", "),
+                    subject,
+                    status: OutboxStatus::parse(&status),
+                    attempts,
+                    next_attempt_at,
+                    last_error,
+                    created_at,
+                }
+            },
+        )
+        .collect())
+}
+
+/// Send an outbox message now, whatever its status; returns whether the server accepted it
+#[command]
+pub async fn retry_outbox_item(app_handle: AppHandle, id: i64) -> Result<bool, String> {
+    // A manual retry gets a fresh set of automatic retries
+    let pool = db::pool();
+    sqlx::query("UPDATE outbox SET attempts = 0 WHERE id = ? AND status = 'failed'")
+        .bind(id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to update outbox: {}", e))?;
+
+    Ok(send_item(&app_handle, id).await? == Delivery::Sent)
+}
+
+/// Take back a message that is still waiting out its undo-send delay, or held after a restart
+///
+/// Returns false if it's too late (the message is already being sent or gone).
+#[command]
+pub async fn cancel_pending_send(app_handle: AppHandle, send_id: i64) -> Result<bool, String> {
+    let pool = db::pool();
+    let cancelled = sqlx::query_as::<_, (i32,)>(
+        "DELETE FROM outbox WHERE id = ? AND status = 'pending' RETURNING account_id",
+    )
+    .bind(send_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to cancel send: {}", e))?;
+
+    let Some((account_id,)) = cancelled else {
+        return Ok(false);
+    };
+    emit_progress(
+        &app_handle,
+        SendProgress {
+            outbox_id: send_id,
+            account_id,
+            status: OutboxStatus::Cancelled,
+            attempts: 0,
+            next_attempt_at: None,
+            error: None,
+        },
+    );
+    println!("↩️ Cancelled sending message {}", send_id);
+    Ok(true)
+}
+
+/// Get the undo-send delay in seconds (0 when undo send is off)
+#[command]
+pub async fn get_undo_send_delay() -> Result<i64, String> {
+    let pool = db::pool();
+    let value =
+        sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = 'undo_send_delay'")
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to get undo send delay: {}", e))?;
+
+    Ok(value
+        .and_then(|(value,)| value.parse::<i64>().ok())
+        .map(clamp_undo_send_delay)
+        .unwrap_or(DEFAULT_UNDO_SEND_DELAY))
+}
+
+/// Set the undo-send delay in seconds; 0 turns undo send off, other values are kept to 5–30
+#[command]
+pub async fn set_undo_send_delay(seconds: i64) -> Result<i64, String> {
+    let seconds = clamp_undo_send_delay(seconds);
+    let pool = db::pool();
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES ('undo_send_delay', ?)")
+        .bind(seconds.to_string())
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to set undo send delay: {}", e))?;
+
+    println!("✅ Set undo send delay to {} seconds", seconds);
+    Ok(seconds)
+}
+
+/// Discard a message from the outbox without sending it
+#[command]
+pub async fn delete_outbox_item(id: i64) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("DELETE FROM outbox WHERE id = ? AND status != 'sending'")
+        .bind(id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete outbox message: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_secs() {
+        assert_eq!(backoff_secs(1), 60);
+        assert_eq!(backoff_secs(3), 240);
+        assert_eq!(backoff_secs(7), MAX_BACKOFF_SECS);
+        assert_eq!(backoff_secs(40), MAX_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn test_clamp_undo_send_delay() {
+        assert_eq!(clamp_undo_send_delay(-3), 0);
+        assert_eq!(clamp_undo_send_delay(0), 0);
+        assert_eq!(clamp_undo_send_delay(1), MIN_UNDO_SEND_DELAY);
+        assert_eq!(clamp_undo_send_delay(20), 20);
+        assert_eq!(clamp_undo_send_delay(90), MAX_UNDO_SEND_DELAY);
+    }
+}
@@ -0,0 +1,261 @@
+// Local plugins
+// A plugin is a directory under `<data dir>/plugins` with a `plugin.json` manifest naming an
+// executable inside that directory and the hooks it handles. On a hook the executable runs as a
+// subprocess (no shell) with a JSON request on stdin and may answer with a JSON reply on stdout.
+// Plugins are disabled until the user enables them.
+
+use crate::commands::authorization::{authorize, Sensitivity};
+use crate::db;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::command;
+use tokio::io::AsyncWriteExt;
+
+/// Default time a plugin may take to answer
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Largest reply read from a plugin's stdout
+const MAX_REPLY_BYTES: usize = 64 * 1024;
+
+/// Points where plugins are called
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginHook {
+    /// Before an email is sent; a plugin can abort the send
+    PreSend,
+    /// After a folder sync finished
+    PostSync,
+    /// When an email body is opened
+    OnOpen,
+}
+
+/// `plugin.json`
+#[derive(Deserialize, Debug, Clone)]
+struct PluginManifest {
+    name: String,
+    #[serde(default)]
+    description: String,
+    command: String, // Executable, relative to the plugin directory
+    hooks: Vec<PluginHook>,
+    timeout_secs: Option<u64>,
+}
+
+/// A discovered plugin
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PluginInfo {
+    pub name: String,
+    pub description: String,
+    pub hooks: Vec<PluginHook>,
+    pub enabled: bool,
+    pub path: String,
+}
+
+/// What a plugin writes to stdout (all fields optional)
+#[derive(Deserialize, Debug, Default)]
+struct PluginReply {
+    #[serde(default)]
+    abort: bool,
+    message: Option<String>,
+}
+
+struct Plugin {
+    manifest: PluginManifest,
+    executable: PathBuf,
+}
+
+/// Directory scanned for plugins
+pub fn plugins_dir() -> PathBuf {
+    db::db_path()
+        .parent()
+        .map(|dir| dir.join("plugins"))
+        .unwrap_or_else(|| PathBuf::from("plugins"))
+}
+
+fn enabled_key(name: &str) -> String {
+    format!("plugin_enabled:{}", name)
+}
+
+/// Read the manifests of all plugin directories; broken plugins are logged and skipped
+fn discover() -> Vec<Plugin> {
+    let Ok(entries) = std::fs::read_dir(plugins_dir()) else {
+        return Vec::new();
+    };
+
+    let mut plugins = Vec::new();
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        match load_plugin(&dir) {
+            Ok(plugin) => plugins.push(plugin),
+            Err(e) => eprintln!("⚠️ Skipping plugin in {}: {}", dir.display(), e),
+        }
+    }
+    plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    plugins
+}
+
+fn load_plugin(dir: &Path) -> Result<Plugin, String> {
+    let manifest_text = std::fs::read_to_string(dir.join("plugin.json"))
+        .map_err(|e| format!("Failed to read plugin.json: {}", e))?;
+    let manifest: PluginManifest =
+        serde_json::from_str(&manifest_text).map_err(|e| format!("Invalid plugin.json: {}", e))?;
+
+    // The executable must live inside the plugin's own directory
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve plugin directory: {}", e))?;
+    let executable = dir
+        .join(&manifest.command)
+        .canonicalize()
+        .map_err(|e| format!("Plugin command '{}' not found: {}", manifest.command, e))?;
+    if !executable.starts_with(&dir) {
+        return Err("Plugin command must be inside the plugin directory".to_string());
+    }
+
+    Ok(Plugin {
+        manifest,
+        executable,
+    })
+}
+
+async fn is_enabled(name: &str) -> bool {
+    let pool = db::pool();
+    sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(enabled_key(name))
+        .fetch_optional(pool.as_ref())
+        .await
+        .ok()
+        .flatten()
+        .map(|(value,)| value == "true")
+        .unwrap_or(false)
+}
+
+/// List the plugins found in the plugins directory
+#[command]
+pub async fn list_plugins() -> Result<Vec<PluginInfo>, String> {
+    let mut result = Vec::new();
+    for plugin in discover() {
+        result.push(PluginInfo {
+            enabled: is_enabled(&plugin.manifest.name).await,
+            name: plugin.manifest.name,
+            description: plugin.manifest.description,
+            hooks: plugin.manifest.hooks,
+            path: plugin.executable.to_string_lossy().to_string(),
+        });
+    }
+    Ok(result)
+}
+
+/// Enable or disable a plugin
+#[command]
+pub async fn set_plugin_enabled(name: String, enabled: bool) -> Result<(), String> {
+    // An enabled plugin runs arbitrary code with the user's mail
+    if enabled {
+        authorize("set_plugin_enabled", Sensitivity::HighRisk).await?;
+    }
+
+    let pool = db::pool();
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(enabled_key(&name))
+        .bind(if enabled { "true" } else { "false" })
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to update plugin setting: {}", e))?;
+
+    crate::commands::audit::record(
+        if enabled {
+            "plugin_enabled"
+        } else {
+            "plugin_disabled"
+        },
+        None,
+        &name,
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Call every enabled plugin registered for `hook` with `data`
+///
+/// Returns an error only when a `PreSend` plugin aborts; other plugin failures are logged.
+pub async fn run_hook(hook: PluginHook, data: serde_json::Value) -> Result<(), String> {
+    for plugin in discover() {
+        if !plugin.manifest.hooks.contains(&hook) || !is_enabled(&plugin.manifest.name).await {
+            continue;
+        }
+
+        match call_plugin(&plugin, hook, &data).await {
+            Ok(reply) if reply.abort && hook == PluginHook::PreSend => {
+                return Err(format!(
+                    "Blocked by plugin '{}': {}",
+                    plugin.manifest.name,
+                    reply
+                        .message
+                        .unwrap_or_else(|| "no reason given".to_string())
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("⚠️ Plugin '{}' failed: {}", plugin.manifest.name, e),
+        }
+    }
+    Ok(())
+}
+
+async fn call_plugin(
+    plugin: &Plugin,
+    hook: PluginHook,
+    data: &serde_json::Value,
+) -> Result<PluginReply, String> {
+    let request = serde_json::json!({ "hook": hook, "data": data }).to_string();
+    let timeout = Duration::from_secs(plugin.manifest.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+
+    let mut child = tokio::process::Command::new(&plugin.executable)
+        .current_dir(plugin.executable.parent().unwrap_or(Path::new(".")))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start: {}", e))?;
+
+    // The request is written while the output is read, both under the timeout, so a plugin
+    // that never reads stdin (or fills stdout first) can't stall the caller
+    let mut stdin = child.stdin.take();
+    let write_request = async move {
+        if let Some(stdin) = stdin.as_mut() {
+            stdin
+                .write_all(request.as_bytes())
+                .await
+                .map_err(|e| format!("Failed to write request: {}", e))?;
+        }
+        // Closing stdin marks the end of the request
+        drop(stdin);
+        Ok::<(), String>(())
+    };
+    let (written, output) = tokio::time::timeout(timeout, async {
+        tokio::join!(write_request, child.wait_with_output())
+    })
+    .await
+    .map_err(|_| format!("No reply within {}s", timeout.as_secs()))?;
+    let output = output.map_err(|e| format!("Failed to run: {}", e))?;
+    written?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = &output.stdout[..output.stdout.len().min(MAX_REPLY_BYTES)];
+    if stdout.iter().all(|byte| byte.is_ascii_whitespace()) {
+        return Ok(PluginReply::default());
+    }
+    serde_json::from_slice(stdout).map_err(|e| format!("Invalid reply: {}", e))
+}
@@ -0,0 +1,154 @@
+// Quick reply
+// Replies to a cached email with a short plain-text message, without going through the
+// composer, e.g. from a notification. The reply is threaded with In-Reply-To/References,
+// is sent from the identity the original was addressed to, can quote the original body, and
+// the original is marked \Answered once it is sent.
+
+use crate::commands::accounts::load_account_configs;
+use crate::commands::emails::cache::{is_encryption_enabled, load_email_body_from_cache};
+use crate::commands::emails::imap_helpers;
+use crate::commands::identities::suggest_reply_identity;
+use crate::commands::outbox::Delivery;
+use crate::commands::plugins::{run_hook, PluginHook};
+use crate::commands::send::{deliver, html_body, QUEUED_MESSAGE, SCHEDULED_MESSAGE};
+use crate::commands::smtp_pool::smtp_mailer;
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use lettre::message::Mailbox;
+use lettre::Message;
+use tauri::{command, AppHandle};
+
+/// Escape plain text for an HTML body, keeping line breaks
+fn text_to_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+fn reply_subject(subject: &str) -> String {
+    if subject.trim_start().to_lowercase().starts_with("re:") {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// Reply to a cached email with plain text, optionally quoting the original
+#[command]
+pub async fn quick_reply(
+    app_handle: AppHandle,
+    email_id: i64,
+    text: String,
+    quote: Option<bool>,
+) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Err("Reply text is empty".to_string());
+    }
+
+    let pool = db::pool();
+    let (account_id, folder_name, uid, subject, from, date, message_id) =
+        sqlx::query_as::<_, (i32, String, i64, String, String, String, Option<String>)>(
+            "SELECT account_id, folder_name, uid, subject, from_addr, date, message_id
+             FROM emails WHERE id = ?",
+        )
+        .bind(email_id)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load email: {}", e))?
+        .ok_or("Email not found")?;
+    let uid = uid as u32;
+
+    let subject = if is_encryption_enabled().await? && is_encryption_unlocked() {
+        decrypt(&subject).unwrap_or_default()
+    } else {
+        subject
+    };
+
+    let mut body = text_to_html(text.trim_end());
+    if quote.unwrap_or(false) {
+        if let Some(original) = load_email_body_from_cache(account_id, &folder_name, uid).await? {
+            body.push_str(&format!(
+                "<br><br><div>On {}, {} wrote:</div><blockquote style=\"margin:0 0 0 .8ex;border-left:1px solid #ccc;padding-left:1ex\">{}</blockquote>",
+                text_to_html(&date),
+                text_to_html(&from),
+                original
+            ));
+        }
+    }
+
+    let config = load_account_configs()
+        .await?
+        .into_iter()
+        .find(|config| config.id == Some(account_id))
+        .ok_or("Account not found")?;
+    run_hook(
+        PluginHook::PreSend,
+        serde_json::json!({
+            "kind": "reply",
+            "from": config.email,
+            "to": from,
+            "cc": null,
+            "subject": subject,
+            "body": body,
+        }),
+    )
+    .await?;
+    let config = ensure_valid_token(config).await?;
+
+    // Reply from the alias the original was addressed to, if any
+    let (from_email, from_name) = match suggest_reply_identity(email_id).await? {
+        Some(identity) => (identity.email, identity.display_name),
+        None => (config.email.clone(), config.display_name.clone()),
+    };
+    let from_mailbox: Mailbox = match &from_name {
+        Some(name) if !name.trim().is_empty() => format!("{} <{}>", name, from_email),
+        _ => from_email,
+    }
+    .parse()
+    .map_err(|e: lettre::address::AddressError| e.to_string())?;
+    let to_mailbox: Mailbox = from
+        .parse()
+        .map_err(|e| format!("Cannot reply to '{}': {}", from, e))?;
+
+    let mut builder = Message::builder()
+        .from(from_mailbox)
+        .to(to_mailbox)
+        .subject(reply_subject(&subject));
+    if let Some(message_id) = &message_id {
+        builder = builder
+            .in_reply_to(message_id.clone())
+            .references(message_id.clone());
+    }
+    let email = builder
+        .multipart(html_body(body))
+        .map_err(|e| e.to_string())?;
+
+    let mailer = smtp_mailer(&config).await?;
+    match deliver(&app_handle, &config, &mailer, email).await? {
+        Delivery::Sent => {}
+        Delivery::Queued => return Ok(QUEUED_MESSAGE.into()),
+        Delivery::Scheduled(_) => return Ok(SCHEDULED_MESSAGE.into()),
+    }
+
+    // The reply is out; failing to flag the original shouldn't report the send as failed
+    let result = tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut session = imap_helpers::connect_and_login(&config)?;
+        session
+            .select(&folder_name)
+            .map_err(|e| format!("Cannot access folder '{}': {}", folder_name, e))?;
+        session
+            .uid_store(uid.to_string(), "+FLAGS (\\Answered)")
+            .map_err(|e| format!("Failed to set \\Answered flag: {}", e))?;
+        let _ = session.logout();
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if let Err(e) = result {
+        eprintln!("⚠️ Reply sent but original not marked answered: {}", e);
+    }
+
+    Ok("Reply sent.".into())
+}
@@ -0,0 +1,154 @@
+// Mailbox quota
+// Reports how full an account's mailbox is on the server, using GETQUOTAROOT on the inbox
+// (RFC 2087 / RFC 9208) when the server advertises QUOTA. STORAGE is counted by servers in
+// units of 1024 octets and returned here in bytes.
+
+use crate::commands::emails::imap_helpers;
+use crate::commands::utils::ensure_valid_token;
+use crate::models::AccountConfig;
+use serde::Serialize;
+use tauri::command;
+
+/// Usage of one quota root
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MailboxQuota {
+    pub root: String,
+    pub used_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub used_messages: Option<u64>,
+    pub total_messages: Option<u64>,
+}
+
+/// Split off a quoted string or atom at the start of `input`
+fn take_astring(input: &str) -> Option<(String, &str)> {
+    let input = input.trim_start();
+    if let Some(quoted) = input.strip_prefix('"') {
+        let mut value = String::new();
+        let mut escaped = false;
+        for (i, c) in quoted.char_indices() {
+            match (escaped, c) {
+                (false, '\\') => escaped = true,
+                (false, '"') => return Some((value, &quoted[i + 1..])),
+                (_, c) => {
+                    value.push(c);
+                    escaped = false;
+                }
+            }
+        }
+        return None;
+    }
+    let end = input.find([' ', '(']).unwrap_or(input.len());
+    Some((input[..end].to_string(), &input[end..]))
+}
+
+/// Parse the untagged `* QUOTA root (RESOURCE usage limit ...)` replies of GETQUOTAROOT
+fn parse_quota_response(raw: &[u8]) -> Vec<MailboxQuota> {
+    let text = String::from_utf8_lossy(raw);
+    let mut quotas = Vec::new();
+
+    for line in text.lines() {
+        let Some(rest) = line.strip_prefix("* QUOTA ") else {
+            continue;
+        };
+        let Some((root, rest)) = take_astring(rest) else {
+            continue;
+        };
+        let resources = rest
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split_whitespace()
+            .collect::<Vec<_>>();
+
+        let mut quota = MailboxQuota {
+            root,
+            used_bytes: None,
+            total_bytes: None,
+            used_messages: None,
+            total_messages: None,
+        };
+        for resource in resources.chunks(3) {
+            let [name, usage, limit] = resource else {
+                continue;
+            };
+            let (Ok(usage), Ok(limit)) = (usage.parse::<u64>(), limit.parse::<u64>()) else {
+                continue;
+            };
+            if name.eq_ignore_ascii_case("STORAGE") {
+                quota.used_bytes = Some(usage * 1024);
+                quota.total_bytes = Some(limit * 1024);
+            } else if name.eq_ignore_ascii_case("MESSAGE") {
+                quota.used_messages = Some(usage);
+                quota.total_messages = Some(limit);
+            }
+        }
+        quotas.push(quota);
+    }
+
+    quotas
+}
+
+/// Get how much of its server quota an account uses
+/// Returns None if the server doesn't support QUOTA or sets no limit on the inbox.
+#[command]
+pub async fn get_mailbox_quota(config: AccountConfig) -> Result<Option<MailboxQuota>, String> {
+    let config = ensure_valid_token(config).await?;
+
+    let quota = tokio::task::spawn_blocking(move || -> Result<Option<MailboxQuota>, String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config)?;
+
+        let supports_quota = imap_session
+            .capabilities()
+            .map_err(|e| format!("Failed to get capabilities: {}", e))?
+            .has_str("QUOTA");
+        if !supports_quota {
+            let _ = imap_session.logout();
+            return Ok(None);
+        }
+
+        let raw = imap_session.run_command_and_read_response("GETQUOTAROOT INBOX");
+        let _ = imap_session.logout();
+        let raw = raw.map_err(|e| format!("GETQUOTAROOT failed: {}", e))?;
+
+        // Prefer the root that limits storage when the inbox has several
+        let mut quotas = parse_quota_response(&raw);
+        let storage = quotas.iter().position(|quota| quota.total_bytes.is_some());
+        Ok(match storage {
+            Some(index) => Some(quotas.swap_remove(index)),
+            None => quotas.into_iter().next(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if let Some(quota) = &quota {
+        println!(
+            "📦 Quota '{}': {:?} of {:?} bytes used",
+            quota.root, quota.used_bytes, quota.total_bytes
+        );
+    }
+    Ok(quota)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quota_response() {
+        let raw = b"* QUOTAROOT INBOX \"User quota\"\r\n\
+                    * QUOTA \"User quota\" (STORAGE 512 15728640 MESSAGE 42 100000)\r\n\
+                    A2 OK Getquotaroot completed\r\n";
+
+        assert_eq!(
+            parse_quota_response(raw),
+            vec![MailboxQuota {
+                root: "User quota".to_string(),
+                used_bytes: Some(512 * 1024),
+                total_bytes: Some(15728640 * 1024),
+                used_messages: Some(42),
+                total_messages: Some(100000),
+            }]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// Reader windows
+// A cached message can be opened in its own window so several can be read side by side. Each
+// window is bound to one message; the backend tracks which message each window shows, so the
+// window menu can list them (`list_open_windows`) and deleting a message closes its window.
+
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+/// An open reader window and the message it shows
+#[derive(Serialize, Debug, Clone)]
+pub struct ReaderWindow {
+    pub label: String,
+    pub email_id: i64,
+    pub account_id: i32,
+    pub folder_name: String,
+    pub uid: u32,
+    pub subject: String,
+}
+
+lazy_static::lazy_static! {
+    static ref WINDOWS: Mutex<HashMap<String, ReaderWindow>> = Mutex::new(HashMap::new());
+}
+
+fn window_label(email_id: i64) -> String {
+    format!("reader-{}", email_id)
+}
+
+/// Open a cached message in a reader window, focusing it if it's already open
+#[command]
+pub async fn open_reader_window(
+    app_handle: AppHandle,
+    account_id: i32,
+    folder_name: String,
+    uid: u32,
+) -> Result<String, String> {
+    let pool = db::pool();
+    let (email_id, subject) = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, subject FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .bind(uid as i64)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load email: {}", e))?
+    .ok_or("Email not found in cache")?;
+
+    let label = window_label(email_id);
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(label);
+    }
+
+    let subject = if is_encryption_enabled().await? && is_encryption_unlocked() {
+        decrypt(&subject).unwrap_or_default()
+    } else {
+        subject
+    };
+    let title = if subject.trim().is_empty() {
+        "(No subject)".to_string()
+    } else {
+        subject.clone()
+    };
+
+    let window = WebviewWindowBuilder::new(
+        &app_handle,
+        &label,
+        WebviewUrl::App(format!("reader?id={}", email_id).into()),
+    )
+    .title(title)
+    .inner_size(800.0, 700.0)
+    .build()
+    .map_err(|e| format!("Failed to open reader window: {}", e))?;
+
+    let closed_label = label.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            if let Ok(mut windows) = WINDOWS.lock() {
+                windows.remove(&closed_label);
+            }
+        }
+    });
+
+    WINDOWS.lock().map_err(|e| e.to_string())?.insert(
+        label.clone(),
+        ReaderWindow {
+            label: label.clone(),
+            email_id,
+            account_id,
+            folder_name,
+            uid,
+            subject,
+        },
+    );
+
+    Ok(label)
+}
+
+/// List open reader windows, oldest message first
+#[command]
+pub fn list_open_windows() -> Result<Vec<ReaderWindow>, String> {
+    let mut windows: Vec<ReaderWindow> = WINDOWS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .values()
+        .cloned()
+        .collect();
+    windows.sort_by_key(|window| window.email_id);
+    Ok(windows)
+}
+
+/// Close the reader window showing a message, if any (called when the message is deleted)
+pub fn close_for_message(app_handle: &AppHandle, account_id: i32, folder_name: &str, uid: u32) {
+    let labels: Vec<String> = match WINDOWS.lock() {
+        Ok(mut windows) => {
+            let labels: Vec<String> = windows
+                .values()
+                .filter(|window| {
+                    window.account_id == account_id
+                        && window.folder_name == folder_name
+                        && window.uid == uid
+                })
+                .map(|window| window.label.clone())
+                .collect();
+            for label in &labels {
+                windows.remove(label);
+            }
+            labels
+        }
+        Err(_) => return,
+    };
+
+    for label in labels {
+        if let Some(window) = app_handle.get_webview_window(&label) {
+            let _ = window.close();
+        }
+    }
+}
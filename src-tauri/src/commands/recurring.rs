@@ -0,0 +1,509 @@
+// Recurring sends
+// A message template that is delivered on a schedule (daily, weekly or monthly at a local
+// time), e.g. a weekly status email. Each occurrence either lands in Drafts as a skeleton to
+// finish by hand or is sent right away. The subject and body may use {{date}}, {{week}} and
+// {{month}} placeholders. Every occurrence is recorded with its outcome.
+
+use crate::commands::accounts::load_account_configs;
+use crate::commands::drafts::save_draft;
+use crate::commands::send::send_email;
+use crate::db;
+use crate::models::DraftType;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{command, AppHandle};
+
+/// How often due recurring sends are checked for
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often a template recurs
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Cadence {
+    Daily,
+    Weekly,  // On `day` (0 = Monday … 6 = Sunday)
+    Monthly, // On `day` of the month (1-28)
+}
+
+impl Cadence {
+    fn as_str(self) -> &'static str {
+        match self {
+            Cadence::Daily => "daily",
+            Cadence::Weekly => "weekly",
+            Cadence::Monthly => "monthly",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "weekly" => Cadence::Weekly,
+            "monthly" => Cadence::Monthly,
+            _ => Cadence::Daily,
+        }
+    }
+}
+
+/// What happens when an occurrence is due
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurringDelivery {
+    Draft, // Save to Drafts for the user to finish
+    Send,  // Send without review
+}
+
+impl RecurringDelivery {
+    fn as_str(self) -> &'static str {
+        match self {
+            RecurringDelivery::Draft => "draft",
+            RecurringDelivery::Send => "send",
+        }
+    }
+}
+
+/// A message template with a schedule
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecurringSend {
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub account_id: i32,
+    pub name: String,
+    pub enabled: bool,
+    pub to_addr: String,
+    pub cc_addr: String,
+    pub subject: String,
+    pub body: String,
+    pub cadence: Cadence,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub delivery: RecurringDelivery,
+    #[serde(default)]
+    pub next_run_at: Option<i64>,
+}
+
+/// One delivered (or failed) occurrence of a recurring send
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecurringOccurrence {
+    pub id: i64,
+    pub recurring_send_id: i64,
+    pub scheduled_for: i64,
+    pub ran_at: i64,
+    pub outcome: String, // "drafted", "sent" or "failed"
+    pub draft_id: Option<i64>,
+    pub error: Option<String>,
+}
+
+type RecurringRow = (
+    i64,
+    i32,
+    String,
+    bool,
+    String,
+    String,
+    String,
+    String,
+    String,
+    i64,
+    i64,
+    i64,
+    String,
+    Option<i64>,
+);
+
+fn recurring_from_row(row: RecurringRow) -> RecurringSend {
+    let (
+        id,
+        account_id,
+        name,
+        enabled,
+        to_addr,
+        cc_addr,
+        subject,
+        body,
+        cadence,
+        day,
+        hour,
+        minute,
+        delivery,
+        next_run_at,
+    ) = row;
+    RecurringSend {
+        id: Some(id),
+        account_id,
+        name,
+        enabled,
+        to_addr,
+        cc_addr,
+        subject,
+        body,
+        cadence: Cadence::parse(&cadence),
+        day: day as u32,
+        hour: hour as u32,
+        minute: minute as u32,
+        delivery: match delivery.as_str() {
+            "send" => RecurringDelivery::Send,
+            _ => RecurringDelivery::Draft,
+        },
+        next_run_at,
+    }
+}
+
+async fn load_recurring(id: Option<i64>) -> Result<Vec<RecurringSend>, String> {
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, RecurringRow>(
+        "SELECT id, account_id, name, enabled, to_addr, cc_addr, subject, body, cadence, day,
+            hour, minute, delivery, next_run_at
+         FROM recurring_sends WHERE (? IS NULL OR id = ?) ORDER BY id",
+    )
+    .bind(id)
+    .bind(id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load recurring sends: {}", e))?;
+
+    Ok(rows.into_iter().map(recurring_from_row).collect())
+}
+
+/// The first scheduled time strictly after `after`
+fn next_occurrence(
+    cadence: Cadence,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    after: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    let at = |date: NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_hms_opt(hour, minute, 0)?)
+            .earliest()
+    };
+    let start = after.date_naive();
+
+    // Look far enough ahead to cover the longest gap between occurrences
+    (0..=62)
+        .map(|offset| start + ChronoDuration::days(offset))
+        .filter(|date| match cadence {
+            Cadence::Daily => true,
+            Cadence::Weekly => date.weekday().num_days_from_monday() == day,
+            Cadence::Monthly => date.day() == day,
+        })
+        .filter_map(at)
+        .find(|time| *time > after)
+}
+
+fn validate(recurring: &RecurringSend) -> Result<(), String> {
+    if recurring.hour > 23 || recurring.minute > 59 {
+        return Err("Invalid time of day".to_string());
+    }
+    match recurring.cadence {
+        Cadence::Weekly if recurring.day > 6 => Err("Weekday must be 0 (Monday) to 6".to_string()),
+        // Days 29-31 don't exist in every month
+        Cadence::Monthly if !(1..=28).contains(&recurring.day) => {
+            Err("Day of month must be between 1 and 28".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Substitute the date placeholders for an occurrence
+fn render(template: &str, scheduled_for: DateTime<Local>) -> String {
+    template
+        .replace("{{date}}", &scheduled_for.format("%Y-%m-%d").to_string())
+        .replace("{{week}}", &scheduled_for.iso_week().week().to_string())
+        .replace("{{month}}", &scheduled_for.format("%B %Y").to_string())
+}
+
+/// Deliver one occurrence and record it; returns the outcome
+async fn run_occurrence(
+    app_handle: &AppHandle,
+    recurring: &RecurringSend,
+    scheduled_for: DateTime<Local>,
+) -> Result<String, String> {
+    let id = recurring.id.ok_or("Recurring send has no id")?;
+    let subject = render(&recurring.subject, scheduled_for);
+    let body = render(&recurring.body, scheduled_for);
+
+    let result = match recurring.delivery {
+        RecurringDelivery::Draft => save_draft(
+            recurring.account_id,
+            recurring.to_addr.clone(),
+            recurring.cc_addr.clone(),
+            subject,
+            body,
+            "[]".to_string(),
+            DraftType::Compose,
+            None,
+        )
+        .await
+        .map(|draft_id| ("drafted", Some(draft_id))),
+        RecurringDelivery::Send => {
+            async {
+                let config = load_account_configs()
+                    .await?
+                    .into_iter()
+                    .find(|config| config.id == Some(recurring.account_id))
+                    .ok_or("Account not found")?;
+                let cc = Some(recurring.cc_addr.clone()).filter(|cc| !cc.trim().is_empty());
+                send_email(
+                    app_handle.clone(),
+                    config,
+                    recurring.to_addr.clone(),
+                    subject,
+                    body,
+                    cc,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .map(|_| ("sent", None))
+            }
+            .await
+        }
+    };
+
+    let (outcome, draft_id, error) = match &result {
+        Ok((outcome, draft_id)) => (*outcome, *draft_id, None),
+        Err(e) => ("failed", None, Some(e.clone())),
+    };
+
+    let pool = db::pool();
+    sqlx::query(
+        "INSERT INTO recurring_send_occurrences
+            (recurring_send_id, scheduled_for, ran_at, outcome, draft_id, error)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(id)
+    .bind(scheduled_for.timestamp())
+    .bind(chrono::Utc::now().timestamp())
+    .bind(outcome)
+    .bind(draft_id)
+    .bind(&error)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to record recurring send: {}", e))?;
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(outcome.to_string()),
+    }
+}
+
+/// Deliver every enabled recurring send whose time has come, then schedule its next run
+async fn run_due(app_handle: &AppHandle) -> Result<(), String> {
+    let now = Local::now();
+    let due = load_recurring(None).await?.into_iter().filter(|recurring| {
+        recurring.enabled
+            && recurring
+                .next_run_at
+                .is_some_and(|at| at <= now.timestamp())
+    });
+
+    let pool = db::pool();
+    for recurring in due {
+        let scheduled_for = recurring
+            .next_run_at
+            .and_then(|at| Local.timestamp_opt(at, 0).single())
+            .unwrap_or(now);
+
+        // Advance first, so a failing send isn't retried every minute
+        let next = next_occurrence(
+            recurring.cadence,
+            recurring.day,
+            recurring.hour,
+            recurring.minute,
+            now,
+        );
+        sqlx::query("UPDATE recurring_sends SET next_run_at = ? WHERE id = ?")
+            .bind(next.map(|next| next.timestamp()))
+            .bind(recurring.id)
+            .execute(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to schedule recurring send: {}", e))?;
+
+        match run_occurrence(app_handle, &recurring, scheduled_for).await {
+            Ok(outcome) => println!("🔁 Recurring send '{}': {}", recurring.name, outcome),
+            Err(e) => eprintln!("⚠️ Recurring send '{}' failed: {}", recurring.name, e),
+        }
+    }
+    Ok(())
+}
+
+/// Background loop that delivers due recurring sends until shutdown
+pub async fn scheduler_loop(app_handle: AppHandle) {
+    let shutdown_token = crate::shutdown::token();
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+
+        if let Err(e) = run_due(&app_handle).await {
+            tracing::warn!(error = %e, "Recurring send check failed");
+        }
+    }
+}
+
+/// List recurring sends
+#[command]
+pub async fn list_recurring_sends() -> Result<Vec<RecurringSend>, String> {
+    load_recurring(None).await
+}
+
+/// Create or update a recurring send, returning its id
+///
+/// The next run is recomputed from the schedule, starting now.
+#[command]
+pub async fn save_recurring_send(recurring: RecurringSend) -> Result<i64, String> {
+    validate(&recurring)?;
+    if recurring.to_addr.trim().is_empty() && recurring.delivery == RecurringDelivery::Send {
+        return Err("A recipient is required to send automatically".to_string());
+    }
+    let next_run_at = next_occurrence(
+        recurring.cadence,
+        recurring.day,
+        recurring.hour,
+        recurring.minute,
+        Local::now(),
+    )
+    .map(|next| next.timestamp());
+
+    let pool = db::pool();
+    let (id,) = sqlx::query_as::<_, (i64,)>(
+        "INSERT INTO recurring_sends
+            (id, account_id, name, enabled, to_addr, cc_addr, subject, body, cadence, day, hour,
+             minute, delivery, next_run_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            account_id = excluded.account_id,
+            name = excluded.name,
+            enabled = excluded.enabled,
+            to_addr = excluded.to_addr,
+            cc_addr = excluded.cc_addr,
+            subject = excluded.subject,
+            body = excluded.body,
+            cadence = excluded.cadence,
+            day = excluded.day,
+            hour = excluded.hour,
+            minute = excluded.minute,
+            delivery = excluded.delivery,
+            next_run_at = excluded.next_run_at
+         RETURNING id",
+    )
+    .bind(recurring.id)
+    .bind(recurring.account_id)
+    .bind(&recurring.name)
+    .bind(recurring.enabled)
+    .bind(&recurring.to_addr)
+    .bind(&recurring.cc_addr)
+    .bind(&recurring.subject)
+    .bind(&recurring.body)
+    .bind(recurring.cadence.as_str())
+    .bind(recurring.day as i64)
+    .bind(recurring.hour as i64)
+    .bind(recurring.minute as i64)
+    .bind(recurring.delivery.as_str())
+    .bind(next_run_at)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save recurring send: {}", e))?;
+
+    Ok(id)
+}
+
+/// Delete a recurring send and its history
+#[command]
+pub async fn delete_recurring_send(recurring_send_id: i64) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("DELETE FROM recurring_sends WHERE id = ?")
+        .bind(recurring_send_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete recurring send: {}", e))?;
+    Ok(())
+}
+
+/// Past occurrences of a recurring send, newest first
+#[command]
+pub async fn get_recurring_send_history(
+    recurring_send_id: i64,
+) -> Result<Vec<RecurringOccurrence>, String> {
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (i64, i64, i64, i64, String, Option<i64>, Option<String>)>(
+        "SELECT id, recurring_send_id, scheduled_for, ran_at, outcome, draft_id, error
+         FROM recurring_send_occurrences WHERE recurring_send_id = ?
+         ORDER BY ran_at DESC LIMIT 200",
+    )
+    .bind(recurring_send_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load recurring send history: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, recurring_send_id, scheduled_for, ran_at, outcome, draft_id, error)| {
+                RecurringOccurrence {
+                    id,
+                    recurring_send_id,
+                    scheduled_for,
+                    ran_at,
+                    outcome,
+                    draft_id,
+                    error,
+                }
+            },
+        )
+        .collect())
+}
+
+/// Deliver an occurrence now, without changing the schedule
+#[command]
+pub async fn run_recurring_send_now(
+    app_handle: AppHandle,
+    recurring_send_id: i64,
+) -> Result<String, String> {
+    let recurring = load_recurring(Some(recurring_send_id))
+        .await?
+        .pop()
+        .ok_or("Recurring send not found")?;
+    run_occurrence(&app_handle, &recurring, Local::now()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_occurrence() {
+        // Thursday 2024-05-16 10:00
+        let after = Local.with_ymd_and_hms(2024, 5, 16, 10, 0, 0).unwrap();
+        let next = |cadence, day, hour| {
+            next_occurrence(cadence, day, hour, 0, after)
+                .unwrap()
+                .naive_local()
+        };
+
+        assert_eq!(
+            next(Cadence::Daily, 0, 9).to_string(),
+            "2024-05-17 09:00:00"
+        );
+        assert_eq!(
+            next(Cadence::Daily, 0, 11).to_string(),
+            "2024-05-16 11:00:00"
+        );
+        // Mondays at 09:00
+        assert_eq!(
+            next(Cadence::Weekly, 0, 9).to_string(),
+            "2024-05-20 09:00:00"
+        );
+        // The 1st of each month
+        assert_eq!(
+            next(Cadence::Monthly, 1, 9).to_string(),
+            "2024-06-01 09:00:00"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+// Reply all
+// Works out the recipients of a reply-all from a cached email: the reply goes to the
+// original's Reply-To (or From), and everyone else on its To and Cc lists is copied. The
+// account's own address and its aliases are left out, so nobody replies to themselves, and
+// when the original was sent by this account the reply goes back to its recipients instead.
+
+use crate::commands::emails::cache::is_encryption_enabled;
+use crate::commands::identities::load_identities;
+use crate::commands::send::{reply_email, AttachmentData};
+use crate::db;
+use crate::encryption::{decrypt, is_encryption_unlocked};
+use crate::models::AccountConfig;
+use serde::Serialize;
+use tauri::{command, AppHandle};
+
+/// Recipients for a reply-all, as comma-separated address lists
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReplyAllRecipients {
+    pub to: String,
+    pub cc: String,
+}
+
+/// Split an address list on commas outside quotes and angle brackets
+pub(crate) fn split_addresses(value: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut in_brackets = false;
+
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_brackets = true,
+            '>' if !in_quotes => in_brackets = false,
+            ',' if !in_quotes && !in_brackets => {
+                entries.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    entries.push(current);
+
+    entries
+        .into_iter()
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| entry.contains('@'))
+        .collect()
+}
+
+/// Bare lowercase address of an entry like `Name <a@x.com>`
+pub(crate) fn bare_address(entry: &str) -> String {
+    let address = match entry.rsplit_once('<') {
+        Some((_, rest)) => rest.trim_end_matches('>'),
+        None => entry,
+    };
+    address.trim().to_lowercase()
+}
+
+/// Compute To and Cc for a reply-all, leaving out `own` addresses and duplicates
+fn reply_all_recipients(
+    from: &str,
+    reply_to: Option<&str>,
+    to: &str,
+    cc: &str,
+    own: &[String],
+) -> ReplyAllRecipients {
+    let is_own = |entry: &String| {
+        own.iter()
+            .any(|own| own.eq_ignore_ascii_case(&bare_address(entry)))
+    };
+    let sent_by_us = split_addresses(from).iter().any(is_own);
+
+    // Replying to our own message continues the conversation with its recipients
+    let (primary, copied) = if sent_by_us {
+        (split_addresses(to), split_addresses(cc))
+    } else {
+        let primary = reply_to
+            .map(split_addresses)
+            .filter(|entries| !entries.is_empty())
+            .unwrap_or_else(|| split_addresses(from));
+        let mut copied = split_addresses(to);
+        copied.extend(split_addresses(cc));
+        (primary, copied)
+    };
+
+    let mut seen: Vec<String> = Vec::new();
+    let mut keep = |entries: Vec<String>| -> Vec<String> {
+        entries
+            .into_iter()
+            .filter(|entry| {
+                let address = bare_address(entry);
+                if is_own(entry) || seen.contains(&address) {
+                    return false;
+                }
+                seen.push(address);
+                true
+            })
+            .collect()
+    };
+    let mut to = keep(primary);
+    let mut cc = keep(copied);
+
+    // Someone has to be on To, e.g. when replying to a message we only received via Cc
+    if to.is_empty() && !cc.is_empty() {
+        to.push(cc.remove(0));
+    }
+
+    ReplyAllRecipients {
+        to: to.join(", "),
+        cc: cc.join(", "),
+    }
+}
+
+/// A cached email's reply-all recipients, subject and Message-ID
+struct ReplyAllSource {
+    recipients: ReplyAllRecipients,
+    subject: String,
+    message_id: Option<String>,
+}
+
+/// Load a cached email and compute its reply-all recipients
+async fn load_recipients(
+    account_id: i32,
+    folder_name: &str,
+    uid: u32,
+) -> Result<ReplyAllSource, String> {
+    let pool = db::pool();
+    #[allow(clippy::type_complexity)]
+    let (email_id, subject, from, to, cc, message_id) =
+        sqlx::query_as::<_, (i64, String, String, String, Option<String>, Option<String>)>(
+            "SELECT id, subject, from_addr, to_addr, cc_addr, message_id FROM emails
+             WHERE account_id = ? AND folder_name = ? AND uid = ?",
+        )
+        .bind(account_id)
+        .bind(folder_name)
+        .bind(uid as i64)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load email: {}", e))?
+        .ok_or("Email not found in cache")?;
+
+    let reply_to = sqlx::query_as::<_, (String,)>(
+        "SELECT value FROM email_raw_headers WHERE email_id = ? AND name = 'reply-to'
+         ORDER BY id LIMIT 1",
+    )
+    .bind(email_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load Reply-To: {}", e))?
+    .map(|(value,)| value);
+
+    let own: Vec<String> = load_identities(account_id)
+        .await?
+        .into_iter()
+        .map(|identity| identity.email.to_lowercase())
+        .collect();
+
+    let subject = if is_encryption_enabled().await? && is_encryption_unlocked() {
+        decrypt(&subject).unwrap_or_default()
+    } else {
+        subject
+    };
+
+    let recipients = reply_all_recipients(
+        &from,
+        reply_to.as_deref(),
+        &to,
+        cc.as_deref().unwrap_or_default(),
+        &own,
+    );
+    Ok(ReplyAllSource {
+        recipients,
+        subject,
+        message_id,
+    })
+}
+
+/// Get the To and Cc of a reply-all to a cached email, e.g. to prefill the composer
+#[command]
+pub async fn get_reply_all_recipients(
+    account_id: i32,
+    uid: u32,
+    folder: Option<String>,
+) -> Result<ReplyAllRecipients, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    Ok(load_recipients(account_id, &folder_name, uid)
+        .await?
+        .recipients)
+}
+
+/// Reply to everyone on a cached email
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn reply_all_email(
+    app_handle: AppHandle,
+    config: AccountConfig,
+    uid: u32,
+    folder: Option<String>,
+    body: String,
+    bcc: Option<String>,
+    reply_to: Option<String>,
+    attachments: Option<Vec<AttachmentData>>,
+) -> Result<String, String> {
+    let folder_name = folder.unwrap_or_else(|| "INBOX".to_string());
+    let account_id = config.id.ok_or("Account ID is required")?;
+
+    let ReplyAllSource {
+        recipients,
+        subject,
+        message_id,
+    } = load_recipients(account_id, &folder_name, uid).await?;
+    if recipients.to.is_empty() {
+        return Err("There is no one to reply to".to_string());
+    }
+    println!(
+        "Replying to all: to {}, cc {}",
+        recipients.to, recipients.cc
+    );
+
+    reply_email(
+        app_handle,
+        config,
+        recipients.to,
+        subject,
+        body,
+        Some(recipients.cc).filter(|cc| !cc.is_empty()),
+        bcc,
+        reply_to,
+        message_id,
+        None,
+        None,
+        attachments,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_all_recipients() {
+        let own = vec![
+            "me@example.com".to_string(),
+            "sales@example.com".to_string(),
+        ];
+
+        let recipients = reply_all_recipients(
+            "Alice <alice@example.org>",
+            None,
+            "\"Doe, Jane\" <jane@example.org>, Sales <SALES@example.com>",
+            "me@example.com, alice@example.org, bob@example.org",
+            &own,
+        );
+        assert_eq!(recipients.to, "Alice <alice@example.org>");
+        assert_eq!(
+            recipients.cc,
+            "\"Doe, Jane\" <jane@example.org>, bob@example.org"
+        );
+
+        // Reply-To wins over From
+        let recipients = reply_all_recipients(
+            "alice@example.org",
+            Some("list@example.org"),
+            "me@example.com",
+            "",
+            &own,
+        );
+        assert_eq!(recipients.to, "list@example.org");
+        assert_eq!(recipients.cc, "alice@example.org");
+
+        // Our own message goes back to its recipients
+        let recipients = reply_all_recipients(
+            "Me <me@example.com>",
+            None,
+            "bob@example.org",
+            "carol@example.org",
+            &own,
+        );
+        assert_eq!(recipients.to, "bob@example.org");
+        assert_eq!(recipients.cc, "carol@example.org");
+    }
+}
@@ -1,12 +1,49 @@
 use crate::attachment_limits::{get_limit_for_email, validate_attachment_sizes};
+use crate::commands::emails::codec::message_ids;
+use crate::commands::outbox::{self, Delivery};
+use crate::commands::plugins::{run_hook, PluginHook};
+use crate::commands::sent_copy::{record_sent_bcc, save_sent_copy};
+use crate::commands::smtp_pool::{self, smtp_mailer};
 use crate::commands::utils::ensure_valid_token;
-use crate::models::{AccountConfig, AuthType};
+use crate::html_text::html_to_text;
+use crate::models::AccountConfig;
 use lettre::{
-    message::{Attachment as LettreAttachment, Body, Mailbox, MultiPart, SinglePart},
-    transport::smtp::authentication::{Credentials, Mechanism},
+    address::Envelope,
+    message::{
+        header::{HeaderName, HeaderValue},
+        Attachment as LettreAttachment, Body, Mailbox, Mailboxes, MessageBuilder, MultiPart,
+    },
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
-use tauri::command;
+use tauri::{command, AppHandle};
+
+/// Returned instead of "sent" when a message had to be left in the outbox
+pub(crate) const QUEUED_MESSAGE: &str =
+    "Email queued in the outbox; it will be sent when the server is reachable.";
+/// Returned when a message is waiting out the undo-send delay
+pub(crate) const SCHEDULED_MESSAGE: &str = "Sending email…";
+
+/// Returned by the send commands
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SendResult {
+    pub message: String,
+    /// Outbox id while the message waits out the undo-send delay (see `cancel_pending_send`)
+    pub send_id: Option<i64>,
+}
+
+impl SendResult {
+    fn new(delivery: Delivery, sent_message: &str) -> Self {
+        let (message, send_id) = match delivery {
+            Delivery::Sent => (sent_message, None),
+            Delivery::Queued => (QUEUED_MESSAGE, None),
+            Delivery::Scheduled(id) => (SCHEDULED_MESSAGE, Some(id)),
+        };
+        SendResult {
+            message: message.to_string(),
+            send_id,
+        }
+    }
+}
 
 #[derive(serde::Deserialize)]
 pub struct AttachmentData {
@@ -15,6 +52,24 @@ pub struct AttachmentData {
     pub data: Vec<u8>,
 }
 
+/// Details of a failed send, emitted as `send-failed` once the outbox gives up on a message
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SendFailure {
+    pub outbox_id: Option<i64>,
+    pub to: String,
+    pub message: String,
+    pub code: Option<u16>, // SMTP reply code, if the server answered
+    pub permanent: bool,   // 5xx: retrying won't help
+    pub rejected_recipients: Vec<String>,
+}
+
+/// An extra header for an outgoing message, e.g. `X-Campaign: spring`
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct CustomHeader {
+    pub name: String,
+    pub value: String,
+}
+
 #[derive(serde::Deserialize)]
 pub struct ForwardEmailParams {
     pub to: String,
@@ -25,20 +80,151 @@ pub struct ForwardEmailParams {
     pub original_body: String,
     pub additional_message: String,
     pub cc: Option<String>,
+    pub bcc: Option<String>,
+    pub reply_to: Option<String>,
+    pub custom_headers: Option<Vec<CustomHeader>>,
     pub attachments: Option<Vec<AttachmentData>>,
 }
 
+/// Parse a comma-separated address list ("a@example.com, Name <b@example.com>")
+fn parse_mailboxes(list: &str) -> Result<Vec<Mailbox>, String> {
+    if list.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    list.parse::<Mailboxes>()
+        .map(|mailboxes| mailboxes.into_iter().collect())
+        .map_err(|e| format!("Invalid address in \"{}\": {}", list, e))
+}
+
+/// An HTML body with a plain-text alternative generated from it
+///
+/// Text-only clients show the text part, and spam filters score HTML-only mail worse.
+pub(crate) fn html_body(html: String) -> MultiPart {
+    MultiPart::alternative_plain_html(html_to_text(&html), html)
+}
+
+/// Message IDs kept in References besides the thread's first message
+const MAX_REFERENCES: usize = 20;
+
+/// In-Reply-To and References for a reply to `message_id`, whose own References were
+/// `references`
+///
+/// References is the parent's chain plus the parent, trimmed to the thread's first message and
+/// the most recent ones so long threads don't grow the header without bound.
+fn threading_headers(
+    message_id: Option<&str>,
+    references: Option<&str>,
+) -> Option<(String, String)> {
+    let parent = message_ids(message_id?).into_iter().next()?;
+    let mut chain = message_ids(references.unwrap_or_default());
+    chain.retain(|id| *id != parent);
+    chain.push(parent.clone());
+    if chain.len() > MAX_REFERENCES {
+        chain.drain(1..chain.len() - (MAX_REFERENCES - 1));
+    }
+    Some((parent, chain.join(" ")))
+}
+
+/// Check that a custom header is an X- header with a single-line value
+///
+/// Only X- headers are accepted so callers can't override the addresses, threading or MIME
+/// structure the message is built with.
+fn validate_custom_header(header: &CustomHeader) -> Result<(), String> {
+    let name = header.name.trim();
+    let valid_name = name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        && name.len() > 2
+        && name[..2].eq_ignore_ascii_case("x-");
+    if !valid_name {
+        return Err(format!(
+            "Invalid custom header '{}': only X- headers are allowed",
+            header.name
+        ));
+    }
+    if header.value.contains(['\r', '\n']) {
+        return Err(format!(
+            "Invalid custom header '{}': value must be a single line",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Add extra X- headers to a message
+fn add_custom_headers(
+    mut builder: MessageBuilder,
+    headers: Option<&[CustomHeader]>,
+) -> Result<MessageBuilder, String> {
+    for header in headers.unwrap_or_default() {
+        validate_custom_header(header)?;
+        let name = HeaderName::new_from_ascii(header.name.trim().to_string())
+            .map_err(|e| format!("Invalid custom header '{}': {}", header.name, e))?;
+        builder = builder.raw_header(HeaderValue::new(name, header.value.trim().to_string()));
+    }
+    Ok(builder)
+}
+
+/// Add the To, Cc, Bcc and Reply-To addresses to a message
+///
+/// Bcc recipients only go into the SMTP envelope: lettre drops the Bcc header when the message
+/// is built, so neither the other recipients nor the saved Sent copy see them.
+fn add_addresses(
+    mut builder: MessageBuilder,
+    to: &str,
+    cc: Option<&str>,
+    bcc: Option<&str>,
+    reply_to: Option<&str>,
+) -> Result<MessageBuilder, String> {
+    let to = parse_mailboxes(to)?;
+    if to.is_empty() {
+        return Err("At least one recipient is required".to_string());
+    }
+    for mailbox in to {
+        builder = builder.to(mailbox);
+    }
+    for mailbox in parse_mailboxes(cc.unwrap_or_default())? {
+        builder = builder.cc(mailbox);
+    }
+    for mailbox in parse_mailboxes(bcc.unwrap_or_default())? {
+        builder = builder.bcc(mailbox);
+    }
+    for mailbox in parse_mailboxes(reply_to.unwrap_or_default())? {
+        builder = builder.reply_to(mailbox);
+    }
+    Ok(builder)
+}
+
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn send_email(
+    app_handle: AppHandle,
     config: AccountConfig,
     to: String,
     subject: String,
     body: String,
     cc: Option<String>,
+    bcc: Option<String>,
+    reply_to: Option<String>,
+    custom_headers: Option<Vec<CustomHeader>>,
     attachments: Option<Vec<AttachmentData>>,
-) -> Result<String, String> {
+) -> Result<SendResult, String> {
     println!("Sending email to {}", to);
 
+    run_hook(
+        PluginHook::PreSend,
+        serde_json::json!({
+            "kind": "send",
+            "from": config.email,
+            "to": to,
+            "cc": cc,
+            "bcc": bcc,
+            "subject": subject,
+            "body": body,
+        }),
+    )
+    .await?;
+
     // Ensure we have a valid access token (refresh if needed)
     let config = ensure_valid_token(config).await?;
 
@@ -54,27 +240,15 @@ pub async fn send_email(
     } else {
         config.email.parse::<Mailbox>().map_err(|e| e.to_string())?
     };
-    let to_mailbox: Mailbox = to.parse::<Mailbox>().map_err(|e| e.to_string())?;
-
-    let mut email_builder = Message::builder()
-        .from(from)
-        .to(to_mailbox)
-        .subject(subject);
-
-    // Add CC recipients if provided
-    if let Some(cc_str) = cc {
-        if !cc_str.trim().is_empty() {
-            // Split by comma and parse each CC recipient
-            for cc_addr in cc_str.split(',') {
-                let cc_addr = cc_addr.trim();
-                if !cc_addr.is_empty() {
-                    let cc_mailbox: Mailbox =
-                        cc_addr.parse::<Mailbox>().map_err(|e| e.to_string())?;
-                    email_builder = email_builder.cc(cc_mailbox);
-                }
-            }
-        }
-    }
+
+    let email_builder = add_addresses(
+        Message::builder().from(from).subject(subject),
+        &to,
+        cc.as_deref(),
+        bcc.as_deref(),
+        reply_to.as_deref(),
+    )?;
+    let email_builder = add_custom_headers(email_builder, custom_headers.as_deref())?;
 
     // Validate attachment sizes if attachments are present
     if let Some(ref attachment_list) = attachments {
@@ -86,7 +260,7 @@ pub async fn send_email(
     // Build multipart message if there are attachments
     let email = if let Some(attachment_list) = attachments {
         if !attachment_list.is_empty() {
-            let mut multipart = MultiPart::mixed().singlepart(SinglePart::html(body));
+            let mut multipart = MultiPart::mixed().multipart(html_body(body));
 
             for attachment_data in attachment_list {
                 let attachment_body = Body::new(attachment_data.data);
@@ -101,104 +275,56 @@ pub async fn send_email(
                 .multipart(multipart)
                 .map_err(|e| e.to_string())?
         } else {
-            // Send as HTML even without attachments
+            // Send as HTML (with a text alternative) even without attachments
             email_builder
-                .multipart(MultiPart::alternative().singlepart(SinglePart::html(body)))
+                .multipart(html_body(body))
                 .map_err(|e| e.to_string())?
         }
     } else {
-        // Send as HTML even without attachments
+        // Send as HTML (with a text alternative) even without attachments
         email_builder
-            .multipart(MultiPart::alternative().singlepart(SinglePart::html(body)))
+            .multipart(html_body(body))
             .map_err(|e| e.to_string())?
     };
 
-    let mailer = match config.auth_type {
-        Some(AuthType::OAuth2) => {
-            let access_token = config
-                .access_token
-                .clone()
-                .ok_or("Access token is required for OAuth2 authentication")?;
+    let mailer = smtp_mailer(&config).await?;
 
-            println!(
-                "🔐 Building SMTP transport with XOAUTH2 for {}",
-                config.email
-            );
-            println!("   Server: {}:{}", config.smtp_server, config.smtp_port);
-            println!("   Token length: {} chars", access_token.len());
-
-            // For OAuth2, we need to use XOAUTH2 mechanism
-            // The credentials format for XOAUTH2 is: email as username, access_token as password
-            let creds = Credentials::new(config.email.clone(), access_token);
-
-            // Choose connection method based on port
-            if config.smtp_port == 465 {
-                // Port 465: SSL/TLS (implicit TLS)
-                AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_server)
-                    .map_err(|e| e.to_string())?
-                    .credentials(creds)
-                    .port(config.smtp_port)
-                    .authentication(vec![Mechanism::Xoauth2])
-                    .build()
-            } else {
-                // Port 587: STARTTLS
-                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)
-                    .map_err(|e| e.to_string())?
-                    .credentials(creds)
-                    .authentication(vec![Mechanism::Xoauth2])
-                    .build()
-            }
-        }
-        _ => {
-            let password = config
-                .password
-                .clone()
-                .ok_or("Password is required for basic authentication")?;
-
-            let creds = Credentials::new(config.email.clone(), password);
-
-            // Choose connection method based on port
-            if config.smtp_port == 465 {
-                // Port 465: SSL/TLS (implicit TLS, used by 163.com, QQ, etc.)
-                println!("   Using SSL/TLS (implicit TLS) for port 465");
-                AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_server)
-                    .map_err(|e| e.to_string())?
-                    .credentials(creds)
-                    .port(config.smtp_port)
-                    .build()
-            } else {
-                // Port 587 or others: STARTTLS
-                println!("   Using STARTTLS for port {}", config.smtp_port);
-                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)
-                    .map_err(|e| e.to_string())?
-                    .credentials(creds)
-                    .build()
-            }
-        }
-    };
-
-    tokio::spawn(async move {
-        if let Err(e) = mailer.send(email).await {
-            eprintln!("Could not send email: {:?}", e);
-        } else {
-            println!("Email sent successfully!");
-        }
-    });
-
-    Ok("Started sending email.".into())
+    let delivery = deliver(&app_handle, &config, &mailer, email).await?;
+    Ok(SendResult::new(delivery, "Email sent."))
 }
 
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn reply_email(
+    app_handle: AppHandle,
     config: AccountConfig,
     to: String,
     original_subject: String,
     body: String,
     cc: Option<String>,
+    bcc: Option<String>,
+    reply_to: Option<String>,
+    in_reply_to: Option<String>, // Message-ID of the email being replied to
+    references: Option<String>,  // References header of the email being replied to
+    custom_headers: Option<Vec<CustomHeader>>,
     attachments: Option<Vec<AttachmentData>>,
-) -> Result<String, String> {
+) -> Result<SendResult, String> {
     println!("Replying to email: {}", to);
 
+    run_hook(
+        PluginHook::PreSend,
+        serde_json::json!({
+            "kind": "reply",
+            "from": config.email,
+            "to": to,
+            "cc": cc,
+            "bcc": bcc,
+            "subject": original_subject,
+            "body": body,
+        }),
+    )
+    .await?;
+
     // Ensure we have a valid access token (refresh if needed)
     let config = ensure_valid_token(config).await?;
 
@@ -214,7 +340,6 @@ pub async fn reply_email(
     } else {
         config.email.parse::<Mailbox>().map_err(|e| e.to_string())?
     };
-    let to_mailbox: Mailbox = to.parse::<Mailbox>().map_err(|e| e.to_string())?;
 
     // Add "Re: " prefix to subject if not already present
     let reply_subject = if original_subject.to_lowercase().starts_with("re:") {
@@ -223,25 +348,23 @@ pub async fn reply_email(
         format!("Re: {}", original_subject)
     };
 
-    let mut email_builder = Message::builder()
-        .from(from)
-        .to(to_mailbox)
-        .subject(reply_subject);
-
-    // Add CC recipients if provided
-    if let Some(cc_str) = cc {
-        if !cc_str.trim().is_empty() {
-            // Split by comma and parse each CC recipient
-            for cc_addr in cc_str.split(',') {
-                let cc_addr = cc_addr.trim();
-                if !cc_addr.is_empty() {
-                    let cc_mailbox: Mailbox =
-                        cc_addr.parse::<Mailbox>().map_err(|e| e.to_string())?;
-                    email_builder = email_builder.cc(cc_mailbox);
-                }
-            }
-        }
+    let mut email_builder = add_addresses(
+        Message::builder().from(from).subject(reply_subject),
+        &to,
+        cc.as_deref(),
+        bcc.as_deref(),
+        reply_to.as_deref(),
+    )?;
+
+    // Thread the reply under the original in other clients
+    if let Some((in_reply_to, references)) =
+        threading_headers(in_reply_to.as_deref(), references.as_deref())
+    {
+        email_builder = email_builder
+            .in_reply_to(in_reply_to)
+            .references(references);
     }
+    let email_builder = add_custom_headers(email_builder, custom_headers.as_deref())?;
 
     // Validate attachment sizes if attachments are present
     if let Some(ref attachment_list) = attachments {
@@ -253,7 +376,7 @@ pub async fn reply_email(
     // Build multipart message if there are attachments
     let email = if let Some(attachment_list) = attachments {
         if !attachment_list.is_empty() {
-            let mut multipart = MultiPart::mixed().singlepart(SinglePart::html(body));
+            let mut multipart = MultiPart::mixed().multipart(html_body(body));
 
             for attachment_data in attachment_list {
                 let attachment_body = Body::new(attachment_data.data);
@@ -268,73 +391,46 @@ pub async fn reply_email(
                 .multipart(multipart)
                 .map_err(|e| e.to_string())?
         } else {
-            // Send as HTML even without attachments
+            // Send as HTML (with a text alternative) even without attachments
             email_builder
-                .multipart(MultiPart::alternative().singlepart(SinglePart::html(body)))
+                .multipart(html_body(body))
                 .map_err(|e| e.to_string())?
         }
     } else {
-        // Send as HTML even without attachments
+        // Send as HTML (with a text alternative) even without attachments
         email_builder
-            .multipart(MultiPart::alternative().singlepart(SinglePart::html(body)))
+            .multipart(html_body(body))
             .map_err(|e| e.to_string())?
     };
 
-    let mailer = match config.auth_type {
-        Some(AuthType::OAuth2) => {
-            let access_token = config
-                .access_token
-                .clone()
-                .ok_or("Access token is required for OAuth2 authentication")?;
-
-            println!(
-                "🔐 Building SMTP transport with XOAUTH2 for {}",
-                config.email
-            );
-            println!("   Server: {}:{}", config.smtp_server, config.smtp_port);
-            println!("   Token length: {} chars", access_token.len());
-
-            let creds = Credentials::new(config.email.clone(), access_token);
-
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)
-                .map_err(|e| e.to_string())?
-                .credentials(creds)
-                .authentication(vec![Mechanism::Xoauth2])
-                .build()
-        }
-        _ => {
-            let password = config
-                .password
-                .clone()
-                .ok_or("Password is required for basic authentication")?;
-
-            let creds = Credentials::new(config.email.clone(), password);
-
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)
-                .map_err(|e| e.to_string())?
-                .credentials(creds)
-                .build()
-        }
-    };
-
-    tokio::spawn(async move {
-        if let Err(e) = mailer.send(email).await {
-            eprintln!("Could not send reply email: {:?}", e);
-        } else {
-            println!("Reply email sent successfully!");
-        }
-    });
+    let mailer = smtp_mailer(&config).await?;
 
-    Ok("Started sending reply email.".into())
+    let delivery = deliver(&app_handle, &config, &mailer, email).await?;
+    Ok(SendResult::new(delivery, "Reply sent."))
 }
 
 #[command]
 pub async fn forward_email(
+    app_handle: AppHandle,
     config: AccountConfig,
     params: ForwardEmailParams,
-) -> Result<String, String> {
+) -> Result<SendResult, String> {
     println!("Forwarding email to: {}", params.to);
 
+    run_hook(
+        PluginHook::PreSend,
+        serde_json::json!({
+            "kind": "forward",
+            "from": config.email,
+            "to": params.to,
+            "cc": params.cc,
+            "bcc": params.bcc,
+            "subject": params.original_subject,
+            "body": params.additional_message,
+        }),
+    )
+    .await?;
+
     // Ensure we have a valid access token (refresh if needed)
     let config = ensure_valid_token(config).await?;
 
@@ -350,7 +446,6 @@ pub async fn forward_email(
     } else {
         config.email.parse::<Mailbox>().map_err(|e| e.to_string())?
     };
-    let to_mailbox: Mailbox = params.to.parse::<Mailbox>().map_err(|e| e.to_string())?;
 
     // Add "Fwd: " prefix to subject if not already present
     let forward_subject = if params.original_subject.to_lowercase().starts_with("fwd:") {
@@ -398,25 +493,14 @@ pub async fn forward_email(
         )
     };
 
-    let mut email_builder = Message::builder()
-        .from(from)
-        .to(to_mailbox)
-        .subject(forward_subject);
-
-    // Add CC recipients if provided
-    if let Some(cc_str) = params.cc {
-        if !cc_str.trim().is_empty() {
-            // Split by comma and parse each CC recipient
-            for cc_addr in cc_str.split(',') {
-                let cc_addr = cc_addr.trim();
-                if !cc_addr.is_empty() {
-                    let cc_mailbox: Mailbox =
-                        cc_addr.parse::<Mailbox>().map_err(|e| e.to_string())?;
-                    email_builder = email_builder.cc(cc_mailbox);
-                }
-            }
-        }
-    }
+    let email_builder = add_addresses(
+        Message::builder().from(from).subject(forward_subject),
+        &params.to,
+        params.cc.as_deref(),
+        params.bcc.as_deref(),
+        params.reply_to.as_deref(),
+    )?;
+    let email_builder = add_custom_headers(email_builder, params.custom_headers.as_deref())?;
 
     // Validate attachment sizes if attachments are present
     if let Some(ref attachment_list) = params.attachments {
@@ -428,7 +512,7 @@ pub async fn forward_email(
     // Build multipart message with attachments if present
     let email = if let Some(attachment_list) = params.attachments {
         if !attachment_list.is_empty() {
-            let mut multipart = MultiPart::mixed().singlepart(SinglePart::html(combined_body));
+            let mut multipart = MultiPart::mixed().multipart(html_body(combined_body));
 
             for attachment_data in attachment_list {
                 let attachment_body = Body::new(attachment_data.data);
@@ -444,64 +528,210 @@ pub async fn forward_email(
                 .map_err(|e| e.to_string())?
         } else {
             email_builder
-                .multipart(MultiPart::alternative().singlepart(SinglePart::html(combined_body)))
+                .multipart(html_body(combined_body))
                 .map_err(|e| e.to_string())?
         }
     } else {
         email_builder
-            .multipart(MultiPart::alternative().singlepart(SinglePart::html(combined_body)))
+            .multipart(html_body(combined_body))
             .map_err(|e| e.to_string())?
     };
 
-    let mailer = match config.auth_type {
-        Some(AuthType::OAuth2) => {
-            let access_token = config
-                .access_token
-                .clone()
-                .ok_or("Access token is required for OAuth2 authentication")?;
+    let mailer = smtp_mailer(&config).await?;
+
+    let delivery = deliver(&app_handle, &config, &mailer, email).await?;
+    Ok(SendResult::new(delivery, "Email forwarded."))
+}
+
+/// Queue a message in the outbox and try to send it right away
+///
+/// Returns whether the server accepted it now; temporary failures leave it queued for the
+/// background retry (see `outbox`), and only permanent rejections are returned as errors.
+/// With undo send enabled the message only goes out once the delay has passed.
+pub(crate) async fn deliver(
+    app_handle: &AppHandle,
+    config: &AccountConfig,
+    mailer: &AsyncSmtpTransport<Tokio1Executor>,
+    email: Message,
+) -> Result<Delivery, String> {
+    outbox::submit(
+        app_handle,
+        config,
+        mailer,
+        email.envelope(),
+        &email.formatted(),
+    )
+    .await
+}
 
+/// Hand a message to the SMTP server once
+///
+/// On failure, returns the SMTP reply code and the recipients the server refused. On success a
+/// copy is saved to the Sent folder in the background (see `sent_copy`).
+pub(crate) async fn deliver_raw(
+    config: &AccountConfig,
+    mailer: &AsyncSmtpTransport<Tokio1Executor>,
+    envelope: &Envelope,
+    raw: &[u8],
+) -> Result<(), SendFailure> {
+    let recipients: Vec<String> = envelope
+        .to()
+        .iter()
+        .map(|address| address.to_string())
+        .collect();
+
+    match mailer.send_raw(envelope, raw).await {
+        Ok(response) => {
             println!(
-                "🔐 Building SMTP transport with XOAUTH2 for {}",
-                config.email
+                "✅ Email accepted by server ({}) for {}",
+                response.code(),
+                recipients.join(", ")
             );
-            println!("   Server: {}:{}", config.smtp_server, config.smtp_port);
-            println!("   Token length: {} chars", access_token.len());
 
-            let creds = Credentials::new(config.email.clone(), access_token);
-
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)
-                .map_err(|e| e.to_string())?
-                .credentials(creds)
-                .authentication(vec![Mechanism::Xoauth2])
-                .build()
+            let config = config.clone();
+            let raw = raw.to_vec();
+            tokio::spawn(async move {
+                if let Some(account_id) = config.id {
+                    if let Err(e) = record_sent_bcc(account_id, &raw, &recipients).await {
+                        eprintln!("⚠️ Failed to record Bcc recipients: {}", e);
+                    }
+                }
+                if let Err(e) = save_sent_copy(config, raw).await {
+                    eprintln!("⚠️ Failed to save sent copy: {}", e);
+                }
+            });
+            Ok(())
         }
-        _ => {
-            let password = config
-                .password
-                .clone()
-                .ok_or("Password is required for basic authentication")?;
-
-            let creds = Credentials::new(config.email.clone(), password);
-
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)
-                .map_err(|e| e.to_string())?
-                .credentials(creds)
-                .build()
+        Err(e) => {
+            // The pooled connection may be what failed; the next attempt starts fresh
+            smtp_pool::forget(config.id);
+
+            let message = e.to_string();
+            let rejected_recipients = rejected_recipients(&message, &recipients);
+
+            let failure = SendFailure {
+                outbox_id: None,
+                to: recipients.join(", "),
+                code: e.status().and_then(|code| code.to_string().parse().ok()),
+                permanent: e.is_permanent(),
+                rejected_recipients,
+                message,
+            };
+            eprintln!("❌ Could not send email: {:?}", failure);
+            Err(failure)
         }
-    };
+    }
+}
 
-    tokio::spawn(async move {
-        if let Err(e) = mailer.send(email).await {
-            eprintln!("Could not forward email: {:?}", e);
-        } else {
-            println!("Email forwarded successfully!");
-        }
-    });
+/// Recipients an SMTP error names as refused
+///
+/// Servers quote the refused address in angle brackets in their RCPT reply, so only exact
+/// `<address>` matches count; an address merely appearing in the text, or as part of a longer
+/// one, doesn't.
+fn rejected_recipients(message: &str, recipients: &[String]) -> Vec<String> {
+    let message = message.to_lowercase();
+    recipients
+        .iter()
+        .filter(|recipient| message.contains(&format!("<{}>", recipient.to_lowercase())))
+        .cloned()
+        .collect()
+}
 
-    Ok("Started forwarding email.".into())
+impl SendFailure {
+    /// User-facing description of the failure
+    pub fn describe(&self) -> String {
+        let mut error = match self.code {
+            Some(code) => format!("Server rejected the email ({}): {}", code, self.message),
+            None => format!("Could not send email: {}", self.message),
+        };
+        if !self.rejected_recipients.is_empty() {
+            error.push_str(&format!(
+                " (rejected recipients: {})",
+                self.rejected_recipients.join(", ")
+            ));
+        }
+        error
+    }
 }
 
 #[command]
 pub fn get_attachment_size_limit(email: String) -> Result<u64, String> {
     Ok(get_limit_for_email(&email))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_custom_header() {
+        let header = |name: &str, value: &str| CustomHeader {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        assert!(validate_custom_header(&header("X-Campaign", "spring")).is_ok());
+        assert!(validate_custom_header(&header("Bcc", "a@example.com")).is_err());
+        assert!(validate_custom_header(&header("X-", "empty name")).is_err());
+        assert!(validate_custom_header(&header("X-Bad Name", "v")).is_err());
+        assert!(validate_custom_header(&header("X-Inject", "v\r\nBcc: a@example.com")).is_err());
+    }
+
+    #[test]
+    fn test_threading_headers() {
+        assert_eq!(threading_headers(None, Some("<a@x>")), None);
+        assert_eq!(
+            threading_headers(Some("c@x"), Some("<a@x> <b@x>")),
+            Some(("<c@x>".to_string(), "<a@x> <b@x> <c@x>".to_string()))
+        );
+
+        let long: Vec<String> = (0..30).map(|i| format!("<{}@x>", i)).collect();
+        let (_, references) = threading_headers(Some("<new@x>"), Some(&long.join(" "))).unwrap();
+        let references: Vec<&str> = references.split(' ').collect();
+        assert_eq!(references.len(), MAX_REFERENCES);
+        assert_eq!(references[0], "<0@x>");
+        assert_eq!(references[1], "<12@x>");
+        assert_eq!(references.last(), Some(&"<new@x>"));
+    }
+
+    #[test]
+    fn test_rejected_recipients() {
+        let recipients = vec!["a@x.com".to_string(), "ba@x.com".to_string()];
+        assert_eq!(
+            rejected_recipients(
+                "permanent error (550): 5.1.1 <BA@x.com>: Recipient address rejected",
+                &recipients
+            ),
+            vec!["ba@x.com".to_string()]
+        );
+        assert!(rejected_recipients("mailbox full for a@x.com", &recipients).is_empty());
+    }
+
+    #[test]
+    fn test_bcc_not_in_headers() {
+        let email = add_addresses(
+            Message::builder()
+                .from("me@example.com".parse().unwrap())
+                .subject("Hi"),
+            "a@example.com, \"Doe, Jane\" <jane@example.com>",
+            Some("c@example.com"),
+            Some("hidden@example.com"),
+            Some("replies@example.com"),
+        )
+        .unwrap()
+        .body(String::from("Hello"))
+        .unwrap();
+
+        let recipients: Vec<String> = email
+            .envelope()
+            .to()
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        assert!(recipients.contains(&"jane@example.com".to_string()));
+        assert!(recipients.contains(&"hidden@example.com".to_string()));
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(!formatted.contains("hidden@example.com"));
+        assert!(formatted.contains("Reply-To: replies@example.com"));
+    }
+}
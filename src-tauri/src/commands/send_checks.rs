@@ -0,0 +1,220 @@
+// Pre-send content checks
+// Looks at a draft before it goes out and reports likely mistakes (missing subject, template
+// placeholders left in, malformed links) so the compose window can ask for confirmation.
+// Only the text is inspected; links are not fetched.
+
+use lettre::Address;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Kind of problem found in a draft
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SendWarningKind {
+    EmptySubject,
+    UnresolvedPlaceholder,
+    BrokenLink,
+}
+
+/// A problem found in a draft
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SendWarning {
+    pub kind: SendWarningKind,
+    pub message: String,
+    pub detail: Option<String>, // The offending placeholder or link
+}
+
+/// Check a draft for common mistakes before sending
+#[command]
+pub fn check_email_before_send(subject: String, body: String) -> Vec<SendWarning> {
+    let mut warnings = Vec::new();
+
+    if subject.trim().is_empty() {
+        warnings.push(SendWarning {
+            kind: SendWarningKind::EmptySubject,
+            message: "The email has no subject".to_string(),
+            detail: None,
+        });
+    }
+
+    for placeholder in find_placeholders(&subject)
+        .into_iter()
+        .chain(find_placeholders(&body))
+    {
+        warnings.push(SendWarning {
+            kind: SendWarningKind::UnresolvedPlaceholder,
+            message: "A template placeholder was not filled in".to_string(),
+            detail: Some(placeholder),
+        });
+    }
+
+    for link in find_links(&body) {
+        if let Some(problem) = link_problem(&link) {
+            warnings.push(SendWarning {
+                kind: SendWarningKind::BrokenLink,
+                message: problem.to_string(),
+                detail: Some(link),
+            });
+        }
+    }
+
+    warnings.dedup();
+    warnings
+}
+
+/// Find `{{...}}` placeholders (at most 64 characters between the braces)
+fn find_placeholders(text: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) if end <= 64 && !after[..end].contains('\n') => {
+                found.push(format!("{{{{{}}}}}", &after[..end]));
+                rest = &after[end + 2..];
+            }
+            _ => rest = after,
+        }
+    }
+
+    found
+}
+
+/// Collect link targets: `href` attribute values and bare http(s)/mailto URLs in text
+fn find_links(body: &str) -> Vec<String> {
+    let mut links = Vec::new();
+
+    let lower = body.to_ascii_lowercase();
+    let mut offset = 0;
+    while let Some(pos) = lower[offset..].find("href=") {
+        let start = offset + pos + "href=".len();
+        let value = &body[start..];
+        let (quote, value) = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => (Some(q), &value[1..]),
+            _ => (None, value),
+        };
+        let end = match quote {
+            Some(q) => value.find(q),
+            None => value.find(|c: char| c.is_whitespace() || c == '>'),
+        }
+        .unwrap_or(value.len());
+        links.push(html_escape::decode_html_entities(value[..end].trim()).to_string());
+        offset = start;
+    }
+
+    // Bare URLs outside of tags
+    for word in strip_tags(body).split_whitespace() {
+        let word = word.trim_matches(|c: char| "<>()[]\"',;".contains(c));
+        let word = word.trim_end_matches(['.', '!', '?', ':']);
+        let lower = word.to_ascii_lowercase();
+        if lower.starts_with("http://")
+            || lower.starts_with("https://")
+            || lower.starts_with("mailto:")
+        {
+            let word = html_escape::decode_html_entities(word).to_string();
+            if !links.contains(&word) {
+                links.push(word);
+            }
+        }
+    }
+
+    links
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                out.push(' ');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Describe what is wrong with a link, or None if it looks fine
+fn link_problem(link: &str) -> Option<&'static str> {
+    let lower = link.to_ascii_lowercase();
+
+    if link.is_empty() || link == "#" {
+        return Some("A link has no target");
+    }
+
+    if let Some(rest) = lower.strip_prefix("mailto:") {
+        let address = rest.split('?').next().unwrap_or_default();
+        let address = urlencoding::decode(address)
+            .map(|decoded| decoded.to_string())
+            .unwrap_or_default();
+        if address.is_empty() {
+            return Some("An email link has no address");
+        }
+        if address
+            .split(',')
+            .any(|address| address.trim().parse::<Address>().is_err())
+        {
+            return Some("An email link has an invalid address");
+        }
+        return None;
+    }
+
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        return match url::Url::parse(link) {
+            Ok(url) => match url.host_str() {
+                Some(host) if host.contains('.') || host == "localhost" => None,
+                _ => Some("A web link has an invalid host"),
+            },
+            Err(_) => Some("A web link is malformed"),
+        };
+    }
+
+    // Typos like "htp://" or "http:/example.com"
+    if lower.starts_with("htp") || lower.starts_with("http:/") || lower.starts_with("https:/") {
+        return Some("A web link is malformed");
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(subject: &str, body: &str) -> Vec<SendWarningKind> {
+        check_email_before_send(subject.to_string(), body.to_string())
+            .into_iter()
+            .map(|warning| warning.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_draft_has_no_warnings() {
+        assert!(kinds(
+            "Hello",
+            r#"<p>See <a href="https://example.com/a?b=1&amp;c=2">this</a> or mail <a href="mailto:a@example.com">me</a>.</p>"#
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_detects_subject_placeholders_and_links() {
+        assert_eq!(
+            kinds(
+                " ",
+                r#"Hi {{first_name}}, <a href="http://">x</a> <a href="mailto:nobody">y</a>"#
+            ),
+            vec![
+                SendWarningKind::EmptySubject,
+                SendWarningKind::UnresolvedPlaceholder,
+                SendWarningKind::BrokenLink,
+                SendWarningKind::BrokenLink,
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+// Sent copies
+// After a message is accepted by SMTP, a copy is appended to the account's Sent folder so it
+// shows up there and on other devices. Gmail and Microsoft 365 already file everything sent
+// through their SMTP servers, so appending there would duplicate every message; those
+// providers are skipped unless the account overrides the behaviour.
+// Bcc recipients only exist in the SMTP envelope, so they are recorded by Message-ID and
+// filled into the cached Sent copy, whichever way it gets there.
+
+use crate::commands::detect_display_name::find_sent_folder;
+use crate::commands::emails::append::{append_messages, AppendMessage};
+use crate::commands::emails::codec::{message_ids, parse_header_block};
+use crate::commands::emails::imap_helpers;
+use crate::commands::folder_names::FolderRole;
+use crate::commands::folders::folder_for_role;
+use crate::commands::reply_all::{bare_address, split_addresses};
+use crate::commands::utils::ensure_valid_token;
+use crate::db;
+use crate::models::AccountConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::command;
+
+/// Whether sent messages are appended to the Sent folder
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SentCopyMode {
+    Auto,   // Append unless the provider files sent mail itself
+    Always, // Append even if the provider files sent mail
+    Never,  // Never append
+}
+
+impl SentCopyMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            SentCopyMode::Auto => "auto",
+            SentCopyMode::Always => "always",
+            SentCopyMode::Never => "never",
+        }
+    }
+
+    fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("always") => SentCopyMode::Always,
+            Some("never") => SentCopyMode::Never,
+            _ => SentCopyMode::Auto,
+        }
+    }
+}
+
+/// Whether a provider saves messages sent through its SMTP server to Sent on its own
+fn provider_saves_sent(imap_server: &str) -> bool {
+    let server = imap_server.to_lowercase();
+    server == "imap.gmail.com"
+        || server == "imap.googlemail.com"
+        || server == "outlook.office365.com"
+        || server.ends_with(".outlook.com")
+}
+
+/// Whether a sent message should be appended for an account
+fn should_append(mode: SentCopyMode, imap_server: &str) -> bool {
+    match mode {
+        SentCopyMode::Always => true,
+        SentCopyMode::Never => false,
+        SentCopyMode::Auto => !provider_saves_sent(imap_server),
+    }
+}
+
+async fn load_mode(account_id: i32) -> Result<SentCopyMode, String> {
+    let pool = db::pool();
+    let value =
+        sqlx::query_as::<_, (Option<String>,)>("SELECT sent_copy_mode FROM accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load Sent copy setting: {}", e))?
+            .ok_or("Account not found")?;
+
+    Ok(SentCopyMode::parse(value.0.as_deref()))
+}
+
+/// Envelope recipients that aren't named in the To or Cc header of a message
+fn bcc_recipients(headers: &[(String, String)], recipients: &[String]) -> Vec<String> {
+    let visible: HashSet<String> = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("to") || name.eq_ignore_ascii_case("cc"))
+        .flat_map(|(_, value)| split_addresses(value))
+        .map(|entry| bare_address(&entry))
+        .collect();
+    recipients
+        .iter()
+        .filter(|recipient| !visible.contains(&recipient.to_lowercase()))
+        .cloned()
+        .collect()
+}
+
+/// Remember the Bcc recipients of a message that was just sent, and fill them into its cached
+/// Sent copy if that is already there
+pub async fn record_sent_bcc(
+    account_id: i32,
+    raw: &[u8],
+    recipients: &[String],
+) -> Result<(), String> {
+    let headers = parse_header_block(raw);
+    let bcc = bcc_recipients(&headers, recipients);
+    let message_id = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("message-id"))
+        .and_then(|(_, value)| message_ids(value).into_iter().next());
+    let (Some(message_id), false) = (message_id, bcc.is_empty()) else {
+        return Ok(());
+    };
+    let bcc = bcc.join(", ");
+
+    let pool = db::pool();
+    sqlx::query(
+        "INSERT INTO sent_bcc (account_id, message_id, bcc_addr, created_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(account_id, message_id) DO UPDATE SET bcc_addr = excluded.bcc_addr",
+    )
+    .bind(account_id)
+    .bind(&message_id)
+    .bind(&bcc)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save Bcc recipients: {}", e))?;
+
+    sqlx::query(
+        "UPDATE emails SET bcc_addr = ?
+         WHERE account_id = ? AND message_id = ? AND COALESCE(bcc_addr, '') = ''",
+    )
+    .bind(&bcc)
+    .bind(account_id)
+    .bind(&message_id)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save Bcc recipients: {}", e))?;
+    Ok(())
+}
+
+/// Append a message that was just sent to the account's Sent folder, if the account wants it
+pub async fn save_sent_copy(config: AccountConfig, raw: Vec<u8>) -> Result<(), String> {
+    let Some(account_id) = config.id else {
+        return Ok(());
+    };
+    if !should_append(load_mode(account_id).await?, &config.imap_server) {
+        return Ok(());
+    }
+
+    let config = ensure_valid_token(config).await?;
+    let known_sent = folder_for_role(account_id, FolderRole::Sent).await?;
+    tokio::task::spawn_blocking(move || {
+        let mut session = imap_helpers::connect_and_login(&config)?;
+        let known_sent = match known_sent {
+            Some(folder) => Some(folder),
+            None => find_sent_folder(&mut session)?,
+        };
+        let Some(folder) = known_sent else {
+            let _ = session.logout();
+            eprintln!("⚠️ No Sent folder found, sent copy not saved");
+            return Ok(());
+        };
+
+        let message = AppendMessage {
+            raw,
+            seen: true,
+            flagged: false,
+            internal_date: None,
+        };
+        let result = append_messages(&mut session, &folder, std::slice::from_ref(&message), false);
+        let _ = session.logout();
+        result?;
+
+        println!("📤 Saved sent copy to '{}'", folder);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Sent copy task failed: {}", e))?
+}
+
+/// Get whether an account appends sent messages to its Sent folder
+#[command]
+pub async fn get_sent_copy_mode(account_id: i32) -> Result<SentCopyMode, String> {
+    load_mode(account_id).await
+}
+
+/// Override whether an account appends sent messages to its Sent folder
+#[command]
+pub async fn set_sent_copy_mode(account_id: i32, mode: SentCopyMode) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query("UPDATE accounts SET sent_copy_mode = ? WHERE id = ?")
+        .bind(mode.as_str())
+        .bind(account_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save Sent copy setting: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bcc_recipients() {
+        let headers = vec![
+            (
+                "To".to_string(),
+                "\"Doe, Jane\" <Jane@example.com>".to_string(),
+            ),
+            ("Cc".to_string(), "ba@example.com".to_string()),
+        ];
+        let recipients = vec![
+            "jane@example.com".to_string(),
+            "ba@example.com".to_string(),
+            "a@example.com".to_string(),
+        ];
+        assert_eq!(
+            bcc_recipients(&headers, &recipients),
+            vec!["a@example.com".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_should_append() {
+        assert!(!should_append(SentCopyMode::Auto, "imap.gmail.com"));
+        assert!(should_append(SentCopyMode::Auto, "imap.fastmail.com"));
+        assert!(should_append(SentCopyMode::Always, "imap.gmail.com"));
+        assert!(!should_append(SentCopyMode::Never, "imap.fastmail.com"));
+    }
+}
@@ -0,0 +1,142 @@
+// SMTP transport pool
+// Each account's SMTP transport is built once and reused, so sends in quick succession
+// (several replies, outbox retries, recurring sends) share pooled connections instead of
+// negotiating TLS and authenticating every time. A transport is rebuilt when the account's
+// server or credentials change or after a failed send, tested with NOOP before it's reused
+// after sitting idle, and dropped once it has gone unused for a few minutes.
+
+use crate::models::{AccountConfig, AuthType};
+use lettre::transport::smtp::authentication::{Credentials, Mechanism};
+use lettre::transport::smtp::PoolConfig;
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Transports unused for this long are dropped, closing their connections
+const IDLE_EXPIRY: Duration = Duration::from_secs(5 * 60);
+/// Transports idle for longer than this are tested before they're reused
+const TEST_AFTER_IDLE: Duration = Duration::from_secs(30);
+/// Open connections kept per account
+const MAX_CONNECTIONS: u32 = 2;
+/// Pooled connections unused for this long are closed (servers drop them around a minute)
+const CONNECTION_IDLE_TIMEOUT: Duration = Duration::from_secs(50);
+
+struct PooledTransport {
+    fingerprint: u64,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    last_used: Instant,
+}
+
+lazy_static::lazy_static! {
+    static ref TRANSPORTS: Mutex<HashMap<i32, PooledTransport>> = Mutex::new(HashMap::new());
+}
+
+/// Identifies the server and credentials a transport was built with
+fn fingerprint(config: &AccountConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    config.smtp_server.to_lowercase().hash(&mut hasher);
+    config.smtp_port.hash(&mut hasher);
+    config.email.hash(&mut hasher);
+    matches!(config.auth_type, Some(AuthType::OAuth2)).hash(&mut hasher);
+    config.password.hash(&mut hasher);
+    config.access_token.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Build a new authenticated SMTP transport for an account whose token is already valid
+async fn build_transport(
+    config: &AccountConfig,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+    let builder = crate::net::smtp_transport(&config.smtp_server, config.smtp_port, config.id)
+        .await?
+        .pool_config(
+            PoolConfig::new()
+                .max_size(MAX_CONNECTIONS)
+                .idle_timeout(CONNECTION_IDLE_TIMEOUT),
+        );
+    Ok(match config.auth_type {
+        Some(AuthType::OAuth2) => {
+            let access_token = config
+                .access_token
+                .clone()
+                .ok_or("Access token is required for OAuth2 authentication")?;
+            println!(
+                "🔐 Building SMTP transport with XOAUTH2 for {}",
+                config.email
+            );
+            builder
+                .credentials(Credentials::new(config.email.clone(), access_token))
+                .authentication(vec![Mechanism::Xoauth2])
+                .build()
+        }
+        _ => {
+            let password = config
+                .password
+                .clone()
+                .ok_or("Password is required for basic authentication")?;
+            builder
+                .credentials(Credentials::new(config.email.clone(), password))
+                .build()
+        }
+    })
+}
+
+/// Get the SMTP transport of an account whose token is already valid, reusing a pooled one
+pub(crate) async fn smtp_mailer(
+    config: &AccountConfig,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+    let Some(account_id) = config.id else {
+        return build_transport(config).await;
+    };
+    let fingerprint = fingerprint(config);
+
+    let cached = {
+        let mut transports = TRANSPORTS.lock().unwrap();
+        transports.retain(|_, pooled| pooled.last_used.elapsed() < IDLE_EXPIRY);
+        transports
+            .get(&account_id)
+            .filter(|pooled| pooled.fingerprint == fingerprint)
+            .map(|pooled| (pooled.transport.clone(), pooled.last_used.elapsed()))
+    };
+
+    if let Some((transport, idle)) = cached {
+        let usable =
+            idle < TEST_AFTER_IDLE || matches!(transport.test_connection().await, Ok(true));
+        if usable {
+            if let Some(pooled) = TRANSPORTS.lock().unwrap().get_mut(&account_id) {
+                pooled.last_used = Instant::now();
+            }
+            return Ok(transport);
+        }
+        println!(
+            "♻️ Pooled SMTP connection for account {} is stale, reconnecting",
+            account_id
+        );
+        crate::net::forget_race_winner(&config.smtp_server, config.smtp_port, config.id);
+    }
+
+    println!(
+        "   Connecting to {}:{}",
+        config.smtp_server, config.smtp_port
+    );
+    let transport = build_transport(config).await?;
+    TRANSPORTS.lock().unwrap().insert(
+        account_id,
+        PooledTransport {
+            fingerprint,
+            transport: transport.clone(),
+            last_used: Instant::now(),
+        },
+    );
+    Ok(transport)
+}
+
+/// Drop an account's pooled transport, e.g. after a failed send, so the next one reconnects
+pub(crate) fn forget(account_id: Option<i32>) {
+    if let Some(account_id) = account_id {
+        TRANSPORTS.lock().unwrap().remove(&account_id);
+    }
+}
@@ -0,0 +1,205 @@
+// Local storage usage
+// Reports how much of the cache each account and folder takes (headers, bodies, attachments),
+// what the search indexes and archived attachment files add on top, and lets the user drop
+// cached bodies or attachments of a folder to reclaim space.
+
+use crate::db;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Cache usage of one folder, in bytes
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
+pub struct FolderStorageUsage {
+    pub account_id: i64,
+    pub account_email: String,
+    pub folder_name: String,
+    pub email_count: i64,
+    pub header_bytes: i64,
+    pub body_count: i64,
+    pub body_bytes: i64,
+    pub attachment_count: i64,
+    pub attachment_bytes: i64,
+}
+
+/// Cache usage of the whole database
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StorageUsage {
+    pub database_bytes: i64,    // Size of the SQLite file, including free pages
+    pub email_index_bytes: i64, // Search index of emails and notes (part of database_bytes)
+    pub attachment_index_bytes: i64, // Extracted attachment text (part of database_bytes)
+    pub archived_attachment_bytes: u64, // Attachment files moved out of the database
+    pub folders: Vec<FolderStorageUsage>,
+}
+
+/// Bytes used by the tables of an FTS5 index (the index and its shadow tables)
+///
+/// Needs SQLite's `dbstat` table; reports 0 where it isn't compiled in.
+async fn index_bytes(pool: &sqlx::SqlitePool, indexes: &[&str]) -> i64 {
+    let mut total = 0;
+    for index in indexes {
+        match sqlx::query_as::<_, (Option<i64>,)>(
+            "SELECT SUM(pgsize) FROM dbstat WHERE name IN (?1 || '_data', ?1 || '_idx',
+                ?1 || '_content', ?1 || '_docsize', ?1 || '_config')",
+        )
+        .bind(index)
+        .fetch_one(pool)
+        .await
+        {
+            Ok((bytes,)) => total += bytes.unwrap_or(0),
+            Err(e) => eprintln!("⚠️ Failed to measure search index {}: {}", index, e),
+        }
+    }
+    total
+}
+
+/// Get cache usage broken down per account and folder, largest folders first
+#[command]
+pub async fn get_storage_usage() -> Result<StorageUsage, String> {
+    let pool = db::pool();
+
+    let (page_count,) = sqlx::query_as::<_, (i64,)>("PRAGMA page_count")
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to read page count: {}", e))?;
+    let (page_size,) = sqlx::query_as::<_, (i64,)>("PRAGMA page_size")
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to read page size: {}", e))?;
+
+    // length() of a BLOB is its size in bytes, so cast text columns to count bytes, not chars
+    let folders = sqlx::query_as::<_, FolderStorageUsage>(
+        "SELECT e.account_id AS account_id,
+            a.email AS account_email,
+            e.folder_name AS folder_name,
+            COUNT(*) AS email_count,
+            COALESCE(SUM(
+                length(CAST(e.subject AS BLOB)) + length(CAST(e.from_addr AS BLOB))
+                + length(CAST(e.to_addr AS BLOB)) + COALESCE(length(CAST(e.cc_addr AS BLOB)), 0)
+                + length(CAST(e.date AS BLOB)) + COALESCE(length(CAST(e.flags AS BLOB)), 0)
+                + COALESCE(length(CAST(e.message_id AS BLOB)), 0)
+            ), 0) AS header_bytes,
+            COUNT(e.body) AS body_count,
+            COALESCE(SUM(length(CAST(e.body AS BLOB))), 0) AS body_bytes,
+            COALESCE(SUM(att.attachment_count), 0) AS attachment_count,
+            COALESCE(SUM(att.attachment_bytes), 0) AS attachment_bytes
+         FROM emails e
+         JOIN accounts a ON a.id = e.account_id
+         LEFT JOIN (
+            SELECT email_id, COUNT(*) AS attachment_count, SUM(length(data)) AS attachment_bytes
+            FROM attachments GROUP BY email_id
+         ) att ON att.email_id = e.id
+         GROUP BY e.account_id, e.folder_name
+         ORDER BY header_bytes + body_bytes + attachment_bytes DESC",
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to compute storage usage: {}", e))?;
+
+    Ok(StorageUsage {
+        database_bytes: page_count * page_size,
+        email_index_bytes: index_bytes(pool.as_ref(), &["emails_fts", "notes_fts"]).await,
+        attachment_index_bytes: index_bytes(pool.as_ref(), &["attachment_fts"]).await,
+        archived_attachment_bytes: crate::commands::attachment_archive::archived_bytes().await?,
+        folders,
+    })
+}
+
+/// Cached bodies of local-only folders are the only copy, so they can't be cleared
+async fn ensure_remote_folder(account_id: i32, folder_name: &str) -> Result<(), String> {
+    let pool = db::pool();
+
+    let is_local = sqlx::query_as::<_, (i64,)>(
+        "SELECT COALESCE(is_local, 0) FROM folders WHERE account_id = ? AND name = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to look up folder: {}", e))?
+    .map(|(is_local,)| is_local != 0)
+    .unwrap_or(false);
+
+    if is_local {
+        return Err(
+            "This is a local folder; its cached emails are the only copy and can't be cleared"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Drop cached bodies (and their attachments) of a folder; they are fetched again when opened
+#[command]
+pub async fn clear_folder_bodies(account_id: i32, folder_name: String) -> Result<u64, String> {
+    ensure_remote_folder(account_id, &folder_name).await?;
+    let pool = db::pool();
+
+    // Attachments are cached together with the body, so they go too
+    sqlx::query(
+        "DELETE FROM attachments WHERE email_id IN
+            (SELECT id FROM emails WHERE account_id = ? AND folder_name = ?)",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to clear cached attachments: {}", e))?;
+    // The trigger queued archived files of the deleted rows; take them off the disk too
+    crate::commands::attachment_archive::remove_deleted_files().await?;
+
+    let result = sqlx::query(
+        "UPDATE emails SET body = NULL, content_hash = NULL
+         WHERE account_id = ? AND folder_name = ? AND body IS NOT NULL",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to clear cached bodies: {}", e))?;
+
+    println!(
+        "🧹 Cleared {} cached bodies in folder '{}'",
+        result.rows_affected(),
+        folder_name
+    );
+    Ok(result.rows_affected())
+}
+
+/// Drop cached attachments of a folder; affected emails are fetched again when opened
+#[command]
+pub async fn clear_folder_attachments(account_id: i32, folder_name: String) -> Result<u64, String> {
+    ensure_remote_folder(account_id, &folder_name).await?;
+    let pool = db::pool();
+
+    // The body cache is what decides whether an email is fetched again, so clear the bodies
+    // of emails that had attachments; otherwise their attachments would never come back
+    sqlx::query(
+        "UPDATE emails SET body = NULL, content_hash = NULL
+         WHERE account_id = ? AND folder_name = ?
+            AND id IN (SELECT email_id FROM attachments)",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to clear bodies of emails with attachments: {}", e))?;
+
+    let result = sqlx::query(
+        "DELETE FROM attachments WHERE email_id IN
+            (SELECT id FROM emails WHERE account_id = ? AND folder_name = ?)",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to clear cached attachments: {}", e))?;
+    // The trigger queued archived files of the deleted rows; take them off the disk too
+    crate::commands::attachment_archive::remove_deleted_files().await?;
+
+    println!(
+        "🧹 Cleared {} cached attachments in folder '{}'",
+        result.rows_affected(),
+        folder_name
+    );
+    Ok(result.rows_affected())
+}
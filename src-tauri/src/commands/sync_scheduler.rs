@@ -0,0 +1,240 @@
+// Background sync scheduler
+// Syncs every account's folders on their own schedule without the frontend having to poll.
+// A folder's interval comes from its own setting, else its account's, else the global
+// `sync_interval` (seconds; 0 = manual only, -1 = never). Passes run one after another, so a
+// slow pass delays the next one instead of overlapping it, and folders an IDLE-triggered sync
+// is already working on are skipped. `sync-progress` events report each folder's sync.
+
+use crate::commands::account_health;
+use crate::commands::accounts::load_account_configs;
+use crate::commands::emails::{get_sync_interval, should_sync, sync_emails};
+use crate::commands::folders::load_folders;
+use crate::db;
+use crate::models::AccountConfig;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+/// How often folders are checked for being due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where a scheduled folder sync is at
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPhase {
+    Started,
+    Finished,
+    Failed,
+}
+
+/// Emitted as `sync-progress` for every scheduled folder sync
+#[derive(Serialize, Debug, Clone)]
+pub struct SyncProgress {
+    pub account_id: i32,
+    pub folder_name: String,
+    pub phase: SyncPhase,
+    /// Position of this folder among the folders due in this pass (1-based) and their count
+    pub position: usize,
+    pub total: usize,
+    pub error: Option<String>,
+}
+
+fn validate_interval(interval: Option<i64>) -> Result<(), String> {
+    match interval {
+        Some(interval) if interval < -1 => {
+            Err("Sync interval must be -1 (never), 0 (manual) or a number of seconds".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The account's own interval and its folders' intervals, where set
+async fn load_intervals(account_id: i32) -> Result<(Option<i64>, HashMap<String, i64>), String> {
+    let pool = db::pool();
+
+    let account_interval =
+        sqlx::query_as::<_, (Option<i64>,)>("SELECT sync_interval FROM accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load account sync interval: {}", e))?
+            .and_then(|row| row.0);
+
+    let folder_intervals = sqlx::query_as::<_, (String, i64)>(
+        "SELECT folder_name, sync_interval FROM folder_sync_settings
+         WHERE account_id = ? AND sync_interval IS NOT NULL",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load folder sync intervals: {}", e))?
+    .into_iter()
+    .collect();
+
+    Ok((account_interval, folder_intervals))
+}
+
+/// Folders of an account that are due for a sync
+async fn due_folders(account_id: i32, global_interval: i64) -> Result<Vec<String>, String> {
+    let (account_interval, folder_intervals) = load_intervals(account_id).await?;
+    let mut due = Vec::new();
+
+    for folder in load_folders(account_id).await? {
+        if folder.is_local || folder.sync_on_open || !folder.is_selectable() {
+            continue;
+        }
+        let interval = folder_intervals
+            .get(&folder.name)
+            .copied()
+            .or(account_interval)
+            .unwrap_or(global_interval);
+        if should_sync(account_id, Some(folder.name.clone()), interval).await? {
+            due.push(folder.name);
+        }
+    }
+
+    Ok(due)
+}
+
+/// Sync an account's due folders, reporting progress
+async fn sync_account(app_handle: &AppHandle, config: AccountConfig, global_interval: i64) {
+    let Some(account_id) = config.id else {
+        return;
+    };
+
+    let folders = match due_folders(account_id, global_interval).await {
+        Ok(folders) => folders,
+        Err(e) => {
+            tracing::warn!(account_id = account_id, error = %e, "Failed to find folders to sync");
+            return;
+        }
+    };
+
+    let total = folders.len();
+    for (index, folder_name) in folders.into_iter().enumerate() {
+        if crate::shutdown::is_shutting_down() || account_health::needs_attention(account_id) {
+            break;
+        }
+        if crate::idle_manager::is_syncing(account_id, &folder_name) {
+            continue;
+        }
+
+        let progress = |phase, error| SyncProgress {
+            account_id,
+            folder_name: folder_name.clone(),
+            phase,
+            position: index + 1,
+            total,
+            error,
+        };
+        let _ = app_handle.emit("sync-progress", progress(SyncPhase::Started, None));
+
+        let result = sync_emails(
+            app_handle.clone(),
+            config.clone(),
+            Some(folder_name.clone()),
+            Some(true),
+        )
+        .await;
+
+        let event = match result {
+            Ok(_) => progress(SyncPhase::Finished, None),
+            Err(e) => {
+                tracing::warn!(
+                    account_id = account_id,
+                    folder = %folder_name,
+                    error = %e,
+                    "Scheduled sync failed"
+                );
+                progress(SyncPhase::Failed, Some(e))
+            }
+        };
+        let _ = app_handle.emit("sync-progress", event);
+    }
+}
+
+/// Run one scheduler pass over every account
+async fn run_pass(app_handle: &AppHandle) -> Result<(), String> {
+    let global_interval = get_sync_interval().await?;
+
+    for config in load_account_configs().await? {
+        if crate::commands::demo::is_demo_account(&config) {
+            continue;
+        }
+        let Some(account_id) = config.id else {
+            continue;
+        };
+        if account_health::needs_attention(account_id) {
+            continue;
+        }
+        sync_account(app_handle, config, global_interval).await;
+    }
+
+    Ok(())
+}
+
+/// Background loop: sync folders as they come due
+pub async fn scheduler_loop(app_handle: AppHandle) {
+    let shutdown_token = crate::shutdown::token();
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+
+        if let Err(e) = run_pass(&app_handle).await {
+            tracing::warn!(error = %e, "Scheduled sync pass failed");
+        }
+    }
+}
+
+/// Override the sync interval of an account (None uses the global interval)
+#[command]
+pub async fn set_account_sync_interval(
+    account_id: i32,
+    interval: Option<i64>,
+) -> Result<(), String> {
+    validate_interval(interval)?;
+    let pool = db::pool();
+    sqlx::query("UPDATE accounts SET sync_interval = ? WHERE id = ?")
+        .bind(interval)
+        .bind(account_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save account sync interval: {}", e))?;
+
+    println!(
+        "⚙️ Sync interval for account {}: {:?}",
+        account_id, interval
+    );
+    Ok(())
+}
+
+/// Override the sync interval of a folder (None uses the account's interval)
+#[command]
+pub async fn set_folder_sync_interval(
+    account_id: i32,
+    folder_name: String,
+    interval: Option<i64>,
+) -> Result<(), String> {
+    validate_interval(interval)?;
+    let pool = db::pool();
+    sqlx::query(
+        "INSERT INTO folder_sync_settings (account_id, folder_name, sync_interval) VALUES (?, ?, ?)
+         ON CONFLICT(account_id, folder_name) DO UPDATE SET sync_interval = excluded.sync_interval",
+    )
+    .bind(account_id)
+    .bind(&folder_name)
+    .bind(interval)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to save folder sync interval: {}", e))?;
+
+    println!(
+        "⚙️ Sync interval for folder '{}': {:?}",
+        folder_name, interval
+    );
+    Ok(())
+}
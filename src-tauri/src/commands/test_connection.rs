@@ -39,8 +39,7 @@ fn test_imap_connection(config: &AccountConfig) -> Result<(), String> {
 
     tracing::info!(server = %domain, port = port, "Connecting to IMAP server");
 
-    let client = imap::ClientBuilder::new(domain, port)
-        .connect()
+    let client = crate::net::connect_imap(domain, port, config.id)
         .map_err(|e| format!("Failed to connect to IMAP server: {}", e))?;
 
     // Attempt login
@@ -76,25 +75,12 @@ async fn test_smtp_connection(config: &AccountConfig) -> Result<(), String> {
 
     // Choose connection method based on port:
     // - Port 465: SSL/TLS (implicit TLS, used by 163.com, QQ, etc.)
-    // - Port 587: STARTTLS (explicit TLS, used by Gmail, Outlook, etc.)
-    // - Port 25: Plain or STARTTLS (legacy, rarely used)
-    let mailer: AsyncSmtpTransport<Tokio1Executor> = if config.smtp_port == 465 {
-        // Port 465 requires SSL/TLS direct connection (implicit TLS)
-        tracing::debug!("Using SSL/TLS (implicit TLS) for port 465");
-        AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_server)
-            .map_err(|e| format!("Failed to create SMTP transport: {}", e))?
+    // - Port 587 and others: STARTTLS (explicit TLS, used by Gmail, Outlook, etc.)
+    let mailer: AsyncSmtpTransport<Tokio1Executor> =
+        crate::net::smtp_transport(&config.smtp_server, config.smtp_port, config.id)
+            .await?
             .credentials(creds)
-            .port(config.smtp_port)
-            .build()
-    } else {
-        // Port 587 or others use STARTTLS
-        tracing::debug!(port = config.smtp_port, "Using STARTTLS");
-        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)
-            .map_err(|e| format!("Failed to create SMTP transport: {}", e))?
-            .credentials(creds)
-            .port(config.smtp_port)
-            .build()
-    };
+            .build();
 
     // Test connection
     mailer
@@ -0,0 +1,171 @@
+// OAuth refresh-token expiry
+// Some providers limit how long a refresh token lives (Microsoft: 90 days, rotated on every
+// refresh). The time each account's refresh token was issued is recorded on sign-in and on
+// rotation, and a background check emits `reauth-recommended` a week before the predicted
+// expiry. `start_reauthorization` restarts the OAuth flow for an existing account; completing
+// it updates the same account row, so its ID, cache and settings are kept.
+
+use crate::commands::event_buffer::emit_buffered;
+use crate::commands::oauth2::start_oauth2_flow;
+use crate::db;
+use crate::models::{OAuth2StartRequest, OAuth2StartResponse};
+use crate::oauth2_config::OAuth2Provider;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{command, AppHandle};
+
+/// Warn this many days before a refresh token is predicted to expire
+const WARN_BEFORE_DAYS: i64 = 7;
+
+/// Delay before the first check after startup
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(2 * 60);
+
+/// Interval between expiry checks
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+lazy_static::lazy_static! {
+    /// (account id, issued_at) pairs already warned about, so each token is announced once
+    static ref WARNED: Mutex<HashSet<(i32, i64)>> = Mutex::new(HashSet::new());
+}
+
+/// Predicted lifetime of an account's refresh token
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenExpiry {
+    pub account_id: i32,
+    pub email: String,
+    pub provider: String,
+    pub issued_at: Option<i64>, // Unknown for tokens issued before tracking started
+    pub expires_at: Option<i64>, // None if the provider has no fixed lifetime
+    pub days_left: Option<i64>,
+}
+
+/// Remember that an account just received a new refresh token
+pub(crate) async fn record_refresh_token_issued(account_id: i32) -> Result<(), String> {
+    let pool = db::pool();
+    sqlx::query(
+        "INSERT OR REPLACE INTO oauth_tokens (account_id, refresh_token_issued_at) VALUES (?, ?)",
+    )
+    .bind(account_id)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to record token issue time: {}", e))?;
+    Ok(())
+}
+
+/// Expiry predictions of OAuth accounts, optionally only one
+async fn load_expiries(account_id: Option<i32>) -> Result<Vec<TokenExpiry>, String> {
+    let pool = db::pool();
+    let rows = sqlx::query_as::<_, (i32, String, String, Option<i64>)>(
+        "SELECT a.id, a.email, a.imap_server, t.refresh_token_issued_at
+         FROM accounts a
+         LEFT JOIN oauth_tokens t ON t.account_id = a.id
+         WHERE a.auth_type = 'oauth2' AND (?1 IS NULL OR a.id = ?1)",
+    )
+    .bind(account_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load OAuth accounts: {}", e))?;
+
+    let now = chrono::Utc::now().timestamp();
+    Ok(rows
+        .into_iter()
+        .filter_map(|(account_id, email, imap_server, issued_at)| {
+            let provider = OAuth2Provider::for_imap_server(&imap_server)?;
+            let expires_at = issued_at
+                .zip(provider.refresh_token_lifetime_days)
+                .map(|(issued_at, days)| issued_at + days * DAY_SECS);
+            Some(TokenExpiry {
+                account_id,
+                email,
+                provider: provider.name,
+                issued_at,
+                expires_at,
+                days_left: expires_at.map(|expires_at| (expires_at - now).div_euclid(DAY_SECS)),
+            })
+        })
+        .collect())
+}
+
+/// Get the predicted refresh-token expiry of an account (None for non-OAuth accounts)
+#[command]
+pub async fn get_token_expiry(account_id: i32) -> Result<Option<TokenExpiry>, String> {
+    Ok(load_expiries(Some(account_id)).await?.into_iter().next())
+}
+
+/// Emit `reauth-recommended` for accounts whose refresh token expires soon
+async fn check_expiries(app_handle: &AppHandle) -> Result<(), String> {
+    for expiry in load_expiries(None).await? {
+        let (Some(issued_at), Some(days_left)) = (expiry.issued_at, expiry.days_left) else {
+            continue;
+        };
+        if days_left > WARN_BEFORE_DAYS {
+            continue;
+        }
+        let first_warning = WARNED
+            .lock()
+            .map(|mut warned| warned.insert((expiry.account_id, issued_at)))
+            .unwrap_or(false);
+        if first_warning {
+            tracing::info!(
+                account_id = expiry.account_id,
+                days_left = days_left,
+                "Refresh token expires soon, recommending re-authentication"
+            );
+            emit_buffered(app_handle, "reauth-recommended", &expiry);
+        }
+    }
+    Ok(())
+}
+
+/// Periodically check refresh-token expiry until shutdown
+pub async fn expiry_check_loop(app_handle: AppHandle) {
+    let shutdown_token = crate::shutdown::token();
+    let mut delay = FIRST_CHECK_DELAY;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+
+        if let Err(e) = check_expiries(&app_handle).await {
+            tracing::warn!(error = %e, "Token expiry check failed");
+        }
+        delay = CHECK_INTERVAL;
+    }
+}
+
+/// Start a new OAuth sign-in for an existing account
+///
+/// Finish it with `listen_for_oauth_callback` and `complete_oauth2_flow` as for a new
+/// account; the account is matched by email and updated in place.
+#[command]
+pub async fn start_reauthorization(account_id: i32) -> Result<OAuth2StartResponse, String> {
+    let pool = db::pool();
+    let (email, imap_server, auth_type) = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT email, imap_server, auth_type FROM accounts WHERE id = ?",
+    )
+    .bind(account_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to load account: {}", e))?
+    .ok_or("Account not found")?;
+
+    if auth_type != "oauth2" {
+        return Err("Account does not use OAuth sign-in".to_string());
+    }
+    let provider = OAuth2Provider::for_imap_server(&imap_server)
+        .ok_or_else(|| format!("No OAuth provider known for {}", imap_server))?;
+
+    println!("🔑 Restarting OAuth sign-in for {}", email);
+    start_oauth2_flow(OAuth2StartRequest {
+        provider: provider.name,
+        email,
+    })
+    .await
+}
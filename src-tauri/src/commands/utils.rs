@@ -11,7 +11,8 @@ pub async fn ensure_valid_token(mut config: AccountConfig) -> Result<AccountConf
 
     // IMPORTANT: Reload token info from keyring to get the latest credentials
     // This is necessary because the config passed from frontend may be stale
-    let creds = security::get_credentials(&config.email)?;
+    let namespace = security::namespace_for_config(config.id, &config.email).await?;
+    let creds = security::get_credentials(&namespace, &config.email)?;
 
     config.access_token = creds.access_token;
     config.refresh_token = creds.refresh_token;
@@ -52,11 +53,7 @@ pub async fn ensure_valid_token(mut config: AccountConfig) -> Result<AccountConf
         .ok_or("No refresh token available")?;
 
     // Determine provider based on IMAP server
-    let provider_name = if config.imap_server.contains("gmail") {
-        "google"
-    } else if config.imap_server.contains("outlook") || config.imap_server.contains("office365") {
-        "outlook"
-    } else {
+    let Some(provider) = OAuth2Provider::for_imap_server(&config.imap_server) else {
         // For unknown providers (e.g., GMX), skip token refresh
         // The existing token will be used as-is
         println!(
@@ -66,19 +63,26 @@ pub async fn ensure_valid_token(mut config: AccountConfig) -> Result<AccountConf
         return Ok(config);
     };
 
-    let provider = OAuth2Provider::get_provider(provider_name)?;
-
     // Refresh the token
-    let (new_access_token, new_expires_at) = provider.refresh_access_token(refresh_token).await?;
+    let (new_access_token, new_refresh_token, new_expires_at) = provider
+        .refresh_access_token(refresh_token, config.id)
+        .await?;
 
     println!("✓ Access token refreshed successfully");
 
+    // Keep a rotated refresh token; the old one may stop working
+    let rotated = new_refresh_token.is_some();
+    if let Some(new_refresh_token) = new_refresh_token {
+        config.refresh_token = Some(new_refresh_token);
+    }
+
     // Update config with new token
     config.access_token = Some(new_access_token.clone());
     config.token_expires_at = new_expires_at;
 
     // Update keyring with new token
     security::update_credentials(
+        &namespace,
         &config.email,
         None, // Don't change password
         Some(new_access_token),
@@ -88,5 +92,12 @@ pub async fn ensure_valid_token(mut config: AccountConfig) -> Result<AccountConf
 
     println!("✓ Token updated in keyring");
 
+    if let (true, Some(account_id)) = (rotated, config.id) {
+        if let Err(e) = crate::commands::token_expiry::record_refresh_token_issued(account_id).await
+        {
+            eprintln!("⚠️ {}", e);
+        }
+    }
+
     Ok(config)
 }
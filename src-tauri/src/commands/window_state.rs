@@ -0,0 +1,149 @@
+// Window state persistence
+// The main window's size, position and maximized state, plus the last selected account and
+// folder, are kept in the settings table so a restart returns to where the user left off even
+// if the webview's localStorage is cleared. Geometry is saved when the window is closed and
+// applied during setup; the selection is saved by the frontend whenever it changes.
+
+use crate::db;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+const SETTING_KEY: &str = "window_state";
+
+/// Saved main window geometry and selection
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WindowState {
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub x: Option<i32>,
+    #[serde(default)]
+    pub y: Option<i32>,
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub last_account_id: Option<i32>,
+    #[serde(default)]
+    pub last_folder_name: Option<String>,
+}
+
+async fn load_state() -> Result<WindowState, String> {
+    let pool = db::pool();
+    let value = sqlx::query_as::<_, (String,)>("SELECT value FROM settings WHERE key = ?")
+        .bind(SETTING_KEY)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to load window state: {}", e))?;
+
+    Ok(value
+        .and_then(|(value,)| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+async fn store_state(state: &WindowState) -> Result<(), String> {
+    let value = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    let pool = db::pool();
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(SETTING_KEY)
+        .bind(value)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save window state: {}", e))?;
+    Ok(())
+}
+
+/// Record the window's current geometry
+///
+/// While maximized only the flag is updated, so un-maximizing after a restart goes back to
+/// the last normal size. A minimized window reports bogus coordinates and is skipped.
+pub async fn capture(window: &WebviewWindow) -> Result<(), String> {
+    if window.is_minimized().unwrap_or(false) {
+        return Ok(());
+    }
+
+    let mut state = load_state().await?;
+    state.maximized = window.is_maximized().unwrap_or(false);
+    if !state.maximized {
+        if let Ok(size) = window.outer_size() {
+            state.width = Some(size.width);
+            state.height = Some(size.height);
+        }
+        if let Ok(position) = window.outer_position() {
+            state.x = Some(position.x);
+            state.y = Some(position.y);
+        }
+    }
+    store_state(&state).await
+}
+
+/// Apply saved geometry to a window
+///
+/// The position is only restored if it still lies on a connected monitor, so a window saved
+/// on a since-disconnected display doesn't open off-screen.
+pub async fn apply(window: &WebviewWindow) -> Result<(), String> {
+    let state = load_state().await?;
+
+    if let (Some(width), Some(height)) = (state.width, state.height) {
+        let _ = window.set_size(PhysicalSize::new(width.max(400), height.max(300)));
+    }
+    if let (Some(x), Some(y)) = (state.x, state.y) {
+        let on_screen = window
+            .available_monitors()
+            .unwrap_or_default()
+            .iter()
+            .any(|monitor| {
+                let origin = monitor.position();
+                let size = monitor.size();
+                x >= origin.x
+                    && y >= origin.y
+                    && x < origin.x + size.width as i32
+                    && y < origin.y + size.height as i32
+            });
+        if on_screen {
+            let _ = window.set_position(PhysicalPosition::new(x, y));
+        }
+    }
+    if state.maximized {
+        let _ = window.maximize();
+    }
+    Ok(())
+}
+
+/// Get the saved window state and last selection
+#[command]
+pub async fn get_window_state() -> Result<WindowState, String> {
+    load_state().await
+}
+
+/// Save the main window's current geometry
+#[command]
+pub async fn save_window_state(app_handle: AppHandle) -> Result<(), String> {
+    let window = app_handle
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    capture(&window).await
+}
+
+/// Re-apply the saved geometry to the main window and return the saved state
+#[command]
+pub async fn restore_window_state(app_handle: AppHandle) -> Result<WindowState, String> {
+    let window = app_handle
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    apply(&window).await?;
+    load_state().await
+}
+
+/// Remember the selected account and folder
+#[command]
+pub async fn save_last_selection(
+    account_id: Option<i32>,
+    folder_name: Option<String>,
+) -> Result<(), String> {
+    let mut state = load_state().await?;
+    state.last_account_id = account_id;
+    state.last_folder_name = folder_name;
+    store_state(&state).await
+}
@@ -0,0 +1,187 @@
+// Secure wipe of all local app data
+// Removes stored credentials, the database, log files, archived attachments and the app's data
+// directories (Maildir store, plugins, imported sounds) so nothing is left behind on a shared machine. The app
+// restarts with a fresh, empty profile afterwards.
+
+use crate::commands::audit;
+use crate::commands::auth::AUTH_SERVICE_NAME;
+use crate::commands::authorization::{authorize, Sensitivity};
+use crate::{db, logger, security, shutdown};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{command, AppHandle};
+
+/// Summary of a wipe run
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WipeReport {
+    pub credentials_cleared: usize,
+    pub files_deleted: usize,
+    pub errors: Vec<String>, // Non-fatal failures (e.g. a log file still held open)
+}
+
+/// Wipe all local data and restart the app
+///
+/// `secure_storage_keys` are extra app-auth keyring entries (e.g. the sign-in session) to clear.
+/// With `overwrite`, files are overwritten with zeros before deletion. This only helps on
+/// filesystems that write in place; SSDs and copy-on-write filesystems may keep old blocks.
+#[command]
+pub async fn wipe_all_data(
+    app_handle: AppHandle,
+    overwrite: Option<bool>,
+    secure_storage_keys: Option<Vec<String>>,
+) -> Result<WipeReport, String> {
+    authorize("wipe_all_data", Sensitivity::HighRisk).await?;
+
+    let overwrite = overwrite.unwrap_or(false);
+    let mut report = WipeReport {
+        credentials_cleared: 0,
+        files_deleted: 0,
+        errors: Vec::new(),
+    };
+
+    audit::record(
+        "wipe_all_data",
+        None,
+        if overwrite { "overwrite" } else { "delete" },
+    )
+    .await;
+
+    // 1. Credentials: must be read from the database before it goes away
+    let pool = db::pool();
+    let accounts =
+        sqlx::query_as::<_, (String, Option<String>)>("SELECT email, credential_ns FROM accounts")
+            .fetch_all(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load accounts: {}", e))?;
+    drop(pool);
+
+    // The archive directory is chosen by the user, so only the files the app wrote are removed
+    let archived = crate::commands::attachment_archive::archived_files()
+        .await
+        .unwrap_or_else(|e| {
+            report.errors.push(e);
+            Vec::new()
+        });
+
+    for (email, namespace) in accounts {
+        match security::delete_credentials(&namespace.unwrap_or_default(), &email) {
+            Ok(()) => report.credentials_cleared += 1,
+            Err(e) => report
+                .errors
+                .push(format!("Failed to clear credentials for {}: {}", email, e)),
+        }
+    }
+
+    for key in secure_storage_keys.unwrap_or_default() {
+        if let Ok(entry) = Entry::new(AUTH_SERVICE_NAME, &key) {
+            if entry.delete_credential().is_ok() {
+                report.credentials_cleared += 1;
+            }
+        }
+    }
+
+    // 2. Stop background work and close the database so its files can be removed
+    shutdown::shutdown(Duration::from_secs(5)).await;
+
+    let db_path = db::db_path();
+    let mut files = vec![db_path.clone()];
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut name = db_path.as_os_str().to_owned();
+        name.push(suffix);
+        files.push(PathBuf::from(name));
+    }
+
+    // 3. Log files
+    match logger::get_log_dir() {
+        Ok(log_dir) => {
+            if let Ok(entries) = fs::read_dir(&log_dir) {
+                files.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        }
+        Err(e) => report.errors.push(e),
+    }
+
+    // 4. Data directories next to the database
+    let dirs = [
+        crate::commands::emails::maildir::maildir_root(),
+        crate::commands::plugins::plugins_dir(),
+        crate::commands::notification_sounds::sounds_dir(),
+    ];
+    for dir in &dirs {
+        files_under(dir, &mut files);
+    }
+    files.extend(archived);
+
+    for path in files.iter().filter(|path| path.is_file()) {
+        match wipe_file(path, overwrite) {
+            Ok(()) => report.files_deleted += 1,
+            Err(e) => report.errors.push(e),
+        }
+    }
+    for dir in dirs.iter().filter(|dir| dir.exists()) {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            report
+                .errors
+                .push(format!("Failed to delete {}: {}", dir.display(), e));
+        }
+    }
+
+    println!(
+        "🧹 Wiped local data: {} credential set(s), {} file(s), {} error(s)",
+        report.credentials_cleared,
+        report.files_deleted,
+        report.errors.len()
+    );
+
+    // Restart once the result has reached the frontend
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        app_handle.restart();
+    });
+
+    Ok(report)
+}
+
+/// Collect every file below a directory
+fn files_under(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            files_under(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Delete a file, optionally overwriting its contents with zeros first
+fn wipe_file(path: &Path, overwrite: bool) -> Result<(), String> {
+    if overwrite {
+        let len = fs::metadata(path)
+            .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?
+            .len();
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+        let zeros = vec![0u8; 64 * 1024];
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..chunk])
+                .map_err(|e| format!("Failed to overwrite {}: {}", path.display(), e))?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all()
+            .map_err(|e| format!("Failed to flush {}: {}", path.display(), e))?;
+    }
+
+    fs::remove_file(path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+}
@@ -1,16 +1,22 @@
 use directories::ProjectDirs;
 use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use std::path::PathBuf;
 use std::sync::{Arc, OnceLock};
 
 static POOL: OnceLock<Arc<SqlitePool>> = OnceLock::new();
 
-/// Initialize the database connection pool and schema.
-pub async fn init() -> Result<(), sqlx::Error> {
+/// Path of the SQLite database file (creates the data directory if needed)
+pub fn db_path() -> PathBuf {
     let proj_dirs = ProjectDirs::from("com", "Colimail", "Colimail")
         .expect("Failed to determine project directories");
     let data_dir = proj_dirs.data_dir();
     std::fs::create_dir_all(data_dir).expect("Failed to create data directory");
-    let db_path = data_dir.join("colimail.db");
+    data_dir.join("colimail.db")
+}
+
+/// Initialize the database connection pool and schema.
+pub async fn init() -> Result<(), sqlx::Error> {
+    let db_path = db_path();
 
     println!("Database path: {}", db_path.display());
 
@@ -79,6 +85,24 @@ pub async fn init() -> Result<(), sqlx::Error> {
         .execute(&pool)
         .await;
 
+    // Migration: Add cached STATUS counts to folders table (filled from LIST-STATUS)
+    let _ = sqlx::query("ALTER TABLE folders ADD COLUMN total_count INTEGER")
+        .execute(&pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE folders ADD COLUMN unread_count INTEGER")
+        .execute(&pool)
+        .await;
+
+    // Migration: Add inaccessible column to folders table (set when SELECT fails on first use)
+    let _ = sqlx::query("ALTER TABLE folders ADD COLUMN inaccessible INTEGER DEFAULT 0")
+        .execute(&pool)
+        .await;
+
+    // Migration: Special-use role of the folder (trash, sent, junk, ...), from SPECIAL-USE/XLIST
+    let _ = sqlx::query("ALTER TABLE folders ADD COLUMN role TEXT")
+        .execute(&pool)
+        .await;
+
     // Create emails cache table with all columns included
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS emails (
@@ -109,6 +133,11 @@ pub async fn init() -> Result<(), sqlx::Error> {
         .execute(&pool)
         .await;
 
+    // Migration: Add bcc_addr column to emails table for BCC recipients of sent mail
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN bcc_addr TEXT")
+        .execute(&pool)
+        .await;
+
     // Migration: Add has_attachments column to emails table if it doesn't exist (for existing tables)
     let _ = sqlx::query("ALTER TABLE emails ADD COLUMN has_attachments INTEGER DEFAULT 0")
         .execute(&pool)
@@ -129,6 +158,55 @@ pub async fn init() -> Result<(), sqlx::Error> {
         .execute(&pool)
         .await;
 
+    // Migration: Add message_id column to emails table for draft matching and threading
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN message_id TEXT")
+        .execute(&pool)
+        .await;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_emails_message_id
+        ON emails(account_id, message_id)",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: Add content_hash column to emails table for duplicate detection and body verification
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN content_hash TEXT")
+        .execute(&pool)
+        .await;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_emails_content_hash
+        ON emails(account_id, content_hash)",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: INTERNALDATE kept apart from the Date header, for sorting by date received
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN received_at INTEGER")
+        .execute(&pool)
+        .await;
+
+    // Migration: UTC offset of the Date header (seconds east), to show the sender's local time
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN date_offset INTEGER")
+        .execute(&pool)
+        .await;
+
+    // Migration: Gmail thread ID (X-GM-THRID) and labels (X-GM-LABELS, a JSON array)
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN gmail_thread_id TEXT")
+        .execute(&pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN gmail_labels TEXT")
+        .execute(&pool)
+        .await;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_emails_gmail_thread
+        ON emails(account_id, gmail_thread_id)",
+    )
+    .execute(&pool)
+    .await?;
+
     // Create index for faster queries
     sqlx::query(
         "CREATE INDEX IF NOT EXISTS idx_emails_account_folder
@@ -137,6 +215,44 @@ pub async fn init() -> Result<(), sqlx::Error> {
     .execute(&pool)
     .await?;
 
+    // Per-sender unread aggregates, so the UI doesn't have to load every message to count them
+    sqlx::query(
+        "CREATE VIEW IF NOT EXISTS sender_unread_counts AS
+        SELECT account_id, folder_name, from_addr,
+            COUNT(*) AS total,
+            SUM(CASE WHEN COALESCE(seen, 0) = 0 THEN 1 ELSE 0 END) AS unread,
+            MAX(timestamp) AS latest_timestamp
+        FROM emails
+        GROUP BY account_id, folder_name, from_addr",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: first message of the conversation (from References / In-Reply-To), NULL for
+    // messages that start one
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN thread_root TEXT")
+        .execute(&pool)
+        .await;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_emails_thread_root ON emails(account_id, thread_root)",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Per-conversation unread aggregates; a message without a thread root starts its own
+    sqlx::query(
+        "CREATE VIEW IF NOT EXISTS thread_unread_counts AS
+        SELECT account_id, folder_name, COALESCE(thread_root, message_id) AS thread_id,
+            COUNT(*) AS total,
+            SUM(CASE WHEN COALESCE(seen, 0) = 0 THEN 1 ELSE 0 END) AS unread,
+            MAX(timestamp) AS latest_timestamp
+        FROM emails
+        WHERE COALESCE(thread_root, message_id) IS NOT NULL
+        GROUP BY account_id, folder_name, COALESCE(thread_root, message_id)",
+    )
+    .execute(&pool)
+    .await?;
+
     // Create sync_status table to track last sync times and incremental sync state
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS sync_status (
@@ -160,6 +276,25 @@ pub async fn init() -> Result<(), sqlx::Error> {
     let _ = sqlx::query("ALTER TABLE sync_status ADD COLUMN highest_uid INTEGER")
         .execute(&pool)
         .await;
+    // Migration: HIGHESTMODSEQ of the last flag sync, for CONDSTORE servers (RFC 7162)
+    let _ = sqlx::query("ALTER TABLE sync_status ADD COLUMN highest_modseq INTEGER")
+        .execute(&pool)
+        .await;
+
+    // Progress of an interrupted first (or UIDVALIDITY-reset) sync, so it can resume
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sync_checkpoints (
+            account_id INTEGER NOT NULL,
+            folder_name TEXT NOT NULL,
+            uidvalidity INTEGER NOT NULL,
+            last_uid INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY(account_id, folder_name),
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
 
     // Create settings table for user preferences
     sqlx::query(
@@ -203,6 +338,35 @@ pub async fn init() -> Result<(), sqlx::Error> {
     sqlx::query("INSERT OR IGNORE INTO settings (key, value) VALUES ('password_hash', '')")
         .execute(&pool)
         .await?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES ('reprompt_sensitive_commands', 'false')",
+    )
+    .execute(&pool)
+    .await?;
+    sqlx::query("INSERT OR IGNORE INTO settings (key, value) VALUES ('unlock_max_attempts', '10')")
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES ('unlock_lockout_minutes', '15')",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: Content hashes no longer cover addresses and timestamp, which syncs rewrite;
+    // drop the old ones once so they are recomputed instead of reported as mismatches
+    let content_hash_version = sqlx::query_as::<_, (String,)>(
+        "SELECT value FROM settings WHERE key = 'content_hash_version'",
+    )
+    .fetch_optional(&pool)
+    .await?;
+    if content_hash_version.is_none() {
+        let _ = sqlx::query("UPDATE emails SET content_hash = NULL")
+            .execute(&pool)
+            .await;
+        sqlx::query("INSERT INTO settings (key, value) VALUES ('content_hash_version', '2')")
+            .execute(&pool)
+            .await?;
+    }
 
     // Create attachments table for storing email attachments
     sqlx::query(
@@ -247,6 +411,27 @@ pub async fn init() -> Result<(), sqlx::Error> {
     .execute(&pool)
     .await?;
 
+    // Migration: Link drafts to their server copy for conflict detection
+    let _ = sqlx::query("ALTER TABLE drafts ADD COLUMN message_id TEXT")
+        .execute(&pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE drafts ADD COLUMN remote_folder TEXT")
+        .execute(&pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE drafts ADD COLUMN remote_uid INTEGER")
+        .execute(&pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE drafts ADD COLUMN remote_timestamp INTEGER")
+        .execute(&pool)
+        .await;
+    // Local updated_at at the time the draft last matched the server copy
+    let _ = sqlx::query("ALTER TABLE drafts ADD COLUMN remote_synced_at INTEGER")
+        .execute(&pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE drafts ADD COLUMN conflict_uid INTEGER")
+        .execute(&pool)
+        .await;
+
     // Create index for faster draft queries
     sqlx::query(
         "CREATE INDEX IF NOT EXISTS idx_drafts_account_updated
@@ -276,6 +461,493 @@ pub async fn init() -> Result<(), sqlx::Error> {
         .execute(&pool)
         .await;
 
+    // Migration: Add credential_ns (per-account keyring namespace) and backfill existing accounts.
+    // Keyring entries are moved from the old email-hash keys on first access.
+    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN credential_ns TEXT")
+        .execute(&pool)
+        .await;
+
+    // Migration: Add sent_copy_mode (auto / always / never; NULL means auto) to accounts
+    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN sent_copy_mode TEXT")
+        .execute(&pool)
+        .await;
+
+    // Migration: Only list folders the user is subscribed to (LSUB) for this account
+    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN subscribed_only INTEGER DEFAULT 0")
+        .execute(&pool)
+        .await;
+
+    // Migration: Folders kept in IDLE besides INBOX (JSON array; others are polled)
+    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN idle_folders TEXT")
+        .execute(&pool)
+        .await;
+
+    // Migration: Per-account sync interval in seconds (NULL uses the global sync_interval)
+    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN sync_interval INTEGER")
+        .execute(&pool)
+        .await;
+
+    // Migration: Sync window for first syncs, by age in days and message count (NULL = no limit)
+    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN sync_window_days INTEGER")
+        .execute(&pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN sync_window_messages INTEGER")
+        .execute(&pool)
+        .await;
+    sqlx::query(
+        "UPDATE accounts SET credential_ns = lower(hex(randomblob(16)))
+         WHERE credential_ns IS NULL OR credential_ns = ''",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Create append_jobs table to track bulk uploads so they can resume after interruption
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS append_jobs (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            source_folder TEXT NOT NULL,
+            target_folder TEXT NOT NULL,
+            last_uid INTEGER NOT NULL DEFAULT 0,
+            uploaded_count INTEGER NOT NULL DEFAULT 0,
+            total_count INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'running',
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Append-only security audit log
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY,
+            timestamp INTEGER NOT NULL,
+            event_type TEXT NOT NULL,
+            account TEXT,
+            details TEXT NOT NULL DEFAULT ''
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS audit_log_no_update
+         BEFORE UPDATE ON audit_log
+         BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS audit_log_no_delete
+         BEFORE DELETE ON audit_log
+         BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Per-folder aging policies (e.g. archive read mail older than a year)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS folder_policies (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            folder_name TEXT NOT NULL,
+            action TEXT NOT NULL DEFAULT 'archive',
+            older_than_days INTEGER NOT NULL,
+            only_read INTEGER NOT NULL DEFAULT 1,
+            target_folder TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            last_run_at INTEGER,
+            UNIQUE(account_id, folder_name),
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Automation hooks run on new mail (webhook or local command)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS automation_hooks (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            account_id INTEGER,
+            folder_name TEXT,
+            match_from TEXT,
+            match_subject TEXT,
+            action TEXT NOT NULL,
+            target TEXT NOT NULL,
+            payload_template TEXT,
+            min_interval_secs INTEGER NOT NULL DEFAULT 60,
+            last_fired_at INTEGER,
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Private per-message notes; message_id lets a note follow its message across folders
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS email_notes (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            folder_name TEXT NOT NULL,
+            uid INTEGER NOT NULL,
+            message_id TEXT,
+            note TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            UNIQUE(account_id, folder_name, uid),
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_email_notes_message_id ON email_notes(account_id, message_id)",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Selected raw headers (List-Id, X-Mailer, Return-Path, Authentication-Results, Received IPs)
+    // kept for header search
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS email_raw_headers (
+            id INTEGER PRIMARY KEY,
+            email_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            FOREIGN KEY(email_id) REFERENCES emails(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_email_raw_headers_email ON email_raw_headers(email_id)",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_email_raw_headers_name ON email_raw_headers(name, value)",
+    )
+    .execute(&pool)
+    .await?;
+
+    // When each OAuth account's current refresh token was issued, for expiry prediction
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS oauth_tokens (
+            account_id INTEGER PRIMARY KEY,
+            refresh_token_issued_at INTEGER NOT NULL,
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Per-account host name -> IP pins for split-horizon DNS
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS host_overrides (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            host TEXT NOT NULL,
+            ip TEXT NOT NULL,
+            UNIQUE(account_id, host),
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Folders that are only synced when opened, not by background or refresh-all syncs
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS folder_sync_settings (
+            account_id INTEGER NOT NULL,
+            folder_name TEXT NOT NULL,
+            sync_on_open INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY(account_id, folder_name),
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: Per-folder sync interval in seconds (NULL uses the account's interval)
+    let _ = sqlx::query("ALTER TABLE folder_sync_settings ADD COLUMN sync_interval INTEGER")
+        .execute(&pool)
+        .await;
+
+    // Full-text index of text extracted from attachments (rowid = attachment id)
+    sqlx::query("CREATE VIRTUAL TABLE IF NOT EXISTS attachment_fts USING fts5(content)")
+        .execute(&pool)
+        .await?;
+
+    // Migration: Track which attachments have had their text extracted (2 = unsupported)
+    let _ = sqlx::query("ALTER TABLE attachments ADD COLUMN text_indexed INTEGER DEFAULT 0")
+        .execute(&pool)
+        .await;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS attachments_fts_delete AFTER DELETE ON attachments
+         BEGIN
+            DELETE FROM attachment_fts WHERE rowid = old.id;
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: Attachment blobs moved to the external archive keep only this path
+    let _ = sqlx::query("ALTER TABLE attachments ADD COLUMN archived_path TEXT")
+        .execute(&pool)
+        .await;
+
+    // Archived files of deleted attachments, removed from disk by the next cleanup. The trigger
+    // also catches rows removed by cascades from emails and accounts.
+    sqlx::query("CREATE TABLE IF NOT EXISTS archived_attachment_removals (path TEXT PRIMARY KEY)")
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS attachments_archive_delete AFTER DELETE ON attachments
+         WHEN old.archived_path IS NOT NULL
+         BEGIN
+            INSERT OR IGNORE INTO archived_attachment_removals (path) VALUES (old.archived_path);
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Message templates delivered on a schedule, and the history of their occurrences
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS recurring_sends (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            to_addr TEXT NOT NULL,
+            cc_addr TEXT NOT NULL DEFAULT '',
+            subject TEXT NOT NULL,
+            body TEXT NOT NULL,
+            cadence TEXT NOT NULL,
+            day INTEGER NOT NULL DEFAULT 0,
+            hour INTEGER NOT NULL,
+            minute INTEGER NOT NULL,
+            delivery TEXT NOT NULL,
+            next_run_at INTEGER,
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS recurring_send_occurrences (
+            id INTEGER PRIMARY KEY,
+            recurring_send_id INTEGER NOT NULL,
+            scheduled_for INTEGER NOT NULL,
+            ran_at INTEGER NOT NULL,
+            outcome TEXT NOT NULL,
+            draft_id INTEGER,
+            error TEXT,
+            FOREIGN KEY(recurring_send_id) REFERENCES recurring_sends(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Contact groups: named recipient lists expanded at compose time
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS contact_groups (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS contact_group_members (
+            id INTEGER PRIMARY KEY,
+            group_id INTEGER NOT NULL,
+            address TEXT NOT NULL COLLATE NOCASE,
+            name TEXT,
+            opted_out INTEGER NOT NULL DEFAULT 0,
+            UNIQUE(group_id, address),
+            FOREIGN KEY(group_id) REFERENCES contact_groups(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Extra addresses (aliases) an account can send from
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS account_identities (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            email TEXT NOT NULL,
+            display_name TEXT,
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: S/MIME/PGP summary detected from BODYSTRUCTURE (JSON MessageSecurity)
+    let _ = sqlx::query("ALTER TABLE emails ADD COLUMN security TEXT")
+        .execute(&pool)
+        .await;
+
+    // Outgoing messages, kept until the SMTP server accepts them (see commands::outbox)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS outbox (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            envelope_from TEXT,
+            envelope_to TEXT NOT NULL,
+            message BLOB NOT NULL,
+            encrypted INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'queued',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at INTEGER,
+            last_error TEXT,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Bcc recipients of sent messages by Message-ID; no copy of a message carries them, so the
+    // cached Sent copy picks them up from here when it is synced (see commands::sent_copy)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sent_bcc (
+            account_id INTEGER NOT NULL,
+            message_id TEXT NOT NULL,
+            bcc_addr TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY(account_id, message_id),
+            FOREIGN KEY(account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Full-text index over cached emails, kept in sync with `emails` by triggers. It is an
+    // external-content table, so the text itself is only stored once (in `emails`).
+    let fts_exists = sqlx::query_as::<_, (i64,)>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'emails_fts'",
+    )
+    .fetch_one(&pool)
+    .await?
+    .0 > 0;
+
+    sqlx::query(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS emails_fts USING fts5(
+            subject, from_addr, to_addr, body,
+            content='emails', content_rowid='id'
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS emails_fts_insert AFTER INSERT ON emails
+         BEGIN
+            INSERT INTO emails_fts (rowid, subject, from_addr, to_addr, body)
+            VALUES (new.id, new.subject, new.from_addr, new.to_addr, new.body);
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS emails_fts_delete AFTER DELETE ON emails
+         BEGIN
+            INSERT INTO emails_fts (emails_fts, rowid, subject, from_addr, to_addr, body)
+            VALUES ('delete', old.id, old.subject, old.from_addr, old.to_addr, old.body);
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS emails_fts_update
+         AFTER UPDATE OF subject, from_addr, to_addr, body ON emails
+         BEGIN
+            INSERT INTO emails_fts (emails_fts, rowid, subject, from_addr, to_addr, body)
+            VALUES ('delete', old.id, old.subject, old.from_addr, old.to_addr, old.body);
+            INSERT INTO emails_fts (rowid, subject, from_addr, to_addr, body)
+            VALUES (new.id, new.subject, new.from_addr, new.to_addr, new.body);
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    // Migration: Index the emails cached before the search index existed
+    if !fts_exists {
+        sqlx::query("INSERT INTO emails_fts (emails_fts) VALUES ('rebuild')")
+            .execute(&pool)
+            .await?;
+    }
+
+    // Full-text index over notes, so search also finds messages by what the user wrote on them
+    let notes_fts_exists = sqlx::query_as::<_, (i64,)>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'notes_fts'",
+    )
+    .fetch_one(&pool)
+    .await?
+    .0 > 0;
+
+    sqlx::query(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+            note, content='email_notes', content_rowid='id'
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON email_notes
+         BEGIN
+            INSERT INTO notes_fts (rowid, note) VALUES (new.id, new.note);
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON email_notes
+         BEGIN
+            INSERT INTO notes_fts (notes_fts, rowid, note) VALUES ('delete', old.id, old.note);
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE OF note ON email_notes
+         BEGIN
+            INSERT INTO notes_fts (notes_fts, rowid, note) VALUES ('delete', old.id, old.note);
+            INSERT INTO notes_fts (rowid, note) VALUES (new.id, new.note);
+         END",
+    )
+    .execute(&pool)
+    .await?;
+
+    if !notes_fts_exists {
+        sqlx::query("INSERT INTO notes_fts (notes_fts) VALUES ('rebuild')")
+            .execute(&pool)
+            .await?;
+    }
+
     // Store pool globally
     POOL.set(Arc::new(pool))
         .expect("Database pool already initialized");
@@ -0,0 +1,189 @@
+// Fault injection for development
+// Debug builds can simulate a flaky mail server to exercise the retry and reconnect paths:
+// extra latency on every IMAP/SMTP connection, IMAP logins refused with BYE, IMAP sessions cut
+// off mid-response (the socket is shut down after a random delay, so the client sees a
+// truncated reply) and failing SMTP connections. Configured with the `COLIMAIL_FAULTS`
+// environment variable, e.g. `latency=800,jitter=400,bye=0.2,truncate=0.1,smtp=0.3,seed=7`,
+// or at runtime with the `set_fault_injection` command. Giving a seed makes a run
+// reproducible. Release builds ignore both.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::net::{Shutdown, TcpStream};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Environment variable read at startup
+const ENV_VAR: &str = "COLIMAIL_FAULTS";
+
+/// Faults to inject; rates are probabilities between 0 and 1
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FaultConfig {
+    pub latency_ms: u64,    // Added to every connection
+    pub jitter_ms: u64,     // Random extra latency up to this value
+    pub bye_rate: f64,      // IMAP login answered with BYE
+    pub truncate_rate: f64, // IMAP session cut off mid-response
+    pub smtp_failure_rate: f64,
+    pub seed: Option<u64>,
+}
+
+struct FaultState {
+    config: FaultConfig,
+    rng: StdRng,
+}
+
+lazy_static::lazy_static! {
+    static ref FAULTS: Mutex<Option<FaultState>> = Mutex::new(
+        std::env::var(ENV_VAR)
+            .ok()
+            .and_then(|spec| match parse_spec(&spec) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    eprintln!("⚠️ Ignoring {}: {}", ENV_VAR, e);
+                    None
+                }
+            })
+            .and_then(new_state)
+    );
+}
+
+fn new_state(config: FaultConfig) -> Option<FaultState> {
+    if !cfg!(debug_assertions) || config == FaultConfig::default() {
+        return None;
+    }
+    println!("🧨 Fault injection enabled: {:?}", config);
+    let rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    Some(FaultState { config, rng })
+}
+
+/// Parse a `key=value,...` fault spec
+fn parse_spec(spec: &str) -> Result<FaultConfig, String> {
+    let mut config = FaultConfig::default();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (key, value) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got '{}'", entry))?;
+        let value = value.trim();
+        let invalid = || format!("invalid value for {}: '{}'", key.trim(), value);
+        match key.trim() {
+            "latency" => config.latency_ms = value.parse().map_err(|_| invalid())?,
+            "jitter" => config.jitter_ms = value.parse().map_err(|_| invalid())?,
+            "bye" => config.bye_rate = value.parse().map_err(|_| invalid())?,
+            "truncate" => config.truncate_rate = value.parse().map_err(|_| invalid())?,
+            "smtp" => config.smtp_failure_rate = value.parse().map_err(|_| invalid())?,
+            "seed" => config.seed = Some(value.parse().map_err(|_| invalid())?),
+            other => return Err(format!("unknown fault '{}'", other)),
+        }
+    }
+    Ok(config)
+}
+
+/// Replace the active faults; `None` turns injection off
+pub fn configure(config: Option<FaultConfig>) -> Result<(), String> {
+    if !cfg!(debug_assertions) {
+        return Err("Fault injection is only available in debug builds".to_string());
+    }
+    let mut faults = FAULTS.lock().map_err(|e| e.to_string())?;
+    *faults = config.and_then(new_state);
+    if faults.is_none() {
+        println!("🧨 Fault injection disabled");
+    }
+    Ok(())
+}
+
+/// The active faults, if any
+pub fn current() -> Option<FaultConfig> {
+    FAULTS
+        .lock()
+        .ok()
+        .and_then(|faults| faults.as_ref().map(|state| state.config.clone()))
+}
+
+/// Latency to add and whether a fault with the selected rate fires
+fn roll(rate: impl Fn(&FaultConfig) -> f64) -> Option<(Duration, bool)> {
+    let mut faults = FAULTS.lock().ok()?;
+    let state = faults.as_mut()?;
+    let jitter = match state.config.jitter_ms {
+        0 => 0,
+        max => state.rng.gen_range(0..=max),
+    };
+    let rate = rate(&state.config);
+    let fire = rate > 0.0 && state.rng.gen_bool(rate.min(1.0));
+    Some((
+        Duration::from_millis(state.config.latency_ms + jitter),
+        fire,
+    ))
+}
+
+/// Before an IMAP login: delay, and possibly refuse the connection with BYE
+pub fn imap_login() -> Result<(), String> {
+    let Some((delay, bye)) = roll(|config| config.bye_rate) else {
+        return Ok(());
+    };
+    std::thread::sleep(delay);
+    if bye {
+        eprintln!("🧨 Injecting IMAP BYE");
+        return Err(
+            "Failed to connect to IMAP server: * BYE Server shutting down (injected fault)"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// After an IMAP connection is set up: possibly shut the socket down after a random delay
+pub fn imap_session(socket: &TcpStream) {
+    let Some((_, truncate)) = roll(|config| config.truncate_rate) else {
+        return;
+    };
+    if !truncate {
+        return;
+    }
+    let Ok(socket) = socket.try_clone() else {
+        return;
+    };
+    let after = Duration::from_millis(rand::thread_rng().gen_range(200..5_000));
+    std::thread::spawn(move || {
+        std::thread::sleep(after);
+        eprintln!("🧨 Injecting truncated IMAP response after {:?}", after);
+        let _ = socket.shutdown(Shutdown::Both);
+    });
+}
+
+/// Before an SMTP connection: delay, and possibly fail it
+pub async fn smtp_connect() -> Result<(), String> {
+    let Some((delay, fail)) = roll(|config| config.smtp_failure_rate) else {
+        return Ok(());
+    };
+    tokio::time::sleep(delay).await;
+    if fail {
+        eprintln!("🧨 Injecting SMTP connection failure");
+        return Err(
+            "Failed to connect to SMTP server: connection reset (injected fault)".to_string(),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        let config = parse_spec("latency=800, bye=0.2,truncate=0.1,seed=7").unwrap();
+        assert_eq!(config.latency_ms, 800);
+        assert_eq!(config.bye_rate, 0.2);
+        assert_eq!(config.truncate_rate, 0.1);
+        assert_eq!(config.seed, Some(7));
+        assert_eq!(config.smtp_failure_rate, 0.0);
+
+        assert!(parse_spec("latency=fast").is_err());
+        assert!(parse_spec("explode=1").is_err());
+        assert_eq!(parse_spec("").unwrap(), FaultConfig::default());
+    }
+}
@@ -0,0 +1,313 @@
+// HTML to plain text
+// Converts the composer's HTML into the text/plain alternative of outgoing mail: block
+// elements become line breaks, lists get bullets or numbers, quotes get "> " prefixes and
+// links keep their target in parentheses. Style, script and head content is dropped.
+
+/// Elements that end the current line
+const LINE_ELEMENTS: &[&str] = &["br", "div", "tr", "li", "dt", "dd", "table", "ul", "ol"];
+/// Elements surrounded by a blank line
+const PARAGRAPH_ELEMENTS: &[&str] = &["p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre"];
+/// Elements whose content isn't shown
+const HIDDEN_ELEMENTS: &[&str] = &["head", "style", "script", "title"];
+
+struct TextWriter {
+    out: String,
+    quote_depth: usize,
+    at_line_start: bool,
+    line_has_text: bool,
+    pending_space: bool,
+}
+
+impl TextWriter {
+    fn new() -> Self {
+        TextWriter {
+            out: String::new(),
+            quote_depth: 0,
+            at_line_start: true,
+            line_has_text: false,
+            pending_space: false,
+        }
+    }
+
+    fn start_line(&mut self) {
+        if self.at_line_start {
+            self.out.push_str(&"> ".repeat(self.quote_depth));
+            self.at_line_start = false;
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        self.start_line();
+        if self.pending_space && self.line_has_text {
+            self.out.push(' ');
+        }
+        self.pending_space = false;
+        self.out.push(c);
+        self.line_has_text = true;
+    }
+
+    fn push_str(&mut self, text: &str) {
+        text.chars().for_each(|c| self.push_char(c));
+    }
+
+    fn space(&mut self) {
+        self.pending_space = true;
+    }
+
+    fn newline(&mut self) {
+        self.start_line();
+        self.out.push('\n');
+        self.at_line_start = true;
+        self.line_has_text = false;
+        self.pending_space = false;
+    }
+
+    /// End the current line, if anything is on it
+    fn break_line(&mut self) {
+        if self.line_has_text {
+            self.newline();
+        }
+    }
+
+    /// End the current line and leave one blank line
+    fn break_paragraph(&mut self) {
+        self.break_line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") && !self.out.ends_with(">\n") {
+            self.newline();
+        }
+    }
+
+    /// Drop a trailing line that holds nothing but quote markers
+    fn drop_empty_quote_line(&mut self) {
+        let Some(without_newline) = self.out.strip_suffix('\n') else {
+            return;
+        };
+        let line_start = without_newline.rfind('\n').map_or(0, |i| i + 1);
+        let line = &without_newline[line_start..];
+        if !line.is_empty() && line.chars().all(|c| c == '>' || c == ' ') {
+            self.out.truncate(line_start);
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut text = String::new();
+        let mut blank_lines = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.chars().all(|c| c == '>' || c == ' ') {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.trim().to_string()
+    }
+}
+
+/// Value of an attribute in a start tag like `a href="x" class=y`
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut offset = 0;
+    while let Some(pos) = lower[offset..].find(name) {
+        let start = offset + pos;
+        offset = start + name.len();
+        let preceded_by_space = lower[..start].ends_with(char::is_whitespace);
+        let rest = lower[offset..].trim_start();
+        if !preceded_by_space || !rest.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - rest.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(char::is_whitespace).next().unwrap_or_default(),
+        };
+        return Some(html_escape::decode_html_entities(value).to_string());
+    }
+    None
+}
+
+/// Convert an HTML body to readable plain text
+pub fn html_to_text(html: &str) -> String {
+    let mut writer = TextWriter::new();
+    let mut hidden: Option<String> = None; // Hidden element being skipped
+    let mut in_pre = false;
+    let mut lists: Vec<Option<usize>> = Vec::new(); // Next number for <ol>, None for <ul>
+    let mut link: Option<(String, usize)> = None; // href and where the link text starts
+
+    let mut rest = html;
+    while !rest.is_empty() {
+        // Text up to the next tag
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        let (text, after) = rest.split_at(text_end);
+        rest = after;
+        if hidden.is_none() && !text.is_empty() {
+            let text = html_escape::decode_html_entities(text);
+            for c in text.chars() {
+                match c {
+                    '\n' if in_pre => writer.newline(),
+                    c if in_pre => writer.push_char(c),
+                    c if c.is_whitespace() && c != '\u{a0}' => writer.space(),
+                    '\u{a0}' => writer.push_char(' '),
+                    c => writer.push_char(c),
+                }
+            }
+        }
+        if rest.is_empty() {
+            break;
+        }
+
+        // Comments
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        // The tag, honouring quoted attribute values that may contain '>'
+        let mut quote = None;
+        let tag_end = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| match (quote, c) {
+                (None, '"' | '\'') => {
+                    quote = Some(c);
+                    false
+                }
+                (Some(q), c) if c == q => {
+                    quote = None;
+                    false
+                }
+                (None, '>') => true,
+                _ => false,
+            })
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        let tag = rest.get(1..tag_end).unwrap_or_default();
+        rest = rest.get(tag_end + 1..).unwrap_or_default();
+
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/').trim_end_matches('/');
+        let name = tag
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if let Some(hidden_name) = &hidden {
+            if closing && name == *hidden_name {
+                hidden = None;
+            }
+            continue;
+        }
+        if !closing && HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            hidden = Some(name);
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("blockquote", false) => {
+                writer.break_paragraph();
+                writer.quote_depth += 1;
+            }
+            ("blockquote", true) => {
+                writer.break_line();
+                writer.drop_empty_quote_line();
+                writer.quote_depth = writer.quote_depth.saturating_sub(1);
+                writer.break_paragraph();
+            }
+            ("pre", _) => {
+                writer.break_paragraph();
+                in_pre = !closing;
+            }
+            ("ul", false) => {
+                writer.break_line();
+                lists.push(None);
+            }
+            ("ol", false) => {
+                writer.break_line();
+                lists.push(Some(1));
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                writer.break_line();
+            }
+            ("li", false) => {
+                writer.break_line();
+                writer.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        writer.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => writer.push_str("- "),
+                }
+                writer.pending_space = false;
+            }
+            ("hr", _) => {
+                writer.break_line();
+                writer.push_str("----------");
+                writer.newline();
+            }
+            ("td" | "th", false) => writer.space(),
+            ("img", _) => {
+                if let Some(alt) = attribute(tag, "alt").filter(|alt| !alt.trim().is_empty()) {
+                    writer.push_str(&format!("[{}]", alt.trim()));
+                }
+            }
+            ("a", false) => {
+                link = attribute(tag, "href").map(|href| (href, writer.out.len()));
+            }
+            ("a", true) => {
+                if let Some((href, start)) = link.take() {
+                    let text = writer.out.get(start..).unwrap_or_default().trim();
+                    let target = href.strip_prefix("mailto:").unwrap_or(&href);
+                    if !href.is_empty() && !href.starts_with('#') && text != target {
+                        writer.push_str(&format!(" ({})", target));
+                    }
+                }
+            }
+            (name, _) if PARAGRAPH_ELEMENTS.contains(&name) => writer.break_paragraph(),
+            (name, _) if LINE_ELEMENTS.contains(&name) => {
+                if name == "br" {
+                    writer.newline();
+                } else {
+                    writer.break_line();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<html><head><style>p { color: red; }</style></head><body>
+            <p>Hi   Jane,</p><p>See <a href="https://example.com/doc">the doc</a> &amp; reply
+            to <a href="mailto:bob@example.com">bob@example.com</a>.<br>Thanks</p>
+            <ol><li>One</li><li>Two</li></ol>
+            <blockquote><p>Earlier message</p></blockquote>
+            <!-- comment --><div>Bye</div></body></html>"#;
+
+        assert_eq!(
+            html_to_text(html),
+            "Hi Jane,\n\n\
+             See the doc (https://example.com/doc) & reply to bob@example.com.\n\
+             Thanks\n\n\
+             1. One\n\
+             2. Two\n\n\
+             > Earlier message\n\n\
+             Bye"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+// IDLE-triggered sync
+// New mail reported by IDLE or polling is synced right away in the backend instead of waiting
+// for the frontend to call `sync_emails`, and a `sync-complete` event carries the new headers.
+// Only one sync runs per folder at a time; mail arriving during a sync triggers one more
+// sync when it finishes, so bursts of EXISTS responses don't start overlapping syncs.
+
+use super::notification::send_notification;
+use crate::commands::event_buffer::emit_buffered;
+use crate::db;
+use crate::models::{AccountConfig, EmailHeader};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+/// Emitted once an IDLE-triggered sync has saved a folder's new mail
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SyncComplete {
+    pub account_id: i32,
+    pub folder_name: String,
+    pub new_emails: Vec<EmailHeader>,
+}
+
+lazy_static::lazy_static! {
+    /// Folders with a sync running; true if more mail arrived since it started
+    static ref IN_FLIGHT: Mutex<HashMap<(i32, String), bool>> = Mutex::new(HashMap::new());
+}
+
+/// Whether an IDLE-triggered sync of a folder is running
+pub fn is_syncing(account_id: i32, folder_name: &str) -> bool {
+    IN_FLIGHT
+        .lock()
+        .unwrap()
+        .contains_key(&(account_id, folder_name.to_string()))
+}
+
+/// Highest UID cached for a folder
+async fn cached_max_uid(account_id: i32, folder_name: &str) -> u32 {
+    let pool = db::pool();
+    sqlx::query_as::<_, (Option<i64>,)>(
+        "SELECT MAX(uid) FROM emails WHERE account_id = ? AND folder_name = ?",
+    )
+    .bind(account_id)
+    .bind(folder_name)
+    .fetch_one(pool.as_ref())
+    .await
+    .ok()
+    .and_then(|row| row.0)
+    .unwrap_or(0) as u32
+}
+
+/// Sync a folder after new mail was detected, then emit `sync-complete` and notify
+pub fn sync_new_messages(
+    app_handle: &AppHandle,
+    account_id: i32,
+    folder_name: &str,
+    config: &AccountConfig,
+    count: u32,
+) {
+    let key = (account_id, folder_name.to_string());
+    {
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        if let Some(pending) = in_flight.get_mut(&key) {
+            *pending = true;
+            return;
+        }
+        in_flight.insert(key.clone(), false);
+    }
+
+    let app_handle = app_handle.clone();
+    let config = config.clone();
+    tokio::spawn(async move {
+        let folder_name = key.1.clone();
+        loop {
+            let previous_max_uid = cached_max_uid(account_id, &folder_name).await;
+
+            match crate::commands::sync_emails(
+                app_handle.clone(),
+                config.clone(),
+                Some(folder_name.clone()),
+                Some(true),
+            )
+            .await
+            {
+                Ok(emails) => {
+                    let new_emails: Vec<EmailHeader> = emails
+                        .into_iter()
+                        .filter(|email| email.uid > previous_max_uid)
+                        .collect();
+                    tracing::info!(
+                        account_id = account_id,
+                        folder = %folder_name,
+                        new = new_emails.len(),
+                        "IDLE-triggered sync complete"
+                    );
+
+                    let new_count = new_emails.len() as u32;
+                    emit_buffered(
+                        &app_handle,
+                        "sync-complete",
+                        SyncComplete {
+                            account_id,
+                            folder_name: folder_name.clone(),
+                            new_emails,
+                        },
+                    );
+                    if new_count > 0 {
+                        send_notification(&app_handle, account_id, &folder_name, new_count).await;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(
+                        account_id = account_id,
+                        folder = %folder_name,
+                        error = %e,
+                        "IDLE-triggered sync failed"
+                    );
+                    // Still tell the user about the mail, from whatever is cached
+                    send_notification(&app_handle, account_id, &folder_name, count).await;
+                }
+            }
+
+            // Go again if more mail arrived meanwhile, otherwise release the folder
+            let mut in_flight = IN_FLIGHT.lock().unwrap();
+            match in_flight.get_mut(&key) {
+                Some(pending) if *pending => *pending = false,
+                _ => {
+                    in_flight.remove(&key);
+                    break;
+                }
+            }
+        }
+    });
+}
@@ -1,17 +1,68 @@
 // IDLE manager core
 // This module manages IDLE connections and command processing
 
-use super::session::idle_connection_loop;
+use super::session::{idle_connection_loop, poll_connection_loop};
+use super::settings::{load_idle_folders, MAX_IDLE_FOLDERS};
+use super::status;
 use super::types::IdleCommand;
+use crate::models::{AccountConfig, Folder};
+use crate::watchdog;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::AppHandle;
 use tokio::sync::mpsc;
 
+/// An IDLE loop beats at least every 29 minutes (IDLE re-entry) or 30 seconds (reconnect wait)
+const IDLE_STALL_THRESHOLD: Duration = Duration::from_secs(35 * 60);
+
+/// Task key of an account's shared polling connection ("*" is a LIST wildcard, never a folder)
+const POLLER_KEY: &str = "*";
+
+/// Folder roles that aren't polled for new mail (sent and drafts are our own messages, and
+/// All Mail repeats INBOX)
+const UNWATCHED_ROLES: [&str; 5] = ["sent", "drafts", "trash", "junk", "all"];
+
+type ActiveConnections = Arc<Mutex<HashMap<(i32, String), ()>>>;
+
+/// Split an account's folders into the ones kept in IDLE (INBOX first, then the priority list)
+/// and the ones polled over the shared connection
+fn plan_watch(folders: &[Folder], priority: &[String]) -> (Vec<String>, Vec<String>) {
+    // Find INBOX by its role, falling back to the name for folders synced before roles existed
+    let inbox_names = ["inbox", "收件箱"];
+    let inbox = folders
+        .iter()
+        .find(|f| f.role.as_deref() == Some("inbox"))
+        .or_else(|| {
+            folders.iter().find(|f| {
+                let folder_lower = f.name.to_lowercase();
+                inbox_names.iter().any(|inbox| folder_lower.contains(inbox))
+            })
+        });
+
+    let mut idle: Vec<String> = inbox.map(|f| f.name.clone()).into_iter().collect();
+    let mut polled = Vec::new();
+
+    for folder in folders {
+        if idle.contains(&folder.name) || folder.is_local || !folder.is_selectable() {
+            continue;
+        }
+        if priority.contains(&folder.name) && idle.len() < MAX_IDLE_FOLDERS {
+            idle.push(folder.name.clone());
+        } else if !folder.sync_on_open
+            && !UNWATCHED_ROLES.contains(&folder.role.as_deref().unwrap_or_default())
+        {
+            polled.push(folder.name.clone());
+        }
+    }
+
+    (idle, polled)
+}
+
 /// Global IDLE manager instance
 pub struct IdleManager {
     command_tx: mpsc::UnboundedSender<IdleCommand>,
-    active_connections: Arc<Mutex<HashMap<(i32, String), ()>>>,
+    active_connections: ActiveConnections,
 }
 
 impl IdleManager {
@@ -35,6 +86,17 @@ impl IdleManager {
 
     /// Send a command to the manager
     pub fn send_command(&self, cmd: IdleCommand) -> Result<(), String> {
+        // Safe mode keeps IDLE off; stop commands still go through
+        if crate::safe_mode::is_enabled()
+            && matches!(
+                cmd,
+                IdleCommand::Start { .. } | IdleCommand::StartAllForAccount { .. }
+            )
+        {
+            tracing::info!("Safe mode: not starting IDLE");
+            return Ok(());
+        }
+
         self.command_tx
             .send(cmd)
             .map_err(|e| format!("Failed to send command: {}", e))
@@ -44,7 +106,7 @@ impl IdleManager {
     async fn run_manager(
         app_handle: AppHandle,
         mut command_rx: mpsc::UnboundedReceiver<IdleCommand>,
-        active_connections: Arc<Mutex<HashMap<(i32, String), ()>>>,
+        active_connections: ActiveConnections,
     ) {
         let mut tasks: HashMap<(i32, String), tokio::task::JoinHandle<()>> = HashMap::new();
 
@@ -62,35 +124,19 @@ impl IdleManager {
                         task.abort();
                     }
 
-                    // Mark as active
-                    active_connections.lock().unwrap().insert(key.clone(), ());
-
                     tracing::info!(
                         account_id = account_id,
                         folder = %folder_name,
                         "Starting IDLE connection"
                     );
 
-                    // Spawn new IDLE task
-                    let app_handle_clone = app_handle.clone();
-                    let active_connections_clone = active_connections.clone();
-
-                    let task = tokio::spawn(async move {
-                        idle_connection_loop(
-                            app_handle_clone,
-                            account_id,
-                            folder_name.clone(),
-                            config,
-                        )
-                        .await;
-
-                        // Remove from active connections when done
-                        active_connections_clone
-                            .lock()
-                            .unwrap()
-                            .remove(&(account_id, folder_name));
-                    });
-
+                    let task = Self::spawn_idle(
+                        &app_handle,
+                        &active_connections,
+                        account_id,
+                        folder_name,
+                        config,
+                    );
                     tasks.insert(key, task);
                 }
 
@@ -108,6 +154,7 @@ impl IdleManager {
                         );
                         task.abort();
                         active_connections.lock().unwrap().remove(&key);
+                        status::forget(account_id, Some(&folder_name));
                     }
                 }
 
@@ -119,6 +166,7 @@ impl IdleManager {
                     }
 
                     active_connections.lock().unwrap().clear();
+                    status::forget_all();
                 }
 
                 IdleCommand::StartAllForAccount { config } => {
@@ -130,83 +178,73 @@ impl IdleManager {
                         }
                     };
 
-                    tracing::info!(
-                        account_id = account_id,
-                        email = %config.email,
-                        "Starting IDLE for INBOX"
-                    );
+                    if crate::commands::demo::is_demo_account(&config) {
+                        continue;
+                    }
 
-                    // Only start IDLE for INBOX to avoid hitting connection limits
-                    // Most email providers (Gmail, Outlook, etc.) limit concurrent IMAP connections to 10-15
-                    // IDLE monitoring is primarily needed for INBOX to get new email notifications
-                    let inbox_folders = ["INBOX", "收件箱"];
-
-                    // Load folders to find the actual INBOX folder name
-                    match crate::commands::load_folders(account_id).await {
-                        Ok(folders) => {
-                            // Find INBOX folder (case-insensitive match)
-                            let inbox_folder = folders.iter().find(|f| {
-                                let folder_lower = f.name.to_lowercase();
-                                inbox_folders
-                                    .iter()
-                                    .any(|inbox| folder_lower.contains(&inbox.to_lowercase()))
-                            });
-
-                            if let Some(folder) = inbox_folder {
-                                let key = (account_id, folder.name.clone());
-
-                                // Stop existing connection if any
-                                if let Some(task) = tasks.remove(&key) {
-                                    task.abort();
-                                }
-
-                                // Mark as active
-                                active_connections.lock().unwrap().insert(key.clone(), ());
-
-                                tracing::info!(
-                                    folder = %folder.display_name,
-                                    "Starting IDLE for folder"
-                                );
-
-                                // Clone necessary data
-                                let app_handle_clone = app_handle.clone();
-                                let active_connections_clone = active_connections.clone();
-                                let config_clone = config.clone();
-                                let folder_name = folder.name.clone();
-
-                                // Spawn IDLE task
-                                let task = tokio::spawn(async move {
-                                    idle_connection_loop(
-                                        app_handle_clone,
-                                        account_id,
-                                        folder_name.clone(),
-                                        config_clone,
-                                    )
-                                    .await;
-
-                                    // Remove from active connections when done
-                                    active_connections_clone
-                                        .lock()
-                                        .unwrap()
-                                        .remove(&(account_id, folder_name));
-                                });
-
-                                tasks.insert(key, task);
-                                tracing::info!("Started IDLE monitoring for INBOX");
-                            } else {
-                                tracing::error!(
-                                    account_id = account_id,
-                                    "Could not find INBOX folder"
-                                );
-                            }
-                        }
+                    // One IDLE connection per folder quickly exceeds provider connection limits
+                    // (Gmail allows 15), so only INBOX and the account's priority folders get
+                    // IDLE; every other folder is polled over one shared connection
+                    let folders = match crate::commands::load_folders(account_id).await {
+                        Ok(folders) => folders,
                         Err(e) => {
                             tracing::error!(
                                 account_id = account_id,
                                 error = %e,
                                 "Failed to load folders"
                             );
+                            continue;
+                        }
+                    };
+                    let priority = load_idle_folders(account_id).await.unwrap_or_default();
+                    let (idle_folders, polled_folders) = plan_watch(&folders, &priority);
+
+                    if idle_folders.is_empty() {
+                        tracing::error!(account_id = account_id, "Could not find INBOX folder");
+                    }
+
+                    // Replace whatever was watching this account before
+                    tasks.retain(|(acc_id, _), task| {
+                        if *acc_id == account_id {
+                            task.abort();
                         }
+                        *acc_id != account_id
+                    });
+                    active_connections
+                        .lock()
+                        .unwrap()
+                        .retain(|(acc_id, _), _| *acc_id != account_id);
+                    status::forget(account_id, None);
+
+                    tracing::info!(
+                        account_id = account_id,
+                        email = %config.email,
+                        idle = ?idle_folders,
+                        polled = polled_folders.len(),
+                        "Starting IDLE for account"
+                    );
+
+                    for folder_name in idle_folders {
+                        let key = (account_id, folder_name.clone());
+                        let task = Self::spawn_idle(
+                            &app_handle,
+                            &active_connections,
+                            account_id,
+                            folder_name,
+                            config.clone(),
+                        );
+                        tasks.insert(key, task);
+                    }
+
+                    if !polled_folders.is_empty() {
+                        let task = Self::spawn_poller(
+                            &app_handle,
+                            &active_connections,
+                            account_id,
+                            polled_folders,
+                            config,
+                        );
+                        tasks.insert((account_id, POLLER_KEY.to_string()), task);
                     }
                 }
 
@@ -233,6 +271,12 @@ impl IdleManager {
                             );
                         }
                     }
+                    // Polled folders are marked active without a task of their own
+                    active_connections
+                        .lock()
+                        .unwrap()
+                        .retain(|(acc_id, _), _| *acc_id != account_id);
+                    status::forget(account_id, None);
 
                     tracing::info!(
                         account_id = account_id,
@@ -243,6 +287,81 @@ impl IdleManager {
         }
     }
 
+    /// Spawn a supervised IDLE task for one folder, marked active while it runs
+    fn spawn_idle(
+        app_handle: &AppHandle,
+        active_connections: &ActiveConnections,
+        account_id: i32,
+        folder_name: String,
+        config: AccountConfig,
+    ) -> tokio::task::JoinHandle<()> {
+        active_connections
+            .lock()
+            .unwrap()
+            .insert((account_id, folder_name.clone()), ());
+
+        let app_handle = app_handle.clone();
+        let active_connections = active_connections.clone();
+        tokio::spawn(async move {
+            let task_name = format!("idle:{}:{}", account_id, folder_name);
+            let loop_folder_name = folder_name.clone();
+            watchdog::supervise(task_name, IDLE_STALL_THRESHOLD, move |heartbeat| {
+                idle_connection_loop(
+                    app_handle.clone(),
+                    account_id,
+                    loop_folder_name.clone(),
+                    config.clone(),
+                    heartbeat,
+                )
+            })
+            .await;
+
+            // Remove from active connections when done
+            active_connections
+                .lock()
+                .unwrap()
+                .remove(&(account_id, folder_name));
+        })
+    }
+
+    /// Spawn a supervised task polling an account's secondary folders over one connection
+    fn spawn_poller(
+        app_handle: &AppHandle,
+        active_connections: &ActiveConnections,
+        account_id: i32,
+        folder_names: Vec<String>,
+        config: AccountConfig,
+    ) -> tokio::task::JoinHandle<()> {
+        {
+            let mut active = active_connections.lock().unwrap();
+            for folder_name in &folder_names {
+                active.insert((account_id, folder_name.clone()), ());
+            }
+        }
+
+        let app_handle = app_handle.clone();
+        let active_connections = active_connections.clone();
+        tokio::spawn(async move {
+            let task_name = format!("idle:{}:{}", account_id, POLLER_KEY);
+            let loop_folder_names = folder_names.clone();
+            watchdog::supervise(task_name, IDLE_STALL_THRESHOLD, move |heartbeat| {
+                poll_connection_loop(
+                    app_handle.clone(),
+                    account_id,
+                    loop_folder_names.clone(),
+                    config.clone(),
+                    heartbeat,
+                )
+            })
+            .await;
+
+            let mut active = active_connections.lock().unwrap();
+            for folder_name in folder_names {
+                active.remove(&(account_id, folder_name));
+            }
+        })
+    }
+
     /// Check if a connection is active
     pub fn is_active(&self, account_id: i32, folder_name: &str) -> bool {
         self.active_connections
@@ -2,11 +2,18 @@
 // This module manages IMAP IDLE connections for real-time email notifications
 
 // Sub-modules
+mod auto_sync;
 mod manager;
 mod notification;
+mod polling;
 mod session;
+mod settings;
+mod status;
 mod types;
 
 // Re-export public types and manager
+pub use auto_sync::is_syncing;
 pub use manager::IdleManager;
+pub use settings::{get_idle_folders, set_idle_folders};
+pub use status::get_idle_status;
 pub use types::IdleCommand;
@@ -1,6 +1,8 @@
 // Notification system for IDLE events
 // This module handles desktop notifications
 
+use crate::commands::date_format::{display_zone, format_email_date};
+use crate::commands::notification_sounds::next_sound;
 use crate::db;
 use tauri::{AppHandle, Emitter};
 use tauri_plugin_notification::NotificationExt;
@@ -57,8 +59,8 @@ pub async fn send_notification(
 
     // Fetch latest email info for notification
     let pool = db::pool();
-    let latest_email = sqlx::query_as::<_, (String, String)>(
-        "SELECT subject, from_addr FROM emails
+    let latest_email = sqlx::query_as::<_, (String, String, i64, Option<i32>)>(
+        "SELECT subject, from_addr, timestamp, date_offset FROM emails
          WHERE account_id = ? AND folder_name = ?
          ORDER BY timestamp DESC LIMIT 1",
     )
@@ -68,14 +70,15 @@ pub async fn send_notification(
     .await;
 
     if notification_enabled {
-        if let Ok(Some((subject, from))) = latest_email {
+        if let Ok(Some((subject, from, timestamp, date_offset))) = latest_email {
             let title = if count == 1 {
                 "New Email".to_string()
             } else {
                 format!("{} New Emails", count)
             };
 
-            let body = format!("From: {}\nSubject: {}", from, subject);
+            let date = format_email_date(timestamp, date_offset, display_zone().await);
+            let body = format!("From: {}\nSubject: {}\nDate: {}", from, subject, date);
 
             tracing::info!(
                 title = %title,
@@ -112,10 +115,12 @@ pub async fn send_notification(
         tracing::debug!("Notifications are disabled in settings");
     }
 
-    // Play notification sound if enabled
+    // Play notification sound if enabled (at most one per burst of mail)
     if sound_enabled {
-        // Emit event to frontend to play sound
-        let _ = app_handle.emit("play-notification-sound", ());
-        tracing::debug!("Triggered notification sound");
+        if let Some(sound) = next_sound(account_id).await {
+            // Emit event to frontend to play sound
+            let _ = app_handle.emit("play-notification-sound", sound);
+            tracing::debug!("Triggered notification sound");
+        }
     }
 }
@@ -0,0 +1,121 @@
+// Polling for folders that aren't watched with IDLE
+// One long-lived connection runs STATUS on each of its folders every poll interval, and message
+// count growth is announced exactly like an IDLE EXISTS. This covers servers without IDLE
+// (RFC 2177) and an account's secondary folders, which share a single connection instead of
+// each holding an IDLE connection open. STATUS doesn't SELECT anything, so it also serves as
+// the keepalive the server would otherwise get from NOOP.
+
+use super::session::announce_new_messages;
+use super::status::{self, ConnectionState};
+use crate::commands::account_health;
+use crate::commands::emails::imap_helpers;
+use crate::commands::get_idle_poll_interval;
+use crate::commands::utils::ensure_valid_token;
+use crate::models::AccountConfig;
+use crate::watchdog::Heartbeat;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// Poll intervals are kept inside the watchdog's stall threshold and above what servers tolerate
+const MIN_POLL_INTERVAL: u64 = 30;
+const MAX_POLL_INTERVAL: u64 = 30 * 60;
+
+/// Poll interval from settings, clamped to a sane range
+async fn poll_interval() -> Duration {
+    let seconds = get_idle_poll_interval().await.unwrap_or(120).max(0) as u64;
+    Duration::from_secs(seconds.clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL))
+}
+
+/// Run a single polling session over `folder_names`, returning when the connection fails or
+/// the app shuts down
+pub async fn poll_session(
+    app_handle: &AppHandle,
+    account_id: i32,
+    folder_names: &[String],
+    config: &AccountConfig,
+    heartbeat: &Heartbeat,
+) -> Result<(), String> {
+    let interval = poll_interval().await;
+    let config_refreshed = ensure_valid_token(config.clone()).await?;
+
+    let folder_names = folder_names.to_vec();
+    let app_handle_clone = app_handle.clone();
+    let shutdown_token = crate::shutdown::token();
+    let heartbeat = heartbeat.clone();
+
+    tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let mut imap_session = imap_helpers::connect_and_login(&config_refreshed)?;
+        account_health::record_success(&app_handle_clone, account_id);
+
+        // Message count of every folder whose STATUS succeeded; fails only if none did, which
+        // means the connection is gone rather than a folder
+        let mut message_counts = || -> Result<HashMap<&str, u32>, String> {
+            let mut counts = HashMap::new();
+            let mut last_error = None;
+            for folder_name in &folder_names {
+                match imap_session.status(folder_name, "(MESSAGES)") {
+                    Ok(mailbox) => {
+                        counts.insert(folder_name.as_str(), mailbox.exists);
+                    }
+                    Err(e) => {
+                        tracing::warn!(folder = %folder_name, error = %e, "Poll: STATUS failed");
+                        last_error = Some(format!("STATUS failed for '{}': {}", folder_name, e));
+                    }
+                }
+            }
+            match last_error {
+                Some(e) if counts.is_empty() => Err(e),
+                _ => Ok(counts),
+            }
+        };
+
+        let mut prev_counts = message_counts()?;
+        status::set_state(account_id, &folder_names, ConnectionState::Polling);
+        tracing::info!(
+            account_id = account_id,
+            folders = folder_names.len(),
+            interval_secs = interval.as_secs(),
+            "Polling mode activated"
+        );
+
+        let result = loop {
+            // Sleep in short steps so shutdown and the watchdog aren't kept waiting
+            let started = Instant::now();
+            while started.elapsed() < interval && !shutdown_token.is_cancelled() {
+                heartbeat.beat();
+                std::thread::sleep(Duration::from_secs(1));
+            }
+            if shutdown_token.is_cancelled() {
+                break Ok(());
+            }
+
+            let counts = match message_counts() {
+                Ok(counts) => counts,
+                Err(e) => break Err(e),
+            };
+            for (folder_name, exists) in &counts {
+                let prev_exists = prev_counts.get(folder_name).copied().unwrap_or(*exists);
+                tracing::debug!(folder = %folder_name, exists = exists, "Poll: STATUS");
+
+                if *exists > prev_exists {
+                    let new_count = exists - prev_exists;
+                    tracing::info!(folder = %folder_name, count = new_count, "Detected new message(s)");
+                    announce_new_messages(
+                        &app_handle_clone,
+                        account_id,
+                        folder_name,
+                        &config_refreshed,
+                        new_count,
+                    );
+                }
+            }
+            prev_counts = counts;
+        };
+
+        let _ = imap_session.logout();
+        result
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
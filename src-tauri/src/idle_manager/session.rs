@@ -1,13 +1,21 @@
 // IDLE session handling
 // This module manages individual IMAP IDLE sessions and connection loops
 
-use super::notification::send_notification;
+use super::auto_sync::sync_new_messages;
+use super::polling::poll_session;
+use super::status::{self, ConnectionState};
 use super::types::{IdleEvent, IdleEventType};
+use crate::commands::account_health;
 use crate::commands::emails::imap_helpers;
+use crate::commands::event_buffer::emit_buffered;
 use crate::commands::utils::ensure_valid_token;
+use crate::db;
 use crate::models::AccountConfig;
+use crate::watchdog::Heartbeat;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
+
+type ImapSession = imap::Session<Box<dyn imap::ImapConnection>>;
 
 /// IDLE connection loop for a single folder
 pub async fn idle_connection_loop(
@@ -15,15 +23,95 @@ pub async fn idle_connection_loop(
     account_id: i32,
     folder_name: String,
     config: AccountConfig,
+    heartbeat: Heartbeat,
+) {
+    connection_loop(
+        app_handle,
+        account_id,
+        vec![folder_name],
+        false,
+        config,
+        heartbeat,
+    )
+    .await
+}
+
+/// Polling connection loop for an account's secondary folders
+pub async fn poll_connection_loop(
+    app_handle: AppHandle,
+    account_id: i32,
+    folder_names: Vec<String>,
+    config: AccountConfig,
+    heartbeat: Heartbeat,
 ) {
+    connection_loop(
+        app_handle,
+        account_id,
+        folder_names,
+        true,
+        config,
+        heartbeat,
+    )
+    .await
+}
+
+/// Keep a connection watching `folder_names` alive, reconnecting after errors
+/// IDLE watches the first folder only; polling covers all of them.
+async fn connection_loop(
+    app_handle: AppHandle,
+    account_id: i32,
+    folder_names: Vec<String>,
+    mut polling: bool,
+    config: AccountConfig,
+    heartbeat: Heartbeat,
+) {
+    let shutdown_token = crate::shutdown::token();
+    let Some(folder_name) = folder_names.first().cloned() else {
+        return;
+    };
+    let mut reconnecting = false;
+
     loop {
+        if shutdown_token.is_cancelled() {
+            break;
+        }
+        heartbeat.beat();
+
+        if reconnecting {
+            status::record_reconnect(account_id, &folder_names);
+        } else {
+            status::set_state(account_id, &folder_names, ConnectionState::Connecting);
+        }
+
         tracing::info!(
             account_id = account_id,
             folder = %folder_name,
+            folders = folder_names.len(),
+            polling = polling,
             "Establishing IDLE connection"
         );
 
-        match idle_session(&app_handle, account_id, &folder_name, &config).await {
+        // Servers without IDLE are polled instead (see `polling`)
+        let session = async {
+            if polling {
+                poll_session(&app_handle, account_id, &folder_names, &config, &heartbeat).await
+            } else {
+                idle_session(&app_handle, account_id, &folder_name, &config, &heartbeat).await
+            }
+        };
+        let result = tokio::select! {
+            result = session => result,
+            _ = shutdown_token.cancelled() => {
+                tracing::info!(
+                    account_id = account_id,
+                    folder = %folder_name,
+                    "IDLE loop stopped for shutdown"
+                );
+                break;
+            }
+        };
+
+        match result {
             Ok(_) => {
                 tracing::info!(
                     account_id = account_id,
@@ -38,32 +126,100 @@ pub async fn idle_connection_loop(
                     error = %e,
                     "IDLE session error"
                 );
+                status::record_error(account_id, &folder_names, &e);
 
-                // Check if error is due to IDLE not being supported
-                if e.contains("does not support IDLE") {
-                    tracing::warn!("IDLE not supported by server, stopping IDLE monitoring for this account/folder");
-                    tracing::info!(
-                        "Tip: Use manual sync (Sync Mail button) to check for new emails"
+                // Without IDLE, fall back to polling right away instead of giving up
+                if !polling && e.contains("does not support IDLE") {
+                    tracing::warn!(
+                        "IDLE not supported by server, polling this account/folder instead"
                     );
-                    // Stop the IDLE loop for this account/folder
+                    polling = true;
+                    continue;
+                }
+
+                // Reconnecting can't fix bad credentials; wait for the user to sign in again
+                account_health::record_failure(&app_handle, account_id, e);
+                if account_health::needs_attention(account_id) {
+                    tracing::warn!(
+                        account_id = account_id,
+                        folder = %folder_name,
+                        "Stopping IDLE until the account signs in again"
+                    );
+                    status::set_state(account_id, &folder_names, ConnectionState::Stopped);
                     break;
                 }
 
                 // Emit connection lost event
-                let _ = app_handle.emit(
-                    "idle-event",
-                    IdleEvent {
-                        account_id,
-                        folder_name: folder_name.clone(),
-                        event_type: IdleEventType::ConnectionLost,
-                    },
-                );
+                for folder_name in &folder_names {
+                    emit_buffered(
+                        &app_handle,
+                        "idle-event",
+                        IdleEvent {
+                            account_id,
+                            folder_name: folder_name.clone(),
+                            event_type: IdleEventType::ConnectionLost,
+                        },
+                    );
+                }
             }
         }
 
         // Wait before reconnecting (exponential backoff would be better)
+        reconnecting = true;
         tracing::info!("Waiting 30 seconds before reconnecting...");
-        tokio::time::sleep(Duration::from_secs(30)).await;
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(30)) => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+    }
+}
+
+/// Tell the frontend about new messages in a folder and sync it right away
+/// The desktop notification is sent once the sync has cached the new mail.
+pub(super) fn announce_new_messages(
+    app_handle: &AppHandle,
+    account_id: i32,
+    folder_name: &str,
+    config: &AccountConfig,
+    count: u32,
+) {
+    status::record_event(account_id, folder_name);
+    emit_buffered(
+        app_handle,
+        "idle-event",
+        IdleEvent {
+            account_id,
+            folder_name: folder_name.to_string(),
+            event_type: IdleEventType::NewMessages { count },
+        },
+    );
+
+    sync_new_messages(app_handle, account_id, folder_name, config, count);
+}
+
+/// UIDs of the selected folder in sequence number order
+fn load_uids(imap_session: &mut ImapSession) -> Result<Vec<u32>, String> {
+    let mut uids: Vec<u32> = imap_session
+        .uid_search("ALL")
+        .map_err(|e| format!("Failed to list UIDs: {}", e))?
+        .into_iter()
+        .collect();
+    uids.sort_unstable();
+    Ok(uids)
+}
+
+/// Remove a message expunged on the server from the cache
+async fn remove_cached_email(account_id: i32, folder_name: &str, uid: u32) {
+    let pool = db::pool();
+    let result =
+        sqlx::query("DELETE FROM emails WHERE account_id = ? AND folder_name = ? AND uid = ?")
+            .bind(account_id)
+            .bind(folder_name)
+            .bind(uid as i64)
+            .execute(pool.as_ref())
+            .await;
+    if let Err(e) = result {
+        tracing::error!(uid = uid, error = %e, "Failed to remove expunged email from cache");
     }
 }
 
@@ -73,7 +229,9 @@ async fn idle_session(
     account_id: i32,
     folder_name: &str,
     config: &AccountConfig,
+    heartbeat: &Heartbeat,
 ) -> Result<(), String> {
+    use imap::extensions::idle::WaitOutcome;
     use imap::types::UnsolicitedResponse;
 
     // Ensure we have a valid access token (refresh if needed)
@@ -82,12 +240,15 @@ async fn idle_session(
     let config_clone = config_refreshed.clone();
     let folder_name_owned = folder_name.to_string();
     let app_handle_clone = app_handle.clone();
+    let shutdown_token = crate::shutdown::token();
+    let heartbeat = heartbeat.clone();
 
     tokio::task::spawn_blocking(move || {
         // Use helper function for connection with imap 3.0.0 API
         let mut imap_session = imap_helpers::connect_and_login(&config_clone)?;
 
         tracing::info!("IDLE IMAP authentication successful");
+        account_health::record_success(&app_handle_clone, account_id);
 
         // Check if server supports IDLE capability
         let capabilities = imap_session
@@ -99,7 +260,7 @@ async fn idle_session(
 
         if !has_idle {
             tracing::warn!("Server does not support IDLE capability (RFC 2177)");
-            tracing::info!("Use manual sync (Sync Mail button) to check for new emails");
+            let _ = imap_session.logout();
             return Err("Server does not support IDLE extension (RFC 2177)".to_string());
         }
 
@@ -114,111 +275,168 @@ async fn idle_session(
             folder = %folder_name_owned,
             "IDLE mode activated"
         );
+        status::set_state(
+            account_id,
+            std::slice::from_ref(&folder_name_owned),
+            ConnectionState::Idle,
+        );
         tracing::info!(
             exists = mailbox.exists,
             recent = mailbox.recent,
             "Initial mailbox state"
         );
 
-        // Track initial state
+        // Track initial state. Untagged EXPUNGE and FETCH responses carry sequence numbers,
+        // so keep the UID of every sequence number (UIDs ascend with sequence numbers)
         let mut prev_exists = mailbox.exists;
+        let mut uids = load_uids(&mut imap_session)?;
 
-        // Start IDLE session with imap 3.0.0 API
-        // Note: .idle() returns Handle directly, not Result
-        let mut idle_handle = imap_session.idle();
+        // Re-enter IDLE every 29 minutes (per RFC 2177) ourselves instead of relying on the
+        // handle's keepalive, so each round trip shows up as a watchdog heartbeat
+        let wait_result = loop {
+            heartbeat.beat();
 
-        // Set keepalive to true (default, but explicit for clarity)
-        idle_handle.keepalive(true);
+            // Start IDLE session with imap 3.0.0 API
+            // Note: .idle() returns Handle directly, not Result
+            let mut idle_handle = imap_session.idle();
+            idle_handle.keepalive(false);
+            idle_handle.timeout(Duration::from_secs(29 * 60));
 
-        // Set timeout to 29 minutes (default, per RFC 2177)
-        idle_handle.timeout(Duration::from_secs(29 * 60));
+            tracing::debug!("IDLE waiting for changes...");
 
-        tracing::debug!("IDLE waiting for changes...");
+            // Wait for mailbox changes using the new wait_while API
+            let wait_result = idle_handle.wait_while(|response: UnsolicitedResponse| {
+                heartbeat.beat();
+
+                // Stop waiting at the next server response once the app is shutting down
+                if shutdown_token.is_cancelled() {
+                    return false;
+                }
+
+                match response {
+                    UnsolicitedResponse::Exists(count) => {
+                        tracing::debug!(count = count, "IDLE: EXISTS");
+
+                        // Detect new messages
+                        if count > prev_exists {
+                            let new_count = count - prev_exists;
+                            tracing::info!(count = new_count, "Detected new message(s)");
+                            announce_new_messages(
+                                &app_handle_clone,
+                                account_id,
+                                &folder_name_owned,
+                                &config_clone,
+                                new_count,
+                            );
+                            prev_exists = count;
 
-        // Wait for mailbox changes using the new wait_while API
-        let wait_result = idle_handle.wait_while(|response: UnsolicitedResponse| {
-            match response {
-                UnsolicitedResponse::Exists(count) => {
-                    tracing::debug!(count = count, "IDLE: EXISTS");
+                            // Leave IDLE to learn the new messages' UIDs
+                            return false;
+                        }
 
-                    // Detect new messages
-                    if count > prev_exists {
-                        let new_count = count - prev_exists;
-                        tracing::info!(count = new_count, "Detected new message(s)");
+                        prev_exists = count;
 
-                        // Emit event to frontend
-                        let _ = app_handle_clone.emit(
+                        // Continue waiting for more changes instead of stopping IDLE
+                        true
+                    }
+                    UnsolicitedResponse::Recent(count) => {
+                        tracing::debug!(count = count, "IDLE: RECENT");
+                        // Continue waiting
+                        true
+                    }
+                    UnsolicitedResponse::Expunge(seq) => {
+                        tracing::info!(seq = seq, "IDLE: EXPUNGE");
+
+                        // An expunge shifts every later sequence number down by one
+                        let index = seq.saturating_sub(1) as usize;
+                        if seq == 0 || index >= uids.len() {
+                            tracing::warn!(seq = seq, "IDLE: EXPUNGE for unknown sequence number");
+                            return false;
+                        }
+                        let uid = uids.remove(index);
+                        status::record_event(account_id, &folder_name_owned);
+                        prev_exists = prev_exists.saturating_sub(1);
+
+                        // Drop it from the cache, then tell the frontend
+                        let folder_name_for_cache = folder_name_owned.clone();
+                        tokio::spawn(async move {
+                            remove_cached_email(account_id, &folder_name_for_cache, uid).await;
+                        });
+                        emit_buffered(
+                            &app_handle_clone,
                             "idle-event",
                             IdleEvent {
                                 account_id,
                                 folder_name: folder_name_owned.clone(),
-                                event_type: IdleEventType::NewMessages { count: new_count },
+                                event_type: IdleEventType::Expunge { uid },
                             },
                         );
 
-                        // Send desktop notification
-                        let app_handle_clone2 = app_handle_clone.clone();
-                        let folder_name_clone = folder_name_owned.clone();
-                        tokio::spawn(async move {
-                            send_notification(
-                                &app_handle_clone2,
-                                account_id,
-                                &folder_name_clone,
-                                new_count,
-                            )
-                            .await;
-                        });
+                        // Continue waiting
+                        true
+                    }
+                    UnsolicitedResponse::Fetch { id, .. } => {
+                        tracing::debug!(id = id, "IDLE: FETCH");
+
+                        // Emit flags changed event
+                        match uids.get(id.saturating_sub(1) as usize) {
+                            Some(&uid) if id > 0 => {
+                                status::record_event(account_id, &folder_name_owned);
+                                emit_buffered(
+                                    &app_handle_clone,
+                                    "idle-event",
+                                    IdleEvent {
+                                        account_id,
+                                        folder_name: folder_name_owned.clone(),
+                                        event_type: IdleEventType::FlagsChanged { uid },
+                                    },
+                                );
+                            }
+                            _ => {
+                                tracing::debug!(id = id, "IDLE: FETCH for unknown sequence number")
+                            }
+                        }
+
+                        // Continue waiting
+                        true
+                    }
+                    _ => {
+                        tracing::debug!(response = ?response, "IDLE: Other response");
+                        // Continue waiting for other responses
+                        true
                     }
-
-                    prev_exists = count;
-
-                    // Continue waiting for more changes instead of stopping IDLE
-                    true
-                }
-                UnsolicitedResponse::Recent(count) => {
-                    tracing::debug!(count = count, "IDLE: RECENT");
-                    // Continue waiting
-                    true
-                }
-                UnsolicitedResponse::Expunge(seq) => {
-                    tracing::info!(seq = seq, "IDLE: EXPUNGE");
-
-                    // Emit expunge event
-                    let _ = app_handle_clone.emit(
-                        "idle-event",
-                        IdleEvent {
-                            account_id,
-                            folder_name: folder_name_owned.clone(),
-                            event_type: IdleEventType::Expunge { uid: seq },
-                        },
-                    );
-
-                    // Continue waiting
-                    true
                 }
-                UnsolicitedResponse::Fetch { id, .. } => {
-                    tracing::debug!(id = id, "IDLE: FETCH");
-
-                    // Emit flags changed event
-                    let _ = app_handle_clone.emit(
-                        "idle-event",
-                        IdleEvent {
+            });
+
+            // Ending the IDLE handle sends DONE
+            drop(idle_handle);
+
+            match wait_result {
+                Ok(WaitOutcome::TimedOut) if !shutdown_token.is_cancelled() => continue,
+                Ok(WaitOutcome::MailboxChanged) if !shutdown_token.is_cancelled() => {
+                    // Refresh the sequence map; mail that arrived outside IDLE shows up here
+                    uids = load_uids(&mut imap_session)?;
+                    let exists = uids.len() as u32;
+                    if exists > prev_exists {
+                        announce_new_messages(
+                            &app_handle_clone,
                             account_id,
-                            folder_name: folder_name_owned.clone(),
-                            event_type: IdleEventType::FlagsChanged { uid: id },
-                        },
-                    );
-
-                    // Continue waiting
-                    true
-                }
-                _ => {
-                    tracing::debug!(response = ?response, "IDLE: Other response");
-                    // Continue waiting for other responses
-                    true
+                            &folder_name_owned,
+                            &config_clone,
+                            exists - prev_exists,
+                        );
+                    }
+                    prev_exists = exists;
+                    continue;
                 }
+                other => break other,
             }
-        });
+        };
+
+        // Log out cleanly if we stopped for shutdown
+        if shutdown_token.is_cancelled() {
+            let _ = imap_session.logout();
+        }
 
         match wait_result {
             Ok(_outcome) => {
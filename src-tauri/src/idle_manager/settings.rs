@@ -0,0 +1,64 @@
+// IDLE folder settings
+// Each IDLE needs its own connection, and providers cap concurrent connections (Gmail allows
+// 15 per account), so only INBOX and a short per-account priority list are kept in IDLE. Every
+// other folder is polled over one shared connection (see `polling`).
+
+use crate::db;
+use tauri::command;
+
+/// IDLE connections per account, including INBOX
+pub const MAX_IDLE_FOLDERS: usize = 3;
+
+/// Load the folders an account keeps in IDLE besides INBOX
+pub async fn load_idle_folders(account_id: i32) -> Result<Vec<String>, String> {
+    let pool = db::pool();
+    let value =
+        sqlx::query_as::<_, (Option<String>,)>("SELECT idle_folders FROM accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load IDLE folders: {}", e))?
+            .ok_or("Account not found")?;
+
+    Ok(value
+        .0
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+/// Get the folders an account keeps in IDLE besides INBOX
+#[command]
+pub async fn get_idle_folders(account_id: i32) -> Result<Vec<String>, String> {
+    load_idle_folders(account_id).await
+}
+
+/// Choose the folders an account keeps in IDLE besides INBOX
+/// Takes effect the next time IDLE starts for the account.
+#[command]
+pub async fn set_idle_folders(account_id: i32, folders: Vec<String>) -> Result<(), String> {
+    let mut folders: Vec<String> = folders
+        .into_iter()
+        .filter(|folder| !folder.eq_ignore_ascii_case("INBOX"))
+        .collect();
+    folders.sort();
+    folders.dedup();
+    if folders.len() >= MAX_IDLE_FOLDERS {
+        return Err(format!(
+            "At most {} folders besides INBOX can be watched with IDLE",
+            MAX_IDLE_FOLDERS - 1
+        ));
+    }
+
+    let json = serde_json::to_string(&folders)
+        .map_err(|e| format!("Failed to serialize IDLE folders: {}", e))?;
+    let pool = db::pool();
+    sqlx::query("UPDATE accounts SET idle_folders = ? WHERE id = ?")
+        .bind(json)
+        .bind(account_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save IDLE folders: {}", e))?;
+
+    println!("⚙️ IDLE folders for account {}: {:?}", account_id, folders);
+    Ok(())
+}
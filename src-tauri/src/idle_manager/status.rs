@@ -0,0 +1,111 @@
+// IDLE connection status
+// Each watched folder's connection state, last mailbox event, reconnect count and last error,
+// so "why am I not getting notifications" can be answered from `get_idle_status` instead of
+// the logs. Entries are updated by the connection loops and removed when IDLE is stopped.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::command;
+
+/// What a watched folder's connection is doing
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    Idle,
+    Polling,
+    Reconnecting,
+    Stopped,
+}
+
+/// Status of one watched folder
+#[derive(Serialize, Debug, Clone)]
+pub struct IdleStatus {
+    pub account_id: i32,
+    pub folder_name: String,
+    pub state: ConnectionState,
+    /// When the connection entered its current state (Unix seconds)
+    pub state_since: i64,
+    /// Last new mail, expunge or flag change seen (Unix seconds)
+    pub last_event_at: Option<i64>,
+    pub reconnect_count: u32,
+    pub last_error: Option<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref STATUSES: Mutex<HashMap<(i32, String), IdleStatus>> = Mutex::new(HashMap::new());
+}
+
+/// Apply `apply` to the status of each folder, creating missing entries
+fn update(account_id: i32, folder_names: &[String], apply: impl Fn(&mut IdleStatus)) {
+    let mut statuses = STATUSES.lock().unwrap();
+    for folder_name in folder_names {
+        let status = statuses
+            .entry((account_id, folder_name.clone()))
+            .or_insert_with(|| IdleStatus {
+                account_id,
+                folder_name: folder_name.clone(),
+                state: ConnectionState::Connecting,
+                state_since: chrono::Utc::now().timestamp(),
+                last_event_at: None,
+                reconnect_count: 0,
+                last_error: None,
+            });
+        apply(status);
+    }
+}
+
+/// Record the connection state of some folders
+pub fn set_state(account_id: i32, folder_names: &[String], state: ConnectionState) {
+    let now = chrono::Utc::now().timestamp();
+    update(account_id, folder_names, |status| {
+        if status.state != state {
+            status.state = state;
+            status.state_since = now;
+        }
+    });
+}
+
+/// Record that some folders' connection is being re-established
+pub fn record_reconnect(account_id: i32, folder_names: &[String]) {
+    set_state(account_id, folder_names, ConnectionState::Reconnecting);
+    update(account_id, folder_names, |status| {
+        status.reconnect_count += 1
+    });
+}
+
+/// Record the error that ended some folders' connection
+pub fn record_error(account_id: i32, folder_names: &[String], error: &str) {
+    update(account_id, folder_names, |status| {
+        status.last_error = Some(error.to_string())
+    });
+}
+
+/// Record a mailbox event on a folder
+pub fn record_event(account_id: i32, folder_name: &str) {
+    let now = chrono::Utc::now().timestamp();
+    update(account_id, &[folder_name.to_string()], |status| {
+        status.last_event_at = Some(now)
+    });
+}
+
+/// Drop the status of an account's folder, or of all its folders if `folder_name` is None
+pub fn forget(account_id: i32, folder_name: Option<&str>) {
+    STATUSES.lock().unwrap().retain(|(acc_id, name), _| {
+        *acc_id != account_id || folder_name.is_some_and(|folder| folder != name)
+    });
+}
+
+/// Drop every status
+pub fn forget_all() {
+    STATUSES.lock().unwrap().clear();
+}
+
+/// Get the IDLE/polling status of every watched folder
+#[command]
+pub fn get_idle_status() -> Result<Vec<IdleStatus>, String> {
+    let mut statuses: Vec<IdleStatus> = STATUSES.lock().unwrap().values().cloned().collect();
+    statuses.sort_by(|a, b| (a.account_id, &a.folder_name).cmp(&(b.account_id, &b.folder_name)));
+    Ok(statuses)
+}
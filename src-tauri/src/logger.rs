@@ -23,8 +23,8 @@ pub fn get_log_dir() -> Result<PathBuf, String> {
 /// This sets up:
 /// - File logging with daily rotation (keeps last 7 days)
 /// - Console logging (only in debug mode)
-/// - Appropriate log levels for production vs development
-pub fn init() -> Result<(), String> {
+/// - Appropriate log levels for production vs development (debug when `verbose`)
+pub fn init(verbose: bool) -> Result<(), String> {
     let log_dir = get_log_dir()?;
 
     // Create a rolling file appender (rotates daily, keeps last 7 days)
@@ -36,12 +36,12 @@ pub fn init() -> Result<(), String> {
         .build(&log_dir)
         .map_err(|e| format!("Failed to create file appender: {}", e))?;
 
-    // Determine log level based on build type
-    #[cfg(debug_assertions)]
-    let default_level = Level::DEBUG;
-
-    #[cfg(not(debug_assertions))]
-    let default_level = Level::INFO;
+    // Determine log level based on build type (safe mode always logs verbosely)
+    let default_level = if verbose || cfg!(debug_assertions) {
+        Level::DEBUG
+    } else {
+        Level::INFO
+    };
 
     // Create environment filter
     // Users can override log level by setting RUST_LOG environment variable
@@ -4,31 +4,74 @@ mod attachment_limits;
 mod commands;
 mod db;
 mod encryption;
+mod faults;
+mod html_text;
 mod idle_manager;
 mod logger;
+mod metrics;
 mod models;
+mod net;
 mod oauth2_config;
+mod safe_mode;
 mod security;
+mod shutdown;
+mod watchdog;
 
 use commands::{
-    change_master_password, check_folder_capabilities, complete_oauth2_flow, create_local_folder,
-    create_remote_folder, delete_account, delete_app_user, delete_draft, delete_email,
-    delete_local_folder, delete_remote_folder, delete_secure_storage,
-    detect_display_name_from_sent, disable_encryption, download_attachment, enable_encryption,
+    archive_email, archive_old_attachments, cancel_onboarding, cancel_pending_send,
+    change_master_password, check_email_before_send, check_folder_capabilities, clear_dns_cache,
+    clear_folder_attachments, clear_folder_bodies, complete_oauth2_flow, confirm_master_password,
+    convert_local_folder_to_remote, convert_remote_folder_to_local, copy_email_to_folder,
+    create_demo_account, create_local_folder, create_remote_folder, delete_account,
+    delete_app_user, delete_automation_hook, delete_contact_group, delete_draft, delete_email,
+    delete_email_note, delete_emails, delete_folder_policy, delete_identity, delete_local_folder,
+    delete_notification_sound, delete_outbox_item, delete_recurring_send, delete_remote_folder,
+    delete_secure_storage, detect_display_name_from_sent, disable_encryption, download_attachment,
+    enable_encryption, expand_recipients, export_config_backup, export_conversation,
     export_logs_as_zip, fetch_email_body, fetch_email_body_cached, fetch_emails, fetch_folders,
-    forward_email, get_app_user, get_attachment_size_limit, get_current_log_file,
-    get_encryption_status, get_last_sync_time, get_log_directory, get_minimize_to_tray,
-    get_notification_enabled, get_secure_storage, get_sound_enabled, get_sync_interval,
-    list_drafts, list_log_files, listen_for_oauth_callback, load_account_configs,
-    load_attachments_info, load_draft, load_emails_from_cache, load_folders,
-    lock_encryption_command, mark_email_as_flagged, mark_email_as_read, mark_email_as_unflagged,
-    mark_email_as_unread, move_email_to_trash, read_log_file, read_recent_logs, reply_email,
-    save_account_config, save_attachment_to_file, save_draft, send_email, set_minimize_to_tray,
-    set_notification_enabled, set_secure_storage, set_sound_enabled, set_sync_interval,
-    should_sync, start_oauth2_flow, sync_app_user, sync_email_flags, sync_emails, sync_folders,
-    sync_specific_email_flags, test_connection, unlock_encryption_with_password,
+    fetch_missed_events, find_duplicate_emails, forward_email, get_account_health,
+    get_account_notification_sound, get_all_folder_status, get_app_user,
+    get_attachment_archive_policy, get_attachment_size_limit, get_audit_log,
+    get_conversation_unread_count, get_current_log_file, get_date_display_zone, get_draft_conflict,
+    get_email_note, get_encryption_status, get_fault_injection, get_folder_status,
+    get_idle_poll_interval, get_language, get_last_sync_time, get_local_store_format,
+    get_log_directory, get_mailbox_quota, get_minimize_to_tray, get_notification_enabled,
+    get_notification_sound_data, get_onboarding_state, get_performance_metrics,
+    get_recurring_send_history, get_reply_all_recipients, get_reprompt_sensitive_commands,
+    get_secure_storage, get_sent_copy_mode, get_sound_enabled, get_storage_usage,
+    get_subscribed_folders_only, get_sync_interval, get_sync_window, get_token_expiry,
+    get_undo_send_delay, get_unlock_cooldown, get_unread_counts_by_sender,
+    get_unread_counts_by_thread, get_watchdog_incidents, get_window_state, import_mailbox,
+    import_notification_sound, list_automation_hooks, list_contact_groups, list_drafts,
+    list_email_notes, list_folder_policies, list_host_overrides, list_identities, list_log_files,
+    list_notification_sounds, list_open_windows, list_outbox, list_plugins, list_recurring_sends,
+    listen_for_oauth_callback, load_account_configs, load_attachments_info, load_draft,
+    load_emails_by_date, load_emails_from_cache, load_folders, load_older_emails,
+    load_unified_inbox, locate_email, lock_encryption_command, mark_as_not_spam, mark_as_spam,
+    mark_email_as_flagged, mark_email_as_read, mark_email_as_unflagged, mark_email_as_unread,
+    mark_emails_as_read, mark_folder_as_read, migrate_local_store, move_email_to_folder,
+    move_email_to_trash, move_emails_to_folder, onboarding_initial_sync,
+    onboarding_test_connection, open_reader_window, open_remote_draft, preview_folder_policy,
+    prioritize_bodystructure, quick_reply, read_config_backup, read_log_file, read_recent_logs,
+    rename_remote_folder, reply_all_email, reply_email, reset_performance_metrics,
+    resolve_draft_conflict, restore_config_backup, restore_window_state, retry_outbox_item,
+    run_folder_policies, run_integrity_check, run_recurring_send_now, save_account_config,
+    save_attachment_to_file, save_automation_hook, save_contact_group, save_draft, save_email_note,
+    save_folder_policy, save_identity, save_last_selection, save_recurring_send, save_window_state,
+    search_attachment_text, search_emails, search_emails_by_header, search_gmail_raw, send_email,
+    set_account_notification_sound, set_account_sync_interval, set_attachment_archive_policy,
+    set_contact_group_opt_out, set_date_display_zone, set_fault_injection,
+    set_folder_sync_interval, set_folder_sync_on_open, set_host_override, set_idle_poll_interval,
+    set_language, set_minimize_to_tray, set_notification_enabled, set_plugin_enabled,
+    set_reprompt_sensitive_commands, set_secure_storage, set_sent_copy_mode, set_sound_enabled,
+    set_subscribed_folders_only, set_sync_interval, set_sync_window, set_undo_send_delay,
+    set_unlock_lockout_policy, should_sync, start_oauth2_flow, start_onboarding,
+    start_reauthorization, subscribe_folder, suggest_reply_identity, sync_all_folders,
+    sync_app_user, sync_email_flags, sync_emails, sync_folders, sync_specific_email_flags,
+    sync_unified_inbox, test_automation_hook, test_connection, unlock_encryption_with_password,
+    unsubscribe_folder, upload_folder_to_remote, verify_cached_bodies, wipe_all_data,
 };
-use idle_manager::{IdleCommand, IdleManager};
+use idle_manager::{get_idle_folders, get_idle_status, set_idle_folders, IdleCommand, IdleManager};
 use models::AccountConfig;
 use std::sync::{Arc, Mutex};
 use tauri::{
@@ -150,13 +193,43 @@ async fn start_idle_for_all_accounts(
 
 #[tokio::main]
 async fn main() {
+    // Safe mode decides the log level, so check it before logging starts
+    let safe_mode = safe_mode::init();
+
     // Initialize logging system first
-    logger::init().expect("Failed to initialize logging system");
+    logger::init(safe_mode).expect("Failed to initialize logging system");
 
     tracing::info!("Starting Colimail application");
+    if safe_mode {
+        tracing::warn!("Safe mode: IDLE, background tasks and auto-sync are disabled");
+    }
 
     db::init().await.expect("Failed to initialize database");
 
+    if let Err(e) = commands::host_overrides::apply_host_overrides().await {
+        tracing::error!(error = %e, "Failed to load host overrides");
+    }
+
+    // Validate cache state before anything reads from it; only report, repairs are up to the user
+    if let Err(e) = run_integrity_check(Some(false)).await {
+        tracing::error!(error = %e, "Startup integrity check failed");
+    }
+
+    if let Err(e) = commands::emails::maildir::migrate_legacy_dirs().await {
+        tracing::error!(error = %e, "Failed to migrate Maildir folders");
+    }
+
+    if !safe_mode {
+        // Apply folder aging policies in the background
+        shutdown::spawn("folder_policies", commands::aging::maintenance_loop());
+
+        // Extract attachment text for search in the background
+        shutdown::spawn(
+            "attachment_index",
+            commands::emails::attachment_index::index_loop(),
+        );
+    }
+
     match load_account_configs().await {
         Ok(accounts) => {
             tracing::info!(
@@ -184,14 +257,13 @@ async fn main() {
                     if let Ok(url) = url::Url::parse(arg) {
                         if url.scheme() == "colimail" && url.host_str() == Some("auth") {
                             // Extract authorization code from query params
-                            if let Some(code) = url.query_pairs().find(|(key, _)| key == "code") {
+                            if let Some(payload) = commands::oauth2::oauth_code_payload(&url) {
                                 tracing::info!("OAuth code received from second instance via deep link");
 
-                                // Emit event to frontend with the code
+                                // Emit event to frontend with the code and the flow it belongs to
                                 if let Some(window) = app.get_webview_window("main") {
-                                    let code_str = code.1.to_string();
-                                    tracing::info!("Emitting oauth-code-received event with code: {}", code_str);
-                                    match window.emit("oauth-code-received", code_str) {
+                                    tracing::info!(state = ?payload.state, "Emitting oauth-code-received event");
+                                    match window.emit("oauth-code-received", payload) {
                                         Ok(_) => tracing::info!("Event emitted successfully"),
                                         Err(e) => tracing::error!("Failed to emit event: {}", e),
                                     }
@@ -223,6 +295,32 @@ async fn main() {
         .setup(|app| {
             oauth2_config::init_credentials(app.handle());
 
+            if !safe_mode::is_enabled() {
+                // Warn before OAuth refresh tokens expire
+                shutdown::spawn(
+                    "token_expiry",
+                    commands::token_expiry::expiry_check_loop(app.handle().clone()),
+                );
+
+                // Deliver recurring sends when they come due
+                shutdown::spawn(
+                    "recurring_sends",
+                    commands::recurring::scheduler_loop(app.handle().clone()),
+                );
+
+                // Retry outgoing messages that couldn't be sent yet
+                shutdown::spawn(
+                    "outbox",
+                    commands::outbox::retry_loop(app.handle().clone()),
+                );
+
+                // Sync folders as their sync interval comes due
+                shutdown::spawn(
+                    "sync_scheduler",
+                    commands::sync_scheduler::scheduler_loop(app.handle().clone()),
+                );
+            }
+
             // Setup deep link handler for OAuth callbacks
             use tauri_plugin_deep_link::DeepLinkExt;
             app.deep_link().register_all()?;
@@ -241,14 +339,13 @@ async fn main() {
                 let url = &urls[0];
                 if url.scheme() == "colimail" && url.host_str() == Some("auth") {
                     // Extract authorization code from query params
-                    if let Some(code) = url.query_pairs().find(|(key, _)| key == "code") {
+                    if let Some(payload) = commands::oauth2::oauth_code_payload(url) {
                         tracing::info!("Deep link plugin: OAuth code found, emitting oauth-code-received event");
 
-                        // Emit event to frontend with the code
+                        // Emit event to frontend with the code and the flow it belongs to
                         if let Some(window) = handle.get_webview_window("main") {
-                            let code_str = code.1.to_string();
-                            tracing::info!("Deep link plugin: Emitting event with code: {}", code_str);
-                            match window.emit("oauth-code-received", code_str) {
+                            tracing::info!(state = ?payload.state, "Deep link plugin: Emitting event");
+                            match window.emit("oauth-code-received", payload) {
                                 Ok(_) => tracing::info!("Deep link plugin: Event emitted successfully"),
                                 Err(e) => tracing::error!("Deep link plugin: Failed to emit event: {}", e),
                             }
@@ -332,12 +429,26 @@ async fn main() {
                 .build(app)?;
 
             // Handle window close event - minimize to tray or close based on user setting
+            // (the window's geometry is saved first and restored here on startup)
             if let Some(window) = app.get_webview_window("main") {
+                let window_clone = window.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = commands::window_state::apply(&window_clone).await {
+                        tracing::warn!(error = %e, "Failed to restore window state");
+                    }
+                });
+
                 let app_handle = app.handle().clone();
                 window.on_window_event(move |event| {
                     if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                         let app_handle_clone = app_handle.clone();
                         tauri::async_runtime::spawn(async move {
+                            if let Some(window) = app_handle_clone.get_webview_window("main") {
+                                if let Err(e) = commands::window_state::capture(&window).await {
+                                    tracing::warn!(error = %e, "Failed to save window state");
+                                }
+                            }
+
                             // Check user preference for minimize to tray
                             let minimize_to_tray = get_minimize_to_tray().await.unwrap_or(true);
 
@@ -452,6 +563,8 @@ async fn main() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            safe_mode::get_safe_mode,
+            safe_mode::restart_in_safe_mode,
             save_account_config,
             load_account_configs,
             delete_account,
@@ -487,6 +600,9 @@ async fn main() {
             delete_remote_folder,
             create_local_folder,
             delete_local_folder,
+            upload_folder_to_remote,
+            convert_local_folder_to_remote,
+            convert_remote_folder_to_local,
             start_oauth2_flow,
             listen_for_oauth_callback,
             complete_oauth2_flow,
@@ -528,8 +644,159 @@ async fn main() {
             disable_encryption,
             unlock_encryption_with_password,
             lock_encryption_command,
-            change_master_password
+            change_master_password,
+            run_integrity_check,
+            confirm_master_password,
+            get_reprompt_sensitive_commands,
+            set_reprompt_sensitive_commands,
+            get_audit_log,
+            get_unlock_cooldown,
+            set_unlock_lockout_policy,
+            wipe_all_data,
+            open_remote_draft,
+            get_draft_conflict,
+            resolve_draft_conflict,
+            get_watchdog_incidents,
+            find_duplicate_emails,
+            verify_cached_bodies,
+            prioritize_bodystructure,
+            get_unread_counts_by_sender,
+            list_folder_policies,
+            save_folder_policy,
+            delete_folder_policy,
+            preview_folder_policy,
+            run_folder_policies,
+            get_storage_usage,
+            clear_folder_bodies,
+            clear_folder_attachments,
+            list_automation_hooks,
+            save_automation_hook,
+            delete_automation_hook,
+            test_automation_hook,
+            list_plugins,
+            set_plugin_enabled,
+            import_mailbox,
+            get_local_store_format,
+            migrate_local_store,
+            delete_email_note,
+            get_email_note,
+            list_email_notes,
+            save_email_note,
+            check_email_before_send,
+            export_conversation,
+            search_emails_by_header,
+            mark_folder_as_read,
+            list_host_overrides,
+            set_host_override,
+            clear_dns_cache,
+            get_account_health,
+            get_token_expiry,
+            start_reauthorization,
+            start_onboarding,
+            get_onboarding_state,
+            cancel_onboarding,
+            onboarding_test_connection,
+            onboarding_initial_sync,
+            create_demo_account,
+            set_fault_injection,
+            get_fault_injection,
+            get_sent_copy_mode,
+            set_sent_copy_mode,
+            set_folder_sync_on_open,
+            load_emails_by_date,
+            get_date_display_zone,
+            set_date_display_zone,
+            locate_email,
+            search_attachment_text,
+            archive_old_attachments,
+            get_attachment_archive_policy,
+            set_attachment_archive_policy,
+            delete_recurring_send,
+            get_recurring_send_history,
+            list_recurring_sends,
+            run_recurring_send_now,
+            save_recurring_send,
+            quick_reply,
+            delete_contact_group,
+            expand_recipients,
+            list_contact_groups,
+            save_contact_group,
+            set_contact_group_opt_out,
+            delete_identity,
+            list_identities,
+            save_identity,
+            suggest_reply_identity,
+            fetch_missed_events,
+            get_window_state,
+            restore_window_state,
+            save_last_selection,
+            save_window_state,
+            list_open_windows,
+            open_reader_window,
+            delete_notification_sound,
+            get_account_notification_sound,
+            get_notification_sound_data,
+            import_notification_sound,
+            list_notification_sounds,
+            set_account_notification_sound,
+            get_language,
+            set_language,
+            export_config_backup,
+            read_config_backup,
+            restore_config_backup,
+            get_performance_metrics,
+            reset_performance_metrics,
+            search_emails,
+            load_unified_inbox,
+            sync_unified_inbox,
+            copy_email_to_folder,
+            move_email_to_folder,
+            archive_email,
+            delete_emails,
+            mark_emails_as_read,
+            move_emails_to_folder,
+            mark_as_not_spam,
+            mark_as_spam,
+            delete_outbox_item,
+            list_outbox,
+            retry_outbox_item,
+            cancel_pending_send,
+            get_undo_send_delay,
+            set_undo_send_delay,
+            get_reply_all_recipients,
+            reply_all_email,
+            search_gmail_raw,
+            rename_remote_folder,
+            get_all_folder_status,
+            get_folder_status,
+            get_mailbox_quota,
+            subscribe_folder,
+            unsubscribe_folder,
+            get_subscribed_folders_only,
+            set_subscribed_folders_only,
+            get_idle_poll_interval,
+            set_idle_poll_interval,
+            get_idle_folders,
+            set_idle_folders,
+            get_idle_status,
+            set_account_sync_interval,
+            set_folder_sync_interval,
+            sync_all_folders,
+            get_sync_window,
+            set_sync_window,
+            load_older_emails,
+            get_unread_counts_by_thread,
+            get_conversation_unread_count,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Cancel background tasks and flush the database before the process ends
+                tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current()
+                        .block_on(shutdown::shutdown(std::time::Duration::from_secs(5)));
+                });
+            }
+        });
 }
@@ -0,0 +1,158 @@
+// Local performance metrics
+// Timings (sync duration, IMAP connect/login latency, body fetches), throughput and cache
+// hit/miss counts are recorded in memory, with the most recent samples of each series kept
+// in a ring buffer. Nothing leaves the machine unless the user includes the snapshot in a
+// diagnostics export.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Samples kept per series; older ones are dropped first
+const MAX_SAMPLES: usize = 1000;
+
+#[derive(Default)]
+struct Metrics {
+    series: BTreeMap<&'static str, VecDeque<f64>>,
+    caches: BTreeMap<&'static str, (u64, u64)>, // (hits, misses)
+}
+
+lazy_static::lazy_static! {
+    static ref METRICS: Mutex<Metrics> = Mutex::new(Metrics::default());
+}
+
+/// Summary of one series of samples
+#[derive(Serialize, Debug, Clone)]
+pub struct SeriesSummary {
+    pub name: String,
+    pub count: usize,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+/// Hit rate of one cache
+#[derive(Serialize, Debug, Clone)]
+pub struct CacheSummary {
+    pub name: String,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+/// All metrics recorded since startup (or the last reset)
+#[derive(Serialize, Debug, Clone)]
+pub struct PerformanceMetrics {
+    pub series: Vec<SeriesSummary>,
+    pub caches: Vec<CacheSummary>,
+}
+
+/// Record a sample; series names carry their unit, e.g. `sync_ms`
+pub fn record(name: &'static str, value: f64) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        let samples = metrics.series.entry(name).or_default();
+        samples.push_back(value);
+        if samples.len() > MAX_SAMPLES {
+            samples.pop_front();
+        }
+    }
+}
+
+/// Record a duration in milliseconds
+pub fn record_duration(name: &'static str, duration: Duration) {
+    record(name, duration.as_secs_f64() * 1000.0);
+}
+
+/// Run `f` and record how long it took
+pub fn time<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    record_duration(name, started.elapsed());
+    result
+}
+
+/// Count a cache lookup
+pub fn record_cache(name: &'static str, hit: bool) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        let counts = metrics.caches.entry(name).or_default();
+        if hit {
+            counts.0 += 1;
+        } else {
+            counts.1 += 1;
+        }
+    }
+}
+
+/// Value below which `p` (0..=1) of the sorted samples fall (nearest rank)
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Summarise the recorded metrics
+pub fn snapshot() -> PerformanceMetrics {
+    let Ok(metrics) = METRICS.lock() else {
+        return PerformanceMetrics {
+            series: Vec::new(),
+            caches: Vec::new(),
+        };
+    };
+
+    let series = metrics
+        .series
+        .iter()
+        .map(|(name, samples)| {
+            let mut sorted: Vec<f64> = samples.iter().copied().collect();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            SeriesSummary {
+                name: name.to_string(),
+                count: sorted.len(),
+                mean: sorted.iter().sum::<f64>() / sorted.len().max(1) as f64,
+                p50: percentile(&sorted, 0.5),
+                p90: percentile(&sorted, 0.9),
+                p99: percentile(&sorted, 0.99),
+                max: sorted.last().copied().unwrap_or(0.0),
+            }
+        })
+        .collect();
+
+    let caches = metrics
+        .caches
+        .iter()
+        .map(|(name, &(hits, misses))| CacheSummary {
+            name: name.to_string(),
+            hits,
+            misses,
+            hit_rate: hits as f64 / (hits + misses).max(1) as f64,
+        })
+        .collect();
+
+    PerformanceMetrics { series, caches }
+}
+
+/// Forget all recorded metrics
+pub fn reset() {
+    if let Ok(mut metrics) = METRICS.lock() {
+        *metrics = Metrics::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let sorted: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&sorted, 0.5), 50.0);
+        assert_eq!(percentile(&sorted, 0.99), 99.0);
+        assert_eq!(percentile(&[7.0], 0.9), 7.0);
+        assert_eq!(percentile(&[], 0.5), 0.0);
+    }
+}
@@ -41,6 +41,52 @@ pub struct OAuth2StartResponse {
     pub state: String,
 }
 
+/// Payload of the "oauth-code-received" event (deep-link callback)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OAuthCodeReceived {
+    pub code: String,
+    pub state: Option<String>, // Identifies the flow started by start_oauth2_flow
+    pub provider: Option<String>,
+    pub account_hint: Option<String>,
+}
+
+/// Which date email lists are sorted by
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailSortOrder {
+    DateSent,     // Date header
+    DateReceived, // INTERNALDATE
+}
+
+/// Which standard a message is signed or encrypted with
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityScheme {
+    Smime,
+    Pgp,
+}
+
+/// Outcome of checking a message's signature
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    #[default]
+    Unverified, // Not checked (yet)
+    Verified,
+    Invalid,
+    SenderMismatch, // Valid signature, but not from the From address
+}
+
+/// Signature/encryption summary of a message, detected from its MIME structure
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageSecurity {
+    pub scheme: SecurityScheme,
+    pub signed: bool,
+    pub encrypted: bool,
+    #[serde(default)]
+    pub signature: SignatureStatus,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EmailHeader {
     pub uid: u32,
@@ -49,6 +95,8 @@ pub struct EmailHeader {
     pub to: String,
     #[serde(default)]
     pub cc: String, // CC recipients
+    #[serde(default)]
+    pub bcc: String, // BCC recipients (only known for mail sent from this account)
     pub date: String,
     pub timestamp: i64, // Unix timestamp in seconds for sorting and conversion
     #[serde(default)]
@@ -57,6 +105,14 @@ pub struct EmailHeader {
     pub seen: bool, // Read/unread status
     #[serde(default)]
     pub flagged: bool, // Starred/flagged status
+    #[serde(default)]
+    pub message_id: Option<String>, // Message-ID header from the envelope
+    #[serde(default)]
+    pub received_at: Option<i64>, // INTERNALDATE (when the server received it), Unix seconds
+    #[serde(default)]
+    pub security: Option<MessageSecurity>, // None for plain (unsigned, unencrypted) messages
+    #[serde(skip)]
+    pub raw_headers: Vec<(String, String)>, // Searchable raw headers, stored in email_raw_headers
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -77,6 +133,24 @@ pub struct AttachmentInfo {
     pub size: i64,
 }
 
+/// Message and unread counts for one sender
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
+pub struct SenderUnreadCount {
+    pub from_addr: String,
+    pub total: i64,
+    pub unread: i64,
+    pub latest_timestamp: i64,
+}
+
+/// Message and unread counts for one conversation
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
+pub struct ThreadUnreadCount {
+    pub thread_id: String, // Message-ID of the conversation's first message
+    pub total: i64,
+    pub unread: i64,
+    pub latest_timestamp: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Folder {
     pub id: Option<i32>,
@@ -87,14 +161,23 @@ pub struct Folder {
     pub flags: Option<String>,
     #[serde(default)]
     pub is_local: bool, // True for local-only folders, False for remote IMAP folders
+    #[serde(default)]
+    pub total_count: Option<i64>, // Cached STATUS MESSAGES, when the server reported it
+    #[serde(default)]
+    pub unread_count: Option<i64>, // Cached STATUS UNSEEN, when the server reported it
+    #[serde(default)]
+    pub sync_on_open: bool, // Skipped by background syncs; synced when the user opens it
+    #[serde(default)]
+    pub role: Option<String>, // Special-use role (RFC 6154), e.g. "trash" or "sent"
 }
 
 impl Folder {
     /// Check if the folder is selectable (not marked with \Noselect flag)
     pub fn is_selectable(&self) -> bool {
         if let Some(ref flags) = self.flags {
-            // Check if flags contain "Noselect" (case-insensitive)
-            !flags.to_lowercase().contains("noselect")
+            // Check if flags contain "Noselect" or "NonExistent" (case-insensitive)
+            let flags = flags.to_lowercase();
+            !flags.contains("noselect") && !flags.contains("nonexistent")
         } else {
             true // If no flags, assume it's selectable
         }
@@ -161,4 +244,78 @@ pub struct DraftListItem {
     pub draft_type: DraftType,
     pub created_at: i64,
     pub updated_at: i64,
+    #[serde(default)]
+    pub has_conflict: bool, // The server copy changed while this draft had local edits
+}
+
+/// One side of a draft conflict
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DraftVersion {
+    pub to_addr: String,
+    pub cc_addr: String,
+    pub subject: String,
+    pub body: Option<String>, // None if the server copy's body isn't cached yet
+    pub updated_at: i64,
+}
+
+/// A local draft whose server copy was changed elsewhere
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DraftConflict {
+    pub draft_id: i64,
+    pub remote_folder: String,
+    pub remote_uid: u32,
+    pub local: DraftVersion,
+    pub remote: DraftVersion,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DraftConflictResolution {
+    KeepLocal,  // Keep local edits; they replace the server copy on the next save
+    KeepRemote, // Replace the local draft with the server copy
+    KeepBoth,   // Take the server copy and keep the local edits as a separate draft
+}
+
+/// Progress payload for the `append-progress` event
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppendProgress {
+    pub job_id: i64,
+    pub source_folder: String,
+    pub target_folder: String,
+    pub uploaded: i64,
+    pub total: i64,
+}
+
+/// Result of a bulk upload (APPEND) job
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppendJobSummary {
+    pub job_id: i64,
+    pub uploaded: i64,
+    pub total: i64,
+    pub resumed: bool, // True if the job continued an interrupted upload
+}
+
+/// Progress payload for the `folder-conversion-progress` event
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FolderConversionProgress {
+    pub folder_name: String,
+    pub stage: String, // creating, uploading, downloading, deleting_remote, finalizing, rolling_back, completed
+    pub processed: i64,
+    pub total: i64,
+}
+
+/// A single problem found by the integrity check
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IntegrityIssue {
+    pub kind: String,
+    pub description: String,
+    pub count: i64,
+    pub repaired: bool,
+}
+
+/// Result of an integrity check run
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IntegrityReport {
+    pub ok: bool, // True if no issues were found (or all of them were repaired)
+    pub issues: Vec<IntegrityIssue>,
 }
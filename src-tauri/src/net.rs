@@ -0,0 +1,414 @@
+// Connection setup for mail servers
+// Resolves every address of a server and races TCP connects "happy eyeballs" style
+// (RFC 8305): IPv6 and IPv4 addresses are interleaved and a new attempt starts every
+// 250 ms, or as soon as the previous one fails. The first connection to succeed wins, so a
+// broken IPv6 route or a dead address costs a fraction of a second instead of a full TCP
+// timeout. IMAP uses the winning socket directly; lettre connects on its own, so SMTP is
+// pointed at the address that won the last race to that server, which is remembered
+// alongside the DNS cache so a send doesn't cost an extra connection.
+// Lookups are cached for a few minutes and can be pinned per account to a fixed IP (for
+// split-horizon corporate DNS). The OAuth HTTP clients resolve through the same cache.
+
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::transport::smtp::AsyncSmtpTransportBuilder;
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Delay before starting the next connection attempt
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Timeout of a single connection attempt
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Read/write timeout while the IMAP greeting and TLS handshake are exchanged
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a successful lookup is reused (the system resolver doesn't expose record TTLs)
+const DNS_CACHE_TTL: Duration = Duration::from_secs(300);
+
+lazy_static::lazy_static! {
+    static ref DNS_CACHE: Mutex<HashMap<String, (Instant, Vec<IpAddr>)>> =
+        Mutex::new(HashMap::new());
+
+    /// Address that won the last connection race by (lowercased host, port, account id)
+    static ref RACE_WINNERS: Mutex<HashMap<(String, u16, Option<i32>), (Instant, SocketAddr)>> =
+        Mutex::new(HashMap::new());
+
+    /// Host overrides by (account id, lowercased host)
+    static ref HOST_OVERRIDES: RwLock<HashMap<(i32, String), IpAddr>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Replace the host overrides of all accounts
+pub fn set_host_overrides(overrides: impl IntoIterator<Item = (i32, String, IpAddr)>) {
+    let overrides = overrides
+        .into_iter()
+        .map(|(account_id, host, ip)| ((account_id, host.to_lowercase()), ip))
+        .collect();
+    if let Ok(mut map) = HOST_OVERRIDES.write() {
+        *map = overrides;
+    }
+    if let Ok(mut winners) = RACE_WINNERS.lock() {
+        winners.clear();
+    }
+}
+
+/// Forget all cached lookups
+pub fn clear_dns_cache() {
+    if let Ok(mut cache) = DNS_CACHE.lock() {
+        cache.clear();
+    }
+    if let Ok(mut winners) = RACE_WINNERS.lock() {
+        winners.clear();
+    }
+}
+
+/// Forget the race winner of a server, so the next connection races its addresses again
+pub fn forget_race_winner(host: &str, port: u16, account_id: Option<i32>) {
+    if let Ok(mut winners) = RACE_WINNERS.lock() {
+        winners.remove(&(host.to_lowercase(), port, account_id));
+    }
+}
+
+/// Address that recently won a connection race to a server
+fn race_winner(host: &str, port: u16, account_id: Option<i32>) -> Option<SocketAddr> {
+    let key = (host.to_lowercase(), port, account_id);
+    RACE_WINNERS.lock().ok().and_then(|winners| {
+        winners
+            .get(&key)
+            .filter(|(won_at, _)| won_at.elapsed() < DNS_CACHE_TTL)
+            .map(|(_, addr)| *addr)
+    })
+}
+
+/// Resolve the addresses of a server: IP literals as-is, then the account's override, then
+/// the cache, then the system resolver
+pub fn resolve(host: &str, port: u16, account_id: Option<i32>) -> io::Result<Vec<SocketAddr>> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let key = host.to_lowercase();
+
+    if let Some(account_id) = account_id {
+        let overridden = HOST_OVERRIDES
+            .read()
+            .ok()
+            .and_then(|map| map.get(&(account_id, key.clone())).copied());
+        if let Some(ip) = overridden {
+            return Ok(vec![SocketAddr::new(ip, port)]);
+        }
+    }
+
+    let cached = DNS_CACHE.lock().ok().and_then(|cache| {
+        cache
+            .get(&key)
+            .filter(|(resolved_at, _)| resolved_at.elapsed() < DNS_CACHE_TTL)
+            .map(|(_, ips)| ips.clone())
+    });
+    if let Some(ips) = cached {
+        return Ok(ips
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port))
+            .collect());
+    }
+
+    let addrs: Vec<SocketAddr> = (host, port).to_socket_addrs()?.collect();
+    if !addrs.is_empty() {
+        if let Ok(mut cache) = DNS_CACHE.lock() {
+            let ips = addrs.iter().map(|addr| addr.ip()).collect();
+            cache.insert(key, (Instant::now(), ips));
+        }
+    }
+    Ok(addrs)
+}
+
+/// Resolver for reqwest that goes through the cache and the account's overrides
+struct CachedResolver {
+    account_id: Option<i32>,
+}
+
+impl reqwest::dns::Resolve for CachedResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        let account_id = self.account_id;
+        Box::pin(async move {
+            let addrs =
+                tokio::task::spawn_blocking(move || resolve(&host, 0, account_id)).await??;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// HTTP client builder whose lookups use the DNS cache and the account's host overrides
+pub fn http_client_builder(account_id: Option<i32>) -> reqwest::ClientBuilder {
+    reqwest::ClientBuilder::new().dns_resolver(Arc::new(CachedResolver { account_id }))
+}
+
+/// Order addresses for connection attempts: alternate address families, starting with the
+/// family the resolver listed first, and otherwise keep the resolver's order
+fn interleave_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return addrs;
+    };
+    let prefer_v6 = first.is_ipv6();
+    let (mut preferred, mut other): (Vec<_>, Vec<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == prefer_v6);
+    preferred.reverse();
+    other.reverse();
+
+    let mut ordered = Vec::with_capacity(preferred.len() + other.len());
+    loop {
+        match (preferred.pop(), other.pop()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+    ordered
+}
+
+/// Open a TCP connection to a server, racing its addresses
+pub fn connect_tcp(host: &str, port: u16, account_id: Option<i32>) -> io::Result<TcpStream> {
+    let addrs = interleave_families(resolve(host, port, account_id)?);
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No addresses found for {}", host),
+        ));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut next = 0;
+    let mut pending = 0;
+    let mut last_error = None;
+
+    loop {
+        if next < addrs.len() {
+            let addr = addrs[next];
+            let tx = tx.clone();
+            // A late winner is dropped when the receiver is gone
+            std::thread::spawn(move || {
+                let _ = tx.send((addr, TcpStream::connect_timeout(&addr, ATTEMPT_TIMEOUT)));
+            });
+            next += 1;
+            pending += 1;
+        }
+        if pending == 0 {
+            break;
+        }
+
+        let received = if next < addrs.len() {
+            match rx.recv_timeout(ATTEMPT_DELAY) {
+                Ok(received) => received,
+                // Still nothing: start the next attempt alongside the running ones
+                Err(_) => continue,
+            }
+        } else {
+            match rx.recv() {
+                Ok(received) => received,
+                Err(_) => break,
+            }
+        };
+        pending -= 1;
+
+        match received {
+            (addr, Ok(stream)) => {
+                tracing::debug!(host = %host, addr = %addr, "Connected");
+                if let Ok(mut winners) = RACE_WINNERS.lock() {
+                    winners.insert(
+                        (host.to_lowercase(), port, account_id),
+                        (Instant::now(), addr),
+                    );
+                }
+                return Ok(stream);
+            }
+            (addr, Err(e)) => {
+                tracing::debug!(host = %host, addr = %addr, error = %e, "Connection attempt failed");
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No addresses found for {}", host),
+        )
+    }))
+}
+
+/// Read one CRLF-terminated line without reading past it
+fn read_line(stream: &mut TcpStream) -> io::Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while !line.ends_with(b"\r\n") {
+        if stream.read(&mut byte)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Connection closed by server",
+            ));
+        }
+        line.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+/// Read the greeting and switch a plain IMAP connection to TLS with STARTTLS
+fn imap_starttls(stream: &mut TcpStream) -> Result<(), String> {
+    let greeting = read_line(stream).map_err(|e| format!("Failed to read greeting: {}", e))?;
+    if !greeting.starts_with("* OK") {
+        return Err(format!("Unexpected server greeting: {}", greeting));
+    }
+
+    stream
+        .write_all(b"a0 STARTTLS\r\n")
+        .map_err(|e| format!("Failed to send STARTTLS: {}", e))?;
+    loop {
+        let line =
+            read_line(stream).map_err(|e| format!("Failed to read STARTTLS reply: {}", e))?;
+        if let Some(status) = line.strip_prefix("a0 ") {
+            return if status.starts_with("OK") {
+                Ok(())
+            } else {
+                Err(format!("Server refused STARTTLS: {}", status))
+            };
+        }
+    }
+}
+
+/// Connect to an IMAP server over TLS (port 993) or STARTTLS (any other port)
+///
+/// Equivalent to `imap::ClientBuilder::new(host, port).connect()` with the default mode,
+/// but using the raced TCP connection. The greeting has been read when this returns.
+pub fn connect_imap(
+    host: &str,
+    port: u16,
+    account_id: Option<i32>,
+) -> Result<imap::Client<Box<dyn imap::ImapConnection>>, String> {
+    let mut tcp = connect_tcp(host, port, account_id).map_err(|e| e.to_string())?;
+    let socket = tcp.try_clone().map_err(|e| e.to_string())?;
+    let _ = socket.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+    let _ = socket.set_write_timeout(Some(HANDSHAKE_TIMEOUT));
+
+    let implicit_tls = port == 993;
+    if !implicit_tls {
+        imap_starttls(&mut tcp)?;
+    }
+
+    let connector =
+        native_tls::TlsConnector::new().map_err(|e| format!("Failed to initialize TLS: {}", e))?;
+    let tls = connector
+        .connect(host, tcp)
+        .map_err(|e| format!("TLS handshake failed: {}", e))?;
+
+    let mut client = imap::Client::new(Box::new(tls) as Box<dyn imap::ImapConnection>);
+    if implicit_tls {
+        client
+            .read_greeting()
+            .map_err(|e| format!("Failed to read greeting: {}", e))?;
+    }
+
+    // Callers (e.g. IDLE) manage their own timeouts from here on
+    let _ = socket.set_read_timeout(None);
+    let _ = socket.set_write_timeout(None);
+    crate::faults::imap_session(&socket);
+    Ok(client)
+}
+
+/// Start an SMTP transport for a server: implicit TLS on port 465, STARTTLS otherwise
+///
+/// The transport connects to the address that won the last connection race while TLS still
+/// verifies the certificate against `host`. A race only runs when no recent winner is known,
+/// so repeated sends don't open a throwaway connection first. Falls back to the host name if
+/// the race fails, so lettre reports the connection error itself.
+pub async fn smtp_transport(
+    host: &str,
+    port: u16,
+    account_id: Option<i32>,
+) -> Result<AsyncSmtpTransportBuilder, String> {
+    crate::faults::smtp_connect().await?;
+
+    let target = match race_winner(host, port, account_id) {
+        Some(addr) => Ok(addr),
+        None => {
+            let host = host.to_string();
+            tokio::task::spawn_blocking(move || {
+                connect_tcp(&host, port, account_id).and_then(|s| s.peer_addr())
+            })
+            .await
+            .map_err(|e| format!("Connection task failed: {}", e))?
+        }
+    };
+    let server = match target {
+        Ok(addr) => addr.ip().to_string(),
+        Err(e) => {
+            eprintln!("⚠️ Could not reach {}:{}: {}", host, port, e);
+            host.to_string()
+        }
+    };
+
+    let tls_parameters = TlsParameters::new(host.to_string())
+        .map_err(|e| format!("Failed to create SMTP transport: {}", e))?;
+    let tls = if port == 465 {
+        Tls::Wrapper(tls_parameters)
+    } else {
+        Tls::Required(tls_parameters)
+    };
+
+    Ok(
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(server)
+            .port(port)
+            .tls(tls),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interleave_families_alternates_starting_with_first() {
+        let addrs: Vec<SocketAddr> = [
+            "[2001:db8::1]:993",
+            "[2001:db8::2]:993",
+            "[2001:db8::3]:993",
+            "192.0.2.1:993",
+        ]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+
+        let ordered: Vec<String> = interleave_families(addrs)
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect();
+        assert_eq!(
+            ordered,
+            vec![
+                "[2001:db8::1]:993",
+                "192.0.2.1:993",
+                "[2001:db8::2]:993",
+                "[2001:db8::3]:993",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_uses_account_override() {
+        set_host_overrides([(
+            7,
+            "IMAP.Corp.Example".to_string(),
+            "10.0.0.5".parse().unwrap(),
+        )]);
+
+        let addrs = resolve("imap.corp.example", 993, Some(7)).unwrap();
+        assert_eq!(addrs, vec!["10.0.0.5:993".parse::<SocketAddr>().unwrap()]);
+
+        let addrs = resolve("192.0.2.1", 587, Some(8)).unwrap();
+        assert_eq!(addrs, vec!["192.0.2.1:587".parse::<SocketAddr>().unwrap()]);
+    }
+}
@@ -6,11 +6,86 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager, Wry};
 
+/// Port registered as the redirect URI for providers that don't accept arbitrary loopback ports
+pub const FIXED_REDIRECT_PORT: u16 = 8765;
+
+/// How long a started flow may stay pending before it is discarded
+pub const OAUTH_SESSION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// How long token revocation may take before the account removal moves on
+const REVOCATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A pending OAuth flow, keyed by its state parameter
+struct OAuthSession {
+    verifier: PkceCodeVerifier,
+    redirect_uri: String,
+    provider: String,
+    account_hint: String, // Email the user is adding, lowercased
+    created_at: Instant,
+}
+
 lazy_static::lazy_static! {
     // Store PKCE verifiers for pending OAuth flows
-    static ref OAUTH_SESSIONS: Mutex<HashMap<String, PkceCodeVerifier>> = Mutex::new(HashMap::new());
+    static ref OAUTH_SESSIONS: Mutex<HashMap<String, OAuthSession>> = Mutex::new(HashMap::new());
+}
+
+/// Drop flows older than `OAUTH_SESSION_TTL` and return their states
+pub fn prune_expired_sessions() -> Vec<String> {
+    let mut sessions = OAUTH_SESSIONS.lock().unwrap();
+    let expired: Vec<String> = sessions
+        .iter()
+        .filter(|(_, session)| session.created_at.elapsed() > OAUTH_SESSION_TTL)
+        .map(|(state, _)| state.clone())
+        .collect();
+    for state in &expired {
+        sessions.remove(state);
+    }
+    expired
+}
+
+/// Forget a pending flow (e.g. after a callback timeout)
+pub fn discard_session(state: &str) {
+    OAUTH_SESSIONS.lock().unwrap().remove(state);
+}
+
+/// Remove pending flows that a new flow supersedes and return their states
+///
+/// A new flow replaces an earlier one for the same provider and account. With
+/// `include_provider`, every pending flow of the provider is replaced (used when the
+/// provider's fixed redirect port can only serve one flow at a time).
+pub fn take_superseded_sessions(
+    provider: &str,
+    account_hint: &str,
+    include_provider: bool,
+) -> Vec<String> {
+    let provider = provider.to_lowercase();
+    let account_hint = account_hint.to_lowercase();
+    let mut sessions = OAUTH_SESSIONS.lock().unwrap();
+    let superseded: Vec<String> = sessions
+        .iter()
+        .filter(|(_, session)| {
+            session.provider == provider
+                && (include_provider || session.account_hint == account_hint)
+        })
+        .map(|(state, _)| state.clone())
+        .collect();
+    for state in &superseded {
+        sessions.remove(state);
+    }
+    superseded
+}
+
+/// Provider and account hint of a pending, unexpired flow
+pub fn session_info(state: &str) -> Option<(String, String)> {
+    OAUTH_SESSIONS
+        .lock()
+        .unwrap()
+        .get(state)
+        .filter(|session| session.created_at.elapsed() <= OAUTH_SESSION_TTL)
+        .map(|session| (session.provider.clone(), session.account_hint.clone()))
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -25,6 +100,9 @@ struct ProviderCredentials {
     client_secret: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     note: Option<String>,
+    // Overrides the provider's refresh-token lifetime (e.g. 7 for Google apps in testing mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token_lifetime_days: Option<i64>,
 }
 
 // Use a Mutex-wrapped Option to allow for runtime initialization.
@@ -80,15 +158,19 @@ pub fn init_credentials(app: &AppHandle<Wry>) {
 }
 
 pub struct OAuth2Provider {
+    pub name: String,
     pub client_id: String,
     pub client_secret: String,
     pub auth_url: String,
     pub token_url: String,
+    pub revocation_url: Option<String>, // RFC 7009 endpoint, if the provider has one
     pub scopes: Vec<String>,
     pub imap_server: String,
     pub imap_port: u16,
     pub smtp_server: String,
     pub smtp_port: u16,
+    pub dynamic_redirect_port: bool, // Provider accepts any loopback port in the redirect URI
+    pub refresh_token_lifetime_days: Option<i64>, // None: refresh tokens don't expire on a schedule
 }
 
 impl OAuth2Provider {
@@ -111,14 +193,17 @@ impl OAuth2Provider {
                     eprintln!("⚠ Warning: GOOGLE_CLIENT_SECRET not set");
                     "YOUR_GOOGLE_CLIENT_SECRET".to_string()
                 }),
+                None,
             )
         };
 
         Self {
+            name: "google".to_string(),
             client_id,
             client_secret,
             auth_url: "https://accounts.google.com/o/oauth2/v2/auth".to_string(),
             token_url: "https://oauth2.googleapis.com/token".to_string(),
+            revocation_url: Some("https://oauth2.googleapis.com/revoke".to_string()),
             scopes: vec![
                 "https://mail.google.com/".to_string(),
                 "https://www.googleapis.com/auth/userinfo.email".to_string(),
@@ -127,6 +212,9 @@ impl OAuth2Provider {
             imap_port: 993,
             smtp_server: "smtp.gmail.com".to_string(),
             smtp_port: 587, // Use STARTTLS port instead of 465
+            dynamic_redirect_port: true,
+            // Refresh tokens of published apps only expire when unused for six months
+            refresh_token_lifetime_days: lifetime_override,
         }
     }
 
@@ -149,14 +237,19 @@ impl OAuth2Provider {
                     eprintln!("⚠ Warning: OUTLOOK_CLIENT_SECRET not set");
                     "YOUR_OUTLOOK_CLIENT_SECRET".to_string()
                 }),
+                None,
             )
         };
 
         Self {
+            name: "outlook".to_string(),
             client_id,
             client_secret,
             auth_url: "https://login.microsoftonline.com/common/oauth2/v2.0/authorize".to_string(),
             token_url: "https://login.microsoftonline.com/common/oauth2/v2.0/token".to_string(),
+            // The Microsoft identity platform has no token revocation endpoint; grants can only
+            // be revoked from the account's security page or via Graph with extra scopes
+            revocation_url: None,
             scopes: vec![
                 // Use Outlook-specific scopes for IMAP/SMTP access
                 // Note: These require "Office 365 Exchange Online" API permissions in Azure AD
@@ -168,6 +261,9 @@ impl OAuth2Provider {
             imap_port: 993,
             smtp_server: "smtp.office365.com".to_string(),
             smtp_port: 587,
+            dynamic_redirect_port: false,
+            // Refresh tokens live 90 days; each refresh issues a new one
+            refresh_token_lifetime_days: lifetime_override.or(Some(90)),
         }
     }
 
@@ -179,7 +275,22 @@ impl OAuth2Provider {
         }
     }
 
-    pub fn generate_auth_url(&self) -> Result<(String, String), String> {
+    /// Find the provider that serves `imap_server`, if it is one we know
+    pub fn for_imap_server(imap_server: &str) -> Option<Self> {
+        if imap_server.contains("gmail") {
+            Some(Self::google())
+        } else if imap_server.contains("outlook") || imap_server.contains("office365") {
+            Some(Self::outlook())
+        } else {
+            None
+        }
+    }
+
+    pub fn generate_auth_url(
+        &self,
+        redirect_uri: &str,
+        account_hint: &str,
+    ) -> Result<(String, String), String> {
         // Validate credentials before proceeding
         if self.client_id.starts_with("YOUR_") {
             return Err(
@@ -199,7 +310,7 @@ impl OAuth2Provider {
                     .map_err(|e| format!("Invalid token URL: {}", e))?,
             )
             .set_redirect_uri(
-                RedirectUrl::new("http://localhost:8765/callback".to_string())
+                RedirectUrl::new(redirect_uri.to_string())
                     .map_err(|e| format!("Invalid redirect URL: {}", e))?,
             );
 
@@ -220,10 +331,16 @@ impl OAuth2Provider {
 
         // Store the verifier for later verification
         let state = csrf_token.secret().to_string();
-        OAUTH_SESSIONS
-            .lock()
-            .unwrap()
-            .insert(state.clone(), pkce_verifier);
+        OAUTH_SESSIONS.lock().unwrap().insert(
+            state.clone(),
+            OAuthSession {
+                verifier: pkce_verifier,
+                redirect_uri: redirect_uri.to_string(),
+                provider: self.name.clone(),
+                account_hint: account_hint.to_lowercase(),
+                created_at: Instant::now(),
+            },
+        );
 
         Ok((auth_url.to_string(), state))
     }
@@ -234,10 +351,11 @@ impl OAuth2Provider {
         state: &str,
     ) -> Result<(String, Option<String>, Option<i64>), String> {
         // Retrieve and remove the stored verifier
-        let pkce_verifier = OAUTH_SESSIONS
+        let session = OAUTH_SESSIONS
             .lock()
             .unwrap()
             .remove(state)
+            .filter(|session| session.created_at.elapsed() <= OAUTH_SESSION_TTL)
             .ok_or_else(|| "Invalid or expired OAuth state".to_string())?;
 
         // Create OAuth2 client
@@ -252,11 +370,11 @@ impl OAuth2Provider {
                     .map_err(|e| format!("Invalid token URL: {}", e))?,
             )
             .set_redirect_uri(
-                RedirectUrl::new("http://localhost:8765/callback".to_string())
+                RedirectUrl::new(session.redirect_uri.clone())
                     .map_err(|e| format!("Invalid redirect URL: {}", e))?,
             );
 
-        let http_client = reqwest::ClientBuilder::new()
+        let http_client = crate::net::http_client_builder(None)
             .redirect(reqwest::redirect::Policy::none())
             .build()
             .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
@@ -264,7 +382,7 @@ impl OAuth2Provider {
         // Exchange authorization code for token
         let token_result = client
             .exchange_code(AuthorizationCode::new(code.to_string()))
-            .set_pkce_verifier(pkce_verifier)
+            .set_pkce_verifier(session.verifier)
             .request_async(&http_client)
             .await
             .map_err(|e| format!("Failed to exchange authorization code: {}", e))?;
@@ -287,7 +405,8 @@ impl OAuth2Provider {
     pub async fn refresh_access_token(
         &self,
         refresh_token_str: &str,
-    ) -> Result<(String, Option<i64>), String> {
+        account_id: Option<i32>,
+    ) -> Result<(String, Option<String>, Option<i64>), String> {
         // Create OAuth2 client
         let client = BasicClient::new(ClientId::new(self.client_id.clone()))
             .set_client_secret(ClientSecret::new(self.client_secret.clone()))
@@ -300,7 +419,7 @@ impl OAuth2Provider {
                     .map_err(|e| format!("Invalid token URL: {}", e))?,
             );
 
-        let http_client = reqwest::ClientBuilder::new()
+        let http_client = crate::net::http_client_builder(account_id)
             .redirect(reqwest::redirect::Policy::none())
             .build()
             .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
@@ -313,6 +432,8 @@ impl OAuth2Provider {
             .map_err(|e| format!("Failed to refresh access token: {}", e))?;
 
         let access_token = token_result.access_token().secret().to_string();
+        // Some providers (e.g. Microsoft) rotate the refresh token on every refresh
+        let refresh_token = token_result.refresh_token().map(|t| t.secret().to_string());
 
         let expires_at = token_result.expires_in().map(|duration| {
             let now = std::time::SystemTime::now()
@@ -322,6 +443,46 @@ impl OAuth2Provider {
             now + duration.as_secs() as i64
         });
 
-        Ok((access_token, expires_at))
+        Ok((access_token, refresh_token, expires_at))
+    }
+
+    /// Revoke a refresh (or access) token so the grant disappears from the user's account
+    ///
+    /// Returns Ok(false) if the provider has no revocation endpoint.
+    pub async fn revoke_token(&self, token: &str) -> Result<bool, String> {
+        let Some(revocation_url) = &self.revocation_url else {
+            return Ok(false);
+        };
+
+        let response = crate::net::http_client_builder(None)
+            .timeout(REVOCATION_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?
+            .post(revocation_url)
+            .form(&[("token", token)])
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach revocation endpoint: {}", e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(true);
+        }
+
+        // Only a documented `invalid_token` error (RFC 7009 section 2.2.1) means the grant is
+        // already gone; any other 400 (bad client, malformed request) is a real failure
+        let body = response.text().await.unwrap_or_default();
+        let error = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|json| json.get("error")?.as_str().map(str::to_string));
+        if status == reqwest::StatusCode::BAD_REQUEST && error.as_deref() == Some("invalid_token") {
+            return Ok(true);
+        }
+
+        Err(format!(
+            "Token revocation failed with status {}: {}",
+            status,
+            error.unwrap_or(body)
+        ))
     }
 }
@@ -0,0 +1,47 @@
+// Safe mode
+// Starting with `--safe-mode` (or after `restart_in_safe_mode`) brings the app up without IDLE
+// connections, background loops or auto-sync, and with debug logging, so a user whose
+// accounts crash the app on startup can still get in to fix settings or export data. The
+// restart trigger is a marker file next to the database, read (and removed) before logging
+// and the database are initialised, so it applies for exactly one launch.
+
+use crate::db;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{command, AppHandle};
+
+static SAFE_MODE: AtomicBool = AtomicBool::new(false);
+
+const CLI_FLAG: &str = "--safe-mode";
+
+fn marker_path() -> std::path::PathBuf {
+    db::db_path().with_file_name("safe_mode_next_launch")
+}
+
+/// Decide whether this launch runs in safe mode; call once at startup
+pub fn init() -> bool {
+    let from_flag = std::env::args().any(|arg| arg == CLI_FLAG);
+    let from_marker = std::fs::remove_file(marker_path()).is_ok();
+    let enabled = from_flag || from_marker;
+    SAFE_MODE.store(enabled, Ordering::Relaxed);
+    enabled
+}
+
+/// Whether the app was started in safe mode
+pub fn is_enabled() -> bool {
+    SAFE_MODE.load(Ordering::Relaxed)
+}
+
+/// Whether the app was started in safe mode
+#[command]
+pub fn get_safe_mode() -> bool {
+    is_enabled()
+}
+
+/// Restart the app in safe mode (for the next launch only)
+#[command]
+pub fn restart_in_safe_mode(app_handle: AppHandle) -> Result<(), String> {
+    std::fs::write(marker_path(), b"")
+        .map_err(|e| format!("Failed to schedule safe mode: {}", e))?;
+    tracing::warn!("Restarting in safe mode");
+    app_handle.restart()
+}
@@ -9,6 +9,11 @@
 ///
 /// To avoid Windows Credential Manager's 2560 char limit, we store each
 /// credential field separately instead of as a single JSON blob.
+///
+/// Entries are keyed by a per-account namespace (`accounts.credential_ns`) rather than
+/// by email, so accounts never share entries and renaming an account keeps its secrets.
+/// Entries written under the old email-hash scheme are migrated on first read.
+use crate::db;
 use keyring::Entry;
 use serde::{Deserialize, Serialize};
 
@@ -144,42 +149,95 @@ fn delete_long_value(service: &str, account: &str) -> Result<(), String> {
     }
 }
 
+/// Generate a new random credential namespace for an account
+pub fn new_namespace() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Get the credential namespace of an account, assigning one if it has none yet
+pub async fn account_namespace(account_id: i32) -> Result<String, String> {
+    let pool = db::pool();
+
+    let (namespace,) =
+        sqlx::query_as::<_, (Option<String>,)>("SELECT credential_ns FROM accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| format!("Failed to load credential namespace: {}", e))?
+            .ok_or_else(|| format!("Account {} not found", account_id))?;
+
+    match namespace {
+        Some(namespace) if !namespace.is_empty() => Ok(namespace),
+        _ => {
+            let namespace = new_namespace();
+            sqlx::query("UPDATE accounts SET credential_ns = ? WHERE id = ?")
+                .bind(&namespace)
+                .bind(account_id)
+                .execute(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to assign credential namespace: {}", e))?;
+            Ok(namespace)
+        }
+    }
+}
+
+/// Resolve the credential namespace for an account config (by ID, falling back to email)
+pub async fn namespace_for_config(account_id: Option<i32>, email: &str) -> Result<String, String> {
+    let account_id = match account_id {
+        Some(id) => id,
+        None => {
+            let pool = db::pool();
+            sqlx::query_as::<_, (i64,)>("SELECT id FROM accounts WHERE email = ?")
+                .bind(email)
+                .fetch_optional(pool.as_ref())
+                .await
+                .map_err(|e| format!("Failed to look up account: {}", e))?
+                .map(|(id,)| id as i32)
+                .ok_or_else(|| format!("Account {} not found", email))?
+        }
+    };
+
+    account_namespace(account_id).await
+}
+
 /// Store account credentials securely in the OS keyring
 /// Stores each field separately to avoid platform size limits
-pub fn store_credentials(creds: &AccountCredentials) -> Result<(), String> {
-    // Use base64-encoded hash of email as key to avoid long account names
-    // Windows Credential Manager has a limit on the total credential size including the account name
-    let email_hash = base64_url_safe(&creds.email);
+pub fn store_credentials(namespace: &str, creds: &AccountCredentials) -> Result<(), String> {
+    let key_prefix = namespace_key(namespace);
+    store_credentials_under(&key_prefix, creds)
+}
 
+/// Write every present credential field under the given key prefix
+fn store_credentials_under(key_prefix: &str, creds: &AccountCredentials) -> Result<(), String> {
     // Store password if present
     if let Some(ref password) = creds.password {
-        store_long_value(SERVICE_NAME, &format!("{}:pwd", email_hash), password)
+        store_long_value(SERVICE_NAME, &format!("{}:pwd", key_prefix), password)
             .map_err(|e| format!("Failed to store password: {}", e))?;
     }
 
     // Store access_token if present
     if let Some(ref access_token) = creds.access_token {
-        store_long_value(SERVICE_NAME, &format!("{}:at", email_hash), access_token)
+        store_long_value(SERVICE_NAME, &format!("{}:at", key_prefix), access_token)
             .map_err(|e| format!("Failed to store access_token: {}", e))?;
     }
 
     // Store refresh_token if present
     if let Some(ref refresh_token) = creds.refresh_token {
-        store_long_value(SERVICE_NAME, &format!("{}:rt", email_hash), refresh_token)
+        store_long_value(SERVICE_NAME, &format!("{}:rt", key_prefix), refresh_token)
             .map_err(|e| format!("Failed to store refresh_token: {}", e))?;
     }
 
     // Store token_expires_at if present
     if let Some(expires_at) = creds.token_expires_at {
-        let entry = Entry::new(SERVICE_NAME, &format!("{}:exp", email_hash))
+        let entry = Entry::new(SERVICE_NAME, &format!("{}:exp", key_prefix))
             .map_err(|e| format!("Failed to create token_expires_at entry: {}", e))?;
         entry
             .set_password(&expires_at.to_string())
             .map_err(|e| format!("Failed to store token_expires_at: {}", e))?;
     }
 
-    // Store email mapping so we can retrieve by hash later
-    let entry = Entry::new(SERVICE_NAME, &format!("{}:email", email_hash))
+    // Store email mapping so we can tell which account the entries belong to
+    let entry = Entry::new(SERVICE_NAME, &format!("{}:email", key_prefix))
         .map_err(|e| format!("Failed to create email mapping entry: {}", e))?;
     entry
         .set_password(&creds.email)
@@ -188,7 +246,12 @@ pub fn store_credentials(creds: &AccountCredentials) -> Result<(), String> {
     Ok(())
 }
 
-/// Generate a short, URL-safe identifier from email
+/// Keyring key prefix for an account namespace
+fn namespace_key(namespace: &str) -> String {
+    format!("acct-{}", namespace)
+}
+
+/// Generate a short, URL-safe identifier from email (legacy key scheme)
 fn base64_url_safe(email: &str) -> String {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
@@ -201,10 +264,8 @@ fn base64_url_safe(email: &str) -> String {
     format!("{:x}", hash)[..8].to_string()
 }
 
-/// Retrieve account credentials from the OS keyring
-pub fn get_credentials(email: &str) -> Result<AccountCredentials, String> {
-    let email_hash = base64_url_safe(email);
-
+/// Read every credential field stored under the given key prefix
+fn read_credentials_under(key_prefix: &str, email: &str) -> Result<AccountCredentials, String> {
     let mut creds = AccountCredentials {
         email: email.to_string(),
         password: None,
@@ -214,16 +275,16 @@ pub fn get_credentials(email: &str) -> Result<AccountCredentials, String> {
     };
 
     // Retrieve password if exists
-    creds.password = retrieve_long_value(SERVICE_NAME, &format!("{}:pwd", email_hash))?;
+    creds.password = retrieve_long_value(SERVICE_NAME, &format!("{}:pwd", key_prefix))?;
 
     // Retrieve access_token if exists
-    creds.access_token = retrieve_long_value(SERVICE_NAME, &format!("{}:at", email_hash))?;
+    creds.access_token = retrieve_long_value(SERVICE_NAME, &format!("{}:at", key_prefix))?;
 
     // Retrieve refresh_token if exists
-    creds.refresh_token = retrieve_long_value(SERVICE_NAME, &format!("{}:rt", email_hash))?;
+    creds.refresh_token = retrieve_long_value(SERVICE_NAME, &format!("{}:rt", key_prefix))?;
 
     // Retrieve token_expires_at if exists
-    if let Ok(entry) = Entry::new(SERVICE_NAME, &format!("{}:exp", email_hash)) {
+    if let Ok(entry) = Entry::new(SERVICE_NAME, &format!("{}:exp", key_prefix)) {
         if let Ok(expires_at_str) = entry.get_password() {
             if let Ok(expires_at) = expires_at_str.parse::<i64>() {
                 creds.token_expires_at = Some(expires_at);
@@ -234,34 +295,79 @@ pub fn get_credentials(email: &str) -> Result<AccountCredentials, String> {
     Ok(creds)
 }
 
-/// Delete account credentials from the OS keyring
-pub fn delete_credentials(email: &str) -> Result<(), String> {
-    let email_hash = base64_url_safe(email);
+/// Check whether any entries exist under the given key prefix
+fn has_entries_under(key_prefix: &str) -> bool {
+    Entry::new(SERVICE_NAME, &format!("{}:email", key_prefix))
+        .and_then(|entry| entry.get_password())
+        .is_ok()
+}
 
+/// Delete every credential field stored under the given key prefix
+fn delete_credentials_under(key_prefix: &str) {
     // Delete password
-    let _ = delete_long_value(SERVICE_NAME, &format!("{}:pwd", email_hash));
+    let _ = delete_long_value(SERVICE_NAME, &format!("{}:pwd", key_prefix));
 
     // Delete access_token
-    let _ = delete_long_value(SERVICE_NAME, &format!("{}:at", email_hash));
+    let _ = delete_long_value(SERVICE_NAME, &format!("{}:at", key_prefix));
 
     // Delete refresh_token
-    let _ = delete_long_value(SERVICE_NAME, &format!("{}:rt", email_hash));
+    let _ = delete_long_value(SERVICE_NAME, &format!("{}:rt", key_prefix));
 
     // Delete token_expires_at
-    if let Ok(entry) = Entry::new(SERVICE_NAME, &format!("{}:exp", email_hash)) {
+    if let Ok(entry) = Entry::new(SERVICE_NAME, &format!("{}:exp", key_prefix)) {
         let _ = entry.delete_credential();
     }
 
     // Delete email mapping
-    if let Ok(entry) = Entry::new(SERVICE_NAME, &format!("{}:email", email_hash)) {
+    if let Ok(entry) = Entry::new(SERVICE_NAME, &format!("{}:email", key_prefix)) {
         let _ = entry.delete_credential();
     }
+}
+
+/// Move credentials stored under the legacy email-hash key into the account namespace
+///
+/// Returns true if legacy entries were found and migrated.
+pub fn migrate_legacy_credentials(namespace: &str, email: &str) -> Result<bool, String> {
+    let legacy_prefix = base64_url_safe(email);
+    if !has_entries_under(&legacy_prefix) {
+        return Ok(false);
+    }
+
+    let creds = read_credentials_under(&legacy_prefix, email)?;
+    store_credentials(namespace, &creds)?;
+    delete_credentials_under(&legacy_prefix);
+
+    println!(
+        "🔑 Migrated keyring entries for {} to account namespace",
+        email
+    );
+    Ok(true)
+}
 
+/// Retrieve account credentials from the OS keyring
+pub fn get_credentials(namespace: &str, email: &str) -> Result<AccountCredentials, String> {
+    let key_prefix = namespace_key(namespace);
+
+    // Lazily migrate entries written before namespaces existed
+    if !has_entries_under(&key_prefix) {
+        migrate_legacy_credentials(namespace, email)?;
+    }
+
+    read_credentials_under(&key_prefix, email)
+}
+
+/// Delete account credentials from the OS keyring
+///
+/// Also removes legacy email-hash entries for `email`, so nothing is orphaned.
+pub fn delete_credentials(namespace: &str, email: &str) -> Result<(), String> {
+    delete_credentials_under(&namespace_key(namespace));
+    delete_credentials_under(&base64_url_safe(email));
     Ok(())
 }
 
 /// Update specific fields of stored credentials
 pub fn update_credentials(
+    namespace: &str,
     email: &str,
     password: Option<String>,
     access_token: Option<String>,
@@ -269,7 +375,7 @@ pub fn update_credentials(
     token_expires_at: Option<i64>,
 ) -> Result<(), String> {
     // Try to get existing credentials, or create new ones
-    let mut creds = get_credentials(email).unwrap_or_else(|_| AccountCredentials {
+    let mut creds = get_credentials(namespace, email).unwrap_or_else(|_| AccountCredentials {
         email: email.to_string(),
         password: None,
         access_token: None,
@@ -291,5 +397,5 @@ pub fn update_credentials(
         creds.token_expires_at = token_expires_at;
     }
 
-    store_credentials(&creds)
+    store_credentials(namespace, &creds)
 }
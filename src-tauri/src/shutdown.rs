@@ -0,0 +1,88 @@
+// Structured shutdown coordination
+// Background tasks (IDLE loops, prefetchers, schedulers) register here so that app exit can
+// cancel them, give them time to finish their current write, and then close the database.
+
+use crate::db;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+lazy_static::lazy_static! {
+    /// Root of the cancellation tree; cancelled once when the app exits
+    static ref ROOT_TOKEN: CancellationToken = CancellationToken::new();
+
+    /// Mirrors `ROOT_TOKEN` for blocking code; shared by every `cancel_flag()` caller
+    static ref CANCEL_FLAG: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+
+    /// Handles of tracked background tasks, awaited during shutdown
+    static ref TASKS: Mutex<Vec<(&'static str, JoinHandle<()>)>> = Mutex::new(Vec::new());
+}
+
+/// Get a child token that is cancelled when the app shuts down
+pub fn token() -> CancellationToken {
+    ROOT_TOKEN.child_token()
+}
+
+/// Whether shutdown has started
+pub fn is_shutting_down() -> bool {
+    ROOT_TOKEN.is_cancelled()
+}
+
+/// Spawn a background task that shutdown will wait for
+///
+/// The task should watch `token()` and return promptly once it is cancelled.
+pub fn spawn<F>(name: &'static str, future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let handle = tokio::spawn(future);
+    let mut tasks = TASKS.lock().unwrap();
+    // Drop handles of tasks that already finished so the list doesn't grow forever
+    tasks.retain(|(_, handle)| !handle.is_finished());
+    tasks.push((name, handle));
+}
+
+/// Get an `AtomicBool` cancel flag that flips to true on shutdown
+///
+/// For blocking code that polls a flag between batches instead of awaiting a token. All callers
+/// share one flag, so it must only be read.
+pub fn cancel_flag() -> Arc<AtomicBool> {
+    CANCEL_FLAG.clone()
+}
+
+/// Cancel all background work, wait up to `timeout` for tracked tasks, then close the database
+pub async fn shutdown(timeout: Duration) {
+    if is_shutting_down() {
+        return;
+    }
+
+    tracing::info!("Shutting down background tasks");
+    ROOT_TOKEN.cancel();
+    CANCEL_FLAG.store(true, Ordering::Relaxed);
+
+    let tasks: Vec<(&'static str, JoinHandle<()>)> = TASKS.lock().unwrap().drain(..).collect();
+    let pending = tasks.len();
+
+    let wait_all = async {
+        for (name, handle) in tasks {
+            if let Err(e) = handle.await {
+                tracing::warn!(task = name, error = %e, "Background task ended abnormally");
+            }
+        }
+    };
+
+    if tokio::time::timeout(timeout, wait_all).await.is_err() {
+        tracing::warn!(
+            pending = pending,
+            "Timed out waiting for background tasks, exiting anyway"
+        );
+    }
+
+    // Flush pending writes (WAL checkpoint) and release the SQLite file
+    db::pool().close().await;
+
+    tracing::info!("Shutdown complete");
+}
@@ -0,0 +1,174 @@
+// Watchdog for stuck background tasks
+// Long-running tasks (sync, prefetch, IDLE) report progress through a Heartbeat. If a task
+// stops beating for longer than its threshold (e.g. blocked on a dead socket), the watchdog
+// aborts it, optionally restarts it, and records the incident for diagnostics.
+// Aborting can't interrupt a thread stuck in blocking IMAP I/O; that thread is abandoned and
+// ends when its socket errors out, while the restarted task works on a fresh connection.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Incidents kept in memory for the diagnostics view
+const MAX_INCIDENTS: usize = 100;
+
+lazy_static::lazy_static! {
+    /// Reference point for heartbeat timestamps
+    static ref EPOCH: Instant = Instant::now();
+
+    static ref INCIDENTS: Mutex<VecDeque<WatchdogIncident>> = Mutex::new(VecDeque::new());
+}
+
+/// A task that was aborted because it stopped making progress
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WatchdogIncident {
+    pub task: String,
+    pub stalled_secs: u64,
+    pub restarted: bool,
+    pub timestamp: i64,
+}
+
+/// Progress signal handed to a supervised task; cheap to clone and usable from blocking code
+#[derive(Clone)]
+pub struct Heartbeat {
+    last_beat_ms: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    fn new() -> Self {
+        let heartbeat = Self {
+            last_beat_ms: Arc::new(AtomicU64::new(0)),
+        };
+        heartbeat.beat();
+        heartbeat
+    }
+
+    /// Report that the task is still making progress
+    pub fn beat(&self) {
+        self.last_beat_ms
+            .store(EPOCH.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn since_last_beat(&self) -> Duration {
+        let last = self.last_beat_ms.load(Ordering::Relaxed);
+        Duration::from_millis((EPOCH.elapsed().as_millis() as u64).saturating_sub(last))
+    }
+}
+
+/// Aborts the wrapped task when dropped, so cancelling a supervisor also stops its task
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Wait for the task to finish; None if its heartbeat went silent for longer than `threshold`
+async fn wait_or_stall<T>(
+    task: &mut AbortOnDrop<T>,
+    heartbeat: &Heartbeat,
+    threshold: Duration,
+) -> Option<Result<T, tokio::task::JoinError>> {
+    let check_interval = (threshold / 4).max(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            result = &mut task.0 => return Some(result),
+            _ = tokio::time::sleep(check_interval) => {
+                if heartbeat.since_last_beat() > threshold {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+fn record_incident(task: &str, stalled: Duration, restarted: bool) {
+    tracing::warn!(
+        task = task,
+        stalled_secs = stalled.as_secs(),
+        restarted = restarted,
+        "Watchdog aborted stuck task"
+    );
+
+    let mut incidents = INCIDENTS.lock().unwrap();
+    if incidents.len() >= MAX_INCIDENTS {
+        incidents.pop_front();
+    }
+    incidents.push_back(WatchdogIncident {
+        task: task.to_string(),
+        stalled_secs: stalled.as_secs(),
+        restarted,
+        timestamp: chrono::Utc::now().timestamp(),
+    });
+}
+
+/// Run a long-lived task under the watchdog, restarting it whenever it stalls
+///
+/// Returns when the task finishes on its own. Dropping the returned future (e.g. aborting
+/// the task that awaits it) also aborts the supervised task.
+pub async fn supervise<F, Fut>(name: String, threshold: Duration, make_task: F)
+where
+    F: Fn(Heartbeat) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    loop {
+        let heartbeat = Heartbeat::new();
+        let mut task = AbortOnDrop(tokio::spawn(make_task(heartbeat.clone())));
+
+        if wait_or_stall(&mut task, &heartbeat, threshold)
+            .await
+            .is_some()
+        {
+            return;
+        }
+
+        task.0.abort();
+        record_incident(&name, heartbeat.since_last_beat(), true);
+
+        if crate::shutdown::is_shutting_down() {
+            return;
+        }
+    }
+}
+
+/// Run a one-shot task under the watchdog and return its result
+///
+/// A stalled task is aborted (not restarted) and reported as an error, so the caller's own
+/// retry logic decides what happens next.
+pub async fn run_supervised<T, F, Fut>(
+    name: &str,
+    threshold: Duration,
+    make_task: F,
+) -> Result<T, String>
+where
+    F: FnOnce(Heartbeat) -> Fut,
+    Fut: Future<Output = Result<T, String>> + Send + 'static,
+    T: Send + 'static,
+{
+    let heartbeat = Heartbeat::new();
+    let mut task = AbortOnDrop(tokio::spawn(make_task(heartbeat.clone())));
+
+    match wait_or_stall(&mut task, &heartbeat, threshold).await {
+        Some(result) => result.map_err(|e| format!("Task join error: {}", e))?,
+        None => {
+            task.0.abort();
+            let stalled = heartbeat.since_last_beat();
+            record_incident(name, stalled, false);
+            Err(format!(
+                "{} made no progress for {}s and was aborted",
+                name,
+                stalled.as_secs()
+            ))
+        }
+    }
+}
+
+/// Incidents recorded since startup, oldest first
+pub fn incidents() -> Vec<WatchdogIncident> {
+    INCIDENTS.lock().unwrap().iter().cloned().collect()
+}
@@ -71,6 +71,16 @@ export async function handleAccountClick(
         appState.folders = syncedFolders;
       }
     }
+
+    // Refresh unread badges with STATUS, without selecting every folder
+    invoke("get_all_folder_status", { config: selectedConfig })
+      .then(() => invoke<Folder[]>("load_folders", { accountId: targetAccountId }))
+      .then((refreshedFolders) => {
+        if (appState.selectedAccountId === targetAccountId) {
+          appState.folders = refreshedFolders;
+        }
+      })
+      .catch((e) => console.warn("Failed to refresh folder counts:", e));
   } catch (e) {
     appState.error = `Failed to load folders: ${e}`;
     appState.isLoadingFolders = false;
@@ -9,6 +9,86 @@ import type { AccountConfig, EmailHeader } from "../lib/types";
 import { state as appState } from "../lib/state.svelte";
 import { draftManager } from "../lib/draft-manager";
 
+/** Returned by the send commands; `send_id` is set while the message waits out the undo-send delay */
+interface SendResult {
+  message: string;
+  send_id: number | null;
+}
+
+/** What was in the compose dialog when a message was scheduled, to reopen it on undo */
+interface ScheduledCompose {
+  accountId: number;
+  draftId: number | null;
+  to: string;
+  cc: string;
+  bcc: string;
+  subject: string;
+  body: string;
+  attachments: File[];
+  isReplyMode: boolean;
+  isForwardMode: boolean;
+}
+
+/** Messages waiting out the undo-send delay, by outbox id */
+const scheduledSends = new Map<number, ScheduledCompose>();
+
+/**
+ * A scheduled message went out: its draft is no longer needed
+ */
+export async function finishScheduledSend(sendId: number) {
+  const scheduled = scheduledSends.get(sendId);
+  if (!scheduled) return;
+  scheduledSends.delete(sendId);
+  if (scheduled.draftId) {
+    try {
+      await draftManager.deleteDraft(scheduled.draftId);
+    } catch (error) {
+      console.error("Failed to delete draft after sending:", error);
+    }
+  }
+}
+
+/**
+ * A scheduled message was cancelled: reopen it in the compose dialog, or save it to Drafts if
+ * another message is being composed meanwhile
+ */
+export async function restoreScheduledSend(sendId: number) {
+  const scheduled = scheduledSends.get(sendId);
+  if (!scheduled) return;
+  scheduledSends.delete(sendId);
+
+  if (appState.showComposeDialog) {
+    try {
+      await draftManager.saveDraft(
+        scheduled.accountId,
+        scheduled.to,
+        scheduled.cc,
+        scheduled.subject,
+        scheduled.body,
+        await draftManager.filesToDraftAttachments(scheduled.attachments),
+        scheduled.isReplyMode ? "reply" : scheduled.isForwardMode ? "forward" : "compose",
+        scheduled.draftId ?? undefined
+      );
+      toast.info("The cancelled message was saved to Drafts.");
+    } catch (error) {
+      toast.error(`Failed to save the cancelled message: ${error}`);
+    }
+    return;
+  }
+
+  appState.resetComposeState();
+  appState.currentDraftId = scheduled.draftId;
+  appState.composeTo = scheduled.to;
+  appState.composeCc = scheduled.cc;
+  appState.composeBcc = scheduled.bcc;
+  appState.composeSubject = scheduled.subject;
+  appState.composeBody = scheduled.body;
+  appState.composeAttachments = scheduled.attachments;
+  appState.isReplyMode = scheduled.isReplyMode;
+  appState.isForwardMode = scheduled.isForwardMode;
+  appState.showComposeDialog = true;
+}
+
 /**
  * Handle compose new email button click
  */
@@ -191,14 +271,17 @@ export async function handleSendEmail(
       }
     }
 
-    let result: string;
+    let result: SendResult;
     if (appState.isReplyMode) {
-      result = await invoke<string>("reply_email", {
+      const selectedEmail = emails.find((email) => email.uid === selectedEmailUid);
+      result = await invoke<SendResult>("reply_email", {
         config: selectedConfig,
         to: appState.composeTo,
         originalSubject: appState.composeSubject,
         body: appState.composeBody,
         cc: appState.composeCc || null,
+        bcc: appState.composeBcc || null,
+        inReplyTo: selectedEmail?.message_id ?? null,
         attachments: attachmentsData,
       });
     } else if (appState.isForwardMode) {
@@ -208,7 +291,7 @@ export async function handleSendEmail(
         appState.isSending = false;
         return;
       }
-      result = await invoke<string>("forward_email", {
+      result = await invoke<SendResult>("forward_email", {
         config: selectedConfig,
         params: {
           to: appState.composeTo,
@@ -219,6 +302,7 @@ export async function handleSendEmail(
           originalBody: emailBody || "",
           additionalMessage: appState.composeBody,
           cc: appState.composeCc || null,
+          bcc: appState.composeBcc || null,
           attachments: attachmentsData,
         },
       });
@@ -228,18 +312,35 @@ export async function handleSendEmail(
         appState.isSending = false;
         return;
       }
-      result = await invoke<string>("send_email", {
+      result = await invoke<SendResult>("send_email", {
         config: selectedConfig,
         to: appState.composeTo,
         subject: appState.composeSubject,
         body: appState.composeBody,
         cc: appState.composeCc || null,
+        bcc: appState.composeBcc || null,
         attachments: attachmentsData,
       });
     }
 
-    // Delete draft after successful send
-    if (appState.currentDraftId) {
+    if (result.send_id !== null) {
+      // Waiting out the undo-send delay: keep the draft until the message actually goes out,
+      // and remember the compose state so undo can reopen it. The countdown toast is shown
+      // by the send-progress listener (see +page.svelte).
+      scheduledSends.set(result.send_id, {
+        accountId: selectedAccountId,
+        draftId: appState.currentDraftId,
+        to: appState.composeTo,
+        cc: appState.composeCc,
+        bcc: appState.composeBcc,
+        subject: appState.composeSubject,
+        body: appState.composeBody,
+        attachments: appState.composeAttachments,
+        isReplyMode: appState.isReplyMode,
+        isForwardMode: appState.isForwardMode,
+      });
+    } else if (appState.currentDraftId) {
+      // Delete draft after successful send
       try {
         await draftManager.deleteDraft(appState.currentDraftId);
       } catch (error) {
@@ -251,7 +352,9 @@ export async function handleSendEmail(
     appState.showComposeDialog = false;
     appState.resetComposeState();
 
-    toast.success("Email sent successfully!");
+    if (result.send_id === null) {
+      toast.success(result.message);
+    }
   } catch (e) {
     appState.error = `Failed to send email: ${e}`;
   } finally {
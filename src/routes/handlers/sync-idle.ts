@@ -1,72 +1,20 @@
 /**
  * Sync and IDLE Event Handlers
- * Handles email synchronization, IDLE push notifications, and auto-sync timers
+ * Handles email synchronization, IDLE push notifications, and scheduled sync events
  */
 
 import { invoke } from "@tauri-apps/api/core";
-import type { AccountConfig, EmailHeader, Folder, IdleEvent } from "../lib/types";
+import type {
+  AccountConfig,
+  EmailHeader,
+  Folder,
+  FolderSyncSummary,
+  IdleEvent,
+  SyncComplete,
+  SyncProgress,
+} from "../lib/types";
 import { state as appState } from "../lib/state.svelte";
 
-/**
- * Start auto-sync timer
- */
-export function startAutoSyncTimer(
-  syncInterval: number,
-  accounts: AccountConfig[],
-  selectedAccountId: number | null,
-  selectedFolderName: string
-): ReturnType<typeof setInterval> | null {
-  // Clear existing timer if any
-  if (autoSyncTimerRef) {
-    clearInterval(autoSyncTimerRef);
-    autoSyncTimerRef = null;
-  }
-
-  if (syncInterval <= 0) {
-    return null;
-  }
-
-  const timer = setInterval(async () => {
-    if (!selectedAccountId) return;
-
-    const selectedConfig = accounts.find((acc) => acc.id === selectedAccountId);
-    if (!selectedConfig) return;
-
-    try {
-      const needsSync = await invoke<boolean>("should_sync", {
-        accountId: selectedAccountId,
-        folder: selectedFolderName,
-        syncInterval,
-      });
-
-      if (needsSync && !appState.isSyncing) {
-        appState.isSyncing = true;
-
-        const syncedFolders = await invoke<Folder[]>("sync_folders", { config: selectedConfig });
-        appState.folders = syncedFolders;
-
-        const syncedEmails = await invoke<EmailHeader[]>("sync_emails", {
-          config: selectedConfig,
-          folder: selectedFolderName,
-        });
-
-        appState.emails = syncedEmails;
-        appState.lastSyncTime = Math.floor(Date.now() / 1000);
-        appState.isSyncing = false;
-      }
-    } catch (e) {
-      console.error("❌ Auto-sync failed:", e);
-      appState.isSyncing = false;
-    }
-  }, 60000);
-
-  autoSyncTimerRef = timer;
-  return timer;
-}
-
-// Module-level variable to track auto-sync timer
-let autoSyncTimerRef: ReturnType<typeof setInterval> | null = null;
-
 /**
  * Handle manual refresh button click - sync all accounts and folders
  */
@@ -91,17 +39,22 @@ export async function handleManualRefresh(accounts: AccountConfig[], selectedAcc
           appState.folders = syncedFolders;
         }
 
-        // Sync all folders for this account
-        for (const folder of syncedFolders) {
-          const syncedEmails = await invoke<EmailHeader[]>("sync_emails", {
-            config: account,
-            folder: folder.name,
-          });
+        // Sync all folders for this account (the backend runs a few at a time)
+        const isSelected = account.id === selectedAccountId;
+        const summaries = await invoke<FolderSyncSummary[]>("sync_all_folders", {
+          config: account,
+          openFolder: isSelected ? selectedFolderName : null,
+        });
+        for (const summary of summaries.filter((s) => s.error)) {
+          console.error(`❌ Failed to sync folder ${summary.folder_name}:`, summary.error);
+        }
 
-          // Update emails if this is the currently selected account and folder
-          if (account.id === selectedAccountId && folder.name === selectedFolderName) {
-            appState.emails = syncedEmails;
-          }
+        // Update emails if this is the currently selected account
+        if (isSelected) {
+          appState.emails = await invoke<EmailHeader[]>("load_emails_from_cache", {
+            accountId: account.id,
+            folder: selectedFolderName,
+          });
         }
       } catch (e) {
         console.error(`❌ Failed to sync account ${account.email}:`, e);
@@ -130,56 +83,8 @@ export async function handleIdleEvent(
   const eventType = idleEvent.event_type.type;
 
   if (eventType === "NewMessages") {
-    if (idleEvent.account_id === selectedAccountId && idleEvent.folder_name === selectedFolderName) {
-      const targetAccountId = selectedAccountId;
-      const targetFolderName = selectedFolderName;
-      const selectedConfig = accounts.find((acc) => acc.id === targetAccountId);
-
-      if (selectedConfig) {
-        try {
-          appState.isSyncing = true;
-          const syncedEmails = await invoke<EmailHeader[]>("sync_emails", {
-            config: selectedConfig,
-            folder: targetFolderName,
-          });
-
-          // Check if still viewing same account/folder
-          if (appState.selectedAccountId === targetAccountId && appState.selectedFolderName === targetFolderName) {
-            // Preserve the selected email UID if it exists in the new list
-            const currentlySelectedUid = appState.selectedEmailUid;
-            appState.emails = syncedEmails;
-            appState.lastSyncTime = Math.floor(Date.now() / 1000);
-
-            // Check if the selected email still exists
-            if (currentlySelectedUid) {
-              const stillExists = syncedEmails.find((e) => e.uid === currentlySelectedUid);
-              if (!stillExists) {
-                console.warn(`⚠️ Selected email UID ${currentlySelectedUid} no longer in list after sync!`);
-              }
-            }
-          } else {
-            console.log("Account/folder changed during IDLE sync, discarding result");
-          }
-          appState.isSyncing = false;
-        } catch (e) {
-          console.error("❌ Failed to sync after IDLE event:", e);
-          appState.isSyncing = false;
-        }
-      }
-    } else {
-      // Background sync for other folders - don't update UI
-      const affectedConfig = accounts.find((acc) => acc.id === idleEvent.account_id);
-      if (affectedConfig) {
-        try {
-          await invoke<EmailHeader[]>("sync_emails", {
-            config: affectedConfig,
-            folder: idleEvent.folder_name,
-          });
-        } catch (e) {
-          console.error(`❌ Background sync failed for account ${idleEvent.account_id}:`, e);
-        }
-      }
-    }
+    // The backend syncs the folder itself and follows up with a "sync-complete" event
+    console.log(`📬 ${idleEvent.event_type.count ?? 0} new message(s) in ${idleEvent.folder_name}`);
   } else if (eventType === "FlagsChanged") {
     // Sync flags for specific UID only (efficient!)
     if (idleEvent.account_id === selectedAccountId && idleEvent.folder_name === selectedFolderName) {
@@ -217,28 +122,17 @@ export async function handleIdleEvent(
       }
     }
   } else if (eventType === "Expunge") {
-    // Full sync for email deletions
-    if (idleEvent.account_id === selectedAccountId && idleEvent.folder_name === selectedFolderName) {
-      const targetAccountId = selectedAccountId;
-      const targetFolderName = selectedFolderName;
-      const selectedConfig = accounts.find((acc) => acc.id === targetAccountId);
-
-      if (selectedConfig) {
-        try {
-          const syncedEmails = await invoke<EmailHeader[]>("sync_emails", {
-            config: selectedConfig,
-            folder: targetFolderName,
-          });
-
-          // Check if still viewing same account/folder
-          if (appState.selectedAccountId === targetAccountId && appState.selectedFolderName === targetFolderName) {
-            appState.emails = syncedEmails;
-          } else {
-            console.log("Account/folder changed during IDLE sync, discarding result");
-          }
-        } catch (e) {
-          console.error("❌ Failed to refresh after deletion:", e);
-        }
+    // The event carries the expunged message's UID and the backend has already dropped it
+    // from the cache, so just take it out of the list
+    const uid = idleEvent.event_type.uid;
+    if (
+      uid !== undefined &&
+      idleEvent.account_id === selectedAccountId &&
+      idleEvent.folder_name === selectedFolderName
+    ) {
+      appState.emails = appState.emails.filter((e) => e.uid !== uid);
+      if (appState.selectedEmailUid === uid) {
+        appState.selectedEmailUid = null;
       }
     }
   } else if (eventType === "ConnectionLost") {
@@ -246,10 +140,72 @@ export async function handleIdleEvent(
   }
 }
 
+/**
+ * Handle a scheduled backend sync: reload the open folder from the cache once it's synced
+ */
+export async function handleSyncProgress(
+  event: { payload: SyncProgress },
+  selectedAccountId: number | null,
+  selectedFolderName: string
+) {
+  const { account_id, folder_name, phase } = event.payload;
+  if (phase !== "finished" || account_id !== selectedAccountId || folder_name !== selectedFolderName) {
+    return;
+  }
+
+  try {
+    const emails = await invoke<EmailHeader[]>("load_emails_from_cache", {
+      accountId: account_id,
+      folder: folder_name,
+    });
+    // Check if still viewing same account/folder
+    if (appState.selectedAccountId === account_id && appState.selectedFolderName === folder_name) {
+      appState.emails = emails;
+      appState.lastSyncTime = Math.floor(Date.now() / 1000);
+    }
+  } catch (e) {
+    console.error("❌ Failed to reload emails after scheduled sync:", e);
+  }
+}
+
+/**
+ * Handle a backend sync triggered by IDLE: show the new mail if its folder is open
+ */
+export async function handleSyncComplete(
+  event: { payload: SyncComplete },
+  selectedAccountId: number | null,
+  selectedFolderName: string
+) {
+  const { account_id, folder_name, new_emails } = event.payload;
+  if (account_id !== selectedAccountId || folder_name !== selectedFolderName) {
+    return;
+  }
+
+  // Merge instead of reloading so the list (and the selected email) stays put
+  const known = new Set(appState.emails.map((e) => e.uid));
+  const added = new_emails.filter((e) => !known.has(e.uid));
+  if (added.length > 0) {
+    appState.emails = [...added, ...appState.emails].sort((a, b) => b.timestamp - a.timestamp);
+  }
+  appState.lastSyncTime = Math.floor(Date.now() / 1000);
+}
+
 /**
  * Play notification sound
  */
-export function playNotificationSound() {
+export async function playNotificationSound(sound?: string | null) {
+  // Imported sound files are served by the backend; fall back to the beep if one can't play
+  if (sound) {
+    try {
+      const dataUrl = await invoke<string>("get_notification_sound_data", { name: sound });
+      await new Audio(dataUrl).play();
+      console.log(`🔔 Played notification sound ${sound}`);
+      return;
+    } catch (e) {
+      console.error(`❌ Failed to play notification sound ${sound}:`, e);
+    }
+  }
+
   try {
     // Create a simple beep sound using Web Audio API
     const audioContext = new (window.AudioContext || (window as any).webkitAudioContext)();
@@ -274,20 +230,3 @@ export function playNotificationSound() {
     console.error("❌ Failed to play notification sound:", e);
   }
 }
-
-/**
- * Get the auto-sync timer reference for cleanup
- */
-export function getAutoSyncTimer() {
-  return autoSyncTimerRef;
-}
-
-/**
- * Clear the auto-sync timer
- */
-export function clearAutoSyncTimer() {
-  if (autoSyncTimerRef) {
-    clearInterval(autoSyncTimerRef);
-    autoSyncTimerRef = null;
-  }
-}
@@ -41,6 +41,7 @@ class AppState {
   showComposeDialog = $state<boolean>(false);
   composeTo = $state<string>("");
   composeCc = $state<string>("");
+  composeBcc = $state<string>("");
   composeSubject = $state<string>("");
   composeBody = $state<string>("");
   isReplyMode = $state<boolean>(false);
@@ -73,6 +74,7 @@ class AppState {
   resetComposeState() {
     this.composeTo = "";
     this.composeCc = "";
+    this.composeBcc = "";
     this.composeSubject = "";
     this.composeBody = "";
     this.composeAttachments = [];
@@ -29,6 +29,15 @@ export interface EmailHeader {
   has_attachments?: boolean;
   seen?: boolean; // Read/unread status
   flagged?: boolean; // Starred/flagged status
+  message_id?: string | null; // Message-ID header, used to thread replies
+  security?: MessageSecurity | null; // S/MIME or PGP summary; null for plain messages
+}
+
+export interface MessageSecurity {
+  scheme: "smime" | "pgp";
+  signed: boolean;
+  encrypted: boolean;
+  signature: "unverified" | "verified" | "invalid" | "sender_mismatch";
 }
 
 export interface AttachmentInfo {
@@ -54,6 +63,10 @@ export interface Folder {
   delimiter: string | null;
   flags: string | null;
   is_local?: boolean; // True for local-only folders, False for remote IMAP folders
+  sync_on_open?: boolean; // Skipped by background syncs until the folder is opened
+  role?: string | null; // Special-use role, e.g. "trash" or "sent"
+  total_count?: number | null; // Cached STATUS MESSAGES
+  unread_count?: number | null; // Cached STATUS UNSEEN
 }
 
 export interface IdleEvent {
@@ -66,6 +79,46 @@ export interface IdleEvent {
   };
 }
 
+export interface AccountAttention {
+  account_id: number;
+  reason: "token_expired" | "invalid_credentials";
+  message: string;
+  error: string; // The last server/provider error
+  since: number;
+}
+
+export interface FolderSyncSummary {
+  folder_name: string;
+  new_count: number;
+  total_count: number;
+  error: string | null;
+}
+
+export interface SyncWindow {
+  days: number | null; // Only mail from the last N days on a folder's first sync
+  messages: number | null; // Only the newest N messages on a folder's first sync
+}
+
+export interface OlderEmails {
+  loaded: number;
+  remaining: number; // Older messages still only on the server
+}
+
+export interface SyncProgress {
+  account_id: number;
+  folder_name: string;
+  phase: "started" | "finished" | "failed";
+  position: number;
+  total: number;
+  error: string | null;
+}
+
+export interface SyncComplete {
+  account_id: number;
+  folder_name: string;
+  new_emails: EmailHeader[];
+}
+
 export type DraftType = "compose" | "reply" | "forward";
 
 export interface DraftAttachment {
@@ -84,3 +137,12 @@ export interface DraftListItem {
   created_at: number;
   updated_at: number;
 }
+
+export interface ReaderWindow {
+  label: string;
+  email_id: number;
+  account_id: number;
+  folder_name: string;
+  uid: number;
+  subject: string;
+}